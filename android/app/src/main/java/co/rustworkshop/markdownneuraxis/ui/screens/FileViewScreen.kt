@@ -35,6 +35,7 @@ import androidx.compose.ui.text.font.FontFamily
 import androidx.compose.ui.text.font.FontStyle
 import androidx.compose.ui.text.font.FontWeight
 import androidx.compose.ui.text.input.TextFieldValue
+import androidx.compose.ui.text.style.TextAlign
 import androidx.compose.ui.text.style.TextDecoration
 import androidx.compose.ui.text.withStyle
 import androidx.compose.ui.unit.dp
@@ -44,7 +45,12 @@ import co.rustworkshop.markdownneuraxis.io.resolveDocumentFile
 import co.rustworkshop.markdownneuraxis.io.writeFileContent
 import co.rustworkshop.markdownneuraxis.model.FileTree
 import uniffi.markdown_neuraxis_ffi.Block
+import uniffi.markdown_neuraxis_ffi.BlockKindDto
 import uniffi.markdown_neuraxis_ffi.DocumentHandle
+import uniffi.markdown_neuraxis_ffi.FfiException
+import uniffi.markdown_neuraxis_ffi.HealthStatus
+import uniffi.markdown_neuraxis_ffi.SegmentKindDto
+import uniffi.markdown_neuraxis_ffi.TableAlignmentDto
 import uniffi.markdown_neuraxis_ffi.TextSegment
 import uniffi.markdown_neuraxis_ffi.resolveWikilink
 
@@ -54,12 +60,11 @@ private const val TAG = "MarkdownNeuraxis"
 private fun segmentsToText(segments: List<TextSegment>): String {
 	return segments.joinToString("") { segment ->
 		when (segment.kind) {
-			"text", "code", "strikethrough", "wiki_link" -> segment.content
-			"emphasis", "strong" -> segmentsToText(segment.children)
-			"link", "image" -> segment.content.substringBefore("|")
-			"hard_break" -> "\n"
-			"soft_break" -> " "
-			else -> ""
+			SegmentKindDto.Emphasis, SegmentKindDto.Strong -> segmentsToText(segment.children)
+			SegmentKindDto.Tag -> "#" + segment.content
+			SegmentKindDto.HardBreak -> "\n"
+			SegmentKindDto.SoftBreak -> " "
+			else -> segment.content
 		}
 	}
 }
@@ -141,12 +146,13 @@ fun FileViewScreen(
 
 	// Toggle checkbox state in file
 	val toggleCheckbox: (Block) -> Unit = { item ->
-		val checkboxStart = item.checkboxStart?.toInt()
-		val checkboxEnd = item.checkboxEnd?.toInt()
-		val checked = item.checkboxChecked
+		val checkbox = (item.kind as? BlockKindDto.ListItem)?.checkbox
 		val currentContent = content
 
-		if (checkboxStart != null && checkboxEnd != null && checked != null && currentContent != null) {
+		if (checkbox != null && currentContent != null) {
+			val checkboxStart = checkbox.range.start.toInt()
+			val checkboxEnd = checkbox.range.end.toInt()
+			val checked = checkbox.checked
 			val utf8Bytes = currentContent.toByteArray(Charsets.UTF_8)
 			val newCheckbox = if (checked) "[ ]" else "[x]"
 			val before = String(utf8Bytes, 0, checkboxStart, Charsets.UTF_8)
@@ -274,7 +280,10 @@ private fun RenderBlockTree(
 			onFinishEdit = onFinishEdit
 		)
 		// These blocks handle their own children internally
-		val handlesOwnChildren = block.kind in listOf("list", "table", "table_header_row", "table_row", "block_quote")
+		val handlesOwnChildren = when (block.kind) {
+			is BlockKindDto.List, is BlockKindDto.Table, is BlockKindDto.TableRow, BlockKindDto.BlockQuote -> true
+			else -> false
+		}
 		if (!handlesOwnChildren && block.children.isNotEmpty()) {
 			RenderBlockTree(
 				blocks = block.children,
@@ -301,62 +310,54 @@ private fun AnnotatedString.Builder.appendSegments(
 	codeBackground: Color
 ) {
 	for (segment in segments) {
-		when (segment.kind) {
-			"text" -> append(segment.content)
-			"wiki_link" -> {
-				pushStringAnnotation(tag = "wiki_link", annotation = segment.content)
+		when (val kind = segment.kind) {
+			SegmentKindDto.Text -> append(segment.content)
+			is SegmentKindDto.WikiLink -> {
+				pushStringAnnotation(tag = "wiki_link", annotation = kind.target)
 				withStyle(SpanStyle(color = linkColor)) {
 					append(segment.content)
 				}
 				pop()
 			}
-			"url" -> {
-				pushStringAnnotation(tag = "url", annotation = segment.content)
+			is SegmentKindDto.Link -> {
+				pushStringAnnotation(tag = "url", annotation = kind.url)
 				withStyle(SpanStyle(color = linkColor, textDecoration = TextDecoration.Underline)) {
 					append(segment.content)
 				}
 				pop()
 			}
-			"link" -> {
-				// Format: "text|url"
-				val parts = segment.content.split("|", limit = 2)
-				val text = parts.getOrElse(0) { segment.content }
-				val url = parts.getOrElse(1) { "" }
-				pushStringAnnotation(tag = "url", annotation = url)
-				withStyle(SpanStyle(color = linkColor, textDecoration = TextDecoration.Underline)) {
-					append(text)
-				}
-				pop()
-			}
-			"emphasis" -> {
+			SegmentKindDto.Emphasis -> {
 				withStyle(SpanStyle(fontStyle = FontStyle.Italic)) {
 					appendSegments(segment.children, linkColor, codeBackground)
 				}
 			}
-			"strong" -> {
+			SegmentKindDto.Strong -> {
 				withStyle(SpanStyle(fontWeight = FontWeight.Bold)) {
 					appendSegments(segment.children, linkColor, codeBackground)
 				}
 			}
-			"code" -> {
+			SegmentKindDto.Code -> {
 				withStyle(SpanStyle(fontFamily = FontFamily.Monospace, background = codeBackground)) {
 					append(segment.content)
 				}
 			}
-			"strikethrough" -> {
+			SegmentKindDto.Strikethrough -> {
 				withStyle(SpanStyle(textDecoration = TextDecoration.LineThrough)) {
 					append(segment.content)
 				}
 			}
-			"image" -> {
-				// Format: "alt|url" - show as placeholder text for now
-				val parts = segment.content.split("|", limit = 2)
-				val alt = parts.getOrElse(0) { "image" }
+			SegmentKindDto.Tag -> {
+				withStyle(SpanStyle(color = linkColor)) {
+					append("#" + segment.content)
+				}
+			}
+			is SegmentKindDto.Image -> {
+				// Show as placeholder text for now
+				val alt = segment.content.ifEmpty { "image" }
 				append("[Image: $alt]")
 			}
-			"soft_break" -> append(" ")
-			"hard_break" -> append("\n")
-			else -> append(segment.content)
+			SegmentKindDto.SoftBreak -> append(" ")
+			SegmentKindDto.HardBreak -> append("\n")
 		}
 	}
 }
@@ -427,9 +428,9 @@ private fun RenderBlock(
 	onEditTextChange: (TextFieldValue) -> Unit,
 	onFinishEdit: () -> Unit
 ) {
-	when (block.kind) {
-		"heading" -> {
-			val style = when (block.headingLevel.toInt()) {
+	when (val kind = block.kind) {
+		is BlockKindDto.Heading -> {
+			val style = when (kind.level.toInt()) {
 				1 -> MaterialTheme.typography.headlineLarge
 				2 -> MaterialTheme.typography.headlineMedium
 				3 -> MaterialTheme.typography.headlineSmall
@@ -480,21 +481,21 @@ private fun RenderBlock(
 				)
 			}
 		}
-		"list" -> {
+		is BlockKindDto.List -> {
 			// List container renders its children with index-generated markers
 			// Nesting indent comes from marker width - nested content is after marker
-			val ordered = block.listOrdered == true
+			val ordered = kind.ordered
 			Column {
 				block.children.forEachIndexed { index, item ->
-					val hasCheckbox = item.checkboxChecked != null
+					val checkbox = (item.kind as? BlockKindDto.ListItem)?.checkbox
 					val isEditing = editingBlockId == item.id
 					Row(
 						modifier = Modifier.padding(top = 4.dp, bottom = 4.dp),
 						verticalAlignment = Alignment.CenterVertically
 					) {
-						if (hasCheckbox) {
+						if (checkbox != null) {
 							Checkbox(
-								checked = item.checkboxChecked == true,
+								checked = checkbox.checked,
 								onCheckedChange = { onCheckboxToggle(item) },
 								modifier = Modifier.size(24.dp)
 							)
@@ -566,11 +567,11 @@ private fun RenderBlock(
 				}
 			}
 		}
-		"list_item" -> {
+		is BlockKindDto.ListItem -> {
 			// Standalone list_item should never happen - list container handles its items
 			error("list_item rendered outside of list container - invalid block structure")
 		}
-		"paragraph" -> {
+		BlockKindDto.Paragraph -> {
 			val isEditing = editingBlockId == block.id
 			if (isEditing) {
 				// Edit mode: show text field with visible border
@@ -615,7 +616,7 @@ private fun RenderBlock(
 				)
 			}
 		}
-		"code_fence" -> {
+		is BlockKindDto.CodeFence -> {
 			val isEditing = editingBlockId == block.id
 			if (isEditing) {
 				val focusRequester = remember { FocusRequester() }
@@ -666,8 +667,9 @@ private fun RenderBlock(
 							)
 						}
 				) {
+					// Indented code has a segment per line
 					Text(
-						text = segmentsToText(block.segments),
+						text = block.segments.joinToString("\n") { segmentsToText(listOf(it)) },
 						style = MaterialTheme.typography.bodySmall,
 						fontFamily = FontFamily.Monospace,
 						modifier = Modifier.padding(8.dp)
@@ -675,7 +677,7 @@ private fun RenderBlock(
 				}
 			}
 		}
-		"block_quote" -> {
+		BlockKindDto.BlockQuote -> {
 			val isEditing = editingBlockId == block.id
 			if (isEditing) {
 				// Edit the entire blockquote as raw markdown
@@ -754,7 +756,7 @@ private fun RenderBlock(
 						}
 						// Render child blocks - could be paragraphs or nested blockquotes
 						for (child in block.children) {
-							if (child.kind == "block_quote") {
+							if (child.kind == BlockKindDto.BlockQuote) {
 								// Nested blockquote - render recursively
 								RenderNestedBlockquote(
 									block = child,
@@ -775,7 +777,7 @@ private fun RenderBlock(
 				}
 			}
 		}
-		"thematic_break" -> {
+		BlockKindDto.ThematicBreak -> {
 			val isEditing = editingBlockId == block.id
 			if (isEditing) {
 				val focusRequester = remember { FocusRequester() }
@@ -816,9 +818,11 @@ private fun RenderBlock(
 				)
 			}
 		}
-		"table" -> {
-			// Table container - calculate column count for consistent widths
-			val columnCount = block.children.firstOrNull()?.children?.size ?: 1
+		is BlockKindDto.Table -> {
+			// Table container - column count from the delimiter row keeps widths consistent
+			val alignments = kind.alignments
+			val columnCount = alignments.size.takeIf { it > 0 }
+				?: block.children.firstOrNull()?.children?.size ?: 1
 			Column(
 				modifier = Modifier
 					.padding(vertical = 8.dp)
@@ -828,6 +832,7 @@ private fun RenderBlock(
 					RenderTableRow(
 						block = row,
 						columnCount = columnCount,
+						alignments = alignments,
 						onWikiLinkClick = onWikiLinkClick,
 						editingBlockId = editingBlockId,
 						editText = editText,
@@ -842,7 +847,7 @@ private fun RenderBlock(
 				}
 			}
 		}
-		"table_header_row", "table_row" -> {
+		is BlockKindDto.TableRow -> {
 			// Rows are rendered via RenderTableRow, this handles standalone case
 			RenderTableRow(
 				block = block,
@@ -855,7 +860,7 @@ private fun RenderBlock(
 				onFinishEdit = onFinishEdit
 			)
 		}
-		"table_cell" -> {
+		BlockKindDto.TableCell -> {
 			// Standalone cell (shouldn't happen - rows handle cells)
 			RenderSegments(
 				segments = block.segments,
@@ -911,6 +916,7 @@ private fun RenderBlock(
 private fun RenderTableRow(
 	block: Block,
 	columnCount: Int,
+	alignments: List<TableAlignmentDto> = emptyList(),
 	onWikiLinkClick: (String) -> Unit,
 	editingBlockId: String?,
 	editText: TextFieldValue,
@@ -918,7 +924,7 @@ private fun RenderTableRow(
 	onEditTextChange: (TextFieldValue) -> Unit,
 	onFinishEdit: () -> Unit
 ) {
-	val isHeader = block.kind == "table_header_row"
+	val isHeader = (block.kind as? BlockKindDto.TableRow)?.isHeader == true
 	val cells = block.children
 
 	Row(
@@ -942,10 +948,20 @@ private fun RenderTableRow(
 					color = MaterialTheme.colorScheme.outline
 				)
 			}
+			val textAlign = when (alignments.getOrNull(i)) {
+				TableAlignmentDto.CENTER -> TextAlign.Center
+				TableAlignmentDto.RIGHT -> TextAlign.End
+				else -> TextAlign.Start
+			}
 			Box(
 				modifier = Modifier
 					.weight(1f)
-					.padding(horizontal = 8.dp, vertical = 6.dp)
+					.padding(horizontal = 8.dp, vertical = 6.dp),
+				contentAlignment = when (textAlign) {
+					TextAlign.Center -> Alignment.TopCenter
+					TextAlign.End -> Alignment.TopEnd
+					else -> Alignment.TopStart
+				}
 			) {
 				if (i < cells.size) {
 					val cell = cells[i]
@@ -971,11 +987,13 @@ private fun RenderTableRow(
 							textStyle = if (isHeader) {
 								MaterialTheme.typography.bodyMedium.copy(
 									fontWeight = FontWeight.Bold,
-									color = LocalContentColor.current
+									color = LocalContentColor.current,
+									textAlign = textAlign
 								)
 							} else {
 								MaterialTheme.typography.bodyMedium.copy(
-									color = LocalContentColor.current
+									color = LocalContentColor.current,
+									textAlign = textAlign
 								)
 							}
 						)
@@ -986,9 +1004,12 @@ private fun RenderTableRow(
 						RenderSegments(
 							segments = cell.segments,
 							style = if (isHeader) {
-								MaterialTheme.typography.bodyMedium.copy(fontWeight = FontWeight.Bold)
+								MaterialTheme.typography.bodyMedium.copy(
+									fontWeight = FontWeight.Bold,
+									textAlign = textAlign
+								)
 							} else {
-								MaterialTheme.typography.bodyMedium
+								MaterialTheme.typography.bodyMedium.copy(textAlign = textAlign)
 							},
 							onWikiLinkClick = onWikiLinkClick,
 							onTextClick = {
@@ -1043,7 +1064,7 @@ private fun RenderNestedBlockquote(block: Block, onWikiLinkClick: (String) -> Un
 			}
 			// Render children recursively
 			for (child in block.children) {
-				if (child.kind == "block_quote") {
+				if (child.kind == BlockKindDto.BlockQuote) {
 					// Further nested blockquote
 					RenderNestedBlockquote(
 						block = child,
@@ -1067,7 +1088,21 @@ private fun RenderNestedBlockquote(block: Block, onWikiLinkClick: (String) -> Un
 private fun parseDocument(content: String): List<Block>? {
 	return try {
 		val doc = DocumentHandle.fromString(content)
-		doc.getSnapshot().blocks
+		val blocks = doc.getSnapshot().blocks
+		when (val health = doc.healthCheck()) {
+			is HealthStatus.Healthy -> blocks
+			is HealthStatus.Recovered -> {
+				Log.w(TAG, "Document recovered from saved text: ${health.reason}")
+				doc.getSnapshot().blocks
+			}
+			is HealthStatus.Failed -> {
+				Log.e(TAG, "Document could not be recovered: ${health.reason}")
+				null
+			}
+		}
+	} catch (e: FfiException.Internal) {
+		Log.e(TAG, "Engine error parsing document: ${e.reason}\n${e.backtrace}")
+		null
 	} catch (e: Exception) {
 		Log.e(TAG, "Error parsing document", e)
 		null
@@ -1,12 +1,29 @@
 use anyhow::Result;
 use crossterm::{
+    clipboard::CopyToClipboard,
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
-use markdown_neuraxis_config::Config;
+use markdown_neuraxis_config::bundle::{BundleLocations, ConflictResolution, SettingsBundle};
+use markdown_neuraxis_config::{Config, Contrast as ConfigContrast, ThemeName as ConfigThemeName};
+use markdown_neuraxis_engine::bookmarks::{Bookmark, Bookmarks};
+use markdown_neuraxis_engine::corpus::CorpusReport;
+use markdown_neuraxis_engine::corpus::features::FeatureUsage;
+use markdown_neuraxis_engine::dashboard::Dashboard;
 use markdown_neuraxis_engine::editing::snapshot::{InlineNode, InlineSegment};
-use markdown_neuraxis_engine::{Document, FileTree, FileTreeItem, io};
+use markdown_neuraxis_engine::editing::{AnchorId, Cmd, CodeLine, TOC_MARKER, TaskState};
+use markdown_neuraxis_engine::journal::{JournalCalendar, WeekStart};
+use markdown_neuraxis_engine::links::VaultLint;
+use markdown_neuraxis_engine::notices::{self, Notice, NoticeReceiver};
+use markdown_neuraxis_engine::refactor::replace::{self, MatchMode, ReplaceOptions};
+use markdown_neuraxis_engine::search::SearchIndex;
+use markdown_neuraxis_engine::templates::{self, Template, VariableType};
+use markdown_neuraxis_engine::theme::{Contrast, Rgb, Theme, ThemeName};
+use markdown_neuraxis_engine::titles::TitleIndex;
+use markdown_neuraxis_engine::vault::VaultIndex;
+use markdown_neuraxis_engine::views::kanban::{Board, GroupBy};
+use markdown_neuraxis_engine::{Document, FileTree, FileTreeItem, NotesEvent, assets, io, vault};
 use ratatui::{
     Frame, Terminal,
     backend::CrosstermBackend,
@@ -15,22 +32,97 @@ use ratatui::{
     text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
 };
-use relative_path::RelativePathBuf;
+use relative_path::{RelativePath, RelativePathBuf};
+use std::sync::{Arc, RwLock};
 use std::{env, io::stdout, path::PathBuf, process};
 
+#[cfg(feature = "serve")]
+mod serve;
+
+/// Which panel the arrow keys and `j`/`k` move through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Files,
+    Content,
+}
+
+/// A mark key waiting for the letter that names the mark
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PendingMark {
+    /// `m`: set a mark on the selected block
+    Set,
+    /// `'`: jump to a mark
+    Jump,
+}
+
+/// A block shown in the content panel
+#[derive(Debug, Clone, PartialEq)]
+struct ContentBlock {
+    id: AnchorId,
+    /// Start of the block in the note
+    offset: usize,
+    /// First content panel line showing the block
+    line: usize,
+}
+
+/// A line of the content panel
+#[derive(Debug, Clone, PartialEq)]
+enum ContentLine {
+    Text(String),
+    /// A code block line, shown unwrapped and scrolled sideways, with a
+    /// gutter `gutter_width` digits wide for its line number
+    Code {
+        line: CodeLine,
+        gutter_width: usize,
+    },
+}
+
 struct App {
     notes_path: PathBuf,
     file_tree: FileTree,
     tree_items: Vec<FileTreeItem>,
     file_list_state: ListState,
     selected_document: Option<Document>,
-    current_content: Vec<String>,
+    current_content: Vec<ContentLine>,
+    /// Blocks of the open note in display order
+    content_blocks: Vec<ContentBlock>,
+    /// Index into `content_blocks` of the block marks are set on
+    selected_block: Option<usize>,
+    focus: Focus,
+    pending_mark: Option<PendingMark>,
+    /// Marks `a`–`z`, kept in the vault so they survive restarts
+    bookmarks: Bookmarks,
+    titles: Arc<RwLock<TitleIndex>>,
+    theme: Theme,
+    /// Show line numbers beside code blocks
+    code_line_numbers: bool,
+    /// Columns code blocks are scrolled right by
+    code_scroll: usize,
+    /// Picks up edits made outside the app; `None` if watching failed
+    watcher: Option<io::NotesWatcher>,
+    notices: NoticeReceiver,
+    /// Latest engine notice, shown in place of the help line until a key
+    /// is pressed
+    notice: Option<Notice>,
 }
 
 impl App {
     fn new(notes_path: PathBuf) -> Result<Self> {
-        let file_tree = io::build_file_tree(&notes_path)?;
+        // Titles are cheap enough to index up front for the terminal UI
+        let titles = Arc::new(RwLock::new(TitleIndex::default()));
+        let scan_options = load_scan_options();
+        let options = vault::VaultOptions {
+            scan: scan_options,
+            ..vault::VaultOptions::default()
+        };
+        let mut opening = vault::open_vault_with(&notes_path, vec![titles.clone()], options)?;
+        opening.wait();
+        let mut file_tree = opening.file_tree;
+        file_tree.apply_titles(&titles.read().unwrap_or_else(|e| e.into_inner()));
         let tree_items = file_tree.get_items();
+        let watcher =
+            io::watch_notes_dir_with(&notes_path, io::WATCH_POLL_INTERVAL, scan_options).ok();
+        let bookmarks = Bookmarks::load(&notes_path)?;
 
         let mut app = Self {
             notes_path,
@@ -39,6 +131,18 @@ impl App {
             file_list_state: ListState::default(),
             selected_document: None,
             current_content: Vec::new(),
+            content_blocks: Vec::new(),
+            selected_block: None,
+            focus: Focus::Files,
+            pending_mark: None,
+            bookmarks,
+            titles,
+            theme: load_theme(),
+            code_line_numbers: true,
+            code_scroll: 0,
+            watcher,
+            notices: notices::subscribe(),
+            notice: None,
         };
 
         // Select first item if available
@@ -75,15 +179,19 @@ impl App {
     }
 
     fn update_content_for_selection(&mut self) {
+        self.content_blocks.clear();
+        self.selected_block = None;
         if let Some(index) = self.file_list_state.selected()
             && let Some(item) = self.tree_items.get(index)
         {
             if item.node.is_folder {
                 // For folders, show folder info
                 self.current_content = vec![
-                    format!("📁 {}", item.node.name),
-                    String::new(),
-                    "Press Enter/Space to toggle, → to expand, ← to collapse".to_string(),
+                    ContentLine::Text(format!("📁 {}", item.node.name)),
+                    ContentLine::Text(String::new()),
+                    ContentLine::Text(
+                        "Press Enter/Space to toggle, → to expand, ← to collapse".to_string(),
+                    ),
                 ];
                 self.selected_document = None;
             } else if let Some(ref file) = item.node.markdown_file {
@@ -91,16 +199,21 @@ impl App {
                 match io::read_file(file.relative_path(), &self.notes_path) {
                     Ok(content) => match Document::from_bytes(content.as_bytes()) {
                         Ok(document) => {
-                            self.selected_document = Some(document.clone());
-                            self.current_content = self.render_document_content(&document);
+                            let (lines, blocks) =
+                                self.render_document_content(&document, file.relative_path());
+                            self.selected_document = Some(document);
+                            self.current_content = lines;
+                            self.content_blocks = blocks;
                         }
                         Err(e) => {
-                            self.current_content = vec![format!("Error parsing document: {}", e)];
+                            self.current_content =
+                                vec![ContentLine::Text(format!("Error parsing document: {}", e))];
                             self.selected_document = None;
                         }
                     },
                     Err(e) => {
-                        self.current_content = vec![format!("Error reading file: {}", e)];
+                        self.current_content =
+                            vec![ContentLine::Text(format!("Error reading file: {}", e))];
                         self.selected_document = None;
                     }
                 }
@@ -108,6 +221,80 @@ impl App {
         }
     }
 
+    /// Keep the latest engine notice for the status line.
+    fn poll_notices(&mut self) {
+        if let Some(notice) = self.notices.poll().pop() {
+            self.notice = Some(notice);
+        }
+    }
+
+    /// Apply changes made to the notes folder outside the app, keeping the
+    /// same item selected.
+    fn apply_external_changes(&mut self) {
+        let Some(watcher) = &self.watcher else {
+            return;
+        };
+        let events = watcher.poll();
+        if events.is_empty() {
+            return;
+        }
+
+        let selected = self
+            .file_list_state
+            .selected()
+            .and_then(|i| self.tree_items.get(i))
+            .map(|item| item.node.relative_path.clone());
+        let root = &self.notes_path;
+        let mut titles = self.titles.write().unwrap_or_else(|e| e.into_inner());
+        let mut renamed = false;
+        for event in events {
+            let (removed, added) = match event {
+                NotesEvent::Created(path) => (None, Some(path)),
+                NotesEvent::Modified(path) => {
+                    if let Ok(source) = io::read_file(&path, root) {
+                        titles.add_note(&path, &source);
+                    }
+                    continue;
+                }
+                NotesEvent::Deleted(path) => (Some(path), None),
+                NotesEvent::Renamed { from, to } => (Some(from), Some(to)),
+            };
+            if let (Some(from), Some(to)) = (&removed, &added) {
+                self.bookmarks.rename_note(from, to);
+                renamed = true;
+            }
+            if let Some(path) = removed {
+                titles.remove_note(&path);
+                self.file_tree.remove_file(&path.to_path(root), root);
+            }
+            if let Some(path) = added {
+                if let Ok(source) = io::read_file(&path, root) {
+                    titles.add_note(&path, &source);
+                }
+                self.file_tree.add_file(&path.to_path(root), root);
+            }
+        }
+        self.file_tree.apply_titles(&titles);
+        drop(titles);
+        if renamed {
+            // Best effort: the marks are still right in memory
+            let _ = self.bookmarks.save(&self.notes_path);
+        }
+
+        self.tree_items = self.file_tree.get_items();
+        let index = selected
+            .and_then(|path| {
+                self.tree_items
+                    .iter()
+                    .position(|item| item.node.relative_path == path)
+            })
+            .or_else(|| self.file_list_state.selected())
+            .map(|i| i.min(self.tree_items.len().saturating_sub(1)));
+        self.file_list_state
+            .select(index.filter(|_| !self.tree_items.is_empty()));
+        self.update_content_for_selection();
+    }
+
     fn activate_selected_item(&mut self) -> Result<()> {
         if let Some(index) = self.file_list_state.selected()
             && let Some(item) = self.tree_items.get(index)
@@ -122,6 +309,126 @@ impl App {
         Ok(())
     }
 
+    /// Copy the open note's table of contents to the system clipboard,
+    /// using the terminal's OSC 52 support so it also works over SSH.
+    fn copy_table_of_contents(&self) -> Result<()> {
+        if let Some(document) = &self.selected_document {
+            let toc = document.table_of_contents(false);
+            execute!(stdout(), CopyToClipboard::to_clipboard_from(toc))?;
+        }
+        Ok(())
+    }
+
+    /// Switch between the file list and the open note's blocks.
+    fn toggle_focus(&mut self) {
+        self.focus = match self.focus {
+            Focus::Files if !self.content_blocks.is_empty() => {
+                self.selected_block.get_or_insert(0);
+                Focus::Content
+            }
+            _ => Focus::Files,
+        };
+    }
+
+    fn next_block(&mut self) {
+        if !self.content_blocks.is_empty() {
+            let i = self
+                .selected_block
+                .map_or(0, |i| (i + 1).min(self.content_blocks.len() - 1));
+            self.selected_block = Some(i);
+        }
+    }
+
+    fn previous_block(&mut self) {
+        self.selected_block = self.selected_block.map(|i| i.saturating_sub(1));
+    }
+
+    /// Path of the note open in the content panel.
+    fn open_note_path(&self) -> Option<RelativePathBuf> {
+        let item = self.tree_items.get(self.file_list_state.selected()?)?;
+        let file = item.node.markdown_file.as_ref()?;
+        Some(file.relative_path().to_relative_path_buf())
+    }
+
+    /// Set mark `name` on the selected block (the first block if none is
+    /// selected) and save the marks.
+    fn set_mark(&mut self, name: char) -> Result<()> {
+        let Some(path) = self.open_note_path() else {
+            return Ok(());
+        };
+        let (Some(document), Some(block)) = (
+            &self.selected_document,
+            self.content_blocks.get(self.selected_block.unwrap_or(0)),
+        ) else {
+            return Ok(());
+        };
+        let bookmark = Bookmark::new(&path, block.id, block.offset, document);
+        self.bookmarks.set(&name.to_string(), bookmark);
+        self.bookmarks.save(&self.notes_path)?;
+        Ok(())
+    }
+
+    /// Open the note with mark `name` and select its block.
+    fn jump_to_mark(&mut self, name: char) {
+        let Some(bookmark) = self.bookmarks.get(&name.to_string()).cloned() else {
+            return;
+        };
+        if !self.select_note(&bookmark.path) {
+            return;
+        }
+
+        let Some(block_id) = self
+            .selected_document
+            .as_ref()
+            .and_then(|document| bookmark.resolve(document))
+        else {
+            return;
+        };
+        self.selected_block = self
+            .content_blocks
+            .iter()
+            .position(|block| block.id == block_id);
+        if self.selected_block.is_some() {
+            self.focus = Focus::Content;
+        }
+    }
+
+    /// Open today's journal note, creating it from the journal template
+    /// the first time.
+    fn open_today(&mut self) -> Result<()> {
+        let time_zone = Config::load().ok().flatten().and_then(|c| c.timezone);
+        let calendar = JournalCalendar::new(time_zone.as_deref(), WeekStart::default())?;
+        let note = calendar.open_today(&self.notes_path)?;
+        if let Some(NotesEvent::Created(path)) = &note.created {
+            let root = &self.notes_path;
+            let mut titles = self.titles.write().unwrap_or_else(|e| e.into_inner());
+            titles.add_note(path, &note.document.text());
+            self.file_tree.add_file(&path.to_path(root), root);
+            self.file_tree.apply_titles(&titles);
+        }
+        self.select_note(&note.path);
+        Ok(())
+    }
+
+    /// Reveal a note in the file list and open it, returning whether it
+    /// was found.
+    fn select_note(&mut self, path: &RelativePath) -> bool {
+        if let Some(folder) = path.parent() {
+            self.file_tree.expand_to_folder(folder);
+        }
+        self.tree_items = self.file_tree.get_items();
+        let Some(index) = self
+            .tree_items
+            .iter()
+            .position(|item| item.node.relative_path == path)
+        else {
+            return false;
+        };
+        self.file_list_state.select(Some(index));
+        self.update_content_for_selection();
+        true
+    }
+
     fn toggle_folder(&mut self, relative_path: RelativePathBuf) {
         self.file_tree.toggle_folder(&relative_path);
         self.tree_items = self.file_tree.get_items();
@@ -153,46 +460,83 @@ impl App {
         Ok(())
     }
 
-    fn render_document_content(&self, document: &Document) -> Vec<String> {
+    /// Lines to show for `document`, the note at `path`, and where each
+    /// markable block starts. Embedded notes are shown inline.
+    fn render_document_content(
+        &self,
+        document: &Document,
+        path: &RelativePath,
+    ) -> (Vec<ContentLine>, Vec<ContentBlock>) {
+        use markdown_neuraxis_engine::editing::embeds::resolve_vault_embeds;
         use markdown_neuraxis_engine::editing::snapshot::{Block, BlockContent, BlockKind};
 
-        let snapshot = document.snapshot();
+        let mut snapshot = document.snapshot();
+        resolve_vault_embeds(&mut snapshot, &self.notes_path, Some(path));
         let mut lines = Vec::new();
+        let mut blocks = Vec::new();
 
-        fn render_block(block: &Block, lines: &mut Vec<String>) {
+        fn render_block(
+            block: &Block,
+            lines: &mut Vec<ContentLine>,
+            blocks: &mut Vec<ContentBlock>,
+        ) {
             // Extract plain text from segments (temporary until CLI does rich rendering)
             let content = segments_to_plain_text(&block.segments);
 
+            if matches!(
+                block.kind,
+                BlockKind::Heading { .. }
+                    | BlockKind::Paragraph { .. }
+                    | BlockKind::ListItem { .. }
+                    | BlockKind::FencedCode { .. }
+                    | BlockKind::BlockQuote
+                    | BlockKind::ThematicBreak
+                    | BlockKind::Table { .. }
+                    | BlockKind::Embed { .. }
+            ) {
+                blocks.push(ContentBlock {
+                    id: block.id,
+                    offset: block.node_range.start,
+                    line: lines.len(),
+                });
+            }
+
             match &block.kind {
                 BlockKind::Root => {
                     // Process children
                     if let BlockContent::Children(children) = &block.content {
                         for child in children {
-                            render_block(child, lines);
+                            render_block(child, lines, blocks);
                         }
                     }
                 }
                 BlockKind::Heading { level } => {
                     let prefix = "#".repeat(*level as usize);
-                    lines.push(format!("{} {}", prefix, content));
-                    lines.push(String::new());
+                    lines.push(ContentLine::Text(format!("{} {}", prefix, content)));
+                    lines.push(ContentLine::Text(String::new()));
                 }
-                BlockKind::Paragraph => {
-                    lines.push(content);
-                    lines.push(String::new());
+                BlockKind::Paragraph { .. } => {
+                    lines.push(ContentLine::Text(content));
+                    lines.push(ContentLine::Text(String::new()));
                 }
                 BlockKind::List { .. } => {
                     // Process list items
                     if let BlockContent::Children(children) = &block.content {
                         for child in children {
-                            render_block(child, lines);
+                            render_block(child, lines, blocks);
                         }
                     }
                 }
-                BlockKind::ListItem { marker, checkbox } => {
-                    // Show checkbox or bullet marker
-                    let prefix = match checkbox {
-                        Some(cb) if cb.checked => "✅",
+                BlockKind::ListItem {
+                    marker,
+                    task_state,
+                    ordinal,
+                    ..
+                } => {
+                    // Show task state or bullet marker
+                    let prefix = match task_state {
+                        Some(TaskState::Done) => "✅",
+                        Some(TaskState::Doing) => "⏳",
                         Some(_) => "🔲",
                         None => {
                             // Use bullet for non-checkbox items
@@ -202,44 +546,52 @@ impl App {
                             {
                                 "•"
                             } else {
-                                marker.trim()
+                                ordinal.as_deref().unwrap_or(marker.trim())
                             }
                         }
                     };
-                    lines.push(format!("{} {}", prefix, content));
+                    lines.push(ContentLine::Text(format!("{} {}", prefix, content)));
                     // Process nested content
                     if let BlockContent::Children(children) = &block.content {
                         for child in children {
-                            render_block(child, lines);
+                            render_block(child, lines, blocks);
                         }
                     }
                 }
-                BlockKind::FencedCode { language } => {
-                    lines.push(format!("```{}", language.as_deref().unwrap_or("")));
-                    for line in content.lines() {
-                        lines.push(line.to_string());
+                BlockKind::FencedCode { language, .. } => {
+                    let code = block.code_lines();
+                    let gutter_width = code.len().to_string().len();
+                    lines.push(ContentLine::Text(format!(
+                        "┌─ {}",
+                        language.as_deref().unwrap_or("code")
+                    )));
+                    for line in code {
+                        lines.push(ContentLine::Code { line, gutter_width });
                     }
-                    lines.push("```".to_string());
-                    lines.push(String::new());
+                    lines.push(ContentLine::Text("└─".to_string()));
+                    lines.push(ContentLine::Text(String::new()));
                 }
                 BlockKind::BlockQuote => {
                     for line in content.lines() {
-                        lines.push(format!("> {}", line));
+                        lines.push(ContentLine::Text(format!("> {}", line)));
                     }
-                    lines.push(String::new());
+                    lines.push(ContentLine::Text(String::new()));
                 }
                 BlockKind::ThematicBreak => {
-                    lines.push("---".to_string());
-                    lines.push(String::new());
+                    lines.push(ContentLine::Text("---".to_string()));
+                    lines.push(ContentLine::Text(String::new()));
                 }
-                BlockKind::Table => {
+                BlockKind::FrontMatter { .. } => {
+                    // Metadata, not note content
+                }
+                BlockKind::Table { .. } => {
                     // Render table rows
                     if let BlockContent::Children(children) = &block.content {
                         for child in children {
-                            render_block(child, lines);
+                            render_block(child, lines, blocks);
                         }
                     }
-                    lines.push(String::new());
+                    lines.push(ContentLine::Text(String::new()));
                 }
                 BlockKind::TableRow { .. } => {
                     // Collect cell contents
@@ -249,19 +601,47 @@ impl App {
                             cells.push(segments_to_plain_text(&child.segments));
                         }
                     }
-                    lines.push(format!("| {} |", cells.join(" | ")));
+                    lines.push(ContentLine::Text(format!("| {} |", cells.join(" | "))));
                 }
                 BlockKind::TableCell => {
                     // Cells are rendered by TableRow
                 }
+                BlockKind::Embed {
+                    target,
+                    heading,
+                    source,
+                } => {
+                    let name = match heading {
+                        Some(heading) => format!("{target} › {heading}"),
+                        None => target.clone(),
+                    };
+                    match (source, &block.content) {
+                        (Some(_), BlockContent::Children(children)) => {
+                            // Embedded blocks belong to another note, so
+                            // they aren't markable here
+                            let mut embedded = Vec::new();
+                            for child in children {
+                                render_block(child, &mut embedded, &mut Vec::new());
+                            }
+                            lines.push(ContentLine::Text(format!("┌─ {name}")));
+                            lines.extend(embedded.into_iter().map(|line| match line {
+                                ContentLine::Text(text) => ContentLine::Text(format!("│ {text}")),
+                                code => code,
+                            }));
+                            lines.push(ContentLine::Text("└─".to_string()));
+                        }
+                        _ => lines.push(ContentLine::Text(format!("┄ {name} (not found)"))),
+                    }
+                    lines.push(ContentLine::Text(String::new()));
+                }
             }
         }
 
         for block in &snapshot.blocks {
-            render_block(block, &mut lines);
+            render_block(block, &mut lines, &mut blocks);
         }
 
-        lines
+        (lines, blocks)
     }
 }
 
@@ -277,32 +657,148 @@ fn segments_to_plain_text(segments: &[InlineSegment]) -> String {
 fn inline_node_to_text(node: &InlineNode) -> String {
     match node {
         InlineNode::Text(s) => s.clone(),
-        InlineNode::Strong(children) | InlineNode::Emphasis(children) => {
-            children.iter().map(inline_node_to_text).collect()
-        }
+        InlineNode::Strong(children) | InlineNode::Emphasis(children) => children
+            .iter()
+            .map(|child| inline_node_to_text(&child.kind))
+            .collect(),
         InlineNode::Code(s) => s.clone(),
         InlineNode::Strikethrough(s) => s.clone(),
         InlineNode::WikiLink { target, alias } => alias.as_ref().unwrap_or(target).clone(),
         InlineNode::Link { text, .. } => text.clone(),
         InlineNode::Image { alt, .. } => alt.clone(),
+        InlineNode::Tag { name } => format!("#{name}"),
         InlineNode::HardBreak => "\n".to_string(),
         InlineNode::SoftBreak => " ".to_string(),
     }
 }
 
 fn main() -> Result<()> {
-    // Determine notes path from CLI args or config file
     let args: Vec<String> = env::args().collect();
+
+    match args.get(1).map(String::as_str) {
+        Some("serve") => return run_serve(&args),
+        Some("doctor") => return run_doctor(&args),
+        Some("move-asset") => return run_move_asset(&args),
+        Some("settings") => return run_settings(&args),
+        Some("template") => return run_template(&args),
+        Some("dashboard") => return run_dashboard(&args),
+        Some("kanban") => return run_kanban(&args),
+        Some("corpus-check") => return run_corpus_check(&args),
+        Some("features") => return run_features(&args),
+        Some("toc") => return run_toc(&args),
+        Some("search") => return run_search(&args),
+        Some("replace") => return run_replace(&args),
+        Some("lint") => return run_lint(&args),
+        _ => {}
+    }
+
+    // Determine notes path from CLI args or config file
+    let notes_path = match args.len() {
+        1 => resolve_notes_path(None, &args[0]),
+        2 => resolve_notes_path(Some(&args[1]), &args[0]),
+        _ => {
+            eprintln!("Usage: {} [notes-folder-path]", args[0]);
+            eprintln!(
+                "       {} serve [notes-folder-path] [--addr HOST:PORT] [--token TOKEN]",
+                args[0]
+            );
+            process::exit(1);
+        }
+    };
+
+    // Setup terminal
+    enable_raw_mode()?;
+    let mut stdout = stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    // Create app
+    let mut app = App::new(notes_path)?;
+
+    // Main loop
+    let res = run_app(&mut terminal, &mut app);
+
+    // Restore terminal
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
+    terminal.show_cursor()?;
+
+    // Titles are restored from the snapshot on the next start
+    let indices: [vault::SharedIndex; 1] = [app.titles.clone()];
+    if let Err(err) = vault::save_index_snapshot(&app.notes_path, &indices) {
+        eprintln!("Could not save index snapshot: {err}");
+    }
+
+    if let Err(err) = res {
+        println!("{err:?}");
+    }
+
+    Ok(())
+}
+
+/// How to scan the vault, from the config's `follow_symlinks` setting.
+fn load_scan_options() -> io::ScanOptions {
+    match Config::load() {
+        Ok(Some(config)) => io::ScanOptions {
+            follow_symlinks: config.follow_symlinks,
+        },
+        _ => io::ScanOptions::default(),
+    }
+}
+
+/// Theme from the config's theme, contrast and reduced motion settings,
+/// falling back to the engine's default theme.
+fn load_theme() -> Theme {
+    match Config::load() {
+        Ok(Some(config)) => {
+            let name = match config.theme {
+                ConfigThemeName::SolarizedLight => ThemeName::SolarizedLight,
+                ConfigThemeName::SolarizedDark => ThemeName::SolarizedDark,
+            };
+            let contrast = match config.contrast {
+                ConfigContrast::Normal => Contrast::Normal,
+                ConfigContrast::High => Contrast::High,
+            };
+            Theme::new(name, contrast, config.reduced_motion)
+        }
+        _ => Theme::default(),
+    }
+}
+
+fn color(rgb: Rgb) -> Color {
+    Color::Rgb(rgb.0, rgb.1, rgb.2)
+}
+
+fn selection_style(theme: &Theme) -> Style {
+    let (fg, bg) = theme.selection();
+    Style::default().bg(color(bg)).fg(color(fg))
+}
+
+/// Resolve and validate the notes path from a CLI argument or the config file,
+/// exiting with a usage message on failure.
+fn resolve_notes_path(arg: Option<&str>, program: &str) -> PathBuf {
     let config_path = Config::config_path();
 
     let notes_path;
     let from_config;
 
-    if args.len() == 2 {
+    if let Some(arg) = arg {
         // CLI argument provided - use it
-        notes_path = PathBuf::from(&args[1]);
+        notes_path = PathBuf::from(arg);
+        from_config = false;
+    } else if let Some(vault) = env::current_dir()
+        .ok()
+        .and_then(|dir| vault::Vault::discover(&dir))
+    {
+        // Started inside a vault
+        notes_path = vault.notes_root().to_path_buf();
         from_config = false;
-    } else if args.len() == 1 {
+    } else {
         // No CLI argument - try config file
         match Config::load() {
             Ok(Some(config)) => {
@@ -311,20 +807,17 @@ fn main() -> Result<()> {
             }
             Ok(None) => {
                 eprintln!("Error: No notes path provided and no config file found");
-                eprintln!("Usage: {} <notes-folder-path>", args[0]);
+                eprintln!("Usage: {program} <notes-folder-path>");
                 eprintln!("Or create a config file at {}", config_path.display());
                 process::exit(1);
             }
             Err(e) => {
                 eprintln!("Error: Failed to load config file: {e}");
-                eprintln!("Usage: {} <notes-folder-path>", args[0]);
+                eprintln!("Usage: {program} <notes-folder-path>");
                 process::exit(1);
             }
         }
-    } else {
-        eprintln!("Usage: {} [notes-folder-path]", args[0]);
-        process::exit(1);
-    };
+    }
 
     // Validate notes directory using engine
     if let Err(e) = io::validate_notes_dir(&notes_path) {
@@ -341,47 +834,632 @@ fn main() -> Result<()> {
         process::exit(1);
     }
 
-    // Setup terminal
-    enable_raw_mode()?;
-    let mut stdout = stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    notes_path
+}
 
-    // Create app
-    let mut app = App::new(notes_path)?;
+/// `doctor --orphan-assets [--clean] | --missing-assets [notes-folder-path]`
+///
+/// Lists asset files no note references; `--clean` moves them to the vault
+/// trash. `--missing-assets` lists links and embeds whose file is missing.
+fn run_doctor(args: &[String]) -> Result<()> {
+    let usage = || -> ! {
+        eprintln!(
+            "Usage: {} doctor --orphan-assets [--clean] | --missing-assets [notes-folder-path]",
+            args[0]
+        );
+        process::exit(1);
+    };
 
-    // Main loop
-    let res = run_app(&mut terminal, &mut app);
+    let mut orphan_assets = false;
+    let mut missing_assets = false;
+    let mut clean = false;
+    let mut notes_arg = None;
+    for arg in &args[2..] {
+        match arg.as_str() {
+            "--orphan-assets" => orphan_assets = true,
+            "--missing-assets" => missing_assets = true,
+            "--clean" => clean = true,
+            _ if arg.starts_with("--") || notes_arg.is_some() => usage(),
+            _ => notes_arg = Some(arg.as_str()),
+        }
+    }
+    if orphan_assets == missing_assets || (clean && !orphan_assets) {
+        usage();
+    }
 
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
+    let notes_path = resolve_notes_path(notes_arg, &args[0]);
+    if missing_assets {
+        let missing = assets::find_missing_assets(&notes_path)?;
+        for link in &missing {
+            println!("{}:{}: missing {}", link.path, link.line, link.target);
+        }
+        if missing.is_empty() {
+            println!("No missing assets found");
+        } else {
+            eprintln!("{} link(s) to missing files", missing.len());
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let orphans = assets::find_orphan_assets(&notes_path)?;
+    if orphans.is_empty() {
+        println!("No orphaned assets found");
+        return Ok(());
+    }
+
+    if clean {
+        let trashed = assets::trash_orphan_assets(&notes_path, &orphans)?;
+        for path in &trashed {
+            println!("Moved to trash: {path}");
+        }
+        println!(
+            "{} orphaned asset(s) moved to {}",
+            trashed.len(),
+            io::TRASH_DIR
+        );
+    } else {
+        for path in &orphans {
+            println!("{path}");
+        }
+        println!(
+            "{} orphaned asset(s); run with --clean to move them to {}",
+            orphans.len(),
+            io::TRASH_DIR
+        );
+    }
+    Ok(())
+}
+
+/// `move-asset <from> <to> [notes-folder-path]`
+///
+/// Moves or renames an attachment, rewriting the links to it.
+fn run_move_asset(args: &[String]) -> Result<()> {
+    let (from, to, notes_arg) = match &args[2..] {
+        [from, to] => (from, to, None),
+        [from, to, notes] => (from, to, Some(notes.as_str())),
+        _ => {
+            eprintln!(
+                "Usage: {} move-asset <from> <to> [notes-folder-path]",
+                args[0]
+            );
+            process::exit(1);
+        }
+    };
+    let notes_path = resolve_notes_path(notes_arg, &args[0]);
+    let moved = assets::move_asset(
+        &notes_path,
+        &io::relative_path_from_str(from),
+        &io::relative_path_from_str(to),
     )?;
-    terminal.show_cursor()?;
+    for path in &moved.notes_changed {
+        println!("Updated: {path}");
+    }
+    println!(
+        "Moved {from} to {to}; {} link(s) updated",
+        moved.links_updated
+    );
+    Ok(())
+}
 
-    if let Err(err) = res {
-        println!("{err:?}");
+/// `settings export|import <bundle-file> [notes-folder-path]`
+///
+/// Copies the app config and vault settings to or from a single bundle file,
+/// asking before overwriting files that differ.
+fn run_settings(args: &[String]) -> Result<()> {
+    let usage = || -> ! {
+        eprintln!(
+            "Usage: {} settings export|import <bundle-file> [notes-folder-path]",
+            args[0]
+        );
+        process::exit(1);
+    };
+
+    let (action, bundle_path, notes_arg) = match &args[2..] {
+        [action, bundle] => (action.as_str(), bundle, None),
+        [action, bundle, notes] => (action.as_str(), bundle, Some(notes.as_str())),
+        _ => usage(),
+    };
+    let notes_path = match (notes_arg, Config::load()) {
+        (None, Ok(None)) => None,
+        _ => Some(resolve_notes_path(notes_arg, &args[0])),
+    };
+    let locations = BundleLocations::new(notes_path);
+
+    match action {
+        "export" => {
+            let bundle = SettingsBundle::export(&locations)?;
+            bundle.save_to_path(bundle_path)?;
+            for file in &bundle.files {
+                println!("Exported: {}", file.label());
+            }
+        }
+        "import" => {
+            let bundle = SettingsBundle::load_from_path(bundle_path)?;
+            let report = bundle.import(&locations, |conflict| {
+                let prompt = format!(
+                    "{} differs from the bundle. Overwrite?",
+                    conflict.file.label()
+                );
+                if confirm(&prompt) {
+                    ConflictResolution::Overwrite
+                } else {
+                    ConflictResolution::Keep
+                }
+            })?;
+            for label in &report.written {
+                println!("Imported: {label}");
+            }
+            for label in &report.kept {
+                println!("Kept existing: {label}");
+            }
+            for label in &report.skipped {
+                println!("Skipped (no notes folder): {label}");
+            }
+        }
+        _ => usage(),
+    }
+    Ok(())
+}
+
+/// `template list [notes-folder-path]`
+/// `template new <template> <note-path> [notes-folder-path]`
+///
+/// Lists templates, or creates a note from one after prompting for each of
+/// its variables.
+fn run_template(args: &[String]) -> Result<()> {
+    let usage = || -> ! {
+        eprintln!("Usage: {} template list [notes-folder-path]", args[0]);
+        eprintln!(
+            "       {} template new <template> <note-path> [notes-folder-path]",
+            args[0]
+        );
+        process::exit(1);
+    };
+
+    match &args[2..] {
+        [action, rest @ ..] if action == "list" && rest.len() <= 1 => {
+            let notes_path = resolve_notes_path(rest.first().map(String::as_str), &args[0]);
+            for name in templates::list_templates(&notes_path)? {
+                println!("{name}");
+            }
+        }
+        [action, name, note, rest @ ..] if action == "new" && rest.len() <= 1 => {
+            let notes_path = resolve_notes_path(rest.first().map(String::as_str), &args[0]);
+            let template = Template::load(&notes_path, name)?;
+
+            let mut values = std::collections::BTreeMap::new();
+            for variable in template.variables() {
+                let mut prompt = variable.prompt.clone();
+                if let VariableType::Choice(choices) = &variable.kind {
+                    prompt.push_str(&format!(" [{}]", choices.join("/")));
+                } else if variable.kind == VariableType::Date {
+                    prompt.push_str(" (YYYY-MM-DD)");
+                }
+                if let Some(default) = &variable.default {
+                    prompt.push_str(&format!(" (default: {default})"));
+                }
+                values.insert(variable.name.clone(), ask(&prompt));
+            }
+
+            let time_zone = Config::load().ok().flatten().and_then(|c| c.timezone);
+            let today = JournalCalendar::new(time_zone.as_deref(), WeekStart::default())?.today();
+            let note_path = RelativePathBuf::from(note.as_str());
+            template.create_note(&notes_path, &note_path, &values, today)?;
+            println!("Created {note_path}");
+        }
+        _ => usage(),
     }
+    Ok(())
+}
 
+/// `dashboard [notes-folder-path]`
+///
+/// Prints open tasks, recent edits and upcoming deadlines for each area.
+fn run_dashboard(args: &[String]) -> Result<()> {
+    if args.len() > 3 {
+        eprintln!("Usage: {} dashboard [notes-folder-path]", args[0]);
+        process::exit(1);
+    }
+    let notes_path = resolve_notes_path(args.get(2).map(String::as_str), &args[0]);
+    let time_zone = Config::load().ok().flatten().and_then(|c| c.timezone);
+    let today = JournalCalendar::new(time_zone.as_deref(), WeekStart::default())?.today();
+
+    for area in Dashboard::load(&notes_path, today)?.areas {
+        println!(
+            "{} ({} notes, {} open tasks)",
+            area.name,
+            area.notes,
+            area.open_tasks.len()
+        );
+        for task in &area.upcoming {
+            if let Some(deadline) = task.deadline {
+                let overdue = if deadline < today { " OVERDUE" } else { "" };
+                println!(
+                    "  {deadline}{overdue}  {}  ({}:{})",
+                    task.text, task.path, task.line
+                );
+            }
+        }
+        for edit in &area.recent_edits {
+            println!("  edited {}", edit.path);
+        }
+    }
+    Ok(())
+}
+
+/// `kanban [--by <property>] [notes-folder-path]`
+///
+/// Prints every task as a kanban board, by state or by a property's value.
+fn run_kanban(args: &[String]) -> Result<()> {
+    let usage = || -> ! {
+        eprintln!(
+            "Usage: {} kanban [--by <property>] [notes-folder-path]",
+            args[0]
+        );
+        process::exit(1);
+    };
+
+    let mut group_by = GroupBy::State;
+    let mut positional = Vec::new();
+    let mut rest = args[2..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--by" => {
+                let Some(name) = rest.next() else { usage() };
+                group_by = GroupBy::Property {
+                    name: name.clone(),
+                    values: Vec::new(),
+                };
+            }
+            _ if arg.starts_with("--") => usage(),
+            _ => positional.push(arg.as_str()),
+        }
+    }
+    let notes_arg = match positional[..] {
+        [] => None,
+        [notes] => Some(notes),
+        _ => usage(),
+    };
+
+    let notes_path = resolve_notes_path(notes_arg, &args[0]);
+    for column in Board::load(&notes_path, group_by)?.columns {
+        println!("{} ({})", column.title, column.cards.len());
+        for card in &column.cards {
+            println!("  {}  ({}:{})", card.text, card.path, card.line);
+        }
+    }
+    Ok(())
+}
+
+/// `corpus-check <folder>`
+///
+/// Checks that every note in a folder round-trips through the parser and
+/// editor unchanged, printing problem files and the slowest files. Exits
+/// non-zero if any file has a problem.
+fn run_corpus_check(args: &[String]) -> Result<()> {
+    let [_, _, folder] = args else {
+        eprintln!("Usage: {} corpus-check <folder>", args[0]);
+        process::exit(1);
+    };
+    let report = CorpusReport::check_vault(&PathBuf::from(folder))?;
+    print!("{report}");
+    if !report.is_clean() {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// `features [--files] <folder>`
+///
+/// Prints how many notes use each markdown feature; `--files` lists them.
+fn run_features(args: &[String]) -> Result<()> {
+    let (files, folder) = match &args[2..] {
+        [folder] if !folder.starts_with("--") => (false, folder),
+        [flag, folder] if flag == "--files" => (true, folder),
+        _ => {
+            eprintln!("Usage: {} features [--files] <folder>", args[0]);
+            process::exit(1);
+        }
+    };
+    let usage = FeatureUsage::scan_vault(&PathBuf::from(folder))?;
+    if files {
+        print!("{usage:#}");
+    } else {
+        print!("{usage}");
+    }
+    Ok(())
+}
+
+/// `lint [notes-folder-path]`
+///
+/// Lists dead wiki-links, orphaned notes and empty notes. Exits non-zero
+/// if it finds any.
+fn run_lint(args: &[String]) -> Result<()> {
+    if args.len() > 3 {
+        eprintln!("Usage: {} lint [notes-folder-path]", args[0]);
+        process::exit(1);
+    }
+    let notes_path = resolve_notes_path(args.get(2).map(String::as_str), &args[0]);
+    let lint = VaultLint::load(&notes_path)?;
+    let dead = lint.dead_links();
+    let orphans = lint.orphan_notes();
+    let empty = lint.empty_notes();
+
+    for link in &dead {
+        println!("{}:{}: dead link [[{}]]", link.path, link.line, link.target);
+    }
+    for path in &orphans {
+        println!("{path}: orphan (no notes link here)");
+    }
+    for path in &empty {
+        println!("{path}: empty");
+    }
+    if !(dead.is_empty() && orphans.is_empty() && empty.is_empty()) {
+        eprintln!(
+            "{} dead links, {} orphans, {} empty notes",
+            dead.len(),
+            orphans.len(),
+            empty.len()
+        );
+        process::exit(1);
+    }
     Ok(())
 }
 
+/// `search <query> [notes-folder-path]`
+///
+/// Prints every place the query's words are found across the notes.
+fn run_search(args: &[String]) -> Result<()> {
+    let (query, notes_arg) = match &args[2..] {
+        [query] => (query, None),
+        [query, notes] => (query, Some(notes.as_str())),
+        _ => {
+            eprintln!("Usage: {} search <query> [notes-folder-path]", args[0]);
+            process::exit(1);
+        }
+    };
+    let notes_path = resolve_notes_path(notes_arg, &args[0]);
+    let index = SearchIndex::build(&notes_path)?;
+    let hits = index.search(query);
+    for hit in &hits {
+        println!("{}: {}", hit.path, hit.snippet);
+    }
+    if hits.is_empty() {
+        eprintln!("No matches in {} notes", index.len());
+    }
+    Ok(())
+}
+
+/// `replace <pattern> <replacement> [--regex] [--ignore-case] [--in FOLDER]
+/// [--apply] [notes-folder-path]` or `replace --undo [notes-folder-path]`
+///
+/// Previews replacing a pattern across the notes, or with `--apply` makes
+/// the change. `--undo` puts back the notes changed by the last `--apply`.
+fn run_replace(args: &[String]) -> Result<()> {
+    let usage = || -> ! {
+        eprintln!(
+            "Usage: {} replace <pattern> <replacement> [--regex] [--ignore-case] [--in FOLDER] [--apply] [notes-folder-path]\n       {} replace --undo [notes-folder-path]",
+            args[0], args[0]
+        );
+        process::exit(1);
+    };
+
+    let mut options = ReplaceOptions {
+        dry_run: true,
+        ..ReplaceOptions::default()
+    };
+    let mut undo = false;
+    let mut positional = Vec::new();
+    let mut rest = args[2..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--regex" => options.mode = MatchMode::Regex,
+            "--ignore-case" => options.ignore_case = true,
+            "--apply" => options.dry_run = false,
+            "--undo" => undo = true,
+            "--in" => match rest.next() {
+                Some(folder) => options.folder = Some(io::relative_path_from_str(folder)),
+                None => usage(),
+            },
+            _ if arg.starts_with("--") => usage(),
+            _ => positional.push(arg.as_str()),
+        }
+    }
+
+    if undo {
+        let notes_arg = match positional[..] {
+            [] => None,
+            [notes] => Some(notes),
+            _ => usage(),
+        };
+        let notes_path = resolve_notes_path(notes_arg, &args[0]);
+        match replace::undo_replace_all(&notes_path)? {
+            Some(restored) => println!("Restored {restored} note(s)"),
+            None => println!("Nothing to undo"),
+        }
+        return Ok(());
+    }
+
+    let (pattern, replacement, notes_arg) = match positional[..] {
+        [pattern, replacement] => (pattern, replacement, None),
+        [pattern, replacement, notes] => (pattern, replacement, Some(notes)),
+        _ => usage(),
+    };
+    let notes_path = resolve_notes_path(notes_arg, &args[0]);
+    let patches = replace::replace_all(&notes_path, pattern, replacement, &options)?;
+    let matches: usize = patches.iter().map(|patch| patch.edits.len()).sum();
+    for patch in &patches {
+        for edit in &patch.edits {
+            println!("{}:{}: {}", patch.path, edit.line, edit.context);
+            println!("    {:?} -> {:?}", edit.old_text, edit.new_text);
+        }
+    }
+    if options.dry_run {
+        println!(
+            "{matches} match(es) in {} note(s); run with --apply to replace",
+            patches.len()
+        );
+    } else {
+        println!(
+            "Replaced {matches} match(es) in {} note(s); undo with --undo",
+            patches.len()
+        );
+    }
+    Ok(())
+}
+
+/// `toc <note-path> [--numbered] [--write] [notes-folder-path]`
+///
+/// Prints a note's table of contents, or with `--write` regenerates it
+/// under the note's `<!-- toc -->` marker and saves.
+fn run_toc(args: &[String]) -> Result<()> {
+    let usage = || -> ! {
+        eprintln!(
+            "Usage: {} toc <note-path> [--numbered] [--write] [notes-folder-path]",
+            args[0]
+        );
+        process::exit(1);
+    };
+
+    let mut numbered = false;
+    let mut write = false;
+    let mut positional = Vec::new();
+    for arg in &args[2..] {
+        match arg.as_str() {
+            "--numbered" => numbered = true,
+            "--write" => write = true,
+            _ if arg.starts_with("--") => usage(),
+            _ => positional.push(arg.as_str()),
+        }
+    }
+    let (note, notes_arg) = match positional[..] {
+        [note] => (note, None),
+        [note, notes] => (note, Some(notes)),
+        _ => usage(),
+    };
+
+    let notes_path = resolve_notes_path(notes_arg, &args[0]);
+    let note_path = RelativePathBuf::from(note);
+    let mut doc = Document::from_bytes(io::read_file(&note_path, &notes_path)?.as_bytes())?;
+    if !write {
+        print!("{}", doc.table_of_contents(numbered));
+        return Ok(());
+    }
+
+    if !doc.text().lines().any(|line| line.trim() == TOC_MARKER) {
+        eprintln!("{note_path} has no {TOC_MARKER} line to put the table under");
+        process::exit(1);
+    }
+    let before = doc.text();
+    doc.apply(Cmd::UpdateToc { numbered });
+    if doc.text() == before {
+        println!("{note_path}: table of contents already up to date");
+    } else {
+        io::write_file(&note_path, &notes_path, &doc.text())?;
+        println!("{note_path}: table of contents updated");
+    }
+    Ok(())
+}
+
+/// Ask for a line of text on the terminal.
+fn ask(prompt: &str) -> String {
+    print!("{prompt}: ");
+    let _ = std::io::Write::flush(&mut std::io::stdout());
+    let mut answer = String::new();
+    let _ = std::io::stdin().read_line(&mut answer);
+    answer.trim().to_string()
+}
+
+/// Ask a yes/no question on the terminal, defaulting to no.
+fn confirm(prompt: &str) -> bool {
+    print!("{prompt} [y/N] ");
+    let _ = std::io::Write::flush(&mut std::io::stdout());
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).is_ok() && matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
+/// `serve [notes-folder-path] [--addr HOST:PORT] [--token TOKEN]`
+#[cfg(feature = "serve")]
+fn run_serve(args: &[String]) -> Result<()> {
+    let usage = || -> ! {
+        eprintln!(
+            "Usage: {} serve [notes-folder-path] [--addr HOST:PORT] [--token TOKEN]",
+            args[0]
+        );
+        process::exit(1);
+    };
+
+    let mut notes_arg = None;
+    let mut addr = serve::DEFAULT_ADDR.to_string();
+    let mut token = env::var(serve::TOKEN_ENV).ok().filter(|t| !t.is_empty());
+
+    let mut rest = args[2..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--addr" => addr = rest.next().cloned().unwrap_or_else(|| usage()),
+            "--token" => token = Some(rest.next().cloned().unwrap_or_else(|| usage())),
+            _ if arg.starts_with("--") || notes_arg.is_some() => usage(),
+            _ => notes_arg = Some(arg.as_str()),
+        }
+    }
+
+    let Some(token) = token else {
+        eprintln!(
+            "Error: set {} or pass --token; every request must carry it",
+            serve::TOKEN_ENV
+        );
+        process::exit(1);
+    };
+    let notes_path = resolve_notes_path(notes_arg, &args[0]);
+    serve::run(serve::ServeState { notes_path, token }, &addr)
+}
+
+#[cfg(not(feature = "serve"))]
+fn run_serve(_args: &[String]) -> Result<()> {
+    eprintln!("Error: this build does not include the `serve` feature");
+    eprintln!("Rebuild with: cargo build -p markdown-neuraxis-cli --features serve");
+    process::exit(1);
+}
+
 fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()>
 where
     <B as ratatui::backend::Backend>::Error: Send + Sync + 'static,
 {
     loop {
+        app.poll_notices();
         terminal.draw(|f| ui(f, app))?;
 
+        // Wake up periodically to pick up edits made outside the app
+        if !event::poll(io::WATCH_POLL_INTERVAL)? {
+            app.apply_external_changes();
+            continue;
+        }
         if let Event::Key(key) = event::read()? {
+            app.notice = None;
+            if let Some(pending) = app.pending_mark.take() {
+                // Like vim, marks are named by a lowercase letter
+                if let KeyCode::Char(name @ 'a'..='z') = key.code {
+                    match pending {
+                        PendingMark::Set => app.set_mark(name)?,
+                        PendingMark::Jump => app.jump_to_mark(name),
+                    }
+                }
+                continue;
+            }
             match key.code {
                 KeyCode::Char('q') => return Ok(()),
-                KeyCode::Down | KeyCode::Char('j') => app.next_file(),
-                KeyCode::Up | KeyCode::Char('k') => app.previous_file(),
+                KeyCode::Tab => app.toggle_focus(),
+                KeyCode::Down | KeyCode::Char('j') => match app.focus {
+                    Focus::Files => app.next_file(),
+                    Focus::Content => app.next_block(),
+                },
+                KeyCode::Up | KeyCode::Char('k') => match app.focus {
+                    Focus::Files => app.previous_file(),
+                    Focus::Content => app.previous_block(),
+                },
+                KeyCode::Char('m') => app.pending_mark = Some(PendingMark::Set),
+                KeyCode::Char('\'') => app.pending_mark = Some(PendingMark::Jump),
                 KeyCode::Enter | KeyCode::Char(' ') => {
                     let _ = app.activate_selected_item();
                 }
@@ -391,12 +1469,49 @@ where
                 KeyCode::Left => {
                     let _ = app.collapse_selected_folder();
                 }
+                KeyCode::Char('c') => app.copy_table_of_contents()?,
+                KeyCode::Char('t') => app.open_today()?,
+                KeyCode::Char('n') => app.code_line_numbers = !app.code_line_numbers,
+                KeyCode::Char('<') => app.code_scroll = app.code_scroll.saturating_sub(4),
+                KeyCode::Char('>') => app.code_scroll += 4,
                 _ => {}
             }
         }
     }
 }
 
+/// A code block line cut to `width` columns after the sideways scroll,
+/// behind a gutter with its line number. Highlighted lines get an accent
+/// gutter.
+fn code_line(
+    app: &App,
+    line: &CodeLine,
+    gutter_width: usize,
+    width: usize,
+    style: Style,
+) -> Line<'static> {
+    let gutter = if app.code_line_numbers {
+        format!("{:>gutter_width$} │ ", line.number)
+    } else {
+        "│ ".to_string()
+    };
+    let text: String = line
+        .text
+        .chars()
+        .skip(app.code_scroll)
+        .take(width.saturating_sub(gutter.chars().count()))
+        .collect();
+    let gutter_style = if line.highlighted {
+        style.fg(color(app.theme.accent()))
+    } else {
+        style
+    };
+    Line::from(vec![
+        Span::styled(gutter, gutter_style),
+        Span::styled(text, style),
+    ])
+}
+
 fn ui(f: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
@@ -427,23 +1542,93 @@ fn ui(f: &mut Frame, app: &mut App) {
 
     let files_list = List::new(file_items)
         .block(Block::default().borders(Borders::ALL).title("Files"))
-        .highlight_style(Style::default().bg(Color::Yellow).fg(Color::Black));
+        .highlight_style(selection_style(&app.theme));
+
+    // Marks panel under the file list, only once a mark is set
+    let marks_height = (app.bookmarks.len() as u16 + 2).min(12);
+    let left = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Min(0),
+                Constraint::Length(if app.bookmarks.is_empty() {
+                    0
+                } else {
+                    marks_height
+                }),
+            ]
+            .as_ref(),
+        )
+        .split(chunks[0]);
 
-    f.render_stateful_widget(files_list, chunks[0], &mut app.file_list_state);
+    f.render_stateful_widget(files_list, left[0], &mut app.file_list_state);
+
+    if !app.bookmarks.is_empty() {
+        let mark_items: Vec<ListItem> = app
+            .bookmarks
+            .iter()
+            .map(|(name, bookmark)| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{name} "),
+                        Style::default().fg(color(app.theme.accent())),
+                    ),
+                    Span::raw(format!("{}  {}", bookmark.path, bookmark.excerpt)),
+                ]))
+            })
+            .collect();
+        let marks =
+            List::new(mark_items).block(Block::default().borders(Borders::ALL).title("Marks"));
+        f.render_widget(marks, left[1]);
+    }
 
     // Content panel
+    // Lines of the selected block, highlighted while the content panel has focus
+    let selected_lines = app
+        .selected_block
+        .filter(|_| app.focus == Focus::Content)
+        .and_then(|i| {
+            let start = app.content_blocks.get(i)?.line;
+            let end = app
+                .content_blocks
+                .get(i + 1)
+                .map_or(app.current_content.len(), |next| next.line);
+            Some(start..end)
+        });
+    // Room for code inside the panel borders
+    let code_width = usize::from(chunks[1].width.saturating_sub(2));
     let content_text = if app.current_content.is_empty() {
         vec![Line::from("Select a file to view its content")]
     } else {
         app.current_content
             .iter()
-            .map(|line| Line::from(vec![Span::raw(line.clone())]))
+            .enumerate()
+            .map(|(i, line)| {
+                let style = match &selected_lines {
+                    Some(lines) if lines.contains(&i) => selection_style(&app.theme),
+                    _ => Style::default(),
+                };
+                match line {
+                    ContentLine::Text(text) => Line::from(vec![Span::styled(text.clone(), style)]),
+                    ContentLine::Code { line, gutter_width } => {
+                        code_line(app, line, *gutter_width, code_width, style)
+                    }
+                }
+            })
             .collect()
     };
+    // Keep the selected block in view (approximate, as long lines wrap)
+    let scroll = selected_lines.map_or(0, |lines| {
+        lines
+            .start
+            .saturating_sub(usize::from(chunks[1].height / 3))
+    });
 
     let content = Paragraph::new(content_text)
         .block(Block::default().borders(Borders::ALL).title("Content"))
-        .wrap(ratatui::widgets::Wrap { trim: true });
+        // Not trimmed, so code keeps its indentation
+        .wrap(ratatui::widgets::Wrap { trim: false })
+        .scroll((u16::try_from(scroll).unwrap_or(u16::MAX), 0));
 
     f.render_widget(content, chunks[1]);
 
@@ -452,9 +1637,19 @@ fn ui(f: &mut Frame, app: &mut App) {
         Span::raw("q: Quit | "),
         Span::raw("↑/k: Previous | "),
         Span::raw("↓/j: Next | "),
-        Span::raw("Enter/Space: Toggle | →: Expand | ←: Collapse"),
+        Span::raw("Enter/Space: Toggle | →: Expand | ←: Collapse | "),
+        Span::raw("c: Copy TOC | t: Today | "),
+        Span::raw("n: Line numbers | </>: Scroll code | "),
+        Span::raw("Tab: Files/Content | m<a-z>: Set mark | '<a-z>: Jump to mark"),
     ]);
 
+    let help_text = match &app.notice {
+        Some(notice) => Line::from(Span::styled(
+            notice.to_string(),
+            Style::default().fg(color(app.theme.accent())),
+        )),
+        None => help_text,
+    };
     let help = Paragraph::new(vec![help_text]).block(Block::default());
 
     // Place help at bottom
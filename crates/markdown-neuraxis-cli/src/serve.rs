@@ -0,0 +1,762 @@
+//! HTTP API server mode (`serve` subcommand, behind the `serve` feature).
+//!
+//! Exposes the vault over plain HTTP so a phone browser or another device on
+//! the LAN can read and edit notes hosted on a desktop. Every endpoint
+//! requires `Authorization: Bearer <token>`, and the server won't start
+//! without a token. It listens on localhost unless given another address.
+//!
+//! | Method | Path                          | Description                      |
+//! |--------|-------------------------------|----------------------------------|
+//! | GET    | `/api/files`                  | All markdown files in the vault  |
+//! | GET    | `/api/notes/<path>`           | Note text and block snapshot     |
+//! | GET    | `/api/search?q=<text>`        | Case-insensitive line search     |
+//! | POST   | `/api/notes/<path>/commands`  | Apply an editing command, save   |
+//! | POST   | `/api/notes/<path>/append`    | Merge Markdown into a note       |
+//! | PUT    | `/api/notes/<path>`           | Replace note content             |
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow};
+use markdown_neuraxis_engine::dates::{self, DateError, DateFormat, DateSpec};
+use markdown_neuraxis_engine::editing::snapshot::{Block, BlockContent, BlockKind};
+use markdown_neuraxis_engine::editing::{AnchorId, BlockPosition};
+use markdown_neuraxis_engine::journal::{JournalCalendar, WeekStart};
+use markdown_neuraxis_engine::vault::{AppendPosition, Vault};
+use markdown_neuraxis_engine::{BlockConversion, Cmd, Document, ListNumbering, Marker, io, titles};
+use relative_path::{Component, RelativePath};
+use serde_json::{Value, json};
+
+/// Default address to listen on. Pass `--addr 0.0.0.0:7878` to let other
+/// devices on the LAN connect.
+pub const DEFAULT_ADDR: &str = "127.0.0.1:7878";
+
+/// Environment variable holding the access token
+pub const TOKEN_ENV: &str = "NEURAXIS_TOKEN";
+
+/// Largest request body accepted, in bytes
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Maximum number of search results returned
+const MAX_SEARCH_RESULTS: usize = 100;
+
+/// Shared server configuration
+pub struct ServeState {
+    pub notes_path: PathBuf,
+    /// Token required on every request
+    pub token: String,
+}
+
+/// An HTTP response before it is handed to the transport
+#[derive(Debug, PartialEq)]
+pub struct ApiResponse {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl ApiResponse {
+    fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message.into() }),
+        }
+    }
+}
+
+/// Run the server until the process is killed.
+pub fn run(state: ServeState, addr: &str) -> Result<()> {
+    let server =
+        tiny_http::Server::http(addr).map_err(|e| anyhow!("Failed to bind {addr}: {e}"))?;
+
+    println!("Serving {} on http://{addr}", state.notes_path.display());
+
+    for mut request in server.incoming_requests() {
+        let auth = request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv("Authorization"))
+            .map(|h| h.value.as_str().to_string());
+        let response = match read_body(&mut request) {
+            Ok(body) => handle(
+                &state,
+                request.method().as_str(),
+                request.url(),
+                auth.as_deref(),
+                &body,
+            ),
+            Err(response) => response,
+        };
+
+        let content_type =
+            tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+                .expect("static header is valid");
+        let http_response = tiny_http::Response::from_string(response.body.to_string())
+            .with_status_code(response.status)
+            .with_header(content_type);
+        if let Err(e) = request.respond(http_response) {
+            eprintln!("Failed to send response: {e}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Read a request body of at most [`MAX_BODY_BYTES`].
+fn read_body(request: &mut tiny_http::Request) -> Result<String, ApiResponse> {
+    let too_large = || ApiResponse::error(413, "Request body too large");
+    if request
+        .body_length()
+        .is_some_and(|len| len > MAX_BODY_BYTES)
+    {
+        return Err(too_large());
+    }
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_BYTES as u64 + 1)
+        .read_to_string(&mut body)
+        .map_err(|e| ApiResponse::error(400, format!("Invalid request body: {e}")))?;
+    if body.len() > MAX_BODY_BYTES {
+        return Err(too_large());
+    }
+    Ok(body)
+}
+
+/// Route a request to its handler.
+pub fn handle(
+    state: &ServeState,
+    method: &str,
+    url: &str,
+    auth: Option<&str>,
+    body: &str,
+) -> ApiResponse {
+    if !authorized(state, auth) {
+        return ApiResponse::error(401, "Missing or invalid token");
+    }
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let path = percent_decode(path);
+
+    match (method, path.as_str()) {
+        ("GET", "/api/files") => list_files(state),
+        ("GET", "/api/search") => {
+            let q = query_param(query, "q").unwrap_or_default();
+            search(state, &q)
+        }
+        (_, p) if p.starts_with("/api/notes/") => {
+            let note = &p["/api/notes/".len()..];
+            match method {
+                "GET" => with_note_path(note, |rel| get_note(state, rel)),
+                "PUT" => with_note_path(note, |rel| put_note(state, rel, body)),
+                "POST" => {
+                    if let Some(note) = note.strip_suffix("/commands") {
+                        with_note_path(note, |rel| apply_command(state, rel, body))
+                    } else if let Some(note) = note.strip_suffix("/append") {
+                        with_note_path(note, |rel| append_to_note(state, rel, body))
+                    } else {
+                        ApiResponse::error(404, "Not found")
+                    }
+                }
+                _ => ApiResponse::error(405, "Method not allowed"),
+            }
+        }
+        _ => ApiResponse::error(404, "Not found"),
+    }
+}
+
+/// Whether the request carries the server's token.
+fn authorized(state: &ServeState, auth: Option<&str>) -> bool {
+    auth.and_then(|a| a.strip_prefix("Bearer "))
+        .is_some_and(|given| constant_time_eq(given.as_bytes(), state.token.as_bytes()))
+}
+
+/// Compare without stopping at the first difference, so response times
+/// don't reveal how much of a guessed token is right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let diff = (0..a.len().max(b.len())).fold(a.len() ^ b.len(), |diff, i| {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        diff | usize::from(x ^ y)
+    });
+    diff == 0
+}
+
+/// Validate a note path from the URL, rejecting anything that could escape
+/// the notes root or that is not a markdown file.
+///
+/// Backslashes, drive letters and absolute paths are refused outright: on
+/// Windows they are path syntax, so a name like `..\secret.md` or `C:x.md`
+/// that looks like one component here would leave the vault there.
+fn with_note_path(note: &str, handler: impl FnOnce(&RelativePath) -> ApiResponse) -> ApiResponse {
+    let platform_syntax = note.contains(['\\', ':']) || note.starts_with('/');
+    let rel = io::relative_path_from_str(note);
+    let escapes = rel.components().any(|c| match c {
+        Component::Normal(name) => !matches!(
+            Path::new(name).components().collect::<Vec<_>>()[..],
+            [std::path::Component::Normal(_)]
+        ),
+        _ => true,
+    });
+    if note.is_empty() || platform_syntax || escapes || rel.extension() != Some("md") {
+        return ApiResponse::error(400, "Invalid note path");
+    }
+    handler(&rel)
+}
+
+fn list_files(state: &ServeState) -> ApiResponse {
+    match io::scan_markdown_relative_paths(&state.notes_path) {
+        Ok(mut paths) => {
+            paths.sort();
+            let files: Vec<_> = paths.iter().map(|p| p.as_str()).collect();
+            ApiResponse::ok(json!({ "files": files }))
+        }
+        Err(e) => ApiResponse::error(500, e.to_string()),
+    }
+}
+
+fn get_note(state: &ServeState, rel: &RelativePath) -> ApiResponse {
+    match load_document(&state.notes_path, rel) {
+        Ok(doc) => ApiResponse::ok(note_json(rel, &doc)),
+        Err(response) => response,
+    }
+}
+
+fn put_note(state: &ServeState, rel: &RelativePath, body: &str) -> ApiResponse {
+    if let Err(e) = io::write_file(rel, &state.notes_path, body) {
+        return ApiResponse::error(500, e.to_string());
+    }
+    get_note(state, rel)
+}
+
+fn apply_command(state: &ServeState, rel: &RelativePath, body: &str) -> ApiResponse {
+    let cmd = match serde_json::from_str(body)
+        .map_err(|e| e.to_string())
+        .and_then(|value: Value| parse_command(&value))
+    {
+        Ok(cmd) => cmd,
+        Err(e) => return ApiResponse::error(400, format!("Invalid command: {e}")),
+    };
+
+    let mut doc = match load_document(&state.notes_path, rel) {
+        Ok(doc) => doc,
+        Err(response) => return response,
+    };
+    if !command_in_bounds(&cmd, &doc.text()) {
+        return ApiResponse::error(
+            400,
+            "Command offset is outside the document or inside a character",
+        );
+    }
+    doc.apply(cmd);
+
+    if let Err(e) = io::write_file(rel, &state.notes_path, &doc.text()) {
+        return ApiResponse::error(500, e.to_string());
+    }
+    ApiResponse::ok(note_json(rel, &doc))
+}
+
+/// Body: `{"markdown": "...", "position": "top" | "bottom" | "under_heading",
+/// "heading": "..."}`; position defaults to `bottom`.
+fn append_to_note(state: &ServeState, rel: &RelativePath, body: &str) -> ApiResponse {
+    let request = match serde_json::from_str::<Value>(body) {
+        Ok(request) => request,
+        Err(e) => return ApiResponse::error(400, format!("Invalid append request: {e}")),
+    };
+    let Some(markdown) = request.get("markdown").and_then(Value::as_str) else {
+        return ApiResponse::error(400, "Invalid append request: missing 'markdown'");
+    };
+    let heading = request.get("heading").and_then(Value::as_str);
+    let position = match (request.get("position").and_then(Value::as_str), heading) {
+        (Some("top"), _) => AppendPosition::Top,
+        (None | Some("bottom"), _) => AppendPosition::Bottom,
+        (Some("under_heading"), Some(heading)) => AppendPosition::UnderHeading(heading.to_string()),
+        (Some(other), _) => {
+            return ApiResponse::error(400, format!("Invalid append position: {other}"));
+        }
+    };
+
+    let vault = Vault::new(&state.notes_path);
+    if let Err(e) = vault.append_to_note(rel, markdown, &position) {
+        return ApiResponse::error(500, e.to_string());
+    }
+    get_note(state, rel)
+}
+
+fn search(state: &ServeState, q: &str) -> ApiResponse {
+    let needle = q.trim().to_lowercase();
+    if needle.is_empty() {
+        return ApiResponse::error(400, "Missing search query");
+    }
+    let paths = match io::scan_markdown_relative_paths(&state.notes_path) {
+        Ok(paths) => paths,
+        Err(e) => return ApiResponse::error(500, e.to_string()),
+    };
+
+    let mut results = Vec::new();
+    'files: for path in paths {
+        let Ok(source) = io::read_file(&path, &state.notes_path) else {
+            continue;
+        };
+        for (i, line) in source.lines().enumerate() {
+            if line.to_lowercase().contains(&needle) {
+                results.push(json!({ "path": path.as_str(), "line": i + 1, "text": line }));
+                if results.len() >= MAX_SEARCH_RESULTS {
+                    break 'files;
+                }
+            }
+        }
+    }
+    ApiResponse::ok(json!({ "query": q, "results": results }))
+}
+
+fn load_document(notes_path: &Path, rel: &RelativePath) -> Result<Document, ApiResponse> {
+    let content = io::read_file(rel, notes_path).map_err(|e| match e {
+        io::IoError::NotFound(_) => ApiResponse::error(404, "Note not found"),
+        e => ApiResponse::error(500, e.to_string()),
+    })?;
+    Document::from_bytes(content.as_bytes()).map_err(|e| ApiResponse::error(500, e.to_string()))
+}
+
+fn note_json(rel: &RelativePath, doc: &Document) -> Value {
+    let blocks: Vec<_> = doc
+        .snapshot()
+        .blocks
+        .iter()
+        .map(|b| block_json(doc, b))
+        .collect();
+    json!({
+        "path": rel.as_str(),
+        "title": titles::derive_title(rel, &doc.text()),
+        "text": doc.text(),
+        "blocks": blocks,
+    })
+}
+
+fn block_json(doc: &Document, block: &Block) -> Value {
+    let children: Vec<_> = match &block.content {
+        BlockContent::Leaf => Vec::new(),
+        BlockContent::Children(children) => children.iter().map(|c| block_json(doc, c)).collect(),
+    };
+    let content_range = block.content_range();
+    let mut value = json!({
+        "id": block.id.0.to_string(),
+        "kind": block_kind_name(&block.kind),
+        "range": [block.node_range.start, block.node_range.end],
+        "content": doc.slice(content_range),
+        "children": children,
+    });
+    if let BlockKind::Paragraph { chunks } = &block.kind
+        && !chunks.is_empty()
+    {
+        value["chunks"] = chunks.iter().map(|c| json!([c.start, c.end])).collect();
+    }
+    value
+}
+
+fn block_kind_name(kind: &BlockKind) -> &'static str {
+    match kind {
+        BlockKind::Root => "root",
+        BlockKind::List { .. } => "list",
+        BlockKind::ListItem { .. } => "list_item",
+        BlockKind::BlockQuote => "block_quote",
+        BlockKind::Paragraph { .. } => "paragraph",
+        BlockKind::Heading { .. } => "heading",
+        BlockKind::FencedCode { .. } => "fenced_code",
+        BlockKind::ThematicBreak => "thematic_break",
+        BlockKind::FrontMatter { .. } => "front_matter",
+        BlockKind::Table { .. } => "table",
+        BlockKind::TableRow { .. } => "table_row",
+        BlockKind::TableCell => "table_cell",
+        BlockKind::Embed { .. } => "embed",
+    }
+}
+
+/// Parse a JSON command such as `{"cmd": "insert_text", "at": 0, "text": "x"}`.
+fn parse_command(value: &Value) -> Result<Cmd, String> {
+    let usize_field = |name: &str| {
+        value
+            .get(name)
+            .and_then(Value::as_u64)
+            .map(|n| n as usize)
+            .ok_or_else(|| format!("missing number field '{name}'"))
+    };
+    let str_field = |name: &str| {
+        value
+            .get(name)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| format!("missing string field '{name}'"))
+    };
+    let range = || Ok::<_, String>(usize_field("start")?..usize_field("end")?);
+
+    let name = value
+        .get("cmd")
+        .and_then(Value::as_str)
+        .ok_or("missing 'cmd'")?;
+    let cmd = match name {
+        "insert_text" => Cmd::InsertText {
+            at: usize_field("at")?,
+            text: str_field("text")?,
+        },
+        "delete_range" => Cmd::DeleteRange { range: range()? },
+        "replace_range" => Cmd::ReplaceRange {
+            range: range()?,
+            text: str_field("text")?,
+        },
+        "split_list_item" => Cmd::SplitListItem {
+            at: usize_field("at")?,
+        },
+        "indent_lines" => Cmd::IndentLines { range: range()? },
+        "outdent_lines" => Cmd::OutdentLines { range: range()? },
+        "toggle_marker" => Cmd::ToggleMarker {
+            line_start: usize_field("line_start")?,
+            to: match str_field("marker")?.as_str() {
+                "-" => Marker::Dash,
+                "*" => Marker::Asterisk,
+                "+" => Marker::Plus,
+                n if n.ends_with('.') && n[..n.len() - 1].parse::<u32>().is_ok() => {
+                    Marker::Numbered(n.to_string())
+                }
+                other => return Err(format!("unknown marker '{other}'")),
+            },
+        },
+        "convert_block" => Cmd::ConvertBlock {
+            range: range()?,
+            to: match str_field("to")?.as_str() {
+                "paragraph" => BlockConversion::Paragraph,
+                "heading" => match value.get("level").map(Value::as_u64) {
+                    None => BlockConversion::Heading(1),
+                    Some(Some(level @ 1..=6)) => BlockConversion::Heading(level as u8),
+                    Some(_) => return Err("heading level must be 1 to 6".to_string()),
+                },
+                "bullet" => BlockConversion::Bullet,
+                "quote" => BlockConversion::Quote,
+                "fenced_code" => BlockConversion::FencedCode,
+                "indented_code" => BlockConversion::IndentedCode,
+                other => return Err(format!("unknown block type '{other}'")),
+            },
+        },
+        "materialize_list_numbering" => Cmd::MaterializeListNumbering {
+            range: range()?,
+            numbering: match str_field("numbering")?.as_str() {
+                "source" => ListNumbering::Source,
+                "nested" => ListNumbering::Nested,
+                "outline" => ListNumbering::Outline,
+                other => return Err(format!("unknown list numbering '{other}'")),
+            },
+        },
+        "paste_url" => Cmd::PasteUrl {
+            range: range()?,
+            url: str_field("url")?,
+        },
+        "update_toc" => Cmd::UpdateToc {
+            numbered: value
+                .get("numbered")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+        },
+        "insert_table" => Cmd::InsertTableFromDelimited {
+            at: usize_field("at")?,
+            text: str_field("text")?,
+            delimiter: match value.get("delimiter").and_then(Value::as_str) {
+                None => ',',
+                Some(d) if d.chars().count() == 1 => d.chars().next().unwrap_or(','),
+                Some(other) => return Err(format!("bad delimiter '{other}'")),
+            },
+        },
+        "insert_date" => {
+            // Phrases like "tomorrow" count from today on this machine
+            let today = JournalCalendar::new(None, WeekStart::default())
+                .map_err(|e| e.to_string())?
+                .today();
+            let phrase = str_field("date")?;
+            Cmd::InsertDate {
+                at: usize_field("at")?,
+                spec: DateSpec {
+                    date: dates::parse_date(&phrase, today)
+                        .ok_or_else(|| format!("unknown date '{phrase}'"))?,
+                    format: match value.get("format").and_then(Value::as_str) {
+                        None => DateFormat::default(),
+                        Some(format) => format.parse().map_err(|e: DateError| e.to_string())?,
+                    },
+                },
+            }
+        }
+        "move_block" => {
+            // IDs go out as strings, since they don't fit in a JSON number
+            let anchor = |name: &str| {
+                str_field(name)?
+                    .parse()
+                    .map(AnchorId)
+                    .map_err(|_| format!("bad anchor id in '{name}'"))
+            };
+            Cmd::MoveBlock {
+                anchor_id: anchor("id")?,
+                to: match value.get("after").and_then(Value::as_str) {
+                    Some(_) => BlockPosition::After(anchor("after")?),
+                    None => BlockPosition::Before(anchor("before")?),
+                },
+            }
+        }
+        other => return Err(format!("unknown command '{other}'")),
+    };
+    Ok(cmd)
+}
+
+/// Commands from the network are untrusted; reject offsets past the end or
+/// inside a UTF-8 character, which the rope would panic on.
+fn command_in_bounds(cmd: &Cmd, text: &str) -> bool {
+    match cmd {
+        Cmd::InsertText { at, .. }
+        | Cmd::SplitListItem { at }
+        | Cmd::InsertTableFromDelimited { at, .. }
+        | Cmd::InsertDate { at, .. } => text.is_char_boundary(*at),
+        Cmd::ToggleMarker { line_start, .. } => text.is_char_boundary(*line_start),
+        Cmd::UpdateToc { .. } | Cmd::MoveBlock { .. } => true,
+        Cmd::DeleteRange { range }
+        | Cmd::ReplaceRange { range, .. }
+        | Cmd::IndentLines { range }
+        | Cmd::OutdentLines { range }
+        | Cmd::ConvertBlock { range, .. }
+        | Cmd::MaterializeListNumbering { range, .. }
+        | Cmd::PasteUrl { range, .. } => {
+            range.start <= range.end
+                && text.is_char_boundary(range.start)
+                && text.is_char_boundary(range.end)
+        }
+    }
+}
+
+fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| percent_decode(&value.replace('+', " ")))
+}
+
+/// Decode `%XX` escapes; invalid escapes are kept as-is.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = s
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            out.push(byte);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const AUTH: Option<&str> = Some("Bearer secret");
+
+    fn state_with(files: &[(&str, &str)]) -> (TempDir, ServeState) {
+        let dir = TempDir::new().unwrap();
+        for (path, content) in files {
+            let full = dir.path().join(path);
+            fs::create_dir_all(full.parent().unwrap()).unwrap();
+            fs::write(full, content).unwrap();
+        }
+        let state = ServeState {
+            notes_path: dir.path().to_path_buf(),
+            token: "secret".to_string(),
+        };
+        (dir, state)
+    }
+
+    #[test]
+    fn test_list_files() {
+        let (_dir, state) = state_with(&[("a.md", "# A\n"), ("sub/b.md", "b\n")]);
+        let response = handle(&state, "GET", "/api/files", AUTH, "");
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body["files"], json!(["a.md", "sub/b.md"]));
+    }
+
+    #[test]
+    fn test_get_note_snapshot() {
+        let (_dir, state) = state_with(&[("My Note.md", "# Title\n\n- item\n")]);
+        let response = handle(&state, "GET", "/api/notes/My%20Note.md", AUTH, "");
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body["title"], "Title");
+        assert_eq!(response.body["blocks"][0]["kind"], "heading");
+        assert_eq!(response.body["blocks"][1]["kind"], "list");
+    }
+
+    #[test]
+    fn test_rejects_path_traversal() {
+        let (_dir, state) = state_with(&[]);
+        let response = handle(&state, "GET", "/api/notes/../secret.md", AUTH, "");
+        assert_eq!(response.status, 400);
+    }
+
+    #[test]
+    fn test_rejects_windows_path_syntax() {
+        let (_dir, state) = state_with(&[]);
+        for note in [
+            "..%5C..%5Csecret.md",
+            "C:%5Cx.md",
+            "C:x.md",
+            "%2Fetc%2Fx.md",
+            "/etc/x.md",
+            "notes%5C..%5C..%5Csecret.md",
+        ] {
+            let url = format!("/api/notes/{note}");
+            let response = handle(&state, "GET", &url, AUTH, "");
+            assert_eq!(response.status, 400, "{note}");
+        }
+    }
+
+    #[test]
+    fn test_search() {
+        let (_dir, state) = state_with(&[("a.md", "one\nTwo words\n")]);
+        let response = handle(&state, "GET", "/api/search?q=two+words", AUTH, "");
+        assert_eq!(response.body["results"][0]["line"], 2);
+    }
+
+    #[test]
+    fn test_every_route_requires_token() {
+        let (dir, state) = state_with(&[("a.md", "a\n")]);
+        for (method, url) in [
+            ("GET", "/api/files"),
+            ("GET", "/api/notes/a.md"),
+            ("GET", "/api/search?q=a"),
+            ("PUT", "/api/notes/a.md"),
+        ] {
+            for auth in [
+                None,
+                Some("Bearer nope"),
+                Some("Bearer secre"),
+                Some("secret"),
+            ] {
+                let response = handle(&state, method, url, auth, "b\n");
+                assert_eq!(response.status, 401, "{method} {url} {auth:?}");
+            }
+        }
+        assert_eq!(fs::read_to_string(dir.path().join("a.md")).unwrap(), "a\n");
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+        assert!(!constant_time_eq(b"", b"secret"));
+    }
+
+    #[test]
+    fn test_apply_command_saves() {
+        let (dir, state) = state_with(&[("a.md", "hello\n")]);
+        let response = handle(
+            &state,
+            "POST",
+            "/api/notes/a.md/commands",
+            AUTH,
+            r#"{"cmd": "insert_text", "at": 5, "text": " world"}"#,
+        );
+        assert_eq!(response.status, 200);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.md")).unwrap(),
+            "hello world\n"
+        );
+    }
+
+    #[test]
+    fn test_append_merges_under_heading() {
+        let (dir, state) = state_with(&[("day.md", "# Day\n\n## Tasks\n\n- a\n\n## Notes\n")]);
+        let response = handle(
+            &state,
+            "POST",
+            "/api/notes/day.md/append",
+            AUTH,
+            r#"{"markdown": "- b", "position": "under_heading", "heading": "Tasks"}"#,
+        );
+        assert_eq!(response.status, 200);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("day.md")).unwrap(),
+            "# Day\n\n## Tasks\n\n- a\n- b\n\n## Notes\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_command_out_of_bounds() {
+        let (_dir, state) = state_with(&[("a.md", "hi\n")]);
+        let response = handle(
+            &state,
+            "POST",
+            "/api/notes/a.md/commands",
+            AUTH,
+            r#"{"cmd": "delete_range", "start": 0, "end": 99}"#,
+        );
+        assert_eq!(response.status, 400);
+    }
+
+    #[test]
+    fn test_apply_command_inside_character() {
+        let (dir, state) = state_with(&[("a.md", "café\n")]);
+        for body in [
+            r#"{"cmd": "insert_text", "at": 4, "text": "x"}"#,
+            r#"{"cmd": "delete_range", "start": 4, "end": 5}"#,
+            r#"{"cmd": "replace_range", "start": 0, "end": 4, "text": "x"}"#,
+        ] {
+            let response = handle(&state, "POST", "/api/notes/a.md/commands", AUTH, body);
+            assert_eq!(response.status, 400, "{body}");
+        }
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.md")).unwrap(),
+            "café\n"
+        );
+    }
+
+    #[test]
+    fn test_convert_to_heading_checks_level() {
+        let (dir, state) = state_with(&[("a.md", "Title\n")]);
+        for level in ["0", "7", "256", "\"2\""] {
+            let body = format!(
+                r#"{{"cmd": "convert_block", "start": 0, "end": 5, "to": "heading", "level": {level}}}"#
+            );
+            let response = handle(&state, "POST", "/api/notes/a.md/commands", AUTH, &body);
+            assert_eq!(response.status, 400, "{level}");
+        }
+        let body = r#"{"cmd": "convert_block", "start": 0, "end": 5, "to": "heading", "level": 2}"#;
+        let response = handle(&state, "POST", "/api/notes/a.md/commands", AUTH, body);
+        assert_eq!(response.status, 200);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.md")).unwrap(),
+            "## Title\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_insert_date() {
+        let (dir, state) = state_with(&[("a.md", "due \n")]);
+        let response = handle(
+            &state,
+            "POST",
+            "/api/notes/a.md/commands",
+            AUTH,
+            r#"{"cmd": "insert_date", "at": 4, "date": "2024-03-31", "format": "link"}"#,
+        );
+        assert_eq!(response.status, 200);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.md")).unwrap(),
+            "due [[2024-03-31]]\n"
+        );
+    }
+}
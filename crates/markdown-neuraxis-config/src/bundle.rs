@@ -0,0 +1,458 @@
+//! Settings bundle: a single TOML file carrying the app config and the
+//! vault's `.neuraxis/` settings (dictionary, keymaps, templates, saved
+//! searches...) so a setup can be copied to another machine.
+//!
+//! The notes folder location is machine-specific, so importing `config.toml`
+//! keeps the target machine's `notes_path`.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+
+use crate::Config;
+
+/// Bundle format version written by [`SettingsBundle::export`].
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Folder inside the notes root holding vault settings.
+pub const VAULT_SETTINGS_DIR: &str = ".neuraxis";
+
+/// Sub-folders of [`VAULT_SETTINGS_DIR`] that hold state, not settings.
+const VAULT_EXCLUDED_DIRS: &[&str] = &["trash"];
+
+/// App-level files exported from the config directory.
+const APP_FILES: &[&str] = &["config.toml"];
+
+#[derive(Debug, Error)]
+pub enum BundleError {
+    #[error("Failed to access {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Failed to parse settings bundle: {0}")]
+    Parse(#[from] toml::de::Error),
+
+    #[error("Failed to write settings bundle: {0}")]
+    Serialize(#[from] toml::ser::Error),
+
+    #[error("Unsupported settings bundle version {0} (expected {BUNDLE_VERSION})")]
+    UnsupportedVersion(u32),
+
+    #[error("Settings bundle contains an unsafe path: {0}")]
+    UnsafePath(String),
+}
+
+/// Where a bundled file lives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BundleScope {
+    /// Relative to the app config directory
+    App,
+    /// Relative to the notes root
+    Vault,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleFile {
+    pub scope: BundleScope,
+    /// Forward-slash path relative to the scope's root
+    pub path: String,
+    pub contents: String,
+}
+
+/// Directories a bundle is exported from or imported into.
+#[derive(Debug, Clone)]
+pub struct BundleLocations {
+    pub config_dir: PathBuf,
+    /// Vault settings are skipped when no notes folder is known
+    pub notes_root: Option<PathBuf>,
+}
+
+impl BundleLocations {
+    /// The default config directory with the given notes folder.
+    pub fn new(notes_root: Option<PathBuf>) -> Self {
+        let config_path = Config::config_path();
+        Self {
+            config_dir: config_path
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+            notes_root,
+        }
+    }
+
+    fn root(&self, scope: BundleScope) -> Option<&Path> {
+        match scope {
+            BundleScope::App => Some(&self.config_dir),
+            BundleScope::Vault => self.notes_root.as_deref(),
+        }
+    }
+}
+
+/// A bundled file that differs from what is already on disk.
+#[derive(Debug)]
+pub struct ImportConflict<'a> {
+    pub file: &'a BundleFile,
+    pub existing: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    Overwrite,
+    Keep,
+}
+
+/// Outcome of an import, as `scope/path` labels.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    pub written: Vec<String>,
+    pub kept: Vec<String>,
+    pub unchanged: Vec<String>,
+    /// Vault files skipped because no notes folder was given
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettingsBundle {
+    pub version: u32,
+    #[serde(default, rename = "file")]
+    pub files: Vec<BundleFile>,
+}
+
+impl SettingsBundle {
+    /// Collect the app config and vault settings into a bundle.
+    ///
+    /// Files that are not valid UTF-8 (caches and the like) are left out.
+    pub fn export(locations: &BundleLocations) -> Result<Self, BundleError> {
+        let mut files = Vec::new();
+
+        for name in APP_FILES {
+            let path = locations.config_dir.join(name);
+            if let Some(contents) = read_text(&path)? {
+                files.push(BundleFile {
+                    scope: BundleScope::App,
+                    path: name.to_string(),
+                    contents,
+                });
+            }
+        }
+
+        if let Some(notes_root) = &locations.notes_root {
+            let mut vault_files = Vec::new();
+            collect_vault_files(
+                &notes_root.join(VAULT_SETTINGS_DIR),
+                VAULT_SETTINGS_DIR,
+                &mut vault_files,
+            )?;
+            vault_files.sort_by(|a, b| a.path.cmp(&b.path));
+            files.extend(vault_files);
+        }
+
+        Ok(Self {
+            version: BUNDLE_VERSION,
+            files,
+        })
+    }
+
+    pub fn load_from_path<P: AsRef<Path>>(bundle_path: P) -> Result<Self, BundleError> {
+        let bundle_path = bundle_path.as_ref();
+        let content = std::fs::read_to_string(bundle_path).map_err(|source| BundleError::Io {
+            path: bundle_path.to_path_buf(),
+            source,
+        })?;
+        let bundle: Self = toml::from_str(&content)?;
+        if bundle.version != BUNDLE_VERSION {
+            return Err(BundleError::UnsupportedVersion(bundle.version));
+        }
+        Ok(bundle)
+    }
+
+    pub fn save_to_path<P: AsRef<Path>>(&self, bundle_path: P) -> Result<(), BundleError> {
+        let bundle_path = bundle_path.as_ref();
+        let content = toml::to_string_pretty(self)?;
+        std::fs::write(bundle_path, content).map_err(|source| BundleError::Io {
+            path: bundle_path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Write the bundled files into `locations`.
+    ///
+    /// Files identical to what is on disk are left alone; for files that
+    /// differ, `resolve` decides whether to overwrite or keep the existing
+    /// copy. All paths are checked before anything is written.
+    pub fn import(
+        &self,
+        locations: &BundleLocations,
+        mut resolve: impl FnMut(&ImportConflict) -> ConflictResolution,
+    ) -> Result<ImportReport, BundleError> {
+        for file in &self.files {
+            check_relative(&file.path)?;
+        }
+
+        let mut report = ImportReport::default();
+        for file in &self.files {
+            let label = file.label();
+            let Some(root) = locations.root(file.scope) else {
+                report.skipped.push(label);
+                continue;
+            };
+            let target = root.join(&file.path);
+            let existing = read_text(&target)?;
+
+            let incoming = if file.scope == BundleScope::App && file.path == "config.toml" {
+                localise_config(&file.contents, existing.as_deref(), locations)?
+            } else {
+                file.contents.clone()
+            };
+
+            if let Some(existing) = existing {
+                if existing == incoming {
+                    report.unchanged.push(label);
+                    continue;
+                }
+                let conflict = ImportConflict { file, existing };
+                if resolve(&conflict) == ConflictResolution::Keep {
+                    report.kept.push(label);
+                    continue;
+                }
+            }
+
+            write_text(&target, &incoming)?;
+            report.written.push(label);
+        }
+        Ok(report)
+    }
+}
+
+impl BundleFile {
+    /// Display label such as `vault/.neuraxis/dictionary.txt`.
+    pub fn label(&self) -> String {
+        match self.scope {
+            BundleScope::App => format!("app/{}", self.path),
+            BundleScope::Vault => format!("vault/{}", self.path),
+        }
+    }
+}
+
+/// Keep this machine's notes folder when importing another machine's config.
+fn localise_config(
+    incoming: &str,
+    existing: Option<&str>,
+    locations: &BundleLocations,
+) -> Result<String, BundleError> {
+    let local_notes_path = match existing {
+        Some(existing) => Some(Config::from_toml(existing)?.notes_path),
+        None => locations.notes_root.clone(),
+    };
+    let mut config = Config::from_toml(incoming)?;
+    if let Some(notes_path) = local_notes_path {
+        config.notes_path = notes_path;
+    }
+    Ok(config.to_toml()?)
+}
+
+fn collect_vault_files(
+    dir: &Path,
+    relative: &str,
+    out: &mut Vec<BundleFile>,
+) -> Result<(), BundleError> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    let entries = std::fs::read_dir(dir).map_err(|source| BundleError::Io {
+        path: dir.to_path_buf(),
+        source,
+    })?;
+    for entry in entries {
+        let entry = entry.map_err(|source| BundleError::Io {
+            path: dir.to_path_buf(),
+            source,
+        })?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path();
+        let child = format!("{relative}/{name}");
+        if path.is_dir() {
+            if relative == VAULT_SETTINGS_DIR && VAULT_EXCLUDED_DIRS.contains(&name.as_str()) {
+                continue;
+            }
+            collect_vault_files(&path, &child, out)?;
+        } else if let Ok(contents) = std::fs::read_to_string(&path) {
+            out.push(BundleFile {
+                scope: BundleScope::Vault,
+                path: child,
+                contents,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Reject absolute paths and `..` so a shared bundle cannot write outside
+/// the config directory or notes root.
+fn check_relative(path: &str) -> Result<(), BundleError> {
+    let safe = !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)));
+    if safe {
+        Ok(())
+    } else {
+        Err(BundleError::UnsafePath(path.to_string()))
+    }
+}
+
+fn read_text(path: &Path) -> Result<Option<String>, BundleError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    std::fs::read_to_string(path)
+        .map(Some)
+        .map_err(|source| BundleError::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+}
+
+fn write_text(path: &Path, contents: &str) -> Result<(), BundleError> {
+    let io_err = |source| BundleError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(io_err)?;
+    }
+    std::fs::write(path, contents).map_err(io_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    struct Machine {
+        _dir: TempDir,
+        locations: BundleLocations,
+    }
+
+    fn machine(notes_path: &str, dictionary: Option<&str>) -> Machine {
+        let dir = TempDir::new().unwrap();
+        let config_dir = dir.path().join("config");
+        let notes_root = dir.path().join("notes");
+        std::fs::create_dir_all(notes_root.join(".neuraxis/trash")).unwrap();
+        std::fs::create_dir_all(&config_dir).unwrap();
+        let mut config = Config::new(PathBuf::from(notes_path));
+        config.timezone = Some("Europe/London".to_string());
+        config.save_to_path(config_dir.join("config.toml")).unwrap();
+        if let Some(words) = dictionary {
+            std::fs::write(notes_root.join(".neuraxis/dictionary.txt"), words).unwrap();
+        }
+        std::fs::write(notes_root.join(".neuraxis/trash/old.md"), "old").unwrap();
+        Machine {
+            _dir: dir,
+            locations: BundleLocations {
+                config_dir,
+                notes_root: Some(notes_root),
+            },
+        }
+    }
+
+    #[test]
+    fn test_export_collects_config_and_vault_settings() {
+        let source = machine("/home/a/notes", Some("neuraxis\n"));
+        let bundle = SettingsBundle::export(&source.locations).unwrap();
+
+        let labels: Vec<String> = bundle.files.iter().map(BundleFile::label).collect();
+        assert_eq!(
+            labels,
+            vec!["app/config.toml", "vault/.neuraxis/dictionary.txt"]
+        );
+    }
+
+    #[test]
+    fn test_bundle_file_roundtrip() {
+        let source = machine("/home/a/notes", Some("neuraxis\n"));
+        let bundle = SettingsBundle::export(&source.locations).unwrap();
+        let bundle_path = source.locations.config_dir.join("bundle.toml");
+
+        bundle.save_to_path(&bundle_path).unwrap();
+        let loaded = SettingsBundle::load_from_path(&bundle_path).unwrap();
+
+        assert_eq!(loaded, bundle);
+    }
+
+    #[test]
+    fn test_import_keeps_local_notes_path_and_prompts_on_conflict() {
+        let source = machine("/home/a/notes", Some("neuraxis\n"));
+        let target = machine("/Users/b/notes", Some("rowan\n"));
+        let bundle = SettingsBundle::export(&source.locations).unwrap();
+
+        let mut prompted = Vec::new();
+        let report = bundle
+            .import(&target.locations, |conflict| {
+                prompted.push(conflict.file.label());
+                ConflictResolution::Keep
+            })
+            .unwrap();
+
+        // config.toml only differs in notes_path, which is kept local
+        assert_eq!(report.unchanged, vec!["app/config.toml"]);
+        assert_eq!(report.kept, vec!["vault/.neuraxis/dictionary.txt"]);
+        assert_eq!(prompted, vec!["vault/.neuraxis/dictionary.txt"]);
+
+        let report = bundle
+            .import(&target.locations, |_| ConflictResolution::Overwrite)
+            .unwrap();
+        assert_eq!(report.written, vec!["vault/.neuraxis/dictionary.txt"]);
+        let notes_root = target.locations.notes_root.as_ref().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(notes_root.join(".neuraxis/dictionary.txt")).unwrap(),
+            "neuraxis\n"
+        );
+        let config = Config::load_from_path(target.locations.config_dir.join("config.toml"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.notes_path, PathBuf::from("/Users/b/notes"));
+    }
+
+    #[test]
+    fn test_import_into_fresh_machine_without_notes_folder() {
+        let source = machine("/home/a/notes", Some("neuraxis\n"));
+        let bundle = SettingsBundle::export(&source.locations).unwrap();
+        let dir = TempDir::new().unwrap();
+        let locations = BundleLocations {
+            config_dir: dir.path().join("config"),
+            notes_root: None,
+        };
+
+        let report = bundle
+            .import(&locations, |_| panic!("nothing to conflict with"))
+            .unwrap();
+
+        assert_eq!(report.written, vec!["app/config.toml"]);
+        assert_eq!(report.skipped, vec!["vault/.neuraxis/dictionary.txt"]);
+        let config = Config::load_from_path(locations.config_dir.join("config.toml"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.timezone.as_deref(), Some("Europe/London"));
+    }
+
+    #[test]
+    fn test_import_rejects_unsafe_paths() {
+        let target = machine("/notes", None);
+        let bundle = SettingsBundle {
+            version: BUNDLE_VERSION,
+            files: vec![BundleFile {
+                scope: BundleScope::Vault,
+                path: "../outside.txt".to_string(),
+                contents: "x".to_string(),
+            }],
+        };
+
+        let result = bundle.import(&target.locations, |_| ConflictResolution::Overwrite);
+
+        assert!(matches!(result, Err(BundleError::UnsafePath(_))));
+    }
+}
@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
+pub mod bundle;
+
 /// Android package identifier - must match bundle.identifier in Dioxus.toml
 pub const ANDROID_PACKAGE_NAME: &str = "co.rustworkshop.markdown_neuraxis";
 
@@ -20,12 +22,211 @@ pub enum ConfigError {
     },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// First day of the week for journal weekly notes and agenda grouping.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WeekStart {
+    #[default]
+    Monday,
+    Sunday,
+}
+
+/// How ordered lists are numbered for display; the source keeps plain numbers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListNumbering {
+    /// As written: 1. 2. 3.
+    #[default]
+    Source,
+    /// By depth: 1. then a. then i.
+    Nested,
+    /// Outline: 1. then 1.1 then 1.1.1
+    Outline,
+}
+
+/// Color theme for the desktop app and terminal UI.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    #[default]
+    SolarizedLight,
+    SolarizedDark,
+}
+
+/// How strongly text stands out from the background.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Contrast {
+    #[default]
+    Normal,
+    High,
+}
+
+/// Which platform section of the config file applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Android,
+    Desktop,
+}
+
+impl Platform {
+    /// The platform this build runs on.
+    pub fn current() -> Self {
+        if cfg!(target_os = "android") {
+            Platform::Android
+        } else {
+            Platform::Desktop
+        }
+    }
+
+    /// Name of this platform's config section, e.g. `[android]`.
+    pub fn section(self) -> &'static str {
+        match self {
+            Platform::Android => "android",
+            Platform::Desktop => "desktop",
+        }
+    }
+
+    const ALL: [Platform; 2] = [Platform::Android, Platform::Desktop];
+}
+
+impl Default for Platform {
+    fn default() -> Self {
+        Self::current()
+    }
+}
+
+/// App settings.
+///
+/// The config file can hold `[android]` and `[desktop]` sections whose
+/// settings replace the top-level ones on that platform, so one synced file
+/// works on every device:
+///
+/// ```toml
+/// notes_path = "~/notes"
+///
+/// [android]
+/// notes_path = "/storage/emulated/0/notes"
+/// ```
+///
+/// Saving writes each setting back where it came from: into this
+/// platform's section if the section sets it, otherwise the top level. The
+/// other platform's section and any unknown settings are left as they are.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub notes_path: PathBuf,
+    /// IANA time zone name (e.g. `Europe/London`) deciding when the journal
+    /// rolls over to a new day. Unset follows the system time zone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub week_start: WeekStart,
+    #[serde(default)]
+    pub list_numbering: ListNumbering,
+    /// URL schemes whose bare URLs are clickable links (e.g. `["https",
+    /// "zotero"]`). Unset uses the app's defaults: web, mail, file and a
+    /// few research apps.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_protocols: Option<Vec<String>>,
+    /// How inserted dates are written: a strftime pattern (e.g. `"%e %B
+    /// %Y"`) or `"link"` for a `[[YYYY-MM-DD]]` link to the day's daily
+    /// note. Unset writes `YYYY-MM-DD`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+    #[serde(default)]
+    pub theme: ThemeName,
+    #[serde(default)]
+    pub contrast: Contrast,
+    /// Turn off animations and transitions
+    #[serde(default)]
+    pub reduced_motion: bool,
+    /// Follow symlinked folders and files in the notes folder
+    #[serde(default = "follow_symlinks_default")]
+    pub follow_symlinks: bool,
+    /// Open the notes folder read-only, e.g. a share or another machine's
+    /// checkout. Folders that can't be written to are read-only anyway.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Platform whose section was layered in
+    #[serde(skip)]
+    platform: Platform,
+    /// The file as read, before layering, so saving can keep its sections
+    #[serde(skip)]
+    file: toml::Table,
+}
+
+fn follow_symlinks_default() -> bool {
+    true
 }
 
 impl Config {
+    /// Config for a notes folder with default journal settings.
+    pub fn new(notes_path: PathBuf) -> Self {
+        Self {
+            notes_path,
+            timezone: None,
+            week_start: WeekStart::default(),
+            list_numbering: ListNumbering::default(),
+            link_protocols: None,
+            date_format: None,
+            theme: ThemeName::default(),
+            contrast: Contrast::default(),
+            reduced_motion: false,
+            follow_symlinks: true,
+            read_only: false,
+            platform: Platform::current(),
+            file: toml::Table::new(),
+        }
+    }
+
+    /// Parse config file contents for the current platform.
+    pub fn from_toml(content: &str) -> Result<Self, toml::de::Error> {
+        Self::from_toml_for_platform(content, Platform::current())
+    }
+
+    /// Parse config file contents, layering `platform`'s section over the
+    /// top-level settings.
+    pub fn from_toml_for_platform(
+        content: &str,
+        platform: Platform,
+    ) -> Result<Self, toml::de::Error> {
+        let file: toml::Table = toml::from_str(content)?;
+        let mut layered = file.clone();
+        let section = remove_sections(&mut layered).remove(platform.section());
+        if let Some(toml::Value::Table(section)) = section {
+            merge_tables(&mut layered, section);
+        }
+
+        let mut config: Config = toml::Value::Table(layered).try_into()?;
+        config.platform = platform;
+        config.file = file;
+        Ok(config)
+    }
+
+    /// Config file contents with these settings written back into the file
+    /// they were loaded from (see [`Config`] for where each setting goes).
+    pub fn to_toml(&self) -> Result<String, toml::ser::Error> {
+        let mut file = self.file.clone();
+        // Sections are tables, so they're added back after plain settings
+        let mut sections = remove_sections(&mut file);
+        let mut section = match sections.get_mut(self.platform.section()) {
+            Some(toml::Value::Table(section)) => Some(section),
+            _ => None,
+        };
+        for (key, value) in toml::Table::try_from(self)? {
+            match &mut section {
+                Some(section) if section.contains_key(&key) => {
+                    section.insert(key, value);
+                }
+                _ => {
+                    file.insert(key, value);
+                }
+            }
+        }
+        file.extend(sections);
+        toml::to_string_pretty(&file)
+    }
+
     pub fn load_from_path<P: AsRef<Path>>(config_path: P) -> Result<Option<Self>, ConfigError> {
         let config_path = config_path.as_ref();
         if !config_path.exists() {
@@ -39,8 +240,8 @@ impl Config {
             }
         })?;
 
-        let mut config: Config =
-            toml::from_str(&content).map_err(|source| ConfigError::ConfigParseError {
+        let mut config =
+            Config::from_toml(&content).map_err(|source| ConfigError::ConfigParseError {
                 config_path: config_path.to_path_buf(),
                 source,
             })?;
@@ -56,12 +257,24 @@ impl Config {
         Self::load_from_path(&config_path)
     }
 
+    /// Write the settings to `config_path`, merging them into the file
+    /// already there so its other platform sections survive.
     pub fn save_to_path<P: AsRef<Path>>(&self, config_path: P) -> anyhow::Result<()> {
         let config_path = config_path.as_ref();
         if let Some(parent) = config_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
-        let content = toml::to_string_pretty(self)?;
+        let on_disk = std::fs::read_to_string(config_path)
+            .ok()
+            .and_then(|content| toml::from_str::<toml::Table>(&content).ok());
+        let content = match on_disk {
+            Some(file) => Config {
+                file,
+                ..self.clone()
+            }
+            .to_toml()?,
+            None => self.to_toml()?,
+        };
         std::fs::write(config_path, content)?;
         Ok(())
     }
@@ -96,6 +309,33 @@ impl Config {
     }
 }
 
+/// Take the platform sections out of a config table.
+fn remove_sections(table: &mut toml::Table) -> toml::Table {
+    Platform::ALL
+        .iter()
+        .filter_map(|platform| {
+            let name = platform.section();
+            table
+                .remove(name)
+                .map(|section| (name.to_string(), section))
+        })
+        .collect()
+}
+
+/// Lay `overrides` over `base`, merging nested tables key by key.
+fn merge_tables(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(value)) => {
+                merge_tables(existing, value)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -115,9 +355,7 @@ mod tests {
 
     #[test]
     fn test_config_serialization_roundtrip() {
-        let original = Config {
-            notes_path: PathBuf::from("/tmp/test-notes"),
-        };
+        let original = Config::new(PathBuf::from("/tmp/test-notes"));
 
         let toml_str = toml::to_string(&original).unwrap();
         let deserialized: Config = toml::from_str(&toml_str).unwrap();
@@ -184,9 +422,7 @@ mod tests {
     fn test_save_and_load_config() {
         let temp_dir = TempDir::new().unwrap();
         let config_file = temp_dir.path().join("config.toml");
-        let test_config = Config {
-            notes_path: PathBuf::from("/tmp/test-notes"),
-        };
+        let test_config = Config::new(PathBuf::from("/tmp/test-notes"));
 
         // Test saving
         test_config.save_to_path(&config_file).unwrap();
@@ -235,9 +471,7 @@ notes_path = "$NOTES_ROOT/my-notes"
     fn test_save_convenience_method() {
         let temp_dir = TempDir::new().unwrap();
         let config_file = temp_dir.path().join("config.toml");
-        let test_config = Config {
-            notes_path: PathBuf::from("/tmp/test-notes"),
-        };
+        let test_config = Config::new(PathBuf::from("/tmp/test-notes"));
 
         // Test that save_to_path and save produce the same result
         // First save to a specific path
@@ -248,4 +482,197 @@ notes_path = "$NOTES_ROOT/my-notes"
         let loaded_config = Config::load_from_path(&config_file).unwrap().unwrap();
         assert_eq!(loaded_config.notes_path, test_config.notes_path);
     }
+
+    #[test]
+    fn test_journal_settings_default_when_missing() {
+        let config: Config = toml::from_str(r#"notes_path = "/notes""#).unwrap();
+
+        assert_eq!(config.timezone, None);
+        assert_eq!(config.week_start, WeekStart::Monday);
+        assert_eq!(config.list_numbering, ListNumbering::Source);
+    }
+
+    #[test]
+    fn test_list_numbering_from_toml() {
+        let config: Config = toml::from_str(
+            r#"
+notes_path = "/notes"
+list_numbering = "outline"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.list_numbering, ListNumbering::Outline);
+    }
+
+    #[test]
+    fn test_link_protocols_from_toml() {
+        let config: Config = toml::from_str(r#"notes_path = "/notes""#).unwrap();
+        assert_eq!(config.link_protocols, None);
+
+        let config: Config = toml::from_str(
+            r#"
+notes_path = "/notes"
+link_protocols = ["https", "zotero"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.link_protocols,
+            Some(vec!["https".to_string(), "zotero".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_date_format_from_toml() {
+        let config: Config = toml::from_str(r#"notes_path = "/notes""#).unwrap();
+        assert_eq!(config.date_format, None);
+
+        let config: Config = toml::from_str(
+            r#"
+notes_path = "/notes"
+date_format = "link"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.date_format.as_deref(), Some("link"));
+    }
+
+    #[test]
+    fn test_theme_settings_from_toml() {
+        let config: Config = toml::from_str(r#"notes_path = "/notes""#).unwrap();
+        assert_eq!(config.theme, ThemeName::SolarizedLight);
+        assert_eq!(config.contrast, Contrast::Normal);
+        assert!(!config.reduced_motion);
+
+        let config: Config = toml::from_str(
+            r#"
+notes_path = "/notes"
+theme = "solarized-dark"
+contrast = "high"
+reduced_motion = true
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.theme, ThemeName::SolarizedDark);
+        assert_eq!(config.contrast, Contrast::High);
+        assert!(config.reduced_motion);
+    }
+
+    #[test]
+    fn test_follow_symlinks_defaults_on() {
+        let config: Config = toml::from_str(r#"notes_path = "/notes""#).unwrap();
+        assert!(config.follow_symlinks);
+
+        let config: Config = toml::from_str(
+            r#"
+notes_path = "/notes"
+follow_symlinks = false
+"#,
+        )
+        .unwrap();
+        assert!(!config.follow_symlinks);
+    }
+
+    #[test]
+    fn test_read_only_defaults_off() {
+        let config: Config = toml::from_str(r#"notes_path = "/notes""#).unwrap();
+        assert!(!config.read_only);
+
+        let config: Config = toml::from_str(
+            r#"
+notes_path = "/notes"
+read_only = true
+"#,
+        )
+        .unwrap();
+        assert!(config.read_only);
+    }
+
+    #[test]
+    fn test_journal_settings_roundtrip() {
+        let config_content = r#"
+notes_path = "/notes"
+timezone = "America/New_York"
+week_start = "sunday"
+"#;
+
+        let config: Config = toml::from_str(config_content).unwrap();
+        assert_eq!(config.timezone.as_deref(), Some("America/New_York"));
+        assert_eq!(config.week_start, WeekStart::Sunday);
+
+        let reloaded: Config = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(reloaded.timezone, config.timezone);
+        assert_eq!(reloaded.week_start, config.week_start);
+    }
+    const LAYERED: &str = r#"
+notes_path = "/notes"
+week_start = "sunday"
+custom = "kept"
+
+[android]
+notes_path = "/storage/notes"
+
+[desktop]
+list_numbering = "outline"
+"#;
+
+    #[test]
+    fn test_platform_section_overrides_top_level() {
+        let android = Config::from_toml_for_platform(LAYERED, Platform::Android).unwrap();
+        assert_eq!(android.notes_path, PathBuf::from("/storage/notes"));
+        assert_eq!(android.week_start, WeekStart::Sunday);
+        assert_eq!(android.list_numbering, ListNumbering::Source);
+
+        let desktop = Config::from_toml_for_platform(LAYERED, Platform::Desktop).unwrap();
+        assert_eq!(desktop.notes_path, PathBuf::from("/notes"));
+        assert_eq!(desktop.list_numbering, ListNumbering::Outline);
+    }
+
+    #[test]
+    fn test_saving_writes_settings_back_to_their_section() {
+        let mut android = Config::from_toml_for_platform(LAYERED, Platform::Android).unwrap();
+        android.notes_path = PathBuf::from("/sdcard/notes");
+        android.week_start = WeekStart::Monday;
+        let saved = android.to_toml().unwrap();
+
+        let file: toml::Table = toml::from_str(&saved).unwrap();
+        assert_eq!(file["notes_path"].as_str(), Some("/notes"));
+        assert_eq!(file["week_start"].as_str(), Some("monday"));
+        assert_eq!(file["custom"].as_str(), Some("kept"));
+        assert_eq!(
+            file["android"]["notes_path"].as_str(),
+            Some("/sdcard/notes")
+        );
+        assert_eq!(file["desktop"]["list_numbering"].as_str(), Some("outline"));
+
+        let desktop = Config::from_toml_for_platform(&saved, Platform::Desktop).unwrap();
+        assert_eq!(desktop.notes_path, PathBuf::from("/notes"));
+        assert_eq!(desktop.list_numbering, ListNumbering::Outline);
+    }
+
+    #[test]
+    fn test_save_to_path_keeps_other_platform_sections() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_file = temp_dir.path().join("config.toml");
+        std::fs::write(&config_file, LAYERED).unwrap();
+
+        Config::new(PathBuf::from("/fresh"))
+            .save_to_path(&config_file)
+            .unwrap();
+
+        let saved = std::fs::read_to_string(&config_file).unwrap();
+        assert_eq!(
+            Config::from_toml(&saved).unwrap().notes_path,
+            PathBuf::from("/fresh")
+        );
+        let file: toml::Table = toml::from_str(&saved).unwrap();
+        let original: toml::Table = toml::from_str(LAYERED).unwrap();
+        let other = match Platform::current() {
+            Platform::Android => Platform::Desktop,
+            Platform::Desktop => Platform::Android,
+        };
+        assert_eq!(file[other.section()], original[other.section()]);
+        assert_eq!(file["custom"], original["custom"]);
+    }
 }
@@ -6,8 +6,9 @@ mod platform;
 mod ui;
 
 use markdown_neuraxis_config::Config;
-use ui::App;
+use markdown_neuraxis_engine::vault::Vault;
 use ui::components::{ErrorScreen, SetupScreen};
+use ui::{App, load_theme};
 
 const SOLARIZED_LIGHT_CSS: &str = include_str!("assets/solarized-light.css");
 
@@ -31,11 +32,16 @@ enum AppState {
 }
 
 fn main() {
-    env_logger::Builder::from_default_env()
-        .filter_level(log::LevelFilter::Info)
+    // RUST_LOG overrides the level, e.g. RUST_LOG=markdown_neuraxis_engine=debug
+    // to see engine timings
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
         .init();
 
-    log::info!("markdown-neuraxis starting up!");
+    tracing::info!("markdown-neuraxis starting up!");
 
     dioxus::LaunchBuilder::desktop()
         .with_cfg(make_window_config())
@@ -50,7 +56,7 @@ fn get_initial_state() -> AppState {
     if args_count == 2 {
         let args: Vec<String> = env::args().collect();
         let path = PathBuf::from(&args[1]);
-        log::info!("Using notes path from CLI argument: {}", path.display());
+        tracing::info!("Using notes path from CLI argument: {}", path.display());
         return AppState::Ready(path);
     }
 
@@ -62,22 +68,32 @@ fn get_initial_state() -> AppState {
         });
     }
 
-    // No CLI argument - check config file
-    log::info!("Reading config {}", Config::config_path().display());
+    // Started inside a vault
+    if let Some(vault) = env::current_dir()
+        .ok()
+        .and_then(|dir| Vault::discover(&dir))
+    {
+        let path = vault.notes_root().to_path_buf();
+        tracing::info!("Found vault at {}", path.display());
+        return AppState::Ready(path);
+    }
+
+    // Not in a vault - check config file
+    tracing::info!("Reading config {}", Config::config_path().display());
     match Config::load() {
         Ok(Some(config)) => {
-            log::info!(
+            tracing::info!(
                 "Loaded notes path from config: {}",
                 config.notes_path.display()
             );
             AppState::Ready(config.notes_path)
         }
         Ok(None) => {
-            log::info!("Config not found, showing setup...");
+            tracing::info!("Config not found, showing setup...");
             AppState::NeedsSetup
         }
         Err(e) => {
-            log::error!("Config::load() failed with error: {e}");
+            tracing::error!("Config::load() failed with error: {e}");
             AppState::Error(AppError {
                 title: "Configuration Error".to_string(),
                 message: "Failed to load configuration file".to_string(),
@@ -88,25 +104,26 @@ fn get_initial_state() -> AppState {
 }
 
 fn app_root() -> Element {
-    log::info!("app_root() called");
+    tracing::info!("app_root() called");
 
     let mut app_state = use_signal(get_initial_state);
 
     match app_state.read().clone() {
         AppState::NeedsSetup => {
-            log::info!("Showing setup screen");
+            tracing::info!("Showing setup screen");
             rsx! {
                 style { {SOLARIZED_LIGHT_CSS} }
+                style { {load_theme().css()} }
                 SetupScreen {
                     on_complete: move |path: PathBuf| {
-                        log::info!("Setup complete, transitioning to app with path: {}", path.display());
+                        tracing::info!("Setup complete, transitioning to app with path: {}", path.display());
                         app_state.set(AppState::Ready(path));
                     }
                 }
             }
         }
         AppState::Ready(path) => {
-            log::info!(
+            tracing::info!(
                 "app_root() creating App component with path: {}",
                 path.display()
             );
@@ -115,9 +132,10 @@ fn app_root() -> Element {
             }
         }
         AppState::Error(error) => {
-            log::error!("app_root() error: {} - {}", error.title, error.message);
+            tracing::error!("app_root() error: {} - {}", error.title, error.message);
             rsx! {
                 style { {SOLARIZED_LIGHT_CSS} }
+                style { {load_theme().css()} }
                 ErrorScreen {
                     title: error.title,
                     message: error.message,
@@ -1,11 +1,22 @@
+use super::indexing::{IndexStatus, VaultIndices, open_vault};
 use crate::platform::pick_folder;
 use dioxus::prelude::*;
-use markdown_neuraxis_config::Config;
+use markdown_neuraxis_config::{
+    Config, Contrast as ConfigContrast, ListNumbering as ConfigListNumbering,
+    ThemeName as ConfigThemeName,
+};
+use markdown_neuraxis_engine::notices::{self, Notice, Severity};
+use markdown_neuraxis_engine::theme::{Contrast, Theme, ThemeName};
+use markdown_neuraxis_engine::titles::TitleIndex;
+use markdown_neuraxis_engine::vault::{IndexUpdate, OpenMode, ParsedNote};
 use markdown_neuraxis_engine::{
-    Document, FileTree, MarkdownFile, Snapshot, editing::commands::Cmd, io,
+    Document, FileTree, ListNumbering, MarkdownFile, Snapshot,
+    editing::{LinkProtocols, commands::Cmd},
+    io,
 };
 use relative_path::RelativePathBuf;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::sync::Arc;
 
 const SOLARIZED_LIGHT_CSS: &str = include_str!("../assets/solarized-light.css");
@@ -25,7 +36,7 @@ impl RuntimeError {
         details: impl ToString,
     ) {
         let details = details.to_string();
-        log::error!("{}: {}", message, details);
+        tracing::error!("{}: {}", message, details);
         error_state.set(Some(RuntimeError {
             message,
             details: Some(details),
@@ -33,9 +44,18 @@ impl RuntimeError {
     }
 }
 
+/// Pages that replace the document view in the main content area
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SettingsPage {
+    Dictionary,
+    Diagnostics,
+    OrphanAssets,
+    Search,
+}
+
 #[component]
 pub fn App(notes_path: PathBuf) -> Element {
-    log::info!(
+    tracing::info!(
         "App component initialized with path: {}",
         notes_path.display()
     );
@@ -43,26 +63,44 @@ pub fn App(notes_path: PathBuf) -> Element {
     // Notes path as a signal so it can be changed at runtime
     let notes_path = use_signal(|| notes_path);
 
+    let list_numbering = use_hook(load_list_numbering);
+    let link_protocols = use_hook(load_link_protocols);
+    let theme_css = use_hook(|| load_theme().css());
+    let scan_options = use_hook(load_scan_options);
+    let open_mode = use_hook(|| load_open_mode(&notes_path.read()));
+
     // Error state for runtime errors
     let mut error_state = use_signal(|| None::<RuntimeError>);
 
+    // Engine notices (e.g. parts of a note that can't be shown), as toasts
+    let notice_receiver = use_hook(|| Rc::new(notices::subscribe()));
+    let mut toasts = use_signal(Vec::<Notice>::new);
+
+    // Vault-wide indices, filled in the background after the tree is shown
+    let index_status = use_signal_sync(|| IndexStatus::new(notes_path.peek().clone()));
+    let vault_indices = use_signal(|| VaultIndices::new(&notes_path.peek(), index_status));
+
     // Build file tree
     let mut file_tree = use_signal(|| {
         let path = notes_path.read();
-        log::info!("Building file tree for: {}", path.display());
-        match io::build_file_tree(&path) {
+        tracing::info!("Opening vault: {}", path.display());
+        match open_vault(&path, scan_options, &vault_indices.peek(), index_status) {
             Ok(tree) => {
-                log::info!("File tree built successfully");
+                tracing::info!("File tree built successfully");
                 tree
             }
             Err(e) => {
-                log::error!("Error building file tree: {e}");
+                tracing::error!("Error building file tree: {e}");
                 FileTree::new(path.clone())
             }
         }
     });
 
     let selected_file = use_signal(|| None::<MarkdownFile>);
+    use_context_provider(|| super::components::text_segment::NoteAssets {
+        notes_path,
+        selected_file,
+    });
     let current_document = use_signal(|| None::<Arc<Document>>);
     let current_snapshot = use_signal(|| None::<Snapshot>);
     let focused_folder = use_signal(|| None::<RelativePathBuf>);
@@ -72,50 +110,64 @@ pub fn App(notes_path: PathBuf) -> Element {
     // Mobile navigation state - tracks whether file tree is shown on mobile
     let mut mobile_nav_open = use_signal(|| false);
 
+    // Settings/maintenance page shown instead of the document, if any
+    let mut settings_page = use_signal(|| None::<SettingsPage>);
+
+    // Drain finished index work whenever the worker reports some, and show
+    // the note being opened once it has been parsed
+    use_effect(move || {
+        index_status.read();
+        let indices = vault_indices.read();
+        indices.poll();
+        if let Some(note) = indices.poll_parsed() {
+            show_parsed_note(
+                note,
+                selected_file,
+                current_document,
+                current_snapshot,
+                error_state,
+                is_new_file,
+            );
+        }
+    });
+
+    // Snapshots are where the engine finds problems, so check after each
+    use_effect(move || {
+        current_snapshot.read();
+        let new = notice_receiver.poll();
+        for notice in &new {
+            match notice.severity {
+                Severity::Info => tracing::info!("{notice}"),
+                Severity::Warning => tracing::warn!("{notice}"),
+                Severity::Error => tracing::error!("{notice}"),
+            }
+        }
+        if !new.is_empty() {
+            toasts.write().extend(new);
+        }
+    });
+
     // Create callbacks outside the rsx! block for cleaner code
     let on_sidebar_file_select = {
-        let mut selected_file = selected_file;
-        let mut current_document = current_document;
-        let mut current_snapshot = current_snapshot;
         let mut error_state = error_state;
         let mut mobile_nav_open = mobile_nav_open;
         let mut focused_folder = focused_folder;
-        let mut is_new_file = is_new_file;
+        let mut settings_page = settings_page;
         move |markdown_file: MarkdownFile| {
-            let path = notes_path.read();
-            load_existing_document(
-                &markdown_file,
-                &path,
-                &mut selected_file,
-                &mut current_document,
-                &mut current_snapshot,
-                &mut error_state,
-                &mut is_new_file,
-            );
+            load_document(&markdown_file, &vault_indices.read(), &mut error_state);
             // Clear any folder focus when a file is selected
             focused_folder.set(None);
+            settings_page.set(None);
             // Close mobile nav when file is selected
             mobile_nav_open.set(false);
         }
     };
 
     let on_file_navigate = {
-        let mut selected_file = selected_file;
-        let mut current_document = current_document;
-        let mut current_snapshot = current_snapshot;
         let mut error_state = error_state;
-        let mut is_new_file = is_new_file;
         move |file_path: PathBuf| {
             let path = notes_path.read();
-            navigate_to_path(
-                file_path,
-                &path,
-                &mut selected_file,
-                &mut current_document,
-                &mut current_snapshot,
-                &mut error_state,
-                &mut is_new_file,
-            );
+            navigate_to_path(file_path, &path, &vault_indices.read(), &mut error_state);
         }
     };
 
@@ -126,7 +178,6 @@ pub fn App(notes_path: PathBuf) -> Element {
         let mut error_state = error_state;
         let mut file_tree = file_tree;
         let mut focused_folder = focused_folder;
-        let mut is_new_file = is_new_file;
         move |target: String| {
             let path = notes_path.read();
             // First check if target matches a folder
@@ -135,6 +186,7 @@ pub fn App(notes_path: PathBuf) -> Element {
                 // Expand the folder and all its ancestors
                 file_tree.write().expand_to_folder(&folder_path);
                 // Clear the current file selection and focus the folder
+                vault_indices.read().cancel_parse();
                 selected_file.set(None);
                 current_document.set(None);
                 current_snapshot.set(None);
@@ -144,7 +196,12 @@ pub fn App(notes_path: PathBuf) -> Element {
 
             // Not a folder, resolve as file - clear any folder focus
             focused_folder.set(None);
-            let markdown_file = resolve_wikilink(&target, &path);
+            let titles = vault_indices.read().titles.clone();
+            let markdown_file = resolve_wikilink(
+                &target,
+                &path,
+                &titles.read().unwrap_or_else(|e| e.into_inner()),
+            );
             // Expand parent folders so the file is visible in the tree
             if let Some(parent) = markdown_file.relative_path().parent()
                 && !parent.as_str().is_empty()
@@ -153,36 +210,31 @@ pub fn App(notes_path: PathBuf) -> Element {
                     .write()
                     .expand_to_folder(&parent.to_relative_path_buf());
             }
-            load_document(
-                markdown_file,
-                &path,
-                &mut selected_file,
-                &mut current_document,
-                &mut current_snapshot,
-                &mut error_state,
-                &mut is_new_file,
-            );
+            load_document(&markdown_file, &vault_indices.read(), &mut error_state);
         }
     };
 
     let on_command = create_command_callback(
+        open_mode,
         notes_path,
         selected_file,
         current_document,
         current_snapshot,
         file_tree,
+        vault_indices,
         error_state,
         is_new_file,
     );
 
     rsx! {
         style { {SOLARIZED_LIGHT_CSS} }
+        style { {theme_css} }
         div {
             class: "app-container",
             // Error banner for runtime errors
             if let Some(error) = error_state.read().as_ref() {
                 div {
-                    style: "background: #dc322f; color: white; padding: 8px 16px; display: flex; justify-content: space-between; align-items: center;",
+                    style: "background: var(--red); color: var(--base3); padding: 8px 16px; display: flex; justify-content: space-between; align-items: center;",
                     span {
                         "{error.message}"
                         if let Some(ref details) = error.details {
@@ -195,6 +247,24 @@ pub fn App(notes_path: PathBuf) -> Element {
                     }
                 }
             }
+            if !toasts.read().is_empty() {
+                div {
+                    style: "position: fixed; bottom: 16px; right: 16px; z-index: 100; display: flex; flex-direction: column; gap: 8px; max-width: 360px;",
+                    for (i, notice) in toasts.read().iter().enumerate() {
+                        div {
+                            key: "{i}",
+                            style: "background: var(--base2); color: var(--base01); border-left: 4px solid var(--yellow); padding: 8px 12px; display: flex; gap: 8px; align-items: center;",
+                            span { "{notice}" }
+                            button {
+                                onclick: move |_| {
+                                    toasts.write().remove(i);
+                                },
+                                "×"
+                            }
+                        }
+                    }
+                }
+            }
             div {
                 class: if *mobile_nav_open.read() { "sidebar mobile-visible" } else { "sidebar" },
                 div {
@@ -206,28 +276,16 @@ pub fn App(notes_path: PathBuf) -> Element {
                             class: "new-file-btn",
                             title: "New file in root",
                             onclick: {
-                                let mut selected_file = selected_file;
-                                let mut current_document = current_document;
-                                let mut current_snapshot = current_snapshot;
                                 let mut error_state = error_state;
                                 let mut focused_folder = focused_folder;
                                 let mut mobile_nav_open = mobile_nav_open;
-                                let mut is_new_file = is_new_file;
                                 move |_| {
                                     let path = notes_path.read();
                                     let root_path = RelativePathBuf::new();
                                     let filename = generate_unique_filename(&root_path, &path);
                                     let file_path = RelativePathBuf::from(&filename);
                                     let markdown_file = MarkdownFile::new(file_path);
-                                    load_document(
-                                        markdown_file,
-                                        &path,
-                                        &mut selected_file,
-                                        &mut current_document,
-                                        &mut current_snapshot,
-                                        &mut error_state,
-                                        &mut is_new_file,
-                                    );
+                                    load_document(&markdown_file, &vault_indices.read(), &mut error_state);
                                     focused_folder.set(None);
                                     mobile_nav_open.set(false);
                                 }
@@ -240,6 +298,8 @@ pub fn App(notes_path: PathBuf) -> Element {
                             onclick: move |_| {
                                 let mut notes_path = notes_path;
                                 let mut file_tree = file_tree;
+                                let mut vault_indices = vault_indices;
+                                let mut index_status = index_status;
                                 let mut selected_file = selected_file;
                                 let mut current_document = current_document;
                                 let mut current_snapshot = current_snapshot;
@@ -249,27 +309,37 @@ pub fn App(notes_path: PathBuf) -> Element {
                                 let current_path = notes_path.read().clone();
                                 spawn(async move {
                                     if let Some(new_path) = pick_folder(Some(&current_path)).await {
-                                        // Save the new path to config
-                                        let config = Config { notes_path: new_path.clone() };
+                                        // Save the new path to config, keeping other settings
+                                        let config = match Config::load() {
+                                            Ok(Some(mut config)) => {
+                                                config.notes_path = new_path.clone();
+                                                config
+                                            }
+                                            _ => Config::new(new_path.clone()),
+                                        };
                                         match config.save() {
                                             Ok(()) => {
-                                                log::info!("Config saved with new notes path: {}", new_path.display());
+                                                tracing::info!("Config saved with new notes path: {}", new_path.display());
 
                                                 // Update notes_path signal
                                                 notes_path.set(new_path.clone());
 
-                                                // Rebuild file tree
-                                                match io::build_file_tree(&new_path) {
+                                                // Reopen the vault with fresh indices
+                                                let indices = VaultIndices::new(&new_path, index_status);
+                                                index_status.set(IndexStatus::new(new_path.clone()));
+                                                match open_vault(&new_path, scan_options, &indices, index_status) {
                                                     Ok(tree) => {
-                                                        log::info!("File tree rebuilt successfully");
+                                                        tracing::info!("File tree rebuilt successfully");
                                                         file_tree.set(tree);
                                                     }
                                                     Err(e) => {
-                                                        log::error!("Error building file tree: {e}");
+                                                        tracing::error!("Error building file tree: {e}");
                                                         file_tree.set(FileTree::new(new_path));
                                                     }
                                                 }
 
+                                                vault_indices.set(indices);
+
                                                 // Clear current file state
                                                 selected_file.set(None);
                                                 current_document.set(None);
@@ -291,6 +361,42 @@ pub fn App(notes_path: PathBuf) -> Element {
                             },
                             "📂"
                         }
+                        button {
+                            class: "search-btn",
+                            title: "Find in all notes",
+                            onclick: move |_| {
+                                settings_page.set(Some(SettingsPage::Search));
+                                mobile_nav_open.set(false);
+                            },
+                            "🔍"
+                        }
+                        button {
+                            class: "dictionary-btn",
+                            title: "Spelling dictionary",
+                            onclick: move |_| {
+                                settings_page.set(Some(SettingsPage::Dictionary));
+                                mobile_nav_open.set(false);
+                            },
+                            "📖"
+                        }
+                        button {
+                            class: "maintenance-btn",
+                            title: "Orphaned assets",
+                            onclick: move |_| {
+                                settings_page.set(Some(SettingsPage::OrphanAssets));
+                                mobile_nav_open.set(false);
+                            },
+                            "🧹"
+                        }
+                        button {
+                            class: "maintenance-btn",
+                            title: "Vault diagnostics",
+                            onclick: move |_| {
+                                settings_page.set(Some(SettingsPage::Diagnostics));
+                                mobile_nav_open.set(false);
+                            },
+                            "🩺"
+                        }
                     }
                 }
                 super::components::TreeView {
@@ -302,27 +408,15 @@ pub fn App(notes_path: PathBuf) -> Element {
                         file_tree.write().toggle_folder(&relative_path);
                     },
                     on_new_file: {
-                        let mut selected_file = selected_file;
-                        let mut current_document = current_document;
-                        let mut current_snapshot = current_snapshot;
                         let mut error_state = error_state;
                         let mut focused_folder = focused_folder;
                         let mut mobile_nav_open = mobile_nav_open;
-                        let mut is_new_file = is_new_file;
                         move |folder_path: RelativePathBuf| {
                             let path = notes_path.read();
                             let filename = generate_unique_filename(&folder_path, &path);
                             let file_path = folder_path.join(&filename);
                             let markdown_file = MarkdownFile::new(file_path);
-                            load_document(
-                                markdown_file,
-                                &path,
-                                &mut selected_file,
-                                &mut current_document,
-                                &mut current_snapshot,
-                                &mut error_state,
-                                &mut is_new_file,
-                            );
+                            load_document(&markdown_file, &vault_indices.read(), &mut error_state);
                             focused_folder.set(None);
                             mobile_nav_open.set(false);
                         }
@@ -331,7 +425,30 @@ pub fn App(notes_path: PathBuf) -> Element {
             }
             div {
                 class: if *mobile_nav_open.read() { "main-content mobile-hidden" } else { "main-content" },
-                if let (Some(file), Some(snapshot), Some(document)) = (
+                if *settings_page.read() == Some(SettingsPage::Dictionary) {
+                    super::components::DictionarySettings {
+                        notes_path: notes_path.read().clone(),
+                        on_close: move |_| settings_page.set(None),
+                    }
+                } else if *settings_page.read() == Some(SettingsPage::OrphanAssets) {
+                    super::components::OrphanAssets {
+                        notes_path: notes_path.read().clone(),
+                        on_close: move |_| settings_page.set(None),
+                    }
+                } else if *settings_page.read() == Some(SettingsPage::Diagnostics) {
+                    super::components::VaultDiagnostics {
+                        notes_path: notes_path.read().clone(),
+                        on_file_select: on_sidebar_file_select,
+                        on_close: move |_| settings_page.set(None),
+                    }
+                } else if *settings_page.read() == Some(SettingsPage::Search) {
+                    super::components::NoteSearch {
+                        indices: vault_indices,
+                        index_status,
+                        on_file_select: on_sidebar_file_select,
+                        on_close: move |_| settings_page.set(None),
+                    }
+                } else if let (Some(file), Some(snapshot), Some(document)) = (
                     selected_file.read().as_ref(),
                     current_snapshot.read().as_ref(),
                     current_document.read().as_ref()
@@ -342,6 +459,9 @@ pub fn App(notes_path: PathBuf) -> Element {
                         snapshot: snapshot.clone(),
                         notes_path: notes_path.read().clone(),
                         document: document.clone(),
+                        list_numbering,
+                        link_protocols: link_protocols.clone(),
+                        read_only: open_mode.is_read_only(),
                         on_file_select: Some(Callback::new(on_file_navigate)),
                         on_command,
                         on_wikilink_click: on_wikilink_navigate,
@@ -358,6 +478,16 @@ pub fn App(notes_path: PathBuf) -> Element {
                                         Ok(new_file) => {
                                             // Only update tree if file existed on disk
                                             if !was_new_file {
+                                                let indices = vault_indices.read();
+                                                indices.submit(IndexUpdate::NoteRemoved(
+                                                    current_file.relative_path().to_relative_path_buf(),
+                                                ));
+                                                if let Some(document) = current_document.read().as_ref() {
+                                                    indices.submit(IndexUpdate::NoteChanged {
+                                                        path: new_file.relative_path().to_relative_path_buf(),
+                                                        source: document.text(),
+                                                    });
+                                                }
                                                 file_tree.write().remove_file(&current_file.relative_path().to_path(&notes), &notes);
                                                 file_tree.write().add_file(&new_file.relative_path().to_path(&notes), &notes);
                                                 // Expand parent folders so new file is visible
@@ -407,137 +537,73 @@ pub fn App(notes_path: PathBuf) -> Element {
     }
 }
 
-/// Helper function to load and parse a document from an existing file
-fn load_existing_document(
+/// Start opening a note. The index worker reads and parses it off the UI
+/// thread, and [`show_parsed_note`] displays it; a note that isn't on disk
+/// yet opens blank.
+pub fn load_document(
     markdown_file: &MarkdownFile,
-    notes_path: &Path,
-    selected_file: &mut Signal<Option<MarkdownFile>>,
-    current_document: &mut Signal<Option<Arc<Document>>>,
-    current_snapshot: &mut Signal<Option<Snapshot>>,
+    vault_indices: &VaultIndices,
     error_state: &mut Signal<Option<RuntimeError>>,
-    is_new_file: &mut Signal<bool>,
 ) {
     // Clear any previous error
     error_state.set(None);
-    is_new_file.set(false);
-
-    match io::read_file(markdown_file.relative_path(), notes_path) {
-        Ok(content) => match Document::from_bytes(content.as_bytes()) {
-            Ok(document) => {
-                // Create snapshot for rendering
-                let snapshot = document.snapshot();
+    vault_indices.parse(markdown_file.relative_path().to_relative_path_buf());
+}
 
-                *current_document.write() = Some(Arc::new(document));
-                *current_snapshot.write() = Some(snapshot);
-                *selected_file.write() = Some(markdown_file.clone());
-            }
-            Err(e) => {
-                RuntimeError::log_and_set(
-                    error_state,
-                    format!("Failed to parse '{}'", markdown_file.relative_path()),
-                    e,
-                );
-            }
-        },
+/// Display a note the index worker has finished parsing
+fn show_parsed_note(
+    note: ParsedNote,
+    mut selected_file: Signal<Option<MarkdownFile>>,
+    mut current_document: Signal<Option<Arc<Document>>>,
+    mut current_snapshot: Signal<Option<Snapshot>>,
+    mut error_state: Signal<Option<RuntimeError>>,
+    mut is_new_file: Signal<bool>,
+) {
+    match note.result {
+        Ok((document, snapshot)) => {
+            is_new_file.set(!note.existed);
+            *current_document.write() = Some(Arc::new(document));
+            *current_snapshot.write() = Some(snapshot);
+            *selected_file.write() = Some(MarkdownFile::new(note.path));
+        }
         Err(e) => {
             RuntimeError::log_and_set(
-                error_state,
-                format!("Failed to read '{}'", markdown_file.relative_path()),
+                &mut error_state,
+                format!("Failed to open '{}'", note.path),
                 e,
             );
         }
     }
 }
 
-/// Load a document or create a blank one if it doesn't exist
-pub fn load_document(
-    markdown_file: MarkdownFile,
-    notes_path: &Path,
-    selected_file: &mut Signal<Option<MarkdownFile>>,
-    current_document: &mut Signal<Option<Arc<Document>>>,
-    current_snapshot: &mut Signal<Option<Snapshot>>,
-    error_state: &mut Signal<Option<RuntimeError>>,
-    is_new_file: &mut Signal<bool>,
-) {
-    // Clear any previous error
-    error_state.set(None);
-
-    match io::read_file(markdown_file.relative_path(), notes_path) {
-        Ok(content) => {
-            is_new_file.set(false);
-            match Document::from_bytes(content.as_bytes()) {
-                Ok(document) => {
-                    let snapshot = document.snapshot();
-                    *current_document.write() = Some(Arc::new(document));
-                    *current_snapshot.write() = Some(snapshot);
-                    *selected_file.write() = Some(markdown_file);
-                }
-                Err(e) => {
-                    RuntimeError::log_and_set(
-                        error_state,
-                        format!("Failed to parse '{}'", markdown_file.relative_path()),
-                        e,
-                    );
-                }
-            }
-        }
-        Err(_) => {
-            // File doesn't exist - create a blank document
-            is_new_file.set(true);
-            match Document::from_bytes(b"") {
-                Ok(document) => {
-                    let snapshot = document.snapshot();
-                    *current_document.write() = Some(Arc::new(document));
-                    *current_snapshot.write() = Some(snapshot);
-                    *selected_file.write() = Some(markdown_file);
-                }
-                Err(e) => {
-                    RuntimeError::log_and_set(
-                        error_state,
-                        "Failed to create new document".to_string(),
-                        e,
-                    );
-                }
-            }
-        }
-    }
-}
-
 /// Navigate to a file from an absolute path
 fn navigate_to_path(
     file_path: PathBuf,
     notes_path: &Path,
-    selected_file: &mut Signal<Option<MarkdownFile>>,
-    current_document: &mut Signal<Option<Arc<Document>>>,
-    current_snapshot: &mut Signal<Option<Snapshot>>,
+    vault_indices: &VaultIndices,
     error_state: &mut Signal<Option<RuntimeError>>,
-    is_new_file: &mut Signal<bool>,
 ) {
-    // Convert absolute path to relative
-    let relative_path = if let Ok(rel) = file_path.strip_prefix(notes_path) {
-        rel.to_path_buf()
-    } else {
+    // Convert absolute path to relative, whichever separators it uses
+    let relative_path_buf = io::relative_to_root(&file_path, notes_path)
         // Fallback for paths outside notes root
-        file_path.clone()
-    };
-    let relative_path_buf =
-        RelativePathBuf::from_path(&relative_path).expect("Failed to create relative path");
+        .unwrap_or_else(|| io::relative_path_from_str(&file_path.to_string_lossy()));
     let markdown_file = MarkdownFile::new(relative_path_buf);
 
-    load_document(
-        markdown_file,
-        notes_path,
-        selected_file,
-        current_document,
-        current_snapshot,
-        error_state,
-        is_new_file,
-    );
+    load_document(&markdown_file, vault_indices, error_state);
 }
 
-/// Resolve a wikilink target to a markdown file
-pub fn resolve_wikilink(target: &str, _notes_path: &Path) -> MarkdownFile {
-    MarkdownFile::from_display_path(target)
+/// Resolve a wikilink target to a markdown file. A target with no note at
+/// its path is looked up by file name and alias in `titles`, the vault's
+/// title index (empty until indexing finishes).
+pub fn resolve_wikilink(target: &str, notes_path: &Path, titles: &TitleIndex) -> MarkdownFile {
+    let file = MarkdownFile::from_display_path(target);
+    if file.relative_path().to_path(notes_path).exists() {
+        return file;
+    }
+    titles
+        .resolve(target)
+        .map(|path| MarkdownFile::new(path.to_relative_path_buf()))
+        .unwrap_or(file)
 }
 
 /// Rename/move a file to a new path (display path without .md, extension added automatically)
@@ -580,17 +646,84 @@ fn generate_unique_filename(folder_path: &RelativePathBuf, notes_path: &Path) ->
     format!("{}-999{}", base_name, extension)
 }
 
-/// Create a command callback for document editing
+/// Ordered list display style from config, plain numbers if unset.
+fn load_list_numbering() -> ListNumbering {
+    match Config::load() {
+        Ok(Some(config)) => match config.list_numbering {
+            ConfigListNumbering::Source => ListNumbering::Source,
+            ConfigListNumbering::Nested => ListNumbering::Nested,
+            ConfigListNumbering::Outline => ListNumbering::Outline,
+        },
+        _ => ListNumbering::Source,
+    }
+}
+
+/// Schemes bare URLs are linked for, from config or the defaults.
+fn load_link_protocols() -> LinkProtocols {
+    match Config::load() {
+        Ok(Some(Config {
+            link_protocols: Some(schemes),
+            ..
+        })) => LinkProtocols::new(schemes),
+        _ => LinkProtocols::default(),
+    }
+}
+
+/// Notes folder scan options from config; symlinks are followed if unset.
+fn load_scan_options() -> io::ScanOptions {
+    match Config::load() {
+        Ok(Some(config)) => io::ScanOptions {
+            follow_symlinks: config.follow_symlinks,
+        },
+        _ => io::ScanOptions::default(),
+    }
+}
+
+/// Read-only if the config says so or the notes folder can't be written to.
+fn load_open_mode(notes_path: &Path) -> OpenMode {
+    match Config::load() {
+        Ok(Some(config)) if config.read_only => OpenMode::ReadOnly,
+        _ => OpenMode::detect(notes_path),
+    }
+}
+
+/// Theme from the config's theme, contrast and reduced motion settings,
+/// falling back to the engine's default theme.
+pub fn load_theme() -> Theme {
+    match Config::load() {
+        Ok(Some(config)) => {
+            let name = match config.theme {
+                ConfigThemeName::SolarizedLight => ThemeName::SolarizedLight,
+                ConfigThemeName::SolarizedDark => ThemeName::SolarizedDark,
+            };
+            let contrast = match config.contrast {
+                ConfigContrast::Normal => Contrast::Normal,
+                ConfigContrast::High => Contrast::High,
+            };
+            Theme::new(name, contrast, config.reduced_motion)
+        }
+        _ => Theme::default(),
+    }
+}
+
+/// Create a command callback for document editing. Commands are ignored
+/// in a read-only vault.
+#[allow(clippy::too_many_arguments)]
 fn create_command_callback(
+    open_mode: OpenMode,
     notes_path: Signal<PathBuf>,
     selected_file: Signal<Option<MarkdownFile>>,
     mut current_document: Signal<Option<Arc<Document>>>,
     mut current_snapshot: Signal<Option<Snapshot>>,
     mut file_tree: Signal<FileTree>,
+    vault_indices: Signal<VaultIndices>,
     mut error_state: Signal<Option<RuntimeError>>,
     mut is_new_file: Signal<bool>,
 ) -> impl FnMut(Cmd) + 'static {
     move |cmd: Cmd| {
+        if open_mode.is_read_only() {
+            return;
+        }
         let path = notes_path.read();
         let document_arc = current_document.read().clone();
         if let Some(mut document_arc) = document_arc {
@@ -620,11 +753,15 @@ fn create_command_callback(
                             let absolute_path = file.relative_path().to_path(&*path);
                             file_tree.write().add_file(&absolute_path, &path);
                             is_new_file.set(false);
-                            log::info!(
+                            tracing::info!(
                                 "New file created and auto-saved: {:?}",
                                 file.relative_path()
                             );
                         }
+                        vault_indices.read().submit(IndexUpdate::NoteChanged {
+                            path: file.relative_path().to_relative_path_buf(),
+                            source: content,
+                        });
                     }
                     Err(e) => {
                         RuntimeError::log_and_set(
@@ -645,12 +782,13 @@ fn create_command_callback(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use relative_path::RelativePath;
     use std::path::Path;
 
     #[test]
     fn test_resolve_wikilink_adds_md_extension() {
         let notes_path = Path::new("/test");
-        let result = resolve_wikilink("my-note", notes_path);
+        let result = resolve_wikilink("my-note", notes_path, &TitleIndex::default());
 
         assert_eq!(result.relative_path().as_str(), "my-note.md");
     }
@@ -658,7 +796,7 @@ mod tests {
     #[test]
     fn test_resolve_wikilink_preserves_md_extension() {
         let notes_path = Path::new("/test");
-        let result = resolve_wikilink("my-note.md", notes_path);
+        let result = resolve_wikilink("my-note.md", notes_path, &TitleIndex::default());
 
         assert_eq!(result.relative_path().as_str(), "my-note.md");
     }
@@ -666,7 +804,7 @@ mod tests {
     #[test]
     fn test_resolve_wikilink_with_path_separators() {
         let notes_path = Path::new("/test");
-        let result = resolve_wikilink("folder/my-note", notes_path);
+        let result = resolve_wikilink("folder/my-note", notes_path, &TitleIndex::default());
 
         assert_eq!(result.relative_path().as_str(), "folder/my-note.md");
     }
@@ -674,8 +812,21 @@ mod tests {
     #[test]
     fn test_resolve_wikilink_with_path_separators_and_extension() {
         let notes_path = Path::new("/test");
-        let result = resolve_wikilink("folder/my-note.md", notes_path);
+        let result = resolve_wikilink("folder/my-note.md", notes_path, &TitleIndex::default());
 
         assert_eq!(result.relative_path().as_str(), "folder/my-note.md");
     }
+
+    #[test]
+    fn test_resolve_wikilink_by_alias_from_index() {
+        let notes_path = Path::new("/test");
+        let mut titles = TitleIndex::default();
+        titles.update_note(
+            RelativePath::new("people/ada.md"),
+            "---\naliases: [Ada Lovelace]\n---\n",
+        );
+        let result = resolve_wikilink("Ada Lovelace", notes_path, &titles);
+
+        assert_eq!(result.relative_path().as_str(), "people/ada.md");
+    }
 }
@@ -1,10 +1,11 @@
 use crate::ui::components::{
-    block_quote::BlockQuote, code_fence::CodeFence, editor_block::EditorBlock, heading::Heading,
-    paragraph::Paragraph, text_segment::InlineSegments, thematic_break::ThematicBreak,
+    block_quote::BlockQuote, code_fence::CodeFence, editor_block::EditorBlock, embed::Embed,
+    heading::Heading, paragraph::Paragraph, text_segment::InlineSegments,
+    thematic_break::ThematicBreak,
 };
 use dioxus::prelude::*;
 use markdown_neuraxis_engine::editing::{
-    AnchorId, Block, BlockContent, BlockKind, CheckboxState, Cmd,
+    AnchorId, Block, BlockContent, BlockKind, CheckboxState, Cmd, TableAlignment, TaskState,
 };
 use std::collections::HashSet;
 
@@ -34,6 +35,24 @@ fn render_checkbox(checkbox: &Option<CheckboxState>, on_command: Callback<Cmd>)
     }
 }
 
+/// The cells of a table row block
+fn table_row_cells(row: &Block) -> &[Block] {
+    match &row.content {
+        BlockContent::Children(cells) => cells,
+        BlockContent::Leaf => &[],
+    }
+}
+
+/// Inline style aligning a table cell as its column's delimiter asks
+fn cell_alignment_style(alignment: Option<&TableAlignment>) -> &'static str {
+    match alignment {
+        Some(TableAlignment::Left) => "text-align: left",
+        Some(TableAlignment::Center) => "text-align: center",
+        Some(TableAlignment::Right) => "text-align: right",
+        Some(TableAlignment::None) | None => "",
+    }
+}
+
 /// Collapse toggle component for blocks with children
 #[component]
 pub fn CollapseToggle(
@@ -149,10 +168,16 @@ pub fn BlockRenderer(
                 rsx! {}
             }
         }
-        BlockKind::ListItem { checkbox, .. } => {
+        BlockKind::ListItem {
+            checkbox,
+            ordinal,
+            task_state,
+            ..
+        } => {
             let has_children = matches!(&block.content, BlockContent::Children(c) if !c.is_empty());
             let block_id = block.id;
             let checkbox = checkbox.clone();
+            let ordinal = ordinal.clone();
 
             if is_focused {
                 // Use content_range() - excludes nested children
@@ -199,17 +224,30 @@ pub fn BlockRenderer(
                 let segments = block.segments.clone();
                 let list_class = if checkbox.is_some() {
                     "list-item has-checkbox"
+                } else if ordinal.is_some() {
+                    "list-item has-ordinal"
                 } else {
                     "list-item"
                 };
+                let task_class = match task_state {
+                    Some(TaskState::Todo) => " task-todo",
+                    Some(TaskState::Doing) => " task-doing",
+                    Some(TaskState::Done) => " task-done",
+                    Some(TaskState::Later) => " task-later",
+                    None => "",
+                };
                 rsx! {
                     li {
-                        class: "{list_class}",
+                        class: "{list_class}{task_class}",
                         if has_children {
                             CollapseToggle { block_id, is_collapsed, collapsed_ids, on_context_menu }
                         }
                         // Render checkbox if present
                         {render_checkbox(&checkbox, on_command)}
+                        // Styled ordinal label (a., iv., 1.2) replaces the browser's number
+                        if let Some(label) = ordinal {
+                            span { class: "list-ordinal", "{label}" }
+                        }
                         span {
                             class: "list-item-content clickable-block",
                             onclick: {
@@ -257,25 +295,49 @@ pub fn BlockRenderer(
                 on_wikilink_click
             }
         },
-        BlockKind::Paragraph => rsx! {
+        BlockKind::Paragraph { chunks } => rsx! {
             Paragraph {
                 block: block.clone(),
+                chunks: chunks.clone(),
                 source: source.clone(),
                 focused_anchor_id,
                 on_command,
                 on_wikilink_click
             }
         },
-        BlockKind::FencedCode { language } => rsx! {
-            CodeFence {
+        BlockKind::Embed {
+            target,
+            heading,
+            source: embed_source,
+        } => rsx! {
+            Embed {
                 block: block.clone(),
                 source: source.clone(),
-                lang: language.clone(),
+                target: target.clone(),
+                heading: heading.clone(),
+                embed_source: embed_source.clone(),
                 focused_anchor_id,
                 on_command,
                 on_wikilink_click
             }
         },
+        BlockKind::FencedCode {
+            language,
+            attributes,
+            ..
+        } => rsx! {
+            CodeFence {
+                block: block.clone(),
+                source: source.clone(),
+                lang: language.clone(),
+                title: attributes
+                    .iter()
+                    .find(|attribute| attribute.key == "title")
+                    .and_then(|attribute| attribute.value.clone()),
+                focused_anchor_id,
+                on_command
+            }
+        },
         BlockKind::BlockQuote => rsx! {
             BlockQuote {
                 block: block.clone(),
@@ -295,7 +357,46 @@ pub fn BlockRenderer(
                 on_command
             }
         },
-        BlockKind::Table => {
+        BlockKind::FrontMatter { .. } => {
+            let block_id = block.id;
+            let content_text = source
+                .get(block.node_range.clone())
+                .unwrap_or("")
+                .to_string();
+            if is_focused {
+                // Edit the front matter as raw text, fences included
+                let block_clone = block.clone();
+                rsx! {
+                    div {
+                        class: "frontmatter-container clickable-block",
+                        EditorBlock {
+                            block: block_clone,
+                            content_text,
+                            on_command,
+                            on_cancel: {
+                                let mut focused_anchor_id = focused_anchor_id;
+                                move |_| focused_anchor_id.set(None)
+                            }
+                        }
+                    }
+                }
+            } else {
+                rsx! {
+                    pre {
+                        class: "frontmatter clickable-block",
+                        onclick: {
+                            let mut focused_anchor_id = focused_anchor_id;
+                            move |evt| {
+                                evt.stop_propagation();
+                                focused_anchor_id.set(Some(block_id))
+                            }
+                        },
+                        "{content_text.trim_end()}"
+                    }
+                }
+            }
+        }
+        BlockKind::Table { alignments, .. } => {
             let block_id = block.id;
             if is_focused {
                 // Edit entire table as raw markdown
@@ -319,6 +420,8 @@ pub fn BlockRenderer(
                     }
                 }
             } else if let BlockContent::Children(children) = &block.content {
+                // Rows and cells are rendered here so each cell can take its
+                // column's alignment
                 rsx! {
                     table {
                         class: "table clickable-block",
@@ -329,16 +432,21 @@ pub fn BlockRenderer(
                                 focused_anchor_id.set(Some(block_id))
                             }
                         },
-                        for (i, child) in children.iter().enumerate() {
-                            BlockRenderer {
+                        for (i, row) in children.iter().enumerate() {
+                            tr {
                                 key: "{i}",
-                                block: child.clone(),
-                                source: source.clone(),
-                                focused_anchor_id,
-                                collapsed_ids,
-                                on_context_menu,
-                                on_command,
-                                on_wikilink_click
+                                class: if matches!(row.kind, BlockKind::TableRow { is_header: true }) { "table-header-row" } else { "table-row" },
+                                for (j, cell) in table_row_cells(row).iter().enumerate() {
+                                    td {
+                                        key: "{j}",
+                                        class: "table-cell",
+                                        style: cell_alignment_style(alignments.get(j)),
+                                        InlineSegments {
+                                            segments: cell.segments.clone(),
+                                            on_wikilink_click
+                                        }
+                                    }
+                                }
                             }
                         }
                     }
@@ -23,7 +23,7 @@ fn group_for_editing(children: &[Block]) -> Vec<EditingGroup> {
     let mut current_paras: Vec<Block> = Vec::new();
 
     for child in children {
-        if matches!(child.kind, BlockKind::Paragraph) {
+        if matches!(child.kind, BlockKind::Paragraph { .. }) {
             current_paras.push(child.clone());
         } else {
             // Flush any accumulated paragraphs as an editable group
@@ -1,4 +1,4 @@
-use crate::ui::components::{editor_block::EditorBlock, text_segment::InlineSegments};
+use crate::ui::components::editor_block::EditorBlock;
 use dioxus::prelude::*;
 use markdown_neuraxis_engine::editing::{AnchorId, Block, Cmd};
 
@@ -7,9 +7,9 @@ pub fn CodeFence(
     block: Block,
     source: String,
     lang: Option<String>,
+    title: Option<String>,
     focused_anchor_id: Signal<Option<AnchorId>>,
     on_command: Callback<Cmd>,
-    on_wikilink_click: Callback<String>,
 ) -> Element {
     let is_focused = focused_anchor_id.read().as_ref() == Some(&block.id);
     let code_class = lang
@@ -49,12 +49,21 @@ pub fn CodeFence(
                         focused_anchor_id.set(Some(block_id))
                     }
                 },
+                if let Some(title) = title {
+                    div { class: "code-title", "{title}" }
+                }
                 pre {
                     code {
                         class: "{code_class}",
-                        InlineSegments {
-                            segments: block.segments.clone(),
-                            on_wikilink_click
+                        for line in block.code_lines() {
+                            span {
+                                class: if line.highlighted {
+                                    "code-line highlighted"
+                                } else {
+                                    "code-line"
+                                },
+                                "{line.text}\n"
+                            }
                         }
                     }
                 }
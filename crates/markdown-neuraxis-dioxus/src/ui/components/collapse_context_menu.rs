@@ -221,6 +221,7 @@ fn collect_heading_children(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use markdown_neuraxis_engine::editing::FocusHint;
 
     /// Create a test block with given kind and ID
     fn make_block(id: u128, kind: BlockKind, content: BlockContent) -> Block {
@@ -229,6 +230,8 @@ mod tests {
             kind,
             node_range: 0..10,
             segments: vec![],
+            collapsed: false,
+            focus: FocusHint::Normal,
             content,
         }
     }
@@ -238,7 +241,11 @@ mod tests {
     }
 
     fn paragraph(id: u128) -> Block {
-        make_block(id, BlockKind::Paragraph, BlockContent::Leaf)
+        make_block(
+            id,
+            BlockKind::Paragraph { chunks: vec![] },
+            BlockContent::Leaf,
+        )
     }
 
     fn list_item_leaf(id: u128) -> Block {
@@ -247,6 +254,8 @@ mod tests {
             BlockKind::ListItem {
                 marker: "- ".to_string(),
                 checkbox: None,
+                task_state: None,
+                ordinal: None,
             },
             BlockContent::Leaf,
         )
@@ -258,6 +267,8 @@ mod tests {
             BlockKind::ListItem {
                 marker: "- ".to_string(),
                 checkbox: None,
+                task_state: None,
+                ordinal: None,
             },
             BlockContent::Children(children),
         )
@@ -309,7 +320,10 @@ mod tests {
 
     #[test]
     fn test_collect_all_collapsible_empty() {
-        let snapshot = Snapshot { blocks: vec![] };
+        let snapshot = Snapshot {
+            blocks: vec![],
+            active: None,
+        };
         let ids = collect_all_collapsible(&snapshot);
         assert!(ids.is_empty());
     }
@@ -318,6 +332,7 @@ mod tests {
     fn test_collect_all_collapsible_headings_only() {
         let snapshot = Snapshot {
             blocks: vec![heading(1, 1), heading(2, 2), paragraph(3)],
+            active: None,
         };
         let ids = collect_all_collapsible(&snapshot);
         assert_eq!(ids.len(), 2);
@@ -330,6 +345,7 @@ mod tests {
     fn test_collect_all_collapsible_list_items_without_children() {
         let snapshot = Snapshot {
             blocks: vec![list(1, vec![list_item_leaf(2), list_item_leaf(3)])],
+            active: None,
         };
         let ids = collect_all_collapsible(&snapshot);
         // Leaf list items are not collapsible
@@ -342,6 +358,7 @@ mod tests {
         let parent_item = list_item_with_children(2, vec![list(3, vec![nested_item])]);
         let snapshot = Snapshot {
             blocks: vec![list(1, vec![parent_item])],
+            active: None,
         };
         let ids = collect_all_collapsible(&snapshot);
         // Only parent_item (id=2) is collapsible because it has children
@@ -356,6 +373,7 @@ mod tests {
         let parent_item = list_item_with_children(3, vec![list(4, vec![nested_item])]);
         let snapshot = Snapshot {
             blocks: vec![heading(1, 1), list(2, vec![parent_item])],
+            active: None,
         };
         let ids = collect_all_collapsible(&snapshot);
         assert_eq!(ids.len(), 2);
@@ -369,6 +387,7 @@ mod tests {
     fn test_collect_children_ids_paragraph_returns_empty() {
         let snapshot = Snapshot {
             blocks: vec![paragraph(1)],
+            active: None,
         };
         let para = &snapshot.blocks[0];
         let ids = collect_children_ids(&snapshot, para);
@@ -379,6 +398,7 @@ mod tests {
     fn test_collect_children_ids_list_item_without_children() {
         let snapshot = Snapshot {
             blocks: vec![list(1, vec![list_item_leaf(2)])],
+            active: None,
         };
         let list_block = &snapshot.blocks[0];
         if let BlockContent::Children(children) = &list_block.content {
@@ -395,6 +415,7 @@ mod tests {
         let parent_item = list_item_with_children(2, vec![list(3, vec![nested1, nested2])]);
         let snapshot = Snapshot {
             blocks: vec![list(1, vec![parent_item.clone()])],
+            active: None,
         };
 
         let ids = collect_children_ids(&snapshot, &parent_item);
@@ -409,6 +430,7 @@ mod tests {
     fn test_collect_heading_children_not_found() {
         let snapshot = Snapshot {
             blocks: vec![heading(1, 1)],
+            active: None,
         };
         let ids = collect_heading_children(&snapshot, AnchorId(999), 1);
         assert!(ids.is_empty());
@@ -421,6 +443,7 @@ mod tests {
         // ## H2
         let snapshot = Snapshot {
             blocks: vec![heading(1, 1), heading(2, 2), heading(3, 2)],
+            active: None,
         };
         let ids = collect_heading_children(&snapshot, AnchorId(1), 1);
         assert_eq!(ids.len(), 2);
@@ -436,6 +459,7 @@ mod tests {
         // ### H3 (id=4)
         let snapshot = Snapshot {
             blocks: vec![heading(1, 2), heading(2, 3), heading(3, 2), heading(4, 3)],
+            active: None,
         };
         let ids = collect_heading_children(&snapshot, AnchorId(1), 2);
         // Only H3 (id=2) is a child, H2 (id=3) stops collection
@@ -451,6 +475,7 @@ mod tests {
         // ### H3 (id=4)
         let snapshot = Snapshot {
             blocks: vec![heading(1, 2), heading(2, 3), heading(3, 1), heading(4, 3)],
+            active: None,
         };
         let ids = collect_heading_children(&snapshot, AnchorId(1), 2);
         // Only H3 (id=2) is a child, H1 (id=3) stops collection
@@ -473,6 +498,7 @@ mod tests {
                 heading(4, 4),
                 heading(5, 1),
             ],
+            active: None,
         };
         let ids = collect_heading_children(&snapshot, AnchorId(1), 1);
         // H2, H3, H4 are all children of first H1
@@ -488,6 +514,7 @@ mod tests {
         // ## H2 (id=2)
         let snapshot = Snapshot {
             blocks: vec![heading(1, 1), heading(2, 2)],
+            active: None,
         };
         let ids = collect_heading_children(&snapshot, AnchorId(2), 2);
         // H2 is at end, no children
@@ -503,6 +530,7 @@ mod tests {
         let parent_item = list_item_with_children(3, vec![list(10, vec![nested_item])]);
         let snapshot = Snapshot {
             blocks: vec![heading(1, 1), list(2, vec![parent_item]), heading(5, 1)],
+            active: None,
         };
         let ids = collect_heading_children(&snapshot, AnchorId(1), 1);
         // Should include the collapsible list item (id=3)
@@ -0,0 +1,119 @@
+use dioxus::prelude::*;
+use markdown_neuraxis_engine::SpellDictionary;
+use std::path::PathBuf;
+
+/// Settings page for the vault's custom spelling dictionary.
+/// Changes are saved to the vault immediately so they sync with the notes.
+#[component]
+pub fn DictionarySettings(notes_path: PathBuf, on_close: EventHandler<()>) -> Element {
+    let mut dictionary = use_signal({
+        let notes_path = notes_path.clone();
+        move || match SpellDictionary::load(&notes_path) {
+            Ok(dict) => dict,
+            Err(e) => {
+                tracing::error!("Failed to load dictionary: {e}");
+                SpellDictionary::default()
+            }
+        }
+    });
+    let mut new_word = use_signal(String::new);
+    let mut error_message = use_signal(|| None::<String>);
+
+    let save = {
+        let notes_path = notes_path.clone();
+        move || {
+            if let Err(e) = dictionary.read().save(&notes_path) {
+                error_message.set(Some(format!("Failed to save dictionary: {e}")));
+            } else {
+                error_message.set(None);
+            }
+        }
+    };
+
+    let add_word = {
+        let mut save = save.clone();
+        move || {
+            let word = new_word.read().clone();
+            if dictionary.write().add_word(&word) {
+                save();
+            }
+            new_word.set(String::new());
+        }
+    };
+    let on_add_click = {
+        let mut add_word = add_word.clone();
+        move |_: Event<MouseData>| add_word()
+    };
+    let on_add_keydown = {
+        let mut add_word = add_word.clone();
+        move |event: Event<KeyboardData>| {
+            if event.key() == Key::Enter {
+                event.prevent_default();
+                add_word();
+            }
+        }
+    };
+
+    let words: Vec<String> = dictionary.read().words().map(str::to_string).collect();
+
+    rsx! {
+        div {
+            class: "dictionary-settings",
+            div {
+                class: "settings-header",
+                h2 { "Dictionary" }
+                button {
+                    class: "close-btn",
+                    onclick: move |_| on_close.call(()),
+                    "×"
+                }
+            }
+            p {
+                class: "settings-description",
+                "Words accepted by the spell checker in this vault. Note names and wiki-link targets are accepted automatically."
+            }
+            div {
+                class: "dictionary-add",
+                input {
+                    r#type: "text",
+                    value: new_word.read().clone(),
+                    placeholder: "Add a word...",
+                    oninput: move |event: Event<FormData>| {
+                        new_word.set(event.value());
+                    },
+                    onkeydown: on_add_keydown,
+                }
+                button {
+                    onclick: on_add_click,
+                    "Add"
+                }
+            }
+            ul {
+                class: "dictionary-words",
+                for word in words {
+                    li {
+                        key: "{word}",
+                        span { "{word}" }
+                        button {
+                            class: "remove-word-btn",
+                            title: "Remove",
+                            onclick: {
+                                let word = word.clone();
+                                let mut save = save.clone();
+                                move |_| {
+                                    if dictionary.write().remove_word(&word) {
+                                        save();
+                                    }
+                                }
+                            },
+                            "×"
+                        }
+                    }
+                }
+            }
+            if let Some(error) = error_message.read().as_ref() {
+                p { class: "settings-error", "{error}" }
+            }
+        }
+    }
+}
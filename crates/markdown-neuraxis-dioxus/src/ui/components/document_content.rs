@@ -181,7 +181,7 @@ fn compute_hidden_heading_sections(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use markdown_neuraxis_engine::editing::BlockContent;
+    use markdown_neuraxis_engine::editing::{BlockContent, FocusHint};
 
     /// Create a test block with given kind and ID
     fn make_block(id: u128, kind: BlockKind) -> Block {
@@ -190,6 +190,8 @@ mod tests {
             kind,
             node_range: 0..10,
             segments: vec![],
+            collapsed: false,
+            focus: FocusHint::Normal,
             content: BlockContent::Leaf,
         }
     }
@@ -199,7 +201,7 @@ mod tests {
     }
 
     fn paragraph(id: u128) -> Block {
-        make_block(id, BlockKind::Paragraph)
+        make_block(id, BlockKind::Paragraph { chunks: vec![] })
     }
 
     #[test]
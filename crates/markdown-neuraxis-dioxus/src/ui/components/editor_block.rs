@@ -1,5 +1,5 @@
 use dioxus::prelude::*;
-use markdown_neuraxis_engine::editing::{Block, BlockKind, Cmd};
+use markdown_neuraxis_engine::editing::{Block, BlockKind, Cmd, TextCounts};
 
 /// EditorBlock component for raw markdown editing when a block is focused
 /// This implements the editing pattern from ADR-0004 where focused blocks
@@ -44,6 +44,8 @@ pub fn EditorBlock(
             textarea {
                 class: "editor-textarea",
                 value: local_content.read().clone(),
+                // The webview's checker can't use the vault dictionary (see
+                // markdown_neuraxis_engine::spelling), so it stays off
                 spellcheck: false,
                 rows: calculate_textarea_rows(&local_content.read()),
                 autofocus: true,
@@ -88,6 +90,15 @@ pub fn EditorBlock(
                     // Editor is now active
                 },
             }
+
+            // Live counts for the text being edited
+            div {
+                class: "editor-footer",
+                {
+                    let counts = TextCounts::of_str(&local_content.read());
+                    format!("{} words · {} chars", counts.words, counts.chars)
+                }
+            }
         }
     }
 }
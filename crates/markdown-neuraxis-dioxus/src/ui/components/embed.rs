@@ -0,0 +1,103 @@
+use crate::ui::components::{block::BlockRenderer, editor_block::EditorBlock};
+use dioxus::prelude::*;
+use markdown_neuraxis_engine::editing::{AnchorId, Block, BlockContent, Cmd, EmbedSource};
+use std::collections::HashSet;
+
+/// A note embedded with `![[Note]]` or `![[Note#Heading]]`.
+///
+/// The embedded blocks belong to the other note, so they are shown
+/// read-only from its text; clicking them opens that note. Clicking the
+/// header edits the embed line itself.
+#[component]
+pub fn Embed(
+    block: Block,
+    source: String,
+    target: String,
+    heading: Option<String>,
+    embed_source: Option<EmbedSource>,
+    focused_anchor_id: Signal<Option<AnchorId>>,
+    on_command: Callback<Cmd>,
+    on_wikilink_click: Callback<String>,
+) -> Element {
+    let is_focused = focused_anchor_id.read().as_ref() == Some(&block.id);
+    // Embedded blocks are never edited, and their folds are their own
+    let embedded_focus = use_signal(|| None::<AnchorId>);
+    let embedded_collapsed = use_signal(HashSet::<AnchorId>::new);
+
+    if is_focused {
+        let content_text = source
+            .get(block.node_range.clone())
+            .unwrap_or("")
+            .to_string();
+        let block_clone = block.clone();
+        return rsx! {
+            div {
+                class: "embed clickable-block",
+                EditorBlock {
+                    block: block_clone,
+                    content_text,
+                    on_command,
+                    on_cancel: {
+                        let mut focused_anchor_id = focused_anchor_id;
+                        move |_| focused_anchor_id.set(None)
+                    }
+                }
+            }
+        };
+    }
+
+    let block_id = block.id;
+    let title = match &heading {
+        Some(heading) => format!("{target} › {heading}"),
+        None => target.clone(),
+    };
+    let children = match (&embed_source, &block.content) {
+        (Some(_), BlockContent::Children(children)) => children.clone(),
+        _ => Vec::new(),
+    };
+    let open_target = target.clone();
+
+    rsx! {
+        div {
+            class: "embed",
+            div {
+                class: "embed-header clickable-block",
+                onclick: {
+                    let mut focused_anchor_id = focused_anchor_id;
+                    move |evt| {
+                        evt.stop_propagation();
+                        focused_anchor_id.set(Some(block_id))
+                    }
+                },
+                "{title}"
+            }
+            if let Some(embed_source) = embed_source {
+                div {
+                    class: "embed-content",
+                    title: "{embed_source.path}",
+                    onclick: move |evt| {
+                        evt.stop_propagation();
+                        on_wikilink_click.call(open_target.clone());
+                    },
+                    for (i, child) in children.into_iter().enumerate() {
+                        BlockRenderer {
+                            key: "{i}",
+                            block: child,
+                            source: embed_source.text.clone(),
+                            focused_anchor_id: embedded_focus,
+                            collapsed_ids: embedded_collapsed,
+                            on_context_menu: None,
+                            on_command: Callback::new(|_: Cmd| {}),
+                            on_wikilink_click
+                        }
+                    }
+                }
+            } else {
+                div {
+                    class: "embed-missing",
+                    "Not found"
+                }
+            }
+        }
+    }
+}
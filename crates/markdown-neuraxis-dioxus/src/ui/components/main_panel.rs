@@ -1,7 +1,10 @@
 use crate::ui::components::EmptyDocument;
 use crate::ui::components::document_content::DocumentContent;
 use dioxus::prelude::*;
-use markdown_neuraxis_engine::editing::{AnchorId, Cmd, Document, Snapshot};
+use markdown_neuraxis_engine::editing::embeds::resolve_vault_embeds;
+use markdown_neuraxis_engine::editing::{
+    AnchorId, Cmd, Document, LinkProtocols, ListNumbering, Snapshot,
+};
 use markdown_neuraxis_engine::models::MarkdownFile;
 use std::collections::HashSet;
 use std::path::PathBuf;
@@ -18,7 +21,19 @@ pub fn MainPanel(
     on_wikilink_click: Callback<String>,
     on_rename: Callback<String>,
     #[props(default = false)] is_new_file: bool,
+    #[props(default)] list_numbering: ListNumbering,
+    #[props(default)] link_protocols: LinkProtocols,
+    /// Note can't be edited or renamed (see `vault::OpenMode`)
+    #[props(default = false)]
+    read_only: bool,
 ) -> Element {
+    let snapshot = {
+        let mut snapshot = snapshot;
+        snapshot.apply_list_numbering(list_numbering);
+        snapshot.apply_link_protocols(&link_protocols);
+        resolve_vault_embeds(&mut snapshot, &notes_path, Some(file.relative_path()));
+        snapshot
+    };
     let mut focused_anchor_id = use_signal(|| None::<AnchorId>);
     let collapsed_ids = use_signal(HashSet::<AnchorId>::new);
     let context_menu_position = use_signal(|| None::<(f64, f64)>);
@@ -39,10 +54,16 @@ pub fn MainPanel(
     // Clone values before using in RSX
     let snapshot_for_keydown = snapshot.clone();
     let snapshot_for_content = snapshot.clone();
+    let counts = document.document_counts();
+    let container_class = if read_only {
+        "document-container read-only"
+    } else {
+        "document-container"
+    };
 
     rsx! {
         div {
-            class: "document-container",
+            class: "{container_class}",
             tabindex: "0",
             onkeydown: {
                 move |event| {
@@ -95,10 +116,15 @@ pub fn MainPanel(
                 h1 {
                     class: "document-title",
                     onclick: move |_| {
-                        name_input.set(initial_path_for_click.clone());
-                        editing_name.set(true);
+                        if !read_only {
+                            name_input.set(initial_path_for_click.clone());
+                            editing_name.set(true);
+                        }
                     },
                     "{initial_path}"
+                    if read_only {
+                        span { class: "read-only-badge", "Read-only" }
+                    }
                 }
             }
             hr {}
@@ -125,6 +151,10 @@ pub fn MainPanel(
                     should_focus: *focus_content.read()
                 }
             }
+            div {
+                class: "document-footer",
+                "{counts.words} words · {counts.chars} characters"
+            }
         }
     }
 }
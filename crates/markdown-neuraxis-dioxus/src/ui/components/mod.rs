@@ -2,23 +2,32 @@ pub mod block;
 pub mod block_quote;
 pub mod code_fence;
 pub mod collapse_context_menu;
+pub mod dictionary_settings;
 pub mod document_content;
 pub mod editor_block;
+pub mod embed;
 pub mod empty_document;
 pub mod error_screen;
 pub mod heading;
 pub mod main_panel;
+pub mod note_search;
+pub mod orphan_assets;
 pub mod paragraph;
 pub mod setup_screen;
 pub mod text_segment;
 pub mod thematic_break;
 pub mod tree_view;
 pub mod tree_view_item;
+pub mod vault_diagnostics;
 
 pub use block::CollapseToggle;
 pub use collapse_context_menu::CollapseContextMenu;
+pub use dictionary_settings::DictionarySettings;
 pub use empty_document::*;
 pub use error_screen::*;
 pub use main_panel::*;
+pub use note_search::NoteSearch;
+pub use orphan_assets::OrphanAssets;
 pub use setup_screen::*;
 pub use tree_view::*;
+pub use vault_diagnostics::VaultDiagnostics;
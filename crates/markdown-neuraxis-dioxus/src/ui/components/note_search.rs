@@ -0,0 +1,86 @@
+use crate::ui::indexing::{IndexStatus, VaultIndices};
+use dioxus::prelude::*;
+use markdown_neuraxis_engine::MarkdownFile;
+use markdown_neuraxis_engine::search::SearchHit;
+use markdown_neuraxis_engine::vault::IndexKind;
+
+/// Most hits listed at once; narrowing the query shows the rest.
+const MAX_HITS: usize = 200;
+
+/// Page for finding words across all notes, using the vault's search
+/// index. Clicking a hit opens its note.
+#[component]
+pub fn NoteSearch(
+    indices: Signal<VaultIndices>,
+    index_status: Signal<IndexStatus, SyncStorage>,
+    on_file_select: EventHandler<MarkdownFile>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let mut query = use_signal(String::new);
+
+    // Reading the status re-runs the search as the index changes
+    let ready = index_status.read().readiness.is_ready(IndexKind::Search);
+    let search = indices.read().search.clone();
+    let search = search.read().unwrap_or_else(|e| e.into_inner());
+    let hits: Vec<SearchHit> = if ready {
+        search.search(&query.read())
+    } else {
+        Vec::new()
+    };
+    let total = hits.len();
+    let placeholder = if ready {
+        format!("Search {} notes", search.len())
+    } else {
+        "Indexing notes…".to_string()
+    };
+    drop(search);
+
+    rsx! {
+        div {
+            class: "note-search",
+            div {
+                class: "settings-header",
+                h2 { "Find in all notes" }
+                button {
+                    class: "close-btn",
+                    onclick: move |_| on_close.call(()),
+                    "×"
+                }
+            }
+            input {
+                class: "note-search-input",
+                r#type: "search",
+                placeholder: "{placeholder}",
+                autofocus: true,
+                value: "{query}",
+                oninput: move |evt| query.set(evt.value()),
+            }
+            if !ready {
+                p { class: "settings-status", "Search is available once your notes are indexed." }
+            } else if !query.read().trim().is_empty() {
+                p {
+                    class: "settings-description",
+                    if total > MAX_HITS {
+                        "Showing {MAX_HITS} of {total} matches"
+                    } else {
+                        "{total} matches"
+                    }
+                }
+            }
+            ul {
+                class: "note-search-results",
+                for (i, hit) in hits.into_iter().take(MAX_HITS).enumerate() {
+                    li {
+                        key: "{i}",
+                        onclick: {
+                            let path = hit.path.clone();
+                            move |_| on_file_select.call(MarkdownFile::new(path.clone()))
+                        },
+                        span { class: "note-search-path", "{hit.path}" }
+                        span { class: "note-search-snippet", "{hit.snippet}" }
+                    }
+                }
+            }
+        }
+    }
+}
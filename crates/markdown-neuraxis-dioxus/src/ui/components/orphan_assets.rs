@@ -0,0 +1,107 @@
+use dioxus::prelude::*;
+use markdown_neuraxis_engine::{assets, io};
+use relative_path::RelativePathBuf;
+use std::path::PathBuf;
+
+/// Maintenance page listing asset files no note references, with an action
+/// to move them to the vault trash, and links to files that are missing.
+#[component]
+pub fn OrphanAssets(notes_path: PathBuf, on_close: EventHandler<()>) -> Element {
+    let mut orphans = use_signal(Vec::<RelativePathBuf>::new);
+    let mut missing = use_signal(Vec::<String>::new);
+    let mut status = use_signal(|| None::<String>);
+
+    let scan = {
+        let notes_path = notes_path.clone();
+        move || {
+            match assets::find_orphan_assets(&notes_path) {
+                Ok(found) => orphans.set(found),
+                Err(e) => status.set(Some(format!("Failed to scan assets: {e}"))),
+            }
+            match assets::find_missing_assets(&notes_path) {
+                Ok(found) => missing.set(
+                    found
+                        .iter()
+                        .map(|link| format!("{}:{}: {}", link.path, link.line, link.target))
+                        .collect(),
+                ),
+                Err(e) => status.set(Some(format!("Failed to scan assets: {e}"))),
+            }
+        }
+    };
+    use_hook({
+        let mut scan = scan.clone();
+        move || scan()
+    });
+
+    let on_trash_click = {
+        let notes_path = notes_path.clone();
+        let mut scan = scan.clone();
+        move |_: Event<MouseData>| {
+            let to_trash = orphans.read().clone();
+            match assets::trash_orphan_assets(&notes_path, &to_trash) {
+                Ok(trashed) => status.set(Some(format!(
+                    "Moved {} file(s) to {}",
+                    trashed.len(),
+                    io::TRASH_DIR
+                ))),
+                Err(e) => status.set(Some(format!("Failed to move assets to trash: {e}"))),
+            }
+            scan();
+        }
+    };
+
+    let paths: Vec<String> = orphans.read().iter().map(|p| p.to_string()).collect();
+    let missing_links = missing.read().clone();
+
+    rsx! {
+        div {
+            class: "orphan-assets",
+            div {
+                class: "settings-header",
+                h2 { "Orphaned assets" }
+                button {
+                    class: "close-btn",
+                    onclick: move |_| on_close.call(()),
+                    "×"
+                }
+            }
+            p {
+                class: "settings-description",
+                "Files in assets folders that no note links to or embeds."
+            }
+            if paths.is_empty() {
+                p { "No orphaned assets found." }
+            } else {
+                ul {
+                    class: "orphan-asset-list",
+                    for path in paths {
+                        li { key: "{path}", "{path}" }
+                    }
+                }
+                button {
+                    onclick: on_trash_click,
+                    "Move all to trash"
+                }
+            }
+            h3 { "Missing files" }
+            p {
+                class: "settings-description",
+                "Images, file links and embeds pointing at files that don't exist."
+            }
+            if missing_links.is_empty() {
+                p { "No missing files found." }
+            } else {
+                ul {
+                    class: "orphan-asset-list",
+                    for (i, link) in missing_links.into_iter().enumerate() {
+                        li { key: "{i}", "{link}" }
+                    }
+                }
+            }
+            if let Some(message) = status.read().as_ref() {
+                p { class: "settings-status", "{message}" }
+            }
+        }
+    }
+}
@@ -1,10 +1,14 @@
 use crate::ui::components::{editor_block::EditorBlock, text_segment::InlineSegments};
 use dioxus::prelude::*;
 use markdown_neuraxis_engine::editing::{AnchorId, Block, Cmd};
+use std::ops::Range;
 
 #[component]
 pub fn Paragraph(
     block: Block,
+    /// Chunks of a long paragraph, rendered separately so off-screen ones can
+    /// skip layout; empty for ordinary paragraphs
+    chunks: Vec<Range<usize>>,
     source: String,
     focused_anchor_id: Signal<Option<AnchorId>>,
     on_command: Callback<Cmd>,
@@ -44,9 +48,22 @@ pub fn Paragraph(
                         focused_anchor_id.set(Some(block_id))
                     }
                 },
-                InlineSegments {
-                    segments: block.segments.clone(),
-                    on_wikilink_click
+                if chunks.is_empty() {
+                    InlineSegments {
+                        segments: block.segments.clone(),
+                        on_wikilink_click
+                    }
+                } else {
+                    for chunk in chunks {
+                        span {
+                            key: "{chunk.start}",
+                            class: "paragraph-chunk",
+                            InlineSegments {
+                                segments: block.segments_in(chunk.clone()),
+                                on_wikilink_click
+                            }
+                        }
+                    }
                 }
             }
         }
@@ -47,19 +47,17 @@ pub fn SetupScreen(on_complete: EventHandler<PathBuf>) -> Element {
                         // Create welcome.md in the selected folder
                         let welcome_path = notes_path.join("welcome.md");
                         if let Err(e) = std::fs::write(&welcome_path, WELCOME_CONTENT) {
-                            log::warn!("Failed to create welcome.md: {e}");
+                            tracing::warn!("Failed to create welcome.md: {e}");
                         } else {
-                            log::info!("Created welcome.md file");
+                            tracing::info!("Created welcome.md file");
                         }
 
                         // Save config
-                        let config = Config {
-                            notes_path: notes_path.clone(),
-                        };
+                        let config = Config::new(notes_path.clone());
 
                         match config.save() {
                             Ok(()) => {
-                                log::info!("Config saved successfully");
+                                tracing::info!("Config saved successfully");
                                 on_complete.call(notes_path);
                             }
                             Err(e) => {
@@ -103,13 +101,11 @@ pub fn SetupScreen(on_complete: EventHandler<PathBuf>) -> Element {
                     }
 
                     // Save config
-                    let config = Config {
-                        notes_path: notes_path.clone(),
-                    };
+                    let config = Config::new(notes_path.clone());
 
                     match config.save() {
                         Ok(()) => {
-                            log::info!("Config saved successfully");
+                            tracing::info!("Config saved successfully");
                             on_complete.call(notes_path);
                         }
                         Err(e) => {
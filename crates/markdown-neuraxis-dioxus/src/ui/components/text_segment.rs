@@ -1,5 +1,28 @@
+use std::path::PathBuf;
+
 use dioxus::prelude::*;
 use markdown_neuraxis_engine::editing::{InlineNode, InlineSegment};
+use markdown_neuraxis_engine::{MarkdownFile, io};
+
+/// Where images in the open note are loaded from, provided as context by
+/// the app.
+#[derive(Clone, Copy)]
+pub struct NoteAssets {
+    pub notes_path: Signal<PathBuf>,
+    pub selected_file: Signal<Option<MarkdownFile>>,
+}
+
+impl NoteAssets {
+    /// Image `src` for a Markdown image URL: the file in the notes folder
+    /// if it exists there, otherwise the URL as written (e.g. a web image).
+    fn image_src(&self, url: &str) -> String {
+        let selected_file = self.selected_file.read();
+        selected_file
+            .as_ref()
+            .and_then(|file| io::resolve_asset(url, file.relative_path(), &self.notes_path.read()))
+            .map_or_else(|| url.to_string(), |path| path.display().to_string())
+    }
+}
 
 /// Renders a list of InlineSegments
 #[component]
@@ -36,14 +59,14 @@ fn render_inline_node(
         InlineNode::Strong(children) => rsx! {
             strong { key: "{key}",
                 for (i, child) in children.iter().enumerate() {
-                    {render_inline_node(child, key * 1000 + i, on_wikilink_click)}
+                    {render_inline_node(&child.kind, key * 1000 + i, on_wikilink_click)}
                 }
             }
         },
         InlineNode::Emphasis(children) => rsx! {
             em { key: "{key}",
                 for (i, child) in children.iter().enumerate() {
-                    {render_inline_node(child, key * 1000 + i, on_wikilink_click)}
+                    {render_inline_node(&child.kind, key * 1000 + i, on_wikilink_click)}
                 }
             }
         },
@@ -83,15 +106,22 @@ fn render_inline_node(
                         evt.prevent_default();
                         evt.stop_propagation();
                         if let Err(e) = open_url(&url_clone) {
-                            eprintln!("Failed to open URL {}: {}", url_clone, e);
+                            tracing::error!("Failed to open URL {}: {}", url_clone, e);
                         }
                     },
                     "{text}"
                 }
             }
         }
-        InlineNode::Image { alt, url } => rsx! {
-            img { key: "{key}", alt: "{alt}", src: "{url}" }
+        InlineNode::Image { alt, url } => {
+            let src = try_consume_context::<NoteAssets>()
+                .map_or_else(|| url.clone(), |assets| assets.image_src(url));
+            rsx! {
+                img { key: "{key}", alt: "{alt}", src: "{src}" }
+            }
+        }
+        InlineNode::Tag { name } => rsx! {
+            span { key: "{key}", class: "tag", "#{name}" }
         },
         InlineNode::HardBreak => rsx! {
             br { key: "{key}" }
@@ -0,0 +1,109 @@
+use dioxus::prelude::*;
+use markdown_neuraxis_engine::MarkdownFile;
+use markdown_neuraxis_engine::links::VaultLint;
+use relative_path::RelativePathBuf;
+use std::path::PathBuf;
+
+/// Maintenance page listing dead wiki-links, orphaned notes and empty
+/// notes. Clicking an entry opens its note.
+#[component]
+pub fn VaultDiagnostics(
+    notes_path: PathBuf,
+    on_file_select: EventHandler<MarkdownFile>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let mut status = use_signal(|| None::<String>);
+    // Read when the page opens so it reflects the notes as they are now
+    let lint = use_hook(|| match VaultLint::load(&notes_path) {
+        Ok(lint) => lint,
+        Err(e) => {
+            status.set(Some(format!("Failed to read notes: {e}")));
+            VaultLint::default()
+        }
+    });
+
+    let dead: Vec<(RelativePathBuf, String)> = lint
+        .dead_links()
+        .into_iter()
+        .map(|link| {
+            (
+                link.path,
+                format!("line {}: [[{}]]", link.line, link.target),
+            )
+        })
+        .collect();
+    let orphans: Vec<RelativePathBuf> = lint
+        .orphan_notes()
+        .into_iter()
+        .map(|path| path.to_relative_path_buf())
+        .collect();
+    let empty: Vec<RelativePathBuf> = lint
+        .empty_notes()
+        .into_iter()
+        .map(|path| path.to_relative_path_buf())
+        .collect();
+    let (dead_count, orphan_count, empty_count) = (dead.len(), orphans.len(), empty.len());
+    let open = move |path: RelativePathBuf| {
+        move |_: Event<MouseData>| on_file_select.call(MarkdownFile::new(path.clone()))
+    };
+
+    rsx! {
+        div {
+            class: "vault-diagnostics",
+            div {
+                class: "settings-header",
+                h2 { "Vault diagnostics" }
+                button {
+                    class: "close-btn",
+                    onclick: move |_| on_close.call(()),
+                    "×"
+                }
+            }
+            h3 { "Dead links ({dead_count})" }
+            p {
+                class: "settings-description",
+                "Wiki-links to notes that don't exist."
+            }
+            ul {
+                class: "note-search-results",
+                for (i, (path, detail)) in dead.into_iter().enumerate() {
+                    li {
+                        key: "{i}",
+                        onclick: open(path.clone()),
+                        span { class: "note-search-path", "{path}" }
+                        span { class: "note-search-snippet", "{detail}" }
+                    }
+                }
+            }
+            h3 { "Orphaned notes ({orphan_count})" }
+            p {
+                class: "settings-description",
+                "Notes no other note links to."
+            }
+            ul {
+                class: "note-search-results",
+                for path in orphans {
+                    li {
+                        key: "{path}",
+                        onclick: open(path.clone()),
+                        span { class: "note-search-path", "{path}" }
+                    }
+                }
+            }
+            h3 { "Empty notes ({empty_count})" }
+            ul {
+                class: "note-search-results",
+                for path in empty {
+                    li {
+                        key: "{path}",
+                        onclick: open(path.clone()),
+                        span { class: "note-search-path", "{path}" }
+                    }
+                }
+            }
+            if let Some(message) = status.read().as_ref() {
+                p { class: "settings-status", "{message}" }
+            }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Vault indices for the app, built in the background when a vault is
+//! opened so the file tree shows straight away, then kept current by an
+//! [`IndexWorker`] as notes are saved. The same worker parses notes as
+//! they are opened, so a large note doesn't hold up the UI.
+
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+
+use dioxus::prelude::*;
+use markdown_neuraxis_engine::FileTree;
+use markdown_neuraxis_engine::io::{IoError, ScanOptions};
+use markdown_neuraxis_engine::search::SearchIndex;
+use markdown_neuraxis_engine::titles::TitleIndex;
+use markdown_neuraxis_engine::vault::{
+    self, IndexUpdate, IndexWorker, ParsedNote, Readiness, SharedIndex, VaultEvent, VaultOptions,
+};
+use relative_path::RelativePathBuf;
+
+/// The indices kept for the open vault, and the worker applying note
+/// changes to them.
+#[derive(Clone)]
+pub struct VaultIndices {
+    pub titles: Arc<RwLock<TitleIndex>>,
+    pub search: Arc<RwLock<SearchIndex>>,
+    worker: Rc<IndexWorker>,
+}
+
+impl VaultIndices {
+    /// Empty indices for the vault at `notes_root`. The worker bumps
+    /// `status`'s revision after each batch of changes it applies and each
+    /// note it parses.
+    pub fn new(notes_root: &Path, status: Signal<IndexStatus, SyncStorage>) -> Self {
+        let titles = Arc::new(RwLock::new(TitleIndex::default()));
+        let search = Arc::new(RwLock::new(SearchIndex::default()));
+        let root = notes_root.to_path_buf();
+        let worker = IndexWorker::spawn_notifying(
+            notes_root,
+            vec![titles.clone(), search.clone()],
+            move || {
+                let mut status = status;
+                // The app may already be gone when the last batch finishes
+                if let Ok(mut current) = status.try_write()
+                    && current.root == root
+                {
+                    current.revision += 1;
+                }
+            },
+        );
+        Self {
+            titles,
+            search,
+            worker: Rc::new(worker),
+        }
+    }
+
+    /// Queue a note change; the indices pick it up in the background.
+    pub fn submit(&self, update: IndexUpdate) {
+        self.worker.submit(update);
+    }
+
+    /// Open a note in the background, replacing any note still loading.
+    pub fn parse(&self, path: RelativePathBuf) {
+        self.worker.parse(path);
+    }
+
+    /// Stop loading the note requested last.
+    pub fn cancel_parse(&self) {
+        self.worker.cancel_parse();
+    }
+
+    /// The note requested last, once it is parsed.
+    pub fn poll_parsed(&self) -> Option<ParsedNote> {
+        self.worker.poll_parsed()
+    }
+
+    /// Drain the worker's events, logging notes it couldn't index.
+    pub fn poll(&self) {
+        for event in self.worker.poll() {
+            if let VaultEvent::NoteSkipped { path, reason } = event {
+                tracing::warn!("Could not index '{path}': {reason}");
+            }
+        }
+    }
+
+    fn shared(&self) -> Vec<SharedIndex> {
+        vec![self.titles.clone(), self.search.clone()]
+    }
+}
+
+/// How far indexing of a vault has got. Written from the indexing thread,
+/// so it lives in a sync signal.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IndexStatus {
+    /// Vault this status is for
+    pub root: PathBuf,
+    pub readiness: Readiness,
+    /// Bumped whenever the worker has applied note changes or parsed a note
+    pub revision: u64,
+}
+
+impl IndexStatus {
+    /// Nothing indexed yet for the vault at `root`.
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            readiness: Readiness::default(),
+            revision: 0,
+        }
+    }
+}
+
+/// Open the vault at `notes_root`, returning its file tree now. `indices`
+/// are filled on a background thread, which updates `status` when they are
+/// ready unless another vault has been opened since.
+pub fn open_vault(
+    notes_root: &Path,
+    scan: ScanOptions,
+    indices: &VaultIndices,
+    mut status: Signal<IndexStatus, SyncStorage>,
+) -> Result<FileTree, IoError> {
+    let options = VaultOptions {
+        scan,
+        ..VaultOptions::default()
+    };
+    let mut opening = vault::open_vault_with(notes_root, indices.shared(), options)?;
+    let tree = std::mem::replace(
+        &mut opening.file_tree,
+        FileTree::new(notes_root.to_path_buf()),
+    );
+
+    let root = notes_root.to_path_buf();
+    std::thread::spawn(move || {
+        opening.wait();
+        if let Ok(mut status) = status.try_write()
+            && status.root == root
+        {
+            status.readiness = opening.readiness().clone();
+        }
+    });
+    Ok(tree)
+}
@@ -1,4 +1,5 @@
 pub mod app;
 pub mod components;
+pub mod indexing;
 
 pub use app::*;
@@ -29,14 +29,16 @@ fn segments_to_plain_text(segments: &[InlineSegment]) -> String {
 fn inline_node_to_text(node: &InlineNode) -> String {
     match node {
         InlineNode::Text(s) => s.clone(),
-        InlineNode::Strong(children) | InlineNode::Emphasis(children) => {
-            children.iter().map(inline_node_to_text).collect()
-        }
+        InlineNode::Strong(children) | InlineNode::Emphasis(children) => children
+            .iter()
+            .map(|child| inline_node_to_text(&child.kind))
+            .collect(),
         InlineNode::Code(s) => s.clone(),
         InlineNode::Strikethrough(s) => s.clone(),
         InlineNode::WikiLink { target, alias } => alias.as_ref().unwrap_or(target).clone(),
         InlineNode::Link { text, .. } => text.clone(),
         InlineNode::Image { alt, .. } => alt.clone(),
+        InlineNode::Tag { name } => format!("#{name}"),
         InlineNode::HardBreak => "\n".to_string(),
         InlineNode::SoftBreak => " ".to_string(),
     }
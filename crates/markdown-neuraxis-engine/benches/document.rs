@@ -1,5 +1,5 @@
 use criterion::{Criterion, criterion_group, criterion_main};
-use markdown_neuraxis_engine::editing::document::Document;
+use markdown_neuraxis_engine::editing::{commands::Cmd, document::Document};
 mod common;
 
 fn bench_document_creation(c: &mut Criterion) {
@@ -32,8 +32,73 @@ fn bench_document_operations(c: &mut Criterion) {
         });
     });
 
+    group.bench_function("to_bytes", |b| {
+        b.iter(|| {
+            let bytes = doc.to_bytes();
+            std::hint::black_box(bytes);
+        });
+    });
+
+    group.bench_function("chunks", |b| {
+        b.iter(|| {
+            let len: usize = doc.chunks(0..content.len()).map(str::len).sum();
+            std::hint::black_box(len);
+        });
+    });
+
+    group.bench_function("slice_to_cow", |b| {
+        let middle = content.len() / 2;
+        b.iter(|| {
+            let slice = doc.slice_to_cow(std::hint::black_box(middle..middle + 40));
+            std::hint::black_box(slice);
+        });
+    });
+
+    group.bench_function("line_lookup", |b| {
+        let middle = content.len() / 2;
+        b.iter(|| {
+            let line = doc.line_of_offset(std::hint::black_box(middle));
+            std::hint::black_box(doc.offset_of_line(line));
+        });
+    });
+
+    group.bench_function("document_counts", |b| {
+        b.iter(|| {
+            let counts = doc.document_counts();
+            std::hint::black_box(counts);
+        });
+    });
+
+    group.finish();
+}
+
+/// Typing into a large note, where copying the whole text per edit shows
+fn bench_large_note_edits(c: &mut Criterion) {
+    let mut group = c.benchmark_group("large_note_edits");
+    group.sample_size(10);
+
+    let content = common::generate_markdown_content(2000);
+    let doc = Document::from_bytes(content.as_bytes()).unwrap();
+
+    group.bench_function("insert_text", |b| {
+        let mut d = doc.clone();
+        let at = content.len() / 2;
+        b.iter(|| {
+            let patch = d.apply(Cmd::InsertText {
+                at: std::hint::black_box(at),
+                text: "x".to_string(),
+            });
+            std::hint::black_box(patch);
+        });
+    });
+
     group.finish();
 }
 
-criterion_group!(benches, bench_document_creation, bench_document_operations);
+criterion_group!(
+    benches,
+    bench_document_creation,
+    bench_document_operations,
+    bench_large_note_edits
+);
 criterion_main!(benches);
@@ -0,0 +1,464 @@
+//! # Block Annotations
+//!
+//! Review comments attached to blocks without touching the Markdown: the
+//! note stays clean for other tools while a review mode shows comments in
+//! the margin. Like [saved folds](crate::folds), an annotation records the
+//! block id and the byte offset where the block started, plus the start of
+//! the block's text. Block ids are stable while a note is unchanged; if the
+//! note was edited elsewhere the annotation goes to the block with the same
+//! text, then the block at the same offset, and is listed without a block
+//! if neither exists. [`Annotations::refresh`] records where annotations
+//! are now, so call it after saving a note.
+//!
+//! Annotations are stored in `.neuraxis/annotations.json` (see
+//! [`crate::sidecar`]). The file is JSON rather than a table because a
+//! comment's body is free text:
+//!
+//! ```json
+//! {
+//!   "notes": {
+//!     "plan.md": [
+//!       {
+//!         "id": 1,
+//!         "block_id": "2961...",
+//!         "block_offset": 42,
+//!         "quote": "- Ship the beta",
+//!         "body": "Which date?",
+//!         "created": "2024-03-31T09:00:00Z",
+//!         "resolved": false
+//!       }
+//!     ]
+//!   }
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use jiff::Timestamp;
+use relative_path::{RelativePath, RelativePathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::editing::{AnchorId, Block, BlockKind, Document};
+use crate::io::IoError;
+use crate::read_state::flatten;
+use crate::sidecar;
+
+/// Location of the annotations file, relative to the notes root.
+pub const ANNOTATIONS_PATH: &str = ".neuraxis/annotations.json";
+
+/// Longest block text kept in [`Annotation::quote`], in bytes.
+const QUOTE_LEN: usize = 80;
+
+/// A comment on a block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    /// Unique across the vault
+    pub id: u64,
+    /// The annotated block
+    pub block_id: AnchorId,
+    /// Start of that block, used when neither the id nor the quote match
+    pub block_offset: usize,
+    /// Start of the block's first line, to find it again after edits and
+    /// for listing comments without the note
+    pub quote: String,
+    pub body: String,
+    pub created: Timestamp,
+    pub resolved: bool,
+}
+
+/// Annotations for every note in the vault.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Annotations {
+    notes: BTreeMap<RelativePathBuf, Vec<Annotation>>,
+}
+
+impl Annotations {
+    /// Load annotations from the vault, returning none if there's no file.
+    pub fn load(notes_root: &Path) -> Result<Self, IoError> {
+        match sidecar::read(ANNOTATIONS_PATH, notes_root)? {
+            Some(content) => Self::parse(&content),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Save annotations to the vault.
+    pub fn save(&self, notes_root: &Path) -> Result<(), IoError> {
+        sidecar::write(ANNOTATIONS_PATH, notes_root, &self.to_text())
+    }
+
+    /// Parse the file format. Annotations with an unreadable block id or
+    /// date are skipped; a file that isn't valid JSON is an error, so it
+    /// isn't overwritten with nothing.
+    pub fn parse(content: &str) -> Result<Self, IoError> {
+        let file: AnnotationsFile = serde_json::from_str(content)
+            .map_err(|e| IoError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))?;
+        let notes = file
+            .notes
+            .into_iter()
+            .map(|(path, stored)| {
+                let annotations = stored.into_iter().filter_map(Annotation::from_stored);
+                (RelativePathBuf::from(path), annotations.collect::<Vec<_>>())
+            })
+            .filter(|(_, annotations)| !annotations.is_empty())
+            .collect();
+        Ok(Self { notes })
+    }
+
+    /// Serialize to the file format, sorted by path.
+    pub fn to_text(&self) -> String {
+        let file = AnnotationsFile {
+            notes: self
+                .notes
+                .iter()
+                .map(|(path, annotations)| {
+                    let stored = annotations.iter().map(Annotation::to_stored).collect();
+                    (path.to_string(), stored)
+                })
+                .collect(),
+        };
+        let mut text = serde_json::to_string_pretty(&file).unwrap_or_default();
+        text.push('\n');
+        text
+    }
+
+    /// Annotations on a note, oldest first.
+    pub fn get(&self, path: &RelativePath) -> &[Annotation] {
+        self.notes.get(path).map_or(&[], Vec::as_slice)
+    }
+
+    /// Comment on the block with `block_id` in `doc`, the note at `path`.
+    /// Returns the new annotation's id, or `None` if there's no such block.
+    pub fn add(
+        &mut self,
+        path: &RelativePath,
+        doc: &Document,
+        block_id: AnchorId,
+        body: &str,
+        created: Timestamp,
+    ) -> Option<u64> {
+        let snapshot = doc.snapshot();
+        let mut blocks = Vec::new();
+        flatten(&snapshot.blocks, &mut blocks);
+        let block = blocks.into_iter().find(|b| b.id == block_id)?;
+
+        let id = self
+            .notes
+            .values()
+            .flatten()
+            .map(|a| a.id)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        self.notes
+            .entry(path.to_relative_path_buf())
+            .or_default()
+            .push(Annotation {
+                id,
+                block_id,
+                block_offset: block.node_range.start,
+                quote: quote(doc, block),
+                body: body.trim().to_string(),
+                created,
+                resolved: false,
+            });
+        Some(id)
+    }
+
+    /// Mark an annotation resolved, or open again. Returns false if the
+    /// note has no annotation with `id`.
+    pub fn set_resolved(&mut self, path: &RelativePath, id: u64, resolved: bool) -> bool {
+        let annotation = self
+            .notes
+            .get_mut(path)
+            .and_then(|annotations| annotations.iter_mut().find(|a| a.id == id));
+        match annotation {
+            Some(annotation) => {
+                annotation.resolved = resolved;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Delete an annotation.
+    pub fn delete(&mut self, path: &RelativePath, id: u64) -> Option<Annotation> {
+        let annotations = self.notes.get_mut(path)?;
+        let index = annotations.iter().position(|a| a.id == id)?;
+        let removed = annotations.remove(index);
+        if annotations.is_empty() {
+            self.notes.remove(path);
+        }
+        Some(removed)
+    }
+
+    /// The block each of a note's annotations belongs to in `doc`, or
+    /// `None` for annotations whose block can't be found.
+    pub fn locate<'a>(
+        &'a self,
+        path: &RelativePath,
+        doc: &Document,
+    ) -> Vec<(&'a Annotation, Option<AnchorId>)> {
+        let snapshot = doc.snapshot();
+        let mut blocks = Vec::new();
+        flatten(&snapshot.blocks, &mut blocks);
+        blocks.retain(|b| !matches!(b.kind, BlockKind::Root | BlockKind::List { .. }));
+
+        self.get(path)
+            .iter()
+            .map(|annotation| {
+                let block = blocks
+                    .iter()
+                    .find(|b| b.id == annotation.block_id)
+                    .or_else(|| {
+                        blocks.iter().find(|b| {
+                            !annotation.quote.is_empty() && quote(doc, b) == annotation.quote
+                        })
+                    })
+                    .or_else(|| {
+                        blocks
+                            .iter()
+                            .find(|b| b.node_range.start == annotation.block_offset)
+                    });
+                (annotation, block.map(|b| b.id))
+            })
+            .collect()
+    }
+
+    /// Number of unresolved annotations on each block of `doc`, for
+    /// showing markers in the margin.
+    pub fn open_counts(&self, path: &RelativePath, doc: &Document) -> HashMap<AnchorId, usize> {
+        let mut counts = HashMap::new();
+        for (annotation, block) in self.locate(path, doc) {
+            if let Some(block) = block
+                && !annotation.resolved
+            {
+                *counts.entry(block).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
+    /// Record where a note's annotations are in `doc`, so they're found
+    /// again once block ids change on reopening. Annotations whose block
+    /// is gone keep their old position.
+    pub fn refresh(&mut self, path: &RelativePath, doc: &Document) {
+        let snapshot = doc.snapshot();
+        let mut blocks = Vec::new();
+        flatten(&snapshot.blocks, &mut blocks);
+        let located: HashMap<u64, AnchorId> = self
+            .locate(path, doc)
+            .into_iter()
+            .filter_map(|(annotation, block)| Some((annotation.id, block?)))
+            .collect();
+
+        for annotation in self.notes.get_mut(path).into_iter().flatten() {
+            let block = located
+                .get(&annotation.id)
+                .and_then(|id| blocks.iter().find(|b| b.id == *id));
+            if let Some(block) = block {
+                annotation.block_id = block.id;
+                annotation.block_offset = block.node_range.start;
+                annotation.quote = quote(doc, block);
+            }
+        }
+    }
+
+    /// Forget a note, e.g. after it is deleted.
+    pub fn remove(&mut self, path: &RelativePath) -> Option<Vec<Annotation>> {
+        self.notes.remove(path)
+    }
+
+    /// Keep a note's annotations when it is renamed.
+    pub fn rename(&mut self, from: &RelativePath, to: &RelativePath) {
+        if let Some(annotations) = self.notes.remove(from) {
+            self.notes.insert(to.to_relative_path_buf(), annotations);
+        }
+    }
+}
+
+/// The start of a block's first line, trimmed.
+fn quote(doc: &Document, block: &Block) -> String {
+    let text = doc.slice(block.content_range());
+    let line = text.lines().next().unwrap_or("").trim();
+    let mut end = line.len().min(QUOTE_LEN);
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+    line[..end].to_string()
+}
+
+#[derive(Serialize, Deserialize)]
+struct AnnotationsFile {
+    notes: BTreeMap<String, Vec<StoredAnnotation>>,
+}
+
+/// An annotation as written to the file. Block ids are strings since they
+/// don't fit in a JSON number.
+#[derive(Serialize, Deserialize)]
+struct StoredAnnotation {
+    id: u64,
+    block_id: String,
+    block_offset: usize,
+    #[serde(default)]
+    quote: String,
+    body: String,
+    created: String,
+    #[serde(default)]
+    resolved: bool,
+}
+
+impl Annotation {
+    fn from_stored(stored: StoredAnnotation) -> Option<Self> {
+        Some(Self {
+            id: stored.id,
+            block_id: AnchorId(stored.block_id.parse().ok()?),
+            block_offset: stored.block_offset,
+            quote: stored.quote,
+            body: stored.body,
+            created: stored.created.parse().ok()?,
+            resolved: stored.resolved,
+        })
+    }
+
+    fn to_stored(&self) -> StoredAnnotation {
+        StoredAnnotation {
+            id: self.id,
+            block_id: self.block_id.0.to_string(),
+            block_offset: self.block_offset,
+            quote: self.quote.clone(),
+            body: self.body.clone(),
+            created: self.created.to_string(),
+            resolved: self.resolved,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editing::Cmd;
+    use crate::tests::create_test_notes_dir;
+
+    const NOTE: &str = "# Plan\n\n- Ship the beta\n- Write docs\n\nClosing words\n";
+
+    fn block_starting_with(doc: &Document, prefix: &str) -> Block {
+        let snapshot = doc.snapshot();
+        let mut blocks = Vec::new();
+        flatten(&snapshot.blocks, &mut blocks);
+        blocks
+            .into_iter()
+            .filter(|b| !matches!(b.kind, BlockKind::List { .. }))
+            .find(|b| doc.slice(b.node_range.clone()).starts_with(prefix))
+            .cloned()
+            .unwrap()
+    }
+
+    fn ts(s: &str) -> Timestamp {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_add_resolve_and_count() {
+        let path = RelativePath::new("plan.md");
+        let doc = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        let ship = block_starting_with(&doc, "- Ship");
+        let mut annotations = Annotations::default();
+
+        let first = annotations.add(
+            path,
+            &doc,
+            ship.id,
+            "Which date?",
+            ts("2024-03-31T09:00:00Z"),
+        );
+        let second = annotations.add(
+            path,
+            &doc,
+            ship.id,
+            " Beta scope ",
+            ts("2024-03-31T09:05:00Z"),
+        );
+        assert_eq!((first, second), (Some(1), Some(2)));
+        let (first, second) = (first.unwrap(), second.unwrap());
+        assert_eq!(
+            annotations.add(path, &doc, AnchorId(7), "Lost", ts("2024-03-31T09:00:00Z")),
+            None
+        );
+        assert_eq!(annotations.get(path)[0].quote, "- Ship the beta");
+        assert_eq!(annotations.get(path)[1].body, "Beta scope");
+        assert_eq!(annotations.open_counts(path, &doc)[&ship.id], 2);
+
+        assert!(annotations.set_resolved(path, first, true));
+        assert!(!annotations.set_resolved(path, 99, true));
+        assert_eq!(annotations.open_counts(path, &doc)[&ship.id], 1);
+
+        assert!(annotations.delete(path, second).is_some());
+        assert!(annotations.open_counts(path, &doc).is_empty());
+        assert_eq!(annotations.get(path).len(), 1);
+    }
+
+    #[test]
+    fn test_annotations_survive_reopening_and_edits_elsewhere() {
+        let notes_dir = create_test_notes_dir();
+        let path = RelativePath::new("plan.md");
+        let doc = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        let mut annotations = Annotations::default();
+        let docs = block_starting_with(&doc, "- Write");
+        annotations.add(path, &doc, docs.id, "Who?", ts("2024-03-31T09:00:00Z"));
+        annotations.save(notes_dir.path()).unwrap();
+
+        let loaded = Annotations::load(notes_dir.path()).unwrap();
+        assert_eq!(loaded, annotations);
+
+        // Edited elsewhere: the block moved and the heading above changed
+        let edited = "# Plans for spring\n\nIntro\n\n- Ship the beta\n- Write docs\n";
+        let reopened = Document::from_bytes(edited.as_bytes()).unwrap();
+        let located = loaded.locate(path, &reopened);
+        assert_eq!(
+            located[0].1,
+            Some(block_starting_with(&reopened, "- Write").id)
+        );
+    }
+
+    #[test]
+    fn test_refresh_follows_edited_block() {
+        let path = RelativePath::new("plan.md");
+        let mut doc = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        let mut annotations = Annotations::default();
+        let closing = block_starting_with(&doc, "Closing");
+        annotations.add(
+            path,
+            &doc,
+            closing.id,
+            "Too short",
+            ts("2024-03-31T09:00:00Z"),
+        );
+
+        doc.apply(Cmd::InsertText {
+            at: NOTE.len() - 1,
+            text: ", for now".to_string(),
+        });
+        annotations.refresh(path, &doc);
+        assert_eq!(annotations.get(path)[0].quote, "Closing words, for now");
+
+        // Reopened, the block has a new id but the refreshed quote finds it
+        let reopened = Document::from_bytes(doc.text().as_bytes()).unwrap();
+        let counts = annotations.open_counts(path, &reopened);
+        assert_eq!(
+            counts.get(&block_starting_with(&reopened, "Closing").id),
+            Some(&1)
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_json() {
+        assert!(Annotations::parse("not json").is_err());
+        let parsed = Annotations::parse(
+            r#"{"notes": {"a.md": [{"id": 1, "block_id": "x", "block_offset": 0,
+                "body": "b", "created": "2024-03-31T09:00:00Z"}]}}"#,
+        )
+        .unwrap();
+        assert_eq!(parsed, Annotations::default());
+    }
+}
@@ -0,0 +1,471 @@
+//! # Attachments and Orphan Asset Cleanup
+//!
+//! Images and PDFs live in `assets/` folders next to the notes that use them.
+//! When notes are edited or deleted, attachments are easily left behind. This
+//! module finds asset files that no note references and moves them to the
+//! vault trash (see [`io::move_to_trash`]) so cleanup is always recoverable.
+//!
+//! A note references an asset through a Markdown image or link
+//! (`![alt](assets/pic.png)`, resolved relative to the note) or a wiki-link
+//! embed (`![[pic.png]]`, matched on file name like other wiki-links).
+//!
+//! The reverse problem, links to files that aren't there, is reported by
+//! [`find_missing_assets`]. [`move_asset`] moves or renames an attachment
+//! and rewrites every link to it, so moving files never creates one.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+use markdown_neuraxis_syntax::{SyntaxKind, SyntaxNode, parse, visit};
+use relative_path::{RelativePath, RelativePathBuf};
+
+use crate::io::{self, IoError};
+use crate::links::extract_wikilinks;
+use crate::refactor::{apply_edits, link_edits};
+
+/// Name of the folders holding attachments.
+pub const ASSETS_DIR: &str = "assets";
+
+/// File extensions treated as attachments (compared case-insensitively).
+pub const ASSET_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "svg", "webp", "bmp", "pdf"];
+
+/// All asset files in `assets/` folders anywhere in the vault, sorted.
+///
+/// Hidden folders (such as `.neuraxis/trash`) are skipped.
+pub fn scan_assets(notes_root: &Path) -> Result<Vec<RelativePathBuf>, IoError> {
+    Ok(asset_files(notes_root)?
+        .into_iter()
+        .filter_map(|(path, in_assets)| in_assets.then_some(path))
+        .collect())
+}
+
+/// Files with an asset extension anywhere in the vault, sorted, and
+/// whether each is in an `assets/` folder. Hidden folders are skipped.
+pub(crate) fn asset_files(notes_root: &Path) -> Result<Vec<(RelativePathBuf, bool)>, IoError> {
+    fn walk(
+        dir: &Path,
+        relative: &RelativePath,
+        in_assets: bool,
+        out: &mut Vec<(RelativePathBuf, bool)>,
+    ) -> Result<(), IoError> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            let child = relative.join(&name);
+            if path.is_dir() {
+                walk(&path, &child, in_assets || name == ASSETS_DIR, out)?;
+            } else if is_asset(&child) {
+                out.push((child, in_assets));
+            }
+        }
+        Ok(())
+    }
+
+    io::validate_notes_dir(notes_root)?;
+    let mut files = Vec::new();
+    walk(notes_root, RelativePath::new(""), false, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+/// Asset files not referenced by any note.
+pub fn find_orphan_assets(notes_root: &Path) -> Result<Vec<RelativePathBuf>, IoError> {
+    let refs = collect_references(notes_root)?;
+    Ok(scan_assets(notes_root)?
+        .into_iter()
+        .filter(|asset| !refs.references(asset))
+        .collect())
+}
+
+/// Move the given orphaned assets to the vault trash.
+///
+/// Each asset is checked again before moving, so a file referenced since
+/// the report was produced is left alone. Returns the new trash locations.
+pub fn trash_orphan_assets(
+    notes_root: &Path,
+    assets: &[RelativePathBuf],
+) -> Result<Vec<RelativePathBuf>, IoError> {
+    let refs = collect_references(notes_root)?;
+    let mut trashed = Vec::new();
+    for asset in assets {
+        if !is_asset(asset) || refs.references(asset) || !asset.to_path(notes_root).exists() {
+            continue;
+        }
+        trashed.push(io::move_to_trash(asset, notes_root)?);
+    }
+    Ok(trashed)
+}
+
+/// A link or embed pointing at a file that doesn't exist.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingAsset {
+    /// Note containing the link
+    pub path: RelativePathBuf,
+    /// Byte range of the whole link, image or embed
+    pub range: Range<usize>,
+    /// 1-based line number, for display
+    pub line: usize,
+    /// The destination as written, e.g. `assets/pic.png` or `pic.png`
+    pub target: String,
+}
+
+/// Images, file links and attachment embeds whose file is missing, by
+/// note and then position.
+///
+/// Markdown images count whatever they point at; Markdown links only when
+/// they point at a file that isn't a note (`[report](files/q1.pdf)`); and
+/// wiki-links when they name an attachment (`![[pic.png]]`), which is
+/// found by file name anywhere in the vault, or by path if it has one.
+pub fn find_missing_assets(notes_root: &Path) -> Result<Vec<MissingAsset>, IoError> {
+    let names: HashSet<String> = asset_files(notes_root)?
+        .into_iter()
+        .filter_map(|(path, _)| Some(path.file_name()?.to_lowercase()))
+        .collect();
+    let exists = |path: &RelativePath| path.to_path(notes_root).is_file();
+
+    let mut missing = Vec::new();
+    for note in io::scan_markdown_relative_paths(notes_root)? {
+        let source = io::read_file(&note, notes_root)?;
+        let note_dir = note.parent().unwrap_or(RelativePath::new(""));
+        let mut found = Vec::new();
+
+        let tree = parse(&source);
+        for node in visit::nodes_of_kinds(&tree, &[SyntaxKind::IMAGE, SyntaxKind::LINK]) {
+            let Some(dest) = link_destination(&node) else {
+                continue;
+            };
+            let target = resolve(note_dir, &dest);
+            let is_file_link = node.kind() == SyntaxKind::IMAGE
+                || target.extension().is_some_and(|ext| ext != "md");
+            if is_file_link && !exists(&target) {
+                found.push((node.text_range().into(), dest));
+            }
+        }
+
+        for link in extract_wikilinks(&source) {
+            let target = RelativePath::new(&link.target);
+            if !is_asset(target) {
+                continue;
+            }
+            let present = if link.target.contains('/') {
+                exists(&target.normalize())
+            } else {
+                names.contains(&link.target.to_lowercase())
+            };
+            if !present {
+                found.push((link.range, link.target));
+            }
+        }
+
+        found.sort_by_key(|(range, _): &(Range<usize>, String)| range.start);
+        missing.extend(found.into_iter().map(|(range, target)| MissingAsset {
+            path: note.clone(),
+            line: source[..range.start].matches('\n').count() + 1,
+            range,
+            target,
+        }));
+    }
+    Ok(missing)
+}
+
+/// What [`move_asset`] changed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AssetMove {
+    /// Number of links rewritten
+    pub links_updated: usize,
+    /// Notes whose content was rewritten
+    pub notes_changed: Vec<RelativePathBuf>,
+}
+
+/// Move or rename the attachment `from` to `to`, rewriting the links to it.
+///
+/// Markdown links keep their style (relative, `./` or `/`-rooted) and
+/// path-style wiki-links are updated. Embeds by file name (`![[pic.png]]`)
+/// are renamed with the file, unless another attachment shares the old
+/// name and they might mean that one.
+///
+/// Fails without changing anything if `from` isn't a file or `to` exists.
+pub fn move_asset(
+    notes_root: &Path,
+    from: &RelativePath,
+    to: &RelativePath,
+) -> Result<AssetMove, IoError> {
+    let (from, to) = (from.normalize(), to.normalize());
+    if !from.to_path(notes_root).is_file() {
+        return Err(IoError::NotFound(from.to_path(notes_root)));
+    }
+    if to.to_path(notes_root).exists() {
+        return Err(IoError::FileExists(to.to_path(notes_root)));
+    }
+    let (Some(old_name), Some(new_name)) = (from.file_name(), to.file_name()) else {
+        return Err(IoError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("cannot move {from} to {to}"),
+        )));
+    };
+    let rename_embeds = old_name != new_name
+        && !asset_files(notes_root)?.iter().any(|(path, _)| {
+            *path != from
+                && path
+                    .file_name()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(old_name))
+        });
+
+    let mut result = AssetMove::default();
+    let mut rewritten = BTreeMap::new();
+    for note in io::scan_markdown_relative_paths(notes_root)? {
+        let source = io::read_file(&note, notes_root)?;
+        let mut edits = link_edits(&source, &note, &from, &to);
+        if rename_embeds {
+            for link in extract_wikilinks(&source) {
+                if link.target.contains('/') || !link.target.eq_ignore_ascii_case(old_name) {
+                    continue;
+                }
+                let inner = link.range.start + 2..link.range.end - 2;
+                if let Some(offset) = source[inner.clone()].find(&link.target) {
+                    let start = inner.start + offset;
+                    edits.push((start..start + link.target.len(), new_name.to_string()));
+                }
+            }
+        }
+        if !edits.is_empty() {
+            result.links_updated += edits.len();
+            rewritten.insert(note, apply_edits(&source, edits));
+        }
+    }
+
+    io::rename_file(&from, &to, notes_root)?;
+    for (note, source) in &rewritten {
+        io::write_file(note, notes_root, source)?;
+    }
+    result.notes_changed = rewritten.into_keys().collect();
+    Ok(result)
+}
+
+pub(crate) fn is_asset(path: &RelativePath) -> bool {
+    path.extension().is_some_and(|ext| {
+        ASSET_EXTENSIONS
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(ext))
+    })
+}
+
+/// Every asset reference found in the vault.
+#[derive(Debug, Default)]
+struct References {
+    /// Resolved paths from Markdown images/links
+    paths: HashSet<RelativePathBuf>,
+    /// Lower-cased file names from wiki-link embeds
+    names: HashSet<String>,
+}
+
+impl References {
+    fn references(&self, asset: &RelativePath) -> bool {
+        self.paths.contains(asset)
+            || asset
+                .file_name()
+                .is_some_and(|name| self.names.contains(&name.to_lowercase()))
+    }
+}
+
+fn collect_references(notes_root: &Path) -> Result<References, IoError> {
+    let mut refs = References::default();
+    for note in io::scan_markdown_relative_paths(notes_root)? {
+        let source = io::read_file(&note, notes_root)?;
+        let note_dir = note.parent().unwrap_or(RelativePath::new(""));
+
+        let tree = parse(&source);
+        for node in visit::nodes_of_kinds(&tree, &[SyntaxKind::IMAGE, SyntaxKind::LINK]) {
+            if let Some(url) = link_destination(&node) {
+                refs.paths.insert(resolve(note_dir, &url));
+            }
+        }
+
+        for link in extract_wikilinks(&source) {
+            let target = RelativePath::new(&link.target);
+            if link.target.contains('/') {
+                refs.paths.insert(target.normalize());
+            }
+            if let Some(name) = target.file_name() {
+                refs.names.insert(name.to_lowercase());
+            }
+        }
+    }
+    Ok(refs)
+}
+
+/// Extract a local destination from `[text](dest "title")` / `![alt](dest)`.
+/// External URLs and fragments return `None`.
+fn link_destination(node: &SyntaxNode) -> Option<String> {
+    let dest = visit::child_of_kind(node, SyntaxKind::LINK_DESTINATION)?
+        .text()
+        .to_string();
+    if dest.starts_with('#') || dest.contains(':') {
+        return None;
+    }
+    let dest = dest.split(['#', '?']).next()?;
+    Some(dest.replace("%20", " "))
+}
+
+/// Resolve a link destination relative to the linking note's folder.
+/// Destinations starting with `/` are relative to the notes root.
+pub(crate) fn resolve(note_dir: &RelativePath, dest: &str) -> RelativePathBuf {
+    match dest.strip_prefix('/') {
+        Some(rooted) => RelativePath::new(rooted).normalize(),
+        None => note_dir.join_normalized(dest),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{create_test_file, create_test_notes_dir};
+
+    fn setup() -> tempfile::TempDir {
+        let notes_dir = create_test_notes_dir();
+        fs::create_dir_all(notes_dir.path().join("assets")).unwrap();
+        fs::create_dir_all(notes_dir.path().join("projects/assets")).unwrap();
+        for asset in [
+            "assets/used.png",
+            "assets/embedded.jpg",
+            "assets/My Diagram.svg",
+            "assets/orphan.pdf",
+            "assets/notes.txt",
+            "projects/assets/local.png",
+            "projects/assets/stale.gif",
+        ] {
+            create_test_file(&notes_dir, asset, "data");
+        }
+        create_test_file(
+            &notes_dir,
+            "index.md",
+            "![used](assets/used.png)\n\n![[embedded.jpg]]\n\n[diagram](<assets/My Diagram.svg>)\n",
+        );
+        create_test_file(
+            &notes_dir,
+            "projects/plan.md",
+            "![local](./assets/local.png \"Local\")\n\n![web](https://example.com/x.png)\n",
+        );
+        notes_dir
+    }
+
+    #[test]
+    fn test_scan_assets_only_in_assets_folders() {
+        let notes_dir = setup();
+        create_test_file(&notes_dir, "loose.png", "data");
+        let assets = scan_assets(notes_dir.path()).unwrap();
+        assert_eq!(assets.len(), 6);
+        assert!(
+            !assets
+                .iter()
+                .any(|a| a == "loose.png" || a == "assets/notes.txt")
+        );
+    }
+
+    #[test]
+    fn test_find_orphan_assets() {
+        let notes_dir = setup();
+        let orphans = find_orphan_assets(notes_dir.path()).unwrap();
+        assert_eq!(
+            orphans,
+            vec!["assets/orphan.pdf", "projects/assets/stale.gif"]
+        );
+    }
+
+    #[test]
+    fn test_code_blocks_do_not_count_as_references() {
+        let notes_dir = setup();
+        create_test_file(&notes_dir, "code.md", "```\n![x](assets/orphan.pdf)\n```\n");
+        let orphans = find_orphan_assets(notes_dir.path()).unwrap();
+        assert!(orphans.iter().any(|a| a == "assets/orphan.pdf"));
+    }
+
+    #[test]
+    fn test_find_missing_assets() {
+        let notes_dir = setup();
+        create_test_file(&notes_dir, "loose.png", "data");
+        create_test_file(
+            &notes_dir,
+            "broken.md",
+            "![gone](assets/gone.png) [[loose.png]] ![[nowhere.jpg]]\n\n\
+             [report](files/q1.pdf) [note](other.md) ![[assets/used.png]]\n",
+        );
+        let missing = find_missing_assets(notes_dir.path()).unwrap();
+        let found: Vec<_> = missing
+            .iter()
+            .map(|m| (m.path.as_str(), m.line, m.target.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("broken.md", 1, "assets/gone.png"),
+                ("broken.md", 1, "nowhere.jpg"),
+                ("broken.md", 3, "files/q1.pdf"),
+            ]
+        );
+        assert_eq!(missing[0].range, 0..24);
+    }
+
+    #[test]
+    fn test_move_asset_rewrites_links() {
+        let notes_dir = setup();
+        create_test_file(
+            &notes_dir,
+            "projects/other.md",
+            "![[projects/assets/local.png]] ![[local.png|small]]\n",
+        );
+
+        let moved = move_asset(
+            notes_dir.path(),
+            RelativePath::new("projects/assets/local.png"),
+            RelativePath::new("assets/shared/map.png"),
+        )
+        .unwrap();
+        assert_eq!(moved.links_updated, 3);
+        assert_eq!(
+            moved.notes_changed,
+            ["projects/other.md", "projects/plan.md"]
+        );
+        let read = |path: &str| fs::read_to_string(notes_dir.path().join(path)).unwrap();
+        assert_eq!(
+            read("projects/plan.md"),
+            "![local](../assets/shared/map.png \"Local\")\n\n![web](https://example.com/x.png)\n"
+        );
+        assert_eq!(
+            read("projects/other.md"),
+            "![[assets/shared/map.png]] ![[map.png|small]]\n"
+        );
+        assert!(notes_dir.path().join("assets/shared/map.png").exists());
+        assert!(find_missing_assets(notes_dir.path()).unwrap().is_empty());
+
+        assert!(matches!(
+            move_asset(
+                notes_dir.path(),
+                RelativePath::new("assets/used.png"),
+                RelativePath::new("assets/orphan.pdf"),
+            ),
+            Err(IoError::FileExists(_))
+        ));
+    }
+
+    #[test]
+    fn test_trash_orphan_assets_rechecks_references() {
+        let notes_dir = setup();
+        let orphans = find_orphan_assets(notes_dir.path()).unwrap();
+
+        // Referenced after the report was made - must be kept
+        create_test_file(&notes_dir, "late.md", "![[stale.gif]]\n");
+
+        let trashed = trash_orphan_assets(notes_dir.path(), &orphans).unwrap();
+        assert_eq!(trashed, vec![".neuraxis/trash/assets/orphan.pdf"]);
+        assert!(notes_dir.path().join("projects/assets/stale.gif").exists());
+        assert!(!notes_dir.path().join("assets/orphan.pdf").exists());
+        assert!(find_orphan_assets(notes_dir.path()).unwrap().is_empty());
+    }
+}
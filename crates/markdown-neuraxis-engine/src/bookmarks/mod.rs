@@ -0,0 +1,305 @@
+//! # Bookmarks
+//!
+//! Named places in the vault, each pointing at one block of a note. Front
+//! ends use them for quick jumps, e.g. the terminal UI's `ma` / `'a` marks.
+//!
+//! A bookmark keeps the block id, the byte offset where the block started
+//! and an excerpt of its first line. The id finds the block while the note
+//! is unchanged; after edits the excerpt finds it again even if text was
+//! added above it, and the offset is the last resort.
+//!
+//! Bookmarks are stored in `.neuraxis/bookmarks.tsv` (see
+//! [`crate::sidecar`]), one per line:
+//! `name<TAB>path<TAB>block id<TAB>block offset<TAB>excerpt`.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use relative_path::{RelativePath, RelativePathBuf};
+
+use crate::editing::{AnchorId, Document};
+use crate::io::IoError;
+use crate::read_state::flatten;
+use crate::sidecar;
+
+/// Location of the bookmarks file, relative to the notes root.
+pub const BOOKMARKS_PATH: &str = ".neuraxis/bookmarks.tsv";
+
+/// Longest excerpt kept, in characters.
+const EXCERPT_CHARS: usize = 60;
+
+/// A block to jump back to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    /// Note containing the block
+    pub path: RelativePathBuf,
+    /// Block id when the bookmark was set
+    pub block_id: AnchorId,
+    /// Start of the block when the bookmark was set
+    pub block_offset: usize,
+    /// Start of the block's first line, for finding it after edits and
+    /// for showing in lists of bookmarks
+    pub excerpt: String,
+}
+
+impl Bookmark {
+    /// Bookmark the block `block_id` starting at `block_offset` in `document`.
+    pub fn new(
+        path: &RelativePath,
+        block_id: AnchorId,
+        block_offset: usize,
+        document: &Document,
+    ) -> Self {
+        let text = document.text();
+        Self {
+            path: path.to_relative_path_buf(),
+            block_id,
+            block_offset,
+            excerpt: excerpt(text.get(block_offset..).unwrap_or("")),
+        }
+    }
+
+    /// The bookmarked block in the current version of the note.
+    ///
+    /// Prefers the recorded block id, then the block whose first line
+    /// matches the excerpt (nearest the recorded offset if several do),
+    /// then the last block starting at or before the offset. Returns `None`
+    /// only for an empty note.
+    pub fn resolve(&self, document: &Document) -> Option<AnchorId> {
+        let snapshot = document.snapshot();
+        let mut blocks = Vec::new();
+        flatten(&snapshot.blocks, &mut blocks);
+
+        if blocks.iter().any(|b| b.id == self.block_id) {
+            return Some(self.block_id);
+        }
+        let text = document.text();
+        // Reversed so nested blocks win over the containers sharing their start
+        let by_excerpt = blocks
+            .iter()
+            .rev()
+            .filter(|b| {
+                !self.excerpt.is_empty() && excerpt(&text[b.node_range.start..]) == self.excerpt
+            })
+            .min_by_key(|b| b.node_range.start.abs_diff(self.block_offset));
+        by_excerpt
+            .or_else(|| {
+                blocks
+                    .iter()
+                    .filter(|b| b.node_range.start <= self.block_offset)
+                    .max_by_key(|b| b.node_range.start)
+            })
+            .or(blocks.first())
+            .map(|b| b.id)
+    }
+
+    fn from_record(record: Vec<String>) -> Option<(String, Self)> {
+        let [name, path, block_id, block_offset, excerpt] = record.as_slice() else {
+            return None;
+        };
+        let bookmark = Self {
+            path: RelativePathBuf::from(path),
+            block_id: AnchorId(block_id.parse().ok()?),
+            block_offset: block_offset.parse().ok()?,
+            excerpt: excerpt.clone(),
+        };
+        (!name.is_empty()).then(|| (name.clone(), bookmark))
+    }
+}
+
+/// The trimmed first line of `text`, cut to [`EXCERPT_CHARS`].
+fn excerpt(text: &str) -> String {
+    let line = text.lines().next().unwrap_or("").trim();
+    line.chars().take(EXCERPT_CHARS).collect()
+}
+
+/// Every bookmark in the vault, by name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Bookmarks {
+    bookmarks: BTreeMap<String, Bookmark>,
+}
+
+impl Bookmarks {
+    /// Load bookmarks from the vault, returning none if the file doesn't exist.
+    pub fn load(notes_root: &Path) -> Result<Self, IoError> {
+        Ok(sidecar::read(BOOKMARKS_PATH, notes_root)?
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default())
+    }
+
+    /// Save bookmarks to the vault.
+    pub fn save(&self, notes_root: &Path) -> Result<(), IoError> {
+        sidecar::write(BOOKMARKS_PATH, notes_root, &self.to_text())
+    }
+
+    /// Bookmarks from the file's contents. Lines that aren't a named
+    /// bookmark are dropped.
+    pub fn parse(content: &str) -> Self {
+        Self {
+            bookmarks: sidecar::records(content)
+                .filter_map(Bookmark::from_record)
+                .collect(),
+        }
+    }
+
+    /// Serialize to the file format, sorted by name.
+    pub fn to_text(&self) -> String {
+        self.bookmarks
+            .iter()
+            .map(|(name, bookmark)| {
+                sidecar::record(&[
+                    name,
+                    bookmark.path.as_str(),
+                    &bookmark.block_id.0.to_string(),
+                    &bookmark.block_offset.to_string(),
+                    &bookmark.excerpt,
+                ])
+            })
+            .collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Bookmark> {
+        self.bookmarks.get(name)
+    }
+
+    /// Set or move the bookmark called `name`.
+    pub fn set(&mut self, name: &str, bookmark: Bookmark) {
+        self.bookmarks.insert(name.to_string(), bookmark);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Bookmark> {
+        self.bookmarks.remove(name)
+    }
+
+    /// Bookmarks sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Bookmark)> {
+        self.bookmarks
+            .iter()
+            .map(|(name, bookmark)| (name.as_str(), bookmark))
+    }
+
+    pub fn len(&self) -> usize {
+        self.bookmarks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bookmarks.is_empty()
+    }
+
+    /// Forget the bookmarks in a note, e.g. after it is deleted.
+    pub fn remove_note(&mut self, path: &RelativePath) {
+        self.bookmarks.retain(|_, bookmark| bookmark.path != path);
+    }
+
+    /// Keep a note's bookmarks when it is renamed.
+    pub fn rename_note(&mut self, from: &RelativePath, to: &RelativePath) {
+        for bookmark in self.bookmarks.values_mut() {
+            if bookmark.path == from {
+                bookmark.path = to.to_relative_path_buf();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editing::{Block, Cmd};
+    use crate::tests::create_test_notes_dir;
+
+    const NOTE: &str = "# Title\n\nFirst paragraph.\n\n- one\n- two\n";
+
+    fn block_starting_with(doc: &Document, prefix: &str) -> Block {
+        let snapshot = doc.snapshot();
+        let mut blocks = Vec::new();
+        flatten(&snapshot.blocks, &mut blocks);
+        blocks
+            .into_iter()
+            .rev()
+            .find(|b| doc.slice(b.node_range.clone()).starts_with(prefix))
+            .cloned()
+            .unwrap()
+    }
+
+    fn bookmark_at(doc: &Document, prefix: &str) -> Bookmark {
+        let block = block_starting_with(doc, prefix);
+        Bookmark::new(
+            RelativePath::new("note.md"),
+            block.id,
+            block.node_range.start,
+            doc,
+        )
+    }
+
+    #[test]
+    fn test_resolve_finds_block_after_edits_above_it() {
+        let doc = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        let bookmark = bookmark_at(&doc, "- two");
+        assert_eq!(bookmark.excerpt, "- two");
+        assert_eq!(bookmark.resolve(&doc), Some(bookmark.block_id));
+
+        // Edited elsewhere and reopened: ids and offsets have moved
+        let mut edited = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        edited.apply(Cmd::InsertText {
+            at: 9,
+            text: "Intro paragraph.\n\nAnother one.\n\n".to_string(),
+        });
+        let reopened = Document::from_bytes(edited.text().as_bytes()).unwrap();
+
+        assert_eq!(
+            bookmark.resolve(&reopened),
+            Some(block_starting_with(&reopened, "- two").id)
+        );
+    }
+
+    #[test]
+    fn test_resolve_falls_back_to_offset() {
+        let doc = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        let bookmark = bookmark_at(&doc, "First");
+
+        let reworded = NOTE.replace("First paragraph.", "Reworded.");
+        let reopened = Document::from_bytes(reworded.as_bytes()).unwrap();
+        assert_eq!(
+            bookmark.resolve(&reopened),
+            Some(block_starting_with(&reopened, "Reworded").id)
+        );
+
+        let empty = Document::from_bytes(b"").unwrap();
+        assert_eq!(bookmark.resolve(&empty), None);
+    }
+
+    #[test]
+    fn test_save_load_and_note_changes() {
+        let notes_dir = create_test_notes_dir();
+        let doc = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        let mut bookmarks = Bookmarks::default();
+        bookmarks.set("a", bookmark_at(&doc, "# Title"));
+        bookmarks.set("b", bookmark_at(&doc, "- one"));
+        bookmarks.rename_note(RelativePath::new("note.md"), RelativePath::new("moved.md"));
+
+        bookmarks.save(notes_dir.path()).unwrap();
+        let mut loaded = Bookmarks::load(notes_dir.path()).unwrap();
+        assert_eq!(loaded, bookmarks);
+        assert_eq!(loaded.get("a").unwrap().path, "moved.md");
+        assert_eq!(loaded.get("a").unwrap().excerpt, "# Title");
+
+        loaded.remove_note(RelativePath::new("moved.md"));
+        assert!(loaded.is_empty());
+        assert_eq!(
+            Bookmarks::parse("a\tx.md\t1\t2\t- item\nbroken\nb\tx.md\tid\t2\t\n").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_name_with_tab_or_newline_round_trips() {
+        let doc = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        let mut bookmarks = Bookmarks::default();
+        bookmarks.set("to\tdo\nlater", bookmark_at(&doc, "- one"));
+
+        let loaded = Bookmarks::parse(&bookmarks.to_text());
+        assert_eq!(loaded, bookmarks);
+        assert_eq!(loaded.iter().next().unwrap().0, "to\tdo\nlater");
+        assert_eq!(loaded.get("to\tdo\nlater").unwrap().excerpt, "- one");
+    }
+}
@@ -0,0 +1,341 @@
+//! # Calendar
+//!
+//! Dates for a calendar picker: which days have a daily note (see
+//! [`crate::journal`]) and which notes mention each day. A note mentions a
+//! day with a wiki-link to it (`[[2024-05-01]]`, or to its daily note as
+//! `[[2024_05_01]]`) or an org-style timestamp (`<2024-05-01 Wed>`).
+//! Dates inside code and HTML are ignored.
+//!
+//! [`CalendarIndex`] is a [`VaultIndex`], built with the others when the
+//! vault opens and kept current as notes change.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+
+use jiff::civil::Date;
+use markdown_neuraxis_syntax::{SyntaxKind, parse, visit};
+use relative_path::{RelativePath, RelativePathBuf};
+
+use crate::journal::{JOURNAL_DIR, daily_note_date};
+use crate::links::extract_wikilinks;
+use crate::vault::{IndexKind, SnapshotReader, SnapshotWriter, VaultIndex};
+
+/// How a note refers to a date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DateSource {
+    /// `[[2024-05-01]]`
+    WikiLink,
+    /// `<2024-05-01 Wed>`
+    Timestamp,
+}
+
+/// A date mentioned in a note.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateMention {
+    pub date: Date,
+    pub source: DateSource,
+    /// Byte offset of the mention in the note
+    pub offset: usize,
+}
+
+/// Every date mentioned in a note's source, in order.
+pub fn dates_in(source: &str) -> Vec<DateMention> {
+    let tree = parse(source);
+    let raw: Vec<Range<usize>> = visit::nodes_of_kinds(
+        &tree,
+        &[
+            SyntaxKind::FENCED_CODE,
+            SyntaxKind::INDENTED_CODE,
+            SyntaxKind::CODE_SPAN,
+            SyntaxKind::HTML_BLOCK,
+            SyntaxKind::FRONTMATTER,
+        ],
+    )
+    .map(|node| node.text_range().into())
+    .collect();
+
+    let links = extract_wikilinks(source).into_iter().filter_map(|link| {
+        Some(DateMention {
+            date: linked_date(&link.target)?,
+            source: DateSource::WikiLink,
+            offset: link.range.start,
+        })
+    });
+    let timestamps = source.match_indices('<').filter_map(|(offset, _)| {
+        let inner = source[offset + 1..].split('>').next()?;
+        // Date, then optionally a day name and time, all on one line
+        if inner.contains('\n') || inner.len() > 40 {
+            return None;
+        }
+        let date = parse_iso_date(inner.get(..10)?)?;
+        let rest = &inner[10..];
+        if !(rest.is_empty() || rest.starts_with(' ')) {
+            return None;
+        }
+        Some(DateMention {
+            date,
+            source: DateSource::Timestamp,
+            offset,
+        })
+    });
+
+    let mut mentions: Vec<DateMention> = links
+        .chain(timestamps)
+        .filter(|mention| !raw.iter().any(|range| range.contains(&mention.offset)))
+        .collect();
+    mentions.sort_by_key(|mention| mention.offset);
+    mentions
+}
+
+/// The date a wiki-link target names: an ISO date or a daily note.
+fn linked_date(target: &str) -> Option<Date> {
+    let name = target.strip_prefix("journal/").unwrap_or(target);
+    parse_iso_date(name)
+        .or_else(|| daily_note_date(&RelativePath::new(JOURNAL_DIR).join(format!("{name}.md"))))
+}
+
+/// A `YYYY-MM-DD` date, and nothing else.
+fn parse_iso_date(text: &str) -> Option<Date> {
+    let bytes = text.as_bytes();
+    let shaped = bytes.len() == 10
+        && bytes[4] == b'-'
+        && bytes[7] == b'-'
+        && bytes
+            .iter()
+            .enumerate()
+            .all(|(i, b)| i == 4 || i == 7 || b.is_ascii_digit());
+    if !shaped {
+        return None;
+    }
+    Date::strptime("%Y-%m-%d", text).ok()
+}
+
+/// Everything on one day of the calendar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalendarDay {
+    pub date: Date,
+    /// That day's daily note, if it exists
+    pub daily_note: Option<RelativePathBuf>,
+    /// Notes mentioning the day, by path
+    pub mentioned_in: Vec<RelativePathBuf>,
+}
+
+/// Daily notes and date mentions across the vault.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CalendarIndex {
+    notes: BTreeMap<RelativePathBuf, Vec<DateMention>>,
+    daily_notes: BTreeMap<Date, RelativePathBuf>,
+    /// Notes mentioning each date
+    mentioned: BTreeMap<Date, BTreeSet<RelativePathBuf>>,
+}
+
+impl CalendarIndex {
+    /// Re-read a note's dates from its current source.
+    pub fn update_note(&mut self, path: &RelativePath, source: &str) {
+        self.set_note(path, dates_in(source));
+    }
+
+    fn set_note(&mut self, path: &RelativePath, mentions: Vec<DateMention>) {
+        self.remove_note(path);
+        if let Some(date) = daily_note_date(path) {
+            self.daily_notes.insert(date, path.to_relative_path_buf());
+        }
+        for mention in &mentions {
+            self.mentioned
+                .entry(mention.date)
+                .or_default()
+                .insert(path.to_relative_path_buf());
+        }
+        if !mentions.is_empty() {
+            self.notes.insert(path.to_relative_path_buf(), mentions);
+        }
+    }
+
+    /// Drop a note from the index (e.g. after deletion).
+    pub fn remove_note(&mut self, path: &RelativePath) {
+        if let Some(date) = daily_note_date(path) {
+            self.daily_notes.remove(&date);
+        }
+        for mention in self.notes.remove(path).into_iter().flatten() {
+            if let Some(paths) = self.mentioned.get_mut(&mention.date) {
+                paths.remove(path);
+                if paths.is_empty() {
+                    self.mentioned.remove(&mention.date);
+                }
+            }
+        }
+    }
+
+    /// Days in a month that have a daily note or are mentioned, in order.
+    /// `month` is 1-12; an invalid month has no days.
+    pub fn entries_for_month(&self, year: i16, month: i8) -> Vec<CalendarDay> {
+        let Ok(first) = Date::new(year, month, 1) else {
+            return Vec::new();
+        };
+        let last = first.last_of_month();
+
+        let days: BTreeSet<Date> = self
+            .daily_notes
+            .range(first..=last)
+            .map(|(date, _)| *date)
+            .chain(self.mentioned.range(first..=last).map(|(date, _)| *date))
+            .collect();
+        days.into_iter()
+            .map(|date| CalendarDay {
+                date,
+                daily_note: self.daily_notes.get(&date).cloned(),
+                mentioned_in: self
+                    .notes_mentioning_date(date)
+                    .into_iter()
+                    .map(RelativePath::to_relative_path_buf)
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Notes mentioning `date`, by path.
+    pub fn notes_mentioning_date(&self, date: Date) -> Vec<&RelativePath> {
+        self.mentioned
+            .get(&date)
+            .into_iter()
+            .flatten()
+            .map(|path| path.as_relative_path())
+            .collect()
+    }
+
+    /// Where a note mentions dates, in order.
+    pub fn note_dates(&self, path: &RelativePath) -> &[DateMention] {
+        self.notes.get(path).map_or(&[], Vec::as_slice)
+    }
+}
+
+impl VaultIndex for CalendarIndex {
+    fn kind(&self) -> IndexKind {
+        IndexKind::Calendar
+    }
+
+    fn add_note(&mut self, path: &RelativePath, source: &str) {
+        self.update_note(path, source);
+    }
+
+    fn remove_note(&mut self, path: &RelativePath) {
+        self.remove_note(path);
+    }
+
+    fn snapshot_note(&self, path: &RelativePath) -> Option<Vec<u8>> {
+        let mut out = SnapshotWriter::default();
+        let mentions = self.note_dates(path);
+        out.u64(mentions.len() as u64);
+        for mention in mentions {
+            out.str(&mention.date.to_string());
+            out.u64(match mention.source {
+                DateSource::WikiLink => 0,
+                DateSource::Timestamp => 1,
+            });
+            out.u64(mention.offset as u64);
+        }
+        Some(out.into_bytes())
+    }
+
+    fn restore_note(&mut self, path: &RelativePath, bytes: &[u8]) -> bool {
+        fn decode(input: &mut SnapshotReader<'_>) -> Option<Vec<DateMention>> {
+            let count = input.u64()?;
+            let mut mentions = Vec::new();
+            for _ in 0..count {
+                let date = parse_iso_date(&input.str()?)?;
+                let source = match input.u64()? {
+                    0 => DateSource::WikiLink,
+                    1 => DateSource::Timestamp,
+                    _ => return None,
+                };
+                let offset = usize::try_from(input.u64()?).ok()?;
+                mentions.push(DateMention {
+                    date,
+                    source,
+                    offset,
+                });
+            }
+            input.is_empty().then_some(mentions)
+        }
+        match decode(&mut SnapshotReader::new(bytes)) {
+            Some(mentions) => {
+                self.set_note(path, mentions);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jiff::civil::date;
+
+    #[test]
+    fn test_dates_in_note() {
+        let source = "Meet on [[2024-05-01]] and <2024-05-03 Fri 10:00>.\n\n\
+                      See [[2024_05_02]], not [[2024-13-01]] or <2024-05-04x>.\n\n\
+                      `[[2024-05-05]]`\n\n```\n<2024-05-06>\n```\n";
+        let found: Vec<(Date, DateSource)> = dates_in(source)
+            .into_iter()
+            .map(|m| (m.date, m.source))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (date(2024, 5, 1), DateSource::WikiLink),
+                (date(2024, 5, 3), DateSource::Timestamp),
+                (date(2024, 5, 2), DateSource::WikiLink),
+            ]
+        );
+        assert_eq!(dates_in(source)[1].offset, 27);
+    }
+
+    #[test]
+    fn test_month_entries_and_mentions() {
+        let mut index = CalendarIndex::default();
+        let daily = RelativePath::new("journal/2024_05_01.md");
+        let plan = RelativePath::new("plan.md");
+        let later = RelativePath::new("later.md");
+        index.update_note(daily, "- Standup\n");
+        index.update_note(plan, "Due <2024-05-01 Wed>, review [[2024-05-20]]\n");
+        index.update_note(later, "Next month [[2024-06-01]]\n");
+
+        assert_eq!(
+            index.entries_for_month(2024, 5),
+            vec![
+                CalendarDay {
+                    date: date(2024, 5, 1),
+                    daily_note: Some(daily.to_relative_path_buf()),
+                    mentioned_in: vec![plan.to_relative_path_buf()],
+                },
+                CalendarDay {
+                    date: date(2024, 5, 20),
+                    daily_note: None,
+                    mentioned_in: vec![plan.to_relative_path_buf()],
+                },
+            ]
+        );
+        assert_eq!(index.notes_mentioning_date(date(2024, 6, 1)), vec![later]);
+        assert!(index.entries_for_month(2024, 13).is_empty());
+
+        index.update_note(plan, "Nothing dated\n");
+        index.remove_note(daily);
+        index.remove_note(later);
+        assert_eq!(index, CalendarIndex::default());
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let mut index = CalendarIndex::default();
+        let path = RelativePath::new("journal/2024_05_01.md");
+        index.update_note(path, "Back on <2024-05-08 Wed>\n");
+        let bytes = index.snapshot_note(path).unwrap();
+
+        let mut restored = CalendarIndex::default();
+        assert!(restored.restore_note(path, &bytes));
+        assert_eq!(restored, index);
+        assert!(!restored.restore_note(path, &bytes[..bytes.len() - 1]));
+    }
+}
@@ -0,0 +1,224 @@
+//! # Corpus Checks
+//!
+//! Runs every note in a real vault through the parser and editor core to
+//! check it is fully supported before a user switches tools:
+//!
+//! - the syntax tree reproduces the file byte for byte
+//! - a [`Document`] loads and saves it without changing a byte
+//! - the loaded document passes [`Document::invariant_violations`]
+//! - a render snapshot can be built without panicking
+//!
+//! [`CorpusReport`]'s `Display` output is the summary printed by the CLI's
+//! `corpus-check` command, listing problem files and the slowest files.
+//! [`features::FeatureUsage`] reports which markdown features the vault uses.
+
+pub mod features;
+
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use relative_path::RelativePathBuf;
+
+use crate::editing::Document;
+use crate::io::{self, IoError};
+
+/// How many of the slowest files the summary lists.
+pub const SLOWEST_LISTED: usize = 10;
+
+/// Result of checking one file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileReport {
+    pub path: RelativePathBuf,
+    pub bytes: usize,
+    /// Time to parse, load and snapshot the file
+    pub elapsed: Duration,
+    /// Everything that went wrong; empty if the file is fully supported
+    pub problems: Vec<String>,
+}
+
+/// Result of checking a whole vault.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CorpusReport {
+    /// One report per file, in path order
+    pub files: Vec<FileReport>,
+}
+
+impl CorpusReport {
+    /// Check every markdown file under `notes_root`.
+    pub fn check_vault(notes_root: &Path) -> Result<Self, IoError> {
+        let mut files = Vec::new();
+        for path in io::scan_markdown_relative_paths(notes_root)? {
+            let bytes = std::fs::read(path.to_path(notes_root))?;
+            files.push(check_file(path, &bytes));
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(Self { files })
+    }
+
+    /// Files with at least one problem, most problems first.
+    pub fn problem_files(&self) -> Vec<&FileReport> {
+        let mut files: Vec<_> = self
+            .files
+            .iter()
+            .filter(|f| !f.problems.is_empty())
+            .collect();
+        files.sort_by_key(|f| std::cmp::Reverse(f.problems.len()));
+        files
+    }
+
+    /// The `n` slowest files, slowest first.
+    pub fn slowest(&self, n: usize) -> Vec<&FileReport> {
+        let mut files: Vec<_> = self.files.iter().collect();
+        files.sort_by_key(|f| std::cmp::Reverse(f.elapsed));
+        files.truncate(n);
+        files
+    }
+
+    /// Whether every file passed every check.
+    pub fn is_clean(&self) -> bool {
+        self.files.iter().all(|f| f.problems.is_empty())
+    }
+}
+
+impl fmt::Display for CorpusReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes: usize = self.files.iter().map(|file| file.bytes).sum();
+        let elapsed: Duration = self.files.iter().map(|file| file.elapsed).sum();
+        let problems = self.problem_files();
+        writeln!(
+            f,
+            "Checked {} files ({bytes} bytes) in {elapsed:.2?}: {} with problems",
+            self.files.len(),
+            problems.len()
+        )?;
+
+        if !problems.is_empty() {
+            writeln!(f, "\nProblems:")?;
+            for file in problems {
+                writeln!(f, "  {}", file.path)?;
+                for problem in &file.problems {
+                    writeln!(f, "    - {problem}")?;
+                }
+            }
+        }
+
+        writeln!(f, "\nSlowest files:")?;
+        for file in self.slowest(SLOWEST_LISTED) {
+            writeln!(
+                f,
+                "  {:>10.2?}  {:>9} bytes  {}",
+                file.elapsed, file.bytes, file.path
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Run every check on one file's bytes.
+pub fn check_file(path: RelativePathBuf, bytes: &[u8]) -> FileReport {
+    let started = Instant::now();
+    let mut problems = Vec::new();
+
+    match std::str::from_utf8(bytes) {
+        Ok(source) => {
+            let tree_text = markdown_neuraxis_syntax::parse(source).text().to_string();
+            if let Some(at) = first_difference(source.as_bytes(), tree_text.as_bytes()) {
+                problems.push(format!("syntax tree round-trip differs at byte {at}"));
+            }
+        }
+        Err(e) => problems.push(format!("not valid UTF-8: {e}")),
+    }
+
+    let checked = panic::catch_unwind(AssertUnwindSafe(|| {
+        let doc = Document::from_bytes(bytes)?;
+        let mut problems = Vec::new();
+        if let Some(at) = first_difference(bytes, &doc.to_bytes()) {
+            problems.push(format!("document save differs at byte {at}"));
+        }
+        problems.extend(doc.invariant_violations());
+        doc.snapshot();
+        anyhow::Ok(problems)
+    }));
+    match checked {
+        Ok(Ok(found)) => problems.extend(found),
+        Ok(Err(e)) => problems.push(format!("document failed to load: {e}")),
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            problems.push(format!("panicked: {message}"));
+        }
+    }
+
+    FileReport {
+        path,
+        bytes: bytes.len(),
+        elapsed: started.elapsed(),
+        problems,
+    }
+}
+
+/// Offset of the first differing byte, if the inputs differ.
+fn first_difference(expected: &[u8], actual: &[u8]) -> Option<usize> {
+    if expected == actual {
+        return None;
+    }
+    Some(
+        expected
+            .iter()
+            .zip(actual)
+            .position(|(a, b)| a != b)
+            .unwrap_or(expected.len().min(actual.len())),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{create_test_file, create_test_notes_dir};
+
+    #[test]
+    fn test_supported_files_are_clean() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(
+            &notes_dir,
+            "a.md",
+            "# Title\n\n- [ ] task [[link]]\n\n```rust\nfn x() {}\n```\n",
+        );
+        create_test_file(&notes_dir, "b.md", "> quote\n\n| a | b |\n|---|---|\n");
+
+        let report = CorpusReport::check_vault(notes_dir.path()).unwrap();
+
+        assert_eq!(report.files.len(), 2);
+        assert!(report.is_clean(), "{report}");
+        assert_eq!(report.slowest(1).len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_utf8_reported() {
+        let report = check_file(RelativePathBuf::from("bad.md"), b"ok \xff\xfe\n");
+        assert!(
+            report.problems.iter().any(|p| p.contains("UTF-8")),
+            "{:?}",
+            report.problems
+        );
+
+        let summary = CorpusReport {
+            files: vec![report],
+        }
+        .to_string();
+        assert!(summary.contains("1 with problems"));
+        assert!(summary.contains("bad.md"));
+    }
+
+    #[test]
+    fn test_first_difference() {
+        assert_eq!(first_difference(b"abc", b"abc"), None);
+        assert_eq!(first_difference(b"abc", b"abd"), Some(2));
+        assert_eq!(first_difference(b"abc", b"ab"), Some(2));
+    }
+}
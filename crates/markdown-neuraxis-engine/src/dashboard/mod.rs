@@ -0,0 +1,336 @@
+//! # Area Dashboards
+//!
+//! Data for an overview home screen, grouping notes by PARA area. A note's
+//! area is its `area::` property if it has one (`area:: Health` or
+//! `area:: [[Health]]`), otherwise its top-level folder. Notes at the vault
+//! root with no property are grouped under [`UNFILED_AREA`].
+//!
+//! Each area rolls up:
+//!
+//! - open tasks (`- [ ]`, `- TODO`, `- DOING` and `- LATER` list items)
+//! - the most recently modified notes
+//! - open tasks with a `due:: YYYY-MM-DD` date that is overdue or within
+//!   [`UPCOMING_DAYS`]
+//!
+//! [`Dashboard::build`] works on note sources the caller already has, so
+//! frontends without direct file access can use it; [`Dashboard::load`]
+//! reads the vault from disk.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use jiff::civil::Date;
+use jiff::{Timestamp, ToSpan};
+use relative_path::{RelativePath, RelativePathBuf};
+
+use crate::editing::Document;
+use crate::editing::snapshot::{Block, BlockContent, BlockKind};
+use crate::io::{self, IoError};
+
+/// Area for root-level notes without an `area::` property.
+pub const UNFILED_AREA: &str = "Unfiled";
+
+/// How many recently edited notes each area lists.
+pub const RECENT_EDITS_PER_AREA: usize = 5;
+
+/// How far ahead deadlines count as upcoming.
+pub const UPCOMING_DAYS: i32 = 14;
+
+/// A note to include in a dashboard.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DashboardNote {
+    pub path: RelativePathBuf,
+    pub source: String,
+    /// Last modification time, if known
+    pub modified: Option<Timestamp>,
+}
+
+/// An open task in a note.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Task {
+    pub path: RelativePathBuf,
+    /// 1-based line number of the task
+    pub line: usize,
+    /// Task text after the checkbox
+    pub text: String,
+    /// Date from a `due::` property in the task text
+    pub deadline: Option<Date>,
+}
+
+/// A recently modified note.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecentEdit {
+    pub path: RelativePathBuf,
+    pub modified: Timestamp,
+}
+
+/// Rollup for one area.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AreaSummary {
+    pub name: String,
+    /// Number of notes in the area
+    pub notes: usize,
+    /// Open tasks in source order, by note path
+    pub open_tasks: Vec<Task>,
+    /// Most recently modified first, at most [`RECENT_EDITS_PER_AREA`]
+    pub recent_edits: Vec<RecentEdit>,
+    /// Overdue and upcoming deadlines, soonest first
+    pub upcoming: Vec<Task>,
+}
+
+/// Overview of every area in the vault, sorted by area name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dashboard {
+    pub areas: Vec<AreaSummary>,
+}
+
+impl Dashboard {
+    /// Build a dashboard from note sources.
+    pub fn build(notes: impl IntoIterator<Item = DashboardNote>, today: Date) -> Self {
+        let horizon = today.saturating_add(UPCOMING_DAYS.days());
+        let mut areas: BTreeMap<String, AreaSummary> = BTreeMap::new();
+
+        for note in notes {
+            let name = note_area(&note.path, &note.source);
+            let area = areas.entry(name.clone()).or_insert_with(|| AreaSummary {
+                name,
+                notes: 0,
+                open_tasks: Vec::new(),
+                recent_edits: Vec::new(),
+                upcoming: Vec::new(),
+            });
+            area.notes += 1;
+            area.open_tasks.extend(open_tasks(&note.path, &note.source));
+            if let Some(modified) = note.modified {
+                area.recent_edits.push(RecentEdit {
+                    path: note.path,
+                    modified,
+                });
+            }
+        }
+
+        for area in areas.values_mut() {
+            area.open_tasks
+                .sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+            area.recent_edits
+                .sort_by_key(|edit| std::cmp::Reverse(edit.modified));
+            area.recent_edits.truncate(RECENT_EDITS_PER_AREA);
+            area.upcoming = area
+                .open_tasks
+                .iter()
+                .filter(|task| task.deadline.is_some_and(|due| due <= horizon))
+                .cloned()
+                .collect();
+            area.upcoming.sort_by_key(|task| task.deadline);
+        }
+
+        Self {
+            areas: areas.into_values().collect(),
+        }
+    }
+
+    /// Build a dashboard for the vault on disk.
+    ///
+    /// Skips the notes [`io::is_hidden_or_template`] picks out.
+    pub fn load(notes_root: &Path, today: Date) -> Result<Self, IoError> {
+        let mut notes = Vec::new();
+        for path in io::scan_markdown_relative_paths(notes_root)? {
+            if io::is_hidden_or_template(&path) {
+                continue;
+            }
+            let source = io::read_file(&path, notes_root)?;
+            let modified = std::fs::metadata(path.to_path(notes_root))
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|time| Timestamp::try_from(time).ok());
+            notes.push(DashboardNote {
+                path,
+                source,
+                modified,
+            });
+        }
+        Ok(Self::build(notes, today))
+    }
+
+    /// The summary for an area, if it has any notes.
+    pub fn area(&self, name: &str) -> Option<&AreaSummary> {
+        self.areas.iter().find(|area| area.name == name)
+    }
+}
+
+/// The area a note belongs to.
+pub fn note_area(path: &RelativePath, source: &str) -> String {
+    if let Some(area) = property(source, "area") {
+        let area = area
+            .strip_prefix("[[")
+            .and_then(|a| a.strip_suffix("]]"))
+            .unwrap_or(area)
+            .trim();
+        if !area.is_empty() {
+            return area.to_string();
+        }
+    }
+    match path.parent().and_then(|dir| dir.components().next()) {
+        Some(folder) => folder.as_str().to_string(),
+        None => UNFILED_AREA.to_string(),
+    }
+}
+
+/// Value of the first `key:: value` line in the source.
+fn property<'a>(source: &'a str, key: &str) -> Option<&'a str> {
+    source.lines().find_map(|line| {
+        let (name, value) = line.trim_start().split_once("::")?;
+        (name == key).then_some(value.trim())
+    })
+}
+
+/// Open task list items in a note: unchecked, or marked `TODO`, `DOING`
+/// or `LATER`.
+pub fn open_tasks(path: &RelativePath, source: &str) -> Vec<Task> {
+    fn walk(
+        doc: &Document,
+        source: &str,
+        path: &RelativePath,
+        blocks: &[Block],
+        out: &mut Vec<Task>,
+    ) {
+        for block in blocks {
+            if let BlockKind::ListItem {
+                task_state: Some(state),
+                ..
+            } = &block.kind
+                && state.is_open()
+            {
+                let text = match (block.segments.first(), block.segments.last()) {
+                    (Some(first), Some(last)) => doc.slice(first.range.start..last.range.end),
+                    _ => String::new(),
+                };
+                let text = text.trim().to_string();
+                out.push(Task {
+                    path: path.to_relative_path_buf(),
+                    line: source[..block.node_range.start].matches('\n').count() + 1,
+                    deadline: text
+                        .split_once("due::")
+                        .and_then(|(_, due)| due.split_whitespace().next()?.parse().ok()),
+                    text,
+                });
+            }
+            if let BlockContent::Children(children) = &block.content {
+                walk(doc, source, path, children, out);
+            }
+        }
+    }
+
+    let Ok(doc) = Document::from_bytes(source.as_bytes()) else {
+        return Vec::new();
+    };
+    let mut tasks = Vec::new();
+    walk(&doc, source, path, &doc.snapshot().blocks, &mut tasks);
+    tasks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::create_test_notes_dir;
+    use jiff::civil::date;
+
+    fn note(path: &str, source: &str, modified_secs: Option<i64>) -> DashboardNote {
+        DashboardNote {
+            path: RelativePathBuf::from(path),
+            source: source.to_string(),
+            modified: modified_secs.map(|s| Timestamp::from_second(s).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_note_area_from_property_or_folder() {
+        let path = RelativePath::new("projects/house/plan.md");
+        assert_eq!(note_area(path, "# Plan\n"), "projects");
+        assert_eq!(note_area(path, "area:: Home\n\n# Plan\n"), "Home");
+        assert_eq!(note_area(path, "area:: [[Health]]\n"), "Health");
+        assert_eq!(
+            note_area(RelativePath::new("inbox.md"), "x\n"),
+            UNFILED_AREA
+        );
+    }
+
+    #[test]
+    fn test_open_tasks_with_deadlines() {
+        let source = "# Tasks\n\n- [ ] Call plumber due:: 2024-03-05\n- [x] Done thing\n- Plain item\n  - [ ] Nested\n";
+        let tasks = open_tasks(RelativePath::new("a.md"), source);
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].line, 3);
+        assert_eq!(tasks[0].text, "Call plumber due:: 2024-03-05");
+        assert_eq!(tasks[0].deadline, Some(date(2024, 3, 5)));
+        assert_eq!(tasks[1].text, "Nested");
+        assert_eq!(tasks[1].deadline, None);
+
+        let source = "- TODO a\n- DOING b\n- DONE c\n- [ ] DONE d\n- LATER e\n";
+        let texts: Vec<_> = open_tasks(RelativePath::new("a.md"), source)
+            .into_iter()
+            .map(|task| task.text)
+            .collect();
+        assert_eq!(texts, ["TODO a", "DOING b", "LATER e"]);
+    }
+
+    #[test]
+    fn test_build_groups_and_rolls_up() {
+        let today = date(2024, 3, 1);
+        let dashboard = Dashboard::build(
+            [
+                note(
+                    "projects/a.md",
+                    "- [ ] Later due:: 2024-06-01\n- [ ] Soon due:: 2024-03-10\n",
+                    Some(100),
+                ),
+                note(
+                    "projects/b.md",
+                    "- [ ] Overdue due:: 2024-02-01\n",
+                    Some(300),
+                ),
+                note("areas/health.md", "area:: Health\n- [ ] Run\n", None),
+                note("gym.md", "area:: Health\n", Some(200)),
+                note("inbox.md", "nothing\n", None),
+            ],
+            today,
+        );
+
+        let names: Vec<_> = dashboard.areas.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["Health", UNFILED_AREA, "projects"]);
+
+        let health = dashboard.area("Health").unwrap();
+        assert_eq!(health.notes, 2);
+        assert_eq!(health.open_tasks.len(), 1);
+        assert_eq!(health.recent_edits.len(), 1);
+
+        let projects = dashboard.area("projects").unwrap();
+        assert_eq!(projects.open_tasks.len(), 3);
+        assert_eq!(projects.recent_edits[0].path, "projects/b.md");
+        let upcoming: Vec<_> = projects.upcoming.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(
+            upcoming,
+            ["Overdue due:: 2024-02-01", "Soon due:: 2024-03-10"]
+        );
+    }
+
+    #[test]
+    fn test_load_skips_hidden_and_templates() {
+        let notes_dir = create_test_notes_dir();
+        for (path, content) in [
+            ("projects/a.md", "- [ ] Task\n"),
+            ("templates/meeting.md", "- [ ] {{ agenda }}\n"),
+            (".neuraxis/trash/old.md", "- [ ] Gone\n"),
+        ] {
+            io::write_file(RelativePath::new(path), notes_dir.path(), content).unwrap();
+        }
+
+        let dashboard = Dashboard::load(notes_dir.path(), date(2024, 1, 1)).unwrap();
+
+        assert_eq!(dashboard.areas.len(), 1);
+        let projects = dashboard.area("projects").unwrap();
+        assert_eq!(projects.open_tasks.len(), 1);
+        assert_eq!(projects.recent_edits.len(), 1);
+    }
+}
@@ -0,0 +1,293 @@
+//! # Date Entry
+//!
+//! Typing a date is slow and error-prone, so frontends offer completion:
+//! the user types `tom` or `next fri` and picks the date it means.
+//! [`complete_dates`] suggests phrases for what's been typed so far and
+//! [`parse_date`] reads a whole phrase. Both take today's date from the
+//! caller (usually [`JournalCalendar::today`](crate::journal::JournalCalendar::today))
+//! so every frontend agrees on when "tomorrow" is.
+//!
+//! Phrases understood, case-insensitively:
+//!
+//! - `today`, `tomorrow`, `yesterday`
+//! - a weekday (`friday`, `fri`): the next one, or today if it matches
+//! - `next fri` / `last fri`: the next one after today, or the last before
+//! - `next week`, `last month`, `next year`: a week, month or year away
+//! - `in 3 days`, `2 weeks ago`
+//! - an ISO date, `2024-03-31`
+//!
+//! The chosen date is inserted with [`Cmd::InsertDate`](crate::editing::Cmd),
+//! written in the configured [`DateFormat`]: a `[[2024-03-31]]` link to the
+//! day's daily note (which [`crate::calendar`] counts as a mention of the
+//! day) or a strftime pattern.
+
+use std::str::FromStr;
+
+use jiff::civil::{Date, Weekday};
+use jiff::{Span, ToSpan};
+use thiserror::Error;
+
+/// Pattern dates are written with unless configured otherwise.
+pub const DEFAULT_PATTERN: &str = "%Y-%m-%d";
+
+/// `date_format` setting meaning [`DateFormat::JournalLink`].
+pub const LINK_SETTING: &str = "link";
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("monday", Weekday::Monday),
+    ("tuesday", Weekday::Tuesday),
+    ("wednesday", Weekday::Wednesday),
+    ("thursday", Weekday::Thursday),
+    ("friday", Weekday::Friday),
+    ("saturday", Weekday::Saturday),
+    ("sunday", Weekday::Sunday),
+];
+
+#[derive(Debug, Error)]
+pub enum DateError {
+    #[error("Invalid date format '{pattern}': {source}")]
+    InvalidFormat {
+        pattern: String,
+        source: jiff::Error,
+    },
+}
+
+/// How an inserted date is written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DateFormat {
+    /// `[[2024-03-31]]`, a link to the day's daily note
+    JournalLink,
+    /// A strftime pattern such as `%Y-%m-%d` or `%e %B %Y`
+    Pattern(String),
+}
+
+impl Default for DateFormat {
+    fn default() -> Self {
+        Self::Pattern(DEFAULT_PATTERN.to_string())
+    }
+}
+
+/// Reads a `date_format` setting: `link` for [`DateFormat::JournalLink`],
+/// anything else a strftime pattern.
+impl FromStr for DateFormat {
+    type Err = DateError;
+
+    fn from_str(setting: &str) -> Result<Self, Self::Err> {
+        if setting.trim().eq_ignore_ascii_case(LINK_SETTING) {
+            Ok(Self::JournalLink)
+        } else {
+            Self::pattern(setting)
+        }
+    }
+}
+
+impl DateFormat {
+    /// A strftime pattern, checked by formatting a date with it.
+    pub fn pattern(pattern: &str) -> Result<Self, DateError> {
+        jiff::fmt::strtime::format(pattern, Date::default()).map_err(|source| {
+            DateError::InvalidFormat {
+                pattern: pattern.to_string(),
+                source,
+            }
+        })?;
+        Ok(Self::Pattern(pattern.to_string()))
+    }
+
+    /// `date` written in this format. A pattern that can't be formatted
+    /// falls back to ISO `YYYY-MM-DD`.
+    pub fn format(&self, date: Date) -> String {
+        match self {
+            Self::JournalLink => journal_link(date),
+            Self::Pattern(pattern) => {
+                jiff::fmt::strtime::format(pattern, date).unwrap_or_else(|_| date.to_string())
+            }
+        }
+    }
+}
+
+/// A date to insert and how to write it, for [`Cmd::InsertDate`](crate::editing::Cmd).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateSpec {
+    pub date: Date,
+    pub format: DateFormat,
+}
+
+impl DateSpec {
+    /// The text inserted for this date.
+    pub fn text(&self) -> String {
+        self.format.format(self.date)
+    }
+}
+
+/// A wiki-link to the daily note for `date`, e.g. `[[2024-03-31]]`.
+pub fn journal_link(date: Date) -> String {
+    format!("[[{date}]]")
+}
+
+/// The date a phrase such as `tomorrow` or `next fri` means, counting from
+/// `today`. See the [module docs](self) for the phrases understood.
+pub fn parse_date(text: &str, today: Date) -> Option<Date> {
+    let text = text.trim().to_lowercase();
+    let words: Vec<&str> = text.split_whitespace().collect();
+    match words.as_slice() {
+        ["today"] => Some(today),
+        ["tomorrow"] => today.checked_add(1.day()).ok(),
+        ["yesterday"] => today.checked_sub(1.day()).ok(),
+        [day] if weekday(day).is_some() => {
+            let weekday = weekday(day)?;
+            if today.weekday() == weekday {
+                Some(today)
+            } else {
+                today.nth_weekday(1, weekday).ok()
+            }
+        }
+        [direction @ ("next" | "last"), word] => {
+            let sign = if *direction == "next" { 1 } else { -1 };
+            match weekday(word) {
+                Some(weekday) => today.nth_weekday(sign, weekday).ok(),
+                None => today.checked_add(unit(word, sign as i64)?).ok(),
+            }
+        }
+        ["in", count, word] => today.checked_add(unit(word, count.parse().ok()?)?).ok(),
+        [count, word, "ago"] => today.checked_sub(unit(word, count.parse().ok()?)?).ok(),
+        [date] => date.parse().ok(),
+        _ => None,
+    }
+}
+
+/// A date the user might mean, for a completion list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateCompletion {
+    /// The phrase, e.g. `next friday`
+    pub label: String,
+    pub date: Date,
+}
+
+/// Dates to offer for `typed`, the text entered so far.
+///
+/// Phrases starting with `typed` are suggested in a fixed order (today
+/// first, then weekdays, then next and last). If `typed` is itself a whole
+/// phrase that isn't among them, such as `in 3 days` or an ISO date, it
+/// comes first. Nothing typed suggests today, tomorrow and yesterday.
+pub fn complete_dates(typed: &str, today: Date) -> Vec<DateCompletion> {
+    let typed = typed.trim().to_lowercase();
+    let typed = typed.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut phrases = vec!["today".to_string(), "tomorrow".into(), "yesterday".into()];
+    if !typed.is_empty() {
+        let days = WEEKDAYS.iter().map(|(name, _)| name.to_string());
+        phrases.extend(days.clone());
+        for direction in ["next", "last"] {
+            phrases.extend(days.clone().map(|day| format!("{direction} {day}")));
+            for unit in ["week", "month", "year"] {
+                phrases.push(format!("{direction} {unit}"));
+            }
+        }
+    }
+
+    let mut completions: Vec<DateCompletion> = phrases
+        .into_iter()
+        .filter(|phrase| phrase.starts_with(&typed))
+        .filter_map(|label| {
+            let date = parse_date(&label, today)?;
+            Some(DateCompletion { label, date })
+        })
+        .collect();
+    if !completions.iter().any(|c| c.label == typed)
+        && let Some(date) = parse_date(&typed, today)
+    {
+        completions.insert(0, DateCompletion { label: typed, date });
+    }
+    completions
+}
+
+/// The weekday a word names: its full name or the first three letters or
+/// more (`fri`, `thurs`).
+fn weekday(word: &str) -> Option<Weekday> {
+    WEEKDAYS
+        .iter()
+        .find(|(name, _)| word.len() >= 3 && name.starts_with(word))
+        .map(|(_, weekday)| *weekday)
+}
+
+/// `count` days, weeks, months or years.
+fn unit(word: &str, count: i64) -> Option<Span> {
+    Some(match word.strip_suffix('s').unwrap_or(word) {
+        "day" => count.days(),
+        "week" => count.weeks(),
+        "month" => count.months(),
+        "year" => count.years(),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jiff::civil::date;
+
+    // 2024-03-27 is a Wednesday
+    const TODAY: Date = date(2024, 3, 27);
+
+    #[test]
+    fn test_parse_date_phrases() {
+        for (text, expected) in [
+            ("today", TODAY),
+            ("Tomorrow", date(2024, 3, 28)),
+            ("yesterday", date(2024, 3, 26)),
+            ("fri", date(2024, 3, 29)),
+            ("wednesday", TODAY),
+            ("next wed", date(2024, 4, 3)),
+            ("last  Monday", date(2024, 3, 25)),
+            ("next week", date(2024, 4, 3)),
+            ("last month", date(2024, 2, 27)),
+            ("in 5 days", date(2024, 4, 1)),
+            ("in 1 year", date(2025, 3, 27)),
+            ("2 weeks ago", date(2024, 3, 13)),
+            ("2024-12-25", date(2024, 12, 25)),
+        ] {
+            assert_eq!(parse_date(text, TODAY), Some(expected), "{text}");
+        }
+        for text in ["", "fr", "next", "in x days", "soon", "2024-13-01"] {
+            assert_eq!(parse_date(text, TODAY), None, "{text}");
+        }
+    }
+
+    #[test]
+    fn test_complete_dates() {
+        let labels = |typed: &str| -> Vec<String> {
+            complete_dates(typed, TODAY)
+                .into_iter()
+                .map(|c| c.label)
+                .collect()
+        };
+        assert_eq!(labels(""), ["today", "tomorrow", "yesterday"]);
+        assert_eq!(labels("T"), ["today", "tomorrow", "tuesday", "thursday"]);
+        assert_eq!(labels("next f"), ["next friday"]);
+        assert_eq!(labels("in 2 days"), ["in 2 days"]);
+        assert!(labels("someday").is_empty());
+
+        let completions = complete_dates("tom", TODAY);
+        assert_eq!(completions[0].date, date(2024, 3, 28));
+    }
+
+    #[test]
+    fn test_date_formats() {
+        let day = date(2024, 3, 5);
+        assert_eq!(DateFormat::default().format(day), "2024-03-05");
+        assert_eq!(DateFormat::JournalLink.format(day), "[[2024-03-05]]");
+        assert_eq!(
+            DateFormat::pattern("%e %B %Y").unwrap().format(day),
+            " 5 March 2024"
+        );
+        assert!(DateFormat::pattern("%Q").is_err());
+        assert_eq!(
+            "Link".parse::<DateFormat>().unwrap(),
+            DateFormat::JournalLink
+        );
+        assert_eq!(
+            "%d/%m/%Y".parse::<DateFormat>().unwrap().format(day),
+            "05/03/2024"
+        );
+        assert_eq!(DateFormat::Pattern("%Q".into()).format(day), "2024-03-05");
+    }
+}
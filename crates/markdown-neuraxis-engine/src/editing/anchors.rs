@@ -1,5 +1,6 @@
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use std::collections::HashSet;
+use std::hash::{BuildHasher, RandomState};
+use std::sync::atomic::{AtomicU64, Ordering};
 use xi_rope::delta::Transformer;
 use xi_rope::{Delta, RopeInfo};
 
@@ -55,10 +56,13 @@ pub struct Anchor {
 
 /// Unique identifier for an anchor (128-bit hash for collision resistance)
 ///
-/// AnchorIds are generated using deterministic hashing of:
-/// - Node characteristics (type, position)
-/// - Static/dynamic generation context
-/// - Collision-resistant algorithms (DefaultHasher)
+/// AnchorIds are assigned in two ways:
+/// - **On parse**: a stable hash of the block's kind and text, so the same
+///   document gets the same IDs in every run (snapshot tests, caches keyed
+///   by block id). Repeated blocks with identical text are told apart by
+///   the order they appear in.
+/// - **For blocks inserted by edits**: a random ID, so it can't collide with
+///   an ID the document had before
 ///
 /// The 128-bit width provides sufficient space for large documents while
 /// maintaining reasonable memory usage compared to full UUIDs.
@@ -219,6 +223,19 @@ pub(crate) fn rebind_anchors_in_changed_regions(
     // Trust transform_anchors - it already handled the position shifts
 }
 
+/// Rebind every anchor to the current tree, whatever changed.
+///
+/// For edits that move blocks around, where the changed-region checks
+/// above can't tell which anchors were affected.
+pub(crate) fn rebind_all_anchors(doc: &mut Document) {
+    let Some(tree) = doc.tree.as_ref() else {
+        return;
+    };
+    let mut new_anchor_data = Vec::new();
+    collect_anchor_ranges_recursive(tree.root_node(), &mut new_anchor_data);
+    apply_deterministic_rebinding(doc, new_anchor_data);
+}
+
 /// Apply deterministic rebinding when structural changes have occurred
 fn apply_deterministic_rebinding(
     doc: &mut Document,
@@ -245,7 +262,6 @@ fn apply_deterministic_rebinding(
             new_index,
             &old_anchors,
             &old_anchors_by_position,
-            &new_range,
             &semantic_type,
             &mut used_old_ids,
         );
@@ -265,7 +281,6 @@ fn determine_anchor_id_deterministic(
     position_index: usize,
     old_anchors: &[Anchor],
     old_anchors_by_position: &[Anchor],
-    new_range: &std::ops::Range<usize>,
     _semantic_type: &str,
     used_old_ids: &mut std::collections::HashSet<AnchorId>,
 ) -> AnchorId {
@@ -289,8 +304,11 @@ fn determine_anchor_id_deterministic(
         }
     }
 
-    // Rule 3: Generate new ID if no good match found
-    generate_dynamic_anchor_id(position_index, new_range.clone())
+    // Rule 3: New block, so a new ID that no old anchor had
+    let taken: HashSet<AnchorId> = old_anchors.iter().map(|anchor| anchor.id).collect();
+    let id = new_anchor_id(|id| taken.contains(&id) || used_old_ids.contains(&id));
+    used_old_ids.insert(id);
+    id
 }
 
 /// Collect anchor ranges, node IDs, and semantic type from the tree
@@ -343,11 +361,11 @@ fn collect_anchor_ranges_recursive(
 ///
 /// ## Anchor ID Generation
 ///
-/// Each anchor receives a **static ID** generated from:
-/// - Magic number (differentiates from dynamic IDs)
-/// - Node index within document
-/// - Byte range (ensures uniqueness across content)
-/// - Hash collision resistance (128-bit IDs)
+/// Each anchor receives a **static ID** hashed from the node kind and the
+/// text of its range, so parsing the same document always gives the same
+/// IDs. If that ID is already taken (a repeated block, or in principle a
+/// hash collision), a counter is mixed in until it is unique, so identical
+/// blocks are numbered in document order.
 ///
 /// ## List Item Range Calculation
 ///
@@ -361,9 +379,14 @@ pub(crate) fn create_anchors_from_tree(doc: &mut Document) {
     doc.anchors.clear();
 
     if let Some(ref tree) = doc.tree {
-        let root_node = tree.root_node();
+        let source = doc.buffer.to_string();
         let mut new_anchors = Vec::new();
-        collect_anchors_recursive(root_node, &mut new_anchors);
+        collect_anchors_recursive(
+            tree.root_node(),
+            source.as_bytes(),
+            &mut HashSet::new(),
+            &mut new_anchors,
+        );
         doc.anchors = new_anchors;
     }
 }
@@ -375,9 +398,15 @@ pub fn create_anchors_for_new_blocks(doc: &mut Document) {
     }
 
     let tree = doc.tree.as_ref().unwrap();
-    let root_node = tree.root_node();
+    let source = doc.buffer.to_string();
+    let mut taken: HashSet<AnchorId> = doc.anchors.iter().map(|anchor| anchor.id).collect();
     let mut new_block_anchors = Vec::new();
-    collect_anchors_recursive(root_node, &mut new_block_anchors);
+    collect_anchors_recursive(
+        tree.root_node(),
+        source.as_bytes(),
+        &mut taken,
+        &mut new_block_anchors,
+    );
 
     // Find blocks that don't have anchors yet
     for new_anchor in new_block_anchors {
@@ -400,8 +429,14 @@ pub fn create_anchors_for_new_blocks(doc: &mut Document) {
     }
 }
 
-/// Recursively collect anchors for block-level nodes in the tree  
-fn collect_anchors_recursive(node: tree_sitter::Node, anchors: &mut Vec<Anchor>) {
+/// Recursively collect anchors for block-level nodes in the tree, giving
+/// each an ID not yet in `taken`
+fn collect_anchors_recursive(
+    node: tree_sitter::Node,
+    source: &[u8],
+    taken: &mut HashSet<AnchorId>,
+    anchors: &mut Vec<Anchor>,
+) {
     let node_kind = node.kind();
     let should_create_anchor = matches!(
         node_kind,
@@ -420,7 +455,7 @@ fn collect_anchors_recursive(node: tree_sitter::Node, anchors: &mut Vec<Anchor>)
             node.byte_range()
         };
 
-        let anchor_id = generate_static_anchor_id(anchors.len(), anchor_range.clone());
+        let anchor_id = static_anchor_id(node_kind, &source[anchor_range.clone()], taken);
         let node_id = node.id();
 
         let anchor = Anchor {
@@ -435,7 +470,7 @@ fn collect_anchors_recursive(node: tree_sitter::Node, anchors: &mut Vec<Anchor>)
     // Recursively process child nodes
     let mut cursor = node.walk();
     for child in node.children(&mut cursor) {
-        collect_anchors_recursive(child, anchors);
+        collect_anchors_recursive(child, source, taken, anchors);
     }
 }
 
@@ -459,38 +494,53 @@ fn calculate_list_item_own_range(node: &tree_sitter::Node) -> std::ops::Range<us
     full_range
 }
 
-/// Generate a static anchor ID for initial tree creation
-fn generate_static_anchor_id(index: usize, byte_range: std::ops::Range<usize>) -> AnchorId {
-    let mut hasher = DefaultHasher::new();
-
-    // Include a magic number to differentiate from dynamic IDs
-    let magic = 0x1234567890abcdefu64;
-    magic.hash(&mut hasher);
-
-    // Include index to ensure uniqueness within this generation
-    index.hash(&mut hasher);
-
-    // Include byte range to ensure uniqueness across different content
-    byte_range.start.hash(&mut hasher);
-    byte_range.end.hash(&mut hasher);
-
-    AnchorId(hasher.finish() as u128)
+/// ID for a block found on parse: a stable hash of its kind and text,
+/// numbered past any ID already in `taken`
+fn static_anchor_id(kind: &str, text: &[u8], taken: &mut HashSet<AnchorId>) -> AnchorId {
+    let mut occurrence = 0u64;
+    loop {
+        let id = AnchorId(stable_hash(&[
+            kind.as_bytes(),
+            text,
+            &occurrence.to_le_bytes(),
+        ]));
+        if taken.insert(id) {
+            return id;
+        }
+        occurrence += 1;
+    }
 }
 
-/// Generate a dynamic anchor ID for new blocks created during editing
-fn generate_dynamic_anchor_id(index: usize, byte_range: std::ops::Range<usize>) -> AnchorId {
-    let mut hasher = DefaultHasher::new();
-
-    // Include a different magic number to differentiate from static IDs
-    let magic = 0xfedcba0987654321u64;
-    magic.hash(&mut hasher);
-
-    // Include index and range for uniqueness
-    index.hash(&mut hasher);
-    byte_range.start.hash(&mut hasher);
-    byte_range.end.hash(&mut hasher);
+/// Random ID for a block created by an edit, retried while `taken` says
+/// it is in use
+pub(crate) fn new_anchor_id(taken: impl Fn(AnchorId) -> bool) -> AnchorId {
+    // `RandomState` is seeded randomly per process and differs per instance
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    loop {
+        let state = RandomState::new();
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let high = state.hash_one((count, 0u8)) as u128;
+        let low = state.hash_one((count, 1u8)) as u128;
+        let id = AnchorId(high << 64 | low);
+        if !taken(id) {
+            return id;
+        }
+    }
+}
 
-    AnchorId(hasher.finish() as u128)
+/// 128-bit FNV-1a over `parts`, each length-prefixed so part boundaries
+/// count. Unlike `DefaultHasher`, its output is fixed across Rust releases.
+pub(crate) fn stable_hash(parts: &[&[u8]]) -> u128 {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+    let mut hash = OFFSET;
+    for part in parts {
+        for byte in (part.len() as u64).to_le_bytes().iter().chain(*part) {
+            hash ^= *byte as u128;
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    hash
 }
 
 #[cfg(test)]
@@ -709,6 +759,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_anchor_ids_deterministic_on_parse() {
+        let text = "# Notes\n\n- Same\n- Same\n\n```\ncode\n```\n";
+        let ids = |text: &str| -> Vec<AnchorId> {
+            let doc = Document::from_bytes(text.as_bytes()).unwrap();
+            doc.anchors.iter().map(|a| a.id).collect()
+        };
+
+        let first = ids(text);
+        assert_eq!(first, ids(text));
+        let unique: std::collections::HashSet<_> = first.iter().collect();
+        assert_eq!(
+            unique.len(),
+            first.len(),
+            "repeated blocks get distinct ids"
+        );
+
+        // IDs follow block text, not byte offsets
+        let shifted = ids(&format!("Intro paragraph.\n\n{text}"));
+        assert_eq!(shifted, first);
+    }
+
+    #[test]
+    fn test_inserted_block_gets_fresh_id() {
+        let split = || {
+            let mut doc = Document::from_bytes(b"- Item 1\n- Item 2").unwrap();
+            let before: std::collections::HashSet<AnchorId> =
+                doc.anchors.iter().map(|a| a.id).collect();
+            doc.apply(Cmd::SplitListItem { at: 8 });
+            let after: std::collections::HashSet<AnchorId> =
+                doc.anchors.iter().map(|a| a.id).collect();
+            assert_eq!(after.len(), doc.anchors.len());
+            after.difference(&before).copied().collect::<Vec<_>>()
+        };
+
+        let (first, second) = (split(), split());
+        assert_eq!(first.len(), 1);
+        assert_ne!(first, second, "new blocks get random ids");
+    }
+
     #[test]
     fn test_anchor_generation_for_nested_lists() {
         let text = "- Item 1\n  - Nested 1\n  - Nested 2\n- Item 2";
@@ -1,11 +1,20 @@
+use markdown_neuraxis_syntax::{SyntaxKind, parse, visit};
 use xi_rope::delta::Builder;
 use xi_rope::{Delta, Rope, RopeInfo};
 
-use crate::editing::{Document, document::Marker};
+use crate::dates::DateSpec;
+use crate::editing::link_slots::paste_url_edits;
+use crate::editing::move_block::{BlockMove, BlockPosition};
+use crate::editing::tables::insert_table_edits;
+use crate::editing::toc::toc_edits;
+use crate::editing::{AnchorId, Document, ListNumbering, document::Marker};
 
 /// Indentation string for list items (2 spaces)
 const INDENT_STR: &str = "  ";
 
+/// Indentation string for indented code blocks (4 spaces)
+const CODE_INDENT_STR: &str = "    ";
+
 /// Core edit commands that compile to xi-rope Deltas (ADR-0004)
 ///
 /// This enum represents the "edit algebra" described in ADR-4. All document
@@ -14,7 +23,7 @@ const INDENT_STR: &str = "  ";
 /// 1. **Compile to Deltas**: Each command produces an `xi_rope::Delta`
 /// 2. **Apply atomically**: Commands execute immediately on every input event
 /// 3. **Transform selections**: Cursor/selection positions update automatically
-/// 4. **Enable undo/redo**: Delta history supports branching undo (future)
+/// 4. **Enable undo/redo**: `Document::apply` records each command's edit in the history
 ///
 /// ## Command Design Principles
 ///
@@ -75,6 +84,8 @@ pub enum Cmd {
     ///
     /// **Line-based**: Operates on line starts within the specified byte range.
     /// Adds configured indent string (typically 2 spaces) to each affected line.
+    /// Inside a blockquote it adds a `> ` level instead, or indents list
+    /// items after the quote markers.
     ///
     /// **Delta**: Multiple insert operations at line boundaries.
     IndentLines { range: std::ops::Range<usize> },
@@ -83,6 +94,8 @@ pub enum Cmd {
     ///
     /// **Line-based**: Removes up to one indentation level from lines in range.
     /// Handles both space and tab indentation according to document style.
+    /// Inside a blockquote it removes the outermost `>` level instead, or
+    /// outdents list items after the quote markers.
     ///
     /// **Delta**: Multiple delete operations at line boundaries.
     OutdentLines { range: std::ops::Range<usize> },
@@ -95,6 +108,117 @@ pub enum Cmd {
     ///
     /// **Delta**: Replace operation for marker portion of line.
     ToggleMarker { line_start: usize, to: Marker },
+
+    /// Convert the block covering `range` to another block type
+    ///
+    /// **Markdown-aware**: Rewrites only the line prefixes (`# `, `- `, `> `,
+    /// code indentation) and fence lines; block content is never touched.
+    /// Headings and bullets apply to the first line, quotes to every line.
+    ///
+    /// **Delta**: One replace per changed prefix, plus fence line insert/delete.
+    /// **Selection**: Follows its content; carets inside a replaced prefix
+    /// move to just after the new prefix.
+    ConvertBlock {
+        range: std::ops::Range<usize>,
+        to: BlockConversion,
+    },
+
+    /// Write a list numbering style into the source of ordered lists
+    ///
+    /// **Markdown-aware**: Renumbers markers of ordered items starting on the
+    /// lines in `range` sequentially from each list's first number. CommonMark
+    /// only allows decimal markers, so non-decimal labels (`a.`, `i.`, `1.2`)
+    /// are written at the start of the item text instead (`1. a. text`);
+    /// items already starting with their label are left alone.
+    ///
+    /// **Delta**: One replace per changed marker number or inserted label.
+    /// **Selection**: Follows its content.
+    MaterializeListNumbering {
+        range: std::ops::Range<usize>,
+        numbering: ListNumbering,
+    },
+
+    /// Paste a URL over `range`, filling link slots
+    ///
+    /// **Markdown-aware**: Inside a link's `[text]` or `(href)` slot the URL
+    /// replaces the href (or adds one to bare `[text]`); a selection outside
+    /// a link becomes `[selection](url)`. Anything else, including pasting
+    /// text that isn't a URL, is a plain replace.
+    ///
+    /// **Delta**: One replace.
+    /// **Selection**: Collapses to the end of the link text so a title can
+    /// be typed, or to the end of a plain paste.
+    PasteUrl {
+        range: std::ops::Range<usize>,
+        url: String,
+    },
+
+    /// Regenerate the table of contents under the `<!-- toc -->` marker
+    ///
+    /// **Markdown-aware**: Writes a nested list of `[[#Heading]]` links,
+    /// optionally numbered, between the marker and `<!-- tocstop -->`
+    /// (added if missing). Does nothing without a marker or when the table
+    /// is already up to date.
+    ///
+    /// **Delta**: One replace of the old table.
+    /// **Selection**: Follows its content.
+    UpdateToc { numbered: bool },
+
+    /// Move a block to just before or after another block
+    ///
+    /// **Markdown-aware**: A list item moves with its nested items and a
+    /// heading with its section, each with the blank lines after it. The
+    /// moved lines are re-indented to match the target block. Does nothing
+    /// if either anchor is missing or the target is inside the moved block.
+    ///
+    /// **Delta**: One insert of the moved lines and one delete of the old ones.
+    /// **Selection**: Moves with the block if inside it, otherwise follows
+    /// its content. Anchors in the block keep their IDs.
+    MoveBlock {
+        anchor_id: AnchorId,
+        to: BlockPosition,
+    },
+
+    /// Insert delimited text (CSV, TSV, ...) as a table
+    ///
+    /// **Markdown-aware**: Pasted spreadsheet rows become a GFM table with
+    /// the first row as its header (see [`crate::editing::tables`]). The
+    /// table goes on lines of its own with a blank line either side. Does
+    /// nothing if `text` has no fields.
+    ///
+    /// **Delta**: Single insert of the table.
+    /// **Selection**: Follows its content.
+    InsertTableFromDelimited {
+        at: usize,
+        text: String,
+        delimiter: char,
+    },
+
+    /// Insert a date at absolute byte position
+    ///
+    /// The date and its format are resolved before the command is built (see
+    /// [`crate::dates`]), so replaying it always inserts the same text.
+    ///
+    /// **Delta**: Single insert of the formatted date.
+    /// **Selection**: Same as [`Cmd::InsertText`].
+    InsertDate { at: usize, spec: DateSpec },
+}
+
+/// Target block type for [`Cmd::ConvertBlock`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockConversion {
+    /// Plain paragraph (all prefixes removed)
+    Paragraph,
+    /// ATX heading of the given level (1-6)
+    Heading(u8),
+    /// Bullet list item using `- `
+    Bullet,
+    /// Blockquote, `> ` on every line
+    Quote,
+    /// Fenced code block using backticks
+    FencedCode,
+    /// Indented code block (4 spaces)
+    IndentedCode,
 }
 
 /// Compile a command into an xi-rope Delta (ADR-0004 Core Implementation)
@@ -116,6 +240,20 @@ pub enum Cmd {
 /// - **IndentLines**: Multiple inserts at line boundaries within range
 /// - **OutdentLines**: Multiple deletes removing indentation
 /// - **ToggleMarker**: Replace or insert marker portion of line
+/// - **ConvertBlock**: Replaces line prefixes, inserts/deletes fence lines
+/// - **MaterializeListNumbering**: Replaces marker numbers, inserts labels
+/// - **PasteUrl**: Replaces a link href, wraps a selection, or pastes plainly
+/// - **UpdateToc**: Replaces the table of contents between its markers
+/// - **MoveBlock**: Inserts the moved lines and deletes them from the old place
+/// - **InsertTableFromDelimited**: Inserts the table built from the text
+/// - **InsertDate**: Same as InsertText with the formatted date
+///
+/// Inside code blocks (raw zones) the line-structure commands degrade:
+/// SplitListItem inserts a plain newline, IndentLines inserts the indent at
+/// the cursor, and OutdentLines/ToggleMarker do nothing.
+///
+/// Inside blockquotes IndentLines/OutdentLines change the quote depth (or
+/// list indentation after the `>` markers) instead of leading spaces.
 ///
 /// ## Safety & Correctness
 ///
@@ -166,8 +304,12 @@ pub(crate) fn compile_command(doc: &Document, cmd: &Cmd) -> Delta<RopeInfo> {
             let line_start = find_line_start(doc, clamped_at);
             let line_text = get_line_at(doc, line_start);
 
-            // Extract indent and marker from current line
-            let (indent, marker) = extract_list_info(&line_text);
+            // Extract indent and marker from current line (plain newline inside code)
+            let (indent, marker) = if in_raw_zone(doc, clamped_at) {
+                (None, None)
+            } else {
+                extract_list_info(&line_text)
+            };
 
             // Build the text to insert: newline + indent + marker
             let mut insert_text = String::from("\n");
@@ -185,9 +327,32 @@ pub(crate) fn compile_command(doc: &Document, cmd: &Cmd) -> Delta<RopeInfo> {
             builder.replace(clamped_at..clamped_at, insert_rope);
             builder.build()
         }
+        Cmd::IndentLines { range } if in_raw_zone(doc, range.start) => {
+            // Inside code, indent is just typed whitespace at the cursor
+            let at = range.start.min(doc.len());
+            let mut builder = Builder::new(doc.len());
+            builder.replace(at..at, Rope::from(INDENT_STR));
+            builder.build()
+        }
+        Cmd::IndentLines { range } | Cmd::OutdentLines { range }
+            if in_blockquote(doc, range.start) =>
+        {
+            let indent = matches!(cmd, Cmd::IndentLines { .. });
+            let mut builder = Builder::new(doc.len());
+            for (edit_range, text) in quote_depth_edits(doc, range, indent) {
+                builder.replace(edit_range, Rope::from(text));
+            }
+            builder.build()
+        }
         Cmd::IndentLines { range } => {
             modify_line_starts(doc, range, |_line| Some(INDENT_STR.to_string()))
         }
+        Cmd::OutdentLines { range } if in_raw_zone(doc, range.start) => {
+            Builder::new(doc.len()).build()
+        }
+        Cmd::ToggleMarker { line_start, .. } if in_raw_zone(doc, *line_start) => {
+            Builder::new(doc.len()).build()
+        }
         Cmd::OutdentLines { range } => {
             modify_line_starts(doc, range, |line| {
                 // Remove up to 2 spaces from the start
@@ -244,6 +409,60 @@ pub(crate) fn compile_command(doc: &Document, cmd: &Cmd) -> Delta<RopeInfo> {
 
             builder.build()
         }
+        Cmd::ConvertBlock { range, to } => {
+            let mut builder = Builder::new(doc.len());
+            for (edit_range, text) in block_conversion_edits(doc, range, *to) {
+                builder.replace(edit_range, Rope::from(text));
+            }
+            builder.build()
+        }
+        Cmd::MaterializeListNumbering { range, numbering } => {
+            let mut builder = Builder::new(doc.len());
+            for (edit_range, text) in list_numbering_edits(doc, range, *numbering) {
+                builder.replace(edit_range, Rope::from(text));
+            }
+            builder.build()
+        }
+        Cmd::PasteUrl { range, url } => {
+            let mut builder = Builder::new(doc.len());
+            for (edit_range, text) in paste_url_edits(doc, range, url).0 {
+                builder.replace(edit_range, Rope::from(text));
+            }
+            builder.build()
+        }
+        Cmd::UpdateToc { numbered } => {
+            let mut builder = Builder::new(doc.len());
+            for (edit_range, text) in toc_edits(doc, *numbered) {
+                builder.replace(edit_range, Rope::from(text));
+            }
+            builder.build()
+        }
+        Cmd::MoveBlock { anchor_id, to } => {
+            let mut builder = Builder::new(doc.len());
+            let edits = BlockMove::plan(doc, *anchor_id, *to).map(|plan| plan.edits());
+            for (edit_range, text) in edits.into_iter().flatten() {
+                builder.replace(edit_range, Rope::from(text));
+            }
+            builder.build()
+        }
+        Cmd::InsertTableFromDelimited {
+            at,
+            text,
+            delimiter,
+        } => {
+            let mut builder = Builder::new(doc.len());
+            for (edit_range, text) in insert_table_edits(doc, *at, text, *delimiter) {
+                builder.replace(edit_range, Rope::from(text));
+            }
+            builder.build()
+        }
+        Cmd::InsertDate { at, spec } => compile_command(
+            doc,
+            &Cmd::InsertText {
+                at: *at,
+                text: spec.text(),
+            },
+        ),
     }
 }
 
@@ -348,6 +567,76 @@ pub(crate) fn transform_selection_for_command(
                 range.clone()
             }
         }
+        Cmd::ConvertBlock {
+            range: block_range,
+            to,
+        } => {
+            let edits = block_conversion_edits(doc, block_range, *to);
+            map_offset_through_edits(range.start, &edits, true)
+                ..map_offset_through_edits(range.end, &edits, range.is_empty())
+        }
+        Cmd::MaterializeListNumbering {
+            range: list_range,
+            numbering,
+        } => {
+            let edits = list_numbering_edits(doc, list_range, *numbering);
+            map_offset_through_edits(range.start, &edits, true)
+                ..map_offset_through_edits(range.end, &edits, range.is_empty())
+        }
+        Cmd::PasteUrl {
+            range: paste_range,
+            url,
+        } => {
+            let (_, caret) = paste_url_edits(doc, paste_range, url);
+            caret..caret
+        }
+        Cmd::UpdateToc { numbered } => {
+            let edits = toc_edits(doc, *numbered);
+            map_offset_through_edits(range.start, &edits, true)
+                ..map_offset_through_edits(range.end, &edits, range.is_empty())
+        }
+        Cmd::MoveBlock { anchor_id, to } => match BlockMove::plan(doc, *anchor_id, *to) {
+            Some(plan) => plan.map(range.start, false)..plan.map(range.end, !range.is_empty()),
+            None => range.clone(),
+        },
+        Cmd::InsertTableFromDelimited {
+            at,
+            text,
+            delimiter,
+        } => {
+            let edits = insert_table_edits(doc, *at, text, *delimiter);
+            map_offset_through_edits(range.start, &edits, true)
+                ..map_offset_through_edits(range.end, &edits, range.is_empty())
+        }
+        Cmd::InsertDate { at, spec } => transform_selection_for_command(
+            doc,
+            range,
+            &Cmd::InsertText {
+                at: *at,
+                text: spec.text(),
+            },
+        ),
+        Cmd::IndentLines {
+            range: indent_range,
+        } if in_raw_zone(doc, indent_range.start) => {
+            // Same as inserting the indent string at the cursor
+            transform_selection_for_command(
+                doc,
+                range,
+                &Cmd::InsertText {
+                    at: indent_range.start,
+                    text: INDENT_STR.to_string(),
+                },
+            )
+        }
+        Cmd::IndentLines { range: lines } | Cmd::OutdentLines { range: lines }
+            if in_blockquote(doc, lines.start) =>
+        {
+            let indent = matches!(cmd, Cmd::IndentLines { .. });
+            let edits = quote_depth_edits(doc, lines, indent);
+            map_offset_through_edits(range.start, &edits, true)
+                ..map_offset_through_edits(range.end, &edits, range.is_empty())
+        }
         Cmd::IndentLines { .. } | Cmd::OutdentLines { .. } | Cmd::ToggleMarker { .. } => {
             // For line-based operations, the selection position might shift
             // but for now, keep it simple and leave unchanged
@@ -360,7 +649,11 @@ pub(crate) fn transform_selection_for_command(
 fn calculate_split_insert_length(doc: &Document, at: usize) -> usize {
     let line_start = find_line_start(doc, at);
     let line_text = get_line_at(doc, line_start);
-    let (indent, marker) = extract_list_info(&line_text);
+    let (indent, marker) = if in_raw_zone(doc, at) {
+        (None, None)
+    } else {
+        extract_list_info(&line_text)
+    };
 
     let mut len = 1; // newline
     if let Some(indent_str) = indent {
@@ -372,24 +665,123 @@ fn calculate_split_insert_length(doc: &Document, at: usize) -> usize {
     len
 }
 
+/// Whether an offset is inside a raw zone (fenced or indented code block),
+/// where list-structure commands must not rewrite lines.
+///
+/// A caret at the very end of an unterminated block at the end of the
+/// document still counts as inside it.
+pub(crate) fn in_raw_zone(doc: &Document, offset: usize) -> bool {
+    let Some(tree) = doc.tree() else {
+        return false;
+    };
+    let at_unterminated_end =
+        offset == doc.len() && offset > 0 && !doc.slice_to_cow(offset - 1..offset).ends_with('\n');
+
+    let mut node = tree.root_node();
+    loop {
+        if matches!(node.kind(), "fenced_code_block" | "indented_code_block") {
+            return true;
+        }
+        let mut cursor = node.walk();
+        let child = node.children(&mut cursor).find(|child| {
+            child.start_byte() <= offset
+                && (offset < child.end_byte()
+                    || (at_unterminated_end && offset == child.end_byte()))
+        });
+        match child {
+            Some(child) => node = child,
+            None => return false,
+        }
+    }
+}
+
+/// Whether the line containing `offset` is inside a blockquote, according
+/// to the syntax tree (so `>` inside code is not a quote).
+pub(crate) fn in_blockquote(doc: &Document, offset: usize) -> bool {
+    if in_raw_zone(doc, offset) {
+        return false;
+    }
+    let line_start = find_line_start(doc, offset.min(doc.len()));
+    let line_end = line_start + get_line_at(doc, line_start).len();
+    visit::nodes_of_kind(&parse(&doc.text()), SyntaxKind::BLOCK_QUOTE).any(|node| {
+        let start = usize::from(node.text_range().start());
+        line_start <= start && start <= line_end
+    })
+}
+
+/// Edits adding (`indent`) or removing one level of quote depth on each
+/// line overlapping `range`.
+///
+/// List items are indented after the `>` markers so they nest within the
+/// quote; other lines gain or lose their outermost `> `.
+fn quote_depth_edits(
+    doc: &Document,
+    range: &std::ops::Range<usize>,
+    indent: bool,
+) -> Vec<(std::ops::Range<usize>, String)> {
+    let text = doc.text();
+    let first_line = find_line_start(doc, range.start.min(text.len()));
+    let mut edits = Vec::new();
+
+    let mut line_start = first_line;
+    while line_start < text.len() || line_start == first_line {
+        let line_end = text[line_start..]
+            .find('\n')
+            .map_or(text.len(), |i| line_start + i);
+        let line = &text[line_start..line_end];
+        let prefix_len = quote_prefix_len(line);
+        let content = &line[prefix_len..];
+        let first_gt = line_start + line.len() - line.trim_start().len();
+        let (_, marker) = extract_list_info(content);
+
+        if indent {
+            if prefix_len > 0 && marker.is_some() {
+                let at = line_start + prefix_len;
+                edits.push((at..at, INDENT_STR.to_string()));
+            } else {
+                edits.push((first_gt..first_gt, "> ".to_string()));
+            }
+        } else if prefix_len > 0 && content.starts_with(' ') {
+            let spaces = content.len() - content.trim_start_matches(' ').len();
+            let at = line_start + prefix_len;
+            edits.push((at..at + spaces.min(INDENT_STR.len()), String::new()));
+        } else if line.trim_start().starts_with('>') {
+            let after = &text[first_gt + 1..line_end];
+            let len = if after.starts_with(' ') { 2 } else { 1 };
+            edits.push((first_gt..first_gt + len, String::new()));
+        }
+
+        if line_end >= range.end || line_end == text.len() {
+            break;
+        }
+        line_start = line_end + 1;
+    }
+    edits
+}
+
+/// Length of the `>` markers (each with an optional following space) at
+/// the start of a line, including leading indentation.
+fn quote_prefix_len(line: &str) -> usize {
+    let mut rest = line.trim_start_matches(' ');
+    if !rest.starts_with('>') {
+        return 0;
+    }
+    while let Some(after) = rest.strip_prefix('>') {
+        rest = after.strip_prefix(' ').unwrap_or(after);
+    }
+    line.len() - rest.len()
+}
+
 /// Find the start of the line containing the given offset
 pub(crate) fn find_line_start(doc: &Document, offset: usize) -> usize {
-    let text = doc.slice_to_cow(0..offset);
-    if let Some(newline_pos) = text.rfind('\n') {
-        newline_pos + 1
-    } else {
-        0
-    }
+    doc.offset_of_line(doc.line_of_offset(offset))
 }
 
 /// Get the text of the line starting at the given offset
 pub(crate) fn get_line_at(doc: &Document, line_start: usize) -> String {
-    let text = doc.slice_to_cow(line_start..doc.len());
-    if let Some(newline_pos) = text.find('\n') {
-        text[..newline_pos].to_string()
-    } else {
-        text.to_string()
-    }
+    let next_line = doc.offset_of_line(doc.line_of_offset(line_start) + 1);
+    let line = doc.slice_to_cow(line_start..next_line);
+    line.strip_suffix('\n').unwrap_or(&line).to_string()
 }
 
 /// Extract indentation and list marker from a line
@@ -421,6 +813,247 @@ pub(crate) fn extract_list_info(line: &str) -> (Option<String>, Option<String>)
     (indent, marker)
 }
 
+/// Compute the edits writing `numbering` into ordered list items that start
+/// on the lines covered by `range`.
+fn list_numbering_edits(
+    doc: &Document,
+    range: &std::ops::Range<usize>,
+    numbering: ListNumbering,
+) -> Vec<(std::ops::Range<usize>, String)> {
+    let first_line = find_line_start(doc, range.start.min(doc.len()));
+    let mut edits = Vec::new();
+
+    for item in doc.snapshot().numbered_items(numbering) {
+        let start = item.node_range.start;
+        if start < first_line || start > range.end {
+            continue;
+        }
+
+        let indent_len = item.marker.len() - item.marker.trim_start().len();
+        let digits_start = start + indent_len;
+        let digits_len = item
+            .marker
+            .trim_start()
+            .bytes()
+            .take_while(u8::is_ascii_digit)
+            .count();
+        let number = item.number.to_string();
+        if doc.slice_to_cow(digits_start..digits_start + digits_len) != number {
+            edits.push((digits_start..digits_start + digits_len, number.clone()));
+        }
+
+        if item.label != format!("{number}.") {
+            let content_start = item.content_start;
+            let prefix = format!("{} ", item.label);
+            let existing = doc.slice_to_cow(content_start..content_start + prefix.len());
+            if existing != prefix {
+                edits.push((content_start..content_start, prefix));
+            }
+        }
+    }
+
+    edits
+}
+
+/// Compute the minimal edits converting the block covering `range`.
+///
+/// Returns sorted, non-overlapping `(range, replacement)` pairs so the same
+/// edits drive both delta compilation and selection transformation.
+fn block_conversion_edits(
+    doc: &Document,
+    range: &std::ops::Range<usize>,
+    to: BlockConversion,
+) -> Vec<(std::ops::Range<usize>, String)> {
+    let lines = lines_in_range(doc, range);
+    let Some(&(first_start, _)) = lines.first() else {
+        return Vec::new();
+    };
+    let first_line = get_line_at(doc, first_start);
+    let is_fenced = is_fence_line(&first_line);
+
+    match to {
+        BlockConversion::FencedCode => {
+            if is_fenced {
+                return Vec::new();
+            }
+            let mut edits = vec![(first_start..first_start, "```\n".to_string())];
+            for &(start, ref line) in &lines {
+                let strip = code_indent_len(line);
+                if strip > 0 {
+                    edits.push((start..start + strip, String::new()));
+                }
+            }
+            let &(last_start, ref last_line) = lines.last().unwrap();
+            let last_end = last_start + last_line.len();
+            if last_end < doc.len() {
+                // Line has a trailing newline - add the closing fence after it
+                edits.push((last_end + 1..last_end + 1, "```\n".to_string()));
+            } else {
+                edits.push((last_end..last_end, "\n```".to_string()));
+            }
+            edits
+        }
+        BlockConversion::IndentedCode => {
+            let indent = |lines: &[(usize, String)]| -> Vec<_> {
+                lines
+                    .iter()
+                    .filter(|(_, line)| !line.is_empty() && code_indent_len(line) == 0)
+                    .map(|&(start, _)| (start..start, CODE_INDENT_STR.to_string()))
+                    .collect()
+            };
+            if !is_fenced {
+                return indent(&lines);
+            }
+
+            // Drop the fence lines (including their newlines) and indent the rest
+            let line_span =
+                |(start, line): &(usize, String)| *start..(start + line.len() + 1).min(doc.len());
+            let mut content = &lines[1..];
+            let closing = match content.split_last() {
+                Some((last, rest)) if is_fence_line(&last.1) => {
+                    content = rest;
+                    Some(last)
+                }
+                _ => None,
+            };
+
+            let mut edits = vec![(line_span(&lines[0]), String::new())];
+            edits.extend(indent(content));
+            if let Some(closing) = closing {
+                edits.push((line_span(closing), String::new()));
+            }
+            edits
+        }
+        BlockConversion::Paragraph
+        | BlockConversion::Heading(_)
+        | BlockConversion::Bullet
+        | BlockConversion::Quote => {
+            let mut edits = Vec::new();
+            for (i, (start, line)) in lines.iter().enumerate() {
+                let is_first = i == 0;
+                let trimmed = line.trim_start();
+                let indent_len = line.len() - trimmed.len();
+                if trimmed.is_empty() {
+                    continue;
+                }
+
+                let old_len = block_prefix_len(trimmed, is_first);
+                let new_prefix = match to {
+                    BlockConversion::Heading(level) if is_first => {
+                        format!("{} ", "#".repeat(level.clamp(1, 6) as usize))
+                    }
+                    BlockConversion::Bullet if is_first => "- ".to_string(),
+                    BlockConversion::Quote => "> ".to_string(),
+                    _ => String::new(),
+                };
+
+                if trimmed[..old_len] != new_prefix {
+                    let prefix_start = start + indent_len;
+                    edits.push((prefix_start..prefix_start + old_len, new_prefix));
+                }
+            }
+            edits
+        }
+    }
+}
+
+/// Lines (start offset, text without newline) starting within `range`.
+/// Always includes the line containing `range.start`.
+fn lines_in_range(doc: &Document, range: &std::ops::Range<usize>) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut line_start = find_line_start(doc, range.start.min(doc.len()));
+    loop {
+        let line = get_line_at(doc, line_start);
+        let next = line_start + line.len() + 1;
+        lines.push((line_start, line));
+        if next >= range.end || next > doc.len() {
+            break;
+        }
+        line_start = next;
+    }
+    lines
+}
+
+/// Length of the block prefix at the start of an already-unindented line:
+/// a quote marker on any line, or a heading/list marker on the first line.
+fn block_prefix_len(trimmed: &str, is_first: bool) -> usize {
+    if let Some(rest) = trimmed.strip_prefix('>') {
+        return 1 + usize::from(rest.starts_with(' '));
+    }
+    if !is_first {
+        return 0;
+    }
+
+    let hashes = trimmed.chars().take_while(|&c| c == '#').count();
+    if (1..=6).contains(&hashes) {
+        let rest = &trimmed[hashes..];
+        if rest.is_empty() {
+            return hashes;
+        }
+        if rest.starts_with(' ') {
+            return hashes + 1;
+        }
+    }
+
+    if let (_, Some(marker)) = extract_list_info(trimmed)
+        && (marker.len() == 1
+            || marker[..marker.len() - 1]
+                .chars()
+                .all(|c| c.is_ascii_digit()))
+    {
+        return marker.len() + 1;
+    }
+    if trimmed.starts_with(['-', '*', '+']) && trimmed.len() == 1 {
+        return 1;
+    }
+    0
+}
+
+fn is_fence_line(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("```") || trimmed.starts_with("~~~")
+}
+
+/// Bytes of code indentation (4 spaces or a tab) at the start of a line
+fn code_indent_len(line: &str) -> usize {
+    if line.starts_with(CODE_INDENT_STR) {
+        CODE_INDENT_STR.len()
+    } else if line.starts_with('\t') {
+        1
+    } else {
+        0
+    }
+}
+
+/// Map an offset through sorted edits, as xi-rope would transform it.
+///
+/// Offsets strictly inside a replaced span move to the end of its
+/// replacement, so a caret inside an old prefix lands at the start of the
+/// block content. Insertions exactly at the offset shift it only when
+/// `after_insert` is set (caret and selection start, not selection end).
+pub(crate) fn map_offset_through_edits(
+    offset: usize,
+    edits: &[(std::ops::Range<usize>, String)],
+    after_insert: bool,
+) -> usize {
+    let mut shift: isize = 0;
+    for (range, text) in edits {
+        let before = if range.is_empty() {
+            range.start < offset || (range.start == offset && after_insert)
+        } else {
+            range.end <= offset
+        };
+        if before {
+            shift += text.len() as isize - range.len() as isize;
+        } else if range.start < offset {
+            return (range.start as isize + shift) as usize + text.len();
+        } else {
+            break;
+        }
+    }
+    (offset as isize + shift) as usize
+}
+
 /// Modify line starts within a range
 fn modify_line_starts(
     doc: &Document,
@@ -428,23 +1061,16 @@ fn modify_line_starts(
     modifier: impl Fn(&str) -> Option<String>,
 ) -> Delta<RopeInfo> {
     let mut builder = Builder::new(doc.len());
-    let text = doc.text();
 
-    // Split text into lines while tracking positions
-    let mut lines = Vec::new();
-    let mut line_start = 0;
-    for (i, ch) in text.char_indices() {
-        if ch == '\n' {
-            lines.push((line_start, i));
-            line_start = i + 1;
+    // Only the lines the range touches, found through the line index
+    let first_line = doc.line_of_offset(range.start);
+    let last_line = doc.line_of_offset(range.end);
+    for line_start in (first_line..=last_line).map(|line| doc.offset_of_line(line)) {
+        if line_start >= doc.len() {
+            break;
         }
-    }
-    if line_start < text.len() {
-        lines.push((line_start, text.len()));
-    }
-
-    for (line_start, line_end) in lines {
-        let line = &text[line_start..line_end];
+        let line = &get_line_at(doc, line_start);
+        let line_end = line_start + line.len();
 
         // Check if this line overlaps with the range
         if line_start < range.end
@@ -540,6 +1166,23 @@ mod tests {
         assert_eq!(patch.changed, vec![6..20]);
     }
 
+    #[test]
+    fn test_insert_date() {
+        let mut doc = Document::from_bytes(b"Due: ").unwrap();
+        doc.set_selection(5..5);
+
+        let patch = doc.apply(Cmd::InsertDate {
+            at: 5,
+            spec: DateSpec {
+                date: jiff::civil::date(2024, 3, 31),
+                format: crate::dates::DateFormat::JournalLink,
+            },
+        });
+
+        assert_eq!(doc.text(), "Due: [[2024-03-31]]");
+        assert_eq!(patch.new_selection, 19..19);
+    }
+
     // ============ DeleteRange command tests ============
 
     #[test]
@@ -721,6 +1364,57 @@ mod tests {
         assert_eq!(doc.text(), "- Item 1\n- Item 2\n  - Item 3");
     }
 
+    // ============ Quote-aware indent/outdent tests ============
+
+    #[test]
+    fn test_indent_in_quote_adds_level() {
+        let mut doc = Document::from_bytes(b"> One\n> Two\n\nAfter\n").unwrap();
+
+        doc.apply(Cmd::IndentLines { range: 2..9 });
+
+        assert_eq!(doc.text(), "> > One\n> > Two\n\nAfter\n");
+    }
+
+    #[test]
+    fn test_outdent_in_quote_removes_outer_level() {
+        let mut doc = Document::from_bytes(b"> > Deep\n> Shallow\n").unwrap();
+
+        doc.apply(Cmd::OutdentLines { range: 0..17 });
+
+        assert_eq!(doc.text(), "> Deep\nShallow\n");
+    }
+
+    #[test]
+    fn test_quoted_list_items_indent_after_markers() {
+        let mut doc = Document::from_bytes(b"> - a\n> - b\n").unwrap();
+
+        doc.apply(Cmd::IndentLines { range: 8..9 });
+        assert_eq!(doc.text(), "> - a\n>   - b\n");
+
+        doc.apply(Cmd::OutdentLines { range: 10..11 });
+        assert_eq!(doc.text(), "> - a\n> - b\n");
+    }
+
+    #[test]
+    fn test_quote_indent_moves_selection_with_content() {
+        let mut doc = Document::from_bytes(b"> Text\n").unwrap();
+        doc.set_selection(4..4);
+
+        let patch = doc.apply(Cmd::IndentLines { range: 4..4 });
+
+        assert_eq!(doc.text(), "> > Text\n");
+        assert_eq!(patch.new_selection, 6..6);
+    }
+
+    #[test]
+    fn test_quote_marker_in_code_is_not_a_quote() {
+        let mut doc = Document::from_bytes(b"```\n> not quote\n```\n").unwrap();
+
+        doc.apply(Cmd::OutdentLines { range: 4..5 });
+
+        assert_eq!(doc.text(), "```\n> not quote\n```\n");
+    }
+
     // ============ ToggleMarker command tests ============
 
     #[test]
@@ -819,6 +1513,279 @@ mod tests {
         assert_eq!(doc.text(), "  - Plain text");
     }
 
+    // ============ ConvertBlock command tests ============
+
+    fn convert(text: &str, selection: std::ops::Range<usize>, to: BlockConversion) -> Document {
+        let mut doc = Document::from_bytes(text.as_bytes()).unwrap();
+        doc.set_selection(selection);
+        let range = 0..text.len();
+        let patch = doc.apply(Cmd::ConvertBlock { range, to });
+        assert_eq!(patch.new_selection, doc.selection());
+        doc
+    }
+
+    #[test]
+    fn test_convert_paragraph_to_heading() {
+        let doc = convert("Hello world\n", 6..11, BlockConversion::Heading(2));
+        assert_eq!(doc.text(), "## Hello world\n");
+        assert_eq!(doc.slice(doc.selection()), "world");
+    }
+
+    #[test]
+    fn test_convert_heading_to_paragraph() {
+        let doc = convert("### Title\n", 6..6, BlockConversion::Paragraph);
+        assert_eq!(doc.text(), "Title\n");
+        assert_eq!(doc.selection(), 2..2);
+    }
+
+    #[test]
+    fn test_convert_heading_level() {
+        let doc = convert("# Title\n", 4..4, BlockConversion::Heading(3));
+        assert_eq!(doc.text(), "### Title\n");
+        assert_eq!(doc.selection(), 6..6);
+    }
+
+    #[test]
+    fn test_convert_heading_keeps_hash_in_content() {
+        let doc = convert("#hashtag here\n", 0..0, BlockConversion::Bullet);
+        assert_eq!(doc.text(), "- #hashtag here\n");
+    }
+
+    #[test]
+    fn test_convert_paragraph_to_bullet_and_back() {
+        let doc = convert("Some text\nwrapped\n", 5..9, BlockConversion::Bullet);
+        assert_eq!(doc.text(), "- Some text\nwrapped\n");
+        assert_eq!(doc.slice(doc.selection()), "text");
+
+        let doc = convert("- Some text\n", 7..11, BlockConversion::Paragraph);
+        assert_eq!(doc.text(), "Some text\n");
+        assert_eq!(doc.slice(doc.selection()), "text");
+    }
+
+    #[test]
+    fn test_convert_bullet_to_quote() {
+        let doc = convert("- Quoted\n", 4..4, BlockConversion::Quote);
+        assert_eq!(doc.text(), "> Quoted\n");
+        assert_eq!(doc.selection(), 4..4);
+    }
+
+    #[test]
+    fn test_convert_quote_to_bullet_multiline() {
+        let doc = convert("> one\n> two\n", 8..11, BlockConversion::Bullet);
+        assert_eq!(doc.text(), "- one\ntwo\n");
+        assert_eq!(doc.slice(doc.selection()), "two");
+    }
+
+    #[test]
+    fn test_convert_numbered_to_bullet() {
+        let doc = convert("12. Item\n", 4..8, BlockConversion::Bullet);
+        assert_eq!(doc.text(), "- Item\n");
+        assert_eq!(doc.slice(doc.selection()), "Item");
+    }
+
+    #[test]
+    fn test_convert_sentence_with_number_is_not_a_list() {
+        let doc = convert("2024 was fine. Then\n", 0..0, BlockConversion::Bullet);
+        assert_eq!(doc.text(), "- 2024 was fine. Then\n");
+    }
+
+    #[test]
+    fn test_convert_caret_inside_prefix_moves_to_content() {
+        let doc = convert("## Title\n", 1..1, BlockConversion::Paragraph);
+        assert_eq!(doc.text(), "Title\n");
+        assert_eq!(doc.selection(), 0..0);
+    }
+
+    #[test]
+    fn test_convert_only_changed_prefix_is_edited() {
+        let mut doc = Document::from_bytes(b"- item\n").unwrap();
+        let patch = doc.apply(Cmd::ConvertBlock {
+            range: 0..7,
+            to: BlockConversion::Bullet,
+        });
+        assert_eq!(doc.text(), "- item\n");
+        assert!(patch.changed.is_empty());
+    }
+
+    #[test]
+    fn test_convert_fenced_to_indented_code() {
+        let doc = convert(
+            "```rust\nlet x = 1;\n\nx\n```\n",
+            12..13,
+            BlockConversion::IndentedCode,
+        );
+        assert_eq!(doc.text(), "    let x = 1;\n\n    x\n");
+        assert_eq!(doc.slice(doc.selection()), "x");
+    }
+
+    #[test]
+    fn test_convert_unclosed_fence_to_indented_code() {
+        let doc = convert("```\ncode", 4..8, BlockConversion::IndentedCode);
+        assert_eq!(doc.text(), "    code");
+        assert_eq!(doc.slice(doc.selection()), "code");
+    }
+
+    #[test]
+    fn test_convert_indented_to_fenced_code() {
+        let doc = convert("    a\n    b\n", 10..11, BlockConversion::FencedCode);
+        assert_eq!(doc.text(), "```\na\nb\n```\n");
+        assert_eq!(doc.slice(doc.selection()), "b");
+    }
+
+    #[test]
+    fn test_convert_to_fenced_without_trailing_newline() {
+        let doc = convert("code", 0..4, BlockConversion::FencedCode);
+        assert_eq!(doc.text(), "```\ncode\n```");
+        assert_eq!(doc.slice(doc.selection()), "code");
+    }
+
+    #[test]
+    fn test_convert_only_touches_lines_in_range() {
+        let mut doc = Document::from_bytes(b"first\n\nsecond\n").unwrap();
+        doc.set_selection(13..13);
+        let patch = doc.apply(Cmd::ConvertBlock {
+            range: 0..6,
+            to: BlockConversion::Heading(1),
+        });
+        assert_eq!(doc.text(), "# first\n\nsecond\n");
+        assert_eq!(patch.new_selection, 15..15);
+    }
+
+    // ============ MaterializeListNumbering command tests ============
+
+    fn materialize(text: &str, numbering: ListNumbering) -> Document {
+        let mut doc = Document::from_bytes(text.as_bytes()).unwrap();
+        let patch = doc.apply(Cmd::MaterializeListNumbering {
+            range: 0..text.len(),
+            numbering,
+        });
+        assert_eq!(patch.new_selection, doc.selection());
+        doc
+    }
+
+    #[test]
+    fn test_materialize_source_renumbers_sequentially() {
+        let doc = materialize("1. One\n1. Two\n1. Three\n", ListNumbering::Source);
+        assert_eq!(doc.text(), "1. One\n2. Two\n3. Three\n");
+    }
+
+    #[test]
+    fn test_materialize_nested_writes_labels_into_text() {
+        let text = "1. One\n   1. Sub\n   1. Sub\n2. Two\n";
+        let doc = materialize(text, ListNumbering::Nested);
+        assert_eq!(doc.text(), "1. One\n   1. a. Sub\n   2. b. Sub\n2. Two\n");
+
+        // Applying again changes nothing
+        let again = materialize(&doc.text(), ListNumbering::Nested);
+        assert_eq!(again.text(), doc.text());
+    }
+
+    #[test]
+    fn test_materialize_only_touches_items_in_range() {
+        let text = "1. One\n1. Two\n1. Three\n";
+        let mut doc = Document::from_bytes(text.as_bytes()).unwrap();
+        doc.apply(Cmd::MaterializeListNumbering {
+            range: 9..10,
+            numbering: ListNumbering::Outline,
+        });
+        assert_eq!(doc.text(), "1. One\n2. Two\n1. Three\n");
+    }
+
+    #[test]
+    fn test_materialize_keeps_selection_on_content() {
+        let text = "1. Sub\n   1. Item\n";
+        let mut doc = Document::from_bytes(text.as_bytes()).unwrap();
+        doc.set_selection(13..17); // "Item"
+        doc.apply(Cmd::MaterializeListNumbering {
+            range: 0..text.len(),
+            numbering: ListNumbering::Nested,
+        });
+        assert_eq!(doc.text(), "1. Sub\n   1. a. Item\n");
+        assert_eq!(doc.slice(doc.selection()), "Item");
+    }
+
+    // ============ Raw zone (code block) guard tests ============
+
+    #[test]
+    fn test_split_list_item_inside_fence_is_plain_newline() {
+        let text = "- item\n\n```\n- not a list\n```\n";
+        let mut doc = Document::from_bytes(text.as_bytes()).unwrap();
+        let at = text.find("- not").unwrap() + "- not".len();
+        doc.set_selection(at..at);
+
+        let patch = doc.apply(Cmd::SplitListItem { at });
+
+        assert_eq!(doc.text(), "- item\n\n```\n- not\n a list\n```\n");
+        assert_eq!(patch.new_selection, at + 1..at + 1);
+    }
+
+    #[test]
+    fn test_split_list_item_in_unclosed_fence_at_end() {
+        let mut doc = Document::from_bytes(b"```\n- code").unwrap();
+        let _patch = doc.apply(Cmd::SplitListItem { at: 10 });
+        assert_eq!(doc.text(), "```\n- code\n");
+    }
+
+    #[test]
+    fn test_split_list_item_after_fence_still_continues_list() {
+        let text = "```\ncode\n```\n- item";
+        let mut doc = Document::from_bytes(text.as_bytes()).unwrap();
+        let _patch = doc.apply(Cmd::SplitListItem { at: text.len() });
+        assert_eq!(doc.text(), "```\ncode\n```\n- item\n- ");
+    }
+
+    #[test]
+    fn test_indent_inside_fence_inserts_at_cursor() {
+        let text = "```\nfoo bar\n```\n";
+        let mut doc = Document::from_bytes(text.as_bytes()).unwrap();
+        doc.set_selection(8..8);
+
+        let patch = doc.apply(Cmd::IndentLines { range: 8..8 });
+
+        assert_eq!(doc.text(), "```\nfoo   bar\n```\n");
+        assert_eq!(patch.new_selection, 10..10);
+    }
+
+    #[test]
+    fn test_outdent_inside_fence_is_noop() {
+        let text = "```\n  code\n```\n";
+        let mut doc = Document::from_bytes(text.as_bytes()).unwrap();
+        let _patch = doc.apply(Cmd::OutdentLines { range: 6..6 });
+        assert_eq!(doc.text(), text);
+    }
+
+    #[test]
+    fn test_toggle_marker_inside_fence_is_noop() {
+        let text = "```\n* code\n```\n";
+        let mut doc = Document::from_bytes(text.as_bytes()).unwrap();
+        let _patch = doc.apply(Cmd::ToggleMarker {
+            line_start: 4,
+            to: Marker::Dash,
+        });
+        assert_eq!(doc.text(), text);
+    }
+
+    #[test]
+    fn test_toggle_marker_inside_indented_code_is_noop() {
+        let text = "para\n\n    * code\n";
+        let mut doc = Document::from_bytes(text.as_bytes()).unwrap();
+        let _patch = doc.apply(Cmd::ToggleMarker {
+            line_start: 6,
+            to: Marker::Dash,
+        });
+        assert_eq!(doc.text(), text);
+    }
+
+    #[test]
+    fn test_indent_range_starting_outside_code_indents_all_lines() {
+        let text = "- item\n  ```\n  code\n  ```\n";
+        let mut doc = Document::from_bytes(text.as_bytes()).unwrap();
+        let _patch = doc.apply(Cmd::IndentLines {
+            range: 0..text.len(),
+        });
+        assert_eq!(doc.text(), "  - item\n    ```\n    code\n    ```\n");
+    }
+
     // ============ Selection transformation tests ============
 
     #[test]
@@ -0,0 +1,172 @@
+//! Word, character and byte counts for blocks and selections.
+//!
+//! Counting streams over rope chunks, so a count never copies the counted
+//! text into a new string. That keeps it cheap enough to run on every
+//! keystroke for a live editor footer.
+
+use std::ops::Range;
+
+use crate::editing::{Block, Document};
+
+/// Counts for a span of text.
+///
+/// A word is a whitespace-separated run containing at least one letter or
+/// digit, so Markdown punctuation such as `-` bullets, `#` heading markers
+/// and `>` quote markers is not counted as words.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextCounts {
+    pub words: usize,
+    /// Unicode scalar values
+    pub chars: usize,
+    /// UTF-8 bytes
+    pub bytes: usize,
+}
+
+impl TextCounts {
+    /// Count a string, e.g. the live contents of an editor textarea.
+    pub fn of_str(text: &str) -> Self {
+        let mut counter = Counter::default();
+        counter.feed(text);
+        counter.finish()
+    }
+}
+
+/// Streaming counter that carries word state across chunk boundaries.
+#[derive(Default)]
+struct Counter {
+    counts: TextCounts,
+    in_word: bool,
+    word_has_alphanumeric: bool,
+}
+
+impl Counter {
+    fn feed(&mut self, chunk: &str) {
+        self.counts.bytes += chunk.len();
+        for c in chunk.chars() {
+            self.counts.chars += 1;
+            if c.is_whitespace() {
+                self.end_word();
+            } else {
+                self.in_word = true;
+                self.word_has_alphanumeric |= c.is_alphanumeric();
+            }
+        }
+    }
+
+    fn end_word(&mut self) {
+        if self.in_word && self.word_has_alphanumeric {
+            self.counts.words += 1;
+        }
+        self.in_word = false;
+        self.word_has_alphanumeric = false;
+    }
+
+    fn finish(mut self) -> TextCounts {
+        self.end_word();
+        self.counts
+    }
+}
+
+impl Document {
+    /// Counts for a byte range of the document (clamped to the document).
+    pub fn counts(&self, range: Range<usize>) -> TextCounts {
+        let len = self.buffer.len();
+        let start = range.start.min(len);
+        let end = range.end.min(len).max(start);
+
+        let mut counter = Counter::default();
+        for chunk in self.buffer.iter_chunks(start..end) {
+            counter.feed(chunk);
+        }
+        counter.finish()
+    }
+
+    /// Counts for the whole document.
+    pub fn document_counts(&self) -> TextCounts {
+        self.counts(0..self.buffer.len())
+    }
+
+    /// Counts for the current selection.
+    pub fn selection_counts(&self) -> TextCounts {
+        self.counts(self.selection.clone())
+    }
+
+    /// Counts for a block's own content, excluding nested child blocks.
+    pub fn block_counts(&self, block: &Block) -> TextCounts {
+        self.counts(block.content_range())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(words: usize, chars: usize, bytes: usize) -> TextCounts {
+        TextCounts {
+            words,
+            chars,
+            bytes,
+        }
+    }
+
+    #[test]
+    fn test_of_str_counts_words_chars_bytes() {
+        assert_eq!(TextCounts::of_str(""), TextCounts::default());
+        assert_eq!(TextCounts::of_str("hello world"), counts(2, 11, 11));
+        assert_eq!(TextCounts::of_str("  café\tnaïve\n"), counts(2, 13, 15));
+    }
+
+    #[test]
+    fn test_markdown_punctuation_is_not_a_word() {
+        assert_eq!(TextCounts::of_str("# Title").words, 1);
+        assert_eq!(TextCounts::of_str("- item one\n  - item two").words, 4);
+        assert_eq!(TextCounts::of_str("> quoted -- text").words, 2);
+    }
+
+    #[test]
+    fn test_counts_match_across_chunk_boundaries() {
+        // Large enough for the rope to split into several chunks
+        let text = "lorem ipsum dolor sit amet, ünïcode wörds\n".repeat(500);
+        let doc = Document::from_bytes(text.as_bytes()).unwrap();
+
+        assert_eq!(doc.document_counts(), TextCounts::of_str(&text));
+        assert_eq!(doc.document_counts().words, 3500);
+    }
+
+    #[test]
+    fn test_range_is_clamped() {
+        let doc = Document::from_bytes(b"one two").unwrap();
+
+        assert_eq!(doc.counts(4..100), counts(1, 3, 3));
+        assert_eq!(doc.counts(50..100), TextCounts::default());
+    }
+
+    #[test]
+    fn test_selection_counts() {
+        let mut doc = Document::from_bytes(b"alpha beta gamma").unwrap();
+        doc.set_selection(6..16);
+
+        assert_eq!(doc.selection_counts(), counts(2, 10, 10));
+    }
+
+    #[test]
+    fn test_block_counts_exclude_children() {
+        let doc =
+            Document::from_bytes(b"# Heading here\n\n- parent item\n  - child item\n").unwrap();
+        let snapshot = doc.snapshot();
+
+        let heading = &snapshot.blocks[0];
+        assert_eq!(doc.block_counts(heading).words, 2);
+
+        let list_item = snapshot
+            .blocks
+            .iter()
+            .flat_map(|b| match &b.content {
+                crate::editing::BlockContent::Children(children) => children.clone(),
+                crate::editing::BlockContent::Leaf => vec![],
+            })
+            .find(|b| doc.slice(b.node_range.clone()).starts_with("- parent"))
+            .expect("parent list item");
+        assert_eq!(doc.block_counts(&list_item).words, 2);
+    }
+}
@@ -1,8 +1,15 @@
+use std::collections::HashSet;
+
 use tree_sitter::{Parser, Tree};
 use tree_sitter_md::LANGUAGE;
-use xi_rope::{Delta, Rope, RopeInfo};
+use xi_rope::rope::Utf16CodeUnitsMetric;
+use xi_rope::{Cursor, Delta, LinesMetric, Rope, RopeInfo};
 
-use crate::editing::{Anchor, Cmd, Patch};
+use crate::editing::history::{History, changes_text};
+use crate::editing::move_block::BlockMove;
+use crate::editing::observe::Observers;
+use crate::editing::{Anchor, AnchorId, Cmd, Patch};
+use crate::trace::timed;
 
 /// Marker types for list items
 #[derive(Debug, Clone, PartialEq)]
@@ -79,7 +86,7 @@ impl IndentStyle {
 /// - **Edit algebra**: All changes flow through `Cmd` enum compilation
 /// - **Atomic operations**: Commands compile to Deltas and apply immediately
 /// - **Selection tracking**: Cursor/selection positions transform automatically
-/// - **Undo foundation**: Delta history enables branching undo (future)
+/// - **Undo/redo**: The state before each edit is kept for `undo()`/`redo()`
 ///
 /// ## Usage Pattern
 ///
@@ -118,11 +125,27 @@ pub struct Document {
     pub(crate) anchors: Vec<Anchor>,
     /// Document's indentation style (spaces vs tabs, detected on load)
     pub(crate) indent_style: IndentStyle,
+    /// Undo/redo stacks (see [`crate::editing::history`])
+    pub(crate) history: History,
+    /// Folded blocks, shown as `Block::collapsed` in snapshots
+    pub(crate) collapsed: HashSet<AnchorId>,
+    /// Subscribers to snapshots (see [`crate::editing::observe`])
+    pub(crate) observers: Observers,
+    /// Edits are refused (see [`Document::set_read_only`])
+    pub(crate) read_only: bool,
+    /// Block being written in focus mode (see [`crate::editing::focus`])
+    pub(crate) active_block: Option<AnchorId>,
 }
 
+/// An edit was refused because the document is read-only.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Document is read-only")]
+pub struct ReadOnlyError;
+
 impl Document {
     /// Create a new document from raw bytes
     pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let _timed = timed(tracing::debug_span!("parse", bytes = bytes.len()));
         // Convert bytes to string, ensuring valid UTF-8
         let text = std::str::from_utf8(bytes)?;
         let buffer = Rope::from(text);
@@ -136,7 +159,7 @@ impl Document {
         parser.set_language(&LANGUAGE.into())?;
 
         // Initial parse of the document
-        let tree = parser.parse(buffer.to_string(), None);
+        let tree = parse_rope(&mut parser, &buffer, None);
 
         let mut doc = Self {
             buffer,
@@ -146,6 +169,11 @@ impl Document {
             tree,
             anchors: Vec::new(),
             indent_style,
+            history: History::default(),
+            collapsed: HashSet::new(),
+            observers: Observers::default(),
+            read_only: false,
+            active_block: None,
         };
 
         // Initialize anchors from the parse tree for stable block IDs
@@ -154,7 +182,8 @@ impl Document {
         Ok(doc)
     }
 
-    /// Get the document's content as raw bytes (exact round-trip)
+    /// Get the document's content as raw bytes (exact round-trip).
+    /// Copies the whole document; use [`Self::chunks`] to stream it.
     pub fn to_bytes(&self) -> Vec<u8> {
         self.buffer.to_string().into_bytes()
     }
@@ -197,9 +226,32 @@ impl Document {
     /// // Document buffer updated, anchors stable, version incremented
     /// assert_eq!(patch.version, doc.version());
     /// ```
+    ///
+    /// A [read-only](Self::set_read_only) document is left unchanged and the
+    /// patch is empty; use [`try_apply`](Self::try_apply) to find out.
     pub fn apply(&mut self, cmd: Cmd) -> Patch {
+        if self.read_only {
+            return Patch {
+                changed: Vec::new(),
+                new_selection: self.selection.clone(),
+                version: self.version,
+            };
+        }
+        let _timed = timed(tracing::debug_span!("apply", bytes = self.buffer.len()));
         // Build delta from command
         let delta = self.compile_command(&cmd);
+        let before = changes_text(&delta).then(|| self.history_state());
+
+        // Transform selection through command, against the pre-edit buffer
+        let new_selection = self.transform_selection_for_command(&self.selection, &cmd);
+
+        // Moved anchors keep their IDs, which rebinding by position loses
+        let block_move = match &cmd {
+            Cmd::MoveBlock { anchor_id, to } => {
+                BlockMove::plan(self, *anchor_id, *to).map(|plan| (plan, self.anchors.clone()))
+            }
+            _ => None,
+        };
 
         // Track changed ranges for the patch
         let mut changed = Vec::new();
@@ -232,11 +284,11 @@ impl Document {
             // NOW apply delta to buffer after we've calculated the edits
             self.buffer = delta.apply(&self.buffer);
 
-            self.tree = self.parser.parse(self.buffer.to_string(), Some(&old_tree));
+            self.tree = parse_rope(&mut self.parser, &self.buffer, Some(&old_tree));
         } else {
             // No old tree, do full parse - apply delta first in this case
             self.buffer = delta.apply(&self.buffer);
-            self.tree = self.parser.parse(self.buffer.to_string(), None);
+            self.tree = parse_rope(&mut self.parser, &self.buffer, None);
         }
 
         // Check if we need to create anchors for a completely new document
@@ -256,13 +308,20 @@ impl Document {
             self.create_anchors_for_new_blocks();
         }
 
-        // Transform selection through command
-        let new_selection = self.transform_selection_for_command(&self.selection, &cmd);
+        if let Some((plan, anchors)) = block_move {
+            plan.restore_anchor_ids(self, &anchors);
+        }
+
         self.selection = new_selection.clone();
 
         // Increment version
         self.version += 1;
 
+        if let Some(before) = before {
+            self.history.record(before, &cmd);
+        }
+        self.notify_changed();
+
         Patch {
             changed,
             new_selection,
@@ -270,6 +329,51 @@ impl Document {
         }
     }
 
+    /// Like [`apply`](Self::apply), failing if the document is read-only.
+    pub fn try_apply(&mut self, cmd: Cmd) -> Result<Patch, ReadOnlyError> {
+        if self.read_only {
+            return Err(ReadOnlyError);
+        }
+        Ok(self.apply(cmd))
+    }
+
+    /// Refuse edits from now on, e.g. for a note in a vault opened
+    /// [read-only](crate::vault::OpenMode::ReadOnly), or allow them again.
+    /// Folding, selection and snapshots still work.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Fold or unfold the block with `anchor_id`. The fold follows the
+    /// anchor through edits; see [`Snapshot::visible_blocks`](crate::editing::Snapshot::visible_blocks).
+    pub fn set_collapsed(&mut self, anchor_id: AnchorId, collapsed: bool) {
+        if collapsed {
+            self.collapsed.insert(anchor_id);
+        } else {
+            self.collapsed.remove(&anchor_id);
+        }
+    }
+
+    /// Whether the block with `anchor_id` is folded.
+    pub fn is_collapsed(&self, anchor_id: AnchorId) -> bool {
+        self.collapsed.contains(&anchor_id)
+    }
+
+    /// Folded blocks that still exist, in document order.
+    pub fn collapsed_anchors(&self) -> Vec<&Anchor> {
+        let mut anchors: Vec<_> = self
+            .anchors
+            .iter()
+            .filter(|anchor| self.collapsed.contains(&anchor.id))
+            .collect();
+        anchors.sort_by_key(|anchor| anchor.range.start);
+        anchors
+    }
+
     /// Get the current selection range
     pub fn selection(&self) -> std::ops::Range<usize> {
         self.selection.clone()
@@ -295,11 +399,20 @@ impl Document {
         self.version
     }
 
-    /// Get the current text content
+    /// Get the current text content. Copies the whole document; prefer
+    /// [`Self::chunks`] or [`Self::slice_to_cow`] where a borrow will do.
     pub fn text(&self) -> String {
         self.buffer.to_string()
     }
 
+    /// `range` of the document (clamped to its length) as the rope's own
+    /// chunks, in order, without copying
+    pub fn chunks(&self, range: std::ops::Range<usize>) -> impl Iterator<Item = &str> {
+        let start = range.start.min(self.buffer.len());
+        let end = range.end.min(self.buffer.len()).max(start);
+        self.buffer.iter_chunks(start..end)
+    }
+
     /// Get a slice of the document as a string (for raw editing)
     pub fn slice(&self, range: std::ops::Range<usize>) -> String {
         self.slice_to_cow(range).to_string()
@@ -310,8 +423,84 @@ impl Document {
         self.buffer.len()
     }
 
-    /// Slice the buffer to a cow string
-    pub(crate) fn slice_to_cow(&self, range: std::ops::Range<usize>) -> std::borrow::Cow<'_, str> {
+    // Line and UTF-16 lookups below use the counts the rope keeps in each
+    // tree node, which applying a delta updates along with the text, so
+    // they're O(log n) without a separate index to keep in sync.
+
+    /// Number of lines, counting the (possibly empty) line after a final
+    /// newline
+    pub fn line_count(&self) -> usize {
+        self.buffer.measure::<LinesMetric>() + 1
+    }
+
+    /// Zero-based line containing `offset` (clamped to the document)
+    pub fn line_of_offset(&self, offset: usize) -> usize {
+        self.buffer.line_of_offset(offset.min(self.buffer.len()))
+    }
+
+    /// Byte offset where `line` starts, or the document length past the
+    /// last line
+    pub fn offset_of_line(&self, line: usize) -> usize {
+        if line >= self.line_count() {
+            self.buffer.len()
+        } else {
+            self.buffer.offset_of_line(line)
+        }
+    }
+
+    /// The UTF-16 offset of byte `offset`, or None if it's past the end or
+    /// inside a character
+    pub fn utf16_of_offset(&self, offset: usize) -> Option<usize> {
+        (offset <= self.buffer.len() && self.buffer.is_codepoint_boundary(offset))
+            .then(|| self.buffer.count::<Utf16CodeUnitsMetric>(offset))
+    }
+
+    /// The byte offset of UTF-16 offset `utf16`, or None if it's past the
+    /// end or inside a surrogate pair
+    pub fn offset_of_utf16(&self, utf16: usize) -> Option<usize> {
+        if utf16 > self.buffer.measure::<Utf16CodeUnitsMetric>() {
+            return None;
+        }
+        // Offsets inside a pair round up to the end of the character
+        let offset = self.buffer.count_base_units::<Utf16CodeUnitsMetric>(utf16);
+        (self.buffer.count::<Utf16CodeUnitsMetric>(offset) == utf16).then_some(offset)
+    }
+
+    /// Describe any broken internal invariants, e.g. a selection or anchor
+    /// pointing past the end of the buffer. An empty list means the document
+    /// is consistent; anything else means it should be reloaded from disk.
+    pub fn invariant_violations(&self) -> Vec<String> {
+        let len = self.buffer.len();
+        let mut problems = Vec::new();
+
+        if self.selection.start > self.selection.end || self.selection.end > len {
+            problems.push(format!(
+                "selection {:?} outside document of {len} bytes",
+                self.selection
+            ));
+        }
+        for anchor in &self.anchors {
+            if anchor.range.start > anchor.range.end || anchor.range.end > len {
+                problems.push(format!(
+                    "anchor {} range {:?} outside document of {len} bytes",
+                    anchor.id.0, anchor.range
+                ));
+            }
+        }
+        match &self.tree {
+            None => problems.push("no parse tree".to_string()),
+            Some(tree) if tree.root_node().end_byte() > len => problems.push(format!(
+                "parse tree ends at {} past document of {len} bytes",
+                tree.root_node().end_byte()
+            )),
+            Some(_) => {}
+        }
+        problems
+    }
+
+    /// Read `range` of the document, clamped to its length. Borrows when
+    /// the range lies within one chunk of the rope and copies otherwise.
+    pub fn slice_to_cow(&self, range: std::ops::Range<usize>) -> std::borrow::Cow<'_, str> {
         let doc_len = self.buffer.len();
 
         // Clamp range to document bounds to prevent xi-rope panic
@@ -368,8 +557,6 @@ impl Document {
         let mut edits = Vec::new();
         let mut old_pos = 0; // Current position in OLD document (source of truth)
 
-        let old_text = self.buffer.to_string(); // OLD text for coordinate calculation
-
         for op in &delta.els {
             match op {
                 xi_rope::delta::DeltaElement::Copy(from, to) => {
@@ -380,8 +567,8 @@ impl Document {
                         let old_end_byte = *from; // End at copy start in OLD doc  
                         let new_end_byte = old_pos; // After deletion, new position = start
 
-                        let start_pos = byte_to_point_in_text(&old_text, old_pos);
-                        let old_end_pos = byte_to_point_in_text(&old_text, *from);
+                        let start_pos = point_at(&self.buffer, old_pos);
+                        let old_end_pos = point_at(&self.buffer, *from);
                         let new_end_pos = start_pos; // After deletion, new position = start position
 
                         edits.push(tree_sitter::InputEdit {
@@ -412,7 +599,7 @@ impl Document {
                     let old_end_byte = old_pos; // No content consumed in OLD doc
                     let new_end_byte = old_pos + text.len(); // New content extends beyond old position
 
-                    let start_pos = byte_to_point_in_text(&old_text, old_pos);
+                    let start_pos = point_at(&self.buffer, old_pos);
                     let old_end_pos = start_pos; // No old content consumed
 
                     // Calculate new end position by simulating the text insertion
@@ -465,8 +652,8 @@ impl Document {
             let old_end_byte = delta.base_len;
             let new_end_byte = old_pos; // After deletion, position stays at start
 
-            let start_pos = byte_to_point_in_text(&old_text, old_pos);
-            let old_end_pos = byte_to_point_in_text(&old_text, delta.base_len);
+            let start_pos = point_at(&self.buffer, old_pos);
+            let old_end_pos = point_at(&self.buffer, delta.base_len);
             let new_end_pos = start_pos; // After deletion, position stays at start
 
             edits.push(tree_sitter::InputEdit {
@@ -492,6 +679,7 @@ impl Document {
     }
 
     pub fn snapshot(&self) -> crate::editing::snapshot::Snapshot {
+        let _timed = timed(tracing::debug_span!("snapshot", bytes = self.buffer.len()));
         crate::editing::snapshot::create_snapshot(self)
     }
 
@@ -502,36 +690,9 @@ impl Document {
         &self,
         byte_position: usize,
     ) -> Option<(crate::editing::AnchorId, usize)> {
-        let snapshot = self.snapshot();
-
-        // Recursively search blocks for the position
-        fn find_in_blocks(
-            blocks: &[crate::editing::snapshot::Block],
-            byte_position: usize,
-        ) -> Option<(crate::editing::AnchorId, usize)> {
-            for block in blocks {
-                if block.node_range.contains(&byte_position) {
-                    // Check children first (more specific match)
-                    if let crate::editing::snapshot::BlockContent::Children(ref children) =
-                        block.content
-                        && let Some(result) = find_in_blocks(children, byte_position)
-                    {
-                        return Some(result);
-                    }
-                    // Calculate local offset using first segment's range or node_range
-                    let content_start = block
-                        .segments
-                        .first()
-                        .map(|s| s.range.start)
-                        .unwrap_or(block.node_range.start);
-                    let local_offset = byte_position.saturating_sub(content_start);
-                    return Some((block.id, local_offset));
-                }
-            }
-            None
-        }
-
-        find_in_blocks(&snapshot.blocks, byte_position)
+        self.snapshot()
+            .block_at_offset(byte_position)
+            .map(|(id, position)| (id, position.offset))
     }
 }
 
@@ -567,23 +728,33 @@ fn detect_indent_style(buffer: &Rope) -> IndentStyle {
     IndentStyle::Spaces(2)
 }
 
-/// Convert byte offset to (row, column) position in given text
-fn byte_to_point_in_text(text: &str, byte_offset: usize) -> (usize, usize) {
-    let text_bytes = text.as_bytes();
-    let offset = byte_offset.min(text_bytes.len());
-
-    let mut row = 0;
-    let mut last_newline = 0;
+/// Convert byte offset to (row, column) position in the rope, using its
+/// line index rather than scanning the text
+fn point_at(buffer: &Rope, byte_offset: usize) -> (usize, usize) {
+    let offset = byte_offset.min(buffer.len());
+    let row = buffer.line_of_offset(offset);
+    (row, offset - buffer.offset_of_line(row))
+}
 
-    for (i, &byte) in text_bytes.iter().enumerate().take(offset) {
-        if byte == b'\n' {
-            row += 1;
-            last_newline = i + 1;
-        }
-    }
+/// Parse the rope with tree-sitter, feeding it the rope's chunks instead
+/// of a copy of the whole text
+fn parse_rope(parser: &mut Parser, buffer: &Rope, old_tree: Option<&Tree>) -> Option<Tree> {
+    parser.parse_with_options(
+        &mut |byte, _| match Cursor::new(buffer, byte).get_leaf() {
+            Some((leaf, at)) if byte < buffer.len() => &leaf.as_bytes()[at..],
+            _ => &[][..],
+        },
+        old_tree,
+        None,
+    )
+}
 
-    let col = offset - last_newline;
-    (row, col)
+/// Whether two ropes hold the same text, compared chunk by chunk
+fn same_text(a: &Rope, b: &Rope) -> bool {
+    a.len() == b.len()
+        && a.iter_chunks(..)
+            .flat_map(str::bytes)
+            .eq(b.iter_chunks(..).flat_map(str::bytes))
 }
 
 impl Clone for Document {
@@ -593,7 +764,7 @@ impl Clone for Document {
         let _ = parser.set_language(&LANGUAGE.into());
 
         // Re-parse the document for the cloned version
-        let tree = parser.parse(self.buffer.to_string(), None);
+        let tree = parse_rope(&mut parser, &self.buffer, None);
 
         let mut cloned_doc = Self {
             buffer: self.buffer.clone(),
@@ -603,6 +774,11 @@ impl Clone for Document {
             tree,
             anchors: Vec::new(), // Start with empty anchors
             indent_style: self.indent_style.clone(),
+            history: History::default(), // Undo doesn't carry over to copies
+            collapsed: self.collapsed.clone(),
+            observers: Observers::default(), // Subscribers stay with the original
+            read_only: self.read_only,
+            active_block: self.active_block,
         };
 
         // FIX: Regenerate anchors for the new tree to fix stale node_id references
@@ -616,7 +792,7 @@ impl PartialEq for Document {
     fn eq(&self, other: &Self) -> bool {
         // Compare the essential state that matters for equality
         // Compare buffer content as strings since Node doesn't implement PartialEq
-        self.buffer.to_string() == other.buffer.to_string()
+        same_text(&self.buffer, &other.buffer)
             && self.selection == other.selection
             && self.version == other.version
             && self.anchors == other.anchors
@@ -757,26 +933,73 @@ mod tests {
     }
 
     #[test]
-    fn test_byte_to_point_in_text_helper() {
+    fn test_point_at_helper() {
         let text = "Line 1\nLine 2\nLine 3";
+        let rope = Rope::from(text);
 
         // Position 0 should be (0, 0)
-        assert_eq!(byte_to_point_in_text(text, 0), (0, 0));
+        assert_eq!(point_at(&rope, 0), (0, 0));
 
         // Position 6 should be (0, 6) - end of first line
-        assert_eq!(byte_to_point_in_text(text, 6), (0, 6));
+        assert_eq!(point_at(&rope, 6), (0, 6));
 
         // Position 7 should be (1, 0) - start of second line (after \n)
-        assert_eq!(byte_to_point_in_text(text, 7), (1, 0));
+        assert_eq!(point_at(&rope, 7), (1, 0));
 
         // Position 13 should be (1, 6) - end of second line
-        assert_eq!(byte_to_point_in_text(text, 13), (1, 6));
+        assert_eq!(point_at(&rope, 13), (1, 6));
 
         // Position at end should be (2, 6) - end of third line
-        assert_eq!(byte_to_point_in_text(text, text.len()), (2, 6));
+        assert_eq!(point_at(&rope, text.len()), (2, 6));
 
         // Beyond end should be clamped to end
-        assert_eq!(byte_to_point_in_text(text, text.len() + 100), (2, 6));
+        assert_eq!(point_at(&rope, text.len() + 100), (2, 6));
+    }
+
+    #[test]
+    fn test_line_and_utf16_lookups_follow_edits() {
+        let mut doc = Document::from_bytes("# Title\n\n- one\n- two 😀\n".as_bytes()).unwrap();
+        assert_eq!(doc.line_count(), 5);
+        assert_eq!(doc.line_of_offset(11), 2);
+        assert_eq!(doc.offset_of_line(3), 15);
+        assert_eq!(doc.offset_of_line(99), doc.len());
+        assert_eq!(doc.line_of_offset(999), 4);
+
+        doc.apply(Cmd::InsertText {
+            at: 9,
+            text: "Intro\nmore\n\n".to_string(),
+        });
+        assert_eq!(doc.line_count(), 8);
+        assert_eq!(doc.offset_of_line(5), 21);
+        assert_eq!(doc.slice(21..26), "- one");
+
+        // The emoji is two UTF-16 units and four bytes
+        let emoji = doc.text().find('😀').unwrap();
+        assert_eq!(doc.utf16_of_offset(emoji + 4), Some(emoji + 2));
+        assert_eq!(doc.utf16_of_offset(emoji + 1), None);
+        assert_eq!(doc.offset_of_utf16(emoji + 2), Some(emoji + 4));
+        assert_eq!(doc.offset_of_utf16(emoji + 1), None);
+        assert_eq!(doc.offset_of_utf16(emoji + 99), None);
+    }
+
+    #[test]
+    fn test_chunked_parse_matches_whole_text() {
+        // Big enough to span many rope leaves, with multi-byte characters
+        // landing on chunk boundaries
+        let text = "# Café ☕\n\n- item é\n  - nested ✓\n\n".repeat(400);
+        let doc = Document::from_bytes(text.as_bytes()).unwrap();
+        assert!(doc.chunks(0..text.len()).count() > 1);
+        assert_eq!(doc.chunks(0..usize::MAX).collect::<String>(), text);
+        assert_eq!(doc.chunks(2..8).collect::<String>(), "Café ");
+
+        let mut parser = Parser::new();
+        parser.set_language(&LANGUAGE.into()).unwrap();
+        let whole = parser.parse(&text, None).unwrap();
+        assert_eq!(
+            doc.tree.as_ref().unwrap().root_node().to_sexp(),
+            whole.root_node().to_sexp()
+        );
+        assert!(same_text(&doc.buffer, &Rope::from(text.as_str())));
     }
 
     // ============ Delta to InputEdit conversion tests ============
@@ -1045,15 +1268,17 @@ mod tests {
                 use crate::editing::InlineNode;
                 match node {
                     InlineNode::Text(t) => t.clone(),
-                    InlineNode::Strong(children) | InlineNode::Emphasis(children) => {
-                        children.iter().map(extract_text).collect()
-                    }
+                    InlineNode::Strong(children) | InlineNode::Emphasis(children) => children
+                        .iter()
+                        .map(|child| extract_text(&child.kind))
+                        .collect(),
                     InlineNode::Code(t) | InlineNode::Strikethrough(t) => t.clone(),
                     InlineNode::WikiLink { target, alias } => {
                         alias.as_ref().unwrap_or(target).clone()
                     }
                     InlineNode::Link { text, .. } => text.clone(),
                     InlineNode::Image { alt, .. } => alt.clone(),
+                    InlineNode::Tag { name } => format!("#{name}"),
                     InlineNode::HardBreak => "\n".to_string(),
                     InlineNode::SoftBreak => " ".to_string(),
                 }
@@ -1170,4 +1395,59 @@ mod tests {
             );
         }
     }
+
+    // ============ Invariant tests ============
+
+    #[test]
+    fn test_invariants_hold_after_edits() {
+        let mut doc = Document::from_bytes(b"# Title\n\n- one\n- two\n").unwrap();
+        assert!(doc.invariant_violations().is_empty());
+
+        doc.apply(Cmd::InsertText {
+            at: 0,
+            text: "Intro\n\n".to_string(),
+        });
+        doc.apply(Cmd::DeleteRange { range: 0..7 });
+        assert!(doc.invariant_violations().is_empty());
+    }
+
+    #[test]
+    fn test_invariant_violations_reported() {
+        let mut doc = Document::from_bytes(b"# Title\n\nBody\n").unwrap();
+        doc.set_selection(3..50);
+        doc.anchors[0].range = 0..99;
+
+        let problems = doc.invariant_violations();
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert!(problems[0].starts_with("selection"));
+        assert!(problems[1].contains("range 0..99"));
+    }
+
+    // ============ Read-only tests ============
+
+    #[test]
+    fn test_read_only_document_refuses_edits() {
+        let mut doc = Document::from_bytes(b"Hello\n").unwrap();
+        doc.apply(Cmd::InsertText {
+            at: 0,
+            text: "Oh, ".to_string(),
+        });
+        doc.set_read_only(true);
+
+        let patch = doc.apply(Cmd::DeleteRange { range: 0..4 });
+        assert!(patch.changed.is_empty());
+        assert_eq!(patch.version, 1);
+        assert!(matches!(
+            doc.try_apply(Cmd::DeleteRange { range: 0..4 }),
+            Err(ReadOnlyError)
+        ));
+        assert!(!doc.can_undo());
+        assert!(doc.undo().is_none());
+        assert_eq!(doc.text(), "Oh, Hello\n");
+        assert!(doc.clone().is_read_only());
+
+        doc.set_read_only(false);
+        assert!(doc.try_apply(Cmd::DeleteRange { range: 0..4 }).is_ok());
+        assert_eq!(doc.text(), "Hello\n");
+    }
 }
@@ -0,0 +1,286 @@
+//! # Note Embeds (Transclusion)
+//!
+//! A paragraph that is only `![[Other Note]]` or `![[Other Note#Heading]]`
+//! is an [`Embed`](BlockKind::Embed) block in the snapshot rather than a
+//! paragraph of link syntax. The snapshot is built from the one document,
+//! so the embed starts out empty; [`Snapshot::resolve_embeds`] loads each
+//! target and puts its blocks (or the heading's section) under the embed
+//! with an [`EmbedSource`] saying where they came from.
+//!
+//! Embedded blocks are read-only. Their ranges and anchors belong to the
+//! embedded note, so commands built from them must not be sent to the host
+//! document; frontends render them from [`EmbedSource::text`] and follow
+//! [`EmbedSource::path`] to edit them.
+//!
+//! Embeds inside embeds are resolved too, up to [`MAX_EMBED_DEPTH`] deep.
+//! A note that embeds itself (directly or through others) stops there.
+//! Attachment embeds (`![[pic.png]]`) are images and stay paragraphs.
+
+use std::path::Path;
+
+use relative_path::{RelativePath, RelativePathBuf};
+
+use crate::editing::snapshot::{Block, BlockContent, BlockKind, Snapshot};
+use crate::editing::{AnchorId, Document};
+use crate::io;
+use crate::links::rename::collect_headings;
+use crate::models::MarkdownFile;
+use crate::titles::TitleIndex;
+
+/// How many embeds deep [`Snapshot::resolve_embeds`] goes.
+pub const MAX_EMBED_DEPTH: usize = 4;
+
+/// Where an embed's blocks came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbedSource {
+    /// The embedded note
+    pub path: RelativePathBuf,
+    /// The embedded heading's block in that note, for `![[Note#Heading]]`
+    pub anchor: Option<AnchorId>,
+    /// The embedded note's text. The embedded blocks' ranges index into
+    /// this, not into the host document.
+    pub text: String,
+}
+
+impl Snapshot {
+    /// Fill in [`Embed`](BlockKind::Embed) blocks with the blocks of the
+    /// notes they name.
+    ///
+    /// `load` maps an embed target to the note's path and text, or `None`
+    /// if there's no such note, which leaves the embed empty. `host` is the
+    /// path of the document this snapshot is of, if it has one, so it isn't
+    /// embedded in itself. See [`resolve_vault_embeds`] to load from disk.
+    pub fn resolve_embeds(
+        &mut self,
+        host: Option<&RelativePath>,
+        mut load: impl FnMut(&str) -> Option<(RelativePathBuf, String)>,
+    ) {
+        let mut stack: Vec<RelativePathBuf> = host.map(|h| h.normalize()).into_iter().collect();
+        resolve_blocks(&mut self.blocks, &mut load, &mut stack, 0);
+    }
+
+    /// Whether any block is an embed, resolved or not.
+    pub fn has_embeds(&self) -> bool {
+        fn any(blocks: &[Block]) -> bool {
+            blocks.iter().any(|block| {
+                matches!(block.kind, BlockKind::Embed { .. })
+                    || matches!(&block.content, BlockContent::Children(children) if any(children))
+            })
+        }
+        any(&self.blocks)
+    }
+}
+
+/// [`Snapshot::resolve_embeds`] with notes from `notes_root`. A target is
+/// looked up as a path first (`projects/plan` is `projects/plan.md`), then
+/// by file name or alias anywhere in the vault, like wiki-links.
+pub fn resolve_vault_embeds(
+    snapshot: &mut Snapshot,
+    notes_root: &Path,
+    host: Option<&RelativePath>,
+) {
+    if !snapshot.has_embeds() {
+        return;
+    }
+    // Only scanned if a target isn't found by path
+    let mut titles: Option<Option<TitleIndex>> = None;
+    snapshot.resolve_embeds(host, |target| {
+        let direct = MarkdownFile::from_display_path(target)
+            .relative_path()
+            .normalize();
+        let path = if direct.to_path(notes_root).is_file() {
+            direct
+        } else {
+            titles
+                .get_or_insert_with(|| TitleIndex::scan(notes_root).ok())
+                .as_ref()?
+                .resolve(target)?
+                .to_relative_path_buf()
+        };
+        let text = io::read_file(&path, notes_root).ok()?;
+        Some((path, text))
+    });
+}
+
+fn resolve_blocks(
+    blocks: &mut [Block],
+    load: &mut dyn FnMut(&str) -> Option<(RelativePathBuf, String)>,
+    stack: &mut Vec<RelativePathBuf>,
+    depth: usize,
+) {
+    for block in blocks {
+        let BlockKind::Embed {
+            target,
+            heading,
+            source,
+        } = &mut block.kind
+        else {
+            if let BlockContent::Children(children) = &mut block.content {
+                resolve_blocks(children, load, stack, depth);
+            }
+            continue;
+        };
+        if depth >= MAX_EMBED_DEPTH {
+            continue;
+        }
+        let Some((path, text)) = load(target) else {
+            continue;
+        };
+        if stack.contains(&path) {
+            continue;
+        }
+        let Ok(doc) = Document::from_bytes(text.as_bytes()) else {
+            continue;
+        };
+
+        let mut blocks = doc.snapshot().blocks;
+        let anchor = match heading {
+            Some(heading) => match section(&doc, &blocks, heading) {
+                Some((id, range)) => {
+                    blocks = blocks.drain(range).collect();
+                    Some(id)
+                }
+                None => continue,
+            },
+            None => None,
+        };
+
+        stack.push(path.clone());
+        resolve_blocks(&mut blocks, load, stack, depth + 1);
+        stack.pop();
+
+        *source = Some(EmbedSource { path, anchor, text });
+        block.content = BlockContent::Children(blocks);
+    }
+}
+
+/// The top-level heading titled `heading` (case-insensitive) and the range
+/// of `blocks` from it up to the next heading of the same or a higher
+/// level.
+fn section(
+    doc: &Document,
+    blocks: &[Block],
+    heading: &str,
+) -> Option<(AnchorId, std::ops::Range<usize>)> {
+    let heading = heading.trim();
+    let info = collect_headings(doc)
+        .into_iter()
+        .find(|info| info.text.eq_ignore_ascii_case(heading))?;
+    let start = blocks.iter().position(|block| block.id == info.id)?;
+    let end = blocks[start + 1..]
+        .iter()
+        .position(|block| matches!(block.kind, BlockKind::Heading { level } if level <= info.level))
+        .map_or(blocks.len(), |offset| start + 1 + offset);
+    Some((info.id, start..end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{create_test_file, create_test_notes_dir};
+
+    fn embed_parts(block: &Block) -> (&str, Option<&str>, Option<&EmbedSource>, &[Block]) {
+        let BlockKind::Embed {
+            target,
+            heading,
+            source,
+        } = &block.kind
+        else {
+            panic!("not an embed: {:?}", block.kind);
+        };
+        let children = match &block.content {
+            BlockContent::Children(children) => children.as_slice(),
+            BlockContent::Leaf => &[],
+        };
+        (target, heading.as_deref(), source.as_ref(), children)
+    }
+
+    #[test]
+    fn test_embed_blocks_in_snapshot() {
+        let doc =
+            Document::from_bytes(b"Intro\n\n![[Other Note]]\n\n![[pic.png]]\n\nSee ![[x]] here\n")
+                .unwrap();
+        let snapshot = doc.snapshot();
+        assert!(snapshot.has_embeds());
+        let (target, heading, source, children) = embed_parts(&snapshot.blocks[1]);
+        assert_eq!((target, heading, source), ("Other Note", None, None));
+        assert!(children.is_empty());
+        // Images and embeds within text stay paragraphs
+        assert!(matches!(
+            snapshot.blocks[2].kind,
+            BlockKind::Paragraph { .. }
+        ));
+        assert!(matches!(
+            snapshot.blocks[3].kind,
+            BlockKind::Paragraph { .. }
+        ));
+    }
+
+    #[test]
+    fn test_resolve_note_and_section() {
+        let notes = [
+            (
+                "recipe.md",
+                "# Soup\n\nMix.\n\n## Serving\n\nHot.\n\n### Garnish\n\nParsley.\n\n## Storage\n\nCold.\n",
+            ),
+            ("loop.md", "![[host]]\n"),
+        ];
+        let mut snapshot = Document::from_bytes(
+            b"![[Recipe#serving]]\n\n![[recipe]]\n\n![[Missing]]\n\n![[loop]]\n",
+        )
+        .unwrap()
+        .snapshot();
+        snapshot.resolve_embeds(Some(RelativePath::new("host.md")), |target| {
+            let (path, text) = notes
+                .iter()
+                .find(|(path, _)| path.trim_end_matches(".md").eq_ignore_ascii_case(target))?;
+            Some((RelativePathBuf::from(*path), text.to_string()))
+        });
+
+        let (_, heading, source, children) = embed_parts(&snapshot.blocks[0]);
+        assert_eq!(heading, Some("serving"));
+        let source = source.unwrap();
+        assert_eq!(source.path, "recipe.md");
+        assert_eq!(source.anchor, Some(children[0].id));
+        let texts: Vec<&str> = children
+            .iter()
+            .map(|block| source.text[block.content_range()].trim_end())
+            .collect();
+        assert_eq!(texts, ["## Serving", "Hot.", "### Garnish", "Parsley."]);
+
+        let (_, _, source, children) = embed_parts(&snapshot.blocks[1]);
+        assert_eq!(source.unwrap().anchor, None);
+        assert_eq!(children.len(), 8);
+
+        let (_, _, source, _) = embed_parts(&snapshot.blocks[2]);
+        assert_eq!(source, None);
+
+        // loop.md embeds the host, which isn't embedded again
+        let (_, _, source, children) = embed_parts(&snapshot.blocks[3]);
+        assert!(source.is_some());
+        let (_, _, inner, inner_children) = embed_parts(&children[0]);
+        assert_eq!(inner, None);
+        assert!(inner_children.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_vault_embeds_by_path_and_alias() {
+        let notes_dir = create_test_notes_dir();
+        std::fs::create_dir(notes_dir.path().join("projects")).unwrap();
+        create_test_file(
+            &notes_dir,
+            "projects/plan.md",
+            "---\naliases: [Big Plan]\n---\nStep one.\n",
+        );
+        let mut snapshot = Document::from_bytes(b"![[projects/plan]]\n\n![[big plan]]\n")
+            .unwrap()
+            .snapshot();
+        resolve_vault_embeds(&mut snapshot, notes_dir.path(), None);
+
+        for block in &snapshot.blocks {
+            let (_, _, source, children) = embed_parts(block);
+            assert_eq!(source.unwrap().path, "projects/plan.md");
+            assert_eq!(children.len(), 2);
+        }
+    }
+}
@@ -0,0 +1,266 @@
+//! Focus and typewriter mode.
+//!
+//! Writing apps can dim everything but the block being written and keep
+//! the current line or sentence at a fixed height on screen. Frontends mark
+//! the block the caret is in with [`Document::set_active_block`]; each
+//! snapshot then carries:
+//!
+//! - a [`FocusHint`] on every block, saying whether to dim it
+//! - [`Snapshot::active`](crate::editing::Snapshot::active): the active
+//!   block's line and sentence ranges, so a frontend can highlight the
+//!   sentence at the caret or scroll its line to the middle of the view
+//!
+//! The mark follows the block's anchor through edits. If the block is
+//! deleted, snapshots show no focus until another block is marked.
+
+use std::ops::Range;
+
+use crate::editing::selection::sentence_ranges;
+use crate::editing::snapshot::{Block, BlockContent};
+use crate::editing::{AnchorId, Document};
+
+/// How a block shows in focus mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FocusHint {
+    /// No block is active: show everything normally
+    #[default]
+    Normal,
+    /// The active block
+    Active,
+    /// A list, list item or quote containing the active block; draw its
+    /// marker or border normally but leave the rest to its children
+    Ancestor,
+    /// Any other block, to be dimmed
+    Dimmed,
+}
+
+/// The active block's text, split for focus and typewriter mode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActiveBlock {
+    pub id: AnchorId,
+    /// Source range of each line of the block's text, without indentation,
+    /// `>` quote markers or the line break
+    pub lines: Vec<Range<usize>>,
+    /// Source range of each sentence; a sentence ends at `.`, `!` or `?`
+    /// followed by whitespace and may span lines
+    pub sentences: Vec<Range<usize>>,
+}
+
+impl ActiveBlock {
+    /// The line containing byte `offset`, e.g. the caret's, or the nearest
+    /// one before it.
+    pub fn line_at(&self, offset: usize) -> Option<&Range<usize>> {
+        self.lines.iter().rev().find(|line| line.start <= offset)
+    }
+
+    /// The sentence containing byte `offset`, or the nearest one before it.
+    pub fn sentence_at(&self, offset: usize) -> Option<&Range<usize>> {
+        self.sentences
+            .iter()
+            .rev()
+            .find(|sentence| sentence.start <= offset)
+    }
+}
+
+impl Document {
+    /// Mark the block with `anchor_id` as the one being written, or clear
+    /// the mark with `None`. See the [module docs](self).
+    pub fn set_active_block(&mut self, anchor_id: Option<AnchorId>) {
+        self.active_block = anchor_id;
+    }
+
+    pub fn active_block(&self) -> Option<AnchorId> {
+        self.active_block
+    }
+}
+
+/// Set each block's [`FocusHint`] for the active block `id` and describe
+/// it, or leave the blocks alone if there's no block `id`.
+pub(crate) fn mark_focus(blocks: &mut [Block], id: AnchorId, source: &str) -> Option<ActiveBlock> {
+    let active = find_block(blocks, id)?;
+    let text = match (active.segments.first(), active.segments.last()) {
+        (Some(first), Some(last)) => first.range.start..last.range.end,
+        _ => active.content_range(),
+    };
+    let lines = line_ranges(source, text.clone());
+    let sentences = if text.is_empty() {
+        Vec::new()
+    } else {
+        sentence_ranges(source, text)
+    };
+    set_hints(blocks, id);
+    Some(ActiveBlock {
+        id,
+        lines,
+        sentences,
+    })
+}
+
+/// The block `id` among `blocks` or their descendants.
+pub(crate) fn find_block(blocks: &[Block], id: AnchorId) -> Option<&Block> {
+    blocks.iter().find_map(|block| {
+        if block.id == id {
+            return Some(block);
+        }
+        match &block.content {
+            BlockContent::Children(children) => find_block(children, id),
+            BlockContent::Leaf => None,
+        }
+    })
+}
+
+/// Mark `blocks` and their children; returns whether the active block is
+/// among them.
+fn set_hints(blocks: &mut [Block], id: AnchorId) -> bool {
+    let mut found = false;
+    for block in blocks {
+        let contains = match &mut block.content {
+            BlockContent::Children(children) => set_hints(children, id),
+            BlockContent::Leaf => false,
+        };
+        block.focus = if block.id == id {
+            FocusHint::Active
+        } else if contains {
+            FocusHint::Ancestor
+        } else {
+            FocusHint::Dimmed
+        };
+        found |= block.id == id || contains;
+    }
+    found
+}
+
+/// Each line of `range`, trimmed, skipping blank lines.
+fn line_ranges(source: &str, range: Range<usize>) -> Vec<Range<usize>> {
+    let mut lines = Vec::new();
+    let mut start = range.start;
+    for line in source[range.clone()].split('\n') {
+        let end = start + line.len();
+        let trimmed = line.trim_start_matches(['>', ' ', '\t']).trim_end();
+        if !trimmed.is_empty() {
+            let offset = start + (line.len() - line.trim_start_matches(['>', ' ', '\t']).len());
+            lines.push(offset..offset + trimmed.len());
+        }
+        start = end + 1;
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editing::BlockKind;
+
+    fn text(source: &str, ranges: &[Range<usize>]) -> Vec<String> {
+        ranges
+            .iter()
+            .map(|r| source[r.clone()].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_no_focus_without_active_block() {
+        let doc = Document::from_bytes(b"One.\n\nTwo.\n").unwrap();
+        let snapshot = doc.snapshot();
+        assert_eq!(snapshot.active, None);
+        assert!(
+            snapshot
+                .blocks
+                .iter()
+                .all(|block| block.focus == FocusHint::Normal)
+        );
+    }
+
+    #[test]
+    fn test_active_paragraph_lines_and_sentences() {
+        let source = "# Title\n\nFirst one. Second\nspans lines! Third\n\nOther.\n";
+        let mut doc = Document::from_bytes(source.as_bytes()).unwrap();
+        let id = doc.snapshot().blocks[1].id;
+        doc.set_active_block(Some(id));
+
+        let snapshot = doc.snapshot();
+        let hints: Vec<FocusHint> = snapshot.blocks.iter().map(|b| b.focus).collect();
+        assert_eq!(
+            hints,
+            [FocusHint::Dimmed, FocusHint::Active, FocusHint::Dimmed]
+        );
+
+        let active = snapshot.active.unwrap();
+        assert_eq!(active.id, id);
+        assert_eq!(
+            text(source, &active.lines),
+            ["First one. Second", "spans lines! Third"]
+        );
+        assert_eq!(
+            text(source, &active.sentences),
+            ["First one.", "Second\nspans lines!", "Third"]
+        );
+        let caret = source.find("lines").unwrap();
+        assert_eq!(
+            &source[active.sentence_at(caret).unwrap().clone()],
+            "Second\nspans lines!"
+        );
+        assert_eq!(
+            &source[active.line_at(caret).unwrap().clone()],
+            "spans lines! Third"
+        );
+    }
+
+    #[test]
+    fn test_zoomed_view_keeps_active_block() {
+        let mut doc = Document::from_bytes(b"Intro.\n\n# Title\n\nOne. Two.\n").unwrap();
+        let snapshot = doc.snapshot();
+        let (heading, paragraph) = (snapshot.blocks[1].id, snapshot.blocks[2].id);
+        doc.set_active_block(Some(paragraph));
+
+        let view = doc.zoom(heading).unwrap();
+        let active = view.snapshot.active.unwrap();
+        let text = &doc.text()[view.range.clone()];
+        assert_eq!(
+            active
+                .sentences
+                .iter()
+                .map(|r| &text[r.clone()])
+                .collect::<Vec<_>>(),
+            ["One.", "Two."]
+        );
+
+        doc.set_active_block(Some(snapshot.blocks[0].id));
+        assert_eq!(doc.zoom(heading).unwrap().snapshot.active, None);
+    }
+
+    #[test]
+    fn test_nested_item_and_unknown_block() {
+        let source = "- Parent\n  - Child. Done.\n- Sibling\n";
+        let mut doc = Document::from_bytes(source.as_bytes()).unwrap();
+        let snapshot = doc.snapshot();
+        let BlockContent::Children(items) = &snapshot.blocks[0].content else {
+            panic!("expected list");
+        };
+        let BlockContent::Children(nested) = &items[0].content else {
+            panic!("expected nested list");
+        };
+        let BlockContent::Children(children) = &nested[0].content else {
+            panic!("expected nested items");
+        };
+        let child = children[0].id;
+        doc.set_active_block(Some(child));
+
+        let snapshot = doc.snapshot();
+        let list = &snapshot.blocks[0];
+        assert_eq!(list.focus, FocusHint::Ancestor);
+        let BlockContent::Children(items) = &list.content else {
+            panic!("expected list");
+        };
+        assert_eq!(items[0].focus, FocusHint::Ancestor);
+        assert_eq!(items[1].focus, FocusHint::Dimmed);
+        assert!(matches!(items[1].kind, BlockKind::ListItem { .. }));
+        let active = snapshot.active.unwrap();
+        assert_eq!(text(source, &active.sentences), ["Child.", "Done."]);
+
+        doc.set_active_block(Some(AnchorId(7)));
+        let snapshot = doc.snapshot();
+        assert_eq!(snapshot.active, None);
+        assert_eq!(snapshot.blocks[0].focus, FocusHint::Normal);
+    }
+}
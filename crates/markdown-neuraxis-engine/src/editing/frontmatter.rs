@@ -0,0 +1,177 @@
+//! # Front Matter
+//!
+//! Metadata at the very start of a note, fenced by `---` (YAML) or `+++`
+//! (TOML). The grammar parses it as a `FRONTMATTER` node, which snapshots
+//! show as a [`BlockKind::FrontMatter`](crate::editing::BlockKind) block.
+//! [`Document::frontmatter`] reads its top-level keys; both formats come
+//! back as [`toml::Value`]s so callers handle one value type. TOML dates are
+//! given as strings, the same as YAML's, and YAML nulls are left out since
+//! TOML has none.
+
+use std::collections::BTreeMap;
+
+use markdown_neuraxis_syntax::{SyntaxKind, SyntaxNode, parse};
+use serde_yaml_ng::{Mapping, Value as YamlValue};
+use toml::Value;
+
+use crate::editing::Document;
+
+/// How a front matter block is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontMatterFormat {
+    /// `---` fenced YAML
+    Yaml,
+    /// `+++` fenced TOML
+    Toml,
+}
+
+impl FrontMatterFormat {
+    /// Format of a `FRONTMATTER` node, from its opening fence.
+    pub fn of(node: &SyntaxNode) -> Self {
+        if node.text().to_string().starts_with('+') {
+            Self::Toml
+        } else {
+            Self::Yaml
+        }
+    }
+}
+
+impl Document {
+    /// Top-level front matter fields, or `None` if the note has no front
+    /// matter or it isn't a valid key/value table.
+    pub fn frontmatter(&self) -> Option<BTreeMap<String, Value>> {
+        parse(&self.text())
+            .children()
+            .find(|node| node.kind() == SyntaxKind::FRONTMATTER)
+            .and_then(|node| parse_frontmatter(&node))
+    }
+}
+
+/// Parse the fields of a `FRONTMATTER` node.
+pub fn parse_frontmatter(node: &SyntaxNode) -> Option<BTreeMap<String, Value>> {
+    let text = node.text().to_string();
+    let body = frontmatter_body(&text);
+    match FrontMatterFormat::of(node) {
+        FrontMatterFormat::Yaml => match serde_yaml_ng::from_str(body).ok()? {
+            YamlValue::Mapping(mapping) => Some(mapping_fields(mapping)),
+            YamlValue::Null => Some(BTreeMap::new()),
+            _ => None,
+        },
+        FrontMatterFormat::Toml => {
+            let table: toml::Table = toml::from_str(body).ok()?;
+            Some(
+                table
+                    .into_iter()
+                    .map(|(key, value)| (key, dates_as_strings(value)))
+                    .collect(),
+            )
+        }
+    }
+}
+
+/// The text between the opening and closing fence lines.
+fn frontmatter_body(text: &str) -> &str {
+    let body = text.split_once('\n').map_or("", |(_, rest)| rest);
+    let body = body.trim_end_matches(['\r', '\n']);
+    match body.rfind('\n') {
+        Some(last) => &body[..last + 1],
+        None => "",
+    }
+}
+
+/// YAML mapping entries keyed by their scalar keys as strings. Entries with
+/// sequence or mapping keys, or null values, are left out.
+fn mapping_fields<T: FromIterator<(String, Value)>>(mapping: Mapping) -> T {
+    mapping
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let key = match key {
+                YamlValue::String(key) => key,
+                YamlValue::Number(key) => key.to_string(),
+                YamlValue::Bool(key) => key.to_string(),
+                _ => return None,
+            };
+            Some((key, yaml_to_toml(value)?))
+        })
+        .collect()
+}
+
+/// `value` as TOML, or `None` for null.
+fn yaml_to_toml(value: YamlValue) -> Option<Value> {
+    Some(match value {
+        YamlValue::Null => return None,
+        YamlValue::Bool(value) => Value::Boolean(value),
+        YamlValue::Number(value) => match value.as_i64() {
+            Some(value) => Value::Integer(value),
+            None => Value::Float(value.as_f64()?),
+        },
+        YamlValue::String(value) => Value::String(value),
+        YamlValue::Sequence(values) => {
+            Value::Array(values.into_iter().filter_map(yaml_to_toml).collect())
+        }
+        YamlValue::Mapping(mapping) => Value::Table(mapping_fields(mapping)),
+        YamlValue::Tagged(tagged) => return yaml_to_toml(tagged.value),
+    })
+}
+
+/// `value` with TOML dates and times turned into strings.
+fn dates_as_strings(value: Value) -> Value {
+    match value {
+        Value::Datetime(value) => Value::String(value.to_string()),
+        Value::Array(values) => Value::Array(values.into_iter().map(dates_as_strings).collect()),
+        Value::Table(table) => Value::Table(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, dates_as_strings(value)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(source: &str) -> Option<BTreeMap<String, Value>> {
+        Document::from_bytes(source.as_bytes())
+            .unwrap()
+            .frontmatter()
+    }
+
+    #[test]
+    fn test_yaml_frontmatter() {
+        let fields = fields(
+            "---\ntitle: Test\ndate: 2024-01-15\ntags: [a, b]\n1: one\nempty:\n---\n\nBody\n",
+        )
+        .unwrap();
+        assert_eq!(fields["title"], Value::from("Test"));
+        assert_eq!(fields["date"], Value::from("2024-01-15"));
+        assert_eq!(
+            fields["tags"],
+            Value::Array(vec![Value::from("a"), Value::from("b")])
+        );
+        assert_eq!(fields["1"], Value::from("one"));
+        assert!(!fields.contains_key("empty"));
+    }
+
+    #[test]
+    fn test_toml_frontmatter() {
+        let fields = fields(
+            "+++\r\ntitle = \"Test\"\r\ndate = 2024-01-15\r\ndraft = true\r\n\r\n[extra]\r\nweight = 2\r\n+++\r\nBody\r\n",
+        )
+        .unwrap();
+        assert_eq!(fields["title"], Value::from("Test"));
+        assert_eq!(fields["date"], Value::from("2024-01-15"));
+        assert_eq!(fields["draft"], Value::Boolean(true));
+        assert_eq!(fields["extra"]["weight"], Value::from(2));
+    }
+
+    #[test]
+    fn test_missing_empty_and_invalid_frontmatter() {
+        assert_eq!(fields("# Title\n\n---\ntitle: x\n---\n"), None);
+        assert_eq!(fields("---\n\n---\nBody\n"), Some(BTreeMap::new()));
+        assert_eq!(fields("---\n- not a table\n---\n"), None);
+        assert_eq!(fields("+++\ntitle = \n+++\n"), None);
+    }
+}
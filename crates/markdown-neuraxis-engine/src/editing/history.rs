@@ -0,0 +1,265 @@
+//! # Undo History
+//!
+//! [`Document::apply`] records the document's state before each edit so
+//! [`Document::undo`] can go back to it and [`Document::redo`] forward
+//! again. Each entry keeps the whole buffer, parse tree, anchors and
+//! selection rather than an inverse delta: xi-rope buffers and Tree-sitter
+//! trees share unchanged parts between copies, so an entry costs little more
+//! than what the edit touched, and block IDs come back exactly as they were.
+//!
+//! Typing is grouped: consecutive single-line [`Cmd::InsertText`] edits that
+//! continue where the previous one ended undo together. Any new edit clears
+//! the redo stack.
+
+use std::ops::Range;
+
+use tree_sitter::Tree;
+use xi_rope::delta::DeltaElement;
+use xi_rope::{Delta, Rope, RopeInfo};
+
+use crate::editing::{Anchor, Cmd, Document, Patch};
+use crate::trace::timed;
+
+/// Most undo steps kept; the oldest are dropped first.
+pub const HISTORY_LIMIT: usize = 500;
+
+/// Document state to return to.
+#[derive(Clone)]
+pub(crate) struct HistoryState {
+    buffer: Rope,
+    tree: Option<Tree>,
+    anchors: Vec<Anchor>,
+    selection: Range<usize>,
+}
+
+/// Undo and redo stacks for a [`Document`].
+#[derive(Clone, Default)]
+pub(crate) struct History {
+    undo: Vec<HistoryState>,
+    redo: Vec<HistoryState>,
+    /// Where the last insertion ended, while typing can still be grouped
+    typing_end: Option<usize>,
+}
+
+impl History {
+    /// Record `before` as the state preceding `cmd`.
+    pub(crate) fn record(&mut self, before: HistoryState, cmd: &Cmd) {
+        self.redo.clear();
+        let typing = match cmd {
+            Cmd::InsertText { at, text } if !text.contains('\n') => Some((*at, at + text.len())),
+            _ => None,
+        };
+        let continues_typing = typing
+            .zip(self.typing_end)
+            .is_some_and(|((at, _), end)| at == end);
+        if !continues_typing {
+            self.undo.push(before);
+            if self.undo.len() > HISTORY_LIMIT {
+                self.undo.remove(0);
+            }
+        }
+        self.typing_end = typing.map(|(_, end)| end);
+    }
+}
+
+impl Document {
+    /// Undo the last edit (or run of typing), restoring the text, block IDs
+    /// and selection from before it. `None` if there is nothing to undo or
+    /// the document is read-only.
+    pub fn undo(&mut self) -> Option<Patch> {
+        if self.read_only {
+            return None;
+        }
+        let previous = self.history.undo.pop()?;
+        let _timed = timed(tracing::debug_span!("undo"));
+        let current = self.history_state();
+        self.history.redo.push(current);
+        Some(self.restore(previous))
+    }
+
+    /// Redo the last undone edit. `None` if there is nothing to redo or the
+    /// document is read-only.
+    pub fn redo(&mut self) -> Option<Patch> {
+        if self.read_only {
+            return None;
+        }
+        let next = self.history.redo.pop()?;
+        let _timed = timed(tracing::debug_span!("redo"));
+        let current = self.history_state();
+        self.history.undo.push(current);
+        Some(self.restore(next))
+    }
+
+    /// Whether [`undo`](Self::undo) would change anything.
+    pub fn can_undo(&self) -> bool {
+        !self.read_only && !self.history.undo.is_empty()
+    }
+
+    /// Whether [`redo`](Self::redo) would change anything.
+    pub fn can_redo(&self) -> bool {
+        !self.read_only && !self.history.redo.is_empty()
+    }
+
+    /// The current state, for recording before an edit.
+    pub(crate) fn history_state(&self) -> HistoryState {
+        HistoryState {
+            buffer: self.buffer.clone(),
+            tree: self.tree.clone(),
+            anchors: self.anchors.clone(),
+            selection: self.selection.clone(),
+        }
+    }
+
+    fn restore(&mut self, state: HistoryState) -> Patch {
+        let old_text = self.buffer.to_string();
+        let new_text = state.buffer.to_string();
+
+        self.buffer = state.buffer;
+        self.tree = state.tree;
+        self.anchors = state.anchors;
+        self.selection = state.selection;
+        self.history.typing_end = None;
+        self.version += 1;
+        self.notify_changed();
+
+        Patch {
+            changed: vec![changed_range(&old_text, &new_text)],
+            new_selection: self.selection.clone(),
+            version: self.version,
+        }
+    }
+}
+
+/// Whether applying `delta` changes the text. Builders split copies at
+/// every edit point, so an empty edit isn't always an identity delta.
+pub(crate) fn changes_text(delta: &Delta<RopeInfo>) -> bool {
+    let mut copied_to = 0;
+    for element in &delta.els {
+        match element {
+            DeltaElement::Copy(from, to) if *from == copied_to => copied_to = *to,
+            _ => return true,
+        }
+    }
+    copied_to != delta.base_len
+}
+
+/// The range of `new` that differs from `old`, after their common prefix
+/// and suffix.
+fn changed_range(old: &str, new: &str) -> Range<usize> {
+    let mut prefix = old
+        .bytes()
+        .zip(new.bytes())
+        .take_while(|(a, b)| a == b)
+        .count();
+    // "é" and "è" share their lead byte, so the bytes can agree mid-character
+    while !(old.is_char_boundary(prefix) && new.is_char_boundary(prefix)) {
+        prefix -= 1;
+    }
+    let suffix = old[prefix..]
+        .bytes()
+        .rev()
+        .zip(new[prefix..].bytes().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let end = (new.len() - suffix..=new.len())
+        .find(|&i| new.is_char_boundary(i))
+        .unwrap_or(new.len());
+    prefix..end
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::editing::{Cmd, Document};
+
+    fn ids(doc: &Document) -> Vec<u128> {
+        doc.snapshot()
+            .blocks
+            .iter()
+            .map(|block| block.id.0)
+            .collect()
+    }
+
+    #[test]
+    fn test_undo_redo_restores_text_ids_and_selection() {
+        let mut doc = Document::from_bytes(b"# Title\n\n- one\n- two\n").unwrap();
+        doc.set_selection(3..3);
+        let original_ids = ids(&doc);
+
+        doc.apply(Cmd::DeleteRange { range: 9..15 });
+        assert_eq!(doc.text(), "# Title\n\n- two\n");
+        assert!(doc.can_undo());
+
+        let patch = doc.undo().unwrap();
+        assert_eq!(doc.text(), "# Title\n\n- one\n- two\n");
+        assert_eq!(ids(&doc), original_ids);
+        assert_eq!(doc.selection(), 3..3);
+        // "- one\n- two\n" -> "- two\n" differs after the shared "- "
+        assert_eq!(patch.changed, vec![11..17]);
+        assert!(!doc.can_undo());
+
+        doc.redo().unwrap();
+        assert_eq!(doc.text(), "# Title\n\n- two\n");
+        assert!(doc.redo().is_none());
+    }
+
+    #[test]
+    fn test_typing_undoes_as_one_step() {
+        let mut doc = Document::from_bytes(b"Hi\n").unwrap();
+        for (at, ch) in [(2, "!"), (3, "!"), (4, "?")] {
+            doc.apply(Cmd::InsertText {
+                at,
+                text: ch.to_string(),
+            });
+        }
+        doc.apply(Cmd::InsertText {
+            at: 0,
+            text: "Oh ".to_string(),
+        });
+        assert_eq!(doc.text(), "Oh Hi!!?\n");
+
+        doc.undo();
+        assert_eq!(doc.text(), "Hi!!?\n");
+        doc.undo();
+        assert_eq!(doc.text(), "Hi\n");
+        assert!(doc.undo().is_none());
+    }
+
+    #[test]
+    fn test_undo_across_accents_sharing_a_lead_byte() {
+        // "é" is C3 A9 and "è" is C3 A8
+        let mut doc = Document::from_bytes("café\n".as_bytes()).unwrap();
+        doc.apply(Cmd::ReplaceRange {
+            range: 3..5,
+            text: "è".to_string(),
+        });
+        assert_eq!(doc.text(), "cafè\n");
+
+        let patch = doc.undo().unwrap();
+        assert_eq!(doc.text(), "café\n");
+        assert_eq!(patch.changed, vec![3..5]);
+        doc.redo().unwrap();
+        assert_eq!(doc.text(), "cafè\n");
+    }
+
+    #[test]
+    fn test_new_edit_clears_redo_and_noops_are_not_recorded() {
+        let mut doc = Document::from_bytes(b"a\n").unwrap();
+        doc.apply(Cmd::InsertText {
+            at: 1,
+            text: "b".to_string(),
+        });
+        doc.undo();
+        assert!(doc.can_redo());
+
+        doc.apply(Cmd::InsertText {
+            at: 0,
+            text: "c".to_string(),
+        });
+        assert!(!doc.can_redo());
+
+        doc.apply(Cmd::DeleteRange { range: 1..1 });
+        doc.undo();
+        assert_eq!(doc.text(), "a\n");
+        assert!(!doc.can_undo());
+    }
+}
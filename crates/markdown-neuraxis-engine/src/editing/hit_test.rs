@@ -0,0 +1,140 @@
+//! Hit testing: which block a tap or click lands in.
+//!
+//! Front-ends map a tap to a source position (a byte offset, or a line and
+//! column) and ask the snapshot where it falls: the innermost block
+//! containing it, so a tap on a nested list item picks that item rather
+//! than its parent, and a [`LocalPosition`] to put the caret at within it.
+
+use crate::editing::AnchorId;
+use crate::editing::snapshot::{Block, BlockContent, Snapshot};
+
+/// A caret position within a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalPosition {
+    /// Bytes from the start of the block's content (its first segment, or
+    /// the block itself if it has none). Zero for positions in a leading
+    /// marker.
+    pub offset: usize,
+    /// Index in `Block::segments` of the segment containing the position,
+    /// or `None` if it's in markup between segments
+    pub segment: Option<usize>,
+}
+
+impl Snapshot {
+    /// The innermost block containing `byte_offset` and where in it the
+    /// offset falls, or `None` outside every block.
+    pub fn block_at_offset(&self, byte_offset: usize) -> Option<(AnchorId, LocalPosition)> {
+        let block = innermost_block(&self.blocks, byte_offset)?;
+        let content_start = block
+            .segments
+            .first()
+            .map_or(block.node_range.start, |segment| segment.range.start);
+        let segment = block
+            .segments
+            .iter()
+            .position(|segment| segment.range.contains(&byte_offset));
+        Some((
+            block.id,
+            LocalPosition {
+                offset: byte_offset.saturating_sub(content_start),
+                segment,
+            },
+        ))
+    }
+
+    /// [`block_at_offset`](Self::block_at_offset) for a 0-based line and
+    /// byte column of `source`, the text the snapshot was taken from.
+    /// Columns past the end of the line land at its end.
+    pub fn block_at_point(
+        &self,
+        source: &str,
+        line: usize,
+        column: usize,
+    ) -> Option<(AnchorId, LocalPosition)> {
+        let line_start = if line == 0 {
+            0
+        } else {
+            source.match_indices('\n').nth(line - 1)?.0 + 1
+        };
+        let line_end = source[line_start..]
+            .find('\n')
+            .map_or(source.len(), |end| line_start + end);
+        self.block_at_offset((line_start + column).min(line_end))
+    }
+}
+
+fn innermost_block(blocks: &[Block], byte_offset: usize) -> Option<&Block> {
+    let block = blocks
+        .iter()
+        .find(|block| block.node_range.contains(&byte_offset))?;
+    match &block.content {
+        BlockContent::Children(children) => innermost_block(children, byte_offset).or(Some(block)),
+        BlockContent::Leaf => Some(block),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editing::Document;
+
+    const SOURCE: &str = "# Title\n\n- item **bold**\n  - nested\n\nLast\n";
+
+    fn block_text<'a>(snapshot: &Snapshot, id: AnchorId) -> &'a str {
+        fn find(blocks: &[Block], id: AnchorId) -> Option<&Block> {
+            blocks.iter().find_map(|block| match &block.content {
+                _ if block.id == id => Some(block),
+                BlockContent::Children(children) => find(children, id),
+                BlockContent::Leaf => None,
+            })
+        }
+        let range = find(&snapshot.blocks, id).unwrap().node_range.clone();
+        SOURCE[range].trim_end()
+    }
+
+    #[test]
+    fn test_block_at_offset_finds_innermost_block() {
+        let snapshot = Document::from_bytes(SOURCE.as_bytes()).unwrap().snapshot();
+
+        let (id, position) = snapshot
+            .block_at_offset(SOURCE.find("bold").unwrap())
+            .unwrap();
+        assert_eq!(block_text(&snapshot, id), "- item **bold**\n  - nested");
+        assert_eq!(position.offset, "item **".len());
+        assert_eq!(position.segment, Some(1));
+
+        let (id, position) = snapshot
+            .block_at_offset(SOURCE.find("nested").unwrap())
+            .unwrap();
+        assert_eq!(block_text(&snapshot, id), "- nested");
+        assert_eq!(position.offset, 0);
+
+        // The heading's `# ` marker comes before its content
+        let (id, position) = snapshot.block_at_offset(0).unwrap();
+        assert_eq!(block_text(&snapshot, id), "# Title");
+        assert_eq!(
+            position,
+            LocalPosition {
+                offset: 0,
+                segment: None
+            }
+        );
+
+        assert!(snapshot.block_at_offset(SOURCE.len()).is_none());
+    }
+
+    #[test]
+    fn test_block_at_point_clamps_to_the_line() {
+        let snapshot = Document::from_bytes(SOURCE.as_bytes()).unwrap().snapshot();
+
+        let (id, position) = snapshot.block_at_point(SOURCE, 5, 2).unwrap();
+        assert_eq!(block_text(&snapshot, id), "Last");
+        assert_eq!(position.offset, 2);
+
+        let (id, position) = snapshot.block_at_point(SOURCE, 5, 99).unwrap();
+        assert_eq!(block_text(&snapshot, id), "Last");
+        assert_eq!(position.offset, 4);
+
+        assert!(snapshot.block_at_point(SOURCE, 9, 0).is_none());
+    }
+}
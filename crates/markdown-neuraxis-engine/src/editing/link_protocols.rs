@@ -0,0 +1,149 @@
+//! Which bare URLs show as links.
+//!
+//! The lexer reads any `scheme://...` (and `mailto:...`) in text as a URL,
+//! so snapshots make them all links. Users pick the schemes they want
+//! clickable, e.g. adding `zotero` or `things` for research and task apps,
+//! and [`Snapshot::apply_link_protocols`] turns bare URLs with other
+//! schemes back into text. Markdown links (`[text](url)`) and autolinks
+//! (`<url>`) were written as links on purpose and always stay links.
+
+use crate::editing::snapshot::{Block, BlockContent, InlineNode, InlineSegment, Snapshot};
+
+/// URL schemes whose bare URLs show as links.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkProtocols {
+    /// Lowercase scheme names, without the `:`
+    schemes: Vec<String>,
+}
+
+impl LinkProtocols {
+    /// Schemes linked unless configured otherwise.
+    pub const DEFAULT: &[&str] = &[
+        "http", "https", "ftp", "mailto", "file", "zotero", "obsidian",
+    ];
+
+    /// Link exactly `schemes`, e.g. `["https", "zotero"]`. Case and a
+    /// trailing `:` or `://` are ignored.
+    pub fn new<S: AsRef<str>>(schemes: impl IntoIterator<Item = S>) -> Self {
+        let schemes = schemes
+            .into_iter()
+            .map(|scheme| {
+                let scheme = scheme.as_ref().trim();
+                let scheme = scheme.strip_suffix("://").unwrap_or(scheme);
+                scheme.strip_suffix(':').unwrap_or(scheme).to_lowercase()
+            })
+            .filter(|scheme| !scheme.is_empty())
+            .collect();
+        Self { schemes }
+    }
+
+    /// Whether `url`'s scheme is one of these.
+    pub fn allows(&self, url: &str) -> bool {
+        url.split_once(':').is_some_and(|(scheme, _)| {
+            self.schemes
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(scheme))
+        })
+    }
+}
+
+impl Default for LinkProtocols {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT)
+    }
+}
+
+impl Snapshot {
+    /// Show bare URLs whose scheme isn't in `protocols` as plain text.
+    pub fn apply_link_protocols(&mut self, protocols: &LinkProtocols) {
+        fn visit_blocks(blocks: &mut [Block], protocols: &LinkProtocols) {
+            for block in blocks {
+                visit_segments(&mut block.segments, protocols);
+                if let BlockContent::Children(children) = &mut block.content {
+                    visit_blocks(children, protocols);
+                }
+            }
+        }
+        fn visit_segments(segments: &mut [InlineSegment], protocols: &LinkProtocols) {
+            for segment in segments {
+                match &mut segment.kind {
+                    InlineNode::Link { text, url }
+                        if is_bare_url(text, url, segment.range.len())
+                            && !protocols.allows(url) =>
+                    {
+                        segment.kind = InlineNode::Text(std::mem::take(text));
+                    }
+                    InlineNode::Strong(children) | InlineNode::Emphasis(children) => {
+                        visit_segments(children, protocols)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        visit_blocks(&mut self.blocks, protocols);
+    }
+}
+
+/// A bare URL's link is its own text, with no markup around it.
+fn is_bare_url(text: &str, url: &str, source_len: usize) -> bool {
+    text == url && source_len == url.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editing::Document;
+
+    fn links(source: &str, protocols: &LinkProtocols) -> Vec<String> {
+        fn collect(segments: &[InlineSegment], out: &mut Vec<String>) {
+            for segment in segments {
+                match &segment.kind {
+                    InlineNode::Link { url, .. } => out.push(url.clone()),
+                    InlineNode::Strong(children) | InlineNode::Emphasis(children) => {
+                        collect(children, out)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let mut snapshot = Document::from_bytes(source.as_bytes()).unwrap().snapshot();
+        snapshot.apply_link_protocols(protocols);
+        let mut out = Vec::new();
+        for block in &snapshot.blocks {
+            collect(&block.segments, &mut out);
+        }
+        out
+    }
+
+    #[test]
+    fn test_default_protocols_link_research_apps() {
+        let source = "See zotero://select/items/ABC and svn+ssh://host/repo\n\n\
+                      **obsidian://open?vault=v** or mailto:me@example.com\n";
+        assert_eq!(
+            links(source, &LinkProtocols::default()),
+            [
+                "zotero://select/items/ABC",
+                "obsidian://open?vault=v",
+                "mailto:me@example.com"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_configured_protocols_replace_defaults() {
+        let protocols = LinkProtocols::new(["HTTPS", "things://"]);
+        assert!(protocols.allows("things:///show?id=today"));
+        assert!(!protocols.allows("http://example.com"));
+
+        let source = "https://a.io http://b.io things:///add [site](http://c.io) <http://d.io>\n";
+        assert_eq!(
+            links(source, &protocols),
+            [
+                "https://a.io",
+                "things:///add",
+                "http://c.io",
+                "http://d.io"
+            ]
+        );
+    }
+}
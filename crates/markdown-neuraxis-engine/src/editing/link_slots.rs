@@ -0,0 +1,278 @@
+//! Caret context inside `[text](href)` links, and pasting URLs into them.
+//!
+//! Context comes from the syntax tree rather than scanning for brackets, so
+//! brackets in code, wikilinks and checkboxes are never mistaken for links.
+
+use std::ops::Range;
+
+use markdown_neuraxis_syntax::{SyntaxKind, SyntaxNode, parse};
+
+use crate::editing::Document;
+use crate::editing::commands::in_raw_zone;
+
+/// Which part of a link the caret is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkSlot {
+    /// Between `[` and `]`
+    Text,
+    /// Between `(` and `)`
+    Href,
+}
+
+/// A link (or bracketed text that could become one) around the caret.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkContext {
+    /// The whole link including brackets and parentheses
+    pub range: Range<usize>,
+    /// Inside the square brackets
+    pub text: Range<usize>,
+    /// Inside the parentheses; `None` for `[text]` with no `(href)` yet
+    pub href: Option<Range<usize>>,
+    pub slot: LinkSlot,
+}
+
+impl Document {
+    /// The link whose text or href slot contains `offset`, if any. Covers
+    /// `[text](href)`, images and bare `[text]` brackets.
+    pub fn link_context_at(&self, offset: usize) -> Option<LinkContext> {
+        let root = parse(&self.text());
+        root.descendants()
+            .filter(|node| {
+                let range = node.text_range();
+                usize::from(range.start()) < offset && offset < usize::from(range.end())
+            })
+            .filter_map(|node| link_context(&node, offset))
+            .last()
+    }
+}
+
+/// Slots of a LINK, IMAGE or closed bracket INLINE node.
+fn link_context(node: &SyntaxNode, offset: usize) -> Option<LinkContext> {
+    if !matches!(
+        node.kind(),
+        SyntaxKind::LINK | SyntaxKind::IMAGE | SyntaxKind::INLINE
+    ) {
+        return None;
+    }
+    let tokens: Vec<_> = node
+        .children_with_tokens()
+        .filter_map(|element| element.into_token())
+        .collect();
+    let end_of = |kind| {
+        tokens
+            .iter()
+            .find(|t| t.kind() == kind)
+            .map(|t| usize::from(t.text_range().end()))
+    };
+    let start_of = |kind| {
+        tokens
+            .iter()
+            .find(|t| t.kind() == kind)
+            .map(|t| usize::from(t.text_range().start()))
+    };
+
+    let text = end_of(SyntaxKind::LBRACKET)?..start_of(SyntaxKind::RBRACKET)?;
+    let href = match (end_of(SyntaxKind::LPAREN), start_of(SyntaxKind::RPAREN)) {
+        (Some(start), Some(end)) if node.kind() != SyntaxKind::INLINE => Some(start..end),
+        _ => None,
+    };
+    let slot = if text.start <= offset && offset <= text.end {
+        LinkSlot::Text
+    } else if href
+        .as_ref()
+        .is_some_and(|h| h.start <= offset && offset <= h.end)
+    {
+        LinkSlot::Href
+    } else {
+        return None;
+    };
+
+    let range = node.text_range();
+    Some(LinkContext {
+        range: range.start().into()..range.end().into(),
+        text,
+        href,
+        slot,
+    })
+}
+
+/// Whether pasted text is a single URL worth turning into a link.
+pub fn looks_like_url(text: &str) -> bool {
+    let text = text.trim();
+    if text.is_empty() || text.contains(char::is_whitespace) {
+        return false;
+    }
+    match text.split_once("://") {
+        Some((scheme, rest)) => {
+            !scheme.is_empty()
+                && !rest.is_empty()
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => text.starts_with("mailto:") && text.len() > "mailto:".len(),
+    }
+}
+
+/// Edits for pasting `url` over `range`, and where the caret ends up.
+///
+/// - Caret in a link's text or href slot: the URL replaces the href (or
+///   adds one to bare `[text]`) and the caret goes to the end of the text
+///   slot, ready to type a title.
+/// - Text selected outside a link: it becomes `[selection](url)`.
+/// - Otherwise, or if `url` isn't a URL: a plain paste.
+pub(crate) fn paste_url_edits(
+    doc: &Document,
+    range: &Range<usize>,
+    url: &str,
+) -> (Vec<(Range<usize>, String)>, usize) {
+    let len = doc.len();
+    let start = range.start.min(len);
+    let end = range.end.min(len).max(start);
+    let plain = || (vec![(start..end, url.to_string())], start + url.len());
+
+    if !looks_like_url(url) || in_raw_zone(doc, start) {
+        return plain();
+    }
+    let url = url.trim();
+
+    if let Some(link) = doc.link_context_at(start) {
+        let slot = match link.slot {
+            LinkSlot::Text => Some(&link.text),
+            LinkSlot::Href => link.href.as_ref(),
+        };
+        if slot.is_some_and(|s| s.start <= start && end <= s.end) {
+            let edit = match &link.href {
+                Some(href) => (href.clone(), url.to_string()),
+                None => (link.range.end..link.range.end, format!("({url})")),
+            };
+            return (vec![edit], link.text.end);
+        }
+        return plain();
+    }
+
+    let selected = doc.slice_to_cow(start..end);
+    if !selected.is_empty() && !selected.contains('\n') {
+        let link = format!("[{selected}]({url})");
+        return (vec![(start..end, link)], start + 1 + selected.len());
+    }
+    plain()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editing::Cmd;
+
+    /// Paste `url` at the `|` marker (or over the `«…»` selection) and return
+    /// the text with the resulting caret marked by `|`.
+    fn paste(marked: &str, url: &str) -> String {
+        let (source, range) = if let Some(caret) = marked.find('|') {
+            (marked.replacen('|', "", 1), caret..caret)
+        } else {
+            let start = marked.find('«').unwrap();
+            let source = marked.replacen('«', "", 1).replacen('»', "", 1);
+            let end = marked.find('»').unwrap() - '«'.len_utf8();
+            (source, start..end)
+        };
+        let mut doc = Document::from_bytes(source.as_bytes()).unwrap();
+        doc.set_selection(range.clone());
+        let patch = doc.apply(Cmd::PasteUrl {
+            range,
+            url: url.to_string(),
+        });
+        let caret = patch.new_selection.start;
+        assert_eq!(patch.new_selection, caret..caret);
+        let mut text = doc.text();
+        text.insert(caret, '|');
+        text
+    }
+
+    #[test]
+    fn test_link_context_slots() {
+        let doc = Document::from_bytes(b"See [docs](http://a.b) and [here]\n").unwrap();
+
+        let text = doc.link_context_at(6).unwrap();
+        assert_eq!(text.slot, LinkSlot::Text);
+        assert_eq!(text.text, 5..9);
+        assert_eq!(text.href, Some(11..21));
+        assert_eq!(text.range, 4..22);
+
+        assert_eq!(doc.link_context_at(15).unwrap().slot, LinkSlot::Href);
+        assert_eq!(doc.link_context_at(2), None);
+
+        let bare = doc.link_context_at(30).unwrap();
+        assert_eq!(bare.text, 28..32);
+        assert_eq!(bare.href, None);
+    }
+
+    #[test]
+    fn test_no_context_in_wikilinks_checkboxes_or_code() {
+        let doc = Document::from_bytes(b"- [ ] [[Page]]\n\n```\n[x](y)\n```\n").unwrap();
+        assert_eq!(doc.link_context_at(3), None);
+        assert_eq!(doc.link_context_at(9), None);
+        assert_eq!(doc.link_context_at(21), None);
+    }
+
+    #[test]
+    fn test_paste_into_empty_link_moves_url_to_href() {
+        assert_eq!(
+            paste("Read [|]() now\n", "https://x.io"),
+            "Read [|](https://x.io) now\n"
+        );
+        assert_eq!(
+            paste("Read []( | ) now\n", "https://x.io"),
+            "Read [|](https://x.io) now\n"
+        );
+    }
+
+    #[test]
+    fn test_paste_replaces_existing_href_keeping_text() {
+        assert_eq!(
+            paste("[Docs|](http://old) x\n", "https://new.io/a?b=c"),
+            "[Docs|](https://new.io/a?b=c) x\n"
+        );
+    }
+
+    #[test]
+    fn test_paste_into_bare_brackets_adds_href() {
+        assert_eq!(
+            paste("A [ti|tle] b\n", "https://x.io"),
+            "A [title|](https://x.io) b\n"
+        );
+    }
+
+    #[test]
+    fn test_paste_over_selection_wraps_in_link() {
+        assert_eq!(
+            paste("Read «the docs» today\n", "https://x.io"),
+            "Read [the docs|](https://x.io) today\n"
+        );
+    }
+
+    #[test]
+    fn test_plain_paste_otherwise() {
+        assert_eq!(
+            paste("A [ti|tle] b\n", "not a url"),
+            "A [tinot a url|tle] b\n"
+        );
+        assert_eq!(
+            paste("Go | there\n", "https://x.io"),
+            "Go https://x.io| there\n"
+        );
+        assert_eq!(
+            paste("```\n[|]()\n```\n", "https://x.io"),
+            "```\n[https://x.io|]()\n```\n"
+        );
+    }
+
+    #[test]
+    fn test_looks_like_url() {
+        assert!(looks_like_url("https://example.com/path"));
+        assert!(looks_like_url("  ftp://host  "));
+        assert!(looks_like_url("mailto:me@example.com"));
+        assert!(!looks_like_url("example.com"));
+        assert!(!looks_like_url("two words://x"));
+        assert!(!looks_like_url("://x"));
+    }
+}
@@ -17,7 +17,7 @@
  * ### 2. Command-Based Editing
  * - All edits are represented as **Commands** (`Cmd` enum) that compile to **Deltas**
  * - Commands are applied immediately on every input event for authoritative model updates
- * - Supports undo/redo through Delta history (future enhancement)
+ * - Supports undo/redo by keeping the state before each edit (`history`)
  *
  * ### 3. Parsing with Rowan
  * - Uses **Rowan** (rust-analyzer's syntax tree library) for lossless parsing
@@ -40,7 +40,17 @@
  *
  * - **`document`**: Core `Document` type with xi-rope buffer and Rowan parsing
  * - **`commands`**: `Cmd` enum and delta compilation logic for all edit operations
+ * - **`counts`**: Streaming word/character/byte counts for blocks and selections
  * - **`anchors`**: Stable block ID system with range transformation and rebinding
+ * - **`history`**: Undo/redo stacks recorded by `Document::apply`
+ * - **`hit_test`**: Which block, and where in it, a tap at an offset or line/column lands
+ * - **`link_protocols`**: Which URL schemes bare URLs are linked for
+ * - **`link_slots`**: Caret context inside `[text](href)` links for smart URL paste
+ * - **`move_block`**: Moving list items and heading sections for drag-and-drop outlining
+ * - **`observe`**: Rate-limited snapshot notifications for front-ends
+ * - **`selection`**: Alt+Up style selection expansion through syntactic levels
+ * - **`tables`**: Converting pasted CSV/TSV to tables and tables back to CSV
+ * - **`toc`**: Tables of contents from headings, kept under a `<!-- toc -->` marker
  * - **`snapshot`**: Immutable view generation with `Block`s for UI consumption
  * - **`patch`**: Edit result metadata including changed ranges and new selection
  *
@@ -74,15 +84,47 @@
 // Module exports
 pub mod anchors;
 pub mod commands;
+pub mod counts;
 pub mod document;
+pub mod embeds;
+pub mod focus;
+pub mod frontmatter;
+pub mod history;
+pub mod hit_test;
+pub mod link_protocols;
+pub mod link_slots;
+pub mod move_block;
+pub mod observe;
+pub mod outline;
 pub mod patch;
+pub mod sections;
+pub mod selection;
 pub mod snapshot;
+pub mod tables;
+pub mod toc;
+pub mod zoom;
 
 // Public API re-exports
 pub use anchors::{Anchor, AnchorId};
-pub use commands::Cmd;
-pub use document::{Document, Marker};
+pub use commands::{BlockConversion, Cmd};
+pub use counts::TextCounts;
+pub use document::{Document, Marker, ReadOnlyError};
+pub use embeds::{EmbedSource, MAX_EMBED_DEPTH};
+pub use focus::{ActiveBlock, FocusHint};
+pub use frontmatter::FrontMatterFormat;
+pub use history::HISTORY_LIMIT;
+pub use hit_test::LocalPosition;
+pub use link_protocols::LinkProtocols;
+pub use link_slots::{LinkContext, LinkSlot};
+pub use markdown_neuraxis_syntax::{FenceAttribute, TableAlignment};
+pub use move_block::BlockPosition;
+pub use observe::{DEFAULT_PUBLISH_INTERVAL, SnapshotEvent};
+pub use outline::OutlineEntry;
 pub use patch::Patch;
+pub use sections::{Section, SectionItem};
 pub use snapshot::{
-    Block, BlockContent, BlockKind, CheckboxState, InlineNode, InlineSegment, Snapshot,
+    Block, BlockContent, BlockKind, CheckboxState, CodeLine, InlineNode, InlineSegment,
+    ListNumbering, Snapshot, TaskState,
 };
+pub use toc::{TOC_END_MARKER, TOC_MARKER};
+pub use zoom::SubDocumentView;
@@ -0,0 +1,364 @@
+//! Moving blocks for drag-and-drop outlining.
+//!
+//! [`Cmd::MoveBlock`](crate::editing::Cmd) moves whole lines: a list item
+//! with its nested items, a heading with its section (as for
+//! [`Document::zoom`]), or any other block on its own, together with the
+//! blank lines after it. The moved lines are re-indented to match the
+//! target block's first line, so an item dropped next to a nested item
+//! becomes nested too.
+//!
+//! Anchors inside the moved block go with it, keeping their IDs, so
+//! front-ends can keep a dragged block focused.
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+use crate::editing::anchors::{new_anchor_id, rebind_all_anchors};
+use crate::editing::commands::map_offset_through_edits;
+use crate::editing::zoom::find_zoomed;
+use crate::editing::{Anchor, AnchorId, Document};
+
+/// Where [`Cmd::MoveBlock`](crate::editing::Cmd) puts a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockPosition {
+    /// Just before the block with this anchor
+    Before(AnchorId),
+    /// Just after the block with this anchor, its nested items or section
+    After(AnchorId),
+}
+
+/// A planned move, in offsets before the edit.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct BlockMove {
+    /// Source removed
+    removed: Range<usize>,
+    /// The moved lines, within `removed`
+    lines: Range<usize>,
+    /// Insertion point
+    to: usize,
+    /// Text inserted at `to`
+    text: String,
+    /// Where the moved lines start within `text`
+    lead: usize,
+    /// Start of each moved line with the indentation change up to and
+    /// including that line
+    shifts: Vec<(usize, isize)>,
+}
+
+impl BlockMove {
+    /// Plan moving the block with `anchor_id` to `position`, or `None` if
+    /// either block is missing or the move changes nothing.
+    pub(crate) fn plan(
+        doc: &Document,
+        anchor_id: AnchorId,
+        position: BlockPosition,
+    ) -> Option<Self> {
+        let source = doc.text();
+        let snapshot = doc.snapshot();
+        let extent = |id| {
+            let blocks = find_zoomed(&snapshot.blocks, id)?;
+            let start = line_start(&source, blocks.first()?.node_range.start);
+            let end = after_blank_lines(&source, line_end(&source, blocks.last()?.node_range.end));
+            Some(start..end)
+        };
+        let lines = extent(anchor_id)?;
+        let (target_id, after) = match position {
+            BlockPosition::Before(id) => (id, false),
+            BlockPosition::After(id) => (id, true),
+        };
+        let target = extent(target_id)?;
+        let to = if after { target.end } else { target.start };
+        if (lines.start..=lines.end).contains(&to) {
+            return None;
+        }
+
+        // Re-indent to the target's first line
+        let indent_change = indent_len(&source[target.start..]) as isize
+            - indent_len(&source[lines.start..]) as isize;
+        let mut text = String::new();
+        let mut shifts = Vec::new();
+        let mut total = 0;
+        let mut line_start = lines.start;
+        for line in source[lines.clone()].split_inclusive('\n') {
+            let mut body = line;
+            if !line.trim().is_empty() {
+                if indent_change > 0 {
+                    text.extend(std::iter::repeat_n(' ', indent_change as usize));
+                    total += indent_change;
+                } else {
+                    let removed = indent_len(line).min(indent_change.unsigned_abs());
+                    body = &line[removed..];
+                    total -= removed as isize;
+                }
+            }
+            text.push_str(body);
+            shifts.push((line_start, total));
+            line_start += line.len();
+        }
+
+        // Keep every line ending in a newline, at either end of the note
+        let mut removed = lines.clone();
+        if !text.ends_with('\n') {
+            text.push('\n');
+            if removed.start > 0 {
+                removed.start -= 1;
+            }
+        }
+        let mut lead = 0;
+        if to == source.len() && !source.ends_with('\n') {
+            text.insert(0, '\n');
+            lead = 1;
+        }
+
+        Some(Self {
+            removed,
+            lines,
+            to,
+            text,
+            lead,
+            shifts,
+        })
+    }
+
+    /// The move as replacements, in order.
+    pub(crate) fn edits(&self) -> Vec<(Range<usize>, String)> {
+        let insert = (self.to..self.to, self.text.clone());
+        let delete = (self.removed.clone(), String::new());
+        if self.to <= self.removed.start {
+            vec![insert, delete]
+        } else {
+            vec![delete, insert]
+        }
+    }
+
+    /// Where `offset` ends up. Offsets in the moved lines go with them;
+    /// `is_end` puts an offset at the end of the lines with them too.
+    pub(crate) fn map(&self, offset: usize, is_end: bool) -> usize {
+        let moved = if is_end {
+            self.lines.start < offset && offset <= self.lines.end
+        } else {
+            self.lines.contains(&offset)
+        };
+        if !moved {
+            return map_offset_through_edits(offset, &self.edits(), !is_end);
+        }
+
+        let dest = if self.to <= self.removed.start {
+            self.to
+        } else {
+            self.to - self.removed.len()
+        };
+        // Offsets at a line start stay before any indentation added there
+        let shift = self
+            .shifts
+            .iter()
+            .take_while(|(start, _)| *start < offset || (*start == offset && is_end))
+            .last()
+            .map_or(0, |(_, shift)| *shift);
+        let offset = (dest + self.lead + offset - self.lines.start) as isize + shift;
+        offset.max(0) as usize
+    }
+
+    /// Give anchors that moved the IDs they had before the edit; the
+    /// rebinding after a reparse matches anchors by position.
+    pub(crate) fn restore_anchor_ids(&self, doc: &mut Document, before: &[Anchor]) {
+        rebind_all_anchors(doc);
+        let expected: Vec<(Range<usize>, AnchorId)> = before
+            .iter()
+            .map(|anchor| {
+                let range = self.map(anchor.range.start, false)..self.map(anchor.range.end, true);
+                (range, anchor.id)
+            })
+            .collect();
+        let unique_start = |start: usize| {
+            let mut matches = expected.iter().filter(|(range, _)| range.start == start);
+            match (matches.next(), matches.next()) {
+                (Some((_, id)), None) => Some(*id),
+                _ => None,
+            }
+        };
+
+        let mut assigned = HashSet::new();
+        let mut unmatched = Vec::new();
+        for (i, anchor) in doc.anchors.iter_mut().enumerate() {
+            let id = expected
+                .iter()
+                .find(|(range, _)| *range == anchor.range)
+                .map(|(_, id)| *id)
+                .or_else(|| unique_start(anchor.range.start));
+            match id {
+                Some(id) if assigned.insert(id) => anchor.id = id,
+                _ => unmatched.push(i),
+            }
+        }
+        // Anchors that matched nothing keep their ID unless a moved anchor
+        // took it
+        for i in unmatched {
+            let anchor = &mut doc.anchors[i];
+            if !assigned.insert(anchor.id) {
+                anchor.id = new_anchor_id(|id| assigned.contains(&id));
+                assigned.insert(anchor.id);
+            }
+        }
+    }
+}
+
+fn line_start(source: &str, offset: usize) -> usize {
+    source[..offset].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// End of the line `offset` is on, after its newline, or `offset` itself
+/// at a line start.
+fn line_end(source: &str, offset: usize) -> usize {
+    if offset == 0 || source[..offset].ends_with('\n') {
+        return offset;
+    }
+    source[offset..]
+        .find('\n')
+        .map_or(source.len(), |i| offset + i + 1)
+}
+
+/// `offset` moved past any blank lines starting there.
+fn after_blank_lines(source: &str, mut offset: usize) -> usize {
+    while let Some(line_len) = source[offset..].find('\n')
+        && source[offset..offset + line_len].trim().is_empty()
+    {
+        offset += line_len + 1;
+    }
+    offset
+}
+
+fn indent_len(line: &str) -> usize {
+    line.len() - line.trim_start_matches([' ', '\t']).len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editing::{Block, BlockContent, Cmd, InlineNode, InlineSegment};
+
+    /// Anchor of the block whose text starts with `text`
+    fn find(doc: &Document, text: &str) -> AnchorId {
+        fn walk(blocks: &[Block], text: &str) -> Option<AnchorId> {
+            blocks.iter().find_map(|block| {
+                let found = matches!(
+                    block.segments.first(),
+                    Some(InlineSegment { kind: InlineNode::Text(t), .. }) if t.starts_with(text)
+                );
+                match &block.content {
+                    BlockContent::Children(children) if !found => walk(children, text),
+                    _ => found.then_some(block.id),
+                }
+            })
+        }
+        walk(&doc.snapshot().blocks, text).unwrap()
+    }
+
+    fn move_block(
+        source: &str,
+        moved: &str,
+        position: fn(AnchorId) -> BlockPosition,
+        target: &str,
+    ) -> String {
+        let mut doc = Document::from_bytes(source.as_bytes()).unwrap();
+        let cmd = Cmd::MoveBlock {
+            anchor_id: find(&doc, moved),
+            to: position(find(&doc, target)),
+        };
+        doc.apply(cmd);
+        doc.text()
+    }
+
+    #[test]
+    fn test_move_list_item_with_children() {
+        let source = "- one\n  - child\n- two\n- three\n";
+        assert_eq!(
+            move_block(source, "one", BlockPosition::After, "three"),
+            "- two\n- three\n- one\n  - child\n"
+        );
+        assert_eq!(
+            move_block(source, "three", BlockPosition::Before, "one"),
+            "- three\n- one\n  - child\n- two\n"
+        );
+    }
+
+    #[test]
+    fn test_move_reindents_to_target() {
+        let source = "- one\n  - child\n- two\n  - nested\n";
+        assert_eq!(
+            move_block(source, "one", BlockPosition::After, "nested"),
+            "- two\n  - nested\n  - one\n    - child\n"
+        );
+        assert_eq!(
+            move_block(source, "nested", BlockPosition::Before, "one"),
+            "- nested\n- one\n  - child\n- two\n"
+        );
+    }
+
+    #[test]
+    fn test_move_heading_section() {
+        let source = "# A\n\nalpha\n\n# B\n\nbeta\n";
+        assert_eq!(
+            move_block(source, "B", BlockPosition::Before, "A"),
+            "# B\n\nbeta\n# A\n\nalpha\n\n"
+        );
+    }
+
+    #[test]
+    fn test_move_last_line_without_newline() {
+        assert_eq!(
+            move_block("- one\n- two", "two", BlockPosition::Before, "one"),
+            "- two\n- one"
+        );
+        assert_eq!(
+            move_block("- one\n- two", "one", BlockPosition::After, "two"),
+            "- two\n- one\n"
+        );
+    }
+
+    #[test]
+    fn test_move_into_itself_does_nothing() {
+        let source = "- one\n  - child\n- two\n";
+        assert_eq!(
+            move_block(source, "one", BlockPosition::Before, "child"),
+            source
+        );
+        assert_eq!(
+            move_block(source, "one", BlockPosition::Before, "two"),
+            source
+        );
+    }
+
+    #[test]
+    fn test_move_keeps_anchor_ids() {
+        let mut doc = Document::from_bytes(b"- one\n  - child\n- two\n- three\n").unwrap();
+        let one = find(&doc, "one");
+        let child = find(&doc, "child");
+        let two = find(&doc, "two");
+        let three = find(&doc, "three");
+
+        doc.apply(Cmd::MoveBlock {
+            anchor_id: one,
+            to: BlockPosition::After(three),
+        });
+
+        assert_eq!(doc.text(), "- two\n- three\n- one\n  - child\n");
+        assert_eq!(find(&doc, "one"), one);
+        assert_eq!(find(&doc, "child"), child);
+        assert_eq!(find(&doc, "two"), two);
+        assert_eq!(find(&doc, "three"), three);
+    }
+
+    #[test]
+    fn test_selection_moves_with_block() {
+        let mut doc = Document::from_bytes(b"- one\n- two\n").unwrap();
+        doc.set_selection(3..5);
+        let cmd = Cmd::MoveBlock {
+            anchor_id: find(&doc, "one"),
+            to: BlockPosition::After(find(&doc, "two")),
+        };
+        doc.apply(cmd);
+        assert_eq!(doc.text(), "- two\n- one\n");
+        assert_eq!(&doc.text()[doc.selection()], "ne");
+    }
+}
@@ -0,0 +1,178 @@
+//! Change notification for front-ends: [`Document::subscribe`] returns a
+//! channel that receives a fresh [`Snapshot`] after edits, instead of each
+//! front-end polling [`Document::version`].
+//!
+//! Rapid edits are coalesced: a subscriber gets at most one snapshot per
+//! interval, always of the latest text. The document owns no thread, so a
+//! change made inside the interval is held back until the next edit or
+//! [`Document::publish_pending`]; front-ends call that from a timer after
+//! the delay it returns.
+
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+use crate::editing::{Document, Snapshot};
+
+/// Shortest time between snapshots sent to a subscriber by default
+/// (about one per frame at 20fps).
+pub const DEFAULT_PUBLISH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A snapshot published after the document changed.
+#[derive(Debug, Clone)]
+pub struct SnapshotEvent {
+    /// Document version the snapshot shows
+    pub version: u64,
+    /// Shared by every subscriber it was sent to
+    pub snapshot: Arc<Snapshot>,
+}
+
+/// A document's subscribers.
+#[derive(Default)]
+pub(crate) struct Observers {
+    subscribers: Vec<Subscriber>,
+}
+
+struct Subscriber {
+    events: Sender<SnapshotEvent>,
+    interval: Duration,
+    last_sent: Option<Instant>,
+    /// Whether the document changed since the last snapshot sent
+    pending: bool,
+}
+
+impl Document {
+    /// Receive snapshots as the document changes, at most one per
+    /// [`DEFAULT_PUBLISH_INTERVAL`].
+    pub fn subscribe(&mut self) -> Receiver<SnapshotEvent> {
+        self.subscribe_with(DEFAULT_PUBLISH_INTERVAL)
+    }
+
+    /// [`subscribe`](Self::subscribe) with the shortest time between
+    /// snapshots.
+    pub fn subscribe_with(&mut self, interval: Duration) -> Receiver<SnapshotEvent> {
+        let (tx, rx) = mpsc::channel();
+        self.observers.subscribers.push(Subscriber {
+            events: tx,
+            interval,
+            last_sent: None,
+            pending: false,
+        });
+        rx
+    }
+
+    /// Send snapshots held back by the rate limit whose time has come.
+    /// Returns how long until the next held-back snapshot is due, or `None`
+    /// if nothing is waiting.
+    pub fn publish_pending(&mut self) -> Option<Duration> {
+        self.publish(Instant::now())
+    }
+
+    /// Record a change for every subscriber and send what is due.
+    pub(crate) fn notify_changed(&mut self) {
+        if self.observers.subscribers.is_empty() {
+            return;
+        }
+        for subscriber in &mut self.observers.subscribers {
+            subscriber.pending = true;
+        }
+        self.publish(Instant::now());
+    }
+
+    fn publish(&mut self, now: Instant) -> Option<Duration> {
+        let mut subscribers = std::mem::take(&mut self.observers.subscribers);
+        let mut snapshot = None;
+        let mut next_due: Option<Duration> = None;
+        // Subscribers that went away are dropped
+        subscribers.retain_mut(|subscriber| {
+            if !subscriber.pending {
+                return true;
+            }
+            let due = subscriber
+                .last_sent
+                .map_or(now, |sent| sent + subscriber.interval);
+            if due > now {
+                let wait = due - now;
+                next_due = Some(next_due.map_or(wait, |next| next.min(wait)));
+                return true;
+            }
+            let snapshot = snapshot
+                .get_or_insert_with(|| Arc::new(self.snapshot()))
+                .clone();
+            subscriber.pending = false;
+            subscriber.last_sent = Some(now);
+            subscriber
+                .events
+                .send(SnapshotEvent {
+                    version: self.version,
+                    snapshot,
+                })
+                .is_ok()
+        });
+        self.observers.subscribers = subscribers;
+        next_due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::editing::{Cmd, Document};
+    use std::time::Duration;
+
+    fn insert(doc: &mut Document, text: &str) {
+        doc.apply(Cmd::InsertText {
+            at: doc.len(),
+            text: text.to_string(),
+        });
+    }
+
+    #[test]
+    fn test_rapid_edits_are_coalesced() {
+        let mut doc = Document::from_bytes(b"# Notes").unwrap();
+        let events = doc.subscribe_with(Duration::from_secs(3600));
+
+        insert(&mut doc, "\n\n- one");
+        insert(&mut doc, "\n- two");
+        insert(&mut doc, "\n- three");
+
+        // The first change is sent at once, the rest wait for the interval
+        let sent: Vec<u64> = events.try_iter().map(|event| event.version).collect();
+        assert_eq!(sent, vec![1]);
+        let wait = doc.publish_pending().unwrap();
+        assert!(wait > Duration::from_secs(3500));
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_held_back_change_is_published_later() {
+        let mut doc = Document::from_bytes(b"a").unwrap();
+        let events = doc.subscribe_with(Duration::from_millis(20));
+        assert_eq!(doc.publish_pending(), None);
+
+        insert(&mut doc, "b");
+        insert(&mut doc, "c");
+        assert_eq!(events.try_iter().count(), 1);
+
+        std::thread::sleep(doc.publish_pending().unwrap());
+        assert_eq!(doc.publish_pending(), None);
+        let latest = events.try_recv().unwrap();
+        assert_eq!(latest.version, 2);
+        assert_eq!(latest.snapshot, std::sync::Arc::new(doc.snapshot()));
+
+        assert!(doc.undo().is_some());
+        std::thread::sleep(Duration::from_millis(20));
+        doc.publish_pending();
+        assert_eq!(events.try_recv().unwrap().version, 3);
+    }
+
+    #[test]
+    fn test_dropped_subscriber_is_removed() {
+        let mut doc = Document::from_bytes(b"a").unwrap();
+        drop(doc.subscribe_with(Duration::ZERO));
+        let events = doc.subscribe_with(Duration::ZERO);
+
+        insert(&mut doc, "b");
+        assert_eq!(doc.observers.subscribers.len(), 1);
+        assert_eq!(events.try_recv().unwrap().version, 1);
+    }
+}
@@ -0,0 +1,163 @@
+//! Heading outlines for table-of-contents sidebars.
+//!
+//! [`Snapshot::outline`] nests each heading under the closest earlier
+//! heading with a lower level, so a skipped level (`#` then `###`) nests one
+//! step deeper rather than leaving gaps. Headings inside list items and
+//! blockquotes are included. Entries carry the heading's anchor and source
+//! range so front-ends can jump to the section.
+
+use std::iter::Peekable;
+use std::ops::Range;
+
+use crate::editing::AnchorId;
+use crate::editing::snapshot::{Block, BlockContent, BlockKind, InlineNode, Snapshot};
+
+/// A heading in a note's outline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineEntry {
+    /// Heading level (1-6)
+    pub level: u8,
+    /// Heading text as displayed, without markers or formatting
+    pub text: String,
+    /// The heading block's anchor
+    pub anchor_id: AnchorId,
+    /// The heading block's full span in the source
+    pub byte_range: Range<usize>,
+    /// Headings in this section, in source order
+    pub children: Vec<OutlineEntry>,
+}
+
+impl Snapshot {
+    /// The note's headings as a tree, in source order.
+    pub fn outline(&self) -> Vec<OutlineEntry> {
+        let mut headings = Vec::new();
+        collect(&self.blocks, &mut headings);
+        nest(&mut headings.into_iter().peekable(), 0)
+    }
+}
+
+fn collect(blocks: &[Block], out: &mut Vec<OutlineEntry>) {
+    for block in blocks {
+        if let BlockKind::Heading { level } = block.kind {
+            let mut text = String::new();
+            for segment in &block.segments {
+                push_text(&segment.kind, &mut text);
+            }
+            out.push(OutlineEntry {
+                level,
+                text: text.trim().to_string(),
+                anchor_id: block.id,
+                byte_range: block.node_range.clone(),
+                children: Vec::new(),
+            });
+        }
+        if let BlockContent::Children(children) = &block.content {
+            collect(children, out);
+        }
+    }
+}
+
+fn push_text(node: &InlineNode, out: &mut String) {
+    match node {
+        InlineNode::Text(text) | InlineNode::Code(text) | InlineNode::Strikethrough(text) => {
+            out.push_str(text)
+        }
+        InlineNode::Strong(children) | InlineNode::Emphasis(children) => {
+            for child in children {
+                push_text(&child.kind, out);
+            }
+        }
+        InlineNode::WikiLink { target, alias } => out.push_str(alias.as_ref().unwrap_or(target)),
+        InlineNode::Link { text, .. } => out.push_str(text),
+        InlineNode::Image { alt, .. } => out.push_str(alt),
+        InlineNode::Tag { name } => {
+            out.push('#');
+            out.push_str(name);
+        }
+        InlineNode::HardBreak | InlineNode::SoftBreak => out.push(' '),
+    }
+}
+
+/// Take headings deeper than `parent_level` off the front of `headings`,
+/// each with the deeper headings that follow it as children.
+fn nest(
+    headings: &mut Peekable<impl Iterator<Item = OutlineEntry>>,
+    parent_level: u8,
+) -> Vec<OutlineEntry> {
+    let mut entries = Vec::new();
+    while let Some(mut entry) = headings.next_if(|heading| heading.level > parent_level) {
+        entry.children = nest(headings, entry.level);
+        entries.push(entry);
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editing::Document;
+
+    fn outline(source: &str) -> Vec<OutlineEntry> {
+        Document::from_bytes(source.as_bytes())
+            .unwrap()
+            .snapshot()
+            .outline()
+    }
+
+    /// (level, text) pairs, indented by depth
+    fn shape(entries: &[OutlineEntry], depth: usize, out: &mut Vec<String>) {
+        for entry in entries {
+            out.push(format!(
+                "{}{} {}",
+                "  ".repeat(depth),
+                entry.level,
+                entry.text
+            ));
+            shape(&entry.children, depth + 1, out);
+        }
+    }
+
+    #[test]
+    fn test_outline_nests_headings() {
+        let entries =
+            outline("# Title\n\n## Goals\n\n### Scope\n\n## Plan\n\n#### Deep\n\n# Appendix\n");
+        let mut lines = Vec::new();
+        shape(&entries, 0, &mut lines);
+        assert_eq!(
+            lines,
+            vec![
+                "1 Title",
+                "  2 Goals",
+                "    3 Scope",
+                "  2 Plan",
+                "    4 Deep",
+                "1 Appendix",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_outline_entry_text_and_position() {
+        let source = "Intro\n\n## The **big** [[Plan|plan]] #work\n\nBody\n";
+        let doc = Document::from_bytes(source.as_bytes()).unwrap();
+        let snapshot = doc.snapshot();
+        let entries = snapshot.outline();
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.level, 2);
+        assert_eq!(entry.text, "The big plan #work");
+        assert_eq!(
+            &source[entry.byte_range.clone()],
+            "## The **big** [[Plan|plan]] #work\n"
+        );
+        assert_eq!(entry.anchor_id, snapshot.blocks[1].id);
+    }
+
+    #[test]
+    fn test_outline_starting_below_top_level() {
+        let mut lines = Vec::new();
+        shape(&outline("### Late\n\n## Early\n\n- item\n"), 0, &mut lines);
+        assert_eq!(lines, vec!["3 Late", "2 Early"]);
+        assert!(outline("Just text\n").is_empty());
+    }
+}
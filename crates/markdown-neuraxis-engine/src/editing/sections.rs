@@ -0,0 +1,155 @@
+//! Heading sections for front-ends that indent or fold content by heading.
+//!
+//! The snapshot's blocks are flat at the top level: a `## Section` heading is
+//! a sibling of the paragraphs and lists under it. [`Snapshot::sections`]
+//! groups them instead, each heading owning the blocks up to the next
+//! heading of the same or a higher level. Subsections nest like
+//! [`Snapshot::outline`]: under the closest earlier heading with a lower
+//! level. Headings inside list items and blockquotes stay part of their
+//! block and don't start sections.
+
+use std::iter::Peekable;
+
+use crate::editing::AnchorId;
+use crate::editing::snapshot::{Block, BlockKind, Snapshot};
+
+/// A heading and the content under it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section<'a> {
+    /// The heading's anchor, which identifies the section
+    pub id: AnchorId,
+    /// Heading level (1-6)
+    pub level: u8,
+    /// How many sections enclose this one (0 for a top-level section)
+    pub depth: usize,
+    /// The heading block
+    pub heading: &'a Block,
+    /// Blocks and subsections, in source order
+    pub children: Vec<SectionItem<'a>>,
+}
+
+/// Something in a section, or before the first heading.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SectionItem<'a> {
+    Block(&'a Block),
+    Section(Section<'a>),
+}
+
+impl Snapshot {
+    /// Top-level blocks grouped under their headings, in source order.
+    /// Blocks before the first heading come first, outside any section.
+    pub fn sections(&self) -> Vec<SectionItem<'_>> {
+        nest(&mut self.blocks.iter().peekable(), 0, 0)
+    }
+}
+
+fn heading_level(block: &Block) -> Option<u8> {
+    match block.kind {
+        BlockKind::Heading { level } => Some(level),
+        _ => None,
+    }
+}
+
+/// Take blocks off the front of `blocks` until a heading at `parent_level`
+/// or higher, each deeper heading with what follows it as a section.
+fn nest<'a>(
+    blocks: &mut Peekable<impl Iterator<Item = &'a Block>>,
+    parent_level: u8,
+    depth: usize,
+) -> Vec<SectionItem<'a>> {
+    let mut items = Vec::new();
+    while let Some(block) =
+        blocks.next_if(|block| heading_level(block).is_none_or(|level| level > parent_level))
+    {
+        let item = match heading_level(block) {
+            Some(level) => SectionItem::Section(Section {
+                id: block.id,
+                level,
+                depth,
+                heading: block,
+                children: nest(blocks, level, depth + 1),
+            }),
+            None => SectionItem::Block(block),
+        };
+        items.push(item);
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editing::Document;
+    use crate::editing::snapshot::BlockContent;
+
+    fn text<'a>(source: &'a str, block: &Block) -> &'a str {
+        source[block.node_range.clone()].trim()
+    }
+
+    #[test]
+    fn test_sections_group_blocks_under_headings() {
+        let source = "Intro\n\n# Title\n\nText\n\n## Goals\n\n- one\n- two\n\n#### Deep\n\nMore\n\n## Plan\n\n# Appendix\n";
+        let snapshot = Document::from_bytes(source.as_bytes()).unwrap().snapshot();
+        let sections = snapshot.sections();
+
+        let SectionItem::Block(intro) = &sections[0] else {
+            panic!("expected the intro paragraph first");
+        };
+        assert_eq!(text(source, intro), "Intro");
+
+        let SectionItem::Section(title) = &sections[1] else {
+            panic!("expected the title section");
+        };
+        assert_eq!((title.level, title.depth), (1, 0));
+        assert_eq!(title.id, title.heading.id);
+        let children: Vec<String> = title
+            .children
+            .iter()
+            .map(|item| match item {
+                SectionItem::Block(block) => text(source, block).to_string(),
+                SectionItem::Section(section) => format!(
+                    "{} ({} items)",
+                    text(source, section.heading),
+                    section.children.len()
+                ),
+            })
+            .collect();
+        assert_eq!(
+            children,
+            vec!["Text", "## Goals (2 items)", "## Plan (0 items)"]
+        );
+
+        let SectionItem::Section(goals) = &title.children[1] else {
+            panic!("expected the goals section");
+        };
+        assert!(matches!(
+            goals.children[0],
+            SectionItem::Block(Block {
+                content: BlockContent::Children(_),
+                ..
+            })
+        ));
+        let SectionItem::Section(deep) = &goals.children[1] else {
+            panic!("expected the skipped-level section");
+        };
+        assert_eq!((deep.level, deep.depth), (4, 2));
+
+        let SectionItem::Section(appendix) = &sections[2] else {
+            panic!("expected the appendix section");
+        };
+        assert_eq!((appendix.level, appendix.depth), (1, 0));
+        assert_eq!(sections.len(), 3);
+    }
+
+    #[test]
+    fn test_headings_in_list_items_do_not_start_sections() {
+        let source = "- # Not a section\n\nText\n";
+        let snapshot = Document::from_bytes(source.as_bytes()).unwrap().snapshot();
+        assert!(
+            snapshot
+                .sections()
+                .iter()
+                .all(|item| matches!(item, SectionItem::Block(_)))
+        );
+    }
+}
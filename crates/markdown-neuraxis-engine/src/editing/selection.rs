@@ -0,0 +1,366 @@
+//! Progressive selection expansion (Alt+Up style).
+//!
+//! Each call grows a selection to the next enclosing syntactic level:
+//!
+//! 1. the word under the caret
+//! 2. the inside of an inline node (`**bold**`, `[[link]]`, …), then the
+//!    whole node with its delimiters
+//! 3. the sentence
+//! 4. the block content, without its marker (`# `, `- [ ] `, `> `, fences)
+//! 5. the block with its marker, then any enclosing list or list item
+//! 6. the heading section containing the block, then its parent sections
+//! 7. the whole document
+//!
+//! Levels come from the syntax tree, so a level that doesn't exist at the
+//! caret (no inline node, a block with no marker) is simply skipped.
+
+use std::ops::Range;
+
+use markdown_neuraxis_syntax::heading::heading_level;
+use markdown_neuraxis_syntax::{SyntaxKind, SyntaxNode, parse};
+
+use crate::editing::Document;
+
+impl Document {
+    /// The smallest syntactic range that strictly contains `range`.
+    ///
+    /// Returns `range` unchanged once it covers the whole document.
+    pub fn expand_selection(&self, range: Range<usize>) -> Range<usize> {
+        let text = self.text();
+        let start = floor_char_boundary(&text, range.start.min(text.len()));
+        let end = floor_char_boundary(&text, range.end.min(text.len())).max(start);
+        let root = parse(&text);
+
+        let mut candidates = vec![word_at(&text, start..end), 0..text.len()];
+        let mut innermost_block = None;
+        for node in root.descendants() {
+            let node_range = node_range(&node);
+            if !(node_range.start <= start && end <= node_range.end) {
+                continue;
+            }
+            if is_inline(node.kind()) {
+                candidates.extend(delimited_content(&node, &text));
+                candidates.push(trim_end(&text, node_range));
+            } else if node.kind() != SyntaxKind::ROOT {
+                candidates.extend(block_content(&node, &text));
+                candidates.push(trim_end(&text, node_range));
+                innermost_block = Some(node);
+            }
+        }
+        if let Some(block) = innermost_block
+            && matches!(block.kind(), SyntaxKind::PARAGRAPH | SyntaxKind::HEADING)
+            && let Some(content) = block_content(&block, &text)
+        {
+            candidates.extend(sentence_at(&text, content, start..end));
+        }
+        candidates.extend(sections(&root, &text));
+
+        candidates
+            .into_iter()
+            .filter(|c| c.start <= start && end <= c.end && c.len() > end - start)
+            .min_by_key(|c| c.len())
+            .unwrap_or(start..end)
+    }
+}
+
+fn is_inline(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::WIKILINK
+            | SyntaxKind::CODE_SPAN
+            | SyntaxKind::LINK
+            | SyntaxKind::EMPHASIS
+            | SyntaxKind::STRONG
+            | SyntaxKind::STRIKETHROUGH
+            | SyntaxKind::IMAGE
+            | SyntaxKind::AUTOLINK
+            | SyntaxKind::PROPERTY
+            | SyntaxKind::BLOCK_REF
+            | SyntaxKind::CHECKBOX
+            | SyntaxKind::INLINE
+            | SyntaxKind::LINK_DESTINATION
+            | SyntaxKind::LINK_TITLE
+            | SyntaxKind::WIKILINK_TARGET
+            | SyntaxKind::WIKILINK_SECTION
+            | SyntaxKind::WIKILINK_ALIAS
+            | SyntaxKind::CODE_SPAN_CONTENT
+    )
+}
+
+fn node_range(node: &SyntaxNode) -> Range<usize> {
+    let range = node.text_range();
+    range.start().into()..range.end().into()
+}
+
+fn trim_end(text: &str, range: Range<usize>) -> Range<usize> {
+    let trimmed = text[range.clone()].trim_end();
+    range.start..range.start + trimmed.len()
+}
+
+fn floor_char_boundary(text: &str, mut offset: usize) -> usize {
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '\''
+}
+
+/// The run of word characters around `range`, if `range` lies in one word.
+fn word_at(text: &str, range: Range<usize>) -> Range<usize> {
+    if !text[range.clone()].chars().all(is_word_char) {
+        return range;
+    }
+    let start = text[..range.start]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| is_word_char(c))
+        .last()
+        .map_or(range.start, |(i, _)| i);
+    let end = range.end
+        + text[range.end..]
+            .char_indices()
+            .find(|&(_, c)| !is_word_char(c))
+            .map_or(text.len() - range.end, |(i, _)| i);
+    start..end
+}
+
+/// Inside an inline node's opening and closing delimiter tokens.
+fn delimited_content(node: &SyntaxNode, text: &str) -> Option<Range<usize>> {
+    let is_delimiter = |kind| {
+        matches!(
+            kind,
+            SyntaxKind::LBRACKET
+                | SyntaxKind::RBRACKET
+                | SyntaxKind::LPAREN
+                | SyntaxKind::RPAREN
+                | SyntaxKind::STAR
+                | SyntaxKind::UNDERSCORE
+                | SyntaxKind::TILDE
+                | SyntaxKind::BACKTICK
+                | SyntaxKind::EXCLAIM
+                | SyntaxKind::LT
+        )
+    };
+    let children: Vec<_> = node.children_with_tokens().collect();
+    let first = children.iter().position(|c| !is_delimiter(c.kind()))?;
+    let last = children.iter().rposition(|c| !is_delimiter(c.kind()))?;
+    if first == 0 && last == children.len() - 1 {
+        return None;
+    }
+    let start = usize::from(children[first].text_range().start());
+    let end = usize::from(children[last].text_range().end());
+    Some(trim_end(text, start..end))
+}
+
+/// A block without its marker and trailing newline.
+///
+/// - headings skip `#`s, quotes skip `>`, list items skip the bullet or
+///   number and any checkbox
+/// - fenced code is the lines between the fences
+fn block_content(node: &SyntaxNode, text: &str) -> Option<Range<usize>> {
+    let range = node_range(node);
+    let start = match node.kind() {
+        SyntaxKind::FENCED_CODE => {
+            let source = &text[range.clone()];
+            let body_start = range.start + source.find('\n')? + 1;
+            let body = text[body_start..range.end].trim_end();
+            let closing = body.rfind('\n').map_or(0, |i| i + 1);
+            let end = if body[closing..].trim_start().starts_with(['`', '~']) {
+                body_start + closing
+            } else {
+                body_start + body.len()
+            };
+            return Some(trim_end(text, body_start..end.max(body_start)));
+        }
+        SyntaxKind::LIST_ITEM => node
+            .children()
+            .find(|child| child.kind() != SyntaxKind::CHECKBOX)
+            .map(|child| node_range(&child).start)?,
+        SyntaxKind::HEADING | SyntaxKind::BLOCK_QUOTE => node
+            .children_with_tokens()
+            .find(|c| {
+                !matches!(
+                    c.kind(),
+                    SyntaxKind::HASH | SyntaxKind::GT | SyntaxKind::WHITESPACE
+                )
+            })
+            .map(|c| usize::from(c.text_range().start()))?,
+        _ => range.start,
+    };
+    Some(trim_end(text, start..range.end))
+}
+
+/// The sentence within `content` containing `range`.
+fn sentence_at(text: &str, content: Range<usize>, range: Range<usize>) -> Option<Range<usize>> {
+    sentence_ranges(text, content)
+        .into_iter()
+        .find(|sentence| sentence.end >= range.end)
+}
+
+/// The sentences of `content`, in order. A sentence ends at `.`, `!` or
+/// `?` followed by whitespace; the whitespace between sentences is left
+/// out, and text after the last one is a sentence of its own.
+pub(crate) fn sentence_ranges(text: &str, content: Range<usize>) -> Vec<Range<usize>> {
+    let source = &text[content.clone()];
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = source.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let ends_sentence = matches!(c, '.' | '!' | '?')
+            && chars.peek().is_none_or(|&(_, next)| next.is_whitespace());
+        if !ends_sentence {
+            continue;
+        }
+        let end = i + c.len_utf8();
+        sentences.push(content.start + start..content.start + end);
+        start = end + (source[end..].len() - source[end..].trim_start().len());
+    }
+    if start < source.len() || sentences.is_empty() {
+        sentences.push(content.start + start..content.end);
+    }
+    sentences
+}
+
+/// Every heading section: a heading plus everything up to the next heading
+/// of the same or higher level.
+fn sections(root: &SyntaxNode, text: &str) -> Vec<Range<usize>> {
+    let headings: Vec<(usize, usize)> = root
+        .children()
+        .filter_map(|node| {
+            let level = usize::from(heading_level(&node)?);
+            Some((node_range(&node).start, level))
+        })
+        .collect();
+
+    headings
+        .iter()
+        .enumerate()
+        .map(|(i, &(start, level))| {
+            let end = headings[i + 1..]
+                .iter()
+                .find(|&&(_, other)| other <= level)
+                .map_or(text.len(), |&(next, _)| next);
+            trim_end(text, start..end)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Expand from the `|` caret (or `«…»` selection) until the whole
+    /// document is selected, returning each selected slice.
+    fn expansions(marked: &str) -> Vec<String> {
+        let (source, mut range) = if let Some(caret) = marked.find('|') {
+            (marked.replacen('|', "", 1), caret..caret)
+        } else {
+            let start = marked.find('«').unwrap();
+            let source = marked.replacen('«', "", 1).replacen('»', "", 1);
+            let end = marked.find('»').unwrap() - '«'.len_utf8();
+            (source, start..end)
+        };
+        let doc = Document::from_bytes(source.as_bytes()).unwrap();
+        let mut steps = Vec::new();
+        loop {
+            let next = doc.expand_selection(range.clone());
+            if next == range {
+                return steps;
+            }
+            steps.push(source[next.clone()].to_string());
+            range = next;
+        }
+    }
+
+    #[test]
+    fn test_word_inline_sentence_block() {
+        let steps = expansions("# T\n\nFirst one. Then **bo|ld text** here!\n");
+        assert_eq!(
+            steps,
+            [
+                "bold",
+                "bold text",
+                "**bold text**",
+                "Then **bold text** here!",
+                "First one. Then **bold text** here!",
+                "# T\n\nFirst one. Then **bold text** here!",
+                "# T\n\nFirst one. Then **bold text** here!\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_link_destination_then_link() {
+        let steps = expansions("Go [there](notes/a|.md \"A\") now.\n");
+        assert_eq!(
+            steps,
+            [
+                "a",
+                "notes/a.md",
+                "there](notes/a.md \"A\"",
+                "[there](notes/a.md \"A\")",
+                "Go [there](notes/a.md \"A\") now.",
+                "Go [there](notes/a.md \"A\") now.\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_item_content_then_marker() {
+        let steps = expansions("- [ ] Buy |milk\n- Other\n");
+        assert_eq!(
+            steps,
+            [
+                "milk",
+                "Buy milk",
+                "- [ ] Buy milk",
+                "- [ ] Buy milk\n- Other",
+                "- [ ] Buy milk\n- Other\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_heading_and_nested_sections() {
+        let source = "# A\n\n## B\n\nText |here.\n\n## C\n\nMore.\n";
+        let steps = expansions(source);
+        assert_eq!(
+            &steps[2..],
+            [
+                "## B\n\nText here.",
+                "# A\n\n## B\n\nText here.\n\n## C\n\nMore.",
+                "# A\n\n## B\n\nText here.\n\n## C\n\nMore.\n",
+            ]
+        );
+
+        let steps = expansions("## Hea|ding text\n");
+        assert_eq!(steps[..3], ["Heading", "Heading text", "## Heading text"]);
+    }
+
+    #[test]
+    fn test_code_fence_content_then_fence() {
+        let steps = expansions("```rust\nlet |x = 1;\n```\n");
+        assert_eq!(
+            steps,
+            [
+                "x",
+                "let x = 1;",
+                "```rust\nlet x = 1;\n```",
+                "```rust\nlet x = 1;\n```\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_selection_already_covering_word() {
+        let steps = expansions("Say «hello» [[wiki page]]\n");
+        assert_eq!(steps[0], "Say hello [[wiki page]]");
+        assert_eq!(
+            expansions("Say hello [[wiki |page]]\n")[..3],
+            ["page", "wiki page", "[[wiki page]]"]
+        );
+    }
+}
@@ -9,11 +9,24 @@
 //! - Editor gets clean primitives without understanding markdown syntax
 //! - Use segments for both rendering and editing ranges
 
-use std::ops::Range;
+use std::collections::HashSet;
+use std::ops::{Range, RangeInclusive};
 
-use markdown_neuraxis_syntax::{SyntaxElement, SyntaxKind, SyntaxNode, parse};
+use markdown_neuraxis_syntax::{
+    FenceAttribute, FenceInfo, SyntaxElement, SyntaxKind, SyntaxNode, TableAlignment, parse, visit,
+};
+use relative_path::RelativePath;
 
-use crate::editing::{Anchor, AnchorId};
+use markdown_neuraxis_syntax::code_span::code_span_text;
+use markdown_neuraxis_syntax::heading::heading_level;
+use markdown_neuraxis_syntax::tag::tag_name;
+use markdown_neuraxis_syntax::wikilink::WikiLinkParts;
+
+use crate::editing::anchors::stable_hash;
+use crate::editing::embeds::EmbedSource;
+use crate::editing::focus::{ActiveBlock, FocusHint, mark_focus};
+use crate::editing::{Anchor, AnchorId, FrontMatterFormat};
+use crate::notices::{Notice, notify};
 
 /// Content of a block: either leaf (no children) or nested children
 #[derive(Debug, Clone, PartialEq)]
@@ -30,7 +43,8 @@ pub enum BlockContent {
 pub struct InlineSegment {
     /// The kind of segment with its content
     pub kind: InlineNode,
-    /// Byte range in source (for verification/debugging)
+    /// Byte range in source, markup included (e.g. `[[Page]]` for a wiki
+    /// link). See [`Block::local_range`] for the range within its block.
     pub range: Range<usize>,
 }
 
@@ -40,14 +54,15 @@ pub enum InlineNode {
     /// Plain text content
     Text(String),
     /// Strong emphasis (**text**) - contains children for nested formatting
-    Strong(Vec<InlineNode>),
+    Strong(Vec<InlineSegment>),
     /// Emphasis (*text*) - contains children for nested formatting
-    Emphasis(Vec<InlineNode>),
+    Emphasis(Vec<InlineSegment>),
     /// Inline code (`code`) - leaf node
     Code(String),
     /// Strikethrough (~~text~~) - leaf node for now
     Strikethrough(String),
-    /// Wiki link [[target]] or [[target|alias]]
+    /// Wiki link [[target]] or [[target|alias]]: link to `target`, show
+    /// `alias` if there is one. Both are trimmed; an empty alias is `None`.
     WikiLink {
         target: String,
         alias: Option<String>,
@@ -56,6 +71,8 @@ pub enum InlineNode {
     Link { text: String, url: String },
     /// Image ![alt](url)
     Image { alt: String, url: String },
+    /// Tag #name
+    Tag { name: String },
     /// Hard line break (two trailing spaces + newline)
     HardBreak,
     /// Soft line break (newline absorbed during line wrapping, renders as space)
@@ -71,6 +88,47 @@ pub struct CheckboxState {
     pub byte_range: Range<usize>,
 }
 
+/// Task state of a list item, from its checkbox or a `TODO`/`DOING`/
+/// `DONE`/`LATER` keyword (the keyword wins when both are present)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskState {
+    /// `TODO` or an unchecked `[ ]`
+    Todo,
+    /// `DOING`
+    Doing,
+    /// `DONE` or a checked `[x]`
+    Done,
+    /// `LATER`
+    Later,
+}
+
+impl TaskState {
+    /// Whether the task still needs doing (anything but `Done`)
+    pub fn is_open(self) -> bool {
+        self != TaskState::Done
+    }
+
+    /// The state's keyword, e.g. `DOING`
+    pub fn keyword(self) -> &'static str {
+        match self {
+            TaskState::Todo => "TODO",
+            TaskState::Doing => "DOING",
+            TaskState::Done => "DONE",
+            TaskState::Later => "LATER",
+        }
+    }
+
+    pub(crate) fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword {
+            "TODO" => Some(TaskState::Todo),
+            "DOING" => Some(TaskState::Doing),
+            "DONE" => Some(TaskState::Done),
+            "LATER" => Some(TaskState::Later),
+            _ => None,
+        }
+    }
+}
+
 /// The kind of block
 #[derive(Debug, Clone, PartialEq)]
 pub enum BlockKind {
@@ -82,19 +140,59 @@ pub enum BlockKind {
     ListItem {
         marker: String,
         checkbox: Option<CheckboxState>,
+        /// Task state from the checkbox or state keyword; `None` for plain
+        /// items. The keyword stays in the item's segments.
+        task_state: Option<TaskState>,
+        /// Display label for ordered items (e.g. `b.`, `iv.`, `1.2`) when a
+        /// [`ListNumbering`] style other than `Source` has been applied;
+        /// `None` means render the source marker.
+        ordinal: Option<String>,
     },
     /// Blockquote (can span multiple lines)
     BlockQuote,
     /// Paragraph
-    Paragraph,
+    Paragraph {
+        /// Source ranges splitting a long paragraph at line and sentence
+        /// ends so front-ends can lay out and virtualize it piecewise.
+        /// Empty unless the paragraph exceeds [`LONG_PARAGRAPH_BYTES`].
+        /// Edits still target the paragraph as a whole.
+        chunks: Vec<Range<usize>>,
+    },
+    /// A paragraph that is only a note embed, `![[Note]]` or
+    /// `![[Note#Heading]]`. Its segments are the link as written; it has
+    /// the embedded blocks as children once
+    /// [`Snapshot::resolve_embeds`] has loaded them.
+    Embed {
+        /// Note name or path as written
+        target: String,
+        /// Heading whose section is embedded, if any
+        heading: Option<String>,
+        /// Where the embedded blocks came from; `None` until resolved, or if
+        /// the note or heading wasn't found
+        source: Option<EmbedSource>,
+    },
     /// ATX heading
     Heading { level: u8 },
     /// Fenced code block
-    FencedCode { language: Option<String> },
+    FencedCode {
+        language: Option<String>,
+        /// Info string attributes after the language (`title="main.rs"`)
+        attributes: Vec<FenceAttribute>,
+        /// 1-based code lines to highlight, from `{1,4-6}` in the info string
+        highlight_lines: Vec<RangeInclusive<u32>>,
+    },
     /// Thematic break
     ThematicBreak,
-    /// Table container
-    Table,
+    /// Front matter metadata at the start of the note; read its fields with
+    /// [`Document::frontmatter`](crate::editing::Document::frontmatter)
+    FrontMatter { format: FrontMatterFormat },
+    /// Table container (wraps TABLE_ROWs)
+    Table {
+        /// Number of rows, header included
+        rows: usize,
+        /// Column alignments from the delimiter row, one per column
+        alignments: Vec<TableAlignment>,
+    },
     /// Table row (in head or body)
     TableRow { is_header: bool },
     /// Table cell
@@ -113,6 +211,10 @@ pub struct Block {
     /// Inline content for rendering. Top-level siblings with byte ranges;
     /// each segment's InlineNode may contain recursive nested formatting.
     pub segments: Vec<InlineSegment>,
+    /// Whether the block is folded (see [`Document::set_collapsed`](crate::editing::Document::set_collapsed))
+    pub collapsed: bool,
+    /// Whether to dim the block in focus mode (see [`crate::editing::focus`])
+    pub focus: FocusHint,
     /// Block content (text or children)
     pub content: BlockContent,
 }
@@ -139,20 +241,306 @@ impl Block {
             }
         }
     }
+
+    /// `segment`'s range relative to the start of this block's content
+    /// (`content_range().start`), for segments of this block or nested in
+    /// them.
+    pub fn local_range(&self, segment: &InlineSegment) -> Range<usize> {
+        let start = self.content_range().start;
+        segment.range.start - start..segment.range.end - start
+    }
+
+    /// The inline segments that fall within `range`, e.g. one chunk of a
+    /// long paragraph. Text segments are trimmed to the range; other
+    /// segments belong to the range containing their start.
+    pub fn segments_in(&self, range: Range<usize>) -> Vec<InlineSegment> {
+        let mut result = Vec::new();
+        for segment in &self.segments {
+            match &segment.kind {
+                InlineNode::Text(text) if text.len() == segment.range.len() => {
+                    let start = segment.range.start.max(range.start);
+                    let end = segment.range.end.min(range.end);
+                    if start < end {
+                        let offset = segment.range.start;
+                        result.push(InlineSegment {
+                            kind: InlineNode::Text(text[start - offset..end - offset].to_string()),
+                            range: start..end,
+                        });
+                    }
+                }
+                _ if range.contains(&segment.range.start) => result.push(segment.clone()),
+                _ => {}
+            }
+        }
+        result
+    }
+
+    /// Lines of a fenced or indented code block, numbered from 1, or none
+    /// for other blocks. Front-ends share this for line numbers and highlighting.
+    pub fn code_lines(&self) -> Vec<CodeLine> {
+        let BlockKind::FencedCode {
+            highlight_lines, ..
+        } = &self.kind
+        else {
+            return Vec::new();
+        };
+        let mut lines = Vec::new();
+        for segment in &self.segments {
+            if let InlineNode::Text(text) = &segment.kind {
+                // Indented code has a segment per line, empty when blank
+                let text_lines = text.lines().chain(text.is_empty().then_some(""));
+                for (number, line) in (lines.len() as u32 + 1..).zip(text_lines) {
+                    lines.push(CodeLine {
+                        number,
+                        text: line.to_string(),
+                        highlighted: highlight_lines.iter().any(|range| range.contains(&number)),
+                    });
+                }
+            }
+        }
+        lines
+    }
+}
+
+/// A line of a fenced code block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeLine {
+    /// 1-based line number within the block
+    pub number: u32,
+    /// The line without its newline
+    pub text: String,
+    /// Whether the info string's `{1,4-6}` marks this line
+    pub highlighted: bool,
 }
 
+/// Paragraphs with more content than this are split into chunks.
+pub const LONG_PARAGRAPH_BYTES: usize = 4096;
+
+/// Preferred size of each chunk of a long paragraph.
+const PARAGRAPH_CHUNK_BYTES: usize = 1024;
+
 /// Tree-structured document snapshot
 #[derive(Debug, Clone, PartialEq)]
 pub struct Snapshot {
     /// Root-level blocks
     pub blocks: Vec<Block>,
+    /// The block marked with
+    /// [`Document::set_active_block`](crate::editing::Document::set_active_block),
+    /// split into lines and sentences for focus and typewriter mode
+    pub active: Option<ActiveBlock>,
+}
+
+/// Display numbering style for ordered lists.
+///
+/// The source always keeps plain CommonMark numbers; styles only change the
+/// labels front-ends render (see [`Snapshot::apply_list_numbering`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ListNumbering {
+    /// Render markers as written
+    #[default]
+    Source,
+    /// `1.` then `a.` then `i.` by ordered-list depth, repeating every three levels
+    Nested,
+    /// Outline numbers: `1.`, `1.1`, `1.1.1`
+    Outline,
+}
+
+impl ListNumbering {
+    /// Label for an item given the numbers of it and its ordered ancestors
+    /// (outermost first).
+    fn label(self, path: &[u64]) -> String {
+        let n = *path.last().unwrap_or(&1);
+        match self {
+            ListNumbering::Source => format!("{n}."),
+            ListNumbering::Nested => match (path.len() - 1) % 3 {
+                0 => format!("{n}."),
+                1 => format!("{}.", alpha_ordinal(n)),
+                _ => format!("{}.", roman_ordinal(n)),
+            },
+            ListNumbering::Outline if path.len() == 1 => format!("{n}."),
+            ListNumbering::Outline => path
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join("."),
+        }
+    }
+}
+
+/// `1 → a`, `26 → z`, `27 → aa`
+fn alpha_ordinal(mut n: u64) -> String {
+    let mut letters = Vec::new();
+    while n > 0 {
+        n -= 1;
+        letters.push(b'a' + (n % 26) as u8);
+        n /= 26;
+    }
+    letters.reverse();
+    String::from_utf8(letters).unwrap_or_default()
+}
+
+/// Lower-case roman numerals; falls back to decimal past 3999 or for 0.
+fn roman_ordinal(n: u64) -> String {
+    if n == 0 || n > 3999 {
+        return n.to_string();
+    }
+    const NUMERALS: &[(u64, &str)] = &[
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+    let mut rest = n;
+    let mut out = String::new();
+    for &(value, numeral) in NUMERALS {
+        while rest >= value {
+            out.push_str(numeral);
+            rest -= value;
+        }
+    }
+    out
+}
+
+/// An ordered list item with its position in the list numbering.
+pub(crate) struct NumberedItem {
+    pub(crate) node_range: Range<usize>,
+    pub(crate) marker: String,
+    /// Where the item text starts (after marker and checkbox)
+    pub(crate) content_start: usize,
+    /// Sequential number (list start + position)
+    pub(crate) number: u64,
+    pub(crate) label: String,
+}
+
+/// Walk ordered list items in document order, numbering each from its
+/// list's first marker (CommonMark: `1. 1. 1.` displays as 1, 2, 3).
+fn visit_ordered_items(
+    blocks: &mut [Block],
+    path: &mut Vec<u64>,
+    visit: &mut impl FnMut(&mut Block, &[u64]),
+) {
+    for block in blocks {
+        let ordered = block.kind == BlockKind::List { ordered: true };
+        let BlockContent::Children(children) = &mut block.content else {
+            continue;
+        };
+        if !ordered {
+            visit_ordered_items(children, path, visit);
+            continue;
+        }
+
+        let start = children
+            .first()
+            .and_then(|first| match &first.kind {
+                BlockKind::ListItem { marker, .. } => marker
+                    .trim()
+                    .trim_end_matches(['.', ')'])
+                    .parse::<u64>()
+                    .ok(),
+                _ => None,
+            })
+            .unwrap_or(1);
+        for (i, item) in children.iter_mut().enumerate() {
+            path.push(start + i as u64);
+            visit(item, path);
+            if let BlockContent::Children(nested) = &mut item.content {
+                visit_ordered_items(nested, path, visit);
+            }
+            path.pop();
+        }
+    }
+}
+
+impl Snapshot {
+    /// Set display labels on ordered list items for the given style.
+    ///
+    /// `ListNumbering::Source` clears them so the source markers are shown.
+    pub fn apply_list_numbering(&mut self, numbering: ListNumbering) {
+        visit_ordered_items(&mut self.blocks, &mut Vec::new(), &mut |item, path| {
+            if let BlockKind::ListItem { ordinal, .. } = &mut item.kind {
+                *ordinal = (numbering != ListNumbering::Source).then(|| numbering.label(path));
+            }
+        });
+    }
+
+    /// Every ordered list item with its sequential number and style label.
+    pub(crate) fn numbered_items(&self, numbering: ListNumbering) -> Vec<NumberedItem> {
+        let mut items = Vec::new();
+        let mut blocks = self.blocks.clone();
+        visit_ordered_items(&mut blocks, &mut Vec::new(), &mut |item, path| {
+            if let BlockKind::ListItem { marker, .. } = &item.kind {
+                items.push(NumberedItem {
+                    node_range: item.node_range.clone(),
+                    marker: marker.clone(),
+                    content_start: item
+                        .segments
+                        .first()
+                        .map_or(item.node_range.start + marker.len(), |s| s.range.start),
+                    number: *path.last().unwrap_or(&1),
+                    label: numbering.label(path),
+                });
+            }
+        });
+        items
+    }
+
+    /// Blocks not hidden by a fold, in document order with containers
+    /// before their children. A folded block hides its nested blocks, and
+    /// a folded heading also hides its section.
+    pub fn visible_blocks(&self) -> Vec<&Block> {
+        let mut visible = Vec::new();
+        push_visible(&self.blocks, &mut visible);
+        visible
+    }
+}
+
+fn push_visible<'a>(blocks: &'a [Block], visible: &mut Vec<&'a Block>) {
+    let mut folded_heading = None;
+    for block in blocks {
+        if let Some(level) = folded_heading {
+            match block.kind {
+                BlockKind::Heading { level: l } if l <= level => folded_heading = None,
+                _ => continue,
+            }
+        }
+        visible.push(block);
+        if block.collapsed {
+            if let BlockKind::Heading { level } = block.kind {
+                folded_heading = Some(level);
+            }
+        } else if let BlockContent::Children(children) = &block.content {
+            push_visible(children, visible);
+        }
+    }
+}
+
+/// Set `collapsed` on the folded blocks.
+fn mark_collapsed(blocks: &mut [Block], folded: &HashSet<AnchorId>) {
+    for block in blocks {
+        block.collapsed = folded.contains(&block.id);
+        if let BlockContent::Children(children) = &mut block.content {
+            mark_collapsed(children, folded);
+        }
+    }
 }
 
 /// Create a snapshot from a document
 pub fn create_snapshot(doc: &crate::editing::Document) -> Snapshot {
     let source = doc.text();
     if source.is_empty() {
-        return Snapshot { blocks: vec![] };
+        return Snapshot {
+            blocks: vec![],
+            active: None,
+        };
     }
 
     // Parse using Rowan parser
@@ -168,9 +556,15 @@ pub fn create_snapshot(doc: &crate::editing::Document) -> Snapshot {
     }
 
     // Consolidate consecutive blockquotes into single blocks
-    let blocks = consolidate_blockquotes(blocks, &source);
+    let mut blocks = consolidate_blockquotes(blocks, &source);
+    if !doc.collapsed.is_empty() {
+        mark_collapsed(&mut blocks, &doc.collapsed);
+    }
+    let active = doc
+        .active_block
+        .and_then(|id| mark_focus(&mut blocks, id, &source));
 
-    Snapshot { blocks }
+    Snapshot { blocks, active }
 }
 
 /// Consolidate consecutive blockquotes into single blocks.
@@ -311,9 +705,11 @@ fn merge_blockquote_run(blocks: &[Block], source: &str) -> Block {
                 id: id
                     .take()
                     .expect("paragraph must have ID from first contributing block"),
-                kind: BlockKind::Paragraph,
+                kind: BlockKind::Paragraph { chunks: Vec::new() },
                 node_range: range,
                 segments: std::mem::take(segments),
+                collapsed: false,
+                focus: FocusHint::Normal,
                 content: BlockContent::Leaf,
             });
             *start = None;
@@ -353,8 +749,11 @@ fn merge_blockquote_run(blocks: &[Block], source: &str) -> Block {
                     para_segments.push(soft_break_segment(&para_segments));
                 }
             } else {
-                // First content of this paragraph - extend to line start to include all `>` markers
-                para_start = Some(line_start(source, block.node_range.start));
+                // First content of this paragraph - extend to line start to include all `>` markers,
+                // but not to indentation before the quote (in a list item)
+                para_start = Some(
+                    line_start(source, block.node_range.start).max(blocks[0].node_range.start),
+                );
                 para_id = Some(block.id);
             }
             para_segments.extend(block.segments.clone());
@@ -394,6 +793,8 @@ fn merge_blockquote_run(blocks: &[Block], source: &str) -> Block {
         kind: BlockKind::BlockQuote,
         node_range: merged_range,
         segments: vec![], // BlockQuote content is now in Paragraph children
+        collapsed: false,
+        focus: FocusHint::Normal,
         content,
     }
 }
@@ -428,19 +829,13 @@ fn find_anchor_for_range(anchors: &[Anchor], range: &Range<usize>) -> AnchorId {
 }
 
 /// Generate a fallback anchor ID when no matching anchor is found.
-/// Uses a simple hash of the range for determinism.
+/// Uses a stable hash of the range for determinism.
 fn generate_fallback_anchor_id(range: &Range<usize>) -> AnchorId {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-
-    let mut hasher = DefaultHasher::new();
-    // Magic number to differentiate from static/dynamic IDs in anchors.rs
-    let magic = 0xabcdef0123456789u64;
-    magic.hash(&mut hasher);
-    range.start.hash(&mut hasher);
-    range.end.hash(&mut hasher);
-
-    AnchorId(hasher.finish() as u128)
+    AnchorId(stable_hash(&[
+        b"fallback",
+        &range.start.to_le_bytes(),
+        &range.end.to_le_bytes(),
+    ]))
 }
 
 /// Process a Rowan node into a Block
@@ -452,10 +847,23 @@ fn process_node(source: &str, node: SyntaxNode, anchors: &[Anchor]) -> Option<Bl
         SyntaxKind::PARAGRAPH => process_paragraph(source, node, anchors),
         SyntaxKind::BLOCK_QUOTE => process_block_quote(source, node, anchors),
         SyntaxKind::HEADING => process_heading(source, node, anchors),
+        SyntaxKind::SETEXT_HEADING => process_setext_heading(source, node, anchors),
         SyntaxKind::FENCED_CODE => process_fenced_code(source, node, anchors),
+        SyntaxKind::INDENTED_CODE => process_indented_code(source, node, anchors),
         SyntaxKind::THEMATIC_BREAK => process_thematic_break(source, node, anchors),
+        SyntaxKind::FRONTMATTER => process_frontmatter(source, node, anchors),
         SyntaxKind::TABLE => process_table(source, node, anchors),
-        _ => None, // Skip unknown node types
+        // Read by process_list_item
+        SyntaxKind::CHECKBOX | SyntaxKind::TASK_MARKER => None,
+        kind => {
+            let start = usize::from(node.text_range().start());
+            let line = source[start..].lines().next().unwrap_or_default();
+            notify(Notice::warning(
+                format!("Unknown markdown element {kind:?} is not shown"),
+                Some(line.chars().take(60).collect()),
+            ));
+            None
+        }
     }
 }
 
@@ -486,6 +894,8 @@ fn process_list(
         kind: BlockKind::List { ordered },
         node_range,
         segments: vec![],
+        collapsed: false,
+        focus: FocusHint::Normal,
         content: BlockContent::Children(children),
     })
 }
@@ -548,19 +958,38 @@ fn process_list_item(source: &str, node: SyntaxNode, anchors: &[Anchor]) -> Opti
         .map(|(cb, len)| (Some(cb), len))
         .unwrap_or((None, 0));
 
+    // A state keyword overrides the checkbox; it stays in the text
+    let task_marker = node
+        .children()
+        .find(|c| c.kind() == SyntaxKind::TASK_MARKER);
+    let task_state = task_marker
+        .as_ref()
+        .and_then(|m| TaskState::from_keyword(&m.text().to_string()))
+        .or_else(|| {
+            checkbox.as_ref().map(|cb| {
+                if cb.checked {
+                    TaskState::Done
+                } else {
+                    TaskState::Todo
+                }
+            })
+        });
+
     // Content starts after marker (and checkbox if present), ends before newline
     let content_start = node_range.start + marker_len + checkbox_len;
     let fallback_content_end = node_range.start + first_line_content_end;
 
-    // Process children (nested content)
-    // Skip the FIRST paragraph - its content becomes the list item's segments.
-    // Subsequent paragraphs are separate child blocks.
+    // The item's text is the paragraph right after the marker, if any; an
+    // item can also open with a code fence, quote or table instead
+    let lead_paragraph = node
+        .children()
+        .find(|c| !matches!(c.kind(), SyntaxKind::CHECKBOX | SyntaxKind::TASK_MARKER))
+        .filter(|c| c.kind() == SyntaxKind::PARAGRAPH);
+
+    // Everything else is nested content
     let mut children = Vec::new();
-    let mut first_paragraph_seen = false;
     for child in node.children() {
-        // Skip the first paragraph - its content becomes the list item's segments
-        if child.kind() == SyntaxKind::PARAGRAPH && !first_paragraph_seen {
-            first_paragraph_seen = true;
+        if Some(&child) == lead_paragraph.as_ref() {
             continue;
         }
         if let Some(block) = process_node(source, child, anchors) {
@@ -582,9 +1011,7 @@ fn process_list_item(source: &str, node: SyntaxNode, anchors: &[Anchor]) -> Opti
 
     // Extract segments from the list item's content
     // We look in the PARAGRAPH child (if present) since that's where inlines live
-    let segments = node
-        .children()
-        .find(|c| c.kind() == SyntaxKind::PARAGRAPH)
+    let segments = lead_paragraph
         .map(|para| {
             // Use paragraph's range, but start after the list marker
             // and exclude trailing newline
@@ -595,10 +1022,17 @@ fn process_list_item(source: &str, node: SyntaxNode, anchors: &[Anchor]) -> Opti
             if para_end > para_start && source.as_bytes().get(para_end - 1) == Some(&b'\n') {
                 para_end -= 1;
             }
-            let content_range = content_start.max(para_start)..para_end;
+            let text_start = task_marker
+                .as_ref()
+                .map_or(para_start, |m| m.text_range().start().into());
+            let content_range = content_start.max(text_start)..para_end;
             extract_segments(&para, source, content_range)
         })
         .unwrap_or_else(|| {
+            // A block opening the item is shown as a child, not as text
+            if matches!(content, BlockContent::Children(_)) {
+                return vec![];
+            }
             // No paragraph child - use first line content range as fallback
             let fallback_range = content_start..fallback_content_end;
             if !fallback_range.is_empty() {
@@ -615,9 +1049,16 @@ fn process_list_item(source: &str, node: SyntaxNode, anchors: &[Anchor]) -> Opti
 
     Some(Block {
         id,
-        kind: BlockKind::ListItem { marker, checkbox },
+        kind: BlockKind::ListItem {
+            marker,
+            checkbox,
+            task_state,
+            ordinal: None,
+        },
         node_range,
         segments,
+        collapsed: false,
+        focus: FocusHint::Normal,
         content,
     })
 }
@@ -635,19 +1076,120 @@ fn process_paragraph(source: &str, node: SyntaxNode, anchors: &[Anchor]) -> Opti
         node_range.end
     };
     let content_range = node_range.start..content_end;
-    let segments = extract_segments(&node, source, content_range);
+    let segments = extract_segments(&node, source, content_range.clone());
+    let chunks = paragraph_chunks(source, content_range, &segments);
 
     let id = find_anchor_for_range(anchors, &node_range);
+    let kind = match embed_target(&node) {
+        Some((target, heading)) => BlockKind::Embed {
+            target,
+            heading,
+            source: None,
+        },
+        None => BlockKind::Paragraph { chunks },
+    };
 
     Some(Block {
         id,
-        kind: BlockKind::Paragraph,
+        kind,
         node_range,
         segments,
+        collapsed: false,
+        focus: FocusHint::Normal,
         content: BlockContent::Leaf,
     })
 }
 
+/// Target and heading of a paragraph that is only a note embed
+/// (`![[Note#Heading]]`). Attachment embeds and same-note links don't count.
+fn embed_target(paragraph: &SyntaxNode) -> Option<(String, Option<String>)> {
+    let mut parts = paragraph
+        .children_with_tokens()
+        .filter(|e| !matches!(e.kind(), SyntaxKind::WHITESPACE | SyntaxKind::NEWLINE));
+    let (Some(bang), Some(link), None) = (parts.next(), parts.next(), parts.next()) else {
+        return None;
+    };
+    if bang.kind() != SyntaxKind::EXCLAIM || link.kind() != SyntaxKind::WIKILINK {
+        return None;
+    }
+    let link = WikiLinkParts::of(link.as_node()?)?;
+    let target = link.target_text();
+    if target.is_empty() || crate::assets::is_asset(RelativePath::new(&target)) {
+        return None;
+    }
+    Some((
+        target,
+        link.section.map(|section| section.text().to_string()),
+    ))
+}
+
+/// Split a long paragraph into chunks of roughly [`PARAGRAPH_CHUNK_BYTES`],
+/// breaking after line ends or sentence ends where possible and falling back
+/// to any whitespace. Never breaks inside a link, code span or emphasis.
+fn paragraph_chunks(
+    source: &str,
+    content: Range<usize>,
+    segments: &[InlineSegment],
+) -> Vec<Range<usize>> {
+    if content.len() <= LONG_PARAGRAPH_BYTES {
+        return Vec::new();
+    }
+
+    // Ranges a break must not fall inside, in source order
+    let atomic: Vec<&Range<usize>> = segments
+        .iter()
+        .filter(|s| !matches!(s.kind, InlineNode::Text(_) | InlineNode::SoftBreak))
+        .map(|s| &s.range)
+        .collect();
+    let can_break = |offset: usize| {
+        let i = atomic.partition_point(|r| r.start < offset);
+        i == 0 || atomic[i - 1].end <= offset
+    };
+
+    let mut chunks = Vec::new();
+    let mut start = content.start;
+    while content.end - start > PARAGRAPH_CHUNK_BYTES {
+        let Some(end) = chunk_end(&source[start..content.end], start, &can_break) else {
+            break;
+        };
+        chunks.push(start..end);
+        start = end;
+    }
+    chunks.push(start..content.end);
+    chunks
+}
+
+/// End of the chunk starting at `start`, whose remaining text is `rest`.
+fn chunk_end(rest: &str, start: usize, can_break: &impl Fn(usize) -> bool) -> Option<usize> {
+    let preferred_end = start + PARAGRAPH_CHUNK_BYTES;
+    let max_end = start + 2 * PARAGRAPH_CHUNK_BYTES;
+    let mut sentence_before = None;
+    let mut sentence_after = None;
+    let mut space = None;
+    let mut prev = ' ';
+
+    for (i, c) in rest.char_indices() {
+        let offset = start + i + c.len_utf8();
+        if offset > max_end || i + c.len_utf8() == rest.len() {
+            break;
+        }
+        if c.is_whitespace() && can_break(offset) {
+            if c == '\n' || matches!(prev, '.' | '!' | '?') {
+                if offset <= preferred_end {
+                    sentence_before = Some(offset);
+                } else if sentence_after.is_none() {
+                    sentence_after = Some(offset);
+                }
+            }
+            if offset <= preferred_end || space.is_none() {
+                space = Some(offset);
+            }
+        }
+        prev = c;
+    }
+    sentence_before.or(sentence_after).or(space)
+}
+
 fn process_block_quote(source: &str, node: SyntaxNode, anchors: &[Anchor]) -> Option<Block> {
     let text_range = node.text_range();
     let node_range: Range<usize> = (text_range.start().into())..(text_range.end().into());
@@ -688,6 +1230,8 @@ fn process_block_quote(source: &str, node: SyntaxNode, anchors: &[Anchor]) -> Op
         kind: BlockKind::BlockQuote,
         node_range,
         segments,
+        collapsed: false,
+        focus: FocusHint::Normal,
         content,
     })
 }
@@ -695,20 +1239,48 @@ fn process_block_quote(source: &str, node: SyntaxNode, anchors: &[Anchor]) -> Op
 fn process_heading(source: &str, node: SyntaxNode, anchors: &[Anchor]) -> Option<Block> {
     let text_range = node.text_range();
     let node_range: Range<usize> = (text_range.start().into())..(text_range.end().into());
-    let text = &source[node_range.clone()];
-
-    // Count # for level
-    let level = text.chars().take_while(|&c| c == '#').count() as u8;
-    let prefix_len = level as usize + 1; // # + space
+    let level = heading_level(&node)?;
 
-    // Content: after prefix, before trailing newline
-    let content_start = node_range.start + prefix_len;
-    let content_end = if text.ends_with('\n') {
-        node_range.end - 1
-    } else {
-        node_range.end
+    // Content: the heading text, without the `#`s or a closing sequence
+    let id = find_anchor_for_range(anchors, &node_range);
+    let segments = match visit::child_of_kind(&node, SyntaxKind::HEADING_TEXT) {
+        Some(text) => extract_segments(&text, source, text.text_range().into()),
+        None => Vec::new(),
     };
 
+    Some(Block {
+        id,
+        kind: BlockKind::Heading { level },
+        node_range,
+        segments,
+        collapsed: false,
+        focus: FocusHint::Normal,
+        content: BlockContent::Leaf,
+    })
+}
+
+/// A setext heading: its text lines, then an `===` or `---` underline.
+fn process_setext_heading(source: &str, node: SyntaxNode, anchors: &[Anchor]) -> Option<Block> {
+    let text_range = node.text_range();
+    let node_range: Range<usize> = (text_range.start().into())..(text_range.end().into());
+    let level = heading_level(&node)?;
+
+    // Content: the lines before the underline, without their last newline
+    let underline_start = node
+        .children()
+        .find(|child| child.kind() == SyntaxKind::SETEXT_UNDERLINE)
+        .map_or(node_range.end, |underline| {
+            underline.text_range().start().into()
+        });
+    let newline = source[..underline_start]
+        .rfind('\n')
+        .unwrap_or(underline_start);
+    let content_start = node_range.start;
+    let content_end = source[content_start..newline]
+        .trim_end_matches(['\r', '\n'])
+        .len()
+        + content_start;
+
     let id = find_anchor_for_range(anchors, &node_range);
     let segments = extract_segments(&node, source, content_start..content_end);
 
@@ -717,6 +1289,8 @@ fn process_heading(source: &str, node: SyntaxNode, anchors: &[Anchor]) -> Option
         kind: BlockKind::Heading { level },
         node_range,
         segments,
+        collapsed: false,
+        focus: FocusHint::Normal,
         content: BlockContent::Leaf,
     })
 }
@@ -726,18 +1300,11 @@ fn process_fenced_code(source: &str, node: SyntaxNode, anchors: &[Anchor]) -> Op
     let node_range: Range<usize> = (text_range.start().into())..(text_range.end().into());
     let text = &source[node_range.clone()];
 
-    // Extract language from first line
-    let first_line_end = text.find('\n').unwrap_or(text.len());
-    let first_line = &text[..first_line_end];
-    let language = first_line
-        .trim_start_matches('`')
-        .trim_start_matches('~')
-        .trim();
-    let language = if language.is_empty() {
-        None
-    } else {
-        Some(language.to_string())
-    };
+    let FenceInfo {
+        language,
+        attributes,
+        highlight_lines,
+    } = FenceInfo::from_fenced_code(&node);
 
     let id = find_anchor_for_range(anchors, &node_range);
 
@@ -768,9 +1335,69 @@ fn process_fenced_code(source: &str, node: SyntaxNode, anchors: &[Anchor]) -> Op
 
     Some(Block {
         id,
-        kind: BlockKind::FencedCode { language },
+        kind: BlockKind::FencedCode {
+            language,
+            attributes,
+            highlight_lines,
+        },
         node_range,
         segments,
+        collapsed: false,
+        focus: FocusHint::Normal,
+        content: BlockContent::Leaf,
+    })
+}
+
+/// Indented code is shown like a fence without an info string, with a
+/// segment per line: its `CODE_TEXT`, or an empty segment after the
+/// indentation of a blank line.
+fn process_indented_code(_source: &str, node: SyntaxNode, anchors: &[Anchor]) -> Option<Block> {
+    let text_range = node.text_range();
+    let node_range: Range<usize> = (text_range.start().into())..(text_range.end().into());
+    let id = find_anchor_for_range(anchors, &node_range);
+
+    let mut segments = Vec::new();
+    let mut line: Option<InlineSegment> = None;
+    let mut line_start = node_range.start;
+    for child in node.children_with_tokens() {
+        let range = child.text_range();
+        let range: Range<usize> = range.start().into()..range.end().into();
+        match child.kind() {
+            SyntaxKind::CODE_INDENT => {
+                line.get_or_insert(InlineSegment {
+                    kind: InlineNode::Text(String::new()),
+                    range: range.end..range.end,
+                });
+            }
+            SyntaxKind::CODE_TEXT => {
+                line = Some(InlineSegment {
+                    kind: InlineNode::Text(child.to_string()),
+                    range,
+                });
+            }
+            SyntaxKind::NEWLINE => {
+                segments.push(line.take().unwrap_or(InlineSegment {
+                    kind: InlineNode::Text(String::new()),
+                    range: line_start..line_start,
+                }));
+                line_start = range.end;
+            }
+            _ => {}
+        }
+    }
+    segments.extend(line);
+
+    Some(Block {
+        id,
+        kind: BlockKind::FencedCode {
+            language: None,
+            attributes: Vec::new(),
+            highlight_lines: Vec::new(),
+        },
+        segments,
+        node_range,
+        collapsed: false,
+        focus: FocusHint::Normal,
         content: BlockContent::Leaf,
     })
 }
@@ -788,6 +1415,26 @@ fn process_thematic_break(_source: &str, node: SyntaxNode, anchors: &[Anchor]) -
         kind: BlockKind::ThematicBreak,
         node_range,
         segments: vec![],
+        collapsed: false,
+        focus: FocusHint::Normal,
+        content: BlockContent::Leaf,
+    })
+}
+
+fn process_frontmatter(_source: &str, node: SyntaxNode, anchors: &[Anchor]) -> Option<Block> {
+    let text_range = node.text_range();
+    let node_range: Range<usize> = (text_range.start().into())..(text_range.end().into());
+    let id = find_anchor_for_range(anchors, &node_range);
+
+    Some(Block {
+        id,
+        kind: BlockKind::FrontMatter {
+            format: FrontMatterFormat::of(&node),
+        },
+        node_range,
+        segments: vec![],
+        collapsed: false,
+        focus: FocusHint::Normal,
         content: BlockContent::Leaf,
     })
 }
@@ -831,9 +1478,14 @@ fn process_table(source: &str, node: SyntaxNode, anchors: &[Anchor]) -> Option<B
 
     Some(Block {
         id,
-        kind: BlockKind::Table,
+        kind: BlockKind::Table {
+            rows: rows.len(),
+            alignments: TableAlignment::for_table(&node),
+        },
         node_range,
         segments: vec![],
+        collapsed: false,
+        focus: FocusHint::Normal,
         content: if rows.is_empty() {
             BlockContent::Leaf
         } else {
@@ -868,6 +1520,8 @@ fn process_table_row(
         kind: BlockKind::TableRow { is_header },
         node_range,
         segments: vec![],
+        collapsed: false,
+        focus: FocusHint::Normal,
         content: if cells.is_empty() {
             BlockContent::Leaf
         } else {
@@ -894,6 +1548,8 @@ fn process_table_cell(source: &str, node: SyntaxNode, anchors: &[Anchor]) -> Opt
         kind: BlockKind::TableCell,
         node_range,
         segments,
+        collapsed: false,
+        focus: FocusHint::Normal,
         content: BlockContent::Leaf,
     })
 }
@@ -943,7 +1599,7 @@ fn extract_segments(
 }
 
 /// Collect inline elements from a node, recursively extracting children for STRONG/EMPHASIS.
-/// Hard breaks (trailing spaces + newline) are only detected in block types where they
+/// Hard breaks (trailing spaces or `\` + newline) are only kept in block types where they
 /// are semantically meaningful (paragraphs, list items, blockquotes), not in headings.
 fn collect_inlines(node: &SyntaxNode, source: &str) -> Vec<InlineInfo> {
     // Determine if hard breaks should be detected based on block context
@@ -968,27 +1624,44 @@ fn collect_inlines(node: &SyntaxNode, source: &str) -> Vec<InlineInfo> {
 
         let info: Option<InlineInfo> = match child {
             SyntaxElement::Token(token) => match token.kind() {
-                // Detect hard break pattern: WHITESPACE (2+ trailing spaces) + NEWLINE
-                // Only in contexts where hard breaks are semantically meaningful
-                SyntaxKind::WHITESPACE if detect_hard_breaks && text.ends_with("  ") => {
-                    // Check if next token is NEWLINE
-                    if let Some(SyntaxElement::Token(next)) = children.get(i + 1) {
-                        if next.kind() == SyntaxKind::NEWLINE {
-                            // This is a hard break - combine both tokens
-                            let next_range = next.text_range();
-                            let combined_range = range.start..(next_range.end().into());
-                            i += 1; // Skip the NEWLINE token
-                            Some(InlineInfo {
-                                range: combined_range,
-                                node: InlineNode::HardBreak,
-                            })
-                        } else {
-                            None
+                // HARD_BREAK + NEWLINE, both covered by the break
+                SyntaxKind::HARD_BREAK if detect_hard_breaks => {
+                    let end = match children.get(i + 1) {
+                        Some(SyntaxElement::Token(next)) if next.kind() == SyntaxKind::NEWLINE => {
+                            i += 1;
+                            next.text_range().end().into()
                         }
-                    } else {
-                        None
-                    }
+                        _ => range.end,
+                    };
+                    Some(InlineInfo {
+                        range: range.start..end,
+                        node: InlineNode::HardBreak,
+                    })
                 }
+                // `\*` shows as `*`
+                SyntaxKind::ESCAPE => Some(InlineInfo {
+                    range: range.clone(),
+                    node: InlineNode::Text(text[1..].to_string()),
+                }),
+                // The lexer leaves `\`` as `\` then a backtick
+                SyntaxKind::TEXT if text == "\\" => match children.get(i + 1) {
+                    Some(SyntaxElement::Token(next)) if next.kind() == SyntaxKind::BACKTICK => {
+                        i += 1;
+                        Some(InlineInfo {
+                            range: range.start..next.text_range().end().into(),
+                            node: InlineNode::Text("`".to_string()),
+                        })
+                    }
+                    _ => None,
+                },
+                // A bare URL links to itself
+                SyntaxKind::URL => Some(InlineInfo {
+                    range: range.clone(),
+                    node: InlineNode::Link {
+                        text: text.to_string(),
+                        url: text.to_string(),
+                    },
+                }),
                 _ => None,
             },
             SyntaxElement::Node(child_node) => match child_node.kind() {
@@ -1010,30 +1683,50 @@ fn collect_inlines(node: &SyntaxNode, source: &str) -> Vec<InlineInfo> {
                         node: InlineNode::Strong(children),
                     })
                 }
-                SyntaxKind::CODE_SPAN => {
-                    // `code` - skip backtick on each side
-                    let content = (range.start + 1)..(range.end - 1);
-                    let content_text = source[content].to_string();
-                    Some(InlineInfo {
-                        range: range.clone(),
-                        node: InlineNode::Code(content_text),
-                    })
-                }
-                SyntaxKind::LINK => parse_link(text).map(|(link_text, url)| InlineInfo {
+                SyntaxKind::CODE_SPAN => Some(InlineInfo {
+                    range: range.clone(),
+                    node: InlineNode::Code(code_span_text(child_node).unwrap_or_default()),
+                }),
+                SyntaxKind::LINK => link_parts(child_node).map(|(link_text, url)| InlineInfo {
                     range: range.clone(),
                     node: InlineNode::Link {
                         text: link_text,
                         url,
                     },
                 }),
-                SyntaxKind::WIKILINK => parse_wikilink(text).map(|(target, alias)| InlineInfo {
-                    range: range.clone(),
-                    node: InlineNode::WikiLink { target, alias },
-                }),
-                SyntaxKind::IMAGE => parse_image(text).map(|(alt, url)| InlineInfo {
+                SyntaxKind::AUTOLINK => {
+                    // <url> or <email> - shown without the brackets
+                    let target = &text[1..text.len() - 1];
+                    let url = if target.contains(':') {
+                        target.to_string()
+                    } else {
+                        format!("mailto:{target}")
+                    };
+                    Some(InlineInfo {
+                        range: range.clone(),
+                        node: InlineNode::Link {
+                            text: target.to_string(),
+                            url,
+                        },
+                    })
+                }
+                SyntaxKind::WIKILINK => {
+                    wikilink_parts(child_node).map(|(target, alias)| InlineInfo {
+                        range: range.clone(),
+                        node: InlineNode::WikiLink { target, alias },
+                    })
+                }
+                SyntaxKind::IMAGE => link_parts(child_node).map(|(alt, url)| InlineInfo {
                     range: range.clone(),
                     node: InlineNode::Image { alt, url },
                 }),
+                SyntaxKind::TAG => tag_name(text).map(|name| InlineInfo {
+                    // The node can include trailing punctuation; leave that as text
+                    range: range.start..(range.start + 1 + name.len()),
+                    node: InlineNode::Tag {
+                        name: name.to_string(),
+                    },
+                }),
                 SyntaxKind::STRIKETHROUGH => {
                     // ~~text~~ - skip 2 markers on each side
                     let content = (range.start + 2)..(range.end - 2);
@@ -1064,7 +1757,7 @@ fn extract_inline_children(
     node: &SyntaxNode,
     source: &str,
     content_range: Range<usize>,
-) -> Vec<InlineNode> {
+) -> Vec<InlineSegment> {
     if content_range.is_empty() {
         return vec![];
     }
@@ -1075,6 +1768,10 @@ fn extract_inline_children(
     // Filter to inlines within content range and build with gap-filling
     let mut children = Vec::new();
     let mut cursor = content_range.start;
+    let text = |range: Range<usize>| InlineSegment {
+        kind: InlineNode::Text(source[range.clone()].to_string()),
+        range,
+    };
 
     for inline in &nested_inlines {
         // Skip inlines outside content range
@@ -1085,32 +1782,28 @@ fn extract_inline_children(
         // Add Text node for gap before this inline
         if inline.range.start > cursor {
             let text_end = inline.range.start.min(content_range.end);
-            let text = &source[cursor..text_end];
-            if !text.is_empty() {
-                children.push(InlineNode::Text(text.to_string()));
+            if text_end > cursor {
+                children.push(text(cursor..text_end));
             }
         }
 
         // Add the inline node
-        children.push(inline.node.clone());
+        children.push(InlineSegment {
+            kind: inline.node.clone(),
+            range: inline.range.clone(),
+        });
 
         cursor = inline.range.end.max(cursor);
     }
 
     // Add trailing Text node
     if cursor < content_range.end {
-        let text = &source[cursor..content_range.end];
-        if !text.is_empty() {
-            children.push(InlineNode::Text(text.to_string()));
-        }
+        children.push(text(cursor..content_range.end));
     }
 
     // If no inlines found, entire content is plain text
     if children.is_empty() && !content_range.is_empty() {
-        let text = &source[content_range.clone()];
-        if !text.is_empty() {
-            children.push(InlineNode::Text(text.to_string()));
-        }
+        children.push(text(content_range));
     }
 
     children
@@ -1197,39 +1890,36 @@ fn build_segments_with_gaps(
     segments
 }
 
-/// Parse [text](url) into (text, url) strings
-fn parse_link(text: &str) -> Option<(String, String)> {
-    let close_bracket = text.find(']')?;
-    let open_paren = text[close_bracket..].find('(')? + close_bracket;
-    let close_paren = text.rfind(')')?;
-
-    let link_text = text[1..close_bracket].to_string();
-    let url = text[open_paren + 1..close_paren].to_string();
-    Some((link_text, url))
-}
-
-/// Parse [[target]] or [[target|alias]] into (target, Option<alias>) strings
-fn parse_wikilink(text: &str) -> Option<(String, Option<String>)> {
-    let inner = &text[2..text.len() - 2];
-    if let Some(pipe_pos) = inner.find('|') {
-        Some((
-            inner[..pipe_pos].to_string(),
-            Some(inner[pipe_pos + 1..].to_string()),
-        ))
-    } else {
-        Some((inner.to_string(), None))
+/// Target (with any `#section`) and alias of a closed WIKILINK node
+fn wikilink_parts(node: &SyntaxNode) -> Option<(String, Option<String>)> {
+    let link = WikiLinkParts::of(node)?;
+    let mut target = link.target_text();
+    if let Some(section) = &link.section {
+        target = format!("{target}#{}", section.text());
     }
+    Some((target, link.alias.map(|alias| alias.text().to_string())))
 }
 
-/// Parse ![alt](url) into (alt, url) strings
-fn parse_image(text: &str) -> Option<(String, String)> {
-    let close_bracket = text.find(']')?;
-    let open_paren = text[close_bracket..].find('(')? + close_bracket;
-    let close_paren = text.rfind(')')?;
-
-    let alt = text[2..close_bracket].to_string();
-    let url = text[open_paren + 1..close_paren].to_string();
-    Some((alt, url))
+/// The label and URL of a LINK or IMAGE node, `[label](url "title")` or
+/// `![label](url)`, read from its own tokens so brackets inside nested
+/// code spans or emphasis in the label don't count.
+pub(crate) fn link_parts(node: &SyntaxNode) -> Option<(String, String)> {
+    let children: Vec<SyntaxElement> = node.children_with_tokens().collect();
+    let is = |element: &SyntaxElement, kind| element.kind() == kind && element.as_token().is_some();
+    let open = children.iter().position(|e| is(e, SyntaxKind::LBRACKET))?;
+    let close = open
+        + children[open..]
+            .iter()
+            .position(|e| is(e, SyntaxKind::RBRACKET))?;
+
+    let label = children[open + 1..close]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    let url = visit::child_of_kind(node, SyntaxKind::LINK_DESTINATION)
+        .map(|destination| destination.text().to_string())
+        .unwrap_or_default();
+    Some((label, url))
 }
 
 #[cfg(test)]
@@ -1255,16 +1945,50 @@ mod tests {
 
         // Block header - special handling for ListItem to omit checkbox: None
         let kind_str = match &block.kind {
-            BlockKind::ListItem { marker, checkbox } => {
+            BlockKind::ListItem {
+                marker,
+                ordinal: Some(ordinal),
+                ..
+            } => format!(
+                "ListItem {{ marker: {:?}, ordinal: {:?} }}",
+                marker, ordinal
+            ),
+            BlockKind::ListItem {
+                marker,
+                checkbox,
+                task_state,
+                ..
+            } => {
+                let mut fields = format!("marker: {:?}", marker);
                 if let Some(cb) = checkbox {
-                    format!(
-                        "ListItem {{ marker: {:?}, checkbox: {} }}",
-                        marker,
-                        if cb.checked { "[x]" } else { "[ ]" }
-                    )
-                } else {
-                    format!("ListItem {{ marker: {:?} }}", marker)
+                    fields.push_str(if cb.checked {
+                        ", checkbox: [x]"
+                    } else {
+                        ", checkbox: [ ]"
+                    });
+                }
+                // Only show states that come from a keyword
+                let from_checkbox = checkbox.as_ref().map(|cb| {
+                    if cb.checked {
+                        TaskState::Done
+                    } else {
+                        TaskState::Todo
+                    }
+                });
+                if let Some(state) = task_state
+                    && (checkbox.is_none() || *task_state != from_checkbox)
+                {
+                    fields.push_str(&format!(", task: {:?}", state));
                 }
+                format!("ListItem {{ {} }}", fields)
+            }
+            BlockKind::Paragraph { chunks } if chunks.is_empty() => "Paragraph".to_string(),
+            BlockKind::FencedCode {
+                language,
+                attributes,
+                highlight_lines,
+            } if attributes.is_empty() && highlight_lines.is_empty() => {
+                format!("FencedCode {{ language: {:?} }}", language)
             }
             other => format!("{:?}", other),
         };
@@ -1382,6 +2106,14 @@ mod tests {
                 )
                 .unwrap();
             }
+            InlineNode::Tag { name } => {
+                writeln!(
+                    out,
+                    "{}{}Tag [{}..{}] name:{:?}",
+                    prefix, spaces, range.start, range.end, name
+                )
+                .unwrap();
+            }
             InlineNode::HardBreak => {
                 writeln!(
                     out,
@@ -1401,62 +2133,273 @@ mod tests {
         }
     }
 
-    fn insta_format_inline_node(out: &mut String, node: &InlineNode, prefix: &str, indent: usize) {
+    fn insta_format_inline_node(
+        out: &mut String,
+        segment: &InlineSegment,
+        prefix: &str,
+        indent: usize,
+    ) {
         use std::fmt::Write;
 
         let spaces = " ".repeat(indent);
-        match node {
-            InlineNode::Text(text) => {
-                writeln!(out, "{}{}Text {:?}", prefix, spaces, text).unwrap();
+        let range = format!("[{}..{}]", segment.range.start, segment.range.end);
+        let (name, detail) = match &segment.kind {
+            InlineNode::Text(text) => ("Text", format!(" {text:?}")),
+            InlineNode::Strong(_) => ("Strong", String::new()),
+            InlineNode::Emphasis(_) => ("Emphasis", String::new()),
+            InlineNode::Code(text) => ("Code", format!(" {text:?}")),
+            InlineNode::Strikethrough(text) => ("Strikethrough", format!(" {text:?}")),
+            InlineNode::WikiLink {
+                target,
+                alias: Some(alias),
+            } => ("WikiLink", format!(" target:{target:?} alias:{alias:?}")),
+            InlineNode::WikiLink {
+                target,
+                alias: None,
+            } => ("WikiLink", format!(" target:{target:?}")),
+            InlineNode::Link { text, url } => ("Link", format!(" text:{text:?} url:{url:?}")),
+            InlineNode::Image { alt, url } => ("Image", format!(" alt:{alt:?} url:{url:?}")),
+            InlineNode::Tag { name } => ("Tag", format!(" name:{name:?}")),
+            InlineNode::HardBreak => ("HardBreak", String::new()),
+            InlineNode::SoftBreak => ("SoftBreak", String::new()),
+        };
+        writeln!(out, "{prefix}{spaces}{name} {range}{detail}").unwrap();
+        if let InlineNode::Strong(children) | InlineNode::Emphasis(children) = &segment.kind {
+            for child in children {
+                insta_format_inline_node(out, child, prefix, indent + 2);
             }
-            InlineNode::Strong(children) => {
-                writeln!(out, "{}{}Strong", prefix, spaces).unwrap();
-                for child in children {
-                    insta_format_inline_node(out, child, prefix, indent + 2);
+        }
+    }
+
+    // ============ List numbering tests ============
+
+    /// Ordinal labels of all list items in document order.
+    fn ordinals(source: &str, numbering: ListNumbering) -> Vec<Option<String>> {
+        fn collect(blocks: &[Block], out: &mut Vec<Option<String>>) {
+            for block in blocks {
+                if let BlockKind::ListItem { ordinal, .. } = &block.kind {
+                    out.push(ordinal.clone());
                 }
-            }
-            InlineNode::Emphasis(children) => {
-                writeln!(out, "{}{}Emphasis", prefix, spaces).unwrap();
-                for child in children {
-                    insta_format_inline_node(out, child, prefix, indent + 2);
+                if let BlockContent::Children(children) = &block.content {
+                    collect(children, out);
                 }
             }
-            InlineNode::Code(text) => {
-                writeln!(out, "{}{}Code {:?}", prefix, spaces, text).unwrap();
-            }
-            InlineNode::Strikethrough(text) => {
-                writeln!(out, "{}{}Strikethrough {:?}", prefix, spaces, text).unwrap();
-            }
-            InlineNode::WikiLink { target, alias } => {
-                if let Some(alias) = alias {
-                    writeln!(
-                        out,
-                        "{}{}WikiLink target:{:?} alias:{:?}",
-                        prefix, spaces, target, alias
-                    )
-                    .unwrap();
-                } else {
-                    writeln!(out, "{}{}WikiLink target:{:?}", prefix, spaces, target).unwrap();
+        }
+        let doc = Document::from_bytes(source.as_bytes()).unwrap();
+        let mut snapshot = doc.snapshot();
+        snapshot.apply_list_numbering(numbering);
+        let mut out = Vec::new();
+        collect(&snapshot.blocks, &mut out);
+        out
+    }
+
+    fn labels(labels: &[&str]) -> Vec<Option<String>> {
+        labels.iter().map(|l| Some(l.to_string())).collect()
+    }
+
+    const NESTED_LISTS: &str = "1. One
+   1. Sub
+   1. Sub
+      1. Deep
+      2. Deep
+2. Two
+";
+
+    #[test]
+    fn test_source_numbering_leaves_markers() {
+        assert_eq!(ordinals(NESTED_LISTS, ListNumbering::Source), vec![None; 6]);
+    }
+
+    #[test]
+    fn test_nested_numbering_by_depth() {
+        assert_eq!(
+            ordinals(NESTED_LISTS, ListNumbering::Nested),
+            labels(&["1.", "a.", "b.", "i.", "ii.", "2."])
+        );
+    }
+
+    #[test]
+    fn test_outline_numbering() {
+        assert_eq!(
+            ordinals(NESTED_LISTS, ListNumbering::Outline),
+            labels(&["1.", "1.1", "1.2", "1.2.1", "1.2.2", "2."])
+        );
+    }
+
+    #[test]
+    fn test_numbering_starts_from_first_marker_and_skips_bullets() {
+        assert_eq!(
+            ordinals(
+                "3. Three
+   - bullet
+     1. Sub
+3. Four
+",
+                ListNumbering::Nested
+            ),
+            vec![
+                Some("3.".to_string()),
+                None,
+                Some("a.".to_string()),
+                Some("4.".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_alpha_and_roman_ordinals() {
+        assert_eq!(alpha_ordinal(1), "a");
+        assert_eq!(alpha_ordinal(26), "z");
+        assert_eq!(alpha_ordinal(28), "ab");
+        assert_eq!(roman_ordinal(4), "iv");
+        assert_eq!(roman_ordinal(1994), "mcmxciv");
+        assert_eq!(roman_ordinal(0), "0");
+    }
+
+    // ============ Long paragraph tests ============
+
+    fn paragraph_chunks_of(source: &str) -> (Block, Vec<Range<usize>>) {
+        let doc = Document::from_bytes(source.as_bytes()).unwrap();
+        let block = doc.snapshot().blocks.remove(0);
+        let BlockKind::Paragraph { chunks } = block.kind.clone() else {
+            panic!("expected paragraph, got {:?}", block.kind);
+        };
+        (block, chunks)
+    }
+
+    #[test]
+    fn test_code_lines_numbered_and_highlighted() {
+        let doc = crate::editing::Document::from_bytes(
+            b"```rust {2}\nfn main() {\n    run();\n}\n```\n\nText\n",
+        )
+        .unwrap();
+        let snapshot = doc.snapshot();
+        let lines = snapshot.blocks[0].code_lines();
+        assert_eq!(
+            lines
+                .iter()
+                .map(|line| (line.number, line.text.as_str(), line.highlighted))
+                .collect::<Vec<_>>(),
+            vec![
+                (1, "fn main() {", false),
+                (2, "    run();", true),
+                (3, "}", false)
+            ]
+        );
+        assert!(snapshot.blocks[1].code_lines().is_empty());
+    }
+
+    #[test]
+    fn test_indented_code_segment_per_line() {
+        let source = "\tif x {\n\t\treturn;\n\n      \n\t}\n";
+        let doc = crate::editing::Document::from_bytes(source.as_bytes()).unwrap();
+        let block = doc.snapshot().blocks.remove(0);
+
+        for segment in &block.segments {
+            let InlineNode::Text(text) = &segment.kind else {
+                panic!("expected text, got {:?}", segment.kind);
+            };
+            assert_eq!(text, &source[segment.range.clone()]);
+        }
+        assert_eq!(
+            block
+                .code_lines()
+                .iter()
+                .map(|line| line.text.as_str())
+                .collect::<Vec<_>>(),
+            ["if x {", "\treturn;", "", "  ", "}"]
+        );
+    }
+
+    #[test]
+    fn test_short_paragraph_has_no_chunks() {
+        let (_, chunks) = paragraph_chunks_of("Just a few sentences. Nothing long.\n");
+        assert!(chunks.is_empty());
+    }
+
+    #[test]
+    fn test_long_paragraph_chunks_at_sentence_ends() {
+        let source = "This sentence is about forty bytes long. ".repeat(200);
+        let (block, chunks) = paragraph_chunks_of(&source);
+
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.first().unwrap().start, 0);
+        assert_eq!(chunks.last().unwrap().end, block.content_range().end);
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start, "chunks are contiguous");
+            assert!(source[..pair[0].end].ends_with(". "), "{:?}", pair[0]);
+        }
+        assert!(chunks.iter().all(|c| c.len() <= PARAGRAPH_CHUNK_BYTES));
+    }
+
+    #[test]
+    fn test_chunks_prefer_line_ends_and_fall_back_to_spaces() {
+        // No sentence punctuation at all, a line break every ~600 bytes
+        let line = "word ".repeat(120);
+        let source = format!("{line}\n").repeat(10);
+        let (_, chunks) = paragraph_chunks_of(&source);
+        for chunk in &chunks[..chunks.len() - 1] {
+            assert!(source[..chunk.end].ends_with('\n'), "{chunk:?}");
+        }
+
+        let source = "word ".repeat(1200);
+        let (_, chunks) = paragraph_chunks_of(&source);
+        assert!(chunks.len() > 1);
+        for chunk in &chunks[..chunks.len() - 1] {
+            assert!(source[..chunk.end].ends_with(' '), "{chunk:?}");
+        }
+    }
+
+    #[test]
+    fn test_chunks_never_split_inline_markup() {
+        let source = "See [[Some Long Wiki Page Name]] and **bold text here** now. ".repeat(100);
+        let (block, chunks) = paragraph_chunks_of(&source);
+        assert!(chunks.len() > 1);
+
+        let mut rejoined = Vec::new();
+        for chunk in &chunks {
+            for segment in block.segments_in(chunk.clone()) {
+                assert!(segment.range.end <= chunk.end || segment.range.start >= chunk.start);
+                if !matches!(segment.kind, InlineNode::Text(_)) {
+                    assert!(
+                        segment.range.end <= chunk.end,
+                        "{segment:?} crosses {chunk:?}"
+                    );
                 }
-            }
-            InlineNode::Link { text, url } => {
-                writeln!(
-                    out,
-                    "{}{}Link text:{:?} url:{:?}",
-                    prefix, spaces, text, url
-                )
-                .unwrap();
-            }
-            InlineNode::Image { alt, url } => {
-                writeln!(out, "{}{}Image alt:{:?} url:{:?}", prefix, spaces, alt, url).unwrap();
-            }
-            InlineNode::HardBreak => {
-                writeln!(out, "{}{}HardBreak", prefix, spaces).unwrap();
-            }
-            InlineNode::SoftBreak => {
-                writeln!(out, "{}{}SoftBreak", prefix, spaces).unwrap();
+                rejoined.push(segment);
             }
         }
+        // Every markup segment appears exactly once across the chunks
+        let markup = |segments: &[InlineSegment]| {
+            segments
+                .iter()
+                .filter(|s| !matches!(s.kind, InlineNode::Text(_)))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(markup(&rejoined), markup(&block.segments));
+    }
+
+    #[test]
+    fn test_nested_segments_have_local_ranges() {
+        let source = "Intro\n\n- see **bold [[Page]]**\n";
+        let snapshot = crate::editing::Document::from_bytes(source.as_bytes())
+            .unwrap()
+            .snapshot();
+        let BlockContent::Children(items) = &snapshot.blocks[1].content else {
+            panic!("expected list items");
+        };
+        let item = &items[0];
+        let InlineNode::Strong(children) = &item.segments[1].kind else {
+            panic!("expected strong, got {:?}", item.segments);
+        };
+        let link = &children[1];
+        assert_eq!(&source[link.range.clone()], "[[Page]]");
+        assert_eq!(item.local_range(link), 13..21);
+        assert_eq!(
+            &source[item.content_range()][item.local_range(link)],
+            "[[Page]]"
+        );
     }
 
     // ============ Snapshot tests ============
@@ -0,0 +1,275 @@
+//! Converting between GFM tables and delimited text (CSV/TSV), so data can
+//! be pasted in from a spreadsheet and copied back out.
+//!
+//! Delimited text is read as RFC 4180 CSV with any delimiter: fields may be
+//! quoted, with `""` for a quote inside. The first row becomes the header,
+//! short rows are padded with empty cells, and `|` in a field is escaped.
+//! Line breaks inside quoted fields become `<br>`, since a table cell can't
+//! span lines.
+
+use std::ops::Range;
+
+use crate::editing::snapshot::{Block, BlockContent, BlockKind};
+use crate::editing::{AnchorId, Document};
+
+impl Document {
+    /// The table containing the block `anchor_id` (the table itself, or one
+    /// of its rows or cells) as CSV, or `None` if it isn't in a table.
+    pub fn table_to_csv(&self, anchor_id: AnchorId) -> Option<String> {
+        self.table_to_delimited(anchor_id, ',')
+    }
+
+    /// [`table_to_csv`](Self::table_to_csv) with another delimiter, e.g.
+    /// `'\t'` for TSV.
+    pub fn table_to_delimited(&self, anchor_id: AnchorId, delimiter: char) -> Option<String> {
+        let snapshot = self.snapshot();
+        let table = find_table(&snapshot.blocks, anchor_id)?;
+        let BlockContent::Children(rows) = &table.content else {
+            return Some(String::new());
+        };
+
+        let mut out = String::new();
+        for row in rows {
+            // Split the source rather than use the cell blocks, which skip
+            // empty cells
+            let fields: Vec<String> = split_row(&self.slice(row.node_range.clone()))
+                .iter()
+                .map(|field| quote_field(field, delimiter))
+                .collect();
+            out.push_str(&fields.join(&delimiter.to_string()));
+            out.push('\n');
+        }
+        Some(out)
+    }
+}
+
+/// Edits for [`Cmd::InsertTableFromDelimited`](crate::editing::Cmd): the
+/// table on lines of its own at `at`, with blank lines around it.
+pub(crate) fn insert_table_edits(
+    doc: &Document,
+    at: usize,
+    text: &str,
+    delimiter: char,
+) -> Vec<(Range<usize>, String)> {
+    let table = delimited_to_table(text, delimiter);
+    if table.is_empty() {
+        return Vec::new();
+    }
+    let at = at.min(doc.len());
+    let before = doc.slice(at.saturating_sub(2)..at);
+    let after = doc.slice(at..(at + 1).min(doc.len()));
+
+    let mut insert = match before.as_str() {
+        _ if at == 0 => String::new(),
+        b if b.ends_with("\n\n") || b == "\n" => String::new(),
+        b if b.ends_with('\n') => "\n".to_string(),
+        _ => "\n\n".to_string(),
+    };
+    insert.push_str(&table);
+    // A line break right after `at` already ends the table's last line
+    if !after.is_empty() && after != "\n" {
+        insert.push('\n');
+    }
+    vec![(at..at, insert)]
+}
+
+/// A GFM table from delimited text, or an empty string if there are no
+/// fields.
+pub fn delimited_to_table(text: &str, delimiter: char) -> String {
+    let mut rows = parse_delimited(text, delimiter);
+    rows.retain(|row| row.iter().any(|field| !field.trim().is_empty()));
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        return String::new();
+    }
+
+    let mut table = String::new();
+    let mut push_row = |fields: &[String]| {
+        table.push('|');
+        for column in 0..columns {
+            let field = fields.get(column).map_or("", String::as_str);
+            let cell = field
+                .trim()
+                .replace('|', "\\|")
+                .replace("\r\n", "<br>")
+                .replace('\n', "<br>");
+            table.push(' ');
+            table.push_str(&cell);
+            table.push_str(" |");
+        }
+        table.push('\n');
+    };
+    push_row(&rows[0]);
+    push_row(&vec!["---".to_string(); columns]);
+    for row in &rows[1..] {
+        push_row(row);
+    }
+    table
+}
+
+/// Rows of fields from delimited text, honouring quoted fields.
+fn parse_delimited(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            _ if quoted => field.push(c),
+            c if c == delimiter => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+/// Trimmed cell texts of a table row, split on unescaped `|`.
+fn split_row(row: &str) -> Vec<String> {
+    let row = row.trim();
+    let row = row.strip_prefix('|').unwrap_or(row);
+    let row = match row.strip_suffix('|') {
+        Some(rest) if !rest.ends_with('\\') => rest,
+        _ => row,
+    };
+
+    let mut fields = vec![String::new()];
+    let mut chars = row.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                chars.next();
+                fields.last_mut().unwrap().push('|');
+            }
+            '|' => fields.push(String::new()),
+            _ => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+        .iter()
+        .map(|field| field.trim().to_string())
+        .collect()
+}
+
+/// A field quoted if it contains the delimiter, a quote or a line break.
+fn quote_field(field: &str, delimiter: char) -> String {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// The table that is, or contains, the block `anchor_id`.
+fn find_table(blocks: &[Block], anchor_id: AnchorId) -> Option<&Block> {
+    blocks.iter().find_map(|block| match &block.kind {
+        BlockKind::Table { .. } if contains(block, anchor_id) => Some(block),
+        _ => match &block.content {
+            BlockContent::Children(children) => find_table(children, anchor_id),
+            _ => None,
+        },
+    })
+}
+
+fn contains(block: &Block, anchor_id: AnchorId) -> bool {
+    block.id == anchor_id
+        || matches!(&block.content, BlockContent::Children(children)
+            if children.iter().any(|child| contains(child, anchor_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editing::Cmd;
+
+    #[test]
+    fn test_delimited_to_table() {
+        let csv = "Name,Notes\r\nAda,\"Wrote, first\"\n\"Bob \"\"B\"\"\",a|b\nCy\n";
+        assert_eq!(
+            delimited_to_table(csv, ','),
+            "| Name | Notes |\n\
+             | --- | --- |\n\
+             | Ada | Wrote, first |\n\
+             | Bob \"B\" | a\\|b |\n\
+             | Cy |  |\n"
+        );
+        assert_eq!(
+            delimited_to_table("a\tb\n1\t\"two\nlines\"", '\t'),
+            "| a | b |\n| --- | --- |\n| 1 | two<br>lines |\n"
+        );
+        assert_eq!(delimited_to_table("\n\n", ','), "");
+    }
+
+    #[test]
+    fn test_insert_table_separates_blocks() {
+        let mut doc = Document::from_bytes(b"Intro\nOutro\n").unwrap();
+        doc.apply(Cmd::InsertTableFromDelimited {
+            at: 6,
+            text: "a,b\n1,2\n".to_string(),
+            delimiter: ',',
+        });
+        assert_eq!(
+            doc.text(),
+            "Intro\n\n| a | b |\n| --- | --- |\n| 1 | 2 |\n\nOutro\n"
+        );
+
+        let mut empty = Document::from_bytes(b"").unwrap();
+        empty.apply(Cmd::InsertTableFromDelimited {
+            at: 0,
+            text: "x".to_string(),
+            delimiter: ',',
+        });
+        assert_eq!(empty.text(), "| x |\n| --- |\n");
+    }
+
+    #[test]
+    fn test_table_roundtrips_to_csv() {
+        let csv = "Name,Notes\nAda,\"Wrote, first\"\nBob,a|b\nCy,\n";
+        let mut doc = Document::from_bytes(b"# Data\n").unwrap();
+        doc.apply(Cmd::InsertTableFromDelimited {
+            at: doc.len(),
+            text: csv.to_string(),
+            delimiter: ',',
+        });
+
+        let snapshot = doc.snapshot();
+        let table = snapshot
+            .blocks
+            .iter()
+            .find(|b| matches!(b.kind, BlockKind::Table { .. }))
+            .unwrap();
+        assert_eq!(doc.table_to_csv(table.id).as_deref(), Some(csv));
+
+        // Any cell finds its table
+        let BlockContent::Children(rows) = &table.content else {
+            panic!("table has no rows");
+        };
+        let BlockContent::Children(cells) = &rows[1].content else {
+            panic!("row has no cells");
+        };
+        assert_eq!(
+            doc.table_to_delimited(cells[0].id, '\t').as_deref(),
+            Some("Name\tNotes\nAda\tWrote, first\nBob\ta|b\nCy\t\n")
+        );
+        assert_eq!(doc.table_to_csv(snapshot.blocks[0].id), None);
+    }
+}
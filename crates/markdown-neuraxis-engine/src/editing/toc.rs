@@ -0,0 +1,208 @@
+//! Tables of contents built from a note's headings.
+//!
+//! The table is a nested list of `[[#Heading]]` links, which the app and
+//! other wiki-link tools resolve. [`Cmd::UpdateToc`](crate::editing::Cmd)
+//! writes it between [`TOC_MARKER`] and [`TOC_END_MARKER`] lines; running it
+//! again only changes the note when the headings changed.
+//!
+//! A single top-level heading is the note's title and is left out. With
+//! numbering on, each entry starts with its outline number (`2`, `2.1`).
+//! Headings numbered by hand (`## 2.1 Setup`) show only the generated
+//! number, so it isn't doubled.
+
+use std::ops::Range;
+
+use crate::editing::Document;
+use crate::editing::commands::{find_line_start, in_raw_zone};
+use crate::links::rename::collect_headings;
+
+/// Line marking where the table of contents goes.
+pub const TOC_MARKER: &str = "<!-- toc -->";
+
+/// Line ending a generated table of contents.
+pub const TOC_END_MARKER: &str = "<!-- tocstop -->";
+
+impl Document {
+    /// The table of contents for this note, one list item per line, or an
+    /// empty string if there are no headings to list.
+    pub fn table_of_contents(&self, numbered: bool) -> String {
+        let mut headings = collect_headings(self);
+        headings.retain(|heading| !heading.text.is_empty());
+        let Some(top) = headings.iter().map(|heading| heading.level).min() else {
+            return String::new();
+        };
+        if headings
+            .iter()
+            .filter(|heading| heading.level == top)
+            .count()
+            == 1
+            && headings[0].level == top
+        {
+            headings.remove(0);
+        }
+        let top = headings
+            .iter()
+            .map(|heading| heading.level)
+            .min()
+            .unwrap_or(1);
+
+        let mut toc = String::new();
+        let mut numbers: Vec<usize> = Vec::new();
+        for heading in &headings {
+            // Skipped levels nest one step deeper, not several
+            let depth = usize::from(heading.level - top).min(numbers.len());
+            numbers.truncate(depth + 1);
+            if numbers.len() == depth {
+                numbers.push(0);
+            }
+            numbers[depth] += 1;
+
+            let target = &heading.text;
+            let link = if numbered && strip_number(target) != target {
+                format!("[[#{target}|{}]]", strip_number(target))
+            } else {
+                format!("[[#{target}]]")
+            };
+            toc.push_str(&"  ".repeat(depth));
+            toc.push_str("- ");
+            if numbered {
+                let number: Vec<String> = numbers.iter().map(usize::to_string).collect();
+                toc.push_str(&number.join("."));
+                toc.push(' ');
+            }
+            toc.push_str(&link);
+            toc.push('\n');
+        }
+        toc
+    }
+}
+
+/// Heading text without a hand-written outline number such as `2.`, `2.1`
+/// or `2.1.`.
+fn strip_number(text: &str) -> &str {
+    let number_len = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let rest = &text[number_len..];
+    if number_len > 0 && text.starts_with(|c: char| c.is_ascii_digit()) && rest.starts_with(' ') {
+        rest.trim_start()
+    } else {
+        text
+    }
+}
+
+/// Edits writing the table of contents under the first [`TOC_MARKER`] line
+/// outside code, replacing everything up to the following
+/// [`TOC_END_MARKER`] line. Empty if there is no marker or the table is
+/// already up to date.
+pub(crate) fn toc_edits(doc: &Document, numbered: bool) -> Vec<(Range<usize>, String)> {
+    let Some(marker_end) = marker_line_end(doc, TOC_MARKER, 0) else {
+        return Vec::new();
+    };
+    let toc = doc.table_of_contents(numbered);
+    let body = if toc.is_empty() {
+        "\n".to_string()
+    } else {
+        format!("\n{toc}\n")
+    };
+
+    let edit = match marker_line_end(doc, TOC_END_MARKER, marker_end) {
+        Some(end_marker_end) => {
+            let end_marker_start = find_line_start(doc, end_marker_end - 1);
+            (marker_end..end_marker_start, body)
+        }
+        None => {
+            let newline = if doc.slice_to_cow(0..marker_end).ends_with('\n') {
+                ""
+            } else {
+                "\n"
+            };
+            (
+                marker_end..marker_end,
+                format!("{newline}{body}{TOC_END_MARKER}\n"),
+            )
+        }
+    };
+    if doc.slice_to_cow(edit.0.clone()) == edit.1 {
+        return Vec::new();
+    }
+    vec![edit]
+}
+
+/// End (after the newline) of the first line at or after `from` that
+/// consists of `marker`, skipping code blocks.
+fn marker_line_end(doc: &Document, marker: &str, from: usize) -> Option<usize> {
+    let text = doc.text();
+    let mut line_start = from;
+    for line in text[from..].split_inclusive('\n') {
+        let line_end = line_start + line.len();
+        if line.trim() == marker && !in_raw_zone(doc, line_start) {
+            return Some(line_end);
+        }
+        line_start = line_end;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::editing::{Cmd, Document};
+
+    const NOTE: &str = "# Project\n\n<!-- toc -->\n\n## Goals\n\ntext\n\n### Scope\n\n## 3. Plan\n\n#### Deep\n\n```\n## not a heading\n```\n";
+
+    fn apply(source: &str, numbered: bool) -> String {
+        let mut doc = Document::from_bytes(source.as_bytes()).unwrap();
+        doc.apply(Cmd::UpdateToc { numbered });
+        doc.text()
+    }
+
+    #[test]
+    fn test_table_of_contents() {
+        let doc = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        assert_eq!(
+            doc.table_of_contents(false),
+            "- [[#Goals]]\n  - [[#Scope]]\n- [[#3. Plan]]\n  - [[#Deep]]\n"
+        );
+        assert_eq!(
+            doc.table_of_contents(true),
+            "- 1 [[#Goals]]\n  - 1.1 [[#Scope]]\n- 2 [[#3. Plan|Plan]]\n  - 2.1 [[#Deep]]\n"
+        );
+        assert_eq!(
+            Document::from_bytes(b"Just text\n")
+                .unwrap()
+                .table_of_contents(true),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_several_top_level_headings_are_listed() {
+        let doc = Document::from_bytes(b"# One\n\n# Two\n\n## Sub\n").unwrap();
+        assert_eq!(
+            doc.table_of_contents(false),
+            "- [[#One]]\n- [[#Two]]\n  - [[#Sub]]\n"
+        );
+    }
+
+    #[test]
+    fn test_update_toc_inserts_and_is_idempotent() {
+        let first = apply(NOTE, false);
+        assert!(first.starts_with(
+            "# Project\n\n<!-- toc -->\n\n- [[#Goals]]\n  - [[#Scope]]\n- [[#3. Plan]]\n  - [[#Deep]]\n\n<!-- tocstop -->\n\n## Goals\n"
+        ));
+        assert_eq!(apply(&first, false), first);
+
+        // Regenerating replaces the old table rather than adding another
+        let renamed = first.replace("## Goals\n", "## Aims\n");
+        let updated = apply(&renamed, true);
+        assert!(updated.contains("<!-- toc -->\n\n- 1 [[#Aims]]\n"));
+        assert_eq!(updated.matches("<!-- tocstop -->").count(), 1);
+        assert!(!updated.contains("[[#Goals]]"));
+    }
+
+    #[test]
+    fn test_update_toc_without_marker_does_nothing() {
+        let source = "# A\n\n## B\n\n```\n<!-- toc -->\n```\n";
+        assert_eq!(apply(source, false), source);
+    }
+}
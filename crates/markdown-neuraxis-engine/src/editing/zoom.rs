@@ -0,0 +1,249 @@
+//! Block zoom: viewing one bullet or heading section as if it were the
+//! whole note.
+//!
+//! [`Document::zoom`] scopes a snapshot to one block. A list item brings
+//! its nested items, and a heading brings the blocks after it up to the next
+//! heading of the same or a higher level. Other blocks are shown alone.
+//!
+//! The view's byte offsets start at 0 at the zoomed block, so front-ends
+//! can treat it like a note of its own. Commands built against the view go
+//! through [`SubDocumentView::to_parent`] before [`Document::apply`]. After
+//! an edit, zoom again with the same anchor, which survives edits.
+
+use std::ops::Range;
+
+use crate::editing::focus::find_block;
+use crate::editing::snapshot::{Block, BlockContent, BlockKind, Snapshot};
+use crate::editing::{AnchorId, Cmd, Document};
+
+/// A snapshot scoped to one block, in offsets relative to its start.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubDocumentView {
+    /// The zoomed block's anchor
+    pub anchor_id: AnchorId,
+    /// Source range in the parent note covered by the view
+    pub range: Range<usize>,
+    /// The zoomed blocks, with ranges relative to `range.start`
+    pub snapshot: Snapshot,
+}
+
+impl SubDocumentView {
+    /// Parent offset of an offset in the view, clamped to the view.
+    pub fn parent_offset(&self, offset: usize) -> usize {
+        (self.range.start + offset).min(self.range.end)
+    }
+
+    fn parent_range(&self, range: Range<usize>) -> Range<usize> {
+        self.parent_offset(range.start)..self.parent_offset(range.end)
+    }
+
+    /// A command built against the view, rewritten for the parent note.
+    ///
+    /// Positions past the end of the view are clamped to its end, so edits
+    /// stay inside the zoomed block.
+    pub fn to_parent(&self, cmd: Cmd) -> Cmd {
+        match cmd {
+            Cmd::InsertText { at, text } => Cmd::InsertText {
+                at: self.parent_offset(at),
+                text,
+            },
+            Cmd::DeleteRange { range } => Cmd::DeleteRange {
+                range: self.parent_range(range),
+            },
+            Cmd::ReplaceRange { range, text } => Cmd::ReplaceRange {
+                range: self.parent_range(range),
+                text,
+            },
+            Cmd::SplitListItem { at } => Cmd::SplitListItem {
+                at: self.parent_offset(at),
+            },
+            Cmd::IndentLines { range } => Cmd::IndentLines {
+                range: self.parent_range(range),
+            },
+            Cmd::OutdentLines { range } => Cmd::OutdentLines {
+                range: self.parent_range(range),
+            },
+            Cmd::ToggleMarker { line_start, to } => Cmd::ToggleMarker {
+                line_start: self.parent_offset(line_start),
+                to,
+            },
+            Cmd::ConvertBlock { range, to } => Cmd::ConvertBlock {
+                range: self.parent_range(range),
+                to,
+            },
+            Cmd::MaterializeListNumbering { range, numbering } => Cmd::MaterializeListNumbering {
+                range: self.parent_range(range),
+                numbering,
+            },
+            Cmd::PasteUrl { range, url } => Cmd::PasteUrl {
+                range: self.parent_range(range),
+                url,
+            },
+            Cmd::InsertTableFromDelimited {
+                at,
+                text,
+                delimiter,
+            } => Cmd::InsertTableFromDelimited {
+                at: self.parent_offset(at),
+                text,
+                delimiter,
+            },
+            Cmd::InsertDate { at, spec } => Cmd::InsertDate {
+                at: self.parent_offset(at),
+                spec,
+            },
+            // Work on the whole note
+            Cmd::UpdateToc { numbered } => Cmd::UpdateToc { numbered },
+            cmd @ Cmd::MoveBlock { .. } => cmd,
+        }
+    }
+}
+
+impl Document {
+    /// View the block with `anchor_id` (with its nested items, or its
+    /// section for a heading) as a note of its own, or `None` if no block
+    /// has that anchor.
+    pub fn zoom(&self, anchor_id: AnchorId) -> Option<SubDocumentView> {
+        let snapshot = self.snapshot();
+        let mut blocks = find_zoomed(&snapshot.blocks, anchor_id)?;
+        let start = blocks.first()?.node_range.start;
+        let end = blocks.last()?.node_range.end;
+        for block in &mut blocks {
+            shift(block, start);
+        }
+        // Keep focus mode's active block if it's in view, in view offsets
+        let active = snapshot
+            .active
+            .filter(|active| find_block(&blocks, active.id).is_some());
+        let active = active.map(|mut active| {
+            let back = |range: &mut Range<usize>| *range = range.start - start..range.end - start;
+            active.lines.iter_mut().for_each(back);
+            active.sentences.iter_mut().for_each(back);
+            active
+        });
+        Some(SubDocumentView {
+            anchor_id,
+            range: start..end,
+            snapshot: Snapshot { blocks, active },
+        })
+    }
+}
+
+/// The zoomed block and, for a heading, the rest of its section.
+pub(crate) fn find_zoomed(blocks: &[Block], anchor_id: AnchorId) -> Option<Vec<Block>> {
+    for (i, block) in blocks.iter().enumerate() {
+        if block.id == anchor_id {
+            let BlockKind::Heading { level } = block.kind else {
+                return Some(vec![block.clone()]);
+            };
+            let section = blocks[i + 1..].iter().take_while(
+                |next| !matches!(next.kind, BlockKind::Heading { level: l } if l <= level),
+            );
+            return Some(std::iter::once(block).chain(section).cloned().collect());
+        }
+        if let BlockContent::Children(children) = &block.content
+            && let Some(found) = find_zoomed(children, anchor_id)
+        {
+            return Some(found);
+        }
+    }
+    None
+}
+
+/// Move every source range in `block` back by `by` bytes.
+fn shift(block: &mut Block, by: usize) {
+    let back = |range: &mut Range<usize>| *range = range.start - by..range.end - by;
+    back(&mut block.node_range);
+    for segment in &mut block.segments {
+        back(&mut segment.range);
+    }
+    match &mut block.kind {
+        BlockKind::ListItem {
+            checkbox: Some(checkbox),
+            ..
+        } => back(&mut checkbox.byte_range),
+        BlockKind::Paragraph { chunks } => chunks.iter_mut().for_each(back),
+        _ => {}
+    }
+    if let BlockContent::Children(children) = &mut block.content {
+        for child in children {
+            shift(child, by);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editing::{InlineNode, InlineSegment};
+
+    const NOTE: &str =
+        "# Plan\n\n- one\n  - two\n    - [ ] three\n- four\n\n## Later\n\ntext\n\n# Next\n";
+
+    /// Anchor of the block whose text starts with `text`
+    fn find(blocks: &[Block], text: &str) -> Option<AnchorId> {
+        blocks.iter().find_map(|block| {
+            let found = matches!(
+                block.segments.first(),
+                Some(InlineSegment { kind: InlineNode::Text(t), .. }) if t.starts_with(text)
+            );
+            match &block.content {
+                BlockContent::Children(children) if !found => find(children, text),
+                _ => found.then_some(block.id),
+            }
+        })
+    }
+
+    #[test]
+    fn test_zoom_list_item_with_children() {
+        let doc = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        let id = find(&doc.snapshot().blocks, "two").unwrap();
+        let view = doc.zoom(id).unwrap();
+
+        let zoomed = &NOTE[view.range.clone()];
+        assert!(zoomed.starts_with("- two\n    - [ ] three"));
+        assert!(!zoomed.contains("four"));
+        let item = &view.snapshot.blocks[0];
+        assert_eq!(item.node_range, 0..view.range.len());
+        assert_eq!(&zoomed[item.segments[0].range.clone()], "two");
+    }
+
+    #[test]
+    fn test_zoom_heading_section() {
+        let doc = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        let id = find(&doc.snapshot().blocks, "Plan").unwrap();
+        let view = doc.zoom(id).unwrap();
+        assert_eq!(
+            &NOTE[view.range.clone()],
+            &NOTE[..NOTE.find("# Next").unwrap() - 1]
+        );
+
+        let id = find(&doc.snapshot().blocks, "Later").unwrap();
+        let view = doc.zoom(id).unwrap();
+        assert_eq!(&NOTE[view.range.clone()], "## Later\n\ntext\n");
+        assert_eq!(view.snapshot.blocks.len(), 2);
+    }
+
+    #[test]
+    fn test_edits_on_view_map_to_parent() {
+        let mut doc = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        let id = find(&doc.snapshot().blocks, "Later").unwrap();
+        let view = doc.zoom(id).unwrap();
+
+        // "text" starts at 10 in the view
+        doc.apply(view.to_parent(Cmd::ReplaceRange {
+            range: 10..14,
+            text: "done".to_string(),
+        }));
+        // Past the end is clamped to the view
+        doc.apply(view.to_parent(Cmd::InsertText {
+            at: 1000,
+            text: "more\n".to_string(),
+        }));
+        assert!(doc.text().contains("## Later\n\ndone\nmore\n\n# Next\n"));
+
+        // The anchor survives, so the view can be rebuilt after editing
+        let view = doc.zoom(id).unwrap();
+        assert_eq!(&doc.text()[view.range.clone()], "## Later\n\ndone\nmore\n");
+    }
+}
@@ -0,0 +1,255 @@
+//! # Saved Folds
+//!
+//! Keeps folded blocks (see [`Document::set_collapsed`]) across restarts.
+//! A fold records the block id and the byte offset where the block started.
+//! Block ids are stable while a note is unchanged; if the note was edited
+//! elsewhere the fold goes to the block starting at the same offset, or is
+//! dropped if there is none.
+//!
+//! Folds are stored in `.neuraxis/folds.tsv` (see [`crate::sidecar`]), one
+//! folded block per line: `path<TAB>block id<TAB>block offset`.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use relative_path::{RelativePath, RelativePathBuf};
+
+use crate::editing::{AnchorId, Document};
+use crate::io::IoError;
+use crate::read_state::flatten;
+use crate::sidecar;
+
+/// Location of the folds file, relative to the notes root.
+pub const FOLDS_PATH: &str = ".neuraxis/folds.tsv";
+
+/// A folded block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fold {
+    /// The folded block
+    pub block_id: AnchorId,
+    /// Start of that block, used when the id no longer exists
+    pub block_offset: usize,
+}
+
+impl Fold {
+    fn from_record(record: Vec<String>) -> Option<(RelativePathBuf, Self)> {
+        let [path, block_id, block_offset] = record.as_slice() else {
+            return None;
+        };
+        let fold = Self {
+            block_id: AnchorId(block_id.parse().ok()?),
+            block_offset: block_offset.parse().ok()?,
+        };
+        Some((RelativePathBuf::from(path), fold))
+    }
+}
+
+/// Folded blocks for every note in the vault.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FoldState {
+    folds: BTreeMap<RelativePathBuf, Vec<Fold>>,
+}
+
+impl FoldState {
+    /// Load folds from the vault, returning an empty state if none exists.
+    pub fn load(notes_root: &Path) -> Result<Self, IoError> {
+        Ok(sidecar::read(FOLDS_PATH, notes_root)?
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default())
+    }
+
+    /// Save folds to the vault.
+    pub fn save(&self, notes_root: &Path) -> Result<(), IoError> {
+        sidecar::write(FOLDS_PATH, notes_root, &self.to_text())
+    }
+
+    /// Folds from the file's contents, grouped by note. Lines that aren't
+    /// a fold are dropped.
+    pub fn parse(content: &str) -> Self {
+        let mut state = Self::default();
+        for (path, fold) in sidecar::records(content).filter_map(Fold::from_record) {
+            state.folds.entry(path).or_default().push(fold);
+        }
+        state
+    }
+
+    /// Serialize to the file format, sorted by path.
+    pub fn to_text(&self) -> String {
+        let mut content = String::new();
+        for (path, folds) in &self.folds {
+            for fold in folds {
+                content.push_str(&sidecar::record(&[
+                    path.to_string(),
+                    fold.block_id.0.to_string(),
+                    fold.block_offset.to_string(),
+                ]));
+            }
+        }
+        content
+    }
+
+    /// Folds recorded for a note, in document order.
+    pub fn get(&self, path: &RelativePath) -> &[Fold] {
+        self.folds.get(path).map_or(&[], Vec::as_slice)
+    }
+
+    /// Remember the folded blocks of an open note.
+    pub fn record(&mut self, path: &RelativePath, doc: &Document) {
+        let folds: Vec<Fold> = doc
+            .collapsed_anchors()
+            .into_iter()
+            .map(|anchor| Fold {
+                block_id: anchor.id,
+                block_offset: anchor.range.start,
+            })
+            .collect();
+        if folds.is_empty() {
+            self.folds.remove(path);
+        } else {
+            self.folds.insert(path.to_relative_path_buf(), folds);
+        }
+    }
+
+    /// Fold the blocks recorded for a note that was just opened.
+    pub fn restore(&self, path: &RelativePath, doc: &mut Document) {
+        let snapshot = doc.snapshot();
+        let mut blocks = Vec::new();
+        flatten(&snapshot.blocks, &mut blocks);
+
+        for fold in self.get(path) {
+            let block = blocks.iter().find(|b| b.id == fold.block_id).or_else(|| {
+                blocks
+                    .iter()
+                    .find(|b| b.node_range.start == fold.block_offset)
+            });
+            if let Some(block) = block {
+                doc.set_collapsed(block.id, true);
+            }
+        }
+    }
+
+    /// Forget a note, e.g. after it is deleted.
+    pub fn remove(&mut self, path: &RelativePath) -> Option<Vec<Fold>> {
+        self.folds.remove(path)
+    }
+
+    /// Keep a note's folds when it is renamed.
+    pub fn rename(&mut self, from: &RelativePath, to: &RelativePath) {
+        if let Some(folds) = self.folds.remove(from) {
+            self.folds.insert(to.to_relative_path_buf(), folds);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editing::{Block, BlockKind, Cmd};
+    use crate::tests::create_test_notes_dir;
+
+    const NOTE: &str = "# Plan\n\n- one\n  - child\n- two\n\n## Later\n\ntext\n\n# Next\n";
+
+    /// First block starting with `prefix`, skipping list containers
+    fn block_starting_with(doc: &Document, prefix: &str) -> Block {
+        let snapshot = doc.snapshot();
+        let mut blocks = Vec::new();
+        flatten(&snapshot.blocks, &mut blocks);
+        blocks
+            .into_iter()
+            .filter(|b| !matches!(b.kind, BlockKind::List { .. }))
+            .find(|b| doc.slice(b.node_range.clone()).starts_with(prefix))
+            .cloned()
+            .unwrap()
+    }
+
+    /// Text of each visible block's first line
+    fn visible(doc: &Document) -> Vec<String> {
+        doc.snapshot()
+            .visible_blocks()
+            .into_iter()
+            .map(|b| {
+                doc.slice(b.node_range.clone())
+                    .lines()
+                    .next()
+                    .unwrap_or("")
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_collapsed_list_item_hides_children() {
+        let mut doc = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        let one = block_starting_with(&doc, "- one").id;
+        doc.set_collapsed(one, true);
+
+        assert!(block_starting_with(&doc, "- one").collapsed);
+        let lines = visible(&doc);
+        assert!(lines.contains(&"- one".to_string()));
+        assert!(!lines.iter().any(|l| l.contains("child")));
+        assert!(lines.contains(&"- two".to_string()));
+
+        doc.set_collapsed(one, false);
+        assert!(visible(&doc).iter().any(|l| l.contains("child")));
+    }
+
+    #[test]
+    fn test_collapsed_heading_hides_section() {
+        let mut doc = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        doc.set_collapsed(block_starting_with(&doc, "# Plan").id, true);
+        assert_eq!(visible(&doc), vec!["# Plan", "# Next"]);
+    }
+
+    #[test]
+    fn test_fold_follows_edits() {
+        let mut doc = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        let later = block_starting_with(&doc, "## Later").id;
+        doc.set_collapsed(later, true);
+        doc.apply(Cmd::InsertText {
+            at: 0,
+            text: "Intro\n\n".to_string(),
+        });
+        assert!(block_starting_with(&doc, "## Later").collapsed);
+    }
+
+    #[test]
+    fn test_folds_survive_reopening() {
+        let notes_dir = create_test_notes_dir();
+        let path = RelativePath::new("plan.md");
+        let mut doc = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        doc.set_collapsed(block_starting_with(&doc, "- one").id, true);
+
+        let mut state = FoldState::default();
+        state.record(path, &doc);
+        state.save(notes_dir.path()).unwrap();
+
+        let loaded = FoldState::load(notes_dir.path()).unwrap();
+        assert_eq!(loaded, state);
+        let mut reopened = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        loaded.restore(path, &mut reopened);
+        assert!(block_starting_with(&reopened, "- one").collapsed);
+        assert!(!block_starting_with(&reopened, "- two").collapsed);
+    }
+
+    #[test]
+    fn test_restore_falls_back_to_offset() {
+        let path = RelativePath::new("plan.md");
+        let mut doc = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        let later = block_starting_with(&doc, "## Later");
+        let state = FoldState::parse(&format!("plan.md\t1\t{}\n", later.node_range.start));
+
+        state.restore(path, &mut doc);
+        assert!(block_starting_with(&doc, "## Later").collapsed);
+    }
+
+    #[test]
+    fn test_record_without_folds_forgets_note() {
+        let path = RelativePath::new("plan.md");
+        let mut state = FoldState::parse("plan.md\t1\t0\nbroken\n");
+        assert_eq!(state.get(path).len(), 1);
+
+        state.record(path, &Document::from_bytes(NOTE.as_bytes()).unwrap());
+        assert!(state.get(path).is_empty());
+        assert_eq!(state.to_text(), "");
+    }
+}
@@ -1,8 +1,20 @@
 use crate::models::FileTree;
-use relative_path::RelativePath;
+use crate::templates::TEMPLATES_DIR;
+use crate::trace::timed;
+use relative_path::{RelativePath, RelativePathBuf};
+use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+mod watch;
+
+pub(crate) use watch::Stamp;
+
+pub use watch::{
+    NotesEvent, NotesWatcher, WATCH_POLL_INTERVAL, watch_notes_dir, watch_notes_dir_every,
+    watch_notes_dir_with,
+};
+
 #[derive(Debug, thiserror::Error)]
 pub enum IoError {
     #[error("File not found: {0}")]
@@ -13,10 +25,14 @@ pub enum IoError {
     InvalidNotesDir(String),
     #[error("File already exists: {0}")]
     FileExists(PathBuf),
+    /// The vault was opened read-only (see [`crate::vault::OpenMode`])
+    #[error("Vault is read-only: {0}")]
+    ReadOnly(PathBuf),
 }
 
 /// Read a markdown file and return its content
 pub fn read_file(relative_path: &RelativePath, notes_root: &Path) -> Result<String, IoError> {
+    let _timed = timed(tracing::debug_span!("io.read", path = %relative_path));
     let absolute_path = relative_path.to_path(notes_root);
     if !absolute_path.exists() {
         return Err(IoError::NotFound(absolute_path));
@@ -30,6 +46,8 @@ pub fn write_file(
     notes_root: &Path,
     content: &str,
 ) -> Result<(), IoError> {
+    let _timed =
+        timed(tracing::debug_span!("io.write", path = %relative_path, bytes = content.len()));
     let absolute_path = relative_path.to_path(notes_root);
 
     // Create parent directories if they don't exist
@@ -40,49 +58,310 @@ pub fn write_file(
     fs::write(&absolute_path, content).map_err(IoError::Io)
 }
 
+/// Create a file with `content` unless it already exists, returning
+/// whether it was created.
+///
+/// The content is written to a temporary file first and then linked into
+/// place, so nobody sees a half-written file and a file created at the same
+/// time by someone else is never overwritten.
+pub fn create_file(
+    relative_path: &RelativePath,
+    notes_root: &Path,
+    content: &str,
+) -> Result<bool, IoError> {
+    let _timed =
+        timed(tracing::debug_span!("io.write", path = %relative_path, bytes = content.len()));
+    let absolute_path = relative_path.to_path(notes_root);
+    if absolute_path.exists() {
+        return Ok(false);
+    }
+    let (Some(parent), Some(name)) = (absolute_path.parent(), absolute_path.file_name()) else {
+        return Err(IoError::NotFound(absolute_path));
+    };
+    fs::create_dir_all(parent)?;
+
+    let temp_path = temp_path_for(parent, name);
+    fs::write(&temp_path, content)?;
+    let linked = match fs::hard_link(&temp_path, &absolute_path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+        // Some filesystems (e.g. Android shared storage) have no hard links
+        Err(_) if absolute_path.exists() => Ok(false),
+        Err(_) => fs::rename(&temp_path, &absolute_path).map(|()| true),
+    };
+    let _ = fs::remove_file(&temp_path);
+    Ok(linked?)
+}
+
+/// Replace a file's content so that readers see either the old content or
+/// the new, never a partly written file.
+///
+/// The content is written to a temporary file next to it first and then
+/// renamed over it.
+pub fn replace_file(
+    relative_path: &RelativePath,
+    notes_root: &Path,
+    content: &str,
+) -> Result<(), IoError> {
+    let _timed =
+        timed(tracing::debug_span!("io.write", path = %relative_path, bytes = content.len()));
+    let absolute_path = relative_path.to_path(notes_root);
+    let (Some(parent), Some(name)) = (absolute_path.parent(), absolute_path.file_name()) else {
+        return Err(IoError::NotFound(absolute_path));
+    };
+    fs::create_dir_all(parent)?;
+
+    let temp_path = temp_path_for(parent, name);
+    fs::write(&temp_path, content)?;
+    fs::rename(&temp_path, &absolute_path).inspect_err(|_| {
+        let _ = fs::remove_file(&temp_path);
+    })?;
+    Ok(())
+}
+
+/// A hidden temporary file beside `name` in `parent`.
+fn temp_path_for(parent: &Path, name: &std::ffi::OsStr) -> PathBuf {
+    parent.join(format!(
+        ".{}.{}.tmp",
+        name.to_string_lossy(),
+        std::process::id()
+    ))
+}
+
+/// The file an image or attachment `src` in the note at `note_path` refers
+/// to, for front-ends to display.
+///
+/// `src` is resolved like other links (see [`crate::assets`]): relative to
+/// the note's folder, or to the notes root if it starts with `/`. Remote
+/// URLs, paths outside the notes root and missing files give `None`.
+pub fn resolve_asset(src: &str, note_path: &RelativePath, notes_root: &Path) -> Option<PathBuf> {
+    let src = src.trim();
+    let src = src
+        .strip_prefix('<')
+        .and_then(|s| s.strip_suffix('>'))
+        .unwrap_or(src);
+    if src.is_empty() || src.contains(':') {
+        return None;
+    }
+    let src = src.split(['#', '?']).next()?.replace("%20", " ");
+    let note_dir = note_path.parent().unwrap_or(RelativePath::new(""));
+    let resolved = crate::assets::resolve(note_dir, &src);
+    if resolved.components().next() == Some(relative_path::Component::ParentDir) {
+        return None;
+    }
+    let path = resolved.to_path(notes_root);
+    path.is_file().then_some(path)
+}
+
+/// How the notes folder is scanned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanOptions {
+    /// Follow symlinked folders and files. Each folder and file is listed
+    /// once, under its real path if it is also inside the notes folder, and
+    /// links back to a folder already scanned are skipped, so cycles end.
+    pub follow_symlinks: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            follow_symlinks: true,
+        }
+    }
+}
+
 /// Scan for markdown files in the notes directory
 pub fn scan_markdown_files(notes_root: &Path) -> Result<Vec<PathBuf>, IoError> {
+    scan_markdown_files_with(notes_root, ScanOptions::default())
+}
+
+/// [`scan_markdown_files`] with the given options.
+pub fn scan_markdown_files_with(
+    notes_root: &Path,
+    options: ScanOptions,
+) -> Result<Vec<PathBuf>, IoError> {
     if !notes_root.exists() {
         return Err(IoError::InvalidNotesDir(
             "notes directory not found".to_string(),
         ));
     }
 
-    let mut files = Vec::new();
-    scan_directory_recursive(notes_root, &mut files)?;
+    let mut scan = Scan {
+        options,
+        dirs: HashSet::new(),
+        seen_files: HashSet::new(),
+        links: Vec::new(),
+        files: Vec::new(),
+    };
+    scan.dir(notes_root)?;
+    // Links are followed after everything reachable without them, so
+    // targets inside the notes folder keep their real paths
+    while !scan.links.is_empty() {
+        let mut links = std::mem::take(&mut scan.links);
+        links.sort();
+        for link in links {
+            // Dangling links are skipped
+            let Ok(metadata) = fs::metadata(&link) else {
+                continue;
+            };
+            if metadata.is_dir() {
+                scan.dir(&link)?;
+            } else if is_markdown(&link) {
+                scan.file(link);
+            }
+        }
+    }
+
+    let mut files = scan.files;
     files.sort();
     Ok(files)
 }
 
+/// Scan for markdown files and return their paths relative to the notes directory
+pub fn scan_markdown_relative_paths(notes_root: &Path) -> Result<Vec<RelativePathBuf>, IoError> {
+    scan_markdown_relative_paths_with(notes_root, ScanOptions::default())
+}
+
+/// [`scan_markdown_relative_paths`] with the given options.
+pub fn scan_markdown_relative_paths_with(
+    notes_root: &Path,
+    options: ScanOptions,
+) -> Result<Vec<RelativePathBuf>, IoError> {
+    Ok(scan_markdown_files_with(notes_root, options)?
+        .into_iter()
+        .filter_map(|path| relative_to_root(&path, notes_root))
+        .collect())
+}
+
+/// Whether vault-wide reports such as dashboards should leave `path` out:
+/// it is inside a hidden folder (such as `.neuraxis/trash`) or in the
+/// top-level [templates folder](TEMPLATES_DIR), whose tasks and links are
+/// placeholders rather than real ones.
+pub fn is_hidden_or_template(path: &RelativePath) -> bool {
+    path.components().next().map(|c| c.as_str()) == Some(TEMPLATES_DIR)
+        || path.components().any(|c| c.as_str().starts_with('.'))
+}
+
+/// Convert `\` separators to `/`, keeping the leading `//` of a UNC path
+/// and dropping the `\\?\` prefix Windows adds to canonicalized paths.
+pub fn normalize_separators(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    if let Some(unc) = path.strip_prefix("//?/UNC/") {
+        format!("//{unc}")
+    } else if let Some(verbatim) = path.strip_prefix("//?/") {
+        verbatim.to_string()
+    } else {
+        path
+    }
+}
+
+/// Parse a vault-relative path written with either separator style.
+///
+/// A backslash in a file name on Unix is treated as a separator too; such
+/// names cannot be opened from Windows anyway.
+pub fn relative_path_from_str(path: &str) -> RelativePathBuf {
+    RelativePathBuf::from(normalize_separators(path)).normalize()
+}
+
+/// The path of `path` relative to `notes_root`, if it is inside it.
+///
+/// Besides plain prefix stripping this copes with paths that came from
+/// Windows: either separator style, drive letters and UNC shares compared
+/// case-insensitively, and the `\\?\` prefix from canonicalization.
+pub fn relative_to_root(path: &Path, notes_root: &Path) -> Option<RelativePathBuf> {
+    if let Ok(relative) = path.strip_prefix(notes_root)
+        && let Ok(relative) = RelativePathBuf::from_path(relative)
+        && !relative.as_str().contains('\\')
+    {
+        return Some(relative);
+    }
+
+    let path = normalize_separators(&path.to_string_lossy());
+    let root = normalize_separators(&notes_root.to_string_lossy());
+    let root = root.trim_end_matches('/');
+    let head = path.get(..root.len())?;
+    if head != root && !(is_windows_path(root) && head.eq_ignore_ascii_case(root)) {
+        return None;
+    }
+    let rest = &path[root.len()..];
+    if !rest.is_empty() && !rest.starts_with('/') {
+        return None;
+    }
+    Some(relative_path_from_str(rest.trim_start_matches('/')))
+}
+
+/// Whether a normalized path starts with a drive letter (`C:/`) or UNC share (`//server`).
+fn is_windows_path(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    path.starts_with("//")
+        || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+}
+
 /// Build a file tree from markdown files in the notes directory
 pub fn build_file_tree(notes_root: &Path) -> Result<FileTree, IoError> {
+    build_file_tree_with(notes_root, ScanOptions::default())
+}
+
+/// [`build_file_tree`] with the given scan options.
+pub fn build_file_tree_with(notes_root: &Path, options: ScanOptions) -> Result<FileTree, IoError> {
     if !notes_root.exists() {
         return Err(IoError::InvalidNotesDir(
             "notes directory not found".to_string(),
         ));
     }
 
-    let files = scan_markdown_files(notes_root)?;
+    let files = scan_markdown_files_with(notes_root, options)?;
     Ok(FileTree::build_from_files(notes_root.to_path_buf(), &files))
 }
 
-fn scan_directory_recursive(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), IoError> {
-    let entries = fs::read_dir(dir).map_err(IoError::Io)?;
+/// State of one [`scan_markdown_files_with`] run.
+struct Scan {
+    options: ScanOptions,
+    /// Canonical paths of folders already scanned
+    dirs: HashSet<PathBuf>,
+    /// Canonical paths of files already listed
+    seen_files: HashSet<PathBuf>,
+    /// Symlinks found but not yet followed
+    links: Vec<PathBuf>,
+    files: Vec<PathBuf>,
+}
 
-    for entry in entries {
-        let entry = entry.map_err(IoError::Io)?;
-        let path = entry.path();
+impl Scan {
+    fn dir(&mut self, dir: &Path) -> Result<(), IoError> {
+        let canonical = fs::canonicalize(dir).map_err(IoError::Io)?;
+        if !self.dirs.insert(canonical) {
+            return Ok(());
+        }
 
-        if path.is_dir() {
-            scan_directory_recursive(&path, files)?;
-        } else if let Some(ext) = path.extension()
-            && ext == "md"
-        {
-            files.push(path);
+        for entry in fs::read_dir(dir).map_err(IoError::Io)? {
+            let entry = entry.map_err(IoError::Io)?;
+            let path = entry.path();
+            let file_type = entry.file_type().map_err(IoError::Io)?;
+
+            if file_type.is_symlink() {
+                if self.options.follow_symlinks {
+                    self.links.push(path);
+                }
+            } else if file_type.is_dir() {
+                self.dir(&path)?;
+            } else if is_markdown(&path) {
+                self.file(path);
+            }
         }
+
+        Ok(())
     }
 
-    Ok(())
+    fn file(&mut self, path: PathBuf) {
+        let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        if self.seen_files.insert(canonical) {
+            self.files.push(path);
+        }
+    }
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "md")
 }
 
 /// Rename/move a file to a new path, creating directories as needed
@@ -115,6 +394,66 @@ pub fn rename_file(
     Ok(())
 }
 
+/// Rename/move a folder and everything in it, creating parent directories as needed
+pub fn rename_dir(
+    old_relative_path: &RelativePath,
+    new_relative_path: &RelativePath,
+    notes_root: &Path,
+) -> Result<(), IoError> {
+    let old_abs_path = old_relative_path.to_path(notes_root);
+    let new_abs_path = new_relative_path.to_path(notes_root);
+
+    if !old_abs_path.is_dir() {
+        return Err(IoError::NotFound(old_abs_path));
+    }
+    if new_abs_path.exists() {
+        return Err(IoError::FileExists(new_abs_path));
+    }
+
+    if let Some(parent) = new_abs_path.parent() {
+        fs::create_dir_all(parent).map_err(IoError::Io)?;
+    }
+    fs::rename(&old_abs_path, &new_abs_path).map_err(IoError::Io)?;
+    cleanup_empty_parents(&old_abs_path, notes_root);
+
+    Ok(())
+}
+
+/// Location of the vault trash, relative to the notes root.
+pub const TRASH_DIR: &str = ".neuraxis/trash";
+
+/// Move a file into the vault trash, keeping its relative path so it can be
+/// restored by hand. Returns the file's new path relative to the notes root.
+///
+/// If something with the same name is already in the trash, a numeric suffix
+/// is added rather than overwriting it.
+pub fn move_to_trash(
+    relative_path: &RelativePath,
+    notes_root: &Path,
+) -> Result<RelativePathBuf, IoError> {
+    let source = relative_path.to_path(notes_root);
+    if !source.exists() {
+        return Err(IoError::NotFound(source));
+    }
+
+    let mut trashed = RelativePath::new(TRASH_DIR).join(relative_path);
+    let mut n = 1;
+    while trashed.to_path(notes_root).exists() {
+        let stem = relative_path.file_stem().unwrap_or("file");
+        let name = match relative_path.extension() {
+            Some(ext) => format!("{stem}.{n}.{ext}"),
+            None => format!("{stem}.{n}"),
+        };
+        trashed = RelativePath::new(TRASH_DIR)
+            .join(relative_path)
+            .with_file_name(name);
+        n += 1;
+    }
+
+    rename_file(relative_path, &trashed, notes_root)?;
+    Ok(trashed)
+}
+
 /// Remove empty parent directories up to (but not including) notes_root.
 fn cleanup_empty_parents(path: &Path, notes_root: &Path) {
     let mut current = path.parent();
@@ -167,6 +506,28 @@ mod tests {
         assert!(files.iter().any(|f| f.file_name().unwrap() == "test2.md"));
     }
 
+    #[test]
+    fn test_scan_markdown_relative_paths() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "root.md", "# Root");
+        std::fs::create_dir(notes_dir.path().join("sub")).unwrap();
+        create_test_file(&notes_dir, "sub/nested.md", "# Nested");
+
+        let paths = scan_markdown_relative_paths(notes_dir.path()).unwrap();
+
+        assert_eq!(paths, vec!["root.md", "sub/nested.md"]);
+    }
+
+    #[test]
+    fn test_is_hidden_or_template() {
+        let skipped = |path: &str| is_hidden_or_template(RelativePath::new(path));
+        assert!(skipped(".neuraxis/trash/old.md"));
+        assert!(skipped("projects/.drafts/plan.md"));
+        assert!(skipped("templates/meeting.md"));
+        assert!(!skipped("projects/templates/meeting.md"));
+        assert!(!skipped("notes/plan.md"));
+    }
+
     #[test]
     fn test_handle_invalid_notes_directory() {
         let nonexistent_path = PathBuf::from("/this/path/does/not/exist");
@@ -197,6 +558,37 @@ mod tests {
         assert!(files.iter().any(|f| f.file_name().unwrap() == "nested.md"));
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_follows_symlinks_once() {
+        use std::os::unix::fs::symlink;
+
+        // Given a vault linking to a shared folder, to one of its own
+        // folders and files, back to itself, and to nothing
+        let shared = create_test_notes_dir();
+        create_test_file(&shared, "shared.md", "# Shared");
+        let notes_dir = create_test_notes_dir();
+        let root = notes_dir.path();
+        std::fs::create_dir(root.join("real")).unwrap();
+        create_test_file(&notes_dir, "real/note.md", "# Note");
+        symlink(shared.path(), root.join("linked")).unwrap();
+        symlink(root.join("real"), root.join("alias")).unwrap();
+        symlink(root.join("real/note.md"), root.join("note-link.md")).unwrap();
+        symlink(root, root.join("real/loop")).unwrap();
+        symlink(root.join("missing.md"), root.join("dangling.md")).unwrap();
+
+        // Each note is listed once, under its real path when it has one
+        let paths = scan_markdown_relative_paths(root).unwrap();
+        assert_eq!(paths, vec!["linked/shared.md", "real/note.md"]);
+
+        // With following off, links are skipped
+        let options = ScanOptions {
+            follow_symlinks: false,
+        };
+        let paths = scan_markdown_relative_paths_with(root, options).unwrap();
+        assert_eq!(paths, vec!["real/note.md"]);
+    }
+
     #[test]
     fn test_ignore_non_markdown_files() {
         // Given a notes directory with mixed file types
@@ -298,6 +690,57 @@ mod tests {
         assert_eq!(written_content, new_content);
     }
 
+    #[test]
+    fn test_create_file_never_overwrites() {
+        let notes_dir = create_test_notes_dir();
+        let path = RelativePath::new("journal/2024_01_01.md");
+
+        assert!(create_file(path, notes_dir.path(), "first").unwrap());
+        assert!(!create_file(path, notes_dir.path(), "second").unwrap());
+
+        assert_eq!(read_file(path, notes_dir.path()).unwrap(), "first");
+        // Only the note is left behind
+        let entries = fs::read_dir(notes_dir.path().join("journal")).unwrap();
+        assert_eq!(entries.count(), 1);
+    }
+
+    #[test]
+    fn test_replace_file() {
+        let notes_dir = create_test_notes_dir();
+        let path = RelativePath::new("projects/plan.md");
+
+        replace_file(path, notes_dir.path(), "first").unwrap();
+        replace_file(path, notes_dir.path(), "second").unwrap();
+
+        assert_eq!(read_file(path, notes_dir.path()).unwrap(), "second");
+        let entries = fs::read_dir(notes_dir.path().join("projects")).unwrap();
+        assert_eq!(entries.count(), 1);
+    }
+
+    #[test]
+    fn test_resolve_asset() {
+        let notes_dir = create_test_notes_dir();
+        fs::create_dir_all(notes_dir.path().join("projects/assets")).unwrap();
+        create_test_file(&notes_dir, "projects/assets/My Pic.png", "data");
+        let root = notes_dir.path();
+        let note = RelativePath::new("projects/plan.md");
+        let expected = Some(root.join("projects").join("assets").join("My Pic.png"));
+
+        assert_eq!(resolve_asset("assets/My%20Pic.png", note, root), expected);
+        assert_eq!(resolve_asset("<./assets/My Pic.png>", note, root), expected);
+        assert_eq!(
+            resolve_asset(
+                "/projects/assets/My Pic.png",
+                RelativePath::new("a.md"),
+                root
+            ),
+            expected
+        );
+        assert_eq!(resolve_asset("assets/missing.png", note, root), None);
+        assert_eq!(resolve_asset("https://example.com/x.png", note, root), None);
+        assert_eq!(resolve_asset("../../etc/passwd", note, root), None);
+    }
+
     #[test]
     fn test_rename_file_same_directory() {
         let notes_dir = create_test_notes_dir();
@@ -405,4 +848,67 @@ mod tests {
         assert!(folder.exists());
         assert!(folder.join("other.md").exists());
     }
+
+    #[test]
+    fn test_move_to_trash_keeps_path_and_avoids_overwrite() {
+        let notes_dir = create_test_notes_dir();
+        std::fs::create_dir(notes_dir.path().join("assets")).unwrap();
+        create_test_file(&notes_dir, "assets/pic.png", "one");
+
+        let trashed = move_to_trash(RelativePath::new("assets/pic.png"), notes_dir.path()).unwrap();
+        assert_eq!(trashed, ".neuraxis/trash/assets/pic.png");
+        assert!(!notes_dir.path().join("assets").exists());
+
+        std::fs::create_dir(notes_dir.path().join("assets")).unwrap();
+        create_test_file(&notes_dir, "assets/pic.png", "two");
+        let trashed = move_to_trash(RelativePath::new("assets/pic.png"), notes_dir.path()).unwrap();
+        assert_eq!(trashed, ".neuraxis/trash/assets/pic.1.png");
+    }
+
+    #[test]
+    fn test_relative_to_root_both_separator_styles() {
+        for (root, path, expected) in [
+            ("/notes", "/notes/a/b.md", Some("a/b.md")),
+            ("/notes/", "/notes/b.md", Some("b.md")),
+            ("/notes", "/notes2/b.md", None),
+            (
+                r"C:\Users\me\notes",
+                r"C:\Users\me\notes\a\b.md",
+                Some("a/b.md"),
+            ),
+            (
+                r"C:\Users\me\notes",
+                r"c:\users\me\notes\b.md",
+                Some("b.md"),
+            ),
+            (r"C:\notes", r"D:\notes\b.md", None),
+            (
+                r"\\server\share\notes",
+                r"\\server\share\notes\a\b.md",
+                Some("a/b.md"),
+            ),
+            (
+                r"\\server\share\notes",
+                r"\\?\UNC\server\share\notes\b.md",
+                Some("b.md"),
+            ),
+            (r"C:\notes", r"\\?\C:\notes\a\b.md", Some("a/b.md")),
+        ] {
+            let relative = relative_to_root(Path::new(path), Path::new(root));
+            assert_eq!(
+                relative.as_deref().map(RelativePath::as_str),
+                expected,
+                "{path} in {root}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_relative_path_from_str() {
+        assert_eq!(
+            relative_path_from_str(r"folder\sub\note.md"),
+            "folder/sub/note.md"
+        );
+        assert_eq!(relative_path_from_str("./folder/note.md"), "folder/note.md");
+    }
 }
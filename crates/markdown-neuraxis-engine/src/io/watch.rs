@@ -0,0 +1,286 @@
+//! Watching the notes folder for changes made outside the app (git pull,
+//! Syncthing, another editor).
+//!
+//! The watcher polls: every [`WATCH_POLL_INTERVAL`] it rescans the folder
+//! and compares each markdown file's size and modification time with the
+//! previous scan. That needs no platform-specific APIs and copes with
+//! network and synced folders where change notifications are unreliable.
+//!
+//! A file that disappears while another with the same size and modification
+//! time appears in the same scan is reported as a rename, since a move
+//! keeps both.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
+
+use relative_path::RelativePathBuf;
+
+use crate::io::{IoError, ScanOptions, scan_markdown_relative_paths_with, validate_notes_dir};
+
+/// How often the notes folder is rescanned.
+pub const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A change to a markdown file under the notes root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotesEvent {
+    Created(RelativePathBuf),
+    Modified(RelativePathBuf),
+    Deleted(RelativePathBuf),
+    Renamed {
+        from: RelativePathBuf,
+        to: RelativePathBuf,
+    },
+}
+
+/// Background watcher started by [`watch_notes_dir`]; stops when dropped.
+///
+/// Iterating blocks until the next event.
+pub struct NotesWatcher {
+    events: Receiver<NotesEvent>,
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl NotesWatcher {
+    /// Events seen since the last call, without blocking.
+    pub fn poll(&self) -> Vec<NotesEvent> {
+        self.events.try_iter().collect()
+    }
+
+    /// Wait up to `timeout` for the next event.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<NotesEvent> {
+        self.events.recv_timeout(timeout).ok()
+    }
+}
+
+impl Iterator for NotesWatcher {
+    type Item = NotesEvent;
+
+    fn next(&mut self) -> Option<NotesEvent> {
+        self.events.recv().ok()
+    }
+}
+
+impl Drop for NotesWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Watch the notes folder, reporting changes made after this call.
+pub fn watch_notes_dir(notes_root: &Path) -> Result<NotesWatcher, IoError> {
+    watch_notes_dir_every(notes_root, WATCH_POLL_INTERVAL)
+}
+
+/// [`watch_notes_dir`] with a custom poll interval.
+pub fn watch_notes_dir_every(
+    notes_root: &Path,
+    interval: Duration,
+) -> Result<NotesWatcher, IoError> {
+    watch_notes_dir_with(notes_root, interval, ScanOptions::default())
+}
+
+/// [`watch_notes_dir`] with a custom poll interval and scan options.
+pub fn watch_notes_dir_with(
+    notes_root: &Path,
+    interval: Duration,
+    options: ScanOptions,
+) -> Result<NotesWatcher, IoError> {
+    validate_notes_dir(notes_root)?;
+    let initial = scan_stamps(notes_root, options)?;
+
+    let (tx, rx) = mpsc::channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let root = notes_root.to_path_buf();
+    let worker = {
+        let stop = stop.clone();
+        std::thread::spawn(move || poll_loop(root, options, initial, interval, &stop, &tx))
+    };
+
+    Ok(NotesWatcher {
+        events: rx,
+        stop,
+        worker: Some(worker),
+    })
+}
+
+/// What a scan remembers about each file: if neither changed, the content
+/// is assumed unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Stamp {
+    pub len: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl Stamp {
+    /// Current stamp of a file, or `None` if it can't be read.
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
+
+type Stamps = BTreeMap<RelativePathBuf, Stamp>;
+
+fn scan_stamps(notes_root: &Path, options: ScanOptions) -> Result<Stamps, IoError> {
+    Ok(scan_markdown_relative_paths_with(notes_root, options)?
+        .into_iter()
+        .filter_map(|path| {
+            // Files deleted mid-scan are picked up by the next scan
+            let stamp = Stamp::of(&path.to_path(notes_root))?;
+            Some((path, stamp))
+        })
+        .collect())
+}
+
+fn poll_loop(
+    notes_root: PathBuf,
+    options: ScanOptions,
+    mut previous: Stamps,
+    interval: Duration,
+    stop: &AtomicBool,
+    tx: &Sender<NotesEvent>,
+) {
+    // Sleep in short steps so dropping the watcher doesn't wait a full interval
+    let step = interval.min(Duration::from_millis(50));
+    while !stop.load(Ordering::SeqCst) {
+        let mut waited = Duration::ZERO;
+        while waited < interval && !stop.load(Ordering::SeqCst) {
+            std::thread::sleep(step);
+            waited += step;
+        }
+
+        // The folder may be briefly missing, e.g. mid git checkout
+        let Ok(current) = scan_stamps(&notes_root, options) else {
+            continue;
+        };
+        for event in diff(&previous, &current) {
+            if tx.send(event).is_err() {
+                return;
+            }
+        }
+        previous = current;
+    }
+}
+
+/// Events turning `old` into `new`, in path order: renames, then deletes,
+/// creates and modifications.
+fn diff(old: &Stamps, new: &Stamps) -> Vec<NotesEvent> {
+    let mut deleted: Vec<_> = old.keys().filter(|p| !new.contains_key(*p)).collect();
+    let mut created: Vec<_> = new.keys().filter(|p| !old.contains_key(*p)).collect();
+
+    let mut events = Vec::new();
+    deleted.retain(|&from| {
+        let mut matches = created.iter().filter(|&&to| new[to] == old[from]);
+        let (Some(&to), None) = (matches.next(), matches.next()) else {
+            return true;
+        };
+        created.retain(|&c| c != to);
+        events.push(NotesEvent::Renamed {
+            from: from.clone(),
+            to: to.clone(),
+        });
+        false
+    });
+
+    events.extend(deleted.into_iter().cloned().map(NotesEvent::Deleted));
+    events.extend(created.into_iter().cloned().map(NotesEvent::Created));
+    events.extend(
+        new.iter()
+            .filter(|(path, stamp)| old.get(*path).is_some_and(|old| old != *stamp))
+            .map(|(path, _)| NotesEvent::Modified(path.clone())),
+    );
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{create_test_file, create_test_notes_dir};
+    use std::time::Instant;
+
+    fn stamp(len: u64, secs: u64) -> Stamp {
+        Stamp {
+            len,
+            modified: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs)),
+        }
+    }
+
+    fn stamps(entries: &[(&str, Stamp)]) -> Stamps {
+        entries
+            .iter()
+            .map(|(path, stamp)| (RelativePathBuf::from(*path), *stamp))
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_events() {
+        let old = stamps(&[
+            ("a.md", stamp(1, 1)),
+            ("b.md", stamp(2, 2)),
+            ("c.md", stamp(3, 3)),
+        ]);
+        let new = stamps(&[
+            ("a.md", stamp(1, 1)),
+            ("b.md", stamp(5, 9)),
+            ("moved/c.md", stamp(3, 3)),
+            ("d.md", stamp(4, 4)),
+        ]);
+
+        assert_eq!(
+            diff(&old, &new),
+            [
+                NotesEvent::Renamed {
+                    from: RelativePathBuf::from("c.md"),
+                    to: RelativePathBuf::from("moved/c.md"),
+                },
+                NotesEvent::Created(RelativePathBuf::from("d.md")),
+                NotesEvent::Modified(RelativePathBuf::from("b.md")),
+            ]
+        );
+        assert_eq!(
+            diff(&new, &stamps(&[])).len(),
+            4,
+            "everything deleted, no renames"
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_rename_is_delete_and_create() {
+        let old = stamps(&[("a.md", stamp(1, 1))]);
+        let new = stamps(&[("b.md", stamp(1, 1)), ("c.md", stamp(1, 1))]);
+        let events = diff(&old, &new);
+        assert!(events.contains(&NotesEvent::Deleted(RelativePathBuf::from("a.md"))));
+        assert_eq!(events.len(), 3);
+    }
+
+    #[test]
+    fn test_watcher_reports_external_changes() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "old.md", "old\n");
+        let watcher = watch_notes_dir_every(notes_dir.path(), Duration::from_millis(10)).unwrap();
+
+        create_test_file(&notes_dir, "new.md", "a new note\n");
+        std::fs::remove_file(notes_dir.path().join("old.md")).unwrap();
+        std::fs::write(notes_dir.path().join("ignored.txt"), "x").unwrap();
+
+        let mut events = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while events.len() < 2 && Instant::now() < deadline {
+            events.extend(watcher.recv_timeout(Duration::from_millis(50)));
+        }
+        assert!(events.contains(&NotesEvent::Created(RelativePathBuf::from("new.md"))));
+        assert!(events.contains(&NotesEvent::Deleted(RelativePathBuf::from("old.md"))));
+    }
+}
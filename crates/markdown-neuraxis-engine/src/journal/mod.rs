@@ -0,0 +1,383 @@
+//! # Journal Dates
+//!
+//! Daily notes live at `journal/YYYY_MM_DD.md` and weekly notes at
+//! `journal/week_YYYY_MM_DD.md`, named after the first day of their week.
+//!
+//! Which day is "today" depends on the time zone, and which week a day
+//! belongs to depends on whether weeks start on Monday or Sunday. Both come
+//! from [`JournalCalendar`], so the daily note, weekly note and agenda
+//! grouping always agree with each other.
+//!
+//! When no time zone is configured the system time zone is looked up on
+//! every call rather than captured once, so a device that changes time zone
+//! while the app is running (common on Android) rolls over to the right day.
+//!
+//! [`open_daily_note`] opens a day's note, creating it from the
+//! `templates/journal.md` template the first time, so capture and journal
+//! navigation in every front-end start the day the same way.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use jiff::civil::Date;
+use jiff::tz::TimeZone;
+use jiff::{Timestamp, ToSpan};
+use relative_path::{RelativePath, RelativePathBuf};
+use thiserror::Error;
+
+use crate::editing::Document;
+use crate::io::{self, IoError, NotesEvent};
+use crate::templates::{Template, TemplateError};
+
+/// Folder holding daily and weekly notes, relative to the notes root.
+pub const JOURNAL_DIR: &str = "journal";
+
+/// Template new daily notes are created from, if the vault has it.
+pub const DAILY_TEMPLATE: &str = "journal";
+
+const DAILY_FORMAT: &str = "%Y_%m_%d";
+const WEEKLY_PREFIX: &str = "week_";
+
+#[derive(Debug, Error)]
+pub enum JournalError {
+    #[error("Unknown time zone '{name}': {source}")]
+    UnknownTimeZone { name: String, source: jiff::Error },
+    #[error(transparent)]
+    Io(#[from] IoError),
+    #[error("Could not create daily note from template: {0}")]
+    Template(#[from] TemplateError),
+    #[error("Could not open daily note: {0}")]
+    Document(String),
+}
+
+/// First day of the week for weekly notes and agenda grouping.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WeekStart {
+    #[default]
+    Monday,
+    Sunday,
+}
+
+/// Time zone and week settings used to place journal entries.
+#[derive(Debug, Clone)]
+pub struct JournalCalendar {
+    /// Configured zone; `None` follows the system time zone
+    time_zone: Option<TimeZone>,
+    week_start: WeekStart,
+}
+
+impl JournalCalendar {
+    /// Create a calendar from an IANA time zone name (e.g. `Europe/London`),
+    /// or `None` to follow the system time zone.
+    pub fn new(time_zone: Option<&str>, week_start: WeekStart) -> Result<Self, JournalError> {
+        let time_zone = time_zone
+            .map(|name| {
+                TimeZone::get(name).map_err(|source| JournalError::UnknownTimeZone {
+                    name: name.to_string(),
+                    source,
+                })
+            })
+            .transpose()?;
+        Ok(Self {
+            time_zone,
+            week_start,
+        })
+    }
+
+    pub fn week_start(&self) -> WeekStart {
+        self.week_start
+    }
+
+    /// The time zone in effect right now.
+    pub fn time_zone(&self) -> TimeZone {
+        self.time_zone.clone().unwrap_or_else(TimeZone::system)
+    }
+
+    /// Today's date in the calendar's time zone.
+    pub fn today(&self) -> Date {
+        self.date_at(Timestamp::now())
+    }
+
+    /// The local date at the given instant.
+    pub fn date_at(&self, at: Timestamp) -> Date {
+        at.to_zoned(self.time_zone()).date()
+    }
+
+    /// First day of the week containing `date`.
+    pub fn start_of_week(&self, date: Date) -> Date {
+        let days_in = match self.week_start {
+            WeekStart::Monday => date.weekday().to_monday_zero_offset(),
+            WeekStart::Sunday => date.weekday().to_sunday_zero_offset(),
+        };
+        date.saturating_sub(days_in.days())
+    }
+
+    /// Group dated entries by the week they fall in, keyed by the week's
+    /// first day. Entries keep their input order within a week.
+    pub fn group_by_week<T>(
+        &self,
+        entries: impl IntoIterator<Item = (Date, T)>,
+    ) -> BTreeMap<Date, Vec<T>> {
+        let mut weeks: BTreeMap<Date, Vec<T>> = BTreeMap::new();
+        for (date, entry) in entries {
+            weeks
+                .entry(self.start_of_week(date))
+                .or_default()
+                .push(entry);
+        }
+        weeks
+    }
+
+    /// Path of today's daily note.
+    pub fn today_note_path(&self) -> RelativePathBuf {
+        daily_note_path(self.today())
+    }
+
+    /// Open today's daily note, creating it if needed. See
+    /// [`open_daily_note`].
+    pub fn open_today(&self, notes_root: &Path) -> Result<DailyNote, JournalError> {
+        open_daily_note(notes_root, self.today())
+    }
+
+    /// Path of the weekly note for the week containing `date`.
+    pub fn weekly_note_path_for(&self, date: Date) -> RelativePathBuf {
+        weekly_note_path(self.start_of_week(date))
+    }
+}
+
+/// A daily note opened by [`open_daily_note`].
+pub struct DailyNote {
+    pub path: RelativePathBuf,
+    pub document: Document,
+    /// `Some(NotesEvent::Created)` if the note was just created, for the
+    /// front-end to add to its file tree
+    pub created: Option<NotesEvent>,
+}
+
+/// Open the daily note for `date`, creating it first if it doesn't exist.
+///
+/// A new note is filled from the [`DAILY_TEMPLATE`] template, with `date`
+/// for its `today` date defaults, or left empty if the vault has no such
+/// template. The journal folder is created if needed, and a note created
+/// at the same moment by another device or process is opened rather than
+/// overwritten.
+pub fn open_daily_note(notes_root: &Path, date: Date) -> Result<DailyNote, JournalError> {
+    let path = daily_note_path(date);
+    let created = if path.to_path(notes_root).exists() {
+        false
+    } else {
+        let content = daily_note_content(notes_root, date)?;
+        io::create_file(&path, notes_root, &content)?
+    };
+
+    let source = io::read_file(&path, notes_root)?;
+    let document = Document::from_bytes(source.as_bytes())
+        .map_err(|e| JournalError::Document(e.to_string()))?;
+    Ok(DailyNote {
+        created: created.then(|| NotesEvent::Created(path.clone())),
+        path,
+        document,
+    })
+}
+
+/// Content for a new daily note.
+pub(crate) fn daily_note_content(notes_root: &Path, date: Date) -> Result<String, JournalError> {
+    match Template::load(notes_root, DAILY_TEMPLATE) {
+        Ok(template) => Ok(template.instantiate(&BTreeMap::new(), date)?),
+        Err(TemplateError::Io(IoError::NotFound(_))) => Ok(String::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Path of the daily note for `date`, e.g. `journal/2024_03_31.md`.
+pub fn daily_note_path(date: Date) -> RelativePathBuf {
+    RelativePath::new(JOURNAL_DIR).join(format!("{}.md", date.strftime(DAILY_FORMAT)))
+}
+
+/// Path of the weekly note for the week starting on `week_start`,
+/// e.g. `journal/week_2024_03_25.md`.
+pub fn weekly_note_path(week_start: Date) -> RelativePathBuf {
+    RelativePath::new(JOURNAL_DIR).join(format!(
+        "{WEEKLY_PREFIX}{}.md",
+        week_start.strftime(DAILY_FORMAT)
+    ))
+}
+
+/// The date of a daily note, if `path` is one.
+pub fn daily_note_date(path: &RelativePath) -> Option<Date> {
+    if path.parent()? != RelativePath::new(JOURNAL_DIR) || path.extension()? != "md" {
+        return None;
+    }
+    Date::strptime(DAILY_FORMAT, path.file_stem()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{create_test_file, create_test_notes_dir};
+    use jiff::civil::date;
+
+    fn calendar(tz: &str, week_start: WeekStart) -> JournalCalendar {
+        JournalCalendar::new(Some(tz), week_start).unwrap()
+    }
+
+    fn ts(s: &str) -> Timestamp {
+        s.parse().unwrap()
+    }
+
+    // ============ Rollover across DST ============
+
+    #[test]
+    fn test_date_at_london_spring_forward() {
+        let cal = calendar("Europe/London", WeekStart::Monday);
+        // Still GMT: 23:30 UTC is 23:30 local
+        assert_eq!(cal.date_at(ts("2024-03-30T23:30:00Z")), date(2024, 3, 30));
+        // BST from 01:00 UTC on the 31st: 23:30 UTC is 00:30 the next day
+        assert_eq!(cal.date_at(ts("2024-03-31T23:30:00Z")), date(2024, 4, 1));
+    }
+
+    #[test]
+    fn test_date_at_london_fall_back() {
+        let cal = calendar("Europe/London", WeekStart::Monday);
+        // Still BST: 23:30 UTC is 00:30 the next day
+        assert_eq!(cal.date_at(ts("2024-10-26T23:30:00Z")), date(2024, 10, 27));
+        // Back on GMT: 23:30 UTC stays on the same day
+        assert_eq!(cal.date_at(ts("2024-10-27T23:30:00Z")), date(2024, 10, 27));
+    }
+
+    #[test]
+    fn test_date_at_new_york_spring_forward() {
+        let cal = calendar("America/New_York", WeekStart::Sunday);
+        // EST (UTC-5): 04:30 UTC is 23:30 the previous day
+        assert_eq!(cal.date_at(ts("2024-03-10T04:30:00Z")), date(2024, 3, 9));
+        // EDT (UTC-4) after the switch: 03:30 UTC is 23:30 the previous day
+        assert_eq!(cal.date_at(ts("2024-03-11T03:30:00Z")), date(2024, 3, 10));
+        assert_eq!(cal.date_at(ts("2024-03-11T04:30:00Z")), date(2024, 3, 11));
+    }
+
+    #[test]
+    fn test_same_instant_differs_by_time_zone() {
+        let instant = ts("2024-06-01T20:00:00Z");
+        let tokyo = calendar("Asia/Tokyo", WeekStart::Monday);
+        let los_angeles = calendar("America/Los_Angeles", WeekStart::Monday);
+        assert_eq!(tokyo.date_at(instant), date(2024, 6, 2));
+        assert_eq!(los_angeles.date_at(instant), date(2024, 6, 1));
+    }
+
+    #[test]
+    fn test_unknown_time_zone_is_an_error() {
+        let result = JournalCalendar::new(Some("Not/AZone"), WeekStart::Monday);
+        assert!(matches!(result, Err(JournalError::UnknownTimeZone { .. })));
+    }
+
+    #[test]
+    fn test_system_time_zone_when_unconfigured() {
+        let cal = JournalCalendar::new(None, WeekStart::Monday).unwrap();
+        let now = Timestamp::now();
+        assert_eq!(cal.date_at(now), now.to_zoned(TimeZone::system()).date());
+    }
+
+    // ============ Week start ============
+
+    #[test]
+    fn test_start_of_week_monday_and_sunday() {
+        // 2024-03-31 is a Sunday, and the day London clocks go forward
+        let sunday = date(2024, 3, 31);
+        let monday_cal = calendar("Europe/London", WeekStart::Monday);
+        let sunday_cal = calendar("Europe/London", WeekStart::Sunday);
+        assert_eq!(monday_cal.start_of_week(sunday), date(2024, 3, 25));
+        assert_eq!(sunday_cal.start_of_week(sunday), sunday);
+        assert_eq!(
+            sunday_cal.start_of_week(date(2024, 3, 30)),
+            date(2024, 3, 24)
+        );
+    }
+
+    #[test]
+    fn test_weekly_note_follows_local_rollover() {
+        // Sunday 23:30 UTC in London after spring forward is Monday 00:30
+        let cal = calendar("Europe/London", WeekStart::Monday);
+        let today = cal.date_at(ts("2024-03-31T23:30:00Z"));
+        assert_eq!(
+            cal.weekly_note_path_for(today),
+            "journal/week_2024_04_01.md"
+        );
+    }
+
+    #[test]
+    fn test_group_by_week() {
+        let cal = calendar("Europe/London", WeekStart::Sunday);
+        let grouped = cal.group_by_week([
+            (date(2024, 3, 30), "sat"),
+            (date(2024, 3, 31), "sun"),
+            (date(2024, 4, 1), "mon"),
+            (date(2024, 3, 24), "prev sun"),
+        ]);
+        assert_eq!(
+            grouped.into_iter().collect::<Vec<_>>(),
+            vec![
+                (date(2024, 3, 24), vec!["sat", "prev sun"]),
+                (date(2024, 3, 31), vec!["sun", "mon"]),
+            ]
+        );
+    }
+
+    // ============ Note paths ============
+
+    #[test]
+    fn test_daily_note_path_roundtrip() {
+        let path = daily_note_path(date(2024, 1, 1));
+        assert_eq!(path, "journal/2024_01_01.md");
+        assert_eq!(daily_note_date(&path), Some(date(2024, 1, 1)));
+    }
+
+    #[test]
+    fn test_daily_note_date_rejects_other_notes() {
+        for path in [
+            "journal/week_2024_01_01.md",
+            "journal/notes.md",
+            "2024_01_01.md",
+            "journal/2024_13_01.md",
+        ] {
+            assert_eq!(daily_note_date(RelativePath::new(path)), None, "{path}");
+        }
+    }
+
+    // ============ Opening daily notes ============
+
+    #[test]
+    fn test_open_daily_note_creates_from_template() {
+        let notes_dir = create_test_notes_dir();
+        std::fs::create_dir(notes_dir.path().join("templates")).unwrap();
+        create_test_file(
+            &notes_dir,
+            "templates/journal.md",
+            "---\nvariables:\n  - name: day\n    type: date\n    default: today\n---\n# {{day}}\n",
+        );
+
+        let note = open_daily_note(notes_dir.path(), date(2024, 3, 31)).unwrap();
+        assert_eq!(note.path, "journal/2024_03_31.md");
+        assert_eq!(note.created, Some(NotesEvent::Created(note.path.clone())));
+        assert_eq!(note.document.text(), "# 2024-03-31\n");
+    }
+
+    #[test]
+    fn test_open_daily_note_keeps_existing_note() {
+        let notes_dir = create_test_notes_dir();
+        std::fs::create_dir(notes_dir.path().join(JOURNAL_DIR)).unwrap();
+        create_test_file(&notes_dir, "journal/2024_03_31.md", "- written\n");
+
+        let note = open_daily_note(notes_dir.path(), date(2024, 3, 31)).unwrap();
+        assert_eq!(note.created, None);
+        assert_eq!(note.document.text(), "- written\n");
+    }
+
+    #[test]
+    fn test_open_daily_note_without_template() {
+        let notes_dir = create_test_notes_dir();
+        let note = open_daily_note(notes_dir.path(), date(2024, 1, 1)).unwrap();
+        assert!(note.created.is_some());
+        assert_eq!(note.document.text(), "");
+        assert!(notes_dir.path().join("journal/2024_01_01.md").exists());
+    }
+}
@@ -1,6 +1,30 @@
+pub mod annotations;
+pub mod assets;
+pub mod bookmarks;
+pub mod calendar;
+pub mod corpus;
+pub mod dashboard;
+pub mod dates;
 pub mod editing;
+pub mod folds;
 pub mod io;
+pub mod journal;
+pub mod links;
 pub mod models;
+pub mod notices;
+pub mod read_state;
+pub mod refactor;
+pub mod saved_views;
+pub mod search;
+pub mod sidecar;
+pub mod spelling;
+pub mod tags;
+pub mod templates;
+pub mod theme;
+pub mod titles;
+pub mod trace;
+pub mod vault;
+pub mod views;
 
 #[cfg(test)]
 pub mod tests;
@@ -9,3 +33,4 @@ pub mod tests;
 pub use editing::{anchors::*, commands::*, document::*, snapshot::*};
 pub use io::*;
 pub use models::{file_model::*, file_tree::*, markdown_file::*};
+pub use spelling::*;
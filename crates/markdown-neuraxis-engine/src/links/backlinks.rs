@@ -0,0 +1,269 @@
+//! # Backlinks
+//!
+//! Which notes link to a given note. [`BacklinkIndex`] keeps the wiki-links
+//! of every note keyed by the name they point at, so looking up a page's
+//! backlinks doesn't mean re-reading the vault. It can be built in one go
+//! with [`BacklinkIndex::scan`] or passed to [`open_vault`](crate::vault::open_vault)
+//! and kept current by an [`IndexWorker`](crate::vault::IndexWorker).
+//!
+//! Same-note links (`[[#Heading]]`) are not backlinks and are left out.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+use std::path::Path;
+
+use relative_path::{RelativePath, RelativePathBuf};
+
+use crate::io::{self, IoError};
+use crate::links::{extract_wikilinks, target_matches_note};
+use crate::vault::{IndexKind, SnapshotReader, SnapshotWriter, VaultIndex};
+
+/// A wiki-link from one note to another.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Inbound {
+    source: RelativePathBuf,
+    // Range isn't `Ord`, so the bounds are kept apart to order by position
+    start: usize,
+    end: usize,
+    target: String,
+}
+
+/// Incoming wiki-links for every note in the vault.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BacklinkIndex {
+    /// Links grouped by the lowercased file name they point at
+    by_name: BTreeMap<String, BTreeSet<Inbound>>,
+    /// Names each source note links to, for removing its links again
+    names_by_source: BTreeMap<RelativePathBuf, BTreeSet<String>>,
+}
+
+impl BacklinkIndex {
+    /// Build the index from every note under `notes_root`. Notes that
+    /// can't be read are skipped.
+    pub fn scan(notes_root: &Path) -> Result<Self, IoError> {
+        let mut index = Self::default();
+        for path in io::scan_markdown_relative_paths(notes_root)? {
+            if let Ok(source) = io::read_file(&path, notes_root) {
+                index.update_note(&path, &source);
+            }
+        }
+        Ok(index)
+    }
+
+    /// Re-index a single note's outgoing links from its current source.
+    pub fn update_note(&mut self, path: &RelativePath, source: &str) {
+        let links = extract_wikilinks(source)
+            .into_iter()
+            .map(|link| (link.target, link.range));
+        self.insert_links(path, links);
+    }
+
+    /// Drop a note's outgoing links (e.g. after deletion).
+    pub fn remove_note(&mut self, path: &RelativePath) {
+        let Some(names) = self.names_by_source.remove(path) else {
+            return;
+        };
+        for name in names {
+            if let Some(inbound) = self.by_name.get_mut(&name) {
+                inbound.retain(|link| link.source != path);
+                if inbound.is_empty() {
+                    self.by_name.remove(&name);
+                }
+            }
+        }
+    }
+
+    /// Notes linking to `page`, with the byte range of each link in the
+    /// linking note, ordered by path and then position.
+    pub fn backlinks_for(&self, page: &RelativePath) -> Vec<(RelativePathBuf, Range<usize>)> {
+        let Some(name) = page.file_stem().map(str::to_lowercase) else {
+            return Vec::new();
+        };
+        self.by_name
+            .get(&name)
+            .into_iter()
+            .flatten()
+            .filter(|link| target_matches_note(&link.target, page))
+            .map(|link| (link.source.clone(), link.start..link.end))
+            .collect()
+    }
+
+    /// Number of notes that link to at least one other note.
+    pub fn len(&self) -> usize {
+        self.names_by_source.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names_by_source.is_empty()
+    }
+
+    fn insert_links(
+        &mut self,
+        path: &RelativePath,
+        links: impl IntoIterator<Item = (String, Range<usize>)>,
+    ) {
+        self.remove_note(path);
+        let mut names = BTreeSet::new();
+        for (target, range) in links {
+            let Some(name) = target_name(&target) else {
+                continue;
+            };
+            self.by_name
+                .entry(name.clone())
+                .or_default()
+                .insert(Inbound {
+                    source: path.to_relative_path_buf(),
+                    start: range.start,
+                    end: range.end,
+                    target,
+                });
+            names.insert(name);
+        }
+        if !names.is_empty() {
+            self.names_by_source
+                .insert(path.to_relative_path_buf(), names);
+        }
+    }
+}
+
+/// Lowercased file name a link target points at, without any folders or
+/// `.md` suffix. `None` for same-note links.
+fn target_name(target: &str) -> Option<String> {
+    let target = io::normalize_separators(target);
+    let name = target.rsplit('/').next().unwrap_or(&target);
+    let name = name
+        .strip_suffix(".md")
+        .or(name.strip_suffix(".MD"))
+        .unwrap_or(name);
+    (!name.is_empty()).then(|| name.to_lowercase())
+}
+
+impl VaultIndex for BacklinkIndex {
+    fn kind(&self) -> IndexKind {
+        IndexKind::Backlinks
+    }
+
+    fn add_note(&mut self, path: &RelativePath, source: &str) {
+        self.update_note(path, source);
+    }
+
+    fn remove_note(&mut self, path: &RelativePath) {
+        self.remove_note(path);
+    }
+
+    fn snapshot_note(&self, path: &RelativePath) -> Option<Vec<u8>> {
+        let links: Vec<&Inbound> = self
+            .names_by_source
+            .get(path)?
+            .iter()
+            .filter_map(|name| self.by_name.get(name))
+            .flatten()
+            .filter(|link| link.source == path)
+            .collect();
+        let mut out = SnapshotWriter::default();
+        out.u64(links.len() as u64);
+        for link in links {
+            out.str(&link.target);
+            out.u64(link.start as u64);
+            out.u64(link.end as u64);
+        }
+        Some(out.into_bytes())
+    }
+
+    fn restore_note(&mut self, path: &RelativePath, bytes: &[u8]) -> bool {
+        fn decode(input: &mut SnapshotReader<'_>) -> Option<Vec<(String, Range<usize>)>> {
+            let count = input.u64()?;
+            let mut links = Vec::new();
+            for _ in 0..count {
+                let target = input.str()?;
+                let start = usize::try_from(input.u64()?).ok()?;
+                let end = usize::try_from(input.u64()?).ok()?;
+                links.push((target, start..end));
+            }
+            input.is_empty().then_some(links)
+        }
+
+        match decode(&mut SnapshotReader::new(bytes)) {
+            Some(links) => {
+                self.insert_links(path, links);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{create_test_file, create_test_notes_dir};
+
+    #[test]
+    fn test_backlinks_for() {
+        let mut index = BacklinkIndex::default();
+        index.update_note(
+            RelativePath::new("a.md"),
+            "See [[Target]] and [[target#Intro|intro]]\n",
+        );
+        index.update_note(RelativePath::new("b.md"), "- [[projects/Target.md]]\n");
+        index.update_note(RelativePath::new("c.md"), "[[other/Target]] [[#Target]]\n");
+
+        let page = RelativePath::new("projects/Target.md");
+        assert_eq!(
+            index.backlinks_for(page),
+            vec![
+                (RelativePathBuf::from("a.md"), 4..14),
+                (RelativePathBuf::from("a.md"), 19..41),
+                (RelativePathBuf::from("b.md"), 2..24),
+            ]
+        );
+        assert!(
+            index
+                .backlinks_for(RelativePath::new("Missing.md"))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_update_and_remove_replace_a_notes_links() {
+        let mut index = BacklinkIndex::default();
+        let source = RelativePath::new("a.md");
+        index.update_note(source, "[[b]] [[c]]\n");
+        assert_eq!(index.backlinks_for(RelativePath::new("b.md")).len(), 1);
+
+        index.update_note(source, "[[c]]\n");
+        assert!(index.backlinks_for(RelativePath::new("b.md")).is_empty());
+        assert_eq!(index.backlinks_for(RelativePath::new("c.md")).len(), 1);
+
+        index.remove_note(source);
+        assert!(index.is_empty());
+        assert!(index.backlinks_for(RelativePath::new("c.md")).is_empty());
+    }
+
+    #[test]
+    fn test_scan_and_snapshot_round_trip() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "a.md", "[[b]]\n");
+        std::fs::create_dir(notes_dir.path().join("sub")).unwrap();
+        create_test_file(&notes_dir, "sub/c.md", "text [[B]]\n");
+
+        let index = BacklinkIndex::scan(notes_dir.path()).unwrap();
+        let backlinks = index.backlinks_for(RelativePath::new("b.md"));
+        assert_eq!(
+            backlinks,
+            vec![
+                (RelativePathBuf::from("a.md"), 0..5),
+                (RelativePathBuf::from("sub/c.md"), 5..10),
+            ]
+        );
+
+        let mut restored = BacklinkIndex::default();
+        for path in ["a.md", "sub/c.md"] {
+            let path = RelativePath::new(path);
+            let bytes = index.snapshot_note(path).unwrap();
+            assert!(restored.restore_note(path, &bytes));
+        }
+        assert_eq!(restored, index);
+        assert!(!restored.restore_note(RelativePath::new("a.md"), b"\x05"));
+    }
+}
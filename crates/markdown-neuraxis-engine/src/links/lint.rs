@@ -0,0 +1,296 @@
+//! # Vault Gardening Reports
+//!
+//! Notes drift as a vault grows: pages nothing links to, links to pages
+//! that were never written or have been renamed, and notes created but
+//! never filled in. [`VaultLint`] reads the vault once and reports each:
+//!
+//! - [`orphan_notes`](VaultLint::orphan_notes): no wiki-link from another
+//!   note leads to them
+//! - [`dead_links`](VaultLint::dead_links): wiki-links whose target
+//!   matches no note
+//! - [`empty_notes`](VaultLint::empty_notes): nothing but whitespace and
+//!   front matter
+//!
+//! A link to one of a note's [aliases](crate::titles::note_aliases) counts
+//! as a link to that note. Wiki-link embeds of attachments (`![[pic.png]]`)
+//! aren't note links and are left to [`crate::assets`].
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use std::path::Path;
+
+use markdown_neuraxis_syntax::{SyntaxKind, parse};
+use relative_path::{RelativePath, RelativePathBuf};
+
+use crate::assets::ASSET_EXTENSIONS;
+use crate::io::{self, IoError};
+use crate::links::{WikiLinkRef, extract_wikilinks, target_matches_note};
+use crate::titles::note_aliases;
+
+/// A wiki-link whose target matches no note.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeadLink {
+    /// Note containing the link
+    pub path: RelativePathBuf,
+    /// Byte range of the whole `[[...]]`
+    pub range: Range<usize>,
+    /// 1-based line number, for display
+    pub line: usize,
+    /// Note name the link points at
+    pub target: String,
+}
+
+#[derive(Debug, Clone)]
+struct LintNote {
+    links: Vec<(WikiLinkRef, usize)>,
+    aliases: Vec<String>,
+    empty: bool,
+}
+
+/// Link and content facts about every note in the vault, for gardening
+/// reports.
+#[derive(Debug, Clone, Default)]
+pub struct VaultLint {
+    notes: BTreeMap<RelativePathBuf, LintNote>,
+}
+
+impl VaultLint {
+    /// Read notes from their paths and sources.
+    pub fn build(notes: impl IntoIterator<Item = (RelativePathBuf, String)>) -> Self {
+        let notes = notes
+            .into_iter()
+            .map(|(path, source)| {
+                let links = extract_wikilinks(&source)
+                    .into_iter()
+                    .filter(|link| !link.target.is_empty() && !is_attachment(&link.target))
+                    .map(|link| {
+                        let line = source[..link.range.start].matches('\n').count() + 1;
+                        (link, line)
+                    })
+                    .collect();
+                let aliases = note_aliases(&source);
+                let empty = is_empty_note(&source);
+                (
+                    path,
+                    LintNote {
+                        links,
+                        aliases,
+                        empty,
+                    },
+                )
+            })
+            .collect();
+        Self { notes }
+    }
+
+    /// Read the vault on disk.
+    ///
+    /// Notes in hidden folders and templates are left out (see
+    /// [`io::is_hidden_or_template`]).
+    pub fn load(notes_root: &Path) -> Result<Self, IoError> {
+        let mut notes = Vec::new();
+        for path in io::scan_markdown_relative_paths(notes_root)? {
+            if io::is_hidden_or_template(&path) {
+                continue;
+            }
+            let source = io::read_file(&path, notes_root)?;
+            notes.push((path, source));
+        }
+        Ok(Self::build(notes))
+    }
+
+    /// Notes no other note links to, by path.
+    pub fn orphan_notes(&self) -> Vec<&RelativePath> {
+        let names = self.notes_by_name();
+        let mut linked = BTreeMap::new();
+        for (from, note) in &self.notes {
+            for (link, _) in &note.links {
+                for to in resolve(&names, &link.target) {
+                    if to != from.as_relative_path() {
+                        linked.insert(to, ());
+                    }
+                }
+            }
+        }
+        self.notes
+            .keys()
+            .map(|path| path.as_relative_path())
+            .filter(|path| !linked.contains_key(path))
+            .collect()
+    }
+
+    /// Wiki-links to notes that don't exist, by path and then position.
+    pub fn dead_links(&self) -> Vec<DeadLink> {
+        let names = self.notes_by_name();
+        let mut dead = Vec::new();
+        for (path, note) in &self.notes {
+            for (link, line) in &note.links {
+                if resolve(&names, &link.target).is_empty() {
+                    dead.push(DeadLink {
+                        path: path.clone(),
+                        range: link.range.clone(),
+                        line: *line,
+                        target: link.target.clone(),
+                    });
+                }
+            }
+        }
+        dead
+    }
+
+    /// Notes with no content besides whitespace and front matter, by path.
+    pub fn empty_notes(&self) -> Vec<&RelativePath> {
+        self.notes
+            .iter()
+            .filter(|(_, note)| note.empty)
+            .map(|(path, _)| path.as_relative_path())
+            .collect()
+    }
+
+    /// Notes grouped by lowercased file stem, the part a link target must
+    /// end with, and by lowercased alias.
+    fn notes_by_name(&self) -> NoteNames<'_> {
+        let mut names = NoteNames::default();
+        for (path, note) in &self.notes {
+            if let Some(stem) = path.file_stem() {
+                names
+                    .stems
+                    .entry(stem.to_lowercase())
+                    .or_default()
+                    .push(path.as_relative_path());
+            }
+            for alias in &note.aliases {
+                names
+                    .aliases
+                    .entry(alias.to_lowercase())
+                    .or_default()
+                    .push(path.as_relative_path());
+            }
+        }
+        names
+    }
+}
+
+#[derive(Default)]
+struct NoteNames<'a> {
+    stems: HashMap<String, Vec<&'a RelativePath>>,
+    aliases: HashMap<String, Vec<&'a RelativePath>>,
+}
+
+/// Notes a link target refers to: by path, or else by alias.
+fn resolve<'a>(names: &'a NoteNames<'a>, target: &'a str) -> Vec<&'a RelativePath> {
+    let target_path = io::normalize_separators(target);
+    let stem = RelativePath::new(&target_path)
+        .file_name()
+        .map(|name| {
+            let name = name.to_lowercase();
+            name.strip_suffix(".md").unwrap_or(&name).to_string()
+        })
+        .unwrap_or_default();
+    let by_path: Vec<&RelativePath> = names
+        .stems
+        .get(&stem)
+        .into_iter()
+        .flatten()
+        .copied()
+        .filter(|path| target_matches_note(target, path))
+        .collect();
+    if !by_path.is_empty() {
+        return by_path;
+    }
+    names
+        .aliases
+        .get(&target.trim().to_lowercase())
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn is_attachment(target: &str) -> bool {
+    RelativePath::new(target).extension().is_some_and(|ext| {
+        ASSET_EXTENSIONS
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(ext))
+    })
+}
+
+fn is_empty_note(source: &str) -> bool {
+    let body_start = parse(source)
+        .children()
+        .find(|node| node.kind() == SyntaxKind::FRONTMATTER)
+        .map_or(0, |node| node.text_range().end().into());
+    source[body_start..].trim().is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault(notes: &[(&str, &str)]) -> VaultLint {
+        VaultLint::build(
+            notes
+                .iter()
+                .map(|(path, source)| (RelativePathBuf::from(path), source.to_string())),
+        )
+    }
+
+    #[test]
+    fn test_orphans_and_dead_links() {
+        let lint = vault(&[
+            (
+                "index.md",
+                "See [[Projects/Plan]] and [[ideas|my ideas]].\n",
+            ),
+            (
+                "ideas.md",
+                "Back to [[index]], [[ideas]] and [[Missing Page]]\n",
+            ),
+            (
+                "projects/plan.md",
+                "![[diagram.png]] [[#Goals]]\n\n[[projects/gone]]\n",
+            ),
+            ("lonely.md", "Links to itself: [[lonely]]\n"),
+            ("renamed.md", "---\naliases: [Old Name]\n---\n"),
+            ("log.md", "From [[old name]]\n"),
+        ]);
+
+        assert_eq!(
+            lint.orphan_notes(),
+            [RelativePath::new("log.md"), RelativePath::new("lonely.md")]
+        );
+        assert_eq!(
+            lint.dead_links(),
+            [
+                DeadLink {
+                    path: "ideas.md".into(),
+                    range: 33..49,
+                    line: 1,
+                    target: "Missing Page".into(),
+                },
+                DeadLink {
+                    path: "projects/plan.md".into(),
+                    range: 29..46,
+                    line: 3,
+                    target: "projects/gone".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_notes() {
+        let lint = vault(&[
+            ("blank.md", ""),
+            ("spaces.md", " \n\n\t\n"),
+            ("meta.md", "---\ntitle: Draft\n---\n\n"),
+            ("note.md", "---\ntitle: Done\n---\n\nSome text\n"),
+        ]);
+        assert_eq!(
+            lint.empty_notes(),
+            [
+                RelativePath::new("blank.md"),
+                RelativePath::new("meta.md"),
+                RelativePath::new("spaces.md"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! # Vault-Level Wiki-Link Handling
+//!
+//! Features that look across notes (heading renames, backlinks) need to find
+//! `[[target#heading|alias]]` references in raw note text. This module walks
+//! the Rowan tree for `WIKILINK` nodes and reads their parts with byte
+//! ranges, so callers can make precise edits without re-serialising the
+//! surrounding Markdown.
+//!
+//! [`preview`] builds the small title/excerpt/image cards shown when hovering
+//! a link.
+
+pub mod backlinks;
+pub mod lint;
+pub mod preview;
+pub mod rename;
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use markdown_neuraxis_syntax::wikilink::WikiLinkParts;
+use markdown_neuraxis_syntax::{SyntaxKind, SyntaxNode, parse, visit};
+use relative_path::{RelativePath, RelativePathBuf};
+
+use crate::vault::{IndexKind, SnapshotReader, SnapshotWriter, VaultIndex};
+
+pub use backlinks::BacklinkIndex;
+pub use lint::{DeadLink, VaultLint};
+pub use preview::{NotePreview, PreviewImage};
+pub use rename::{HeadingInfo, HeadingRename, LinkUpdate};
+
+/// A wiki-link found in a note's source text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WikiLinkRef {
+    /// Full byte range of the link, including the `[[` and `]]` delimiters
+    pub range: Range<usize>,
+    /// Note name being linked to. Empty for same-note links like `[[#Heading]]`
+    pub target: String,
+    /// Heading fragment after `#`, if present, without the spaces around it
+    pub heading: Option<String>,
+    /// Byte range of the heading fragment (excluding the `#` and spaces)
+    pub heading_range: Option<Range<usize>>,
+    /// Display alias after `|`, if present and not blank
+    pub alias: Option<String>,
+}
+
+/// Find all wiki-links in the given Markdown source.
+pub fn extract_wikilinks(source: &str) -> Vec<WikiLinkRef> {
+    let tree = parse(source);
+    visit::nodes_of_kind(&tree, SyntaxKind::WIKILINK)
+        .filter_map(|node| wikilink_ref(&node))
+        .collect()
+}
+
+/// The parts of a closed `WIKILINK` node.
+pub(crate) fn wikilink_ref(node: &SyntaxNode) -> Option<WikiLinkRef> {
+    let link = WikiLinkParts::of(node)?;
+    let text = |part: &Option<SyntaxNode>| part.as_ref().map(|node| node.text().to_string());
+    Some(WikiLinkRef {
+        range: node.text_range().into(),
+        target: link.target_text(),
+        heading: text(&link.section),
+        heading_range: link.section.map(|section| section.text_range().into()),
+        alias: text(&link.alias),
+    })
+}
+
+/// Check whether a wiki-link target refers to the given note.
+///
+/// Matching is case-insensitive and ignores a `.md` suffix. Bare names match
+/// on file name alone; targets containing `/` (or `\`) must match the full
+/// relative path.
+pub fn target_matches_note(target: &str, note: &RelativePath) -> bool {
+    let normalize = |s: &str| {
+        let s = s.strip_suffix(".md").or(s.strip_suffix(".MD")).unwrap_or(s);
+        s.to_lowercase()
+    };
+
+    let wanted = normalize(&crate::io::normalize_separators(target));
+    if wanted.contains('/') {
+        normalize(note.as_str()) == wanted
+    } else {
+        note.file_name().map(normalize).as_deref() == Some(wanted.as_str())
+    }
+}
+
+/// Outgoing wiki-links for every note in the vault.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LinkIndex {
+    outgoing: BTreeMap<RelativePathBuf, Vec<WikiLinkRef>>,
+}
+
+impl LinkIndex {
+    /// Re-index a single note from its current source.
+    pub fn update_note(&mut self, path: &RelativePath, source: &str) {
+        self.outgoing
+            .insert(path.to_relative_path_buf(), extract_wikilinks(source));
+    }
+
+    /// Drop a note from the index (e.g. after deletion).
+    pub fn remove_note(&mut self, path: &RelativePath) {
+        self.outgoing.remove(path);
+    }
+
+    /// Wiki-links found in the given note.
+    pub fn links_from(&self, path: &RelativePath) -> &[WikiLinkRef] {
+        self.outgoing.get(path).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Number of indexed notes.
+    pub fn len(&self) -> usize {
+        self.outgoing.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outgoing.is_empty()
+    }
+}
+
+impl VaultIndex for LinkIndex {
+    fn kind(&self) -> IndexKind {
+        IndexKind::Links
+    }
+
+    fn add_note(&mut self, path: &RelativePath, source: &str) {
+        self.update_note(path, source);
+    }
+
+    fn remove_note(&mut self, path: &RelativePath) {
+        self.remove_note(path);
+    }
+
+    fn snapshot_note(&self, path: &RelativePath) -> Option<Vec<u8>> {
+        let links = self.outgoing.get(path)?;
+        let mut out = SnapshotWriter::default();
+        out.u64(links.len() as u64);
+        for link in links {
+            out.u64(link.range.start as u64);
+            out.u64(link.range.end as u64);
+            out.str(&link.target);
+            out.opt_str(link.heading.as_deref());
+            match &link.heading_range {
+                Some(range) => {
+                    out.u64(1);
+                    out.u64(range.start as u64);
+                    out.u64(range.end as u64);
+                }
+                None => out.u64(0),
+            }
+            out.opt_str(link.alias.as_deref());
+        }
+        Some(out.into_bytes())
+    }
+
+    fn restore_note(&mut self, path: &RelativePath, bytes: &[u8]) -> bool {
+        fn decode(input: &mut SnapshotReader<'_>) -> Option<Vec<WikiLinkRef>> {
+            let count = input.u64()?;
+            let mut links = Vec::new();
+            for _ in 0..count {
+                let range = read_range(input)?;
+                let target = input.str()?;
+                let heading = input.opt_str()?;
+                let heading_range = match input.u64()? {
+                    0 => None,
+                    1 => Some(read_range(input)?),
+                    _ => return None,
+                };
+                let alias = input.opt_str()?;
+                links.push(WikiLinkRef {
+                    range,
+                    target,
+                    heading,
+                    heading_range,
+                    alias,
+                });
+            }
+            input.is_empty().then_some(links)
+        }
+        fn read_range(input: &mut SnapshotReader<'_>) -> Option<Range<usize>> {
+            let start = usize::try_from(input.u64()?).ok()?;
+            let end = usize::try_from(input.u64()?).ok()?;
+            Some(start..end)
+        }
+
+        match decode(&mut SnapshotReader::new(bytes)) {
+            Some(links) => {
+                self.outgoing.insert(path.to_relative_path_buf(), links);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_plain_wikilink() {
+        let links = extract_wikilinks("See [[My Note]] here\n");
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].range, 4..15);
+        assert_eq!(links[0].target, "My Note");
+        assert_eq!(links[0].heading, None);
+        assert_eq!(links[0].alias, None);
+    }
+
+    #[test]
+    fn test_extract_wikilink_with_heading_and_alias() {
+        let source = "- [[Note#Some Heading|label]]\n";
+        let links = extract_wikilinks(source);
+        assert_eq!(links.len(), 1);
+        let link = &links[0];
+        assert_eq!(link.target, "Note");
+        assert_eq!(link.heading.as_deref(), Some("Some Heading"));
+        assert_eq!(&source[link.heading_range.clone().unwrap()], "Some Heading");
+        assert_eq!(link.alias.as_deref(), Some("label"));
+    }
+
+    #[test]
+    fn test_extract_same_note_heading_link() {
+        let links = extract_wikilinks("[[#Intro]]\n");
+        assert_eq!(links[0].target, "");
+        assert_eq!(links[0].heading.as_deref(), Some("Intro"));
+    }
+
+    #[test]
+    fn test_wikilinks_in_code_are_ignored() {
+        let links = extract_wikilinks("`[[not a link]]`\n\n```\n[[nor this]]\n```\n");
+        assert!(links.is_empty());
+    }
+
+    #[test]
+    fn test_target_matches_note() {
+        let note = RelativePath::new("projects/My Note.md");
+        assert!(target_matches_note("My Note", note));
+        assert!(target_matches_note("my note", note));
+        assert!(target_matches_note("My Note.md", note));
+        assert!(target_matches_note("projects/My Note", note));
+        assert!(!target_matches_note("other/My Note", note));
+        assert!(!target_matches_note("Other", note));
+        assert!(target_matches_note(r"projects\My Note", note));
+    }
+
+    #[test]
+    fn test_link_index_update_and_remove() {
+        let mut index = LinkIndex::default();
+        let path = RelativePath::new("a.md");
+        index.update_note(path, "[[b]] and [[c#Top]]\n");
+        assert_eq!(index.links_from(path).len(), 2);
+
+        index.update_note(path, "no links\n");
+        assert!(index.links_from(path).is_empty());
+
+        index.remove_note(path);
+        assert!(index.is_empty());
+    }
+}
@@ -0,0 +1,298 @@
+//! # Link Previews
+//!
+//! Hovering a wiki-link shows a card with the target note's title, opening
+//! paragraph and first picture. Building a full [`Snapshot`] of the target
+//! for that is wasteful, so [`preview_note`] walks only the first few leaf
+//! blocks of the Rowan tree and keeps plain text:
+//!
+//! - the title comes from [`derive_title`], as everywhere else
+//! - the excerpt is the first paragraph with any text, with inline markup
+//!   dropped and links reduced to their labels
+//! - the image is the first Markdown image or attachment embed
+//!   (`![[pic.png]]`)
+//!
+//! [`load_preview`] reads the note from disk first, so a frontend can run
+//! it off the UI thread.
+//!
+//! [`Snapshot`]: crate::editing::Snapshot
+
+use std::path::{Path, PathBuf};
+
+use markdown_neuraxis_syntax::{SyntaxElement, SyntaxKind, SyntaxNode, parse};
+use relative_path::{RelativePath, RelativePathBuf};
+
+use super::wikilink_ref;
+use crate::assets::{asset_files, is_asset};
+use crate::editing::snapshot::link_parts;
+use crate::io::{self, IoError};
+use crate::titles::derive_title;
+
+/// Longest excerpt kept, in characters; longer ones end with `…`.
+pub const PREVIEW_EXCERPT_CHARS: usize = 280;
+
+/// Blocks that hold content directly, counted against `max_blocks`.
+const LEAF_BLOCKS: &[SyntaxKind] = &[
+    SyntaxKind::PARAGRAPH,
+    SyntaxKind::HEADING,
+    SyntaxKind::SETEXT_HEADING,
+    SyntaxKind::FENCED_CODE,
+    SyntaxKind::INDENTED_CODE,
+    SyntaxKind::HTML_BLOCK,
+    SyntaxKind::TABLE,
+    SyntaxKind::THEMATIC_BREAK,
+];
+
+/// What a hover card shows for a note.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotePreview {
+    pub path: RelativePathBuf,
+    pub title: String,
+    /// Plain text of the first paragraph, if any
+    pub excerpt: Option<String>,
+    pub image: Option<PreviewImage>,
+}
+
+/// The first image in a note.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreviewImage {
+    /// Destination as written: a URL, a path relative to the note, or an
+    /// attachment name for embeds
+    pub src: String,
+    /// Alt text; empty for embeds
+    pub alt: String,
+    /// Written as a wiki-link embed (`![[pic.png]]`) rather than `![alt](src)`
+    pub embed: bool,
+}
+
+impl PreviewImage {
+    /// The local file this image shows, if it exists.
+    ///
+    /// Markdown images resolve like [`io::resolve_asset`]. Embeds with a
+    /// path are relative to the notes root; bare names are looked up by
+    /// file name anywhere in the vault. Remote URLs give `None`, for the
+    /// frontend to load `src` itself.
+    pub fn resolve(&self, note_path: &RelativePath, notes_root: &Path) -> Option<PathBuf> {
+        if !self.embed {
+            return io::resolve_asset(&self.src, note_path, notes_root);
+        }
+        if self.src.contains('/') {
+            let path = RelativePath::new(&self.src).normalize().to_path(notes_root);
+            return path.is_file().then_some(path);
+        }
+        asset_files(notes_root)
+            .ok()?
+            .into_iter()
+            .find(|(path, _)| {
+                path.file_name()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(&self.src))
+            })
+            .map(|(path, _)| path.to_path(notes_root))
+    }
+}
+
+/// Preview of the note at `path` with the given source, looking no further
+/// than its first `max_blocks` leaf blocks (paragraphs, headings, code,
+/// tables) for the excerpt and image.
+pub fn preview_note(path: &RelativePath, source: &str, max_blocks: usize) -> NotePreview {
+    let tree = parse(source);
+    let mut excerpt = None;
+    let mut image = None;
+
+    let blocks = tree
+        .descendants()
+        .filter(|node| LEAF_BLOCKS.contains(&node.kind()))
+        .take(max_blocks);
+    for block in blocks {
+        if block.kind() != SyntaxKind::PARAGRAPH {
+            continue;
+        }
+        if image.is_none() {
+            image = first_image(&block);
+        }
+        if excerpt.is_none() {
+            let mut text = String::new();
+            push_plain_text(&block, &mut text);
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            if !text.is_empty() {
+                excerpt = Some(truncate(text));
+            }
+        }
+        if excerpt.is_some() && image.is_some() {
+            break;
+        }
+    }
+
+    NotePreview {
+        path: path.to_relative_path_buf(),
+        title: derive_title(path, source),
+        excerpt,
+        image,
+    }
+}
+
+/// Read the note at `path` and preview it; see [`preview_note`].
+pub fn load_preview(
+    notes_root: &Path,
+    path: &RelativePath,
+    max_blocks: usize,
+) -> Result<NotePreview, IoError> {
+    let source = io::read_file(path, notes_root)?;
+    Ok(preview_note(path, &source, max_blocks))
+}
+
+/// The first Markdown image or attachment embed in a paragraph.
+fn first_image(paragraph: &SyntaxNode) -> Option<PreviewImage> {
+    paragraph.descendants().find_map(|node| match node.kind() {
+        SyntaxKind::IMAGE => {
+            let (alt, src) = link_parts(&node)?;
+            (!src.is_empty()).then_some(PreviewImage {
+                src,
+                alt,
+                embed: false,
+            })
+        }
+        SyntaxKind::WIKILINK if is_embed(&node) => {
+            let link = wikilink_ref(&node)?;
+            is_asset(RelativePath::new(&link.target)).then_some(PreviewImage {
+                src: link.target,
+                alt: String::new(),
+                embed: true,
+            })
+        }
+        _ => None,
+    })
+}
+
+/// Whether a wiki-link is preceded by `!`.
+fn is_embed(wikilink: &SyntaxNode) -> bool {
+    wikilink
+        .prev_sibling_or_token()
+        .is_some_and(|prev| prev.kind() == SyntaxKind::EXCLAIM)
+}
+
+/// Append the readable text of `node`: markup delimiters, images, embeds
+/// and properties are dropped, and links give their label.
+fn push_plain_text(node: &SyntaxNode, out: &mut String) {
+    let delimiters: &[SyntaxKind] = match node.kind() {
+        SyntaxKind::EMPHASIS | SyntaxKind::STRONG => &[SyntaxKind::STAR, SyntaxKind::UNDERSCORE],
+        SyntaxKind::STRIKETHROUGH => &[SyntaxKind::TILDE],
+        SyntaxKind::CODE_SPAN => &[SyntaxKind::BACKTICK],
+        _ => &[],
+    };
+
+    for element in node.children_with_tokens() {
+        match element {
+            SyntaxElement::Node(child) => match child.kind() {
+                SyntaxKind::IMAGE | SyntaxKind::PROPERTY => {}
+                SyntaxKind::WIKILINK if is_embed(&child) => {}
+                SyntaxKind::WIKILINK => {
+                    if let Some(link) = wikilink_ref(&child) {
+                        out.push_str(match (&link.alias, link.target.is_empty()) {
+                            (Some(alias), _) => alias,
+                            (None, false) => &link.target,
+                            (None, true) => link.heading.as_deref().unwrap_or(""),
+                        });
+                    }
+                }
+                SyntaxKind::LINK => match link_parts(&child) {
+                    Some((label, _)) => out.push_str(&label),
+                    None => out.push_str(&child.text().to_string()),
+                },
+                _ => push_plain_text(&child, out),
+            },
+            SyntaxElement::Token(token) => match token.kind() {
+                kind if delimiters.contains(&kind) => {}
+                SyntaxKind::EXCLAIM
+                    if token
+                        .next_sibling_or_token()
+                        .is_some_and(|next| next.kind() == SyntaxKind::WIKILINK) => {}
+                SyntaxKind::NEWLINE | SyntaxKind::HARD_BREAK => out.push(' '),
+                SyntaxKind::ESCAPE => out.push_str(&token.text()[1..]),
+                _ => out.push_str(token.text()),
+            },
+        }
+    }
+}
+
+fn truncate(text: String) -> String {
+    match text.char_indices().nth(PREVIEW_EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}…", text[..end].trim_end()),
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{create_test_file, create_test_notes_dir};
+
+    #[test]
+    fn test_preview_title_excerpt_and_image() {
+        let source = "---\ntitle: Garden Plans\n---\n# Heading\n\n\
+                      Plant **tomatoes** and [[Herbs|basil]] by `May`, see [guide](g.md).\n\
+                      alias:: garden\n\n\
+                      ![Beds](assets/beds.png \"Raised\")\n";
+        let preview = preview_note(RelativePath::new("garden.md"), source, 8);
+
+        assert_eq!(preview.title, "Garden Plans");
+        assert_eq!(
+            preview.excerpt.as_deref(),
+            Some("Plant tomatoes and basil by May, see guide.")
+        );
+        assert_eq!(
+            preview.image,
+            Some(PreviewImage {
+                src: "assets/beds.png".to_string(),
+                alt: "Beds".to_string(),
+                embed: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_preview_outline_embed_and_block_limit() {
+        let source = "- ![[Diagram.PNG]]\n- First *point*\n- Second\n";
+        let preview = preview_note(RelativePath::new("notes/outline.md"), source, 2);
+        assert_eq!(preview.title, "outline");
+        assert_eq!(preview.excerpt.as_deref(), Some("First point"));
+        assert!(preview.image.as_ref().is_some_and(|image| image.embed));
+
+        let preview = preview_note(RelativePath::new("notes/outline.md"), source, 1);
+        assert_eq!(preview.excerpt, None);
+        assert!(preview.image.is_some());
+
+        let long = format!("{}\n", "word ".repeat(100));
+        let excerpt = preview_note(RelativePath::new("long.md"), &long, 1)
+            .excerpt
+            .unwrap();
+        assert!(excerpt.ends_with('…'));
+        assert!(excerpt.chars().count() <= PREVIEW_EXCERPT_CHARS + 1);
+    }
+
+    #[test]
+    fn test_load_preview_resolves_images() {
+        let notes_dir = create_test_notes_dir();
+        let root = notes_dir.path();
+        std::fs::create_dir_all(root.join("assets")).unwrap();
+        create_test_file(&notes_dir, "assets/Diagram.png", "png");
+        create_test_file(&notes_dir, "embed.md", "Intro\n\n![[diagram.png]]\n");
+        create_test_file(&notes_dir, "image.md", "![x](assets/Diagram.png)\n");
+        create_test_file(&notes_dir, "remote.md", "![x](https://example.com/a.png)\n");
+
+        for note in ["embed.md", "image.md"] {
+            let path = RelativePath::new(note);
+            let preview = load_preview(root, path, 4).unwrap();
+            assert_eq!(
+                preview.image.unwrap().resolve(path, root),
+                Some(root.join("assets/Diagram.png"))
+            );
+        }
+
+        let path = RelativePath::new("remote.md");
+        let image = load_preview(root, path, 4).unwrap().image.unwrap();
+        assert_eq!(image.src, "https://example.com/a.png");
+        assert_eq!(image.resolve(path, root), None);
+
+        assert!(load_preview(root, RelativePath::new("missing.md"), 4).is_err());
+    }
+}
@@ -0,0 +1,339 @@
+//! # Heading Rename Refactoring
+//!
+//! Renaming a heading breaks `[[note#Old Heading]]` links in other notes.
+//! This module lets frontends notice a rename as it happens (by comparing
+//! headings before and after a command) and then preview and apply updates
+//! to inbound heading links across the vault.
+//!
+//! The flow is:
+//!
+//! 1. [`apply_detecting_heading_renames`] wraps `Document::apply`
+//! 2. [`plan_heading_rename`] scans the vault and returns a preview list
+//! 3. [`apply_link_updates`] writes the (possibly user-filtered) updates
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::Path;
+
+use relative_path::{RelativePath, RelativePathBuf};
+
+use crate::editing::snapshot::{Block, BlockContent, BlockKind};
+use crate::editing::{AnchorId, Cmd, Document, Patch};
+use crate::io::{self, IoError};
+use crate::links::{extract_wikilinks, target_matches_note};
+
+/// A heading in a document, identified by its stable block ID.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadingInfo {
+    /// Stable block ID of the heading
+    pub id: AnchorId,
+    /// Heading level (1-6)
+    pub level: u8,
+    /// Raw heading text without the `#` prefix or closing sequence
+    pub text: String,
+}
+
+/// A heading whose text changed while keeping the same block ID.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadingRename {
+    pub id: AnchorId,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+/// A single proposed edit to an inbound heading link.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkUpdate {
+    /// Note containing the link
+    pub path: RelativePathBuf,
+    /// Byte range of the heading fragment to replace
+    pub range: Range<usize>,
+    /// 1-based line number, for preview display
+    pub line: usize,
+    /// Full source line containing the link, for preview display
+    pub context: String,
+    /// Current heading fragment text
+    pub old_text: String,
+    /// Replacement heading fragment text
+    pub new_text: String,
+}
+
+/// Collect all headings in the document in source order.
+pub fn collect_headings(doc: &Document) -> Vec<HeadingInfo> {
+    fn walk(doc: &Document, blocks: &[Block], out: &mut Vec<HeadingInfo>) {
+        for block in blocks {
+            if let BlockKind::Heading { level } = block.kind {
+                out.push(HeadingInfo {
+                    id: block.id,
+                    level,
+                    text: heading_text(doc, block),
+                });
+            }
+            if let BlockContent::Children(children) = &block.content {
+                walk(doc, children, out);
+            }
+        }
+    }
+
+    let mut headings = Vec::new();
+    walk(doc, &doc.snapshot().blocks, &mut headings);
+    headings
+}
+
+/// Text of a heading block: its segments, which leave out the `#`s and
+/// any closing sequence.
+pub(crate) fn heading_text(doc: &Document, block: &Block) -> String {
+    match (block.segments.first(), block.segments.last()) {
+        (Some(first), Some(last)) => doc.slice(first.range.start..last.range.end),
+        _ => String::new(),
+    }
+}
+
+/// Compare headings before and after an edit and report renames.
+///
+/// Headings are matched by block ID, so a heading whose text was edited in
+/// place is reported while inserted or deleted headings are not.
+pub fn detect_heading_renames(before: &[HeadingInfo], after: &[HeadingInfo]) -> Vec<HeadingRename> {
+    after
+        .iter()
+        .filter_map(|new| {
+            let old = before.iter().find(|old| old.id == new.id)?;
+            (old.text != new.text && !old.text.is_empty() && !new.text.is_empty()).then(|| {
+                HeadingRename {
+                    id: new.id,
+                    old_text: old.text.clone(),
+                    new_text: new.text.clone(),
+                }
+            })
+        })
+        .collect()
+}
+
+/// Apply a command and report any headings it renamed.
+pub fn apply_detecting_heading_renames(
+    doc: &mut Document,
+    cmd: Cmd,
+) -> (Patch, Vec<HeadingRename>) {
+    let before = collect_headings(doc);
+    let patch = doc.apply(cmd);
+    let after = collect_headings(doc);
+    (patch, detect_heading_renames(&before, &after))
+}
+
+/// Find inbound links to a renamed heading across the vault.
+///
+/// `note` is the note containing the heading. Same-note links (`[[#Heading]]`)
+/// inside that note are included. Heading matching is case-insensitive.
+pub fn plan_heading_rename(
+    notes_root: &Path,
+    note: &RelativePath,
+    rename: &HeadingRename,
+) -> Result<Vec<LinkUpdate>, IoError> {
+    let mut updates = Vec::new();
+    let old_heading = rename.old_text.to_lowercase();
+
+    for path in io::scan_markdown_relative_paths(notes_root)? {
+        let source = io::read_file(&path, notes_root)?;
+        let is_same_note = path.as_relative_path() == note;
+
+        for link in extract_wikilinks(&source) {
+            let (Some(heading), Some(range)) = (&link.heading, &link.heading_range) else {
+                continue;
+            };
+            let targets_note = if link.target.is_empty() {
+                is_same_note
+            } else {
+                target_matches_note(&link.target, note)
+            };
+            if !targets_note || heading.trim().to_lowercase() != old_heading {
+                continue;
+            }
+
+            let line_start = source[..range.start].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = source[range.end..]
+                .find('\n')
+                .map_or(source.len(), |i| range.end + i);
+            updates.push(LinkUpdate {
+                path: path.clone(),
+                range: range.clone(),
+                line: source[..range.start].matches('\n').count() + 1,
+                context: source[line_start..line_end].to_string(),
+                old_text: heading.clone(),
+                new_text: rename.new_text.clone(),
+            });
+        }
+    }
+
+    Ok(updates)
+}
+
+/// Write link updates to disk, returning the number of files changed.
+///
+/// Updates are grouped per file and applied back-to-front so earlier byte
+/// ranges stay valid. Updates whose range no longer holds the expected old
+/// text (the file changed since planning) are skipped.
+pub fn apply_link_updates(notes_root: &Path, updates: &[LinkUpdate]) -> Result<usize, IoError> {
+    let mut by_file: BTreeMap<&RelativePath, Vec<&LinkUpdate>> = BTreeMap::new();
+    for update in updates {
+        by_file.entry(&update.path).or_default().push(update);
+    }
+
+    let mut files_changed = 0;
+    for (path, mut file_updates) in by_file {
+        let mut source = io::read_file(path, notes_root)?;
+        file_updates.sort_by_key(|u| std::cmp::Reverse(u.range.start));
+
+        let mut changed = false;
+        for update in file_updates {
+            if source.get(update.range.clone()) == Some(update.old_text.as_str()) {
+                source.replace_range(update.range.clone(), &update.new_text);
+                changed = true;
+            }
+        }
+
+        if changed {
+            io::write_file(path, notes_root, &source)?;
+            files_changed += 1;
+        }
+    }
+
+    Ok(files_changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{create_test_file, create_test_notes_dir};
+
+    fn rename(old: &str, new: &str) -> HeadingRename {
+        HeadingRename {
+            id: AnchorId(0),
+            old_text: old.to_string(),
+            new_text: new.to_string(),
+        }
+    }
+
+    // ============ Heading detection ============
+
+    #[test]
+    fn test_collect_headings() {
+        let doc = Document::from_bytes(b"# Title\n\ntext\n\n## Section ##\n").unwrap();
+        let headings = collect_headings(&doc);
+        assert_eq!(headings.len(), 2);
+        assert_eq!(headings[0].text, "Title");
+        assert_eq!(headings[0].level, 1);
+        assert_eq!(headings[1].text, "Section");
+        assert_eq!(headings[1].level, 2);
+    }
+
+    #[test]
+    fn test_heading_text_keeps_hash_inside_word() {
+        let doc = Document::from_bytes(
+            b"# C#
+
+## Title ##
+",
+        )
+        .unwrap();
+        let texts: Vec<String> = collect_headings(&doc).into_iter().map(|h| h.text).collect();
+        assert_eq!(texts, ["C#", "Title"]);
+    }
+
+    #[test]
+    fn test_detect_rename_when_editing_heading_text() {
+        let mut doc = Document::from_bytes(b"# Intro\n\nbody\n").unwrap();
+        let (_, renames) = apply_detecting_heading_renames(
+            &mut doc,
+            Cmd::InsertText {
+                at: 7,
+                text: "duction".to_string(),
+            },
+        );
+        assert_eq!(renames.len(), 1);
+        assert_eq!(renames[0].old_text, "Intro");
+        assert_eq!(renames[0].new_text, "Introduction");
+    }
+
+    #[test]
+    fn test_no_rename_for_body_edit() {
+        let mut doc = Document::from_bytes(b"# Intro\n\nbody\n").unwrap();
+        let (_, renames) = apply_detecting_heading_renames(
+            &mut doc,
+            Cmd::InsertText {
+                at: 13,
+                text: " more".to_string(),
+            },
+        );
+        assert!(renames.is_empty());
+    }
+
+    // ============ Vault link updates ============
+
+    #[test]
+    fn test_plan_finds_inbound_and_same_note_links() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "target.md", "# Intro\n\nSee [[#intro]]\n");
+        create_test_file(
+            &notes_dir,
+            "other.md",
+            "- [[Target#Intro]]\n- [[Target#Other]]\n- [[Elsewhere#Intro]]\n",
+        );
+
+        let updates = plan_heading_rename(
+            notes_dir.path(),
+            RelativePath::new("target.md"),
+            &rename("Intro", "Overview"),
+        )
+        .unwrap();
+
+        assert_eq!(updates.len(), 2);
+        let other = updates.iter().find(|u| u.path == "other.md").unwrap();
+        assert_eq!(other.line, 1);
+        assert_eq!(other.context, "- [[Target#Intro]]");
+        assert!(updates.iter().any(|u| u.path == "target.md"));
+    }
+
+    #[test]
+    fn test_apply_link_updates_rewrites_files() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "target.md", "# Intro\n");
+        create_test_file(
+            &notes_dir,
+            "other.md",
+            "[[target#Intro|start]] and [[target#Intro]]\n",
+        );
+
+        let updates = plan_heading_rename(
+            notes_dir.path(),
+            RelativePath::new("target.md"),
+            &rename("Intro", "Getting Started"),
+        )
+        .unwrap();
+        let changed = apply_link_updates(notes_dir.path(), &updates).unwrap();
+
+        assert_eq!(changed, 1);
+        let content = io::read_file(RelativePath::new("other.md"), notes_dir.path()).unwrap();
+        assert_eq!(
+            content,
+            "[[target#Getting Started|start]] and [[target#Getting Started]]\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_skips_stale_updates() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "target.md", "# Intro\n");
+        create_test_file(&notes_dir, "other.md", "[[target#Intro]]\n");
+
+        let updates = plan_heading_rename(
+            notes_dir.path(),
+            RelativePath::new("target.md"),
+            &rename("Intro", "Overview"),
+        )
+        .unwrap();
+        create_test_file(&notes_dir, "other.md", "changed meanwhile\n");
+
+        let changed = apply_link_updates(notes_dir.path(), &updates).unwrap();
+        assert_eq!(changed, 0);
+    }
+}
@@ -1,4 +1,6 @@
+use crate::io;
 use crate::models::MarkdownFile;
+use crate::titles::TitleIndex;
 use relative_path::{RelativePath, RelativePathBuf};
 use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
@@ -37,62 +39,73 @@ impl FileTreeNode {
         }
     }
 
+    /// Insert a file given its path relative to this node. Either `/` or
+    /// `\\` separators are accepted.
     pub fn insert_file(&mut self, relative_path: &Path) {
-        let components: Vec<_> = relative_path.components().collect();
-        if components.is_empty() {
+        self.insert_relative(&io::relative_path_from_str(
+            &relative_path.to_string_lossy(),
+        ));
+    }
+
+    fn insert_relative(&mut self, relative_path: &RelativePath) {
+        let (first_component, rest) = match relative_path.as_str().split_once('/') {
+            Some((first, rest)) => (first.to_string(), Some(rest)),
+            None => (relative_path.as_str().to_string(), None),
+        };
+        if first_component.is_empty() {
             return;
         }
+        let child_relative_path = if self.relative_path.as_str().is_empty() {
+            RelativePathBuf::from(&first_component)
+        } else {
+            self.relative_path.join(&first_component)
+        };
 
-        let first_component = components[0].as_os_str().to_string_lossy().to_string();
-
-        if components.len() == 1 {
+        match rest {
             // This is a file in the current directory
-            let file_relative_path = if self.relative_path.as_str().is_empty() {
-                RelativePathBuf::from(&first_component)
-            } else {
-                self.relative_path.join(&first_component)
-            };
-
-            self.children.insert(
-                first_component.clone(),
-                FileTreeNode::new_file(first_component, file_relative_path),
-            );
-        } else {
+            None => {
+                self.children.insert(
+                    first_component.clone(),
+                    FileTreeNode::new_file(first_component, child_relative_path),
+                );
+            }
             // This is a folder, recurse
-            let remaining_path = relative_path.iter().skip(1).collect::<PathBuf>();
-            let folder_relative_path = if self.relative_path.as_str().is_empty() {
-                RelativePathBuf::from(&first_component)
-            } else {
-                self.relative_path.join(&first_component)
-            };
-
-            self.children
-                .entry(first_component.clone())
-                .or_insert_with(|| FileTreeNode::new_folder(first_component, folder_relative_path))
-                .insert_file(&remaining_path);
+            Some(rest) => {
+                self.children
+                    .entry(first_component.clone())
+                    .or_insert_with(|| {
+                        FileTreeNode::new_folder(first_component, child_relative_path)
+                    })
+                    .insert_relative(RelativePath::new(rest));
+            }
         }
     }
 
     /// Remove a file from the tree, returns true if the node itself should be removed
     pub fn remove_file(&mut self, relative_path: &Path) -> bool {
-        let components: Vec<_> = relative_path.components().collect();
-        if components.is_empty() {
+        self.remove_relative(&io::relative_path_from_str(
+            &relative_path.to_string_lossy(),
+        ))
+    }
+
+    fn remove_relative(&mut self, relative_path: &RelativePath) -> bool {
+        if relative_path.as_str().is_empty() {
             return false;
         }
 
-        let first_component = components[0].as_os_str().to_string_lossy().to_string();
-
-        if components.len() == 1 {
+        match relative_path.as_str().split_once('/') {
             // This is the file to remove
-            self.children.remove(&first_component);
-        } else {
+            None => {
+                self.children.remove(relative_path.as_str());
+            }
             // Recurse into subfolder
-            let remaining_path = relative_path.iter().skip(1).collect::<PathBuf>();
-            if let Some(child) = self.children.get_mut(&first_component) {
-                child.remove_file(&remaining_path);
-                // Remove empty folders
-                if child.is_folder && child.children.is_empty() {
-                    self.children.remove(&first_component);
+            Some((first_component, rest)) => {
+                if let Some(child) = self.children.get_mut(first_component) {
+                    child.remove_relative(RelativePath::new(rest));
+                    // Remove empty folders
+                    if child.is_folder && child.children.is_empty() {
+                        self.children.remove(first_component);
+                    }
                 }
             }
         }
@@ -152,8 +165,9 @@ impl FileTreeNode {
     /// Find a folder by target name or path (case-insensitive).
     /// Matches against full relative path or final folder name.
     pub fn find_folder_recursive(&self, target: &str) -> Option<RelativePathBuf> {
-        // Normalize: trim whitespace and trailing slashes
-        let target_normalized = target.trim().trim_end_matches('/');
+        // Normalize: separators, whitespace and trailing slashes
+        let target_normalized = io::normalize_separators(target.trim());
+        let target_normalized = target_normalized.trim_end_matches('/');
         let target_lower = target_normalized.to_lowercase();
 
         for child in self.children.values() {
@@ -178,6 +192,16 @@ impl FileTreeNode {
         None
     }
 
+    /// Replace file display names with titles from the index.
+    pub fn apply_titles(&mut self, titles: &TitleIndex) {
+        if let Some(markdown_file) = &self.markdown_file {
+            self.name = titles.note_title(markdown_file.relative_path());
+        }
+        for child in self.children.values_mut() {
+            child.apply_titles(titles);
+        }
+    }
+
     pub fn get_flattened_items(&self, depth: usize) -> Vec<FileTreeItem> {
         let mut items = Vec::new();
 
@@ -212,6 +236,16 @@ impl FileTreeNode {
     }
 }
 
+/// Last component of the notes root, whichever separator it uses.
+fn root_name(root_path: &Path) -> String {
+    let lossy = root_path.to_string_lossy();
+    let normalized = io::normalize_separators(&lossy);
+    match normalized.trim_end_matches('/').rsplit('/').next() {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => lossy.to_string(),
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct FileTreeItem {
     pub node: FileTreeNode,
@@ -225,11 +259,7 @@ pub struct FileTree {
 
 impl FileTree {
     pub fn new(root_path: PathBuf) -> Self {
-        let root_name = root_path
-            .file_name()
-            .unwrap_or_else(|| root_path.as_os_str())
-            .to_string_lossy()
-            .to_string();
+        let root_name = root_name(&root_path);
 
         Self {
             root: FileTreeNode::new_folder(root_name, RelativePathBuf::new()),
@@ -237,18 +267,14 @@ impl FileTree {
     }
 
     pub fn build_from_files(root_path: PathBuf, files: &[PathBuf]) -> Self {
-        let root_name = root_path
-            .file_name()
-            .unwrap_or_else(|| root_path.as_os_str())
-            .to_string_lossy()
-            .to_string();
+        let root_name = root_name(&root_path);
 
         let mut root = FileTreeNode::new_folder(root_name, RelativePathBuf::new());
         root.is_expanded = true;
 
         for file in files {
-            if let Ok(relative_path) = file.strip_prefix(&root_path) {
-                root.insert_file(relative_path);
+            if let Some(relative_path) = io::relative_to_root(file, &root_path) {
+                root.insert_relative(&relative_path);
             }
         }
 
@@ -287,18 +313,23 @@ impl FileTree {
 
     /// Add a new file to the tree
     pub fn add_file(&mut self, file_path: &Path, notes_root: &Path) {
-        if let Ok(relative_path) = file_path.strip_prefix(notes_root) {
-            self.root.insert_file(relative_path);
+        if let Some(relative_path) = io::relative_to_root(file_path, notes_root) {
+            self.root.insert_relative(&relative_path);
         }
     }
 
     /// Remove a file from the tree, cleaning up empty parent folders
     pub fn remove_file(&mut self, file_path: &Path, notes_root: &Path) {
-        if let Ok(relative_path) = file_path.strip_prefix(notes_root) {
-            self.root.remove_file(relative_path);
+        if let Some(relative_path) = io::relative_to_root(file_path, notes_root) {
+            self.root.remove_relative(&relative_path);
         }
     }
 
+    /// Show derived note titles (see [`crate::titles`]) instead of file names.
+    pub fn apply_titles(&mut self, titles: &TitleIndex) {
+        self.root.apply_titles(titles);
+    }
+
     pub fn get_items(&self) -> Vec<FileTreeItem> {
         // Return only children of root, not the root itself
         let mut items = Vec::new();
@@ -616,4 +647,55 @@ mod tests {
         assert_eq!(items.len(), 1);
         assert_eq!(items[0].node.name, "file2");
     }
+
+    #[test]
+    fn test_apply_titles() {
+        let root_path = PathBuf::from("/test/notes");
+        let files = vec![
+            PathBuf::from("/test/notes/2024_01_01.md"),
+            PathBuf::from("/test/notes/untitled.md"),
+        ];
+        let mut tree = FileTree::build_from_files(root_path, &files);
+
+        let mut titles = TitleIndex::default();
+        titles.update_note(RelativePath::new("2024_01_01.md"), "# New Year\n");
+        tree.apply_titles(&titles);
+
+        let names: Vec<_> = tree.get_items().into_iter().map(|i| i.node.name).collect();
+        assert_eq!(names, vec!["New Year", "untitled"]);
+    }
+
+    #[test]
+    fn test_windows_and_unc_paths() {
+        for (root, sep) in [
+            (r"C:\Users\me\notes", r"\"),
+            (r"\\server\share\notes", r"\"),
+        ] {
+            let files: Vec<_> = ["inbox.md", "1_Projects/active/plan.md"]
+                .iter()
+                .map(|f| PathBuf::from(format!("{root}{sep}{}", f.replace('/', sep))))
+                .collect();
+            let mut tree = FileTree::build_from_files(PathBuf::from(root), &files);
+
+            assert_eq!(tree.root.name, "notes");
+            let projects = tree.root.children.get("1_Projects").unwrap();
+            let plan = projects
+                .children
+                .get("active")
+                .unwrap()
+                .children
+                .get("plan.md");
+            assert_eq!(
+                plan.unwrap().relative_path.as_str(),
+                "1_Projects/active/plan.md"
+            );
+            assert_eq!(
+                tree.find_folder(r"1_projects\active"),
+                Some(RelativePathBuf::from("1_Projects/active"))
+            );
+
+            tree.remove_file(&files[1], Path::new(root));
+            assert!(!tree.root.children.contains_key("1_Projects"));
+        }
+    }
 }
@@ -1,5 +1,7 @@
 use relative_path::{RelativePath, RelativePathBuf};
 
+use crate::io;
+
 /// Represents a markdown file with a relative path and display-friendly name
 #[derive(Debug, Clone, PartialEq)]
 pub struct MarkdownFile {
@@ -25,9 +27,9 @@ impl MarkdownFile {
         }
     }
 
-    /// Create from a relative path string
+    /// Create from a relative path string, with either separator style
     pub fn from_relative_str(path: &str) -> Self {
-        Self::new(RelativePathBuf::from(path))
+        Self::new(io::relative_path_from_str(path))
     }
 
     /// Get the relative path
@@ -60,7 +62,7 @@ impl MarkdownFile {
         } else {
             format!("{}.md", display_path)
         };
-        Self::new(RelativePathBuf::from(path_with_ext))
+        Self::new(io::relative_path_from_str(&path_with_ext))
     }
 }
 
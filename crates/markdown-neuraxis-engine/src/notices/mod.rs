@@ -0,0 +1,155 @@
+//! # Notices
+//!
+//! One channel for problems users should hear about without stopping what
+//! they are doing, such as parts of a note the engine can't show. Engine
+//! code calls [`notify`]; each front-end calls [`subscribe`] once and
+//! polls the receiver, showing notices as toasts or in a status line and
+//! writing them to its log.
+//!
+//! Snapshots are rebuilt on every edit, so a notice identical to one of
+//! the last few sent is dropped rather than repeated.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// How many recent notices are remembered to drop repeats.
+const RECENT_LIMIT: usize = 32;
+
+/// How serious a notice is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// Something for the user to know about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notice {
+    pub severity: Severity,
+    /// Short description for the user
+    pub message: String,
+    /// What it is about, e.g. the start of the source involved
+    pub context: Option<String>,
+}
+
+impl Notice {
+    pub fn warning(message: impl Into<String>, context: Option<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message: message.into(),
+            context,
+        }
+    }
+}
+
+impl fmt::Display for Notice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.context {
+            Some(context) => write!(f, "{}: {context}", self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// A front-end's end of the notice channel, from [`subscribe`].
+pub struct NoticeReceiver {
+    notices: Receiver<Notice>,
+}
+
+impl NoticeReceiver {
+    /// Notices sent since the last poll, without blocking.
+    pub fn poll(&self) -> Vec<Notice> {
+        self.notices.try_iter().collect()
+    }
+}
+
+struct Channel {
+    subscribers: Vec<Sender<Notice>>,
+    recent: VecDeque<Notice>,
+}
+
+static CHANNEL: Mutex<Channel> = Mutex::new(Channel {
+    subscribers: Vec::new(),
+    recent: VecDeque::new(),
+});
+
+/// Start receiving notices sent from now on.
+pub fn subscribe() -> NoticeReceiver {
+    let (tx, rx) = mpsc::channel();
+    let mut channel = CHANNEL.lock().unwrap_or_else(|e| e.into_inner());
+    channel.subscribers.push(tx);
+    NoticeReceiver { notices: rx }
+}
+
+/// Send a notice to every subscriber, unless it was sent recently.
+pub fn notify(notice: Notice) {
+    let mut channel = CHANNEL.lock().unwrap_or_else(|e| e.into_inner());
+    if channel.recent.contains(&notice) {
+        return;
+    }
+    // Subscribers that went away are dropped
+    channel
+        .subscribers
+        .retain(|subscriber| subscriber.send(notice.clone()).is_ok());
+    if channel.recent.len() == RECENT_LIMIT {
+        channel.recent.pop_front();
+    }
+    channel.recent.push_back(notice);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editing::Document;
+
+    // Tests share the channel, so each sends notices only it would
+    #[test]
+    fn test_subscribers_receive_notices_once() {
+        let first = subscribe();
+        let second = subscribe();
+        let notice = Notice::warning("test notice", Some("receive once".to_string()));
+
+        notify(notice.clone());
+        notify(notice.clone());
+
+        let mine = |receiver: &NoticeReceiver| {
+            receiver
+                .poll()
+                .into_iter()
+                .filter(|n| n.context.as_deref() == Some("receive once"))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(mine(&first), vec![notice.clone()]);
+        assert_eq!(mine(&second), vec![notice]);
+        assert!(mine(&first).is_empty());
+    }
+
+    #[test]
+    fn test_dropped_subscriber_is_forgotten() {
+        drop(subscribe());
+        let live = subscribe();
+        notify(Notice::warning("test notice", Some("dropped".to_string())));
+        assert!(
+            live.poll()
+                .iter()
+                .any(|n| n.to_string() == "test notice: dropped")
+        );
+    }
+
+    #[test]
+    fn test_snapshot_reports_elements_it_cannot_show() {
+        let notices = subscribe();
+        let doc = Document::from_bytes(b"<div class=\"notice-test\">\n</div>\n").unwrap();
+        doc.snapshot();
+        assert!(
+            notices
+                .poll()
+                .iter()
+                .any(|n| n.severity == Severity::Warning
+                    && n.context.as_deref() == Some("<div class=\"notice-test\">"))
+        );
+    }
+}
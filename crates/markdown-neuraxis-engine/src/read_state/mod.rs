@@ -0,0 +1,239 @@
+//! # Read Positions
+//!
+//! Remembers where each note was last read so reopening a long note returns
+//! to the same place. A position records the block in view, the byte offset
+//! where that block started and how far through the note the view was
+//! scrolled. Block ids are stable while a note is unchanged; if the note was
+//! edited elsewhere the offset finds the nearest block instead, and the
+//! scroll fraction is left for the front-end as a last resort.
+//!
+//! Positions are stored in `.neuraxis/read_state.tsv` (see [`crate::sidecar`]),
+//! one note per line: `path<TAB>block id<TAB>block offset<TAB>scroll fraction`.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use relative_path::{RelativePath, RelativePathBuf};
+
+use crate::editing::{AnchorId, Block, BlockContent, Snapshot};
+use crate::io::IoError;
+use crate::sidecar;
+
+/// Location of the read state file, relative to the notes root.
+pub const READ_STATE_PATH: &str = ".neuraxis/read_state.tsv";
+
+/// Where a note was last read.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReadPosition {
+    /// Block in view
+    pub block_id: AnchorId,
+    /// Start of that block, used when the id no longer exists
+    pub block_offset: usize,
+    /// How far through the note the view was scrolled (0.0 to 1.0)
+    pub scroll_fraction: f32,
+}
+
+impl ReadPosition {
+    /// Position at the given block.
+    pub fn at_block(block: &Block, scroll_fraction: f32) -> Self {
+        Self {
+            block_id: block.id,
+            block_offset: block.node_range.start,
+            scroll_fraction: scroll_fraction.clamp(0.0, 1.0),
+        }
+    }
+
+    /// The block to return to in the current version of the note.
+    ///
+    /// Prefers the recorded block id, then the last block starting at or
+    /// before the recorded offset, then the first block. Returns `None` only
+    /// for an empty note.
+    pub fn resolve(&self, snapshot: &Snapshot) -> Option<AnchorId> {
+        let mut blocks = Vec::new();
+        flatten(&snapshot.blocks, &mut blocks);
+
+        if blocks.iter().any(|b| b.id == self.block_id) {
+            return Some(self.block_id);
+        }
+        blocks
+            .iter()
+            .filter(|b| b.node_range.start <= self.block_offset)
+            .max_by_key(|b| b.node_range.start)
+            .or(blocks.first())
+            .map(|b| b.id)
+    }
+
+    fn from_record(record: Vec<String>) -> Option<(RelativePathBuf, Self)> {
+        let [path, block_id, block_offset, scroll_fraction] = record.as_slice() else {
+            return None;
+        };
+        let position = Self {
+            block_id: AnchorId(block_id.parse().ok()?),
+            block_offset: block_offset.parse().ok()?,
+            scroll_fraction: scroll_fraction.parse::<f32>().ok()?.clamp(0.0, 1.0),
+        };
+        Some((RelativePathBuf::from(path), position))
+    }
+}
+
+/// All blocks in document order, containers before their children.
+pub(crate) fn flatten<'a>(blocks: &'a [Block], out: &mut Vec<&'a Block>) {
+    for block in blocks {
+        out.push(block);
+        if let BlockContent::Children(children) = &block.content {
+            flatten(children, out);
+        }
+    }
+}
+
+/// Read positions for every note in the vault.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReadState {
+    positions: BTreeMap<RelativePathBuf, ReadPosition>,
+}
+
+impl ReadState {
+    /// Load read positions from the vault, returning an empty state if none exists.
+    pub fn load(notes_root: &Path) -> Result<Self, IoError> {
+        Ok(sidecar::read(READ_STATE_PATH, notes_root)?
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default())
+    }
+
+    /// Save read positions to the vault.
+    pub fn save(&self, notes_root: &Path) -> Result<(), IoError> {
+        sidecar::write(READ_STATE_PATH, notes_root, &self.to_text())
+    }
+
+    /// Read positions from the file's contents. Lines that aren't a
+    /// position are dropped.
+    pub fn parse(content: &str) -> Self {
+        Self {
+            positions: sidecar::records(content)
+                .filter_map(ReadPosition::from_record)
+                .collect(),
+        }
+    }
+
+    /// Serialize to the file format, sorted by path.
+    pub fn to_text(&self) -> String {
+        self.positions
+            .iter()
+            .map(|(path, position)| {
+                sidecar::record(&[
+                    path.to_string(),
+                    position.block_id.0.to_string(),
+                    position.block_offset.to_string(),
+                    position.scroll_fraction.to_string(),
+                ])
+            })
+            .collect()
+    }
+
+    pub fn get(&self, path: &RelativePath) -> Option<&ReadPosition> {
+        self.positions.get(path)
+    }
+
+    pub fn set(&mut self, path: &RelativePath, position: ReadPosition) {
+        self.positions.insert(path.to_relative_path_buf(), position);
+    }
+
+    /// Forget a note, e.g. after it is deleted.
+    pub fn remove(&mut self, path: &RelativePath) -> Option<ReadPosition> {
+        self.positions.remove(path)
+    }
+
+    /// Keep a note's position when it is renamed.
+    pub fn rename(&mut self, from: &RelativePath, to: &RelativePath) {
+        if let Some(position) = self.positions.remove(from) {
+            self.positions.insert(to.to_relative_path_buf(), position);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editing::{Cmd, Document};
+    use crate::tests::create_test_notes_dir;
+
+    const NOTE: &str = "# Title\n\nFirst paragraph.\n\nSecond paragraph.\n\nThird paragraph.\n";
+
+    fn block_starting_with(doc: &Document, prefix: &str) -> Block {
+        let snapshot = doc.snapshot();
+        let mut blocks = Vec::new();
+        flatten(&snapshot.blocks, &mut blocks);
+        blocks
+            .into_iter()
+            .find(|b| doc.slice(b.node_range.clone()).starts_with(prefix))
+            .cloned()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_resolve_same_block_after_reopening() {
+        let doc = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        let position = ReadPosition::at_block(&block_starting_with(&doc, "Second"), 0.5);
+
+        let reopened = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        let block_id = position.resolve(&reopened.snapshot()).unwrap();
+
+        assert_eq!(block_id, block_starting_with(&reopened, "Second").id);
+    }
+
+    #[test]
+    fn test_resolve_falls_back_to_offset_after_external_edit() {
+        let doc = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        let position = ReadPosition::at_block(&block_starting_with(&doc, "Third"), 0.9);
+
+        // Edited elsewhere: the block ids no longer match
+        let mut edited = Document::from_bytes(NOTE.as_bytes()).unwrap();
+        edited.apply(Cmd::InsertText {
+            at: 9,
+            text: "Intro.\n\n".to_string(),
+        });
+        let reopened = Document::from_bytes(edited.text().as_bytes()).unwrap();
+        let block_id = position.resolve(&reopened.snapshot()).unwrap();
+
+        assert_eq!(block_id, block_starting_with(&reopened, "Second").id);
+    }
+
+    #[test]
+    fn test_resolve_empty_note() {
+        let doc = Document::from_bytes(b"").unwrap();
+        let position = ReadPosition {
+            block_id: AnchorId(1),
+            block_offset: 10,
+            scroll_fraction: 0.0,
+        };
+        assert_eq!(position.resolve(&doc.snapshot()), None);
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let notes_dir = create_test_notes_dir();
+        let mut state = ReadState::default();
+        let position = ReadPosition {
+            block_id: AnchorId(42),
+            block_offset: 120,
+            scroll_fraction: 0.25,
+        };
+        state.set(RelativePath::new("journal/2024_01_01.md"), position);
+        state.set(RelativePath::new("old.md"), position);
+        state.rename(RelativePath::new("old.md"), RelativePath::new("new.md"));
+
+        state.save(notes_dir.path()).unwrap();
+        let loaded = ReadState::load(notes_dir.path()).unwrap();
+
+        assert_eq!(loaded, state);
+        assert_eq!(loaded.get(RelativePath::new("new.md")), Some(&position));
+        assert_eq!(loaded.get(RelativePath::new("old.md")), None);
+    }
+
+    #[test]
+    fn test_parse_skips_malformed_lines() {
+        let state = ReadState::parse("a.md\t1\t2\t0.5\nbroken line\nb.md\tx\t2\t0.5\n");
+        assert!(state.get(RelativePath::new("a.md")).is_some());
+        assert!(state.get(RelativePath::new("b.md")).is_none());
+    }
+}
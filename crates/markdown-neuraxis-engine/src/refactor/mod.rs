@@ -0,0 +1,429 @@
+//! # Folder Rename Refactoring
+//!
+//! Moving a folder on disk breaks links that spell out a path into it
+//! (`[[projects/plan]]`, `[plan](../projects/plan.md)`) and relative links
+//! out of notes inside it. A file watcher would also see the move as a burst
+//! of deletes and creates. [`rename_folder`] does the whole thing as one
+//! operation:
+//!
+//! 1. Plan link rewrites across the vault from the current sources
+//! 2. Tell the [`FolderRenameObserver`] a move is starting (pause watching)
+//! 3. Move the folder
+//! 4. Write the rewritten notes
+//! 5. Move moved and rewritten notes in the given vault indices
+//! 6. Tell the observer the move is done (re-subscribe at the new path)
+//!
+//! Wiki-links by bare note name keep working after a move and are left alone.
+//!
+//! Merging several notes into one and splitting a note at its headings are
+//! in [`notes`]; find and replace across the vault is in [`replace`].
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::Path;
+
+use markdown_neuraxis_syntax::{SyntaxKind, SyntaxNode, parse, visit};
+use relative_path::{RelativePath, RelativePathBuf};
+
+use crate::assets::resolve;
+use crate::io::{self, IoError};
+use crate::links::extract_wikilinks;
+use crate::vault::SharedIndex;
+
+pub mod notes;
+pub mod replace;
+
+/// Progress notifications from [`rename_folder`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenameProgress {
+    /// Rewrites are planned: `notes` notes move, `links` links change
+    Planned { notes: usize, links: usize },
+    /// The folder has been moved on disk
+    Moved,
+    /// `done` of `total` rewritten notes have been written
+    NotesWritten { done: usize, total: usize },
+    /// Vault indices reflect the new paths
+    IndicesUpdated,
+}
+
+/// Hooks around a folder rename, e.g. for a file watcher.
+pub trait FolderRenameObserver {
+    /// Called before anything on disk changes.
+    fn before_move(&mut self, _old: &RelativePath, _new: &RelativePath) {}
+
+    /// Called once the rename has finished or failed part way, whenever
+    /// [`before_move`](Self::before_move) was called.
+    fn after_move(&mut self, _old: &RelativePath, _new: &RelativePath) {}
+
+    fn progress(&mut self, _progress: RenameProgress) {}
+}
+
+/// Observer that ignores everything.
+impl FolderRenameObserver for () {}
+
+/// What a completed folder rename changed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FolderRename {
+    /// Old and new path of every note in the folder
+    pub moved: Vec<(RelativePathBuf, RelativePathBuf)>,
+    /// Number of links rewritten
+    pub links_updated: usize,
+    /// Notes whose content was rewritten, at their new paths
+    pub notes_changed: Vec<RelativePathBuf>,
+}
+
+/// Rename the folder `old` to `new`, rewriting links across the vault.
+///
+/// Fails without changing anything if `old` is not a folder, `new` already
+/// exists or `new` is inside `old`.
+pub fn rename_folder(
+    notes_root: &Path,
+    old: &RelativePath,
+    new: &RelativePath,
+    indices: &[SharedIndex],
+    observer: &mut dyn FolderRenameObserver,
+) -> Result<FolderRename, IoError> {
+    let old = old.normalize();
+    let new = new.normalize();
+    if old.as_str().is_empty() || !old.to_path(notes_root).is_dir() {
+        return Err(IoError::NotFound(old.to_path(notes_root)));
+    }
+    if new.to_path(notes_root).exists() {
+        return Err(IoError::FileExists(new.to_path(notes_root)));
+    }
+    if new.as_str().is_empty() || inside(&new, &old) {
+        return Err(IoError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("cannot move {old} into {new}"),
+        )));
+    }
+
+    let mut result = FolderRename::default();
+    let mut rewritten = BTreeMap::new();
+    let mut sources = BTreeMap::new();
+    for path in io::scan_markdown_relative_paths(notes_root)? {
+        let source = io::read_file(&path, notes_root)?;
+        let edits = link_edits(&source, &path, &old, &new);
+        let new_path = move_path(&path, &old, &new);
+        if let Some(new_path) = &new_path {
+            result.moved.push((path.clone(), new_path.clone()));
+        }
+        let new_path = new_path.unwrap_or(path.clone());
+        if !edits.is_empty() {
+            result.links_updated += edits.len();
+            rewritten.insert(new_path.clone(), apply_edits(&source, edits));
+        } else if new_path != path {
+            sources.insert(new_path, source);
+        }
+    }
+    observer.progress(RenameProgress::Planned {
+        notes: result.moved.len(),
+        links: result.links_updated,
+    });
+
+    observer.before_move(&old, &new);
+    let outcome = move_and_rewrite(notes_root, &old, &new, &rewritten, observer);
+    observer.after_move(&old, &new);
+    outcome?;
+
+    for (old_path, _) in &result.moved {
+        for index in indices {
+            index
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove_note(old_path);
+        }
+    }
+    for (path, source) in rewritten.iter().chain(&sources) {
+        for index in indices {
+            index
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .add_note(path, source);
+        }
+    }
+    observer.progress(RenameProgress::IndicesUpdated);
+
+    result.notes_changed = rewritten.into_keys().collect();
+    Ok(result)
+}
+
+fn move_and_rewrite(
+    notes_root: &Path,
+    old: &RelativePath,
+    new: &RelativePath,
+    rewritten: &BTreeMap<RelativePathBuf, String>,
+    observer: &mut dyn FolderRenameObserver,
+) -> Result<(), IoError> {
+    io::rename_dir(old, new, notes_root)?;
+    observer.progress(RenameProgress::Moved);
+
+    let total = rewritten.len();
+    for (done, (path, source)) in rewritten.iter().enumerate() {
+        io::write_file(path, notes_root, source)?;
+        observer.progress(RenameProgress::NotesWritten {
+            done: done + 1,
+            total,
+        });
+    }
+    Ok(())
+}
+
+/// Whether `path` is `folder` or inside it, ignoring case like link matching.
+fn inside(path: &RelativePath, folder: &RelativePath) -> bool {
+    strip_folder(path, folder).is_some()
+}
+
+/// The part of `path` below `folder`, if it is inside it.
+fn strip_folder<'a>(path: &'a RelativePath, folder: &RelativePath) -> Option<&'a str> {
+    let path = path.as_str();
+    let folder = folder.as_str();
+    let head = path.get(..folder.len())?;
+    if head.to_lowercase() != folder.to_lowercase() {
+        return None;
+    }
+    match &path[folder.len()..] {
+        "" => Some(""),
+        rest => rest.strip_prefix('/'),
+    }
+}
+
+/// Where `path` ends up after the move, if it is inside the moved folder.
+fn move_path(
+    path: &RelativePath,
+    old: &RelativePath,
+    new: &RelativePath,
+) -> Option<RelativePathBuf> {
+    strip_folder(path, old).map(|rest| new.join(rest))
+}
+
+/// Byte edits fixing the links in `source` for moving the folder (or
+/// file) `old` to `new`.
+pub(crate) fn link_edits(
+    source: &str,
+    note: &RelativePath,
+    old: &RelativePath,
+    new: &RelativePath,
+) -> Vec<(Range<usize>, String)> {
+    let mut edits = Vec::new();
+
+    // Path-style wiki-links are relative to the notes root
+    for link in extract_wikilinks(source) {
+        if !link.target.contains('/') {
+            continue;
+        }
+        let inner = link.range.start + 2..link.range.end - 2;
+        let Some(offset) = source[inner.clone()].find(&link.target) else {
+            continue;
+        };
+        let target = RelativePath::new(&link.target);
+        if strip_folder(target, old).is_some() {
+            let start = inner.start + offset;
+            edits.push((start..start + old.as_str().len(), new.to_string()));
+        }
+    }
+
+    // Markdown links and images are relative to the linking note
+    let note_dir = note.parent().unwrap_or(RelativePath::new(""));
+    let new_note_dir = move_path(note_dir, old, new);
+    let tree = parse(source);
+    for node in visit::nodes_of_kinds(&tree, &[SyntaxKind::IMAGE, SyntaxKind::LINK]) {
+        let Some(range) = destination_range(&node) else {
+            continue;
+        };
+        let dest = &source[range.clone()];
+        let target = resolve(note_dir, &dest.replace("%20", " "));
+        let new_target = move_path(&target, old, new);
+        if new_target.is_none() && new_note_dir.is_none() {
+            continue;
+        }
+        let new_target = new_target.unwrap_or(target);
+        let new_dest = if dest.starts_with('/') {
+            format!("/{new_target}")
+        } else {
+            let from = new_note_dir.as_deref().unwrap_or(note_dir);
+            let relative = from.relative(&new_target);
+            if dest.starts_with("./") && !relative.as_str().starts_with("..") {
+                format!("./{relative}")
+            } else {
+                relative.to_string()
+            }
+        };
+        let new_dest = if dest.contains("%20") {
+            new_dest.replace(' ', "%20")
+        } else {
+            new_dest
+        };
+        if new_dest != dest {
+            edits.push((range, new_dest));
+        }
+    }
+
+    edits
+}
+
+/// Byte range in the source of the local path in `[text](dest "title")` /
+/// `![alt](dest)`, without any `#fragment` or `?query`. External URLs
+/// return `None`.
+fn destination_range(node: &SyntaxNode) -> Option<Range<usize>> {
+    let destination = visit::child_of_kind(node, SyntaxKind::LINK_DESTINATION)?;
+    let dest = destination.text().to_string();
+    if dest.starts_with('#') || dest.contains(':') {
+        return None;
+    }
+    let path = dest.split(['#', '?']).next()?;
+    let start = usize::from(destination.text_range().start());
+    (!path.is_empty()).then(|| start..start + path.len())
+}
+
+/// Apply non-overlapping edits, back to front so ranges stay valid.
+pub(crate) fn apply_edits(source: &str, mut edits: Vec<(Range<usize>, String)>) -> String {
+    let mut text = source.to_string();
+    edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    for (range, replacement) in edits {
+        text.replace_range(range, &replacement);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::links::LinkIndex;
+    use crate::tests::create_test_notes_dir;
+    use std::sync::{Arc, RwLock};
+
+    fn write(dir: &tempfile::TempDir, path: &str, content: &str) {
+        io::write_file(RelativePath::new(path), dir.path(), content).unwrap();
+    }
+
+    fn read(dir: &tempfile::TempDir, path: &str) -> String {
+        io::read_file(RelativePath::new(path), dir.path()).unwrap()
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        calls: Vec<String>,
+    }
+
+    impl FolderRenameObserver for Recorder {
+        fn before_move(&mut self, old: &RelativePath, new: &RelativePath) {
+            self.calls.push(format!("before {old} -> {new}"));
+        }
+
+        fn after_move(&mut self, old: &RelativePath, new: &RelativePath) {
+            self.calls.push(format!("after {old} -> {new}"));
+        }
+
+        fn progress(&mut self, progress: RenameProgress) {
+            self.calls.push(format!("{progress:?}"));
+        }
+    }
+
+    #[test]
+    fn test_rename_folder_rewrites_links() {
+        let dir = create_test_notes_dir();
+        write(
+            &dir,
+            "projects/plan.md",
+            "[Home](../index.md) and [[notes]]\n",
+        );
+        write(&dir, "projects/sub/detail.md", "[plan](../plan.md)\n");
+        write(
+            &dir,
+            "index.md",
+            "[[projects/plan]] [[Projects/sub/detail#Top|d]] [[plan]]\n\n\
+             [p](projects/plan.md#x) ![i](</projects/sub/My%20Pic.png>) [w](https://a.b/projects/x)\n",
+        );
+
+        let result = rename_folder(
+            dir.path(),
+            RelativePath::new("projects"),
+            RelativePath::new("archive/2024"),
+            &[],
+            &mut (),
+        )
+        .unwrap();
+
+        assert_eq!(result.moved.len(), 2);
+        assert!(!dir.path().join("projects").exists());
+        assert_eq!(
+            read(&dir, "index.md"),
+            "[[archive/2024/plan]] [[archive/2024/sub/detail#Top|d]] [[plan]]\n\n\
+             [p](archive/2024/plan.md#x) ![i](</archive/2024/sub/My%20Pic.png>) [w](https://a.b/projects/x)\n"
+        );
+        assert_eq!(
+            read(&dir, "archive/2024/plan.md"),
+            "[Home](../../index.md) and [[notes]]\n"
+        );
+        // Links within the moved folder are unchanged
+        assert_eq!(
+            read(&dir, "archive/2024/sub/detail.md"),
+            "[plan](../plan.md)\n"
+        );
+        assert_eq!(result.links_updated, 5);
+    }
+
+    #[test]
+    fn test_rename_folder_updates_indices_and_observer() {
+        let dir = create_test_notes_dir();
+        write(&dir, "a/one.md", "[[b/two]]\n");
+        write(&dir, "b/two.md", "[[a/one]]\n");
+        let links = Arc::new(RwLock::new(LinkIndex::default()));
+        links
+            .write()
+            .unwrap()
+            .update_note(RelativePath::new("a/one.md"), "[[b/two]]\n");
+
+        let shared: SharedIndex = links.clone();
+        let mut recorder = Recorder::default();
+        rename_folder(
+            dir.path(),
+            RelativePath::new("a"),
+            RelativePath::new("c"),
+            &[shared],
+            &mut recorder,
+        )
+        .unwrap();
+
+        let links = links.read().unwrap();
+        assert!(links.links_from(RelativePath::new("a/one.md")).is_empty());
+        assert_eq!(links.links_from(RelativePath::new("c/one.md")).len(), 1);
+        assert_eq!(
+            links.links_from(RelativePath::new("b/two.md"))[0].target,
+            "c/one"
+        );
+        assert_eq!(
+            recorder.calls,
+            [
+                "Planned { notes: 1, links: 1 }",
+                "before a -> c",
+                "Moved",
+                "NotesWritten { done: 1, total: 1 }",
+                "after a -> c",
+                "IndicesUpdated",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rename_folder_rejects_bad_targets() {
+        let dir = create_test_notes_dir();
+        write(&dir, "a/one.md", "x\n");
+        write(&dir, "b/two.md", "y\n");
+        let rename = |old: &str, new: &str| {
+            rename_folder(
+                dir.path(),
+                RelativePath::new(old),
+                RelativePath::new(new),
+                &[],
+                &mut (),
+            )
+        };
+
+        assert!(matches!(rename("missing", "c"), Err(IoError::NotFound(_))));
+        assert!(matches!(rename("a", "b"), Err(IoError::FileExists(_))));
+        assert!(rename("a", "a/inner").is_err());
+        assert_eq!(read(&dir, "a/one.md"), "x\n");
+    }
+}
@@ -0,0 +1,483 @@
+//! # Merging and Splitting Notes
+//!
+//! [`plan_merge_notes`] folds several notes into one, each under a `##`
+//! heading named after it, and points wiki-links at the merged note's
+//! sections. [`plan_split_note`] does the reverse: every top-level heading
+//! up to a level becomes its own note, the original keeps its opening text
+//! and a list of links to the parts, and links to headings follow them.
+//!
+//! Both only plan. The [`NotePatchSet`] they return lists every note
+//! created, rewritten or trashed, for a frontend to preview, and
+//! [`apply_patch_set`] carries it out. Replaced notes go to the vault
+//! trash (see [`io::move_to_trash`]) so nothing is lost. Markdown links
+//! (`[text](note.md)`) are left as they are.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use markdown_neuraxis_syntax::{SyntaxKind, parse};
+use relative_path::{RelativePath, RelativePathBuf};
+
+use super::apply_edits;
+use crate::editing::Document;
+use crate::editing::snapshot::{Block, BlockContent, BlockKind};
+use crate::io::{self, IoError};
+use crate::links::rename::heading_text;
+use crate::links::{WikiLinkRef, extract_wikilinks, target_matches_note};
+
+/// One note a refactor touches.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NoteChange {
+    /// A new note
+    Create {
+        path: RelativePathBuf,
+        content: String,
+    },
+    /// New content for an existing note, planned from `original`
+    Update {
+        path: RelativePathBuf,
+        original: String,
+        content: String,
+    },
+    /// A note moved to the vault trash
+    Trash { path: RelativePathBuf },
+}
+
+impl NoteChange {
+    pub fn path(&self) -> &RelativePath {
+        match self {
+            Self::Create { path, .. } | Self::Update { path, .. } | Self::Trash { path } => path,
+        }
+    }
+}
+
+/// Every change a merge or split makes, for previewing before
+/// [`apply_patch_set`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NotePatchSet {
+    pub changes: Vec<NoteChange>,
+}
+
+/// Plan merging `sources` into `target`, in order.
+///
+/// An existing `target` keeps its content and the sources are added after
+/// it; otherwise it's created. Links to a source become links to its
+/// section of `target` (`[[plan]]` to `[[target#plan]]`, `[[plan#Goals]]`
+/// to `[[target#Goals]]`) and the sources are trashed.
+pub fn plan_merge_notes(
+    notes_root: &Path,
+    sources: &[RelativePathBuf],
+    target: &RelativePath,
+) -> Result<NotePatchSet, IoError> {
+    let target = target.normalize();
+    let mut merged: Vec<RelativePathBuf> = Vec::new();
+    for source in sources.iter().map(|source| source.normalize()) {
+        if source != target && !merged.contains(&source) {
+            merged.push(source);
+        }
+    }
+    if merged.is_empty() {
+        return Err(invalid(format!("no notes to merge into {target}")));
+    }
+
+    let target_name = note_name(&target);
+    let retarget = |link: &WikiLinkRef| {
+        let source = merged
+            .iter()
+            .find(|source| !link.target.is_empty() && target_matches_note(&link.target, source))?;
+        let heading = link.heading.clone().unwrap_or_else(|| note_name(source));
+        Some(format!("{target_name}#{}", heading.trim()))
+    };
+
+    let original = match io::read_file(&target, notes_root) {
+        Ok(original) => Some(original),
+        Err(IoError::NotFound(_)) => None,
+        Err(e) => return Err(e),
+    };
+    let mut content = original
+        .as_deref()
+        .map(|text| rewrite_links(text, retarget))
+        .unwrap_or_default();
+    for source in &merged {
+        let text = io::read_file(source, notes_root)?;
+        let body = rewrite_links(without_frontmatter(&text), retarget);
+        if !content.is_empty() {
+            content = format!("{}\n\n", content.trim_end());
+        }
+        content.push_str(&format!("## {}\n", note_name(source)));
+        if !body.trim().is_empty() {
+            content.push_str(&format!("\n{}\n", body.trim()));
+        }
+    }
+
+    let mut changes = vec![match original {
+        Some(original) => NoteChange::Update {
+            path: target.clone(),
+            original,
+            content,
+        },
+        None => NoteChange::Create {
+            path: target.clone(),
+            content,
+        },
+    }];
+    for path in io::scan_markdown_relative_paths(notes_root)? {
+        if path == target || merged.contains(&path) {
+            continue;
+        }
+        let original = io::read_file(&path, notes_root)?;
+        let content = rewrite_links(&original, retarget);
+        if content != original {
+            changes.push(NoteChange::Update {
+                path,
+                original,
+                content,
+            });
+        }
+    }
+    changes.extend(merged.into_iter().map(|path| NoteChange::Trash { path }));
+    Ok(NotePatchSet { changes })
+}
+
+/// Plan splitting the note at `path` into one note per top-level heading
+/// of `level` or above (1 splits at `#` headings, 2 at `#` and `##`).
+///
+/// Each part is named after its heading, in the same folder, and ends
+/// with a link back. The original keeps the text before the first heading
+/// followed by links to the parts. Links to headings that moved, from
+/// anywhere in the vault, point at the part they're now in.
+pub fn plan_split_note(
+    notes_root: &Path,
+    path: &RelativePath,
+    level: u8,
+) -> Result<NotePatchSet, IoError> {
+    let path = path.normalize();
+    let source = io::read_file(&path, notes_root)?;
+    let doc = Document::from_bytes(source.as_bytes())
+        .map_err(|e| IoError::Io(std::io::Error::other(e.to_string())))?;
+    let blocks = doc.snapshot().blocks;
+
+    // Where each part starts and its title
+    let mut parts: Vec<(usize, String)> = Vec::new();
+    for block in &blocks {
+        if let BlockKind::Heading { level: found } = block.kind
+            && found <= level
+        {
+            parts.push((block.node_range.start, heading_text(&doc, block)));
+        }
+    }
+    if parts.is_empty() {
+        return Err(invalid(format!("{path} has no headings to split at")));
+    }
+
+    let folder = path.parent().unwrap_or(RelativePath::new(""));
+    let mut taken = BTreeSet::new();
+    let names: Vec<String> = parts
+        .iter()
+        .map(|(_, title)| {
+            let base = note_file_name(title);
+            let mut name = base.clone();
+            let mut n = 2;
+            while taken.contains(&name.to_lowercase())
+                || name.eq_ignore_ascii_case(&note_name(&path))
+                || folder
+                    .join(format!("{name}.md"))
+                    .to_path(notes_root)
+                    .exists()
+            {
+                name = format!("{base} {n}");
+                n += 1;
+            }
+            taken.insert(name.to_lowercase());
+            name
+        })
+        .collect();
+
+    // Which part every heading ends up in, and whether it's the part's title
+    let mut headings = HashMap::new();
+    let mut all = Vec::new();
+    heading_starts(&doc, &blocks, &mut all);
+    for (start, text) in all {
+        if let Some(part) = parts
+            .iter()
+            .rposition(|(part_start, _)| *part_start <= start)
+        {
+            let is_title = parts[part].0 == start;
+            headings
+                .entry(text.to_lowercase())
+                .or_insert((part, is_title));
+        }
+    }
+    let retarget = |link: &WikiLinkRef, in_original: bool, part: Option<usize>| {
+        let points_here = if link.target.is_empty() {
+            in_original
+        } else {
+            target_matches_note(&link.target, &path)
+        };
+        let heading = link.heading.as_deref()?.trim();
+        let &(found, is_title) = headings
+            .get(&heading.to_lowercase())
+            .filter(|_| points_here)?;
+        if part == Some(found) && link.target.is_empty() {
+            return None;
+        }
+        Some(if is_title {
+            names[found].clone()
+        } else {
+            format!("{}#{heading}", names[found])
+        })
+    };
+
+    let original_name = note_name(&path);
+    let mut changes = Vec::new();
+    for (i, name) in names.iter().enumerate() {
+        let start = parts[i].0;
+        let end = parts.get(i + 1).map_or(source.len(), |(next, _)| *next);
+        let text = rewrite_links(&source[start..end], |link| retarget(link, true, Some(i)));
+        changes.push(NoteChange::Create {
+            path: folder.join(format!("{name}.md")),
+            content: format!("{}\n\nSplit from [[{original_name}]]\n", text.trim_end()),
+        });
+    }
+
+    let preamble = rewrite_links(&source[..parts[0].0], |link| retarget(link, true, None));
+    let mut index = preamble.trim_end().to_string();
+    if !index.is_empty() {
+        index.push_str("\n\n");
+    }
+    for name in &names {
+        index.push_str(&format!("- [[{name}]]\n"));
+    }
+    changes.push(NoteChange::Update {
+        path: path.clone(),
+        original: source.clone(),
+        content: index,
+    });
+
+    for other in io::scan_markdown_relative_paths(notes_root)? {
+        if other == path {
+            continue;
+        }
+        let original = io::read_file(&other, notes_root)?;
+        let content = rewrite_links(&original, |link| retarget(link, false, None));
+        if content != original {
+            changes.push(NoteChange::Update {
+                path: other,
+                original,
+                content,
+            });
+        }
+    }
+    Ok(NotePatchSet { changes })
+}
+
+/// Carry out a merge or split.
+///
+/// Everything is checked before anything is written, so if a note changed
+/// since planning (or a note to create now exists) nothing happens and an
+/// error is returned.
+pub fn apply_patch_set(notes_root: &Path, patch_set: &NotePatchSet) -> Result<(), IoError> {
+    for change in &patch_set.changes {
+        let absolute = change.path().to_path(notes_root);
+        match change {
+            NoteChange::Create { .. } if absolute.exists() => {
+                return Err(IoError::FileExists(absolute));
+            }
+            NoteChange::Update { path, original, .. }
+                if io::read_file(path, notes_root)? != *original =>
+            {
+                return Err(invalid(format!(
+                    "{path} changed since the refactor was planned"
+                )));
+            }
+            NoteChange::Trash { .. } if !absolute.exists() => {
+                return Err(IoError::NotFound(absolute));
+            }
+            _ => {}
+        }
+    }
+
+    for change in &patch_set.changes {
+        match change {
+            NoteChange::Create { path, content } => {
+                io::create_file(path, notes_root, content)?;
+            }
+            NoteChange::Update { path, content, .. } => io::write_file(path, notes_root, content)?,
+            NoteChange::Trash { path } => {
+                io::move_to_trash(path, notes_root)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Replace the `target#heading` part of wiki-links for which `retarget`
+/// gives a new one, keeping any `|alias`.
+fn rewrite_links(source: &str, retarget: impl Fn(&WikiLinkRef) -> Option<String>) -> String {
+    let edits = extract_wikilinks(source)
+        .into_iter()
+        .filter_map(|link| {
+            let new_target = retarget(&link)?;
+            let inner = link.range.start + 2..link.range.end - 2;
+            let end = source[inner.clone()]
+                .find('|')
+                .map_or(inner.end, |pipe| inner.start + pipe);
+            Some((inner.start..end, new_target))
+        })
+        .collect();
+    apply_edits(source, edits)
+}
+
+fn without_frontmatter(source: &str) -> &str {
+    let body_start = parse(source)
+        .children()
+        .find(|node| node.kind() == SyntaxKind::FRONTMATTER)
+        .map_or(0, |node| node.text_range().end().into());
+    &source[body_start..]
+}
+
+/// The name wiki-links use for a note: its file name without `.md`.
+fn note_name(path: &RelativePath) -> String {
+    path.file_stem().unwrap_or_default().to_string()
+}
+
+/// A file name (without `.md`) for a note titled `title`, leaving out
+/// characters that aren't allowed in file names or would break links.
+fn note_file_name(title: &str) -> String {
+    let name: String = title
+        .chars()
+        .filter(|c| {
+            !matches!(
+                c,
+                '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' | '#' | '[' | ']' | '^'
+            )
+        })
+        .collect();
+    let name = name.trim().trim_matches('.').trim();
+    if name.is_empty() {
+        "Untitled".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Start offset and text of every heading, nested ones included.
+fn heading_starts(doc: &Document, blocks: &[Block], out: &mut Vec<(usize, String)>) {
+    for block in blocks {
+        if let BlockKind::Heading { .. } = block.kind {
+            out.push((block.node_range.start, heading_text(doc, block)));
+        }
+        if let BlockContent::Children(children) = &block.content {
+            heading_starts(doc, children, out);
+        }
+    }
+}
+
+fn invalid(message: String) -> IoError {
+    IoError::Io(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        message,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::create_test_notes_dir;
+
+    fn write(dir: &tempfile::TempDir, path: &str, content: &str) {
+        io::write_file(RelativePath::new(path), dir.path(), content).unwrap();
+    }
+
+    fn read(dir: &tempfile::TempDir, path: &str) -> String {
+        io::read_file(RelativePath::new(path), dir.path()).unwrap()
+    }
+
+    #[test]
+    fn test_merge_notes_rewrites_links_and_trashes_sources() {
+        let dir = create_test_notes_dir();
+        write(&dir, "ideas.md", "# Ideas\n\nSome thoughts\n");
+        write(
+            &dir,
+            "plan.md",
+            "---\ntitle: Plan\n---\n## Goals\n\nShip it, see [[ideas]]\n",
+        );
+        write(
+            &dir,
+            "index.md",
+            "[[Plan#Goals|goals]] and [[ideas]] and [[other]]\n",
+        );
+
+        let patches = plan_merge_notes(
+            dir.path(),
+            &["plan.md".into(), "ideas.md".into()],
+            RelativePath::new("project.md"),
+        )
+        .unwrap();
+        let paths: Vec<_> = patches.changes.iter().map(|c| c.path().as_str()).collect();
+        assert_eq!(paths, ["project.md", "index.md", "plan.md", "ideas.md"]);
+
+        apply_patch_set(dir.path(), &patches).unwrap();
+        assert_eq!(
+            read(&dir, "project.md"),
+            "## plan\n\n## Goals\n\nShip it, see [[project#ideas]]\n\n\
+             ## ideas\n\n# Ideas\n\nSome thoughts\n"
+        );
+        assert_eq!(
+            read(&dir, "index.md"),
+            "[[project#Goals|goals]] and [[project#ideas]] and [[other]]\n"
+        );
+        assert!(!dir.path().join("plan.md").exists());
+        assert!(dir.path().join(io::TRASH_DIR).join("plan.md").exists());
+    }
+
+    #[test]
+    fn test_split_note_by_heading() {
+        let dir = create_test_notes_dir();
+        write(
+            &dir,
+            "notes/book.md",
+            "Intro, see [[#Part Two]]\n\n# Part One\n\nText\n\n## Detail\n\nMore, see [[#Sub]]\n\n\
+             # Part Two\n\n### Sub\n\nEnd\n",
+        );
+        write(
+            &dir,
+            "other.md",
+            "[[book#Detail]] [[book#part one|one]] [[book]]\n",
+        );
+
+        let patches = plan_split_note(dir.path(), RelativePath::new("notes/book.md"), 1).unwrap();
+        apply_patch_set(dir.path(), &patches).unwrap();
+
+        assert_eq!(
+            read(&dir, "notes/book.md"),
+            "Intro, see [[Part Two]]\n\n- [[Part One]]\n- [[Part Two]]\n"
+        );
+        assert_eq!(
+            read(&dir, "notes/Part One.md"),
+            "# Part One\n\nText\n\n## Detail\n\nMore, see [[Part Two#Sub]]\n\n\
+             Split from [[book]]\n"
+        );
+        assert_eq!(
+            read(&dir, "notes/Part Two.md"),
+            "# Part Two\n\n### Sub\n\nEnd\n\nSplit from [[book]]\n"
+        );
+        assert_eq!(
+            read(&dir, "other.md"),
+            "[[Part One#Detail]] [[Part One|one]] [[book]]\n"
+        );
+    }
+
+    #[test]
+    fn test_stale_patch_set_changes_nothing() {
+        let dir = create_test_notes_dir();
+        write(&dir, "a.md", "# A\n\nText\n\n# B\n\nMore\n");
+        let patches = plan_split_note(dir.path(), RelativePath::new("a.md"), 2).unwrap();
+        write(&dir, "a.md", "edited meanwhile\n");
+
+        assert!(apply_patch_set(dir.path(), &patches).is_err());
+        assert!(!dir.path().join("A 2.md").exists());
+        assert!(!dir.path().join("B.md").exists());
+        assert!(plan_split_note(dir.path(), RelativePath::new("a.md"), 2).is_err());
+    }
+}
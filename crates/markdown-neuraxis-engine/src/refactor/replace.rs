@@ -0,0 +1,356 @@
+//! # Find and Replace Across the Vault
+//!
+//! [`plan_replace_all`] finds every match of a pattern, literal or regex,
+//! in the notes (optionally only those in one folder) and returns a
+//! [`ReplacePatch`] per note to preview. [`apply_replace_all`] writes the
+//! (possibly user-filtered) patches, and [`replace_all`] does both, or only
+//! plans when [`ReplaceOptions::dry_run`] is set.
+//!
+//! Applying records each rewritten note's text before and after in
+//! [`REPLACE_JOURNAL_PATH`], so [`undo_replace_all`] can put the last batch
+//! back as long as none of its notes have changed since.
+//!
+//! Hidden folders such as `.neuraxis` are never searched.
+
+use std::path::Path;
+
+use regex::{Regex, RegexBuilder};
+use relative_path::{RelativePath, RelativePathBuf};
+use serde::{Deserialize, Serialize};
+
+use super::notes::{NoteChange, NotePatchSet, apply_patch_set};
+use crate::io::{self, IoError};
+
+/// Where the last applied replacement is recorded for
+/// [`undo_replace_all`], relative to the notes root.
+pub const REPLACE_JOURNAL_PATH: &str = ".neuraxis/replace-journal.json";
+
+/// How a pattern is matched.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MatchMode {
+    /// The exact text; the replacement is inserted as written
+    #[default]
+    Literal,
+    /// A regular expression; `$1` or `${name}` in the replacement insert
+    /// captured groups
+    Regex,
+}
+
+/// Options for [`plan_replace_all`] and [`replace_all`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplaceOptions {
+    pub mode: MatchMode,
+    pub ignore_case: bool,
+    /// Only notes in this folder (and its subfolders)
+    pub folder: Option<RelativePathBuf>,
+    /// [`replace_all`] only plans, writing nothing
+    pub dry_run: bool,
+}
+
+/// One match and what replaces it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplaceEdit {
+    /// Byte range of the match in the note's source
+    pub range: std::ops::Range<usize>,
+    /// 1-based line number, for preview display
+    pub line: usize,
+    /// Full source line the match starts on, for preview display
+    pub context: String,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+/// The replacements in one note.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplacePatch {
+    pub path: RelativePathBuf,
+    /// Edits in source order
+    pub edits: Vec<ReplaceEdit>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Journal {
+    files: Vec<JournalEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct JournalEntry {
+    path: String,
+    before: String,
+    after: String,
+}
+
+/// Find every match of `pattern` in the vault and plan replacing it with
+/// `replacement`, one patch per note that has a match, ordered by path.
+///
+/// Fails if the pattern is empty or not a valid regex.
+pub fn plan_replace_all(
+    notes_root: &Path,
+    pattern: &str,
+    replacement: &str,
+    options: &ReplaceOptions,
+) -> Result<Vec<ReplacePatch>, IoError> {
+    let regex = compile(pattern, options)?;
+    let mut patches = Vec::new();
+    for path in io::scan_markdown_relative_paths(notes_root)? {
+        if path.components().any(|c| c.as_str().starts_with('.'))
+            || options
+                .folder
+                .as_ref()
+                .is_some_and(|folder| !path.starts_with(folder))
+        {
+            continue;
+        }
+        let source = io::read_file(&path, notes_root)?;
+        let edits = replace_edits(&source, &regex, replacement, options.mode);
+        if !edits.is_empty() {
+            patches.push(ReplacePatch { path, edits });
+        }
+    }
+    Ok(patches)
+}
+
+/// Replacements of `regex` in one note's source.
+fn replace_edits(
+    source: &str,
+    regex: &Regex,
+    replacement: &str,
+    mode: MatchMode,
+) -> Vec<ReplaceEdit> {
+    regex
+        .captures_iter(source)
+        .filter_map(|captures| {
+            let found = captures.get(0)?;
+            let range = found.range();
+            let new_text = match mode {
+                MatchMode::Literal => replacement.to_string(),
+                MatchMode::Regex => {
+                    let mut text = String::new();
+                    captures.expand(replacement, &mut text);
+                    text
+                }
+            };
+            if range.is_empty() && new_text.is_empty() {
+                return None;
+            }
+            let line_start = source[..range.start].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = source[range.start..]
+                .find('\n')
+                .map_or(source.len(), |i| range.start + i);
+            Some(ReplaceEdit {
+                line: source[..range.start].matches('\n').count() + 1,
+                context: source[line_start..line_end]
+                    .trim_end_matches('\r')
+                    .to_string(),
+                old_text: found.as_str().to_string(),
+                new_text,
+                range,
+            })
+        })
+        .collect()
+}
+
+fn compile(pattern: &str, options: &ReplaceOptions) -> Result<Regex, IoError> {
+    if pattern.is_empty() {
+        return Err(invalid("nothing to find".to_string()));
+    }
+    let source = match options.mode {
+        MatchMode::Literal => regex::escape(pattern),
+        MatchMode::Regex => pattern.to_string(),
+    };
+    RegexBuilder::new(&source)
+        .case_insensitive(options.ignore_case)
+        .build()
+        .map_err(|e| invalid(format!("invalid pattern: {e}")))
+}
+
+/// Write replacement patches to disk and record them for
+/// [`undo_replace_all`], returning the number of notes changed.
+///
+/// Edits whose range no longer holds the expected old text (the note
+/// changed since planning) are skipped.
+pub fn apply_replace_all(notes_root: &Path, patches: &[ReplacePatch]) -> Result<usize, IoError> {
+    let mut journal = Journal::default();
+    for patch in patches {
+        let before = io::read_file(&patch.path, notes_root)?;
+        let mut after = before.clone();
+        for edit in patch.edits.iter().rev() {
+            if after.get(edit.range.clone()) == Some(edit.old_text.as_str()) {
+                after.replace_range(edit.range.clone(), &edit.new_text);
+            }
+        }
+        if after != before {
+            io::write_file(&patch.path, notes_root, &after)?;
+            journal.files.push(JournalEntry {
+                path: patch.path.to_string(),
+                before,
+                after,
+            });
+        }
+    }
+
+    if !journal.files.is_empty() {
+        let text = serde_json::to_string_pretty(&journal).map_err(std::io::Error::other)?;
+        io::write_file(RelativePath::new(REPLACE_JOURNAL_PATH), notes_root, &text)?;
+    }
+    Ok(journal.files.len())
+}
+
+/// Plan replacing `pattern` with `replacement` across the vault and, unless
+/// [`ReplaceOptions::dry_run`] is set, apply it. Returns the patches
+/// planned.
+pub fn replace_all(
+    notes_root: &Path,
+    pattern: &str,
+    replacement: &str,
+    options: &ReplaceOptions,
+) -> Result<Vec<ReplacePatch>, IoError> {
+    let patches = plan_replace_all(notes_root, pattern, replacement, options)?;
+    if !options.dry_run {
+        apply_replace_all(notes_root, &patches)?;
+    }
+    Ok(patches)
+}
+
+/// Put back the notes changed by the last [`apply_replace_all`], returning
+/// how many, or `None` if there is nothing to undo.
+///
+/// Nothing is written if any of them changed since; that fails with an
+/// error and the journal is kept.
+pub fn undo_replace_all(notes_root: &Path) -> Result<Option<usize>, IoError> {
+    let journal_path = RelativePath::new(REPLACE_JOURNAL_PATH);
+    let text = match io::read_file(journal_path, notes_root) {
+        Ok(text) => text,
+        Err(IoError::NotFound(_)) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let journal: Journal = serde_json::from_str(&text)
+        .map_err(|e| IoError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))?;
+
+    let changes = journal
+        .files
+        .into_iter()
+        .map(|entry| NoteChange::Update {
+            path: io::relative_path_from_str(&entry.path),
+            original: entry.after,
+            content: entry.before,
+        })
+        .collect();
+    let patch_set = NotePatchSet { changes };
+    apply_patch_set(notes_root, &patch_set)?;
+    std::fs::remove_file(journal_path.to_path(notes_root))?;
+    Ok(Some(patch_set.changes.len()))
+}
+
+fn invalid(message: String) -> IoError {
+    IoError::Io(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        message,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::create_test_notes_dir;
+
+    fn write(dir: &tempfile::TempDir, path: &str, content: &str) {
+        io::write_file(RelativePath::new(path), dir.path(), content).unwrap();
+    }
+
+    fn read(dir: &tempfile::TempDir, path: &str) -> String {
+        io::read_file(RelativePath::new(path), dir.path()).unwrap()
+    }
+
+    #[test]
+    fn test_plan_literal_and_regex() {
+        let notes_dir = create_test_notes_dir();
+        write(&notes_dir, "a.md", "Call Bob.\nbob (Bob) again\n");
+        write(&notes_dir, "work/b.md", "Meet bob on 2024-03-05\n");
+        write(&notes_dir, ".neuraxis/trash/c.md", "bob\n");
+
+        let literal = ReplaceOptions::default();
+        let patches = plan_replace_all(notes_dir.path(), "Bob", "Rob", &literal).unwrap();
+        assert_eq!(patches.len(), 1);
+        assert_eq!(
+            patches[0].edits[1],
+            ReplaceEdit {
+                range: 15..18,
+                line: 2,
+                context: "bob (Bob) again".into(),
+                old_text: "Bob".into(),
+                new_text: "Rob".into(),
+            }
+        );
+
+        let scoped = ReplaceOptions {
+            ignore_case: true,
+            folder: Some("work".into()),
+            ..literal
+        };
+        let patches = plan_replace_all(notes_dir.path(), "BOB", "Rob", &scoped).unwrap();
+        let paths: Vec<&str> = patches.iter().map(|p| p.path.as_str()).collect();
+        assert_eq!(paths, ["work/b.md"]);
+
+        let regex = ReplaceOptions {
+            mode: MatchMode::Regex,
+            ..ReplaceOptions::default()
+        };
+        let patches = plan_replace_all(
+            notes_dir.path(),
+            r"(\d{4})-(\d\d)-(\d\d)",
+            "$3/$2/$1",
+            &regex,
+        )
+        .unwrap();
+        assert_eq!(patches[0].edits[0].new_text, "05/03/2024");
+
+        assert!(plan_replace_all(notes_dir.path(), "(", "", &regex).is_err());
+        assert!(plan_replace_all(notes_dir.path(), "", "x", &literal).is_err());
+    }
+
+    #[test]
+    fn test_replace_all_and_undo() {
+        let notes_dir = create_test_notes_dir();
+        write(&notes_dir, "a.md", "old text, old\n");
+        write(&notes_dir, "b.md", "also old\n");
+        write(&notes_dir, "c.md", "untouched\n");
+
+        let dry_run = ReplaceOptions {
+            dry_run: true,
+            ..ReplaceOptions::default()
+        };
+        let planned = replace_all(notes_dir.path(), "old", "new", &dry_run).unwrap();
+        assert_eq!(planned.len(), 2);
+        assert_eq!(read(&notes_dir, "a.md"), "old text, old\n");
+        assert_eq!(undo_replace_all(notes_dir.path()).unwrap(), None);
+
+        replace_all(notes_dir.path(), "old", "new", &ReplaceOptions::default()).unwrap();
+        assert_eq!(read(&notes_dir, "a.md"), "new text, new\n");
+        assert_eq!(read(&notes_dir, "b.md"), "also new\n");
+
+        assert_eq!(undo_replace_all(notes_dir.path()).unwrap(), Some(2));
+        assert_eq!(read(&notes_dir, "a.md"), "old text, old\n");
+        assert_eq!(read(&notes_dir, "b.md"), "also old\n");
+        assert_eq!(undo_replace_all(notes_dir.path()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_undo_refused_after_later_edits() {
+        let notes_dir = create_test_notes_dir();
+        write(&notes_dir, "a.md", "old\n");
+        write(&notes_dir, "b.md", "old\n");
+        let patches =
+            plan_replace_all(notes_dir.path(), "old", "new", &ReplaceOptions::default()).unwrap();
+        assert_eq!(apply_replace_all(notes_dir.path(), &patches).unwrap(), 2);
+
+        write(&notes_dir, "b.md", "new, edited\n");
+        assert!(undo_replace_all(notes_dir.path()).is_err());
+        assert_eq!(read(&notes_dir, "a.md"), "new\n");
+        assert!(
+            RelativePath::new(REPLACE_JOURNAL_PATH)
+                .to_path(notes_dir.path())
+                .exists()
+        );
+    }
+}
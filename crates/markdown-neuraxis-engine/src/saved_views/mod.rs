@@ -0,0 +1,298 @@
+//! # Saved Views
+//!
+//! Named queries over the vault's indexes, such as "all open tasks tagged
+//! #work", that front-ends list in their sidebar. A query is a list of
+//! terms separated by spaces:
+//!
+//! - a word must appear in the note (matched like [`SearchIndex::search`])
+//! - `#tag` must be used in the note (see [`crate::tags`])
+//! - `is:task` lists open tasks (see [`open_tasks`]) instead of notes; each
+//!   task must itself contain every word and tag
+//!
+//! Views are stored per vault in `.neuraxis/saved_views.tsv` (see
+//! [`crate::sidecar`]), one per line: `name<TAB>query`.
+
+use std::path::Path;
+
+use relative_path::{RelativePath, RelativePathBuf};
+
+use crate::dashboard::{Task, open_tasks};
+use crate::io::IoError;
+use crate::search::{SearchIndex, words};
+use crate::sidecar;
+use crate::tags::{TagIndex, tags_in};
+use crate::vault::Vault;
+
+/// Location of the saved views file, relative to the notes root.
+pub const SAVED_VIEWS_PATH: &str = ".neuraxis/saved_views.tsv";
+
+/// Query term listing open tasks instead of notes.
+const TASKS_TERM: &str = "is:task";
+
+/// A parsed view query.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ViewQuery {
+    /// Lowercased words
+    pub words: Vec<String>,
+    /// Lowercased tag names without the `#`
+    pub tags: Vec<String>,
+    /// Whether the view lists open tasks rather than notes
+    pub tasks: bool,
+}
+
+impl ViewQuery {
+    pub fn parse(query: &str) -> Self {
+        let mut parsed = Self::default();
+        for term in query.split_whitespace() {
+            if term.eq_ignore_ascii_case(TASKS_TERM) {
+                parsed.tasks = true;
+            } else if let Some(tag) = term.strip_prefix('#').filter(|tag| !tag.is_empty()) {
+                parsed.tags.push(tag.to_lowercase());
+            } else {
+                parsed.words.extend(words(term).map(|(_, word)| word));
+            }
+        }
+        parsed
+    }
+
+    /// What the query finds in the vault's indexes.
+    pub fn evaluate(&self, search: &SearchIndex, tags: &TagIndex) -> Vec<ViewItem> {
+        let notes = self.matching_notes(search, tags);
+        if !self.tasks {
+            return notes.into_iter().map(ViewItem::Note).collect();
+        }
+        notes
+            .iter()
+            .filter_map(|path| Some(open_tasks(path, search.source(path)?)))
+            .flatten()
+            .filter(|task| self.task_matches(task))
+            .map(ViewItem::Task)
+            .collect()
+    }
+
+    /// Notes with every word and tag, by path.
+    fn matching_notes(&self, search: &SearchIndex, tags: &TagIndex) -> Vec<RelativePathBuf> {
+        let mut notes: Vec<RelativePathBuf> = if self.words.is_empty() {
+            search
+                .paths()
+                .map(RelativePath::to_relative_path_buf)
+                .collect()
+        } else {
+            let mut found: Vec<_> = search
+                .search(&self.words.join(" "))
+                .into_iter()
+                .map(|hit| hit.path)
+                .collect();
+            found.dedup();
+            found
+        };
+        for tag in &self.tags {
+            let tagged = tags.notes_with(tag);
+            notes.retain(|path| tagged.contains(&path.as_relative_path()));
+        }
+        notes
+    }
+
+    fn task_matches(&self, task: &Task) -> bool {
+        let task_words: Vec<String> = words(&task.text).map(|(_, word)| word).collect();
+        let task_tags: Vec<String> = tags_in(&task.text).into_iter().map(|t| t.name).collect();
+        self.words
+            .iter()
+            .all(|word| task_words.iter().any(|w| w.starts_with(word.as_str())))
+            && self.tags.iter().all(|tag| task_tags.contains(tag))
+    }
+}
+
+/// Something a view lists.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ViewItem {
+    Note(RelativePathBuf),
+    Task(Task),
+}
+
+/// A named query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedView {
+    pub name: String,
+    pub query: String,
+}
+
+impl SavedView {
+    /// What the view finds in the vault's indexes.
+    pub fn evaluate(&self, search: &SearchIndex, tags: &TagIndex) -> Vec<ViewItem> {
+        ViewQuery::parse(&self.query).evaluate(search, tags)
+    }
+
+    fn from_record(record: Vec<String>) -> Option<Self> {
+        let [name, query] = record.as_slice() else {
+            return None;
+        };
+        (!name.is_empty()).then(|| Self {
+            name: name.clone(),
+            query: query.clone(),
+        })
+    }
+}
+
+/// A vault's saved views, in the order they were added.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SavedViews {
+    views: Vec<SavedView>,
+}
+
+impl SavedViews {
+    /// Load views from the vault, returning none if the file doesn't exist.
+    pub fn load(notes_root: &Path) -> Result<Self, IoError> {
+        Ok(sidecar::read(SAVED_VIEWS_PATH, notes_root)?
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default())
+    }
+
+    /// Save views to the vault.
+    pub fn save(&self, notes_root: &Path) -> Result<(), IoError> {
+        sidecar::write(SAVED_VIEWS_PATH, notes_root, &self.to_text())
+    }
+
+    /// Views from the file's contents. Lines that aren't a named view are
+    /// dropped.
+    pub fn parse(content: &str) -> Self {
+        Self {
+            views: sidecar::records(content)
+                .filter_map(SavedView::from_record)
+                .collect(),
+        }
+    }
+
+    /// Serialize to the file format.
+    pub fn to_text(&self) -> String {
+        self.views
+            .iter()
+            .map(|view| sidecar::record(&[&view.name, &view.query]))
+            .collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SavedView> {
+        self.views.iter().find(|view| view.name == name)
+    }
+
+    /// Add a view, or change the query of the view called `name`. Spaces
+    /// around the name and query are dropped.
+    pub fn set(&mut self, name: &str, query: &str) {
+        let (name, query) = (name.trim().to_string(), query.trim().to_string());
+        match self.views.iter_mut().find(|view| view.name == name) {
+            Some(view) => view.query = query,
+            None => self.views.push(SavedView { name, query }),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<SavedView> {
+        let index = self.views.iter().position(|view| view.name == name)?;
+        Some(self.views.remove(index))
+    }
+
+    /// Views in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &SavedView> {
+        self.views.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.views.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.views.is_empty()
+    }
+}
+
+impl Vault {
+    /// The views saved in this vault.
+    pub fn saved_views(&self) -> Result<SavedViews, IoError> {
+        SavedViews::load(self.notes_root())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::create_test_notes_dir;
+
+    fn indexes(notes: &[(&str, &str)]) -> (SearchIndex, TagIndex) {
+        let mut search = SearchIndex::default();
+        let mut tags = TagIndex::default();
+        for (path, source) in notes {
+            search.update_note(RelativePath::new(path), source);
+            tags.update_note(RelativePath::new(path), source);
+        }
+        (search, tags)
+    }
+
+    #[test]
+    fn test_parse_query() {
+        assert_eq!(
+            ViewQuery::parse("is:task #Work  Budget-review"),
+            ViewQuery {
+                words: vec!["budget".to_string(), "review".to_string()],
+                tags: vec!["work".to_string()],
+                tasks: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_open_tasks_tagged() {
+        let (search, tags) = indexes(&[
+            (
+                "a.md",
+                "- [ ] Send report #work\n- [x] Old #work\n- [ ] Buy milk #home\n",
+            ),
+            ("b.md", "- TODO Plan budget #WORK\n"),
+            ("c.md", "- [ ] Unrelated\n"),
+        ]);
+
+        let texts = |query: &str| -> Vec<String> {
+            ViewQuery::parse(query)
+                .evaluate(&search, &tags)
+                .into_iter()
+                .map(|item| match item {
+                    ViewItem::Task(task) => format!("{}: {}", task.path, task.text),
+                    ViewItem::Note(path) => path.to_string(),
+                })
+                .collect()
+        };
+        assert_eq!(
+            texts("is:task #work"),
+            vec!["a.md: Send report #work", "b.md: TODO Plan budget #WORK"]
+        );
+        assert_eq!(
+            texts("is:task #work budg"),
+            vec!["b.md: TODO Plan budget #WORK"]
+        );
+        assert_eq!(texts("#home"), vec!["a.md"]);
+        assert_eq!(texts(""), vec!["a.md", "b.md", "c.md"]);
+    }
+
+    #[test]
+    fn test_saved_views_roundtrip() {
+        let notes_dir = create_test_notes_dir();
+        let vault = Vault::new(notes_dir.path());
+        assert!(vault.saved_views().unwrap().is_empty());
+
+        let mut views = SavedViews::default();
+        views.set("Work tasks", "is:task #work");
+        views.set("Ideas", "#idea");
+        views.set("Work tasks", "is:task\t#work #urgent");
+        views.save(notes_dir.path()).unwrap();
+
+        let loaded = vault.saved_views().unwrap();
+        assert_eq!(loaded, views);
+        let names: Vec<&str> = loaded.iter().map(|view| view.name.as_str()).collect();
+        assert_eq!(names, vec!["Work tasks", "Ideas"]);
+        assert_eq!(
+            loaded.get("Work tasks").unwrap().query,
+            "is:task\t#work #urgent"
+        );
+
+        views.remove("Ideas");
+        assert_eq!(views.to_text(), "Work tasks\tis:task\\t#work #urgent\n");
+    }
+}
@@ -0,0 +1,282 @@
+//! # Full-Text Search
+//!
+//! Finds words across every note in the vault. [`SearchIndex`] maps each
+//! lowercased word to the notes containing it, so a query only looks
+//! inside notes that contain all of its words. It is a [`VaultIndex`], so
+//! it can be built in the background by
+//! [`open_vault`](crate::vault::open_vault) and kept current by an
+//! [`IndexWorker`](crate::vault::IndexWorker), or updated directly with
+//! [`SearchIndex::update_note`] as notes change.
+//!
+//! A query is split into words the same way as notes. A note matches when
+//! it has every word; the last word also matches longer words starting
+//! with it, so results can update as the user types. Each occurrence of a
+//! query word in a matching note is one [`SearchHit`].
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::{Bound, Range};
+use std::path::Path;
+
+use relative_path::{RelativePath, RelativePathBuf};
+
+use crate::io::{self, IoError};
+use crate::vault::{IndexKind, VaultIndex};
+
+/// Longest snippet shown for a hit, in characters.
+const SNIPPET_CHARS: usize = 80;
+
+/// One place a query word was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    /// Note containing the word
+    pub path: RelativePathBuf,
+    /// Bytes of the word in the note's source
+    pub byte_range: Range<usize>,
+    /// The line around the word, trimmed and shortened to fit a list
+    pub snippet: String,
+}
+
+/// Word index over the whole vault.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchIndex {
+    /// Source of each note, for finding hits and snippets
+    notes: BTreeMap<RelativePathBuf, String>,
+    /// Notes containing each lowercased word
+    postings: BTreeMap<String, BTreeSet<RelativePathBuf>>,
+}
+
+impl SearchIndex {
+    /// Index every markdown file under `notes_root`. Files that can't be
+    /// read as text are skipped.
+    pub fn build(notes_root: &Path) -> Result<Self, IoError> {
+        let mut index = Self::default();
+        for path in io::scan_markdown_relative_paths(notes_root)? {
+            if let Ok(source) = io::read_file(&path, notes_root) {
+                index.update_note(&path, &source);
+            }
+        }
+        Ok(index)
+    }
+
+    /// Index a note's current source, replacing what was indexed for it.
+    pub fn update_note(&mut self, path: &RelativePath, source: &str) {
+        self.remove_note(path);
+        let path = path.to_relative_path_buf();
+        for (_, word) in words(source) {
+            self.postings.entry(word).or_default().insert(path.clone());
+        }
+        self.notes.insert(path, source.to_string());
+    }
+
+    /// Drop a note from the index (e.g. after it is deleted).
+    pub fn remove_note(&mut self, path: &RelativePath) {
+        let Some(source) = self.notes.remove(path) else {
+            return;
+        };
+        for (_, word) in words(&source) {
+            if let Some(paths) = self.postings.get_mut(&word) {
+                paths.remove(path);
+                if paths.is_empty() {
+                    self.postings.remove(&word);
+                }
+            }
+        }
+    }
+
+    /// Paths of the indexed notes, sorted.
+    pub fn paths(&self) -> impl Iterator<Item = &RelativePath> {
+        self.notes.keys().map(|path| path.as_relative_path())
+    }
+
+    /// The indexed source of a note.
+    pub fn source(&self, path: &RelativePath) -> Option<&str> {
+        self.notes.get(path).map(String::as_str)
+    }
+
+    /// Number of indexed notes.
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+
+    /// Every occurrence of the query's words in notes containing all of
+    /// them, by path and then position. Empty for a query with no words.
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        let terms: Vec<String> = words(query).map(|(_, word)| word).collect();
+        let Some((last, exact)) = terms.split_last() else {
+            return Vec::new();
+        };
+
+        let mut candidates: Option<BTreeSet<&RelativePathBuf>> = None;
+        for term in exact {
+            let paths = self.postings.get(term).into_iter().flatten().collect();
+            candidates = Some(match candidates {
+                Some(found) => found.intersection(&paths).copied().collect(),
+                None => paths,
+            });
+        }
+        let prefixed = self.prefixed(last);
+        let candidates = match candidates {
+            Some(found) => found.intersection(&prefixed).copied().collect(),
+            None => prefixed,
+        };
+
+        let is_hit = |word: &str| exact.iter().any(|term| term == word) || word.starts_with(last);
+        let mut hits = Vec::new();
+        for path in candidates {
+            let source = &self.notes[path];
+            for (range, word) in words(source) {
+                if is_hit(&word) {
+                    hits.push(SearchHit {
+                        path: path.clone(),
+                        snippet: snippet(source, &range),
+                        byte_range: range,
+                    });
+                }
+            }
+        }
+        hits
+    }
+
+    /// Notes containing a word starting with `prefix`.
+    fn prefixed(&self, prefix: &str) -> BTreeSet<&RelativePathBuf> {
+        self.postings
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(|(word, _)| word.starts_with(prefix))
+            .flat_map(|(_, paths)| paths)
+            .collect()
+    }
+}
+
+impl VaultIndex for SearchIndex {
+    fn kind(&self) -> IndexKind {
+        IndexKind::Search
+    }
+
+    fn add_note(&mut self, path: &RelativePath, source: &str) {
+        self.update_note(path, source);
+    }
+
+    fn remove_note(&mut self, path: &RelativePath) {
+        self.remove_note(path);
+    }
+}
+
+/// Runs of letters and digits in `text` with their byte ranges, lowercased.
+pub(crate) fn words(text: &str) -> impl Iterator<Item = (Range<usize>, String)> + '_ {
+    let mut chars = text.char_indices().peekable();
+    std::iter::from_fn(move || {
+        let (start, _) = chars.find(|(_, c)| c.is_alphanumeric())?;
+        let mut end = text.len();
+        while let Some(&(i, c)) = chars.peek() {
+            if !c.is_alphanumeric() {
+                end = i;
+                break;
+            }
+            chars.next();
+        }
+        Some((start..end, text[start..end].to_lowercase()))
+    })
+}
+
+/// The line containing `range`, trimmed, with long lines cut to
+/// [`SNIPPET_CHARS`] around the match and marked with `…`.
+fn snippet(source: &str, range: &Range<usize>) -> String {
+    let line_start = source[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[range.start..]
+        .find('\n')
+        .map_or(source.len(), |i| range.start + i);
+    let untrimmed = &source[line_start..line_end];
+    let line = untrimmed.trim();
+    if line.chars().count() <= SNIPPET_CHARS {
+        return line.to_string();
+    }
+
+    // Start a third of the way back so the match sits near the front
+    let line_start = line_start + untrimmed.len() - untrimmed.trim_start().len();
+    let before = source[line_start..range.start].chars().count();
+    let skip = before.saturating_sub(SNIPPET_CHARS / 3);
+    let mut snippet: String = line.chars().skip(skip).take(SNIPPET_CHARS).collect();
+    if skip > 0 {
+        snippet.insert(0, '…');
+    }
+    if skip + SNIPPET_CHARS < line.chars().count() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{create_test_file, create_test_notes_dir};
+
+    fn hits(index: &SearchIndex, query: &str) -> Vec<(String, String)> {
+        index
+            .search(query)
+            .into_iter()
+            .map(|hit| (hit.path.to_string(), hit.snippet))
+            .collect()
+    }
+
+    #[test]
+    fn test_search_needs_every_word() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "a.md", "# Garden\n\nPlant tomatoes in May.\n");
+        create_test_file(&notes_dir, "b.md", "- buy tomato seeds\n- Plant later\n");
+        let index = SearchIndex::build(notes_dir.path()).unwrap();
+        assert_eq!(index.len(), 2);
+
+        assert_eq!(
+            hits(&index, "plant tomatoes"),
+            vec![
+                ("a.md".to_string(), "Plant tomatoes in May.".to_string()),
+                ("a.md".to_string(), "Plant tomatoes in May.".to_string()),
+            ]
+        );
+        // The last word matches as a prefix
+        assert_eq!(
+            hits(&index, "PLANT tom"),
+            vec![
+                ("a.md".to_string(), "Plant tomatoes in May.".to_string()),
+                ("a.md".to_string(), "Plant tomatoes in May.".to_string()),
+                ("b.md".to_string(), "- buy tomato seeds".to_string()),
+                ("b.md".to_string(), "- Plant later".to_string()),
+            ]
+        );
+        assert!(index.search("  ").is_empty());
+        assert!(index.search("tom plant").is_empty());
+    }
+
+    #[test]
+    fn test_byte_ranges_and_incremental_updates() {
+        let mut index = SearchIndex::default();
+        let path = RelativePath::new("note.md");
+        index.update_note(path, "Ärger über Straße\n");
+        let found = index.search("straße");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].byte_range, 13..20);
+
+        index.update_note(path, "calm now\n");
+        assert!(index.search("straße").is_empty());
+        assert_eq!(index.search("calm").len(), 1);
+
+        index.remove_note(path);
+        assert!(index.is_empty());
+        assert_eq!(index, SearchIndex::default());
+    }
+
+    #[test]
+    fn test_long_lines_are_shortened_around_the_match() {
+        let mut index = SearchIndex::default();
+        let line = format!("{} needle {}", "word ".repeat(30), "tail ".repeat(30));
+        index.update_note(RelativePath::new("long.md"), &line);
+        let snippet = &index.search("needle")[0].snippet;
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert!(snippet.contains(" needle "));
+        assert_eq!(snippet.chars().count(), SNIPPET_CHARS + 2);
+    }
+}
@@ -0,0 +1,105 @@
+//! # Sidecar Files
+//!
+//! State the engine keeps beside the notes, under `.neuraxis/`, so it syncs
+//! with the vault: read positions, folds, bookmarks, saved views and
+//! annotations.
+//!
+//! The table files share one format: one record per line, fields separated
+//! by tabs. A backslash, tab, carriage return or line feed inside a field is
+//! written as `\\`, `\t`, `\r` or `\n`, so names, queries and excerpts can
+//! hold any text. Any other backslash is read as itself, which keeps files
+//! written before escaping was added readable. Readers skip lines without
+//! the fields they expect, so one damaged record doesn't lose the rest.
+
+use std::path::Path;
+
+use relative_path::RelativePath;
+
+use crate::io::{self, IoError};
+
+/// Read a sidecar file, or `None` if the vault doesn't have one yet.
+pub fn read(path: &str, notes_root: &Path) -> Result<Option<String>, IoError> {
+    match io::read_file(RelativePath::new(path), notes_root) {
+        Ok(content) => Ok(Some(content)),
+        Err(IoError::NotFound(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Write a sidecar file, creating `.neuraxis/` if needed.
+pub fn write(path: &str, notes_root: &Path, content: &str) -> Result<(), IoError> {
+    io::write_file(RelativePath::new(path), notes_root, content)
+}
+
+/// The records of a table file, each split into unescaped fields.
+pub fn records(content: &str) -> impl Iterator<Item = Vec<String>> + '_ {
+    content
+        .lines()
+        .map(|line| line.split('\t').map(unescape).collect())
+}
+
+/// One line of a table file, with its line break.
+pub fn record<S: AsRef<str>>(fields: &[S]) -> String {
+    let mut line = fields
+        .iter()
+        .map(|field| escape(field.as_ref()))
+        .collect::<Vec<_>>()
+        .join("\t");
+    line.push('\n');
+    line
+}
+
+fn escape(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars().peekable();
+    while let Some(c) = chars.next() {
+        let escaped = match (c, chars.peek()) {
+            ('\\', Some('\\')) => '\\',
+            ('\\', Some('t')) => '\t',
+            ('\\', Some('r')) => '\r',
+            ('\\', Some('n')) => '\n',
+            _ => {
+                unescaped.push(c);
+                continue;
+            }
+        };
+        chars.next();
+        unescaped.push(escaped);
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields_with_separators_round_trip() {
+        let fields = ["to\tdo", "line\none\r\n", "C:\\notes\\n", ""];
+        let line = record(&fields);
+        assert_eq!(line.lines().count(), 1);
+        assert_eq!(records(&line).collect::<Vec<_>>(), [fields]);
+    }
+
+    #[test]
+    fn test_unknown_escapes_kept() {
+        assert_eq!(
+            records("a\\b\tend\\\n").collect::<Vec<_>>(),
+            [["a\\b", "end\\"]]
+        );
+    }
+}
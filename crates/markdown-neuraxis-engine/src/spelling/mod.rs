@@ -0,0 +1,214 @@
+//! # Per-Vault Spelling Dictionary
+//!
+//! Notes are full of names, jargon and page titles that a general-purpose
+//! spell checker flags. The vault keeps its own list of accepted words in
+//! `.neuraxis/dictionary.txt` (one word per line) so it syncs along with the
+//! notes. Wiki-link targets and note names are whitelisted automatically but
+//! never written to the file.
+//!
+//! Nothing consults [`SpellDictionary::is_ignored`] yet. The Dioxus editors
+//! are plain textareas whose only checker is the webview's, which can't be
+//! given extra words, so they turn it off (`spellcheck: false`) rather than
+//! underline every note name. The dictionary is ready for a front-end that
+//! runs its own checker.
+
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
+
+use relative_path::RelativePath;
+
+use crate::io::{self, IoError};
+use crate::links::extract_wikilinks;
+
+/// Location of the dictionary file, relative to the notes root.
+pub const DICTIONARY_PATH: &str = ".neuraxis/dictionary.txt";
+
+/// Words the spell checker should accept for this vault.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpellDictionary {
+    /// User-managed words, persisted to the vault
+    words: BTreeSet<String>,
+    /// `words` lowercased, for lookups
+    lowercase_words: HashSet<String>,
+    /// Lowercased words derived from note names and wiki-link targets (not
+    /// persisted)
+    auto_words: HashSet<String>,
+}
+
+impl SpellDictionary {
+    /// Load the dictionary from the vault, returning an empty one if none exists.
+    pub fn load(notes_root: &Path) -> Result<Self, IoError> {
+        let path = RelativePath::new(DICTIONARY_PATH);
+        let content = match io::read_file(path, notes_root) {
+            Ok(content) => content,
+            Err(IoError::NotFound(_)) => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+
+        let mut dict = Self::default();
+        for line in content.lines().map(str::trim) {
+            if !line.starts_with('#') {
+                dict.add_word(line);
+            }
+        }
+        Ok(dict)
+    }
+
+    /// Save the user-managed words to the vault, sorted one per line.
+    pub fn save(&self, notes_root: &Path) -> Result<(), IoError> {
+        let mut content = String::new();
+        for word in &self.words {
+            content.push_str(word);
+            content.push('\n');
+        }
+        io::write_file(RelativePath::new(DICTIONARY_PATH), notes_root, &content)
+    }
+
+    /// Add a word. Returns false if it was already present.
+    pub fn add_word(&mut self, word: &str) -> bool {
+        let word = word.trim();
+        if word.is_empty() || !self.words.insert(word.to_string()) {
+            return false;
+        }
+        self.lowercase_words.insert(word.to_lowercase());
+        true
+    }
+
+    /// Remove a word. Returns false if it was not present.
+    pub fn remove_word(&mut self, word: &str) -> bool {
+        if !self.words.remove(word.trim()) {
+            return false;
+        }
+        // Another spelling, e.g. "Rust" for "rust", may still need the entry
+        self.lowercase_words = self.words.iter().map(|w| w.to_lowercase()).collect();
+        true
+    }
+
+    /// User-managed words in sorted order.
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.words.iter().map(String::as_str)
+    }
+
+    /// Whitelist note names and wiki-link targets found in the vault.
+    ///
+    /// Replaces any previously whitelisted words, so it can be called again
+    /// after the vault changes.
+    pub fn whitelist_vault_names(&mut self, notes_root: &Path) -> Result<(), IoError> {
+        self.auto_words.clear();
+
+        for path in io::scan_markdown_relative_paths(notes_root)? {
+            if let Some(stem) = path.file_stem() {
+                self.auto_words.extend(split_words(stem));
+            }
+
+            let source = io::read_file(&path, notes_root)?;
+            for link in extract_wikilinks(&source) {
+                self.auto_words.extend(split_words(&link.target));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether the spell checker should accept this word.
+    ///
+    /// Matching is case-insensitive so "Rust" in the dictionary also accepts
+    /// "rust" at the start of a sentence and vice versa.
+    pub fn is_ignored(&self, word: &str) -> bool {
+        let word = word.trim();
+        if word.is_empty() {
+            return false;
+        }
+        let lower = word.to_lowercase();
+        self.lowercase_words.contains(&lower) || self.auto_words.contains(&lower)
+    }
+}
+
+/// Split a name into the individual words a spell checker would see,
+/// lowercased.
+fn split_words(name: &str) -> impl Iterator<Item = String> + '_ {
+    name.split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|w| w.chars().any(char::is_alphabetic))
+        .map(str::to_lowercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{create_test_file, create_test_notes_dir};
+
+    #[test]
+    fn test_load_missing_dictionary_is_empty() {
+        let notes_dir = create_test_notes_dir();
+        let dict = SpellDictionary::load(notes_dir.path()).unwrap();
+        assert_eq!(dict.words().count(), 0);
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let notes_dir = create_test_notes_dir();
+        let mut dict = SpellDictionary::default();
+        assert!(dict.add_word("neuraxis"));
+        assert!(dict.add_word("Abell"));
+        assert!(!dict.add_word("neuraxis"));
+        dict.save(notes_dir.path()).unwrap();
+
+        let content = std::fs::read_to_string(notes_dir.path().join(DICTIONARY_PATH)).unwrap();
+        assert_eq!(content, "Abell\nneuraxis\n");
+
+        let loaded = SpellDictionary::load(notes_dir.path()).unwrap();
+        assert_eq!(
+            loaded.words().collect::<Vec<_>>(),
+            vec!["Abell", "neuraxis"]
+        );
+    }
+
+    #[test]
+    fn test_comments_and_blank_lines_are_skipped() {
+        let notes_dir = create_test_notes_dir();
+        std::fs::create_dir(notes_dir.path().join(".neuraxis")).unwrap();
+        create_test_file(&notes_dir, DICTIONARY_PATH, "# names\n\nRowan\n");
+
+        let dict = SpellDictionary::load(notes_dir.path()).unwrap();
+        assert_eq!(dict.words().collect::<Vec<_>>(), vec!["Rowan"]);
+    }
+
+    #[test]
+    fn test_is_ignored_case_insensitive() {
+        let mut dict = SpellDictionary::default();
+        dict.add_word("Logos");
+        assert!(dict.is_ignored("logos"));
+        assert!(dict.is_ignored("LOGOS"));
+        assert!(!dict.is_ignored("lexer"));
+        assert!(!dict.is_ignored(""));
+    }
+
+    #[test]
+    fn test_remove_word() {
+        let mut dict = SpellDictionary::default();
+        dict.add_word("xi-rope");
+        assert!(dict.remove_word("xi-rope"));
+        assert!(!dict.remove_word("xi-rope"));
+        assert!(!dict.is_ignored("xi-rope"));
+
+        dict.add_word("Rust");
+        dict.add_word("rust");
+        dict.remove_word("rust");
+        assert!(dict.is_ignored("RUST"));
+    }
+
+    #[test]
+    fn test_whitelist_vault_names_not_persisted() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "Zettelkasten.md", "See [[Obsidianish Ideas]]\n");
+
+        let mut dict = SpellDictionary::default();
+        dict.whitelist_vault_names(notes_dir.path()).unwrap();
+        assert!(dict.is_ignored("Zettelkasten"));
+        assert!(dict.is_ignored("obsidianish"));
+
+        dict.save(notes_dir.path()).unwrap();
+        let loaded = SpellDictionary::load(notes_dir.path()).unwrap();
+        assert!(!loaded.is_ignored("Zettelkasten"));
+    }
+}
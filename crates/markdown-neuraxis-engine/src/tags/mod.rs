@@ -0,0 +1,263 @@
+//! # Tags
+//!
+//! `#tags` in note text, parsed by the grammar as `TAG` nodes (see
+//! [`markdown_neuraxis_syntax::tag`] for what counts as a tag). Snapshots
+//! show them as [`InlineNode::Tag`](crate::editing::InlineNode) segments.
+//!
+//! [`TagIndex`] records, for every tag in the vault, the notes and blocks
+//! that use it, for browsing notes by tag. Tags match regardless of case
+//! and are listed in lowercase. [`rename`] renames a tag across the vault.
+
+pub mod rename;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+
+use markdown_neuraxis_syntax::tag::tag_name;
+use markdown_neuraxis_syntax::{SyntaxKind, SyntaxNode, parse, visit};
+use relative_path::{RelativePath, RelativePathBuf};
+
+use crate::vault::{IndexKind, SnapshotReader, SnapshotWriter, VaultIndex};
+
+pub use rename::{TagEdit, TagRenamePatch};
+
+/// One use of a tag in a note.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteTag {
+    /// Tag name without the `#`, lowercased
+    pub name: String,
+    /// Source range of the block containing the tag (a paragraph,
+    /// heading or table row)
+    pub block_range: Range<usize>,
+}
+
+/// Every tag in a note's source, in order.
+pub fn tags_in(source: &str) -> Vec<NoteTag> {
+    visit::nodes_of_kind(&parse(source), SyntaxKind::TAG)
+        .filter_map(|node| {
+            let name = tag_name(&node.text().to_string())?.to_lowercase();
+            let block = node.ancestors().find(is_tag_block)?;
+            let range = block.text_range();
+            Some(NoteTag {
+                name,
+                block_range: range.start().into()..range.end().into(),
+            })
+        })
+        .collect()
+}
+
+fn is_tag_block(node: &SyntaxNode) -> bool {
+    matches!(
+        node.kind(),
+        SyntaxKind::PARAGRAPH
+            | SyntaxKind::HEADING
+            | SyntaxKind::SETEXT_HEADING
+            | SyntaxKind::TABLE_ROW
+    )
+}
+
+/// A block using a tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagLocation {
+    pub path: RelativePathBuf,
+    pub block_range: Range<usize>,
+}
+
+/// Tags used across the vault and where each is used.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TagIndex {
+    notes: BTreeMap<RelativePathBuf, Vec<NoteTag>>,
+    /// Notes using each tag
+    tagged: BTreeMap<String, BTreeSet<RelativePathBuf>>,
+}
+
+impl TagIndex {
+    /// Re-read a note's tags from its current source.
+    pub fn update_note(&mut self, path: &RelativePath, source: &str) {
+        self.set_note(path, tags_in(source));
+    }
+
+    fn set_note(&mut self, path: &RelativePath, tags: Vec<NoteTag>) {
+        self.remove_note(path);
+        for tag in &tags {
+            self.tagged
+                .entry(tag.name.clone())
+                .or_default()
+                .insert(path.to_relative_path_buf());
+        }
+        if !tags.is_empty() {
+            self.notes.insert(path.to_relative_path_buf(), tags);
+        }
+    }
+
+    /// Drop a note from the index (e.g. after deletion).
+    pub fn remove_note(&mut self, path: &RelativePath) {
+        for tag in self.notes.remove(path).into_iter().flatten() {
+            if let Some(paths) = self.tagged.get_mut(&tag.name) {
+                paths.remove(path);
+                if paths.is_empty() {
+                    self.tagged.remove(&tag.name);
+                }
+            }
+        }
+    }
+
+    /// Every tag with the number of notes using it, by name.
+    pub fn tags(&self) -> Vec<(&str, usize)> {
+        self.tagged
+            .iter()
+            .map(|(name, paths)| (name.as_str(), paths.len()))
+            .collect()
+    }
+
+    /// Notes using `tag` (with or without its `#`), by path.
+    pub fn notes_with(&self, tag: &str) -> Vec<&RelativePath> {
+        self.tagged
+            .get(&normalize(tag))
+            .into_iter()
+            .flatten()
+            .map(|path| path.as_relative_path())
+            .collect()
+    }
+
+    /// Blocks using `tag` (with or without its `#`), by path and then
+    /// position. A block using the tag twice is listed once.
+    pub fn locations(&self, tag: &str) -> Vec<TagLocation> {
+        let tag = normalize(tag);
+        let mut locations = Vec::new();
+        for path in self.tagged.get(&tag).into_iter().flatten() {
+            let mut seen = BTreeSet::new();
+            for note_tag in &self.notes[path] {
+                if note_tag.name == tag && seen.insert(note_tag.block_range.start) {
+                    locations.push(TagLocation {
+                        path: path.clone(),
+                        block_range: note_tag.block_range.clone(),
+                    });
+                }
+            }
+        }
+        locations
+    }
+
+    /// Tags used in one note, in order of first use.
+    pub fn note_tags(&self, path: &RelativePath) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for tag in self.notes.get(path).into_iter().flatten() {
+            if !names.contains(&tag.name.as_str()) {
+                names.push(&tag.name);
+            }
+        }
+        names
+    }
+}
+
+fn normalize(tag: &str) -> String {
+    tag.strip_prefix('#').unwrap_or(tag).to_lowercase()
+}
+
+impl VaultIndex for TagIndex {
+    fn kind(&self) -> IndexKind {
+        IndexKind::Tags
+    }
+
+    fn add_note(&mut self, path: &RelativePath, source: &str) {
+        self.update_note(path, source);
+    }
+
+    fn remove_note(&mut self, path: &RelativePath) {
+        self.remove_note(path);
+    }
+
+    fn snapshot_note(&self, path: &RelativePath) -> Option<Vec<u8>> {
+        let tags = self.notes.get(path).map_or(&[][..], Vec::as_slice);
+        let mut out = SnapshotWriter::default();
+        out.u64(tags.len() as u64);
+        for tag in tags {
+            out.str(&tag.name);
+            out.u64(tag.block_range.start as u64);
+            out.u64(tag.block_range.end as u64);
+        }
+        Some(out.into_bytes())
+    }
+
+    fn restore_note(&mut self, path: &RelativePath, bytes: &[u8]) -> bool {
+        fn decode(input: &mut SnapshotReader<'_>) -> Option<Vec<NoteTag>> {
+            let count = input.u64()?;
+            let mut tags = Vec::new();
+            for _ in 0..count {
+                let name = input.str()?;
+                let start = usize::try_from(input.u64()?).ok()?;
+                let end = usize::try_from(input.u64()?).ok()?;
+                tags.push(NoteTag {
+                    name,
+                    block_range: start..end,
+                });
+            }
+            input.is_empty().then_some(tags)
+        }
+        match decode(&mut SnapshotReader::new(bytes)) {
+            Some(tags) => {
+                self.set_note(path, tags);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tags_in_note() {
+        let source = "# Plan #Work\n\nCall Sam #work #errands, then #1.\n\n```\n#not-a-tag\n```\n\n- item #home/garden\n";
+        let names: Vec<String> = tags_in(source).into_iter().map(|t| t.name).collect();
+        assert_eq!(names, vec!["work", "work", "errands", "home/garden"]);
+        assert_eq!(tags_in(source)[1].block_range, 14..48);
+    }
+
+    #[test]
+    fn test_index_lookups_and_updates() {
+        let mut index = TagIndex::default();
+        let a = RelativePath::new("a.md");
+        let b = RelativePath::new("b.md");
+        index.update_note(a, "One #work and #WORK.\n\nTwo #home\n");
+        index.update_note(b, "Three #work\n");
+
+        assert_eq!(index.tags(), vec![("home", 1), ("work", 2)]);
+        assert_eq!(index.notes_with("#Work"), vec![a, b]);
+        assert_eq!(
+            index.locations("work"),
+            vec![
+                TagLocation {
+                    path: a.to_relative_path_buf(),
+                    block_range: 0..21,
+                },
+                TagLocation {
+                    path: b.to_relative_path_buf(),
+                    block_range: 0..12,
+                },
+            ]
+        );
+        assert_eq!(index.note_tags(a), vec!["work", "home"]);
+
+        index.update_note(a, "No tags now\n");
+        assert_eq!(index.tags(), vec![("work", 1)]);
+        index.remove_note(b);
+        assert_eq!(index, TagIndex::default());
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let mut index = TagIndex::default();
+        let path = RelativePath::new("a.md");
+        index.update_note(path, "Some #tag here\n");
+        let bytes = index.snapshot_note(path).unwrap();
+
+        let mut restored = TagIndex::default();
+        assert!(restored.restore_note(path, &bytes));
+        assert_eq!(restored, index);
+        assert!(!restored.restore_note(path, &bytes[..bytes.len() - 1]));
+    }
+}
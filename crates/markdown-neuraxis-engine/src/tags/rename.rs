@@ -0,0 +1,292 @@
+//! # Tag Rename
+//!
+//! Renaming a tag means rewriting it everywhere it's written: `#tags` in
+//! text, `tags:: a, b` properties and the `tags` list in front matter.
+//! Nested tags move with their parent, so renaming `home` to `house` turns
+//! `#home/garden` into `#house/garden`.
+//!
+//! The flow matches heading renames (see [`crate::links::rename`]):
+//!
+//! 1. [`plan_tag_rename`] scans the vault and returns a patch per note to
+//!    preview
+//! 2. [`apply_tag_rename`] writes the (possibly user-filtered) patches
+
+use std::ops::Range;
+use std::path::Path;
+
+use markdown_neuraxis_syntax::tag::tag_name;
+use markdown_neuraxis_syntax::{SyntaxKind, parse, visit};
+use relative_path::RelativePathBuf;
+
+use crate::io::{self, IoError};
+
+/// A single proposed edit to one use of a tag.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagEdit {
+    /// Byte range of the tag name (without any `#`) to replace
+    pub range: Range<usize>,
+    /// 1-based line number, for preview display
+    pub line: usize,
+    /// Full source line containing the tag, for preview display
+    pub context: String,
+    /// Current tag name text
+    pub old_text: String,
+    /// Replacement tag name text
+    pub new_text: String,
+}
+
+/// The edits renaming a tag in one note.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagRenamePatch {
+    pub path: RelativePathBuf,
+    /// Edits in source order
+    pub edits: Vec<TagEdit>,
+}
+
+/// Find every use of the tag `old` across the vault and plan renaming it
+/// to `new`. Both may be given with or without their `#`; `old` matches
+/// regardless of case.
+///
+/// Fails if either isn't a valid tag name.
+pub fn plan_tag_rename(
+    notes_root: &Path,
+    old: &str,
+    new: &str,
+) -> Result<Vec<TagRenamePatch>, IoError> {
+    let (old, new) = (valid_name(old)?, valid_name(new)?);
+    let mut patches = Vec::new();
+    for path in io::scan_markdown_relative_paths(notes_root)? {
+        let source = io::read_file(&path, notes_root)?;
+        let edits = tag_rename_edits(&source, &old, &new);
+        if !edits.is_empty() {
+            patches.push(TagRenamePatch { path, edits });
+        }
+    }
+    Ok(patches)
+}
+
+fn valid_name(tag: &str) -> Result<String, IoError> {
+    let tag = tag.trim();
+    let name = tag.strip_prefix('#').unwrap_or(tag);
+    if tag_name(name) == Some(name) {
+        Ok(name.to_string())
+    } else {
+        Err(IoError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("not a valid tag name: {tag}"),
+        )))
+    }
+}
+
+/// Edits renaming the tag `old` (without its `#`) to `new` in one note's
+/// source.
+pub fn tag_rename_edits(source: &str, old: &str, new: &str) -> Vec<TagEdit> {
+    let old = old.to_lowercase();
+    let root = parse(source);
+    let mut names = Vec::new();
+
+    for node in visit::nodes_of_kind(&root, SyntaxKind::TAG) {
+        let start = usize::from(node.text_range().start()) + 1;
+        if let Some(name) = tag_name(&node.text().to_string()) {
+            names.push(start..start + name.len());
+        }
+    }
+    for node in visit::nodes_of_kind(&root, SyntaxKind::PROPERTY) {
+        let text = node.text().to_string();
+        if let Some((key, value)) = text.split_once("::")
+            && key.trim().eq_ignore_ascii_case("tags")
+        {
+            let value_start = usize::from(node.text_range().start()) + key.len() + 2;
+            names.extend(list_item_names(value, value_start));
+        }
+    }
+    if let Some(node) = root
+        .children()
+        .find(|node| node.kind() == SyntaxKind::FRONTMATTER)
+    {
+        let start = usize::from(node.text_range().start());
+        names.extend(frontmatter_tag_names(&node.text().to_string(), start));
+    }
+    names.sort_by_key(|range| range.start);
+
+    names
+        .into_iter()
+        .filter_map(|range| {
+            let name = &source[range.clone()];
+            let end = matched_prefix(name, &old)?;
+            let range = range.start..range.start + end;
+            let line_start = source[..range.start].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = source[range.end..]
+                .find('\n')
+                .map_or(source.len(), |i| range.end + i);
+            Some(TagEdit {
+                line: source[..range.start].matches('\n').count() + 1,
+                context: source[line_start..line_end].to_string(),
+                old_text: name[..end].to_string(),
+                new_text: new.to_string(),
+                range,
+            })
+        })
+        .collect()
+}
+
+/// Length of the part of `name` naming the lowercase tag `old`: all of it,
+/// or the leading segments of a nested tag under `old`.
+fn matched_prefix(name: &str, old: &str) -> Option<usize> {
+    let depth = old.split('/').count();
+    let end = name
+        .match_indices('/')
+        .nth(depth - 1)
+        .map_or(name.len(), |(i, _)| i);
+    (name[..end].to_lowercase() == old).then_some(end)
+}
+
+/// Ranges of the names in a comma-separated list such as `a, #b`,
+/// `[a, "b"]` or `[[a]]`, offset by `offset`.
+fn list_item_names(text: &str, offset: usize) -> Vec<Range<usize>> {
+    let is_decoration = |c: char| c.is_whitespace() || matches!(c, '[' | ']' | '"' | '\'' | '#');
+    let mut names = Vec::new();
+    let mut start = offset;
+    for item in text.split(',') {
+        let lead = item.len() - item.trim_start_matches(is_decoration).len();
+        let name = item.trim_matches(is_decoration);
+        if !name.is_empty() {
+            names.push(start + lead..start + lead + name.len());
+        }
+        start += item.len() + 1;
+    }
+    names
+}
+
+/// Ranges of the names in a front matter `tags` entry: an inline list
+/// (`tags: [a, b]`, `tags = ["a"]`, `tags: a`) or the indented lines
+/// below it (`  - a`).
+fn frontmatter_tag_names(text: &str, offset: usize) -> Vec<Range<usize>> {
+    let mut names = Vec::new();
+    let mut in_tags = false;
+    let mut line_start = offset;
+    for line in text.split_inclusive('\n') {
+        if line.starts_with([' ', '\t']) {
+            if in_tags {
+                let item = line.trim_start();
+                let item = item.strip_prefix("- ").unwrap_or(item);
+                names.extend(list_item_names(item, line_start + line.len() - item.len()));
+            }
+        } else {
+            in_tags = false;
+            if let Some(at) = line.find([':', '='])
+                && line[..at].trim() == "tags"
+            {
+                let value = &line[at + 1..];
+                names.extend(list_item_names(value, line_start + at + 1));
+                in_tags = value.trim().is_empty() || (value.contains('[') && !value.contains(']'));
+            }
+        }
+        line_start += line.len();
+    }
+    names
+}
+
+/// Write tag rename patches to disk, returning the number of files
+/// changed.
+///
+/// Edits whose range no longer holds the expected old text (the file
+/// changed since planning) are skipped.
+pub fn apply_tag_rename(notes_root: &Path, patches: &[TagRenamePatch]) -> Result<usize, IoError> {
+    let mut files_changed = 0;
+    for patch in patches {
+        let mut source = io::read_file(&patch.path, notes_root)?;
+        let mut edits: Vec<&TagEdit> = patch.edits.iter().collect();
+        edits.sort_by_key(|edit| std::cmp::Reverse(edit.range.start));
+
+        let mut changed = false;
+        for edit in edits {
+            if source.get(edit.range.clone()) == Some(edit.old_text.as_str()) {
+                source.replace_range(edit.range.clone(), &edit.new_text);
+                changed = true;
+            }
+        }
+
+        if changed {
+            io::write_file(&patch.path, notes_root, &source)?;
+            files_changed += 1;
+        }
+    }
+    Ok(files_changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use relative_path::RelativePath;
+
+    use crate::tests::{create_test_file, create_test_notes_dir};
+
+    fn renamed(source: &str, old: &str, new: &str) -> String {
+        let mut source = source.to_string();
+        for edit in tag_rename_edits(&source.clone(), old, new).iter().rev() {
+            source.replace_range(edit.range.clone(), &edit.new_text);
+        }
+        source
+    }
+
+    #[test]
+    fn test_renames_text_tags_and_nested_tags() {
+        assert_eq!(
+            renamed(
+                "# Plan #Home\n\nDig #home/garden, not #homework.\n\n```\n#home\n```\n",
+                "home",
+                "house"
+            ),
+            "# Plan #house\n\nDig #house/garden, not #homework.\n\n```\n#home\n```\n"
+        );
+        assert_eq!(
+            renamed(
+                "Zones #home/garden #home/gardening\n",
+                "home/garden",
+                "yard"
+            ),
+            "Zones #yard #home/gardening\n"
+        );
+    }
+
+    #[test]
+    fn test_renames_properties_and_frontmatter() {
+        let source = "---\ntitle: Home\ntags: [home, \"work\"]\naliases:\n  - home\n---\n\n\
+                      tags:: #home, [[home/garden]], other\n";
+        assert_eq!(
+            renamed(source, "home", "house"),
+            "---\ntitle: Home\ntags: [house, \"work\"]\naliases:\n  - home\n---\n\n\
+             tags:: #house, [[house/garden]], other\n"
+        );
+        assert_eq!(
+            renamed("---\ntags:\n  - home\n  - work\n---\n", "home", "house"),
+            "---\ntags:\n  - house\n  - work\n---\n"
+        );
+        assert_eq!(
+            renamed("+++\ntags = [\"home\"]\n+++\n", "home", "house"),
+            "+++\ntags = [\"house\"]\n+++\n"
+        );
+    }
+
+    #[test]
+    fn test_plan_and_apply_across_vault() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "a.md", "Chores #home\n");
+        create_test_file(&notes_dir, "b.md", "Nothing here\n");
+        create_test_file(&notes_dir, "c.md", "---\ntags: home\n---\n");
+
+        let patches = plan_tag_rename(notes_dir.path(), "#home", "house").unwrap();
+        let paths: Vec<_> = patches.iter().map(|p| p.path.as_str()).collect();
+        assert_eq!(paths, ["a.md", "c.md"]);
+        assert_eq!(patches[0].edits[0].line, 1);
+        assert_eq!(patches[0].edits[0].context, "Chores #home");
+
+        create_test_file(&notes_dir, "c.md", "changed meanwhile\n");
+        assert_eq!(apply_tag_rename(notes_dir.path(), &patches).unwrap(), 1);
+        let content = io::read_file(RelativePath::new("a.md"), notes_dir.path()).unwrap();
+        assert_eq!(content, "Chores #house\n");
+
+        assert!(plan_tag_rename(notes_dir.path(), "home", "not a tag").is_err());
+    }
+}
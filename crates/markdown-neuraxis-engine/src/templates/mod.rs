@@ -0,0 +1,505 @@
+//! # Note Templates
+//!
+//! Templates are ordinary notes in the `templates/` folder. A template can
+//! declare variables in its front matter; front-ends read the schema with
+//! [`Template::variables`], show a form, and pass the answers to
+//! [`Template::instantiate`], which validates them and substitutes each
+//! `{{name}}` placeholder in the body:
+//!
+//! ```markdown
+//! ---
+//! variables:
+//!   - name: title
+//!     prompt: Meeting title
+//!   - name: date
+//!     type: date
+//!     default: today
+//!   - name: kind
+//!     type: choice
+//!     choices: [standup, planning, retro]
+//! ---
+//! # {{title}} ({{kind}})
+//!
+//! Held on [[{{date}}]]
+//! ```
+//!
+//! The template's front matter is not copied into new notes. Placeholders
+//! that don't name a declared variable are left as written.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use jiff::civil::Date;
+use relative_path::{RelativePath, RelativePathBuf};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::io::{self, IoError};
+
+/// Folder holding templates, relative to the notes root.
+pub const TEMPLATES_DIR: &str = "templates";
+
+/// Default value for date variables meaning the date the note is created.
+const TODAY: &str = "today";
+
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    #[error(transparent)]
+    Io(#[from] IoError),
+    #[error("Invalid template front matter: {0}")]
+    FrontMatter(String),
+    #[error("No value for required variable '{0}'")]
+    MissingValue(String),
+    #[error("'{value}' is not a valid date for '{name}' (expected YYYY-MM-DD)")]
+    InvalidDate { name: String, value: String },
+    #[error("'{value}' is not one of the choices for '{name}': {}", choices.join(", "))]
+    InvalidChoice {
+        name: String,
+        value: String,
+        choices: Vec<String>,
+    },
+    #[error("Template has no variable '{0}'")]
+    UnknownVariable(String),
+}
+
+/// What kind of value a variable takes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VariableType {
+    String,
+    /// An ISO date, `YYYY-MM-DD`
+    Date,
+    /// One of a fixed list of values
+    Choice(Vec<String>),
+}
+
+/// A variable declared in a template's front matter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateVariable {
+    pub name: String,
+    /// Question to show the user; defaults to the name
+    pub prompt: String,
+    pub kind: VariableType,
+    /// Value used when none is given. `today` for a date variable means the
+    /// date the note is created.
+    pub default: Option<String>,
+    /// Whether the variable may be left empty
+    pub optional: bool,
+}
+
+/// A parsed template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    name: String,
+    variables: Vec<TemplateVariable>,
+    body: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FrontMatter {
+    #[serde(default)]
+    variables: Vec<RawVariable>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawVariable {
+    name: String,
+    prompt: Option<String>,
+    #[serde(rename = "type", default)]
+    kind: RawType,
+    #[serde(default)]
+    choices: Vec<String>,
+    default: Option<String>,
+    #[serde(default)]
+    optional: bool,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum RawType {
+    #[default]
+    String,
+    Date,
+    Choice,
+}
+
+impl Template {
+    /// Parse a template's text. `name` identifies it to the user.
+    pub fn parse(name: &str, text: &str) -> Result<Self, TemplateError> {
+        let (front_matter, body) = split_front_matter(text);
+        let variables = match front_matter {
+            Some(yaml) if !yaml.trim().is_empty() => {
+                let front_matter: FrontMatter = serde_yaml_ng::from_str(yaml)
+                    .map_err(|e| TemplateError::FrontMatter(e.to_string()))?;
+                front_matter
+                    .variables
+                    .into_iter()
+                    .map(TemplateVariable::from_raw)
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+            _ => Vec::new(),
+        };
+
+        let mut seen = std::collections::HashSet::new();
+        if let Some(duplicate) = variables.iter().find(|v| !seen.insert(&v.name)) {
+            return Err(TemplateError::FrontMatter(format!(
+                "variable '{}' is declared twice",
+                duplicate.name
+            )));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            variables,
+            body: body.to_string(),
+        })
+    }
+
+    /// Load `templates/<name>.md` from the vault.
+    pub fn load(notes_root: &Path, name: &str) -> Result<Self, TemplateError> {
+        let text = io::read_file(&template_path(name), notes_root)?;
+        Self::parse(name, &text)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The variables to ask for, in declaration order.
+    pub fn variables(&self) -> &[TemplateVariable] {
+        &self.variables
+    }
+
+    /// Fill in the template. `values` maps variable names to answers; empty
+    /// or missing answers fall back to the variable's default. `today` is
+    /// used for date defaults of `today`.
+    pub fn instantiate(
+        &self,
+        values: &BTreeMap<String, String>,
+        today: Date,
+    ) -> Result<String, TemplateError> {
+        if let Some(unknown) = values
+            .keys()
+            .find(|name| !self.variables.iter().any(|v| &v.name == *name))
+        {
+            return Err(TemplateError::UnknownVariable(unknown.clone()));
+        }
+
+        let mut resolved = BTreeMap::new();
+        for variable in &self.variables {
+            let given = values
+                .get(&variable.name)
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty());
+            resolved.insert(variable.name.as_str(), variable.resolve(given, today)?);
+        }
+
+        Ok(substitute(&self.body, &resolved))
+    }
+
+    /// Create a new note at `path` from this template, refusing to overwrite
+    /// an existing file.
+    pub fn create_note(
+        &self,
+        notes_root: &Path,
+        path: &RelativePath,
+        values: &BTreeMap<String, String>,
+        today: Date,
+    ) -> Result<(), TemplateError> {
+        let absolute = path.to_path(notes_root);
+        if absolute.exists() {
+            return Err(IoError::FileExists(absolute).into());
+        }
+        let content = self.instantiate(values, today)?;
+        io::write_file(path, notes_root, &content)?;
+        Ok(())
+    }
+}
+
+impl TemplateVariable {
+    fn from_raw(raw: RawVariable) -> Result<Self, TemplateError> {
+        let kind = match raw.kind {
+            RawType::String => VariableType::String,
+            RawType::Date => VariableType::Date,
+            RawType::Choice if raw.choices.is_empty() => {
+                return Err(TemplateError::FrontMatter(format!(
+                    "choice variable '{}' has no choices",
+                    raw.name
+                )));
+            }
+            RawType::Choice => VariableType::Choice(raw.choices),
+        };
+        let variable = Self {
+            prompt: raw.prompt.unwrap_or_else(|| raw.name.clone()),
+            name: raw.name,
+            kind,
+            default: raw.default,
+            optional: raw.optional,
+        };
+        // A bad default would only surface when a user skips the question
+        if let Some(default) = &variable.default {
+            variable
+                .validate(default, Date::default())
+                .map_err(|e| TemplateError::FrontMatter(format!("invalid default: {e}")))?;
+        }
+        Ok(variable)
+    }
+
+    fn resolve(&self, given: Option<&str>, today: Date) -> Result<String, TemplateError> {
+        match given.or(self.default.as_deref()) {
+            Some(value) => self.validate(value, today),
+            None if self.optional => Ok(String::new()),
+            None => Err(TemplateError::MissingValue(self.name.clone())),
+        }
+    }
+
+    /// Check a value against the variable's type, returning the text to insert.
+    fn validate(&self, value: &str, today: Date) -> Result<String, TemplateError> {
+        match &self.kind {
+            VariableType::String => Ok(value.to_string()),
+            VariableType::Date if value == TODAY => Ok(today.to_string()),
+            VariableType::Date => {
+                value
+                    .parse::<Date>()
+                    .map(|date| date.to_string())
+                    .map_err(|_| TemplateError::InvalidDate {
+                        name: self.name.clone(),
+                        value: value.to_string(),
+                    })
+            }
+            VariableType::Choice(choices) => {
+                if choices.iter().any(|c| c == value) {
+                    Ok(value.to_string())
+                } else {
+                    Err(TemplateError::InvalidChoice {
+                        name: self.name.clone(),
+                        value: value.to_string(),
+                        choices: choices.clone(),
+                    })
+                }
+            }
+        }
+    }
+}
+
+/// Names of all templates in the vault, sorted.
+pub fn list_templates(notes_root: &Path) -> Result<Vec<String>, IoError> {
+    let dir = notes_root.join(TEMPLATES_DIR);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut names: Vec<String> = io::scan_markdown_relative_paths(&dir)?
+        .into_iter()
+        .map(|path| path.with_extension("").into_string())
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Path of the template called `name`, e.g. `templates/meeting.md`.
+pub fn template_path(name: &str) -> RelativePathBuf {
+    RelativePath::new(TEMPLATES_DIR).join(format!("{name}.md"))
+}
+
+/// Split `---` fenced front matter from the start of `text`.
+fn split_front_matter(text: &str) -> (Option<&str>, &str) {
+    let Some(rest) = text
+        .strip_prefix("---\n")
+        .or_else(|| text.strip_prefix("---\r\n"))
+    else {
+        return (None, text);
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+    (None, text)
+}
+
+/// Replace `{{name}}` placeholders (spaces inside the braces allowed).
+fn substitute(body: &str, values: &BTreeMap<&str, String>) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(open) = rest.find("{{") {
+        let Some(close) = rest[open + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[open + 2..open + 2 + close].trim();
+        out.push_str(&rest[..open]);
+        match values.get(name) {
+            Some(value) => out.push_str(value),
+            None => out.push_str(&rest[open..open + close + 4]),
+        }
+        rest = &rest[open + close + 4..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::create_test_notes_dir;
+    use jiff::civil::date;
+
+    const MEETING: &str = "---
+variables:
+  - name: title
+    prompt: Meeting title
+  - name: date
+    type: date
+    default: today
+  - name: kind
+    type: choice
+    choices: [standup, planning, retro]
+  - name: notes
+    optional: true
+---
+# {{title}} ({{ kind }})
+
+Held on [[{{date}}]]. {{notes}}{{unknown}}
+";
+
+    fn values(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_variable_schema() {
+        let template = Template::parse("meeting", MEETING).unwrap();
+        let variables = template.variables();
+
+        assert_eq!(variables.len(), 4);
+        assert_eq!(variables[0].prompt, "Meeting title");
+        assert_eq!(variables[0].kind, VariableType::String);
+        assert_eq!(variables[1].kind, VariableType::Date);
+        assert_eq!(variables[1].default.as_deref(), Some("today"));
+        assert_eq!(
+            variables[2].kind,
+            VariableType::Choice(vec!["standup".into(), "planning".into(), "retro".into()])
+        );
+        assert_eq!(variables[2].prompt, "kind");
+        assert!(variables[3].optional);
+    }
+
+    #[test]
+    fn test_instantiate_substitutes_and_applies_defaults() {
+        let template = Template::parse("meeting", MEETING).unwrap();
+        let note = template
+            .instantiate(
+                &values(&[("title", "Sync"), ("kind", "retro"), ("date", "")]),
+                date(2024, 3, 31),
+            )
+            .unwrap();
+
+        assert_eq!(
+            note,
+            "# Sync (retro)\n\nHeld on [[2024-03-31]]. {{unknown}}\n"
+        );
+    }
+
+    #[test]
+    fn test_instantiate_validates_values() {
+        let template = Template::parse("meeting", MEETING).unwrap();
+        let today = date(2024, 3, 31);
+
+        let missing = template.instantiate(&values(&[("kind", "retro")]), today);
+        assert!(matches!(missing, Err(TemplateError::MissingValue(name)) if name == "title"));
+
+        let bad_date = template.instantiate(
+            &values(&[("title", "x"), ("kind", "retro"), ("date", "31/03/2024")]),
+            today,
+        );
+        assert!(matches!(bad_date, Err(TemplateError::InvalidDate { .. })));
+
+        let bad_choice = template.instantiate(&values(&[("title", "x"), ("kind", "party")]), today);
+        assert!(matches!(
+            bad_choice,
+            Err(TemplateError::InvalidChoice { .. })
+        ));
+
+        let unknown = template.instantiate(
+            &values(&[("title", "x"), ("kind", "retro"), ("typo", "y")]),
+            today,
+        );
+        assert!(matches!(unknown, Err(TemplateError::UnknownVariable(name)) if name == "typo"));
+    }
+
+    #[test]
+    fn test_invalid_front_matter() {
+        for text in [
+            "---\nvariables:\n  - name: kind\n    type: choice\n---\n",
+            "---\nvariables:\n  - name: when\n    type: date\n    default: soon\n---\n",
+            "---\nvariables:\n  - name: a\n  - name: a\n---\n",
+            "---\nvariables:\n  - name: a\n    type: number\n---\n",
+        ] {
+            assert!(
+                matches!(
+                    Template::parse("t", text),
+                    Err(TemplateError::FrontMatter(_))
+                ),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_template_without_front_matter() {
+        let template = Template::parse("plain", "# {{title}}\n").unwrap();
+        assert!(template.variables().is_empty());
+        assert_eq!(
+            template
+                .instantiate(&BTreeMap::new(), date(2024, 1, 1))
+                .unwrap(),
+            "# {{title}}\n"
+        );
+    }
+
+    #[test]
+    fn test_list_load_and_create_note() {
+        let notes_dir = create_test_notes_dir();
+        let write = |path: &str, content: &str| {
+            io::write_file(RelativePath::new(path), notes_dir.path(), content).unwrap()
+        };
+        write("templates/meeting.md", MEETING);
+        write("templates/work/review.md", "Review\n");
+        write("existing.md", "keep me");
+
+        assert_eq!(
+            list_templates(notes_dir.path()).unwrap(),
+            vec!["meeting", "work/review"]
+        );
+
+        let template = Template::load(notes_dir.path(), "meeting").unwrap();
+        let answers = values(&[("title", "Plan"), ("kind", "planning")]);
+        let today = date(2024, 1, 2);
+        template
+            .create_note(
+                notes_dir.path(),
+                RelativePath::new("plan.md"),
+                &answers,
+                today,
+            )
+            .unwrap();
+        let created = io::read_file(RelativePath::new("plan.md"), notes_dir.path()).unwrap();
+        assert!(created.starts_with("# Plan (planning)"));
+
+        let overwrite = template.create_note(
+            notes_dir.path(),
+            RelativePath::new("existing.md"),
+            &answers,
+            today,
+        );
+        assert!(matches!(
+            overwrite,
+            Err(TemplateError::Io(IoError::FileExists(_)))
+        ));
+    }
+}
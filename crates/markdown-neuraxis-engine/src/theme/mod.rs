@@ -0,0 +1,269 @@
+//! # Themes
+//!
+//! One definition of the app's colors that every front end derives its
+//! styles from, so the desktop app and terminal UI agree and honour the same
+//! accessibility settings.
+//!
+//! A [`Theme`] fills the sixteen slots of the Solarized palette. Stylesheets
+//! are written against the light variant's roles (`base3` background,
+//! `base01` body text, `base1` borders); a dark theme puts the matching dark
+//! colors in those slots, as Solarized itself is designed to. High contrast
+//! pushes text and background to the extremes and deepens the accents.
+//! Reduced motion turns off CSS transitions and animations.
+
+/// A 24-bit color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    /// CSS hex form, e.g. `#fdf6e3`.
+    pub fn hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+
+    /// Move each channel `amount` (0.0 to 1.0) of the way towards `target`.
+    fn mix(self, target: Rgb, amount: f32) -> Rgb {
+        let channel = |from: u8, to: u8| {
+            (f32::from(from) + (f32::from(to) - f32::from(from)) * amount).round() as u8
+        };
+        Rgb(
+            channel(self.0, target.0),
+            channel(self.1, target.1),
+            channel(self.2, target.2),
+        )
+    }
+}
+
+const BLACK: Rgb = Rgb(0, 0, 0);
+const WHITE: Rgb = Rgb(255, 255, 255);
+
+/// Which color theme to use.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ThemeName {
+    #[default]
+    SolarizedLight,
+    SolarizedDark,
+}
+
+/// How strongly text stands out from the background.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Contrast {
+    #[default]
+    Normal,
+    High,
+}
+
+/// Colors for each Solarized slot, named by the light variant's roles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    /// Strongest text
+    pub base03: Rgb,
+    pub base02: Rgb,
+    /// Body text
+    pub base01: Rgb,
+    /// Secondary text
+    pub base00: Rgb,
+    pub base0: Rgb,
+    /// Borders and muted text
+    pub base1: Rgb,
+    /// Panels and highlighted rows
+    pub base2: Rgb,
+    /// Background
+    pub base3: Rgb,
+    pub yellow: Rgb,
+    pub orange: Rgb,
+    pub red: Rgb,
+    pub magenta: Rgb,
+    pub violet: Rgb,
+    pub blue: Rgb,
+    pub cyan: Rgb,
+    pub green: Rgb,
+}
+
+impl Palette {
+    /// Solarized light, as published.
+    pub const SOLARIZED_LIGHT: Palette = Palette {
+        base03: Rgb(0x00, 0x2b, 0x36),
+        base02: Rgb(0x07, 0x36, 0x42),
+        base01: Rgb(0x58, 0x6e, 0x75),
+        base00: Rgb(0x65, 0x7b, 0x83),
+        base0: Rgb(0x83, 0x94, 0x96),
+        base1: Rgb(0x93, 0xa1, 0xa1),
+        base2: Rgb(0xee, 0xe8, 0xd5),
+        base3: Rgb(0xfd, 0xf6, 0xe3),
+        yellow: Rgb(0xb5, 0x89, 0x00),
+        orange: Rgb(0xcb, 0x4b, 0x16),
+        red: Rgb(0xdc, 0x32, 0x2f),
+        magenta: Rgb(0xd3, 0x36, 0x82),
+        violet: Rgb(0x6c, 0x71, 0xc4),
+        blue: Rgb(0x26, 0x8b, 0xd2),
+        cyan: Rgb(0x2a, 0xa1, 0x98),
+        green: Rgb(0x85, 0x99, 0x00),
+    };
+
+    /// The same palette with the base tones mirrored, turning light into
+    /// dark.
+    fn mirrored(self) -> Palette {
+        Palette {
+            base03: self.base3,
+            base02: self.base2,
+            base01: self.base1,
+            base00: self.base0,
+            base0: self.base00,
+            base1: self.base01,
+            base2: self.base02,
+            base3: self.base03,
+            ..self
+        }
+    }
+
+    /// Text and borders moved one step closer to the text end, background
+    /// at the extreme, and accents moved towards the text end.
+    fn high_contrast(self, dark: bool) -> Palette {
+        let (ink, paper) = if dark { (WHITE, BLACK) } else { (BLACK, WHITE) };
+        let accent = |color: Rgb| color.mix(ink, 0.3);
+        Palette {
+            base03: ink,
+            base02: self.base03,
+            base01: self.base03,
+            base00: self.base02,
+            base0: self.base01,
+            base1: self.base00,
+            base2: self.base3,
+            base3: paper,
+            yellow: accent(self.yellow),
+            orange: accent(self.orange),
+            red: accent(self.red),
+            magenta: accent(self.magenta),
+            violet: accent(self.violet),
+            blue: accent(self.blue),
+            cyan: accent(self.cyan),
+            green: accent(self.green),
+        }
+    }
+
+    /// `(name, color)` for every slot, in Solarized order.
+    pub fn slots(&self) -> [(&'static str, Rgb); 16] {
+        [
+            ("base03", self.base03),
+            ("base02", self.base02),
+            ("base01", self.base01),
+            ("base00", self.base00),
+            ("base0", self.base0),
+            ("base1", self.base1),
+            ("base2", self.base2),
+            ("base3", self.base3),
+            ("yellow", self.yellow),
+            ("orange", self.orange),
+            ("red", self.red),
+            ("magenta", self.magenta),
+            ("violet", self.violet),
+            ("blue", self.blue),
+            ("cyan", self.cyan),
+            ("green", self.green),
+        ]
+    }
+}
+
+/// Colors and accessibility settings for the front ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub name: ThemeName,
+    pub contrast: Contrast,
+    /// Turn off transitions and animations
+    pub reduced_motion: bool,
+    pub palette: Palette,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::new(ThemeName::default(), Contrast::default(), false)
+    }
+}
+
+impl Theme {
+    pub fn new(name: ThemeName, contrast: Contrast, reduced_motion: bool) -> Self {
+        let dark = name == ThemeName::SolarizedDark;
+        let mut palette = Palette::SOLARIZED_LIGHT;
+        if dark {
+            palette = palette.mirrored();
+        }
+        if contrast == Contrast::High {
+            palette = palette.high_contrast(dark);
+        }
+        Self {
+            name,
+            contrast,
+            reduced_motion,
+            palette,
+        }
+    }
+
+    /// `(foreground, background)` for the selected row or block.
+    pub fn selection(&self) -> (Rgb, Rgb) {
+        match self.contrast {
+            Contrast::Normal => (self.palette.base03, self.palette.yellow),
+            // Inverted text reads better than any accent background
+            Contrast::High => (self.palette.base3, self.palette.base03),
+        }
+    }
+
+    /// Color for labels that should stand out, such as mark names.
+    pub fn accent(&self) -> Rgb {
+        self.palette.yellow
+    }
+
+    /// CSS declaring the palette as `--base03` … `--green` variables on
+    /// `:root`, plus rules disabling motion when asked. Include it after
+    /// the stylesheet so it takes precedence.
+    pub fn css(&self) -> String {
+        let mut css = String::from(":root {\n");
+        for (name, color) in self.palette.slots() {
+            css.push_str(&format!("    --{name}: {};\n", color.hex()));
+        }
+        css.push_str("}\n");
+        if self.reduced_motion {
+            css.push_str(
+                "*, *::before, *::after {\n    transition: none !important;\n    animation: none !important;\n    scroll-behavior: auto !important;\n}\n",
+            );
+        }
+        css
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_theme_is_solarized_light() {
+        let theme = Theme::default();
+        assert_eq!(theme.palette, Palette::SOLARIZED_LIGHT);
+        let css = theme.css();
+        assert!(css.starts_with(":root {\n    --base03: #002b36;\n"));
+        assert!(css.contains("    --base3: #fdf6e3;\n"));
+        assert!(!css.contains("transition"));
+    }
+
+    #[test]
+    fn test_dark_theme_mirrors_base_tones() {
+        let theme = Theme::new(ThemeName::SolarizedDark, Contrast::Normal, false);
+        assert_eq!(theme.palette.base3, Palette::SOLARIZED_LIGHT.base03);
+        assert_eq!(theme.palette.base01, Palette::SOLARIZED_LIGHT.base1);
+        assert_eq!(theme.palette.blue, Palette::SOLARIZED_LIGHT.blue);
+    }
+
+    #[test]
+    fn test_high_contrast_and_reduced_motion() {
+        let light = Theme::new(ThemeName::SolarizedLight, Contrast::High, true);
+        assert_eq!(light.palette.base3, WHITE);
+        assert_eq!(light.palette.base01, Palette::SOLARIZED_LIGHT.base03);
+        assert_eq!(light.selection(), (WHITE, BLACK));
+        assert!(light.css().contains("transition: none !important"));
+
+        let dark = Theme::new(ThemeName::SolarizedDark, Contrast::High, false);
+        assert_eq!(dark.palette.base3, BLACK);
+        assert_eq!(dark.palette.base01, Palette::SOLARIZED_LIGHT.base3);
+        assert_eq!(Rgb(0xb5, 0x89, 0x00).mix(WHITE, 0.5).hex(), "#dac480");
+    }
+}
@@ -0,0 +1,391 @@
+//! # Note Titles
+//!
+//! A note's title is derived in one place so every view agrees on it. The
+//! precedence is:
+//!
+//! 1. Front matter `title` field (YAML or TOML)
+//! 2. First level-1 ATX heading
+//! 3. File name without the `.md` extension
+//!
+//! A note can also be known by other names, listed in front matter
+//! `aliases` (or `alias`) or a Logseq `alias::` property, so that
+//! `[[Alt Name]]` finds it. See [`note_aliases`].
+//!
+//! [`TitleIndex`] caches derived titles and aliases for the whole vault and
+//! is built in the background like any other [`VaultIndex`]. It resolves
+//! wiki-link targets, aliases included, and lists every name a note goes
+//! by for link autocompletion.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use markdown_neuraxis_syntax::heading::{heading_level, heading_text};
+use markdown_neuraxis_syntax::{SyntaxKind, SyntaxNode, parse, visit};
+use relative_path::{RelativePath, RelativePathBuf};
+
+use crate::editing::frontmatter::parse_frontmatter;
+use crate::io::{self, IoError};
+use crate::links::target_matches_note;
+use crate::models::MarkdownFile;
+use crate::vault::{IndexKind, SnapshotReader, SnapshotWriter, VaultIndex};
+
+/// Derive a note's title from its path and source.
+pub fn derive_title(path: &RelativePath, source: &str) -> String {
+    content_title(source).unwrap_or_else(|| filename_title(path))
+}
+
+/// Title from the note content alone (front matter, then first H1).
+pub fn content_title(source: &str) -> Option<String> {
+    let tree = parse(source);
+
+    let frontmatter = tree
+        .children()
+        .find(|node| node.kind() == SyntaxKind::FRONTMATTER)
+        .and_then(|node| frontmatter_title(&node));
+    if frontmatter.is_some() {
+        return frontmatter;
+    }
+
+    visit::nodes_of_kind(&tree, SyntaxKind::HEADING).find_map(|node| {
+        if heading_level(&node)? != 1 {
+            return None;
+        }
+        let title = heading_text(&node)?;
+        (!title.is_empty()).then_some(title)
+    })
+}
+
+/// Title derived from the file name.
+pub fn filename_title(path: &RelativePath) -> String {
+    MarkdownFile::new(path.to_relative_path_buf())
+        .display_name()
+        .to_string()
+}
+
+/// Read the `title` field from a YAML or TOML front matter block.
+///
+/// Only string values are used; surrounding whitespace is removed.
+fn frontmatter_title(frontmatter: &SyntaxNode) -> Option<String> {
+    let fields = parse_frontmatter(frontmatter)?;
+    let title = fields.get("title")?.as_str()?.trim();
+    (!title.is_empty()).then(|| title.to_string())
+}
+
+/// Other names for a note, from front matter `aliases` or `alias` (a list
+/// or a single name) and Logseq `alias::` properties (comma-separated,
+/// optionally as `[[links]]`), in the order written and without repeats.
+pub fn note_aliases(source: &str) -> Vec<String> {
+    let tree = parse(source);
+    let mut aliases: Vec<String> = Vec::new();
+    let mut push = |name: &str| {
+        let name = name.trim();
+        let name = name
+            .strip_prefix("[[")
+            .and_then(|n| n.strip_suffix("]]"))
+            .unwrap_or(name)
+            .trim();
+        if !name.is_empty() && !aliases.iter().any(|a| a.eq_ignore_ascii_case(name)) {
+            aliases.push(name.to_string());
+        }
+    };
+
+    if let Some(fields) = tree
+        .children()
+        .find(|node| node.kind() == SyntaxKind::FRONTMATTER)
+        .and_then(|node| parse_frontmatter(&node))
+    {
+        for key in ["aliases", "alias"] {
+            match fields.get(key) {
+                Some(toml::Value::String(name)) => push(name),
+                Some(toml::Value::Array(names)) => {
+                    names.iter().filter_map(|n| n.as_str()).for_each(&mut push)
+                }
+                _ => {}
+            }
+        }
+    }
+    for node in visit::nodes_of_kind(&tree, SyntaxKind::PROPERTY) {
+        let text = node.text().to_string();
+        if let Some((key, value)) = text.split_once("::")
+            && key.trim().eq_ignore_ascii_case("alias")
+        {
+            value.split(',').for_each(&mut push);
+        }
+    }
+    aliases
+}
+
+/// A name a note goes by, from [`TitleIndex::titles`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteName {
+    /// Title or alias
+    pub name: String,
+    pub path: RelativePathBuf,
+    /// Whether `name` is an alias rather than the note's title
+    pub is_alias: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TitleEntry {
+    title: String,
+    aliases: Vec<String>,
+}
+
+/// Cached titles and aliases for every note in the vault.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TitleIndex {
+    titles: BTreeMap<RelativePathBuf, TitleEntry>,
+}
+
+impl TitleIndex {
+    /// Build the index from every note under `notes_root`. Notes that
+    /// can't be read are skipped.
+    pub fn scan(notes_root: &Path) -> Result<Self, IoError> {
+        let mut index = Self::default();
+        for path in io::scan_markdown_relative_paths(notes_root)? {
+            if let Ok(source) = io::read_file(&path, notes_root) {
+                index.update_note(&path, &source);
+            }
+        }
+        Ok(index)
+    }
+
+    /// Re-derive a single note's title and aliases from its current source.
+    pub fn update_note(&mut self, path: &RelativePath, source: &str) {
+        let entry = TitleEntry {
+            title: derive_title(path, source),
+            aliases: note_aliases(source),
+        };
+        self.titles.insert(path.to_relative_path_buf(), entry);
+    }
+
+    /// Drop a note from the index (e.g. after deletion).
+    pub fn remove_note(&mut self, path: &RelativePath) {
+        self.titles.remove(path);
+    }
+
+    /// Title for a note, falling back to the file name if it is not indexed.
+    pub fn note_title(&self, path: &RelativePath) -> String {
+        self.titles
+            .get(path)
+            .map(|entry| entry.title.clone())
+            .unwrap_or_else(|| filename_title(path))
+    }
+
+    /// A note's aliases, empty if it has none or is not indexed.
+    pub fn aliases(&self, path: &RelativePath) -> &[String] {
+        self.titles
+            .get(path)
+            .map_or(&[], |entry| entry.aliases.as_slice())
+    }
+
+    /// Every title and alias with the note it names, sorted by name
+    /// (case-insensitively), for link autocompletion.
+    pub fn titles(&self) -> Vec<NoteName> {
+        let mut names: Vec<NoteName> = self
+            .titles
+            .iter()
+            .flat_map(|(path, entry)| {
+                let title = NoteName {
+                    name: entry.title.clone(),
+                    path: path.clone(),
+                    is_alias: false,
+                };
+                let aliases = entry.aliases.iter().map(|alias| NoteName {
+                    name: alias.clone(),
+                    path: path.clone(),
+                    is_alias: true,
+                });
+                std::iter::once(title).chain(aliases)
+            })
+            .collect();
+        names.sort_by_cached_key(|n| (n.name.to_lowercase(), n.path.clone()));
+        names
+    }
+
+    /// The note a wiki-link target refers to: one whose path matches (see
+    /// [`target_matches_note`]), otherwise one with the target as an alias
+    /// (case-insensitively). Ties go to the first path.
+    pub fn resolve(&self, target: &str) -> Option<&RelativePath> {
+        let target = target.trim();
+        if target.is_empty() {
+            return None;
+        }
+        self.titles
+            .keys()
+            .find(|path| target_matches_note(target, path))
+            .or_else(|| {
+                self.titles
+                    .iter()
+                    .find(|(_, entry)| entry.aliases.iter().any(|a| a.eq_ignore_ascii_case(target)))
+                    .map(|(path, _)| path)
+            })
+            .map(|path| path.as_relative_path())
+    }
+
+    /// Number of indexed notes.
+    pub fn len(&self) -> usize {
+        self.titles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.titles.is_empty()
+    }
+}
+
+impl VaultIndex for TitleIndex {
+    fn kind(&self) -> IndexKind {
+        IndexKind::Titles
+    }
+
+    fn add_note(&mut self, path: &RelativePath, source: &str) {
+        self.update_note(path, source);
+    }
+
+    fn remove_note(&mut self, path: &RelativePath) {
+        self.remove_note(path);
+    }
+
+    fn snapshot_note(&self, path: &RelativePath) -> Option<Vec<u8>> {
+        let entry = self.titles.get(path)?;
+        let mut out = SnapshotWriter::default();
+        out.str(&entry.title);
+        out.u64(entry.aliases.len() as u64);
+        for alias in &entry.aliases {
+            out.str(alias);
+        }
+        Some(out.into_bytes())
+    }
+
+    fn restore_note(&mut self, path: &RelativePath, bytes: &[u8]) -> bool {
+        let mut input = SnapshotReader::new(bytes);
+        let entry = (|| {
+            let title = input.str()?;
+            let count = input.u64()?;
+            let aliases = (0..count).map(|_| input.str()).collect::<Option<_>>()?;
+            Some(TitleEntry { title, aliases })
+        })();
+        match entry {
+            Some(entry) if input.is_empty() => {
+                self.titles.insert(path.to_relative_path_buf(), entry);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frontmatter_title_wins() {
+        let source = "---\ntitle: \"From Front Matter\"\n---\n# Heading\n";
+        assert_eq!(
+            derive_title(RelativePath::new("file.md"), source),
+            "From Front Matter"
+        );
+    }
+
+    #[test]
+    fn test_toml_frontmatter_title() {
+        let source = "+++\ntitle = \"From TOML\"\n+++\n# Heading\n";
+        assert_eq!(
+            derive_title(RelativePath::new("file.md"), source),
+            "From TOML"
+        );
+    }
+
+    #[test]
+    fn test_first_h1_used_without_frontmatter() {
+        let source = "Intro\n\n## Not this\n\n# Main Title ##\n\n# Second\n";
+        assert_eq!(
+            derive_title(RelativePath::new("file.md"), source),
+            "Main Title"
+        );
+    }
+
+    #[test]
+    fn test_filename_fallback() {
+        let source = "---\ntags: [a]\n---\njust text\n";
+        assert_eq!(
+            derive_title(RelativePath::new("notes/My Note.md"), source),
+            "My Note"
+        );
+    }
+
+    #[test]
+    fn test_heading_in_code_block_ignored() {
+        let source = "```\n# not a title\n```\n";
+        assert_eq!(derive_title(RelativePath::new("code.md"), source), "code");
+    }
+
+    #[test]
+    fn test_title_index_falls_back_for_unknown_notes() {
+        let mut index = TitleIndex::default();
+        index.update_note(RelativePath::new("a.md"), "# Alpha\n");
+        assert_eq!(index.note_title(RelativePath::new("a.md")), "Alpha");
+        assert_eq!(index.note_title(RelativePath::new("b.md")), "b");
+
+        index.remove_note(RelativePath::new("a.md"));
+        assert_eq!(index.note_title(RelativePath::new("a.md")), "a");
+    }
+
+    #[test]
+    fn test_note_aliases() {
+        assert_eq!(
+            note_aliases("---\naliases: [Alt Name, \"Other\"]\nalias: alt name\n---\n# Note\n"),
+            ["Alt Name", "Other"]
+        );
+        assert_eq!(note_aliases("+++\naliases = \"One\"\n+++\n"), ["One"]);
+        assert_eq!(
+            note_aliases("alias:: [[Big Plan]], roadmap\n\n- item\n"),
+            ["Big Plan", "roadmap"]
+        );
+        assert!(note_aliases("# Plain\n\naliases: not front matter\n").is_empty());
+    }
+
+    #[test]
+    fn test_resolve_and_list_titles_with_aliases() {
+        let mut index = TitleIndex::default();
+        index.update_note(
+            RelativePath::new("projects/plan.md"),
+            "---\ntitle: The Plan\naliases: [Roadmap]\n---\n",
+        );
+        index.update_note(RelativePath::new("roadmap.md"), "# Old roadmap\n");
+        index.update_note(RelativePath::new("ideas.md"), "alias:: Brainstorm\n");
+
+        assert_eq!(
+            index.resolve("Projects/Plan"),
+            Some(RelativePath::new("projects/plan.md"))
+        );
+        assert_eq!(
+            index.resolve("brainstorm"),
+            Some(RelativePath::new("ideas.md"))
+        );
+        // A note named like the target wins over an alias
+        assert_eq!(
+            index.resolve("Roadmap"),
+            Some(RelativePath::new("roadmap.md"))
+        );
+        assert_eq!(index.resolve("Missing"), None);
+        assert_eq!(index.aliases(RelativePath::new("ideas.md")), ["Brainstorm"]);
+
+        let titles = index.titles();
+        let names: Vec<(&str, &str, bool)> = titles
+            .iter()
+            .map(|n| (n.name.as_str(), n.path.as_str(), n.is_alias))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("Brainstorm", "ideas.md", true),
+                ("ideas", "ideas.md", false),
+                ("Old roadmap", "roadmap.md", false),
+                ("Roadmap", "projects/plan.md", true),
+                ("The Plan", "projects/plan.md", false),
+            ]
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! # Tracing
+//!
+//! The engine reports what it is doing as [`tracing`] spans, so slow
+//! operations can be found in a trace rather than guessed at:
+//!
+//! - `parse`: building a [`Document`](crate::editing::Document) from bytes
+//! - `apply`, `undo`, `redo`: edits to a document
+//! - `snapshot`: building a snapshot for rendering
+//! - `io.read`, `io.write`: note files read and written
+//! - `index.open`, `index.rebuild`, `index.update`: vault indexing
+//!
+//! Each span ends with a `DEBUG` event carrying `elapsed_us`, so timings
+//! also show up in plain log output. Spans cost next to nothing unless a
+//! subscriber is installed.
+//!
+//! With the `chrome-trace` feature, [`start_chrome_trace`] writes the spans
+//! to a file that Perfetto or `chrome://tracing` can open, e.g. to diagnose
+//! a slowdown reported from the Android app.
+
+use std::time::Instant;
+
+use tracing::Span;
+use tracing::span::EnteredSpan;
+
+/// A span entered until dropped, when it logs how long it took.
+pub(crate) struct Timed {
+    start: Instant,
+    _span: EnteredSpan,
+}
+
+impl Drop for Timed {
+    fn drop(&mut self) {
+        // Still inside the span: fields drop after this runs
+        tracing::debug!(
+            elapsed_us = self.start.elapsed().as_micros() as u64,
+            "finished"
+        );
+    }
+}
+
+/// Enter `span` for as long as the result is kept.
+pub(crate) fn timed(span: Span) -> Timed {
+    Timed {
+        start: Instant::now(),
+        _span: span.entered(),
+    }
+}
+
+/// Writes spans to a Chrome trace file until dropped.
+#[cfg(feature = "chrome-trace")]
+pub struct ChromeTrace {
+    _guard: tracing_chrome::FlushGuard,
+}
+
+/// Why tracing could not start.
+#[cfg(feature = "chrome-trace")]
+#[derive(Debug, thiserror::Error)]
+pub enum TraceError {
+    #[error("a tracing subscriber is already installed")]
+    AlreadyInstalled,
+}
+
+/// Record every span from now on to the Chrome trace file at `path`, which
+/// is complete once the returned [`ChromeTrace`] is dropped.
+///
+/// This installs the process-wide tracing subscriber, so it fails if the
+/// front-end already installed one.
+#[cfg(feature = "chrome-trace")]
+pub fn start_chrome_trace(path: &std::path::Path) -> Result<ChromeTrace, TraceError> {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let (layer, guard) = tracing_chrome::ChromeLayerBuilder::new()
+        .file(path)
+        .include_args(true)
+        .build();
+    let subscriber = tracing_subscriber::registry().with(layer);
+    tracing::subscriber::set_global_default(subscriber)
+        .map_err(|_| TraceError::AlreadyInstalled)?;
+    Ok(ChromeTrace { _guard: guard })
+}
+
+#[cfg(all(test, feature = "chrome-trace"))]
+mod tests {
+    use super::*;
+    use crate::editing::Document;
+    use crate::tests::create_test_notes_dir;
+
+    #[test]
+    fn test_chrome_trace_records_spans() {
+        let dir = create_test_notes_dir();
+        let path = dir.path().join("trace.json");
+
+        let trace = start_chrome_trace(&path).unwrap();
+        let doc = Document::from_bytes(b"# Title\n").unwrap();
+        doc.snapshot();
+        assert!(matches!(
+            start_chrome_trace(&dir.path().join("second.json")),
+            Err(TraceError::AlreadyInstalled)
+        ));
+        drop(trace);
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.contains("\"parse\""));
+        assert!(written.contains("\"snapshot\""));
+    }
+}
@@ -0,0 +1,375 @@
+//! # Appending to Notes
+//!
+//! Capture flows, scripts and the HTTP API add Markdown to existing notes.
+//! Plain string concatenation glues the new text onto the last paragraph or
+//! breaks a list in two, so [`Vault::append_to_note`] merges by block:
+//!
+//! - the fragment is separated from its neighbours by one blank line
+//! - a list fragment next to a list of the same kind continues it, with no
+//!   blank line and at the same indentation
+//! - the note's line endings are kept
+//!
+//! [`AppendPosition::Top`] inserts after any front matter;
+//! [`AppendPosition::UnderHeading`] inserts at the end of that heading's
+//! section, adding the heading at the bottom of the note if it is missing.
+//!
+//! A missing daily note is started from the journal template, as
+//! [`open_daily_note`](crate::journal::open_daily_note) would.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use markdown_neuraxis_syntax::heading::{heading_level, heading_text};
+use markdown_neuraxis_syntax::{SyntaxKind, SyntaxNode, parse};
+use relative_path::RelativePath;
+
+use crate::io::{self, IoError};
+use crate::journal::{JournalError, daily_note_content, daily_note_date};
+use crate::vault::OpenMode;
+
+/// Where [`Vault::append_to_note`] puts new content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppendPosition {
+    /// Start of the note, after any front matter
+    Top,
+    /// End of the note
+    Bottom,
+    /// End of the section under the heading with this text (matched
+    /// case-insensitively)
+    UnderHeading(String),
+}
+
+/// A notes folder that content can be written into.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vault {
+    notes_root: PathBuf,
+    mode: OpenMode,
+}
+
+impl Vault {
+    pub fn new(notes_root: impl Into<PathBuf>) -> Self {
+        Self {
+            notes_root: notes_root.into(),
+            mode: OpenMode::default(),
+        }
+    }
+
+    /// This vault opened in `mode`; a read-only vault refuses writes with
+    /// [`IoError::ReadOnly`].
+    pub fn with_mode(self, mode: OpenMode) -> Self {
+        Self { mode, ..self }
+    }
+
+    pub fn notes_root(&self) -> &Path {
+        &self.notes_root
+    }
+
+    pub fn mode(&self) -> OpenMode {
+        self.mode
+    }
+
+    /// Merge `markdown` into the note at `position` and save it, creating
+    /// the note if it doesn't exist yet.
+    pub fn append_to_note(
+        &self,
+        path: &RelativePath,
+        markdown: &str,
+        position: &AppendPosition,
+    ) -> Result<(), JournalError> {
+        self.mode.check_writable(&self.notes_root)?;
+        let source = match io::read_file(path, &self.notes_root) {
+            Ok(source) => source,
+            Err(IoError::NotFound(_)) => match daily_note_date(path) {
+                Some(date) => daily_note_content(&self.notes_root, date)?,
+                None => String::new(),
+            },
+            Err(e) => return Err(e.into()),
+        };
+        let merged = append_markdown(&source, markdown, position);
+        Ok(io::write_file(path, &self.notes_root, &merged)?)
+    }
+}
+
+/// `source` with `fragment` merged in at `position`.
+pub fn append_markdown(source: &str, fragment: &str, position: &AppendPosition) -> String {
+    let newline = if source.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let source = source.replace("\r\n", "\n");
+    let fragment = strip_blank_lines(&fragment.replace("\r\n", "\n"))
+        .trim_end()
+        .to_string();
+    if fragment.is_empty() {
+        return source.replace('\n', newline);
+    }
+    let root = parse(&source);
+
+    let (at, fragment) = match position {
+        AppendPosition::Top => {
+            let at = root
+                .children()
+                .find(|node| node.kind() == SyntaxKind::FRONTMATTER)
+                .map_or(0, |node| node_range(&node).end);
+            (at, fragment)
+        }
+        AppendPosition::Bottom => (source.len(), fragment),
+        AppendPosition::UnderHeading(title) => match section_end(&root, &source, title) {
+            Some(end) => (end, fragment),
+            None => (source.len(), format!("## {title}\n\n{fragment}")),
+        },
+    };
+    insert_block(&source, &root, at, &fragment).replace('\n', newline)
+}
+
+/// Insert a block at `at`, fixing up the blank lines around it.
+fn insert_block(source: &str, root: &SyntaxNode, at: usize, fragment: &str) -> String {
+    let before = source[..at].trim_end();
+    let after = strip_blank_lines(&source[at..]);
+
+    let fragment_root = parse(fragment);
+    let first = fragment_root.children().next().map(|node| node.kind());
+    let last = fragment_root.children().last().map(|node| node.kind());
+    let previous = root
+        .children()
+        .filter(|node| node_range(node).start < before.len())
+        .last();
+    let next = parse(after).children().next().map(|node| node.kind());
+
+    let mut fragment = fragment.to_string();
+    let mut before_gap = "\n\n";
+    if let Some(previous) = previous
+        && is_list(previous.kind())
+        && first == Some(previous.kind())
+    {
+        before_gap = "\n";
+        // Continue the list at its own indentation
+        let list_start = node_range(&previous).start;
+        let line = &source[source[..list_start].rfind('\n').map_or(0, |i| i + 1)..];
+        let indent = &line[..line.len() - line.trim_start().len()];
+        if !indent.is_empty() {
+            fragment = fragment
+                .lines()
+                .map(|line| {
+                    if line.is_empty() {
+                        String::new()
+                    } else {
+                        format!("{indent}{line}")
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
+    }
+    let after_gap = match (last, next) {
+        (Some(last), Some(next)) if is_list(last) && last == next => "\n",
+        _ => "\n\n",
+    };
+
+    let mut out = String::new();
+    if !before.is_empty() {
+        out.push_str(before);
+        out.push_str(before_gap);
+    }
+    out.push_str(&fragment);
+    if after.is_empty() {
+        out.push('\n');
+    } else {
+        out.push_str(after_gap);
+        out.push_str(after);
+    }
+    out
+}
+
+fn is_list(kind: SyntaxKind) -> bool {
+    matches!(kind, SyntaxKind::ORDERED_LIST | SyntaxKind::UNORDERED_LIST)
+}
+
+fn node_range(node: &SyntaxNode) -> Range<usize> {
+    let range = node.text_range();
+    range.start().into()..range.end().into()
+}
+
+/// Drop leading lines that are empty or whitespace only.
+fn strip_blank_lines(text: &str) -> &str {
+    let mut rest = text;
+    while let Some((line, tail)) = rest.split_once('\n') {
+        if !line.trim().is_empty() {
+            break;
+        }
+        rest = tail;
+    }
+    if rest.trim().is_empty() { "" } else { rest }
+}
+
+/// End of the section under the heading titled `title`: the start of the
+/// next heading of the same or higher level, or the end of the note.
+fn section_end(root: &SyntaxNode, source: &str, title: &str) -> Option<usize> {
+    let headings: Vec<(Range<usize>, usize, String)> = root
+        .children()
+        .filter_map(|node| {
+            let level = usize::from(heading_level(&node)?);
+            let text = heading_text(&node)?;
+            Some((node_range(&node), level, text.to_lowercase()))
+        })
+        .collect();
+
+    let title = title.trim().to_lowercase();
+    let i = headings.iter().position(|(_, _, text)| *text == title)?;
+    let level = headings[i].1;
+    Some(
+        headings[i + 1..]
+            .iter()
+            .find(|(_, other, _)| *other <= level)
+            .map_or(source.len(), |(range, _, _)| range.start),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{create_test_file, create_test_notes_dir};
+
+    fn append(source: &str, fragment: &str, position: AppendPosition) -> String {
+        append_markdown(source, fragment, &position)
+    }
+
+    #[test]
+    fn test_bottom_separates_blocks() {
+        for (source, expected) in [
+            ("", "New para\n"),
+            ("Last para", "Last para\n\nNew para\n"),
+            ("Last para\n", "Last para\n\nNew para\n"),
+            ("Last para\n\n\n\n", "Last para\n\nNew para\n"),
+        ] {
+            assert_eq!(
+                append(source, "\n\nNew para\n\n", AppendPosition::Bottom),
+                expected,
+                "{source:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_list_fragment_continues_list() {
+        assert_eq!(
+            append(
+                "# Log\n\n- one\n- two\n",
+                "- three\n",
+                AppendPosition::Bottom
+            ),
+            "# Log\n\n- one\n- two\n- three\n"
+        );
+        assert_eq!(
+            append("1. one\n", "- other\n", AppendPosition::Bottom),
+            "1. one\n\n- other\n"
+        );
+        assert_eq!(
+            append("> - a\n\n", "- b", AppendPosition::Bottom),
+            "> - a\n\n- b\n"
+        );
+    }
+
+    #[test]
+    fn test_top_goes_after_front_matter() {
+        assert_eq!(
+            append("---\ntags: x\n---\n# Note\n", "Intro", AppendPosition::Top),
+            "---\ntags: x\n---\n\nIntro\n\n# Note\n"
+        );
+        assert_eq!(append("- b\n", "- a", AppendPosition::Top), "- a\n- b\n");
+    }
+
+    #[test]
+    fn test_under_heading() {
+        let source = "# Day\n\n## Tasks\n\n- [ ] one\n\n### Sub\n\nx\n\n## Notes\n\nText\n";
+        assert_eq!(
+            append(
+                source,
+                "- [ ] two",
+                AppendPosition::UnderHeading("tasks".to_string())
+            ),
+            "# Day\n\n## Tasks\n\n- [ ] one\n\n### Sub\n\nx\n\n- [ ] two\n\n## Notes\n\nText\n"
+        );
+        assert_eq!(
+            append(
+                "# Day\n",
+                "Idea",
+                AppendPosition::UnderHeading("Inbox".to_string())
+            ),
+            "# Day\n\n## Inbox\n\nIdea\n"
+        );
+    }
+
+    #[test]
+    fn test_keeps_crlf() {
+        assert_eq!(
+            append("- a\r\n\r\nEnd\r\n", "Mid", AppendPosition::Bottom),
+            "- a\r\n\r\nEnd\r\n\r\nMid\r\n"
+        );
+    }
+
+    #[test]
+    fn test_append_to_note_saves_and_creates() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "inbox.md", "- first\n");
+        let vault = Vault::new(notes_dir.path());
+
+        vault
+            .append_to_note(
+                RelativePath::new("inbox.md"),
+                "- second",
+                &AppendPosition::Bottom,
+            )
+            .unwrap();
+        vault
+            .append_to_note(
+                RelativePath::new("new/capture.md"),
+                "Hi",
+                &AppendPosition::Top,
+            )
+            .unwrap();
+
+        let read = |path| io::read_file(RelativePath::new(path), notes_dir.path()).unwrap();
+        assert_eq!(read("inbox.md"), "- first\n- second\n");
+        assert_eq!(read("new/capture.md"), "Hi\n");
+    }
+
+    #[test]
+    fn test_read_only_vault_refuses_append() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "inbox.md", "- first\n");
+        let vault = Vault::new(notes_dir.path()).with_mode(OpenMode::ReadOnly);
+
+        let result = vault.append_to_note(
+            RelativePath::new("inbox.md"),
+            "- second",
+            &AppendPosition::Bottom,
+        );
+        assert!(matches!(
+            result,
+            Err(JournalError::Io(IoError::ReadOnly(_)))
+        ));
+        assert_eq!(
+            io::read_file(RelativePath::new("inbox.md"), notes_dir.path()).unwrap(),
+            "- first\n"
+        );
+    }
+
+    #[test]
+    fn test_append_to_missing_daily_note_uses_template() {
+        let notes_dir = create_test_notes_dir();
+        std::fs::create_dir(notes_dir.path().join("templates")).unwrap();
+        create_test_file(&notes_dir, "templates/journal.md", "## Log\n");
+        let vault = Vault::new(notes_dir.path());
+
+        let path = RelativePath::new("journal/2024_03_31.md");
+        vault
+            .append_to_note(path, "- woke up", &AppendPosition::Bottom)
+            .unwrap();
+        assert_eq!(
+            io::read_file(path, notes_dir.path()).unwrap(),
+            "## Log\n\n- woke up\n"
+        );
+    }
+}
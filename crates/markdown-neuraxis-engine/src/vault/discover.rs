@@ -0,0 +1,69 @@
+//! # Finding the Vault from a Working Directory
+//!
+//! Like git finding its repository, [`Vault::discover`] walks up from a
+//! directory to the nearest folder marking a notes root, so the apps can be
+//! started anywhere inside a vault without a path argument or config.
+//!
+//! A folder is a notes root if it contains [`VAULT_MARKER`] (an empty file
+//! or folder created to mark it) or the `.neuraxis` folder the apps keep
+//! vault data in (bookmarks, the index snapshot, the trash).
+
+use std::path::Path;
+
+use crate::vault::Vault;
+
+/// File or folder marking a notes root.
+pub const VAULT_MARKER: &str = ".mdnx";
+
+/// Folder the apps keep per-vault data in, which also marks a notes root.
+const DATA_DIR: &str = ".neuraxis";
+
+impl Vault {
+    /// The vault containing `dir`: the nearest of `dir` and its parents that
+    /// holds a [`VAULT_MARKER`] or `.neuraxis` folder, or `None` if none do.
+    pub fn discover(dir: &Path) -> Option<Self> {
+        dir.ancestors()
+            .find(|ancestor| {
+                ancestor.join(VAULT_MARKER).exists() || ancestor.join(DATA_DIR).is_dir()
+            })
+            .map(Self::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{create_test_file, create_test_notes_dir};
+
+    #[test]
+    fn test_discover_walks_up_to_marker() {
+        let notes_dir = create_test_notes_dir();
+        let root = notes_dir.path();
+        create_test_file(&notes_dir, VAULT_MARKER, "");
+        std::fs::create_dir_all(root.join("projects/garden")).unwrap();
+
+        let vault = Vault::discover(&root.join("projects/garden")).unwrap();
+        assert_eq!(vault.notes_root(), root);
+        assert_eq!(Vault::discover(root).unwrap().notes_root(), root);
+    }
+
+    #[test]
+    fn test_discover_uses_data_folder_and_nearest_root() {
+        let notes_dir = create_test_notes_dir();
+        let root = notes_dir.path();
+        create_test_file(&notes_dir, VAULT_MARKER, "");
+        std::fs::create_dir_all(root.join("nested/.neuraxis")).unwrap();
+        std::fs::create_dir_all(root.join("nested/inner")).unwrap();
+
+        let vault = Vault::discover(&root.join("nested/inner")).unwrap();
+        assert_eq!(vault.notes_root(), root.join("nested"));
+    }
+
+    #[test]
+    fn test_discover_without_marker() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "note.md", "# Note\n");
+        // Temp folders have no vault above them
+        assert_eq!(Vault::discover(notes_dir.path()), None);
+    }
+}
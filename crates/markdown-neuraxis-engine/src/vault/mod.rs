@@ -0,0 +1,398 @@
+//! # Phased Vault Opening
+//!
+//! Opening a vault used to mean doing all the work up front before the UI
+//! could show anything. Vault opening is now split into phases:
+//!
+//! 1. **File tree** - scanned synchronously; enough to render the sidebar
+//! 2. **Indices** - built together on a background thread from the notes
+//!    that scan found, each note read once and fed to every index
+//!
+//! Frontends call [`open_vault`], render the returned [`FileTree`]
+//! immediately, and [`VaultOpening::poll`] for [`VaultEvent`]s to enable
+//! features (backlinks, search, ...) as their indices warm up.
+//!
+//! Indices are shared as `Arc<RwLock<_>>` so the frontend keeps its handle
+//! while the background thread fills it in. [`Readiness`] says when an
+//! index is complete and safe to query.
+//!
+//! Notes unchanged since the last [`save_index_snapshot`] are restored from
+//! the snapshot instead of being read and parsed again, so large vaults are
+//! ready almost immediately.
+//!
+//! Once open, an [`IndexWorker`] keeps the same indices current as notes
+//! change, off the UI thread, and [`Vault::append_to_note`] merges new
+//! content into notes for capture flows and scripts. [`Vault::discover`]
+//! finds the vault a directory is in, and an [`OpenMode`] keeps shared
+//! vaults from being changed.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, RwLock};
+use std::thread::JoinHandle;
+
+use relative_path::{RelativePath, RelativePathBuf};
+
+use crate::io::{self, IoError, ScanOptions};
+use crate::models::FileTree;
+use crate::trace::timed;
+
+mod append;
+mod discover;
+mod mode;
+mod snapshot;
+mod worker;
+
+pub use append::{AppendPosition, Vault, append_markdown};
+pub use discover::VAULT_MARKER;
+pub use mode::OpenMode;
+pub use snapshot::{
+    CachePolicy, INDEX_SNAPSHOT_PATH, IndexSnapshot, SnapshotReader, SnapshotWriter,
+    save_index_snapshot, save_index_snapshot_with,
+};
+pub use worker::{IndexUpdate, IndexWorker, ParsedNote, UPDATE_SLICE_BUDGET};
+
+/// The kinds of vault-wide index built in the background.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum IndexKind {
+    /// Outgoing wiki-links per note
+    Links,
+    /// Derived note titles
+    Titles,
+    /// Incoming wiki-links per note
+    Backlinks,
+    /// Words for full-text search
+    Search,
+    /// Tags per note
+    Tags,
+    /// Daily notes and dates mentioned in notes
+    Calendar,
+}
+
+/// An index built from the content of every note in the vault.
+pub trait VaultIndex: Send + Sync {
+    /// Which kind of index this is (used for readiness reporting)
+    fn kind(&self) -> IndexKind;
+
+    /// Add (or replace) a single note's contribution to the index
+    fn add_note(&mut self, path: &RelativePath, source: &str);
+
+    /// Drop a note's contribution (e.g. after it is deleted or moved)
+    fn remove_note(&mut self, path: &RelativePath);
+
+    /// Encode a note's contribution for the [`IndexSnapshot`], or `None`
+    /// if it isn't indexed or this index isn't snapshotted
+    fn snapshot_note(&self, _path: &RelativePath) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Restore a note's contribution from [`snapshot_note`](Self::snapshot_note)
+    /// bytes; `false` if they can't be decoded, and the note is read instead
+    fn restore_note(&mut self, _path: &RelativePath, _bytes: &[u8]) -> bool {
+        false
+    }
+}
+
+/// A shared handle to an index being built in the background.
+pub type SharedIndex = Arc<RwLock<dyn VaultIndex>>;
+
+/// Progress notifications from background indexing.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VaultEvent {
+    /// An index has processed `done` of `total` notes
+    IndexProgress {
+        kind: IndexKind,
+        done: usize,
+        total: usize,
+    },
+    /// An index is complete and safe to query
+    IndexReady(IndexKind),
+    /// A note could not be read while indexing; indexing continues
+    NoteSkipped {
+        path: RelativePathBuf,
+        reason: String,
+    },
+    /// All requested indices have finished
+    Finished,
+    /// A single note's change has been applied to every index
+    NoteIndexed(RelativePathBuf),
+    /// A rebuild was cancelled before finishing
+    Cancelled,
+}
+
+/// Which parts of the vault are ready for use.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Readiness {
+    /// Whether the file tree has been built
+    pub file_tree: bool,
+    ready: BTreeSet<IndexKind>,
+    finished: bool,
+}
+
+impl Readiness {
+    /// Whether the given index is complete.
+    pub fn is_ready(&self, kind: IndexKind) -> bool {
+        self.ready.contains(&kind)
+    }
+
+    /// Whether all background indexing has finished.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Update readiness from an event.
+    pub fn apply(&mut self, event: &VaultEvent) {
+        match event {
+            VaultEvent::IndexReady(kind) => {
+                self.ready.insert(*kind);
+            }
+            VaultEvent::Finished => self.finished = true,
+            VaultEvent::IndexProgress { .. }
+            | VaultEvent::NoteSkipped { .. }
+            | VaultEvent::NoteIndexed(_)
+            | VaultEvent::Cancelled => {}
+        }
+    }
+}
+
+/// A vault in the process of being opened.
+pub struct VaultOpening {
+    /// File tree, available immediately
+    pub file_tree: FileTree,
+    readiness: Readiness,
+    events: Receiver<VaultEvent>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl VaultOpening {
+    /// Current readiness state, as of the last [`poll`](Self::poll).
+    pub fn readiness(&self) -> &Readiness {
+        &self.readiness
+    }
+
+    /// Drain pending events without blocking, updating readiness.
+    pub fn poll(&mut self) -> Vec<VaultEvent> {
+        let events: Vec<_> = self.events.try_iter().collect();
+        for event in &events {
+            self.readiness.apply(event);
+        }
+        events
+    }
+
+    /// Block until background indexing finishes, returning remaining events.
+    pub fn wait(&mut self) -> Vec<VaultEvent> {
+        let events: Vec<_> = self.events.iter().collect();
+        for event in &events {
+            self.readiness.apply(event);
+        }
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        events
+    }
+}
+
+/// Open a vault: build the file tree now and the given indices in the background.
+///
+/// The vault is walked once; the notes in the returned tree are the ones
+/// indexed. Indices are reported ready together once every note is in.
+pub fn open_vault(notes_root: &Path, indices: Vec<SharedIndex>) -> Result<VaultOpening, IoError> {
+    open_vault_with(notes_root, indices, VaultOptions::default())
+}
+
+/// How a vault is opened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VaultOptions {
+    pub scan: ScanOptions,
+    /// Whether indices may be restored from and saved to disk
+    pub cache: CachePolicy,
+}
+
+/// [`open_vault`] with the given options.
+pub fn open_vault_with(
+    notes_root: &Path,
+    indices: Vec<SharedIndex>,
+    options: VaultOptions,
+) -> Result<VaultOpening, IoError> {
+    let files = io::scan_markdown_files_with(notes_root, options.scan)?;
+    let tree = FileTree::build_from_files(notes_root.to_path_buf(), &files);
+    let notes: Vec<_> = files
+        .iter()
+        .filter_map(|path| io::relative_to_root(path, notes_root))
+        .collect();
+    if options.cache == CachePolicy::MemoryOnly {
+        IndexSnapshot::remove(notes_root)?;
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let root = notes_root.to_path_buf();
+    let worker = std::thread::spawn(move || {
+        let mut snapshot = match options.cache {
+            CachePolicy::OnDisk => IndexSnapshot::load(&root).unwrap_or_default(),
+            CachePolicy::MemoryOnly => IndexSnapshot::default(),
+        };
+        snapshot.retain_unchanged(&root);
+        build_indices(&root, &notes, &indices, &snapshot, &tx)
+    });
+
+    Ok(VaultOpening {
+        file_tree: tree,
+        readiness: Readiness {
+            file_tree: true,
+            ..Readiness::default()
+        },
+        events: rx,
+        worker: Some(worker),
+    })
+}
+
+fn build_indices(
+    notes_root: &Path,
+    notes: &[RelativePathBuf],
+    indices: &[SharedIndex],
+    snapshot: &IndexSnapshot,
+    tx: &Sender<VaultEvent>,
+) {
+    let _timed = timed(tracing::debug_span!("index.open", notes = notes.len()));
+    let kinds = index_kinds(indices);
+    let total = notes.len();
+    for (i, path) in notes.iter().enumerate() {
+        index_note(notes_root, path, indices, snapshot, tx);
+        for &kind in &kinds {
+            let _ = tx.send(VaultEvent::IndexProgress {
+                kind,
+                done: i + 1,
+                total,
+            });
+        }
+    }
+    for kind in kinds {
+        let _ = tx.send(VaultEvent::IndexReady(kind));
+    }
+    let _ = tx.send(VaultEvent::Finished);
+}
+
+fn index_kinds(indices: &[SharedIndex]) -> Vec<IndexKind> {
+    indices
+        .iter()
+        .map(|index| index.read().unwrap_or_else(|e| e.into_inner()).kind())
+        .collect()
+}
+
+/// Add one note to every index, restoring from `snapshot` where it has an
+/// entry and reading the file at most once for the rest.
+fn index_note(
+    notes_root: &Path,
+    path: &RelativePath,
+    indices: &[SharedIndex],
+    snapshot: &IndexSnapshot,
+    tx: &Sender<VaultEvent>,
+) {
+    let mut source: Option<String> = None;
+    for index in indices {
+        let mut index = index.write().unwrap_or_else(|e| e.into_inner());
+        let kind = index.kind();
+        if snapshot
+            .entry(path, kind)
+            .is_some_and(|bytes| index.restore_note(path, bytes))
+        {
+            continue;
+        }
+        if let Some(source) = &source {
+            index.add_note(path, source);
+        } else {
+            match io::read_file(path, notes_root) {
+                Ok(text) => index.add_note(path, source.insert(text)),
+                Err(e) => {
+                    let _ = tx.send(VaultEvent::NoteSkipped {
+                        path: path.to_relative_path_buf(),
+                        reason: e.to_string(),
+                    });
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::links::LinkIndex;
+    use crate::tests::{create_test_file, create_test_notes_dir};
+    use crate::titles::TitleIndex;
+
+    #[test]
+    fn test_file_tree_ready_before_indices() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "a.md", "[[b]]\n");
+
+        let opening = open_vault(notes_dir.path(), vec![]).unwrap();
+
+        assert!(opening.readiness().file_tree);
+        assert!(!opening.readiness().is_ready(IndexKind::Links));
+        assert_eq!(opening.file_tree.get_items().len(), 1);
+    }
+
+    #[test]
+    fn test_indices_built_in_background() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "a.md", "[[b]]\n");
+        create_test_file(&notes_dir, "b.md", "[[a]] [[c]]\n");
+
+        let links = Arc::new(RwLock::new(LinkIndex::default()));
+        let mut opening = open_vault(notes_dir.path(), vec![links.clone()]).unwrap();
+        let events = opening.wait();
+
+        assert!(opening.readiness().is_ready(IndexKind::Links));
+        assert!(opening.readiness().is_finished());
+        assert_eq!(events.last(), Some(&VaultEvent::Finished));
+        assert!(events.contains(&VaultEvent::IndexProgress {
+            kind: IndexKind::Links,
+            done: 2,
+            total: 2,
+        }));
+        let links = links.read().unwrap();
+        assert_eq!(links.links_from(RelativePath::new("b.md")).len(), 2);
+    }
+
+    #[test]
+    fn test_every_index_filled_in_one_pass() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "a.md", "# Alpha\n\n[[b]]\n");
+        create_test_file(&notes_dir, "b.md", "[[a]]\n");
+
+        let links = Arc::new(RwLock::new(LinkIndex::default()));
+        let titles = Arc::new(RwLock::new(TitleIndex::default()));
+        let mut opening =
+            open_vault(notes_dir.path(), vec![links.clone(), titles.clone()]).unwrap();
+        let events = opening.wait();
+
+        assert!(opening.readiness().is_ready(IndexKind::Links));
+        assert!(opening.readiness().is_ready(IndexKind::Titles));
+        let skipped = events
+            .iter()
+            .filter(|event| matches!(event, VaultEvent::NoteSkipped { .. }))
+            .count();
+        assert_eq!(skipped, 0);
+        assert_eq!(links.read().unwrap().len(), 2);
+        assert_eq!(
+            titles.read().unwrap().note_title(RelativePath::new("a.md")),
+            "Alpha"
+        );
+    }
+
+    #[test]
+    fn test_readiness_apply() {
+        let mut readiness = Readiness::default();
+        readiness.apply(&VaultEvent::IndexProgress {
+            kind: IndexKind::Links,
+            done: 1,
+            total: 2,
+        });
+        assert!(!readiness.is_ready(IndexKind::Links));
+        readiness.apply(&VaultEvent::IndexReady(IndexKind::Links));
+        assert!(readiness.is_ready(IndexKind::Links));
+        assert!(!readiness.is_finished());
+    }
+}
@@ -0,0 +1,76 @@
+//! # Read-Only Vaults
+//!
+//! A vault mounted from a network share or checked out from another
+//! machine's git repository may be one the user mustn't change. Opening it
+//! [`OpenMode::ReadOnly`] makes the engine refuse writes with
+//! [`IoError::ReadOnly`] rather than trusting every frontend to grey out
+//! the right buttons:
+//!
+//! - [`Vault`](super::Vault) operations check the mode before writing
+//! - a [`Document`](crate::editing::Document) marked
+//!   [`set_read_only`](crate::editing::Document::set_read_only) ignores
+//!   edits, and [`try_apply`](crate::editing::Document::try_apply) says so
+//!
+//! [`OpenMode::detect`] picks read-only for folders the user can't write to.
+
+use std::fs;
+use std::path::Path;
+
+use crate::io::IoError;
+
+/// Whether a vault's notes may be changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OpenMode {
+    #[default]
+    ReadWrite,
+    ReadOnly,
+}
+
+impl OpenMode {
+    /// [`ReadOnly`](Self::ReadOnly) if the notes folder's permissions don't
+    /// allow writing, otherwise [`ReadWrite`](Self::ReadWrite).
+    pub fn detect(notes_root: &Path) -> Self {
+        match fs::metadata(notes_root) {
+            Ok(metadata) if metadata.permissions().readonly() => Self::ReadOnly,
+            _ => Self::ReadWrite,
+        }
+    }
+
+    pub fn is_read_only(self) -> bool {
+        self == Self::ReadOnly
+    }
+
+    /// Fail with [`IoError::ReadOnly`] unless notes under `notes_root` may
+    /// be written.
+    pub fn check_writable(self, notes_root: &Path) -> Result<(), IoError> {
+        match self {
+            Self::ReadWrite => Ok(()),
+            Self::ReadOnly => Err(IoError::ReadOnly(notes_root.to_path_buf())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_detect_and_check() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(OpenMode::detect(dir.path()), OpenMode::ReadWrite);
+        assert!(OpenMode::ReadWrite.check_writable(dir.path()).is_ok());
+        assert!(matches!(
+            OpenMode::ReadOnly.check_writable(dir.path()),
+            Err(IoError::ReadOnly(path)) if path == dir.path()
+        ));
+
+        let mut permissions = fs::metadata(dir.path()).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(dir.path(), permissions.clone()).unwrap();
+        assert_eq!(OpenMode::detect(dir.path()), OpenMode::ReadOnly);
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(dir.path(), permissions).unwrap();
+    }
+}
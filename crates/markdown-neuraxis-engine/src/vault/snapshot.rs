@@ -0,0 +1,495 @@
+//! # Index Snapshots
+//!
+//! Building the indices means reading and parsing every note, which takes
+//! seconds on a large vault. Instead, frontends call
+//! [`save_index_snapshot`] on shutdown to write every index's per-note data
+//! to [`INDEX_SNAPSHOT_PATH`], and [`open_vault`](super::open_vault) restores
+//! notes from it at startup, reading only notes whose size or modification
+//! time changed since.
+//!
+//! The snapshot is a cache: it is validated against the files on every
+//! load, and a missing, corrupt or outdated one just means notes are read
+//! again. It doesn't need syncing between machines.
+//!
+//! ## Format
+//!
+//! All integers are LEB128 varints and strings are length-prefixed UTF-8:
+//!
+//! ```text
+//! magic "MDNXIDX" version
+//! note count
+//!   path  len  mtime-secs  mtime-nanos  entry count
+//!     index tag  byte length  bytes      (one entry per index)
+//! ```
+//!
+//! Each index encodes its own entries with [`SnapshotWriter`] in
+//! [`VaultIndex::snapshot_note`] and decodes them in
+//! [`VaultIndex::restore_note`].
+//!
+//! ## Cache policy
+//!
+//! Index entries hold note contents (titles, links, tags, words), and the
+//! snapshot is written unencrypted. Vaults whose notes must not reach the
+//! disk in plain text, such as encrypted ones, open with
+//! [`CachePolicy::MemoryOnly`]: indices are then built from the notes on
+//! every start, nothing is saved, and any snapshot left from before is
+//! deleted.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use relative_path::{RelativePath, RelativePathBuf};
+
+use crate::io::{self, IoError, Stamp};
+use crate::vault::{IndexKind, SharedIndex};
+
+/// Where the index snapshot is stored, relative to the notes root.
+pub const INDEX_SNAPSHOT_PATH: &str = ".neuraxis/cache/index.bin";
+
+const MAGIC: &[u8] = b"MDNXIDX";
+
+/// Bumped whenever the file layout or any index's entry encoding changes,
+/// so old snapshots are ignored rather than misread.
+const VERSION: u64 = 1;
+
+/// Stable tag for each index kind in the file.
+fn kind_tag(kind: IndexKind) -> u64 {
+    match kind {
+        IndexKind::Links => 1,
+        IndexKind::Titles => 2,
+        IndexKind::Backlinks => 3,
+        IndexKind::Search => 4,
+        IndexKind::Tags => 5,
+        IndexKind::Calendar => 6,
+    }
+}
+
+/// Whether derived index data may be cached on disk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CachePolicy {
+    /// Save the index snapshot in the vault for fast starts
+    #[default]
+    OnDisk,
+    /// Keep indices in memory only and remove any saved snapshot
+    MemoryOnly,
+}
+
+/// Saved index entries for one note.
+#[derive(Debug, Clone, PartialEq)]
+struct SnapshotNote {
+    len: u64,
+    modified: Duration,
+    entries: BTreeMap<u64, Vec<u8>>,
+}
+
+/// Per-note index data for the whole vault, with the file stamps it was
+/// built from.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IndexSnapshot {
+    notes: BTreeMap<RelativePathBuf, SnapshotNote>,
+}
+
+impl IndexSnapshot {
+    /// Capture the current contents of `indices` for every note on disk.
+    ///
+    /// Indices must be up to date with the files (e.g. after
+    /// [`IndexWorker::flush`](super::IndexWorker::flush)), or stale entries
+    /// would be trusted until the note next changes.
+    pub fn capture(notes_root: &Path, indices: &[SharedIndex]) -> Result<Self, IoError> {
+        let mut notes = BTreeMap::new();
+        for path in io::scan_markdown_relative_paths(notes_root)? {
+            let Some((len, modified)) = stamp_of(notes_root, &path) else {
+                continue;
+            };
+            let entries: BTreeMap<u64, Vec<u8>> = indices
+                .iter()
+                .filter_map(|index| {
+                    let index = index.read().unwrap_or_else(|e| e.into_inner());
+                    Some((kind_tag(index.kind()), index.snapshot_note(&path)?))
+                })
+                .collect();
+            if !entries.is_empty() {
+                notes.insert(
+                    path,
+                    SnapshotNote {
+                        len,
+                        modified,
+                        entries,
+                    },
+                );
+            }
+        }
+        Ok(Self { notes })
+    }
+
+    /// Load the vault's snapshot, or `None` if there is none or it can't be
+    /// decoded.
+    pub fn load(notes_root: &Path) -> Option<Self> {
+        let bytes =
+            std::fs::read(RelativePath::new(INDEX_SNAPSHOT_PATH).to_path(notes_root)).ok()?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Delete the vault's snapshot, if there is one.
+    pub fn remove(notes_root: &Path) -> Result<(), IoError> {
+        let path = RelativePath::new(INDEX_SNAPSHOT_PATH).to_path(notes_root);
+        match std::fs::remove_file(path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(IoError::Io(err)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Write the snapshot into the vault, replacing any previous one.
+    pub fn save(&self, notes_root: &Path) -> Result<(), IoError> {
+        let path = RelativePath::new(INDEX_SNAPSHOT_PATH).to_path(notes_root);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Write then rename, so a crash mid-write never leaves a torn file
+        let partial = path.with_extension("bin.partial");
+        std::fs::write(&partial, self.to_bytes())?;
+        std::fs::rename(&partial, &path)?;
+        Ok(())
+    }
+
+    /// Number of notes in the snapshot.
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+
+    /// Drop notes whose file changed (or vanished) since the snapshot.
+    pub fn retain_unchanged(&mut self, notes_root: &Path) {
+        self.notes
+            .retain(|path, note| stamp_of(notes_root, path) == Some((note.len, note.modified)));
+    }
+
+    /// A note's saved entry for one index.
+    pub(crate) fn entry(&self, path: &RelativePath, kind: IndexKind) -> Option<&[u8]> {
+        self.notes
+            .get(path)?
+            .entries
+            .get(&kind_tag(kind))
+            .map(Vec::as_slice)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = SnapshotWriter::default();
+        out.bytes.extend_from_slice(MAGIC);
+        out.u64(VERSION);
+        out.u64(self.notes.len() as u64);
+        for (path, note) in &self.notes {
+            out.str(path.as_str());
+            out.u64(note.len);
+            out.u64(note.modified.as_secs());
+            out.u64(u64::from(note.modified.subsec_nanos()));
+            out.u64(note.entries.len() as u64);
+            for (tag, bytes) in &note.entries {
+                out.u64(*tag);
+                out.bytes(bytes);
+            }
+        }
+        out.into_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut input = SnapshotReader::new(bytes.strip_prefix(MAGIC)?);
+        if input.u64()? != VERSION {
+            return None;
+        }
+        let mut notes = BTreeMap::new();
+        for _ in 0..input.u64()? {
+            let path = RelativePathBuf::from(input.str()?);
+            let len = input.u64()?;
+            let secs = input.u64()?;
+            let nanos = u32::try_from(input.u64()?).ok()?;
+            let mut entries = BTreeMap::new();
+            for _ in 0..input.u64()? {
+                let tag = input.u64()?;
+                entries.insert(tag, input.bytes()?.to_vec());
+            }
+            let modified = Duration::new(secs, nanos);
+            notes.insert(
+                path,
+                SnapshotNote {
+                    len,
+                    modified,
+                    entries,
+                },
+            );
+        }
+        input.is_empty().then_some(Self { notes })
+    }
+}
+
+/// Capture `indices` and save them as the vault's snapshot, for a fast
+/// start next time. Call on shutdown, once pending index updates are done.
+pub fn save_index_snapshot(notes_root: &Path, indices: &[SharedIndex]) -> Result<(), IoError> {
+    save_index_snapshot_with(notes_root, indices, CachePolicy::default())
+}
+
+/// [`save_index_snapshot`] following `policy`: with
+/// [`CachePolicy::MemoryOnly`] nothing is written and any old snapshot is
+/// deleted.
+pub fn save_index_snapshot_with(
+    notes_root: &Path,
+    indices: &[SharedIndex],
+    policy: CachePolicy,
+) -> Result<(), IoError> {
+    match policy {
+        CachePolicy::OnDisk => IndexSnapshot::capture(notes_root, indices)?.save(notes_root),
+        CachePolicy::MemoryOnly => IndexSnapshot::remove(notes_root),
+    }
+}
+
+/// Size and modification time of a note. Files without a modification time
+/// can't be validated, so are never snapshotted.
+fn stamp_of(notes_root: &Path, path: &RelativePath) -> Option<(u64, Duration)> {
+    let stamp = Stamp::of(&path.to_path(notes_root))?;
+    let modified = stamp
+        .modified?
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .ok()?;
+    Some((stamp.len, modified))
+}
+
+/// Encodes snapshot entries: varints and length-prefixed strings.
+#[derive(Debug, Default)]
+pub struct SnapshotWriter {
+    bytes: Vec<u8>,
+}
+
+impl SnapshotWriter {
+    pub fn u64(&mut self, mut n: u64) {
+        while n >= 0x80 {
+            self.bytes.push((n as u8) | 0x80);
+            n >>= 7;
+        }
+        self.bytes.push(n as u8);
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.u64(bytes.len() as u64);
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    pub fn str(&mut self, s: &str) {
+        self.bytes(s.as_bytes());
+    }
+
+    pub fn opt_str(&mut self, s: Option<&str>) {
+        match s {
+            Some(s) => {
+                self.u64(1);
+                self.str(s);
+            }
+            None => self.u64(0),
+        }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// Decodes what [`SnapshotWriter`] wrote; every read is `None` once the
+/// input is exhausted or malformed.
+#[derive(Debug)]
+pub struct SnapshotReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> SnapshotReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub fn u64(&mut self) -> Option<u64> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let (&byte, rest) = self.bytes.split_first()?;
+            self.bytes = rest;
+            n |= u64::from(byte & 0x7f).checked_shl(shift)?;
+            if byte & 0x80 == 0 {
+                return Some(n);
+            }
+        }
+        None
+    }
+
+    pub fn bytes(&mut self) -> Option<&'a [u8]> {
+        let len = usize::try_from(self.u64()?).ok()?;
+        if len > self.bytes.len() {
+            return None;
+        }
+        let (bytes, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Some(bytes)
+    }
+
+    pub fn str(&mut self) -> Option<String> {
+        String::from_utf8(self.bytes()?.to_vec()).ok()
+    }
+
+    pub fn opt_str(&mut self) -> Option<Option<String>> {
+        match self.u64()? {
+            0 => Some(None),
+            1 => self.str().map(Some),
+            _ => None,
+        }
+    }
+
+    /// Whether everything has been read.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::links::LinkIndex;
+    use crate::tests::{create_test_file, create_test_notes_dir};
+    use crate::titles::TitleIndex;
+    use crate::vault::{VaultEvent, VaultOptions, open_vault, open_vault_with};
+    use std::sync::{Arc, RwLock};
+
+    #[test]
+    fn test_writer_reader_round_trip() {
+        let mut out = SnapshotWriter::default();
+        for n in [0, 1, 127, 128, 300, u64::MAX] {
+            out.u64(n);
+        }
+        out.str("héllo");
+        out.opt_str(None);
+        out.opt_str(Some(""));
+        let bytes = out.into_bytes();
+
+        let mut input = SnapshotReader::new(&bytes);
+        for n in [0, 1, 127, 128, 300, u64::MAX] {
+            assert_eq!(input.u64(), Some(n));
+        }
+        assert_eq!(input.str().as_deref(), Some("héllo"));
+        assert_eq!(input.opt_str(), Some(None));
+        assert_eq!(input.opt_str(), Some(Some(String::new())));
+        assert!(input.is_empty());
+        assert_eq!(input.u64(), None);
+
+        // Truncated input never panics
+        for end in 0..bytes.len() {
+            let mut input = SnapshotReader::new(&bytes[..end]);
+            while input.u64().is_some() {}
+        }
+    }
+
+    #[test]
+    fn test_corrupt_or_old_snapshots_are_ignored() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "a.md", "# A\n\n[[b]]\n");
+        let titles = Arc::new(RwLock::new(TitleIndex::default()));
+        titles
+            .write()
+            .unwrap()
+            .update_note(RelativePath::new("a.md"), "# A\n\n[[b]]\n");
+        let bytes = IndexSnapshot::capture(notes_dir.path(), &[titles])
+            .unwrap()
+            .to_bytes();
+
+        assert!(IndexSnapshot::from_bytes(&bytes).is_some());
+        assert!(IndexSnapshot::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+        assert!(IndexSnapshot::from_bytes(b"nonsense").is_none());
+        let mut newer = bytes.clone();
+        newer[MAGIC.len()] = 99;
+        assert!(IndexSnapshot::from_bytes(&newer).is_none());
+    }
+
+    #[test]
+    fn test_open_vault_restores_unchanged_notes() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "a.md", "# Alpha\n\n[[b]] [[c]]\n");
+        create_test_file(&notes_dir, "b.md", "# Beta\n");
+
+        let open = || {
+            let links = Arc::new(RwLock::new(LinkIndex::default()));
+            let titles = Arc::new(RwLock::new(TitleIndex::default()));
+            let indices: Vec<SharedIndex> = vec![links.clone(), titles.clone()];
+            let events = open_vault(notes_dir.path(), indices.clone())
+                .unwrap()
+                .wait();
+            assert_eq!(events.last(), Some(&VaultEvent::Finished));
+            (links, titles, indices)
+        };
+
+        let (_, _, indices) = open();
+        save_index_snapshot(notes_dir.path(), &indices).unwrap();
+        assert_eq!(IndexSnapshot::load(notes_dir.path()).unwrap().len(), 2);
+
+        // Same size and modification time: trusted without reading, so the
+        // old title survives (a real edit always changes one of them)
+        let a = notes_dir.path().join("a.md");
+        let modified = std::fs::metadata(&a).unwrap().modified().unwrap();
+        create_test_file(&notes_dir, "a.md", "# Alpho\n\n[[b]] [[c]]\n");
+        std::fs::File::options()
+            .write(true)
+            .open(&a)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        // Change b.md behind the snapshot's back: it must be re-read
+        std::thread::sleep(Duration::from_millis(20));
+        create_test_file(&notes_dir, "b.md", "# Beta two\n\n[[a]]\n");
+
+        let (links, titles, _) = open();
+        let links = links.read().unwrap();
+        let titles = titles.read().unwrap();
+        assert_eq!(links.links_from(RelativePath::new("a.md")).len(), 2);
+        assert_eq!(titles.note_title(RelativePath::new("a.md")), "Alpha");
+        assert_eq!(links.links_from(RelativePath::new("b.md")).len(), 1);
+        assert_eq!(titles.note_title(RelativePath::new("b.md")), "Beta two");
+    }
+
+    #[test]
+    fn test_memory_only_policy_keeps_indices_off_disk() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(
+            &notes_dir,
+            "a.md",
+            "# Secret plans
+",
+        );
+        let titles = Arc::new(RwLock::new(TitleIndex::default()));
+        let indices: Vec<SharedIndex> = vec![titles.clone()];
+        let snapshot_path = notes_dir.path().join(INDEX_SNAPSHOT_PATH);
+
+        // A snapshot from before the vault went memory-only is deleted on
+        // open and never loaded
+        open_vault(notes_dir.path(), indices.clone())
+            .unwrap()
+            .wait();
+        save_index_snapshot(notes_dir.path(), &indices).unwrap();
+        assert!(snapshot_path.exists());
+
+        let options = VaultOptions {
+            cache: CachePolicy::MemoryOnly,
+            ..VaultOptions::default()
+        };
+        let titles = Arc::new(RwLock::new(TitleIndex::default()));
+        let indices: Vec<SharedIndex> = vec![titles.clone()];
+        open_vault_with(notes_dir.path(), indices.clone(), options)
+            .unwrap()
+            .wait();
+        assert!(!snapshot_path.exists());
+        assert_eq!(
+            titles.read().unwrap().note_title(RelativePath::new("a.md")),
+            "Secret plans"
+        );
+
+        save_index_snapshot_with(notes_dir.path(), &indices, CachePolicy::MemoryOnly).unwrap();
+        assert!(!snapshot_path.exists());
+    }
+}
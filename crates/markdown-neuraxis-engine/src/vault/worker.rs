@@ -0,0 +1,560 @@
+//! # Background Index Maintenance
+//!
+//! After the vault is open, edits keep the indices up to date. Doing that on
+//! the UI thread makes typing stall whenever a large note (or a whole-vault
+//! rebuild) is re-indexed, so an [`IndexWorker`] owns that work instead:
+//!
+//! - [`IndexWorker::submit`] only sends a message, so it never blocks
+//! - Pending changes to the same note are coalesced; only the latest source
+//!   is indexed
+//! - Rebuilds run in slices of at most [`UPDATE_SLICE_BUDGET`], yielding
+//!   between slices so readers can take the index locks
+//! - [`IndexWorker::cancel`] stops a rebuild at the next note and drops
+//!   everything submitted before it
+//!
+//! Opening a note has the same problem, so [`IndexWorker::parse`] reads and
+//! parses notes on a second thread, which a long rebuild can't hold up. Only
+//! the latest request counts: a new one (or [`IndexWorker::cancel_parse`])
+//! abandons the one before, and its result is never delivered.
+//!
+//! Progress is reported as [`VaultEvent`]s, the same events the frontend
+//! already handles for vault opening. Frontends driven by their own event
+//! loop use [`IndexWorker::spawn_notifying`] to hear when there is
+//! something to [`poll`](IndexWorker::poll) or
+//! [`poll_parsed`](IndexWorker::poll_parsed).
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use relative_path::RelativePathBuf;
+
+use crate::io::{self, IoError};
+use crate::trace::timed;
+use crate::vault::{IndexSnapshot, SharedIndex, VaultEvent, index_kinds, index_note};
+use crate::{Document, Snapshot};
+
+/// Longest stretch of indexing work between yields to other threads.
+pub const UPDATE_SLICE_BUDGET: Duration = Duration::from_millis(8);
+
+/// A change to apply to the indices.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IndexUpdate {
+    /// A note was created or edited
+    NoteChanged {
+        path: RelativePathBuf,
+        source: String,
+    },
+    /// A note was deleted (or moved away)
+    NoteRemoved(RelativePathBuf),
+    /// Re-index every note in the vault from disk
+    Rebuild,
+}
+
+enum Job {
+    Update {
+        update: IndexUpdate,
+        generation: u64,
+    },
+    Flush(Sender<()>),
+}
+
+/// A note read and parsed by [`IndexWorker::parse`].
+pub struct ParsedNote {
+    pub path: RelativePathBuf,
+    /// Whether the note was on disk; a missing note parses as blank
+    pub existed: bool,
+    /// The document and its snapshot, or why the note couldn't be opened
+    pub result: anyhow::Result<(Document, Snapshot)>,
+    generation: u64,
+}
+
+struct ParseJob {
+    path: RelativePathBuf,
+    generation: u64,
+}
+
+/// Applies index updates and parses notes on background threads.
+pub struct IndexWorker {
+    jobs: Option<Sender<Job>>,
+    events: Receiver<VaultEvent>,
+    generation: Arc<AtomicU64>,
+    worker: Option<JoinHandle<()>>,
+    parses: Option<Sender<ParseJob>>,
+    parsed: Receiver<ParsedNote>,
+    parse_generation: Arc<AtomicU64>,
+    parser: Option<JoinHandle<()>>,
+}
+
+impl IndexWorker {
+    /// Start a worker maintaining the given indices.
+    pub fn spawn(notes_root: &Path, indices: Vec<SharedIndex>) -> Self {
+        Self::spawn_notifying(notes_root, indices, || {})
+    }
+
+    /// [`spawn`](Self::spawn), calling `notify` on a worker thread after
+    /// each batch of work and each parsed note so the frontend knows to
+    /// poll.
+    pub fn spawn_notifying(
+        notes_root: &Path,
+        indices: Vec<SharedIndex>,
+        notify: impl Fn() + Send + Sync + 'static,
+    ) -> Self {
+        let notify: Arc<dyn Fn() + Send + Sync> = Arc::new(notify);
+        let (jobs_tx, jobs_rx) = mpsc::channel();
+        let (events_tx, events_rx) = mpsc::channel();
+        let generation = Arc::new(AtomicU64::new(0));
+        let worker = Worker {
+            notes_root: notes_root.to_path_buf(),
+            indices,
+            generation: generation.clone(),
+            events: events_tx,
+            notify: notify.clone(),
+        };
+        let handle = std::thread::spawn(move || worker.run(&jobs_rx));
+
+        let (parses_tx, parses_rx) = mpsc::channel();
+        let (parsed_tx, parsed_rx) = mpsc::channel();
+        let parse_generation = Arc::new(AtomicU64::new(0));
+        let parser = Parser {
+            notes_root: notes_root.to_path_buf(),
+            generation: parse_generation.clone(),
+            parsed: parsed_tx,
+            notify,
+        };
+        let parser = std::thread::spawn(move || parser.run(&parses_rx));
+
+        Self {
+            jobs: Some(jobs_tx),
+            events: events_rx,
+            generation,
+            worker: Some(handle),
+            parses: Some(parses_tx),
+            parsed: parsed_rx,
+            parse_generation,
+            parser: Some(parser),
+        }
+    }
+
+    /// Queue an update without waiting for it.
+    pub fn submit(&self, update: IndexUpdate) {
+        let generation = self.generation.load(Ordering::SeqCst);
+        self.send(Job::Update { update, generation });
+    }
+
+    /// Abandon any running rebuild and everything queued so far.
+    pub fn cancel(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Block until everything submitted so far has been applied or dropped.
+    pub fn flush(&self) {
+        let (tx, rx) = mpsc::channel();
+        self.send(Job::Flush(tx));
+        let _ = rx.recv();
+    }
+
+    /// Drain pending events without blocking.
+    pub fn poll(&self) -> Vec<VaultEvent> {
+        self.events.try_iter().collect()
+    }
+
+    /// Read and parse a note in the background, abandoning any earlier
+    /// request. The result arrives through [`poll_parsed`](Self::poll_parsed).
+    pub fn parse(&self, path: RelativePathBuf) {
+        let generation = self.parse_generation.fetch_add(1, Ordering::SeqCst) + 1;
+        if let Some(parses) = &self.parses {
+            let _ = parses.send(ParseJob { path, generation });
+        }
+    }
+
+    /// Abandon the pending [`parse`](Self::parse), if any.
+    pub fn cancel_parse(&self) {
+        self.parse_generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// The latest requested note, once it has been parsed. Results for
+    /// abandoned requests are dropped.
+    pub fn poll_parsed(&self) -> Option<ParsedNote> {
+        let generation = self.parse_generation.load(Ordering::SeqCst);
+        self.parsed
+            .try_iter()
+            .filter(|note| note.generation == generation)
+            .last()
+    }
+
+    fn send(&self, job: Job) {
+        if let Some(jobs) = &self.jobs {
+            let _ = jobs.send(job);
+        }
+    }
+}
+
+impl Drop for IndexWorker {
+    fn drop(&mut self) {
+        self.cancel();
+        self.cancel_parse();
+        // Closing the channels ends the worker loops
+        self.jobs.take();
+        self.parses.take();
+        for thread in [self.worker.take(), self.parser.take()]
+            .into_iter()
+            .flatten()
+        {
+            let _ = thread.join();
+        }
+    }
+}
+
+struct Worker {
+    notes_root: PathBuf,
+    indices: Vec<SharedIndex>,
+    generation: Arc<AtomicU64>,
+    events: Sender<VaultEvent>,
+    notify: Arc<dyn Fn() + Send + Sync>,
+}
+
+impl Worker {
+    fn run(&self, jobs: &Receiver<Job>) {
+        while let Ok(first) = jobs.recv() {
+            // Take everything already waiting so repeated edits coalesce
+            let mut batch = vec![first];
+            batch.extend(jobs.try_iter());
+
+            let mut changed = BTreeMap::new();
+            let mut flushes = Vec::new();
+            for job in batch {
+                match job {
+                    Job::Flush(done) => flushes.push(done),
+                    Job::Update { generation, .. } if self.cancelled(generation) => {}
+                    Job::Update {
+                        update: IndexUpdate::Rebuild,
+                        generation,
+                    } => {
+                        // Later edits are applied after the rebuild
+                        self.apply_changes(std::mem::take(&mut changed));
+                        self.rebuild(generation);
+                    }
+                    Job::Update {
+                        update: IndexUpdate::NoteChanged { path, source },
+                        generation,
+                    } => {
+                        changed.insert(path, (Some(source), generation));
+                    }
+                    Job::Update {
+                        update: IndexUpdate::NoteRemoved(path),
+                        generation,
+                    } => {
+                        changed.insert(path, (None, generation));
+                    }
+                }
+            }
+            self.apply_changes(changed);
+            (self.notify)();
+
+            for done in flushes {
+                let _ = done.send(());
+            }
+        }
+    }
+
+    fn cancelled(&self, generation: u64) -> bool {
+        self.generation.load(Ordering::SeqCst) != generation
+    }
+
+    /// Apply coalesced note changes: latest source (or `None` for removal)
+    /// and the generation it was submitted in.
+    fn apply_changes(&self, changed: BTreeMap<RelativePathBuf, (Option<String>, u64)>) {
+        if changed.is_empty() {
+            return;
+        }
+        let _timed = timed(tracing::debug_span!("index.update", notes = changed.len()));
+        let mut slice_start = Instant::now();
+        for (path, (source, generation)) in changed {
+            if self.cancelled(generation) {
+                continue;
+            }
+            for index in &self.indices {
+                let mut index = index.write().unwrap_or_else(|e| e.into_inner());
+                match &source {
+                    Some(source) => index.add_note(&path, source),
+                    None => index.remove_note(&path),
+                }
+            }
+            let _ = self.events.send(VaultEvent::NoteIndexed(path));
+            yield_after_budget(&mut slice_start);
+        }
+    }
+
+    fn rebuild(&self, generation: u64) {
+        let _timed = timed(tracing::debug_span!("index.rebuild"));
+        let notes = match io::scan_markdown_relative_paths(&self.notes_root) {
+            Ok(notes) => notes,
+            Err(e) => {
+                let _ = self.events.send(VaultEvent::NoteSkipped {
+                    path: RelativePathBuf::new(),
+                    reason: e.to_string(),
+                });
+                return;
+            }
+        };
+
+        let kinds = index_kinds(&self.indices);
+        let snapshot = IndexSnapshot::default();
+        let total = notes.len();
+        let mut slice_start = Instant::now();
+        for (i, path) in notes.iter().enumerate() {
+            if self.cancelled(generation) {
+                let _ = self.events.send(VaultEvent::Cancelled);
+                return;
+            }
+            index_note(
+                &self.notes_root,
+                path,
+                &self.indices,
+                &snapshot,
+                &self.events,
+            );
+            if yield_after_budget(&mut slice_start) || i + 1 == total {
+                for &kind in &kinds {
+                    let _ = self.events.send(VaultEvent::IndexProgress {
+                        kind,
+                        done: i + 1,
+                        total,
+                    });
+                }
+            }
+        }
+        for kind in kinds {
+            let _ = self.events.send(VaultEvent::IndexReady(kind));
+        }
+        let _ = self.events.send(VaultEvent::Finished);
+    }
+}
+
+struct Parser {
+    notes_root: PathBuf,
+    generation: Arc<AtomicU64>,
+    parsed: Sender<ParsedNote>,
+    notify: Arc<dyn Fn() + Send + Sync>,
+}
+
+impl Parser {
+    fn run(&self, jobs: &Receiver<ParseJob>) {
+        while let Ok(first) = jobs.recv() {
+            // Only the newest request can still be wanted
+            let job = jobs.try_iter().last().unwrap_or(first);
+            if self.cancelled(job.generation) {
+                continue;
+            }
+            let note = self.parse(job);
+            if self.cancelled(note.generation) {
+                continue;
+            }
+            let _ = self.parsed.send(note);
+            (self.notify)();
+        }
+    }
+
+    fn cancelled(&self, generation: u64) -> bool {
+        self.generation.load(Ordering::SeqCst) != generation
+    }
+
+    fn parse(&self, ParseJob { path, generation }: ParseJob) -> ParsedNote {
+        let (existed, content) = match io::read_file(&path, &self.notes_root) {
+            Ok(content) => (true, Ok(content)),
+            Err(IoError::NotFound(_)) => (false, Ok(String::new())),
+            Err(e) => (true, Err(e)),
+        };
+        let result = content.map_err(anyhow::Error::from).and_then(|content| {
+            let document = Document::from_bytes(content.as_bytes())?;
+            let snapshot = document.snapshot();
+            Ok((document, snapshot))
+        });
+        ParsedNote {
+            path,
+            existed,
+            result,
+            generation,
+        }
+    }
+}
+
+/// Yield to other threads once the current slice has used its budget.
+/// Returns whether a new slice started.
+fn yield_after_budget(slice_start: &mut Instant) -> bool {
+    if slice_start.elapsed() < UPDATE_SLICE_BUDGET {
+        return false;
+    }
+    std::thread::yield_now();
+    *slice_start = Instant::now();
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::links::LinkIndex;
+    use crate::tests::{create_test_file, create_test_notes_dir};
+    use relative_path::RelativePath;
+    use std::sync::RwLock;
+
+    fn worker_with_links(notes_root: &Path) -> (IndexWorker, Arc<RwLock<LinkIndex>>) {
+        let links = Arc::new(RwLock::new(LinkIndex::default()));
+        let worker = IndexWorker::spawn(notes_root, vec![links.clone()]);
+        (worker, links)
+    }
+
+    #[test]
+    fn test_updates_applied_in_background() {
+        let notes_dir = create_test_notes_dir();
+        let (worker, links) = worker_with_links(notes_dir.path());
+        let a = RelativePath::new("a.md");
+
+        for n in 0..10 {
+            worker.submit(IndexUpdate::NoteChanged {
+                path: a.to_relative_path_buf(),
+                source: "[[b]] ".repeat(n + 1),
+            });
+        }
+        worker.flush();
+        assert_eq!(links.read().unwrap().links_from(a).len(), 10);
+
+        worker.submit(IndexUpdate::NoteRemoved(a.to_relative_path_buf()));
+        worker.flush();
+        assert!(links.read().unwrap().is_empty());
+        assert!(
+            worker
+                .poll()
+                .contains(&VaultEvent::NoteIndexed(a.to_relative_path_buf()))
+        );
+    }
+
+    #[test]
+    fn test_rebuild_reports_progress() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "a.md", "[[b]]\n");
+        create_test_file(&notes_dir, "b.md", "[[a]] [[c]]\n");
+        let (worker, links) = worker_with_links(notes_dir.path());
+
+        worker.submit(IndexUpdate::Rebuild);
+        worker.flush();
+
+        assert_eq!(links.read().unwrap().len(), 2);
+        let events = worker.poll();
+        assert_eq!(events.last(), Some(&VaultEvent::Finished));
+        assert!(events.contains(&VaultEvent::IndexProgress {
+            kind: crate::vault::IndexKind::Links,
+            done: 2,
+            total: 2,
+        }));
+    }
+
+    #[test]
+    fn test_notified_after_each_batch() {
+        let notes_dir = create_test_notes_dir();
+        let batches = Arc::new(AtomicU64::new(0));
+        let worker = IndexWorker::spawn_notifying(notes_dir.path(), vec![], {
+            let batches = batches.clone();
+            move || {
+                batches.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        worker.submit(IndexUpdate::NoteRemoved(RelativePathBuf::from("a.md")));
+        worker.flush();
+
+        assert!(batches.load(Ordering::SeqCst) >= 1);
+        assert!(
+            worker
+                .poll()
+                .contains(&VaultEvent::NoteIndexed(RelativePathBuf::from("a.md")))
+        );
+    }
+
+    fn wait_for_parsed(worker: &IndexWorker) -> ParsedNote {
+        let start = Instant::now();
+        loop {
+            if let Some(note) = worker.poll_parsed() {
+                return note;
+            }
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "parse never finished"
+            );
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_parse_in_background() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "a.md", "- one\n- two\n");
+        let worker = IndexWorker::spawn(notes_dir.path(), vec![]);
+
+        worker.parse(RelativePathBuf::from("a.md"));
+        let note = wait_for_parsed(&worker);
+        assert_eq!(note.path, "a.md");
+        assert!(note.existed);
+        let (document, snapshot) = note.result.unwrap();
+        assert_eq!(document.text(), "- one\n- two\n");
+        assert!(!snapshot.blocks.is_empty());
+
+        worker.parse(RelativePathBuf::from("new.md"));
+        let note = wait_for_parsed(&worker);
+        assert!(!note.existed);
+        assert_eq!(note.result.unwrap().0.text(), "");
+    }
+
+    #[test]
+    fn test_only_latest_parse_delivered() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "a.md", "# A\n");
+        create_test_file(&notes_dir, "b.md", "# B\n");
+        let worker = IndexWorker::spawn(notes_dir.path(), vec![]);
+
+        worker.parse(RelativePathBuf::from("a.md"));
+        worker.parse(RelativePathBuf::from("b.md"));
+        assert_eq!(wait_for_parsed(&worker).path, "b.md");
+
+        worker.parse(RelativePathBuf::from("a.md"));
+        worker.cancel_parse();
+        // A later request is delivered, the cancelled one never is
+        worker.parse(RelativePathBuf::from("b.md"));
+        assert_eq!(wait_for_parsed(&worker).path, "b.md");
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(worker.poll_parsed().is_none());
+    }
+
+    #[test]
+    fn test_cancel_drops_queued_updates() {
+        let notes_dir = create_test_notes_dir();
+        let (worker, links) = worker_with_links(notes_dir.path());
+
+        // Hold the index so the worker cannot finish before cancel
+        let guard = links.write().unwrap();
+        worker.submit(IndexUpdate::NoteChanged {
+            path: RelativePathBuf::from("first.md"),
+            source: "[[x]]\n".to_string(),
+        });
+        worker.submit(IndexUpdate::NoteChanged {
+            path: RelativePathBuf::from("second.md"),
+            source: "[[y]]\n".to_string(),
+        });
+        worker.cancel();
+        drop(guard);
+        worker.submit(IndexUpdate::NoteChanged {
+            path: RelativePathBuf::from("third.md"),
+            source: "[[z]]\n".to_string(),
+        });
+        worker.flush();
+
+        let links = links.read().unwrap();
+        assert!(links.links_from(RelativePath::new("second.md")).is_empty());
+        assert_eq!(links.links_from(RelativePath::new("third.md")).len(), 1);
+    }
+}
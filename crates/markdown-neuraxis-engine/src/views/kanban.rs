@@ -0,0 +1,611 @@
+//! # Kanban Boards
+//!
+//! A [`Board`] shows every task list item in the vault (`- [ ]`, `- [x]`,
+//! `- TODO`, `- DOING`, `- DONE`, `- LATER`) as a [`Card`] in a column:
+//!
+//! - [`GroupBy::State`]: one column per task state, `TODO`, `DOING` and
+//!   `DONE` always, and `LATER` first when any task has it
+//! - [`GroupBy::Property`]: one column per value of a `name:: value`
+//!   property in the task's text (`- TODO Fix roof status:: blocked`),
+//!   plus a column for tasks without it
+//!
+//! Cards are keyed by note path and anchor id. [`move_commands`] turns
+//! dragging a card to another column into edits of the note it's in:
+//! changing the state keyword or checkbox, or setting or removing the
+//! property. Frontends with the note open apply them to its document;
+//! [`move_card`] edits the note on disk.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::Path;
+
+use relative_path::{RelativePath, RelativePathBuf};
+use thiserror::Error;
+
+use crate::editing::focus::find_block;
+use crate::editing::snapshot::{Block, BlockContent, BlockKind, TaskState};
+use crate::editing::{AnchorId, Cmd, Document};
+use crate::io::{self, IoError};
+
+#[derive(Debug, Error)]
+pub enum KanbanError {
+    #[error("No task with anchor {0:?}")]
+    CardNotFound(AnchorId),
+    #[error(transparent)]
+    Io(#[from] IoError),
+    #[error("Could not open note: {0}")]
+    Document(String),
+}
+
+/// What a board's columns are.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupBy {
+    /// Task state
+    State,
+    /// Value of the property `name`. The `values` columns always show, in
+    /// that order, so cards can be dropped on them; values only found in
+    /// tasks follow, sorted.
+    Property { name: String, values: Vec<String> },
+}
+
+/// Which column a card is in, and what moving a card there writes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnKey {
+    State(TaskState),
+    /// Tasks whose property `name` is `value`, or who don't have it
+    Property {
+        name: String,
+        value: Option<String>,
+    },
+}
+
+/// A task on a board.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Card {
+    pub path: RelativePathBuf,
+    pub id: AnchorId,
+    /// 1-based line number of the task
+    pub line: usize,
+    /// First line of the task's text, without the state keyword
+    pub text: String,
+    pub state: TaskState,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub key: ColumnKey,
+    /// Heading to show: the state keyword, the property value, or
+    /// `No <name>`
+    pub title: String,
+    /// Cards in note path and line order
+    pub cards: Vec<Card>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Board {
+    pub group_by: GroupBy,
+    pub columns: Vec<Column>,
+}
+
+impl Board {
+    /// Build a board from note paths and sources.
+    pub fn build(
+        notes: impl IntoIterator<Item = (RelativePathBuf, String)>,
+        group_by: GroupBy,
+    ) -> Self {
+        let mut cards: Vec<(Card, Option<String>)> = Vec::new();
+        for (path, source) in notes {
+            let Ok(doc) = Document::from_bytes(source.as_bytes()) else {
+                continue;
+            };
+            let property = match &group_by {
+                GroupBy::State => None,
+                GroupBy::Property { name, .. } => Some(name.as_str()),
+            };
+            collect_cards(
+                &doc,
+                &source,
+                &path,
+                &doc.snapshot().blocks,
+                property,
+                &mut cards,
+            );
+        }
+        cards.sort_by(|(a, _), (b, _)| (&a.path, a.line).cmp(&(&b.path, b.line)));
+
+        let columns = match &group_by {
+            GroupBy::State => state_columns(cards),
+            GroupBy::Property { name, values } => property_columns(name, values, cards),
+        };
+        Self { group_by, columns }
+    }
+
+    /// Build a board from the vault on disk.
+    ///
+    /// Cards come from every note except those [`io::is_hidden_or_template`]
+    /// rules out.
+    pub fn load(notes_root: &Path, group_by: GroupBy) -> Result<Self, IoError> {
+        let mut notes = Vec::new();
+        for path in io::scan_markdown_relative_paths(notes_root)? {
+            if io::is_hidden_or_template(&path) {
+                continue;
+            }
+            let source = io::read_file(&path, notes_root)?;
+            notes.push((path, source));
+        }
+        Ok(Self::build(notes, group_by))
+    }
+
+    /// The column a card is in.
+    pub fn column_of(&self, path: &RelativePath, id: AnchorId) -> Option<&Column> {
+        self.columns.iter().find(|column| {
+            column
+                .cards
+                .iter()
+                .any(|card| card.id == id && card.path == path)
+        })
+    }
+}
+
+/// Commands that move the task `id` in `doc` to the column `to`, to be
+/// applied in order. Empty if it's already there.
+///
+/// Moving to a state rewrites the state keyword, or the checkbox of a
+/// `- [ ]` task (adding a keyword for `DOING` and `LATER`); a checkbox next
+/// to a keyword is kept ticked for `DONE` only. Moving to a property value
+/// replaces the value or adds ` name:: value` to the end of the task's
+/// text, and moving to the no-value column removes the property.
+pub fn move_commands(
+    doc: &Document,
+    id: AnchorId,
+    to: &ColumnKey,
+) -> Result<Vec<Cmd>, KanbanError> {
+    let snapshot = doc.snapshot();
+    let block = find_block(&snapshot.blocks, id)
+        .filter(|block| task_state(block).is_some())
+        .ok_or(KanbanError::CardNotFound(id))?;
+    let source = doc.text();
+
+    let mut edits = match to {
+        ColumnKey::State(state) => state_edits(&source, block, *state),
+        ColumnKey::Property { name, value } => {
+            property_edits(&source, block, name, value.as_deref())
+        }
+    };
+    // Later edits first, so earlier offsets stay valid
+    edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    Ok(edits
+        .into_iter()
+        .map(|(range, text)| Cmd::ReplaceRange { range, text })
+        .collect())
+}
+
+/// Move the task `id` in the note at `path` to the column `to` and save
+/// the note. See [`move_commands`].
+pub fn move_card(
+    notes_root: &Path,
+    path: &RelativePath,
+    id: AnchorId,
+    to: &ColumnKey,
+) -> Result<(), KanbanError> {
+    let source = io::read_file(path, notes_root)?;
+    let mut doc = Document::from_bytes(source.as_bytes())
+        .map_err(|e| KanbanError::Document(e.to_string()))?;
+    let cmds = move_commands(&doc, id, to)?;
+    if cmds.is_empty() {
+        return Ok(());
+    }
+    for cmd in cmds {
+        doc.apply(cmd);
+    }
+    io::write_file(path, notes_root, &doc.text())?;
+    Ok(())
+}
+
+fn task_state(block: &Block) -> Option<TaskState> {
+    match &block.kind {
+        BlockKind::ListItem { task_state, .. } => *task_state,
+        _ => None,
+    }
+}
+
+/// Tasks among `blocks` and their children, with the value of `property`
+/// if asked for.
+fn collect_cards(
+    doc: &Document,
+    source: &str,
+    path: &RelativePath,
+    blocks: &[Block],
+    property: Option<&str>,
+    out: &mut Vec<(Card, Option<String>)>,
+) {
+    for block in blocks {
+        if let Some(state) = task_state(block) {
+            let text = text_range(block);
+            let first_line = source[text.clone()].lines().next().unwrap_or("");
+            let title = match keyword_len(source, text.start) {
+                Some(len) => &first_line[len..],
+                None => first_line,
+            };
+            let value = property
+                .and_then(|name| find_property(source, text, name))
+                .map(|found| source[found.value].to_string());
+            out.push((
+                Card {
+                    path: path.to_relative_path_buf(),
+                    id: block.id,
+                    line: doc.line_of_offset(block.node_range.start) + 1,
+                    text: title.trim().to_string(),
+                    state,
+                },
+                value,
+            ));
+        }
+        if let BlockContent::Children(children) = &block.content {
+            collect_cards(doc, source, path, children, property, out);
+        }
+    }
+}
+
+fn state_columns(cards: Vec<(Card, Option<String>)>) -> Vec<Column> {
+    let mut columns: Vec<Column> = [
+        TaskState::Later,
+        TaskState::Todo,
+        TaskState::Doing,
+        TaskState::Done,
+    ]
+    .into_iter()
+    .map(|state| Column {
+        key: ColumnKey::State(state),
+        title: state.keyword().to_string(),
+        cards: Vec::new(),
+    })
+    .collect();
+    for (card, _) in cards {
+        let column = columns
+            .iter_mut()
+            .find(|column| column.key == ColumnKey::State(card.state))
+            .expect("a column for every state");
+        column.cards.push(card);
+    }
+    if columns[0].cards.is_empty() {
+        columns.remove(0);
+    }
+    columns
+}
+
+fn property_columns(
+    name: &str,
+    values: &[String],
+    cards: Vec<(Card, Option<String>)>,
+) -> Vec<Column> {
+    let mut unset = Vec::new();
+    let mut found: BTreeMap<String, Vec<Card>> = BTreeMap::new();
+    for (card, value) in cards {
+        match value.filter(|value| !value.is_empty()) {
+            Some(value) => found.entry(value).or_default().push(card),
+            None => unset.push(card),
+        }
+    }
+
+    let column = |value: String, cards: Vec<Card>| Column {
+        key: ColumnKey::Property {
+            name: name.to_string(),
+            value: Some(value.clone()),
+        },
+        title: value,
+        cards,
+    };
+    let mut columns = Vec::new();
+    if !unset.is_empty() {
+        columns.push(Column {
+            key: ColumnKey::Property {
+                name: name.to_string(),
+                value: None,
+            },
+            title: format!("No {name}"),
+            cards: unset,
+        });
+    }
+    for value in values {
+        let cards = found.remove(value).unwrap_or_default();
+        columns.push(column(value.clone(), cards));
+    }
+    columns.extend(found.into_iter().map(|(value, cards)| column(value, cards)));
+    columns
+}
+
+/// Source range of a task's text, from after the checkbox (keyword
+/// included) to the end of its last line.
+fn text_range(block: &Block) -> Range<usize> {
+    match (block.segments.first(), block.segments.last()) {
+        (Some(first), Some(last)) => first.range.start..last.range.end,
+        _ => {
+            let end = block.content_range().end;
+            end..end
+        }
+    }
+}
+
+/// Length of the state keyword at `start`, if there is one.
+fn keyword_len(source: &str, start: usize) -> Option<usize> {
+    let word = source[start..].split([' ', '\t', '\n']).next()?;
+    TaskState::from_keyword(word).map(|_| word.len())
+}
+
+fn state_edits(source: &str, block: &Block, to: TaskState) -> Vec<(Range<usize>, String)> {
+    let BlockKind::ListItem { checkbox, .. } = &block.kind else {
+        return Vec::new();
+    };
+    let text = text_range(block);
+    let mut edits = Vec::new();
+    let keyword = keyword_len(source, text.start).map(|len| text.start..text.start + len);
+
+    match keyword {
+        Some(range) if &source[range.clone()] != to.keyword() => {
+            edits.push((range, to.keyword().to_string()));
+        }
+        None if checkbox.is_some() && matches!(to, TaskState::Doing | TaskState::Later) => {
+            edits.push((text.start..text.start, format!("{} ", to.keyword())));
+        }
+        _ => {}
+    }
+    if let Some(checkbox) = checkbox
+        && checkbox.checked != (to == TaskState::Done)
+    {
+        let mark = if to == TaskState::Done { "[x]" } else { "[ ]" };
+        edits.push((checkbox.byte_range.clone(), mark.to_string()));
+    }
+    edits
+}
+
+fn property_edits(
+    source: &str,
+    block: &Block,
+    name: &str,
+    value: Option<&str>,
+) -> Vec<(Range<usize>, String)> {
+    let text = text_range(block);
+    match (find_property(source, text.clone(), name), value) {
+        (Some(found), Some(value)) if &source[found.value.clone()] != value => {
+            // `name::value` gains a space
+            let text = if found.value.start == found.separator_end {
+                format!(" {value}")
+            } else {
+                value.to_string()
+            };
+            vec![(found.separator_end..found.value.end, text)]
+        }
+        (Some(found), None) => {
+            let line_start = source[..found.property.start].rfind('\n');
+            let before = &source[line_start.map_or(0, |i| i + 1)..found.property.start];
+            let start = match line_start {
+                // A property on a line of its own goes with its line break
+                Some(i) if before.trim().is_empty() && i >= text.start => i,
+                _ => found.property.start - (before.len() - before.trim_end().len()),
+            };
+            vec![(start..found.property.end, String::new())]
+        }
+        (None, Some(value)) => {
+            let space = if text.is_empty() { "" } else { " " };
+            vec![(text.end..text.end, format!("{space}{name}:: {value}"))]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Where a `name:: value` property is.
+struct FoundProperty {
+    /// From the name to the end of the value
+    property: Range<usize>,
+    /// End of the `::`
+    separator_end: usize,
+    /// The value, trimmed
+    value: Range<usize>,
+}
+
+/// The first `name:: value` property (name matched case-insensitively) in
+/// `range` of `source`. The value runs to the end of its line.
+fn find_property(source: &str, range: Range<usize>, name: &str) -> Option<FoundProperty> {
+    let mut line_start = range.start;
+    for line in source[range].split('\n') {
+        let mut search = 0;
+        while let Some(i) = line[search..].find("::") {
+            let separator = search + i;
+            let name_start = line[..separator]
+                .rfind(char::is_whitespace)
+                .map_or(0, |i| i + 1);
+            if line[name_start..separator].eq_ignore_ascii_case(name) {
+                let after = &line[separator + 2..];
+                let value_start = separator + 2 + (after.len() - after.trim_start().len());
+                let value_end = line.trim_end().len().max(value_start);
+                return Some(FoundProperty {
+                    property: line_start + name_start..line_start + value_end,
+                    separator_end: line_start + separator + 2,
+                    value: line_start + value_start..line_start + value_end,
+                });
+            }
+            search = separator + 2;
+        }
+        line_start += line.len() + 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{create_test_file, create_test_notes_dir};
+
+    fn notes(notes: &[(&str, &str)]) -> Vec<(RelativePathBuf, String)> {
+        notes
+            .iter()
+            .map(|(path, source)| (RelativePathBuf::from(*path), source.to_string()))
+            .collect()
+    }
+
+    fn titles(board: &Board) -> Vec<(String, Vec<String>)> {
+        board
+            .columns
+            .iter()
+            .map(|column| {
+                let cards = column.cards.iter().map(|card| card.text.clone()).collect();
+                (column.title.clone(), cards)
+            })
+            .collect()
+    }
+
+    /// Move the card titled `title` and return the note's new text.
+    fn moved(source: &str, title: &str, to: ColumnKey) -> String {
+        let board = Board::build(notes(&[("a.md", source)]), GroupBy::State);
+        let card = board
+            .columns
+            .iter()
+            .flat_map(|column| &column.cards)
+            .find(|card| card.text == title)
+            .unwrap();
+        let mut doc = Document::from_bytes(source.as_bytes()).unwrap();
+        for cmd in move_commands(&doc, card.id, &to).unwrap() {
+            doc.apply(cmd);
+        }
+        doc.text()
+    }
+
+    #[test]
+    fn test_board_by_state() {
+        let board = Board::build(
+            notes(&[
+                (
+                    "b.md",
+                    "- [ ] Buy milk\n- [x] Call mum\n- Plain\n  - DOING Nested\n",
+                ),
+                ("a.md", "# Work\n\n- TODO Report\n- DONE Review\n"),
+            ]),
+            GroupBy::State,
+        );
+        assert_eq!(
+            titles(&board),
+            [
+                ("TODO".into(), vec!["Report".into(), "Buy milk".into()]),
+                ("DOING".into(), vec!["Nested".into()]),
+                ("DONE".into(), vec!["Review".into(), "Call mum".into()]),
+            ]
+        );
+        let report = &board.columns[0].cards[0];
+        assert_eq!((report.path.as_str(), report.line), ("a.md", 3));
+        assert_eq!(
+            board.column_of(RelativePath::new("b.md"), board.columns[1].cards[0].id),
+            Some(&board.columns[1])
+        );
+
+        let board = Board::build(notes(&[("a.md", "- LATER Someday\n")]), GroupBy::State);
+        assert_eq!(board.columns.len(), 4);
+        assert_eq!(board.columns[0].key, ColumnKey::State(TaskState::Later));
+    }
+
+    #[test]
+    fn test_board_by_property() {
+        let group_by = GroupBy::Property {
+            name: "status".to_string(),
+            values: vec!["ready".to_string(), "blocked".to_string()],
+        };
+        let board = Board::build(
+            notes(&[(
+                "a.md",
+                "- TODO Roof Status:: blocked\n- [ ] Paint\n  status:: waiting\n- [ ] Tidy\n- DONE Old status:: ready\n",
+            )]),
+            group_by,
+        );
+        assert_eq!(
+            titles(&board),
+            [
+                ("No status".into(), vec!["Tidy".into()]),
+                ("ready".into(), vec!["Old status:: ready".into()]),
+                ("blocked".into(), vec!["Roof Status:: blocked".into()]),
+                ("waiting".into(), vec!["Paint".into()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_move_between_states() {
+        let source = "- TODO Report\n- [ ] Milk\n- [x] DOING Mixed\n";
+        let state = ColumnKey::State;
+        assert_eq!(
+            moved(source, "Report", state(TaskState::Doing)),
+            "- DOING Report\n- [ ] Milk\n- [x] DOING Mixed\n"
+        );
+        assert_eq!(
+            moved(source, "Milk", state(TaskState::Done)),
+            "- TODO Report\n- [x] Milk\n- [x] DOING Mixed\n"
+        );
+        assert_eq!(
+            moved(source, "Milk", state(TaskState::Later)),
+            "- TODO Report\n- [ ] LATER Milk\n- [x] DOING Mixed\n"
+        );
+        assert_eq!(
+            moved(source, "Mixed", state(TaskState::Done)),
+            "- TODO Report\n- [ ] Milk\n- [x] DONE Mixed\n"
+        );
+        assert_eq!(
+            moved(source, "Mixed", state(TaskState::Todo)),
+            "- TODO Report\n- [ ] Milk\n- [ ] TODO Mixed\n"
+        );
+        assert_eq!(moved(source, "Report", state(TaskState::Todo)), source);
+
+        let doc = Document::from_bytes(b"- Plain\n").unwrap();
+        let id = doc.snapshot().blocks[0].id;
+        assert!(matches!(
+            move_commands(&doc, id, &state(TaskState::Done)),
+            Err(KanbanError::CardNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_move_between_property_values() {
+        let source = "- TODO Roof status::blocked\n- [ ] Paint\n  status:: waiting\n- [ ] Tidy\n";
+        let to = |value: Option<&str>| ColumnKey::Property {
+            name: "status".to_string(),
+            value: value.map(str::to_string),
+        };
+        assert_eq!(
+            moved(source, "Roof status::blocked", to(Some("ready"))),
+            "- TODO Roof status:: ready\n- [ ] Paint\n  status:: waiting\n- [ ] Tidy\n"
+        );
+        assert_eq!(
+            moved(source, "Roof status::blocked", to(None)),
+            "- TODO Roof\n- [ ] Paint\n  status:: waiting\n- [ ] Tidy\n"
+        );
+        assert_eq!(
+            moved(source, "Paint", to(None)),
+            "- TODO Roof status::blocked\n- [ ] Paint\n- [ ] Tidy\n"
+        );
+        assert_eq!(
+            moved(source, "Tidy", to(Some("ready"))),
+            "- TODO Roof status::blocked\n- [ ] Paint\n  status:: waiting\n- [ ] Tidy status:: ready\n"
+        );
+        assert_eq!(moved(source, "Tidy", to(None)), source);
+    }
+
+    #[test]
+    fn test_load_and_move_card_on_disk() {
+        let notes_dir = create_test_notes_dir();
+        create_test_file(&notes_dir, "tasks.md", "- TODO Write\n");
+        create_test_file(&notes_dir, "other.md", "- TODO Write\n");
+        let board = Board::load(notes_dir.path(), GroupBy::State).unwrap();
+        let cards = &board.columns[0].cards;
+        assert_eq!(cards.len(), 2);
+        let card = cards.iter().find(|card| card.path == "tasks.md").unwrap();
+
+        move_card(
+            notes_dir.path(),
+            &card.path,
+            card.id,
+            &ColumnKey::State(TaskState::Done),
+        )
+        .unwrap();
+        let read = |path| io::read_file(RelativePath::new(path), notes_dir.path()).unwrap();
+        assert_eq!(read("tasks.md"), "- DONE Write\n");
+        assert_eq!(read("other.md"), "- TODO Write\n");
+    }
+}
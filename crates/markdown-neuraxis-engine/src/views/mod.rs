@@ -0,0 +1,9 @@
+//! # Views
+//!
+//! Alternative ways of showing the vault's blocks, built from snapshots so
+//! every frontend draws the same thing. Edits made through a view rewrite
+//! the underlying notes.
+//!
+//! - [`kanban`]: tasks as cards in columns by state or property
+
+pub mod kanban;
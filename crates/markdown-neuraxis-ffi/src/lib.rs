@@ -10,6 +10,8 @@
 //! The current MVP uses a simple **content-passing** pattern:
 //! - Kotlin handles all file I/O via Android's Storage Access Framework (SAF)
 //! - Rust receives document content as strings, parses, and returns UI-ready snapshots
+//! - Edits are applied in Rust as commands ([`CmdDto`]), so Kotlin only reads the
+//!   text back when saving
 //! - No callbacks or workspace state crosses the FFI boundary yet
 //!
 //! ```text
@@ -28,6 +30,8 @@
 //!   blocks for rendering
 //! - [`Block`]: A single block (heading, paragraph, list, etc.) with inline segments
 //!   and nested children
+//! - [`CmdDto`] / [`PatchDto`]: Edits applied with [`DocumentHandle::apply_command`]
+//!   and what they changed
 //! - [`TextSegment`]: Inline content segment (plain text, wiki-links, emphasis, etc.)
 //!   supporting nested formatting per ADR-0013
 //!
@@ -50,7 +54,15 @@
 //! # Thread Safety
 //!
 //! [`DocumentHandle`] uses a `Mutex` internally, making it safe to share across threads.
-//! The mutex uses poison recovery to handle panics gracefully.
+//! If a panic poisons the mutex or leaves the document inconsistent, the handle
+//! rebuilds the document from the last saved text (see [`DocumentHandle::mark_saved`])
+//! and [`DocumentHandle::health_check`] reports what happened, so the app can
+//! refresh instead of crashing.
+//!
+//! Every exported function also catches panics at the boundary and returns
+//! [`FfiError::Internal`] rather than unwinding into Kotlin, keeping details
+//! for [`last_error_report`]. This needs a build that unwinds on panic: build
+//! the library with `--profile android-release`.
 //!
 //! # Future Work
 //!
@@ -63,9 +75,21 @@
 
 use markdown_neuraxis_engine::Document;
 use markdown_neuraxis_engine::editing::snapshot::{
-    self as engine, BlockContent, BlockKind, InlineNode, InlineSegment,
+    self as engine, BlockContent, BlockKind, InlineNode, InlineSegment, TaskState,
 };
-use std::sync::Mutex;
+use markdown_neuraxis_engine::editing::{self, AnchorId, FocusHint, TableAlignment};
+use markdown_neuraxis_engine::refactor::replace;
+use markdown_neuraxis_engine::vault::OpenMode;
+use markdown_neuraxis_engine::views::kanban;
+use markdown_neuraxis_engine::{annotations, dashboard, dates, io, links, read_state, titles};
+use relative_path::{RelativePath, RelativePathBuf};
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, Once};
 
 uniffi::setup_scaffolding!();
 
@@ -77,6 +101,110 @@ uniffi::setup_scaffolding!();
 pub enum FfiError {
     #[error("Parse error: {reason}")]
     ParseError { reason: String },
+    #[error("Invalid command: {reason}")]
+    InvalidCommand { reason: String },
+    #[error("File error: {reason}")]
+    IoError { reason: String },
+    /// The file changed on disk since it was loaded or last saved
+    #[error("Save conflict: {reason}")]
+    Conflict { reason: String },
+    /// The document or vault is read-only, so edits and saves are refused
+    #[error("Read-only: {reason}")]
+    ReadOnly { reason: String },
+    /// The engine panicked; details are also kept for [`last_error_report`]
+    #[error("Internal error: {reason}")]
+    Internal { reason: String, backtrace: String },
+}
+
+impl From<io::IoError> for FfiError {
+    fn from(e: io::IoError) -> Self {
+        match e {
+            io::IoError::ReadOnly(_) => Self::ReadOnly {
+                reason: e.to_string(),
+            },
+            _ => Self::IoError {
+                reason: e.to_string(),
+            },
+        }
+    }
+}
+
+// ============ Panic Boundary ============
+
+/// Diagnostics for the last panic caught at the FFI boundary, for the app
+/// to show or attach to a bug report.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct ErrorReport {
+    /// Exported function that was running, e.g. `DocumentHandle.get_snapshot`
+    pub operation: String,
+    pub reason: String,
+    /// Where the panic happened (empty if it couldn't be captured)
+    pub backtrace: String,
+    /// Version of this library
+    pub version: String,
+}
+
+static LAST_ERROR: Mutex<Option<ErrorReport>> = Mutex::new(None);
+
+thread_local! {
+    /// Exported function running on this thread, for error reports
+    static OPERATION: Cell<&'static str> = const { Cell::new("") };
+    /// Backtrace of the last panic on this thread, left by the panic hook
+    static PANIC_BACKTRACE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// The last panic caught in any exported function, if there has been one.
+#[uniffi::export]
+pub fn last_error_report() -> Option<ErrorReport> {
+    LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Run the body of exported function `operation`, turning a panic into
+/// [`FfiError::Internal`] instead of letting it unwind into Kotlin.
+///
+/// Panics only unwind in builds with `panic = "unwind"`, such as the
+/// `android-release` profile; elsewhere the process still aborts.
+fn boundary<T>(
+    operation: &'static str,
+    f: impl FnOnce() -> Result<T, FfiError>,
+) -> Result<T, FfiError> {
+    install_panic_hook();
+    let outer = OPERATION.replace(operation);
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    OPERATION.set(outer);
+    result.unwrap_or_else(|payload| {
+        let report = report_panic(&*payload);
+        Err(FfiError::Internal {
+            reason: report.reason,
+            backtrace: report.backtrace,
+        })
+    })
+}
+
+/// Record a caught panic as the last error report.
+fn report_panic(payload: &(dyn Any + Send)) -> ErrorReport {
+    let report = ErrorReport {
+        operation: OPERATION.get().to_string(),
+        reason: panic_message(payload),
+        backtrace: PANIC_BACKTRACE.take().unwrap_or_default(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+    };
+    *LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner()) = Some(report.clone());
+    report
+}
+
+/// Capture a backtrace when a panic starts, since by the time it is caught
+/// the stack has unwound. The previous hook still runs, so panics are
+/// logged as before.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            PANIC_BACKTRACE.set(Some(Backtrace::force_capture().to_string()));
+            previous(info);
+        }));
+    });
 }
 
 // ============ Document Handle ============
@@ -85,9 +213,25 @@ pub enum FfiError {
 ///
 /// This wraps the engine's Document type and provides a simple API
 /// for mobile apps to interact with markdown content.
+///
+/// The handle keeps the last saved text so it can rebuild the document if
+/// the engine panics or its internal state becomes inconsistent. Call
+/// [`DocumentHandle::health_check`] to find out whether that happened.
 #[derive(uniffi::Object)]
 pub struct DocumentHandle {
     inner: Mutex<Document>,
+    /// Text to rebuild the document from after a failure
+    saved_text: Mutex<String>,
+    /// Outcome of the last recovery, reported by the next health check
+    last_recovery: Mutex<Option<HealthStatus>>,
+    /// Where [`DocumentHandle::save`] writes, if opened from a file
+    file: Option<NoteFile>,
+}
+
+/// A note on disk.
+struct NoteFile {
+    notes_root: PathBuf,
+    path: RelativePathBuf,
 }
 
 #[uniffi::export]
@@ -95,28 +239,862 @@ impl DocumentHandle {
     /// Create a document from markdown content string.
     #[uniffi::constructor]
     pub fn from_string(content: String) -> Result<Self, FfiError> {
+        boundary("DocumentHandle.from_string", || Self::new(content, None))
+    }
+
+    /// Open the note at `rel_path` under `notes_root`, reading it in Rust
+    /// so the text doesn't have to be copied across from Kotlin. Save it
+    /// back with [`save`](Self::save).
+    ///
+    /// Notes in a folder that can't be written to open
+    /// [read-only](Self::is_read_only).
+    #[uniffi::constructor]
+    pub fn from_file(notes_root: String, rel_path: String) -> Result<Self, FfiError> {
+        boundary("DocumentHandle.from_file", || {
+            let file = NoteFile {
+                notes_root: PathBuf::from(notes_root),
+                path: io::relative_path_from_str(&rel_path),
+            };
+            let content = io::read_file(&file.path, &file.notes_root)?;
+            let read_only = OpenMode::detect(&file.notes_root).is_read_only();
+            let handle = Self::new(content, Some(file))?;
+            handle.with_document_mut(|doc| doc.set_read_only(read_only))?;
+            Ok(handle)
+        })
+    }
+
+    /// Refuse edits and saves with [`FfiError::ReadOnly`], e.g. for a vault
+    /// the user opened read-only, or allow them again.
+    pub fn set_read_only(&self, read_only: bool) -> Result<(), FfiError> {
+        boundary("DocumentHandle.set_read_only", || {
+            self.with_document_mut(|doc| doc.set_read_only(read_only))
+        })
+    }
+
+    /// Whether edits are refused, for greying out editing controls.
+    pub fn is_read_only(&self) -> Result<bool, FfiError> {
+        boundary("DocumentHandle.is_read_only", || {
+            self.with_document(|doc| doc.is_read_only())
+        })
+    }
+
+    /// Mark the block `block_id` (`Block.id`) as the one being written, for
+    /// focus and typewriter mode, or clear the mark with `None`. Snapshots
+    /// then carry each block's `focus` hint and the block's line and
+    /// sentence ranges in `Snapshot.active`.
+    pub fn set_active_block(&self, block_id: Option<String>) -> Result<(), FfiError> {
+        boundary("DocumentHandle.set_active_block", || {
+            let block_id = block_id
+                .map(|id| {
+                    id.parse().map(AnchorId).map_err(|_| FfiError::ParseError {
+                        reason: format!("invalid block id '{id}'"),
+                    })
+                })
+                .transpose()?;
+            self.with_document_mut(|doc| doc.set_active_block(block_id))
+        })
+    }
+
+    /// The block marked with [`set_active_block`](Self::set_active_block).
+    pub fn active_block(&self) -> Result<Option<String>, FfiError> {
+        boundary("DocumentHandle.active_block", || {
+            self.with_document(|doc| doc.active_block().map(|id| id.0.to_string()))
+        })
+    }
+
+    /// Move the task `block_id` to another kanban column by rewriting its
+    /// state keyword, checkbox or property; see [`kanban_board`]. Returns
+    /// false if it was already in that column.
+    pub fn move_kanban_card(
+        &self,
+        block_id: String,
+        to: KanbanColumnKeyDto,
+    ) -> Result<bool, FfiError> {
+        boundary("DocumentHandle.move_kanban_card", || {
+            let id = parse_block_id(&block_id)?;
+            let to = to.to_engine();
+            self.with_document_mut(|doc| {
+                let cmds = kanban::move_commands(doc, id, &to)?;
+                let moved = !cmds.is_empty();
+                for cmd in cmds {
+                    doc.try_apply(cmd).map_err(|e| FfiError::ReadOnly {
+                        reason: e.to_string(),
+                    })?;
+                }
+                Ok(moved)
+            })?
+        })
+    }
+
+    /// Write the document back to the file it was opened from, unless the
+    /// file was changed by something else since it was opened or last
+    /// saved; that fails with [`FfiError::Conflict`].
+    pub fn save(&self) -> Result<(), FfiError> {
+        self.save_with(false)
+    }
+
+    /// [`save`](Self::save), replacing the file even if it has changed when
+    /// `overwrite` is set.
+    pub fn save_with(&self, overwrite: bool) -> Result<(), FfiError> {
+        boundary("DocumentHandle.save", || {
+            let Some(file) = &self.file else {
+                return Err(FfiError::IoError {
+                    reason: "document was not opened from a file".to_string(),
+                });
+            };
+            // Before locking `saved_text`, which recovering the document needs
+            let (text, read_only) = self.with_document(|doc| (doc.text(), doc.is_read_only()))?;
+            if read_only {
+                return Err(FfiError::ReadOnly {
+                    reason: format!("{} is read-only", file.path),
+                });
+            }
+            let mut saved = self.saved_text.lock().unwrap_or_else(|e| e.into_inner());
+            if !overwrite {
+                match io::read_file(&file.path, &file.notes_root) {
+                    Ok(on_disk) if on_disk != *saved => {
+                        return Err(FfiError::Conflict {
+                            reason: format!("{} changed on disk", file.path),
+                        });
+                    }
+                    // A deleted file is simply written again
+                    Ok(_) | Err(io::IoError::NotFound(_)) => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            io::replace_file(&file.path, &file.notes_root, &text)?;
+            *saved = text;
+            Ok(())
+        })
+    }
+
+    /// Get the current text content of the document.
+    pub fn get_text(&self) -> Result<String, FfiError> {
+        boundary("DocumentHandle.get_text", || {
+            self.with_document(|doc| doc.text())
+        })
+    }
+
+    /// Get a snapshot of the document for UI rendering.
+    pub fn get_snapshot(&self) -> Result<Snapshot, FfiError> {
+        self.get_snapshot_with(None)
+    }
+
+    /// Get a snapshot with each block's inline content cut to about
+    /// `max_content_bytes`, so lists of huge blocks render quickly.
+    ///
+    /// Cut blocks have `is_truncated` set; fetch them whole with
+    /// [`get_block_content`](Self::get_block_content) when needed.
+    pub fn get_snapshot_with(&self, max_content_bytes: Option<u64>) -> Result<Snapshot, FfiError> {
+        boundary("DocumentHandle.get_snapshot", || {
+            self.snapshot(max_content_bytes)
+        })
+    }
+
+    /// Like [`get_snapshot_with`](Self::get_snapshot_with), with each
+    /// block's `annotation_count` filled in from `annotations` for the
+    /// note at `path`.
+    pub fn get_annotated_snapshot(
+        &self,
+        path: String,
+        annotations: Arc<AnnotationsHandle>,
+        max_content_bytes: Option<u64>,
+    ) -> Result<Snapshot, FfiError> {
+        boundary("DocumentHandle.get_annotated_snapshot", || {
+            let counts: HashMap<String, u32> = {
+                let annotations = annotations.lock();
+                self.with_document(|doc| {
+                    annotations
+                        .open_counts(RelativePath::new(&path), doc)
+                        .into_iter()
+                        .map(|(id, count)| (id.0.to_string(), count as u32))
+                        .collect()
+                })?
+            };
+            let mut snapshot = self.snapshot(max_content_bytes)?;
+            set_annotation_counts(&mut snapshot.blocks, &counts);
+            Ok(snapshot)
+        })
+    }
+
+    /// Like [`get_snapshot_with`](Self::get_snapshot_with), with note
+    /// embeds (`![[Note]]`, `![[Note#Heading]]`) filled in from the notes
+    /// under `notes_root`. `path` is this note's path, so it isn't embedded
+    /// in itself. Embedded blocks are read-only: open their `source_path`
+    /// to edit them.
+    pub fn get_snapshot_with_embeds(
+        &self,
+        notes_root: String,
+        path: Option<String>,
+        max_content_bytes: Option<u64>,
+    ) -> Result<Snapshot, FfiError> {
+        boundary("DocumentHandle.get_snapshot_with_embeds", || {
+            let path = path.map(|p| io::relative_path_from_str(&p));
+            self.snapshot_resolving(max_content_bytes, |snapshot| {
+                editing::embeds::resolve_vault_embeds(
+                    snapshot,
+                    Path::new(&notes_root),
+                    path.as_deref(),
+                )
+            })
+        })
+    }
+
+    /// The byte offset for `utf16_offset` (e.g. a caret position in a
+    /// Kotlin string of the whole text), for use in [`CmdDto`]. None if the
+    /// offset is past the end or inside a surrogate pair.
+    pub fn byte_offset_from_utf16(&self, utf16_offset: u64) -> Result<Option<u64>, FfiError> {
+        boundary("DocumentHandle.byte_offset_from_utf16", || {
+            self.with_document(|doc| {
+                doc.offset_of_utf16(usize::try_from(utf16_offset).ok()?)
+                    .map(|byte| byte as u64)
+            })
+        })
+    }
+
+    /// The UTF-16 offset of `byte_offset`, e.g. to place a caret after
+    /// [`apply_command`](Self::apply_command). None if the offset is past the
+    /// end or inside a character.
+    pub fn utf16_offset_from_byte(&self, byte_offset: u64) -> Result<Option<u64>, FfiError> {
+        boundary("DocumentHandle.utf16_offset_from_byte", || {
+            self.with_document(|doc| {
+                doc.utf16_of_offset(usize::try_from(byte_offset).ok()?)
+                    .map(|utf16| utf16 as u64)
+            })
+        })
+    }
+
+    /// The whole block with `id`, including its children, or None if no
+    /// block has that id.
+    pub fn get_block_content(&self, id: String) -> Result<Option<Block>, FfiError> {
+        boundary("DocumentHandle.get_block_content", || {
+            Ok(find_block(self.snapshot(None)?.blocks, &id))
+        })
+    }
+
+    /// Id of the block to scroll to when reopening at `position`.
+    ///
+    /// Falls back to the nearest block if the note changed since the
+    /// position was saved; returns None only for an empty document.
+    pub fn resolve_read_position(
+        &self,
+        position: ReadPosition,
+    ) -> Result<Option<String>, FfiError> {
+        boundary("DocumentHandle.resolve_read_position", || {
+            let position = position.to_engine()?;
+            self.with_document(|doc| position.resolve(&doc.snapshot()).map(|id| id.0.to_string()))
+        })
+    }
+
+    /// Apply an edit, returning what changed so the UI can update its
+    /// selection and refresh the affected blocks.
+    pub fn apply_command(&self, cmd: CmdDto) -> Result<PatchDto, FfiError> {
+        boundary("DocumentHandle.apply_command", || {
+            self.with_document_mut(|doc| {
+                let cmd = cmd.to_engine(&doc.text())?;
+                let patch = doc.try_apply(cmd).map_err(|e| FfiError::ReadOnly {
+                    reason: e.to_string(),
+                })?;
+                Ok(PatchDto::from_engine(patch))
+            })?
+        })
+    }
+
+    /// Undo the last edit. Returns false if there was nothing to undo.
+    pub fn undo(&self) -> Result<bool, FfiError> {
+        boundary("DocumentHandle.undo", || {
+            self.with_document_mut(|doc| doc.undo().is_some())
+        })
+    }
+
+    /// Redo the last undone edit. Returns false if there was nothing to redo.
+    pub fn redo(&self) -> Result<bool, FfiError> {
+        boundary("DocumentHandle.redo", || {
+            self.with_document_mut(|doc| doc.redo().is_some())
+        })
+    }
+
+    /// Whether [`undo`](Self::undo) would change anything, for enabling the
+    /// undo button.
+    pub fn can_undo(&self) -> Result<bool, FfiError> {
+        boundary("DocumentHandle.can_undo", || {
+            self.with_document(|doc| doc.can_undo())
+        })
+    }
+
+    /// Whether [`redo`](Self::redo) would change anything.
+    pub fn can_redo(&self) -> Result<bool, FfiError> {
+        boundary("DocumentHandle.can_redo", || {
+            self.with_document(|doc| doc.can_redo())
+        })
+    }
+
+    /// Record the current text as saved, e.g. after Kotlin writes the file.
+    /// Recovery rebuilds the document from this text.
+    pub fn mark_saved(&self) -> Result<(), FfiError> {
+        boundary("DocumentHandle.mark_saved", || {
+            let text = self.with_document(|doc| doc.text())?;
+            *self.saved_text.lock().unwrap_or_else(|e| e.into_inner()) = text;
+            Ok(())
+        })
+    }
+
+    /// Check the document is usable, rebuilding it from the last saved text
+    /// if its internal state is inconsistent.
+    ///
+    /// Also reports any recovery made since the last check (after a panic or
+    /// a lock poisoned by another thread), so the app can tell the user that
+    /// unsaved changes were lost and refresh its view.
+    pub fn health_check(&self) -> Result<HealthStatus, FfiError> {
+        boundary("DocumentHandle.health_check", || {
+            {
+                let mut doc = self.lock_document();
+                let problems = doc.invariant_violations();
+                if !problems.is_empty() {
+                    self.reload_saved(&mut doc, problems.join("; "));
+                }
+            }
+            Ok(self
+                .last_recovery
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .take()
+                .unwrap_or(HealthStatus::Healthy))
+        })
+    }
+}
+
+impl DocumentHandle {
+    fn new(content: String, file: Option<NoteFile>) -> Result<Self, FfiError> {
         let doc = Document::from_bytes(content.as_bytes()).map_err(|e| FfiError::ParseError {
             reason: e.to_string(),
         })?;
 
         Ok(Self {
             inner: Mutex::new(doc),
+            saved_text: Mutex::new(content),
+            last_recovery: Mutex::new(None),
+            file,
         })
     }
 
-    /// Get the current text content of the document.
-    pub fn get_text(&self) -> String {
-        // Recover from poisoned mutex (another thread panicked while holding lock)
-        let doc = self.inner.lock().unwrap_or_else(|e| e.into_inner());
-        doc.text()
+    fn snapshot(&self, max_content_bytes: Option<u64>) -> Result<Snapshot, FfiError> {
+        self.snapshot_resolving(max_content_bytes, |_| {})
     }
 
-    /// Get a snapshot of the document for UI rendering.
-    pub fn get_snapshot(&self) -> Snapshot {
-        // Recover from poisoned mutex (another thread panicked while holding lock)
-        let doc = self.inner.lock().unwrap_or_else(|e| e.into_inner());
-        let snapshot = doc.snapshot();
-        Snapshot::from_engine(snapshot)
+    /// [`snapshot`](Self::snapshot) with `resolve` run on the engine
+    /// snapshot before it's converted.
+    fn snapshot_resolving(
+        &self,
+        max_content_bytes: Option<u64>,
+        resolve: impl FnOnce(&mut engine::Snapshot),
+    ) -> Result<Snapshot, FfiError> {
+        let mut snapshot = self.with_document(|doc| {
+            let mut snapshot = doc.snapshot();
+            resolve(&mut snapshot);
+            Snapshot::from_engine(snapshot, &doc.text())
+        })?;
+        if let Some(max) = max_content_bytes {
+            truncate_blocks(&mut snapshot.blocks, max as usize);
+        }
+        Ok(snapshot)
+    }
+
+    /// Run `f` against the document. If `f` panics, the document is
+    /// rebuilt from the last saved text and the call fails with
+    /// [`FfiError::Internal`]; `f` is not run again, since offsets it was
+    /// given belong to the text that was lost. The app finds out through
+    /// [`health_check`](Self::health_check) and refreshes.
+    fn with_document<T>(&self, f: impl FnOnce(&Document) -> T) -> Result<T, FfiError> {
+        self.with_document_mut(|doc| f(doc))
+    }
+
+    /// Like [`with_document`](Self::with_document), for changes.
+    fn with_document_mut<T>(&self, f: impl FnOnce(&mut Document) -> T) -> Result<T, FfiError> {
+        let mut doc = self.lock_document();
+        panic::catch_unwind(AssertUnwindSafe(|| f(&mut doc))).map_err(|payload| {
+            let report = report_panic(&*payload);
+            self.reload_saved(&mut doc, format!("panic: {}", report.reason));
+            FfiError::Internal {
+                reason: report.reason,
+                backtrace: report.backtrace,
+            }
+        })
+    }
+
+    /// Lock the document, rebuilding it if another thread panicked while
+    /// holding the lock and may have left it half-edited.
+    fn lock_document(&self) -> MutexGuard<'_, Document> {
+        match self.inner.lock() {
+            Ok(doc) => doc,
+            Err(poisoned) => {
+                let mut doc = poisoned.into_inner();
+                self.inner.clear_poison();
+                self.reload_saved(
+                    &mut doc,
+                    "lock poisoned by a panic on another thread".to_string(),
+                );
+                doc
+            }
+        }
+    }
+
+    /// Replace the document with a fresh parse of the last saved text and
+    /// record the outcome for the next health check.
+    fn reload_saved(&self, doc: &mut Document, reason: String) {
+        let saved = self.saved_text.lock().unwrap_or_else(|e| e.into_inner());
+        let status = match Document::from_bytes(saved.as_bytes()) {
+            Ok(mut fresh) => {
+                fresh.set_read_only(doc.is_read_only());
+                *doc = fresh;
+                HealthStatus::Recovered { reason }
+            }
+            Err(e) => HealthStatus::Failed {
+                reason: format!("{reason}; reload failed: {e}"),
+            },
+        };
+        *self.last_recovery.lock().unwrap_or_else(|e| e.into_inner()) = Some(status);
+    }
+}
+
+/// Text of a caught panic payload.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// Result of [`DocumentHandle::health_check`].
+#[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+pub enum HealthStatus {
+    /// Nothing went wrong
+    Healthy,
+    /// The document was rebuilt from its last saved text; unsaved edits are
+    /// lost and any cached snapshot should be refreshed
+    Recovered { reason: String },
+    /// The document could not be rebuilt; reopen the file from storage
+    Failed { reason: String },
+}
+
+// ============ Editing ============
+
+/// An edit for [`DocumentHandle::apply_command`]. Positions are byte
+/// offsets into the document text and must fall on character boundaries.
+#[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+pub enum CmdDto {
+    /// Insert `text` at `at`
+    InsertText { at: u64, text: String },
+    /// Delete the bytes from `start` to `end`
+    DeleteRange { start: u64, end: u64 },
+    /// Start a new list item at `at`, continuing the current item's marker
+    SplitListItem { at: u64 },
+    /// Indent every line touching `start..end` by one level
+    IndentLines { start: u64, end: u64 },
+    /// Outdent every line touching `start..end` by one level
+    OutdentLines { start: u64, end: u64 },
+    /// Set the list marker of the line starting at `line_start`
+    ToggleMarker { line_start: u64, marker: MarkerDto },
+    /// Insert `date` (`YYYY-MM-DD`) at `at`, written with the `date_format`
+    /// setting `format`: a strftime pattern or `link`. `None` writes
+    /// `YYYY-MM-DD`.
+    InsertDate {
+        at: u64,
+        date: String,
+        format: Option<String>,
+    },
+}
+
+/// A list marker for [`CmdDto::ToggleMarker`].
+#[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+pub enum MarkerDto {
+    Dash,
+    Asterisk,
+    Plus,
+    /// Ordered marker such as `1.` (without the trailing space)
+    Numbered {
+        label: String,
+    },
+}
+
+impl CmdDto {
+    /// The engine command, after checking its offsets against `text`.
+    fn to_engine(&self, text: &str) -> Result<editing::Cmd, FfiError> {
+        let offset = |at: u64| -> Result<usize, FfiError> {
+            usize::try_from(at)
+                .ok()
+                .filter(|&at| text.is_char_boundary(at))
+                .ok_or_else(|| FfiError::InvalidCommand {
+                    reason: format!("offset {at} is not a character boundary in the document"),
+                })
+        };
+        let range = |start: u64, end: u64| -> Result<std::ops::Range<usize>, FfiError> {
+            let (start, end) = (offset(start)?, offset(end)?);
+            if start > end {
+                return Err(FfiError::InvalidCommand {
+                    reason: format!("range {start}..{end} ends before it starts"),
+                });
+            }
+            Ok(start..end)
+        };
+
+        Ok(match self {
+            CmdDto::InsertText { at, text } => editing::Cmd::InsertText {
+                at: offset(*at)?,
+                text: text.clone(),
+            },
+            CmdDto::DeleteRange { start, end } => editing::Cmd::DeleteRange {
+                range: range(*start, *end)?,
+            },
+            CmdDto::SplitListItem { at } => editing::Cmd::SplitListItem { at: offset(*at)? },
+            CmdDto::IndentLines { start, end } => editing::Cmd::IndentLines {
+                range: range(*start, *end)?,
+            },
+            CmdDto::OutdentLines { start, end } => editing::Cmd::OutdentLines {
+                range: range(*start, *end)?,
+            },
+            CmdDto::ToggleMarker { line_start, marker } => editing::Cmd::ToggleMarker {
+                line_start: offset(*line_start)?,
+                to: match marker {
+                    MarkerDto::Dash => editing::Marker::Dash,
+                    MarkerDto::Asterisk => editing::Marker::Asterisk,
+                    MarkerDto::Plus => editing::Marker::Plus,
+                    MarkerDto::Numbered { label } => editing::Marker::Numbered(label.clone()),
+                },
+            },
+            CmdDto::InsertDate { at, date, format } => editing::Cmd::InsertDate {
+                at: offset(*at)?,
+                spec: dates::DateSpec {
+                    date: date.parse().map_err(|e| FfiError::InvalidCommand {
+                        reason: format!("invalid date {date:?}: {e}"),
+                    })?,
+                    format: match format {
+                        Some(format) => format.parse().map_err(|e: dates::DateError| {
+                            FfiError::InvalidCommand {
+                                reason: e.to_string(),
+                            }
+                        })?,
+                        None => dates::DateFormat::default(),
+                    },
+                },
+            },
+        })
+    }
+}
+
+/// A byte range in the document text.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ByteRange {
+    fn from_engine(range: &std::ops::Range<usize>) -> Self {
+        Self {
+            start: range.start as u64,
+            end: range.end as u64,
+        }
+    }
+}
+
+/// A range in the document text in UTF-16 code units, as Java and Kotlin
+/// strings index it.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct Utf16Range {
+    pub start: u64,
+    pub end: u64,
+}
+
+/// Converts byte offsets in a text to UTF-16 offsets.
+struct Utf16Index {
+    /// End byte offset of each non-ASCII character, with how many more
+    /// bytes than UTF-16 code units the text has up to there
+    excess: Vec<(usize, usize)>,
+}
+
+impl Utf16Index {
+    fn new(text: &str) -> Self {
+        let mut excess = Vec::new();
+        let mut total = 0;
+        for (at, c) in text.char_indices().filter(|(_, c)| !c.is_ascii()) {
+            total += c.len_utf8() - c.len_utf16();
+            excess.push((at + c.len_utf8(), total));
+        }
+        Self { excess }
+    }
+
+    fn to_utf16(&self, byte: usize) -> usize {
+        let i = self.excess.partition_point(|&(end, _)| end <= byte);
+        byte - i.checked_sub(1).map_or(0, |i| self.excess[i].1)
+    }
+
+    fn range(&self, range: &std::ops::Range<usize>) -> Utf16Range {
+        Utf16Range {
+            start: self.to_utf16(range.start) as u64,
+            end: self.to_utf16(range.end) as u64,
+        }
+    }
+}
+
+/// Result of [`DocumentHandle::apply_command`].
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct PatchDto {
+    /// Ranges of inserted text, as offsets into the new document text
+    pub changed: Vec<ByteRange>,
+    /// Where the selection moved to
+    pub selection: ByteRange,
+    /// Document version after the edit
+    pub version: u64,
+}
+
+impl PatchDto {
+    fn from_engine(patch: editing::Patch) -> Self {
+        Self {
+            changed: patch.changed.iter().map(ByteRange::from_engine).collect(),
+            selection: ByteRange::from_engine(&patch.new_selection),
+            version: patch.version,
+        }
+    }
+}
+
+// ============ Read State ============
+
+/// Per-note read positions, persisted by Kotlin as text (see
+/// `markdown_neuraxis_engine::read_state::READ_STATE_PATH`).
+#[derive(uniffi::Object)]
+pub struct ReadStateHandle {
+    inner: Mutex<read_state::ReadState>,
+}
+
+#[uniffi::export]
+impl ReadStateHandle {
+    /// Load read state from the saved file contents (empty string for none).
+    #[uniffi::constructor]
+    pub fn from_string(content: String) -> Result<Self, FfiError> {
+        boundary("ReadStateHandle.from_string", || {
+            Ok(Self {
+                inner: Mutex::new(read_state::ReadState::parse(&content)),
+            })
+        })
+    }
+
+    /// File contents to save.
+    pub fn to_text(&self) -> Result<String, FfiError> {
+        boundary("ReadStateHandle.to_text", || {
+            let state = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+            Ok(state.to_text())
+        })
+    }
+
+    /// Where the note at `path` was last read.
+    pub fn get(&self, path: String) -> Result<Option<ReadPosition>, FfiError> {
+        boundary("ReadStateHandle.get", || {
+            let state = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+            Ok(state
+                .get(RelativePath::new(&path))
+                .map(ReadPosition::from_engine))
+        })
+    }
+
+    /// Record where the note at `path` is being read.
+    pub fn set(&self, path: String, position: ReadPosition) -> Result<(), FfiError> {
+        boundary("ReadStateHandle.set", || {
+            let position = position.to_engine()?;
+            let mut state = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+            state.set(RelativePath::new(&path), position);
+            Ok(())
+        })
+    }
+
+    /// Forget the note at `path`.
+    pub fn remove(&self, path: String) -> Result<(), FfiError> {
+        boundary("ReadStateHandle.remove", || {
+            let mut state = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+            state.remove(RelativePath::new(&path));
+            Ok(())
+        })
+    }
+}
+
+// ============ Annotations ============
+
+/// Review comments on blocks, persisted by Kotlin as text (see
+/// `markdown_neuraxis_engine::annotations::ANNOTATIONS_PATH`).
+#[derive(uniffi::Object)]
+pub struct AnnotationsHandle {
+    inner: Mutex<annotations::Annotations>,
+}
+
+impl AnnotationsHandle {
+    fn lock(&self) -> MutexGuard<'_, annotations::Annotations> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[uniffi::export]
+impl AnnotationsHandle {
+    /// Load annotations from the saved file contents (empty string for none).
+    #[uniffi::constructor]
+    pub fn from_string(content: String) -> Result<Self, FfiError> {
+        boundary("AnnotationsHandle.from_string", || {
+            let parsed = if content.trim().is_empty() {
+                annotations::Annotations::default()
+            } else {
+                annotations::Annotations::parse(&content).map_err(|e| FfiError::ParseError {
+                    reason: e.to_string(),
+                })?
+            };
+            Ok(Self {
+                inner: Mutex::new(parsed),
+            })
+        })
+    }
+
+    /// File contents to save.
+    pub fn to_text(&self) -> Result<String, FfiError> {
+        boundary("AnnotationsHandle.to_text", || Ok(self.lock().to_text()))
+    }
+
+    /// Comment on the block `block_id` (`Block.id`) of the note at `path`,
+    /// returning the new annotation's id.
+    pub fn add(
+        &self,
+        path: String,
+        doc: Arc<DocumentHandle>,
+        block_id: String,
+        body: String,
+    ) -> Result<u64, FfiError> {
+        boundary("AnnotationsHandle.add", || {
+            let block_id = AnchorId(block_id.parse().map_err(|_| FfiError::ParseError {
+                reason: format!("invalid block id '{block_id}'"),
+            })?);
+            doc.with_document(|doc| {
+                self.lock().add(
+                    RelativePath::new(&path),
+                    doc,
+                    block_id,
+                    &body,
+                    jiff::Timestamp::now(),
+                )
+            })?
+            .ok_or_else(|| FfiError::InvalidCommand {
+                reason: format!("no block with id {}", block_id.0),
+            })
+        })
+    }
+
+    /// A note's annotations, oldest first, with the block each is on now.
+    pub fn list(
+        &self,
+        path: String,
+        doc: Arc<DocumentHandle>,
+    ) -> Result<Vec<AnnotationDto>, FfiError> {
+        boundary("AnnotationsHandle.list", || {
+            let annotations = self.lock();
+            doc.with_document(|doc| {
+                annotations
+                    .locate(RelativePath::new(&path), doc)
+                    .into_iter()
+                    .map(|(annotation, block)| AnnotationDto {
+                        id: annotation.id,
+                        block_id: block.map(|id| id.0.to_string()),
+                        quote: annotation.quote.clone(),
+                        body: annotation.body.clone(),
+                        created: annotation.created.to_string(),
+                        resolved: annotation.resolved,
+                    })
+                    .collect()
+            })
+        })
+    }
+
+    /// Mark an annotation resolved, or open again. False if the note has
+    /// no annotation with `id`.
+    pub fn set_resolved(&self, path: String, id: u64, resolved: bool) -> Result<bool, FfiError> {
+        boundary("AnnotationsHandle.set_resolved", || {
+            Ok(self
+                .lock()
+                .set_resolved(RelativePath::new(&path), id, resolved))
+        })
+    }
+
+    /// Delete an annotation. False if the note has no annotation with `id`.
+    pub fn delete(&self, path: String, id: u64) -> Result<bool, FfiError> {
+        boundary("AnnotationsHandle.delete", || {
+            Ok(self.lock().delete(RelativePath::new(&path), id).is_some())
+        })
+    }
+
+    /// Record where the note's annotations are now; call after saving it.
+    pub fn refresh(&self, path: String, doc: Arc<DocumentHandle>) -> Result<(), FfiError> {
+        boundary("AnnotationsHandle.refresh", || {
+            doc.with_document(|doc| self.lock().refresh(RelativePath::new(&path), doc))
+        })
+    }
+
+    /// Keep a note's annotations when it is renamed.
+    pub fn rename(&self, from: String, to: String) -> Result<(), FfiError> {
+        boundary("AnnotationsHandle.rename", || {
+            self.lock()
+                .rename(RelativePath::new(&from), RelativePath::new(&to));
+            Ok(())
+        })
+    }
+
+    /// Forget the note at `path`.
+    pub fn remove(&self, path: String) -> Result<(), FfiError> {
+        boundary("AnnotationsHandle.remove", || {
+            self.lock().remove(RelativePath::new(&path));
+            Ok(())
+        })
+    }
+}
+
+/// A review comment on a block.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct AnnotationDto {
+    pub id: u64,
+    /// Block it's on now (`Block.id`), or None if the block is gone
+    pub block_id: Option<String>,
+    /// Start of the block's text when last seen
+    pub quote: String,
+    pub body: String,
+    /// RFC 3339 timestamp
+    pub created: String,
+    pub resolved: bool,
+}
+
+/// Where a note was last read.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct ReadPosition {
+    /// Id of the block in view (`Block.id`)
+    pub block_id: String,
+    /// Start byte offset of that block (`Block.source_start`)
+    pub block_offset: u64,
+    /// How far through the note the view was scrolled (0.0 to 1.0)
+    pub scroll_fraction: f32,
+}
+
+impl ReadPosition {
+    fn from_engine(position: &read_state::ReadPosition) -> Self {
+        Self {
+            block_id: position.block_id.0.to_string(),
+            block_offset: position.block_offset as u64,
+            scroll_fraction: position.scroll_fraction,
+        }
+    }
+
+    fn to_engine(&self) -> Result<read_state::ReadPosition, FfiError> {
+        let block_id = self.block_id.parse().map_err(|_| FfiError::ParseError {
+            reason: format!("invalid block id '{}'", self.block_id),
+        })?;
+        Ok(read_state::ReadPosition {
+            block_id: AnchorId(block_id),
+            block_offset: self.block_offset as usize,
+            scroll_fraction: self.scroll_fraction.clamp(0.0, 1.0),
+        })
     }
 }
 
@@ -129,24 +1107,31 @@ pub struct Snapshot {
     pub version: u64,
     /// Hierarchical tree of blocks for rendering
     pub blocks: Vec<Block>,
+    /// The block marked with `DocumentHandle::set_active_block`
+    pub active: Option<ActiveBlockDto>,
 }
 
 impl Snapshot {
-    fn from_engine(snapshot: engine::Snapshot) -> Self {
-        let blocks = convert_blocks(&snapshot.blocks);
+    fn from_engine(snapshot: engine::Snapshot, text: &str) -> Self {
+        let utf16 = Utf16Index::new(text);
+        let blocks = convert_blocks(&snapshot.blocks, &utf16);
         Self {
             version: 0, // TODO: Add version to Snapshot when needed
             blocks,
+            active: snapshot
+                .active
+                .as_ref()
+                .map(|active| ActiveBlockDto::from_engine(active, &utf16)),
         }
     }
 }
 
 /// Convert engine blocks recursively, preserving tree structure.
 /// List containers are "unwrapped" - their children are promoted to the parent level.
-fn convert_blocks(blocks: &[engine::Block]) -> Vec<Block> {
+fn convert_blocks(blocks: &[engine::Block], utf16: &Utf16Index) -> Vec<Block> {
     let mut result = Vec::new();
     for block in blocks {
-        convert_block_into(block, &mut result);
+        convert_block_into(block, utf16, &mut result);
     }
     result
 }
@@ -154,143 +1139,178 @@ fn convert_blocks(blocks: &[engine::Block]) -> Vec<Block> {
 /// Convert a single engine block, appending to the result vector.
 /// Root blocks are "unwrapped" and their children are added directly.
 /// List blocks are preserved with their ordered flag.
-fn convert_block_into(block: &engine::Block, result: &mut Vec<Block>) {
+fn convert_block_into(block: &engine::Block, utf16: &Utf16Index, result: &mut Vec<Block>) {
     if block.kind == BlockKind::Root {
         // Unwrap root container: add children directly to result
         if let BlockContent::Children(children) = &block.content {
             for child in children {
-                convert_block_into(child, result);
+                convert_block_into(child, utf16, result);
             }
         }
         return;
     }
 
-    let (
-        kind,
-        heading_level,
-        list_marker,
-        list_ordered,
-        checkbox_checked,
-        checkbox_start,
-        checkbox_end,
-    ) = match &block.kind {
-        BlockKind::Root => unreachable!(), // Handled above
-        BlockKind::Paragraph => ("paragraph".to_string(), 0, None, None, None, None, None),
-        BlockKind::Heading { level } => {
-            ("heading".to_string(), *level, None, None, None, None, None)
-        }
-        BlockKind::List { ordered } => (
-            "list".to_string(),
-            0,
-            None,
-            Some(*ordered),
-            None,
-            None,
-            None,
-        ),
-        BlockKind::ListItem { marker, checkbox } => {
-            let (checked, start, end) = match checkbox {
-                Some(cb) => (
-                    Some(cb.checked),
-                    Some(cb.byte_range.start as u64),
-                    Some(cb.byte_range.end as u64),
-                ),
-                None => (None, None, None),
-            };
-            (
-                "list_item".to_string(),
-                0,
-                Some(marker.clone()),
-                None,
-                checked,
-                start,
-                end,
-            )
-        }
-        BlockKind::FencedCode { .. } => ("code_fence".to_string(), 0, None, None, None, None, None),
-        BlockKind::ThematicBreak => (
-            "thematic_break".to_string(),
-            0,
-            None,
-            None,
-            None,
-            None,
-            None,
-        ),
-        BlockKind::BlockQuote => ("block_quote".to_string(), 0, None, None, None, None, None),
-        BlockKind::Table => ("table".to_string(), 0, None, None, None, None, None),
-        BlockKind::TableRow { is_header } => {
-            if *is_header {
-                (
-                    "table_header_row".to_string(),
-                    0,
-                    None,
-                    None,
-                    None,
-                    None,
-                    None,
-                )
-            } else {
-                ("table_row".to_string(), 0, None, None, None, None, None)
-            }
-        }
-        BlockKind::TableCell => ("table_cell".to_string(), 0, None, None, None, None, None),
-    };
+    let kind = BlockKindDto::from_engine(&block.kind);
+    let content_range = block.content_range();
+    let convert_segment =
+        |segment: &InlineSegment| TextSegment::from_segment(segment, content_range.start, utf16);
 
     // Convert engine segments (engine now provides flat segments)
-    let segments: Vec<TextSegment> = block
-        .segments
-        .iter()
-        .map(TextSegment::from_segment)
-        .collect();
+    let segments: Vec<TextSegment> = block.segments.iter().map(convert_segment).collect();
+
+    // Process children recursively; embedded blocks index the embedded note
+    let children = match (&block.content, &block.kind) {
+        (
+            BlockContent::Children(child_blocks),
+            BlockKind::Embed {
+                source: Some(source),
+                ..
+            },
+        ) => convert_blocks(child_blocks, &Utf16Index::new(&source.text)),
+        (BlockContent::Children(child_blocks), _) => convert_blocks(child_blocks, utf16),
+        (BlockContent::Leaf, _) => Vec::new(),
+    };
 
-    // Process children recursively
-    let children = if let BlockContent::Children(child_blocks) = &block.content {
-        convert_blocks(child_blocks)
-    } else {
-        Vec::new()
+    let chunks = match &block.kind {
+        BlockKind::Paragraph { chunks } => chunks
+            .iter()
+            .map(|chunk| ParagraphChunk {
+                start: chunk.start as u64,
+                end: chunk.end as u64,
+                segments: block
+                    .segments_in(chunk.clone())
+                    .iter()
+                    .map(convert_segment)
+                    .collect(),
+            })
+            .collect(),
+        _ => Vec::new(),
     };
 
-    let content_range = block.content_range();
     result.push(Block {
         id: block.id.0.to_string(),
         kind,
-        heading_level,
-        list_marker,
-        list_ordered,
-        checkbox_checked,
-        checkbox_start,
-        checkbox_end,
         segments,
+        chunks,
         children,
         source_start: block.node_range.start as u64,
         source_end: block.node_range.end as u64,
         content_start: content_range.start as u64,
         content_end: content_range.end as u64,
+        source_utf16: utf16.range(&block.node_range),
+        content_utf16: utf16.range(&content_range),
+        is_truncated: false,
+        annotation_count: 0,
+        focus: block.focus.into(),
     });
 }
 
+fn set_annotation_counts(blocks: &mut [Block], counts: &HashMap<String, u32>) {
+    for block in blocks {
+        block.annotation_count = counts.get(&block.id).copied().unwrap_or(0);
+        set_annotation_counts(&mut block.children, counts);
+    }
+}
+
+fn find_block(blocks: Vec<Block>, id: &str) -> Option<Block> {
+    for block in blocks {
+        if block.id == id {
+            return Some(block);
+        }
+        if let Some(found) = find_block(block.children, id) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+/// Cut each block's segments and chunks to about `max_bytes` of content.
+fn truncate_blocks(blocks: &mut [Block], max_bytes: usize) {
+    for block in blocks {
+        let mut truncated = truncate_segments(&mut block.segments, max_bytes);
+
+        let mut budget = max_bytes;
+        let mut kept = 0;
+        for chunk in &mut block.chunks {
+            let len: usize = chunk.segments.iter().map(segment_len).sum();
+            if len > budget {
+                truncate_segments(&mut chunk.segments, budget);
+                truncated = true;
+                if !chunk.segments.is_empty() {
+                    kept += 1;
+                }
+                break;
+            }
+            budget -= len;
+            kept += 1;
+        }
+        block.chunks.truncate(kept);
+
+        block.is_truncated = truncated;
+        truncate_blocks(&mut block.children, max_bytes);
+    }
+}
+
+fn segment_len(segment: &TextSegment) -> usize {
+    segment.content.len() + segment.children.iter().map(segment_len).sum::<usize>()
+}
+
+/// Keep segments up to `max_bytes` of content, cutting a text segment at a
+/// word or character boundary and dropping other segments whole so links
+/// and formatting are never split. Returns whether anything was cut.
+fn truncate_segments(segments: &mut Vec<TextSegment>, max_bytes: usize) -> bool {
+    let mut used = 0;
+    let Some(cut) = segments.iter().position(|segment| {
+        used += segment_len(segment);
+        used > max_bytes
+    }) else {
+        return false;
+    };
+
+    let room = max_bytes - (used - segment_len(&segments[cut]));
+    let segment = &mut segments[cut];
+    let keep = if segment.kind == SegmentKindDto::Text {
+        let mut end = room;
+        while !segment.content.is_char_boundary(end) {
+            end -= 1;
+        }
+        // Prefer ending at a word
+        segment.content[..end]
+            .rfind(char::is_whitespace)
+            .filter(|&space| space > 0)
+            .unwrap_or(end)
+    } else {
+        0
+    };
+    if keep > 0 {
+        // Plain text maps byte for byte to the source, so its range shrinks too
+        if segment.content.len() as u64 == segment.source_end - segment.source_start {
+            segment.source_end = segment.source_start + keep as u64;
+            segment.local_end = segment.local_start + keep as u64;
+            segment.source_utf16.end =
+                segment.source_utf16.start + segment.content[..keep].encode_utf16().count() as u64;
+        }
+        segment.content.truncate(keep);
+        segments.truncate(cut + 1);
+    } else {
+        segments.truncate(cut);
+    }
+    true
+}
+
 /// A single block in the document tree.
 #[derive(uniffi::Record)]
 pub struct Block {
     /// Stable identifier for this block (persists across edits)
     pub id: String,
-    /// Block type (e.g., "heading", "list_item", "paragraph", "list")
-    pub kind: String,
-    /// Heading level (1-6) if this is a heading, 0 otherwise
-    pub heading_level: u8,
-    /// List marker if this is a list item
-    pub list_marker: Option<String>,
-    /// Whether this is an ordered list (only set for kind="list")
-    pub list_ordered: Option<bool>,
-    /// Checkbox state: None if not a checkbox, Some(true) if checked, Some(false) if unchecked
-    pub checkbox_checked: Option<bool>,
-    /// Byte offset where checkbox starts (for editing)
-    pub checkbox_start: Option<u64>,
-    /// Byte offset where checkbox ends (for editing)
-    pub checkbox_end: Option<u64>,
+    /// What kind of block this is, with its kind-specific details
+    pub kind: BlockKindDto,
     /// Parsed inline segments (wiki-links, URLs, plain text)
     pub segments: Vec<TextSegment>,
+    /// Long paragraphs split at line and sentence ends, for rendering as
+    /// separate lazy list items. Empty for other blocks; edits still use
+    /// the block's content range.
+    pub chunks: Vec<ParagraphChunk>,
     /// Child blocks (e.g., nested list items)
     pub children: Vec<Block>,
     /// Start byte offset of full block in source (node_range.start)
@@ -303,106 +1323,856 @@ pub struct Block {
     /// End byte offset of editable content (content_range().end)
     /// For list items, this excludes nested children.
     pub content_end: u64,
+    /// `source_start..source_end` in UTF-16 code units, for indexing
+    /// Java/Kotlin strings
+    pub source_utf16: Utf16Range,
+    /// `content_start..content_end` in UTF-16 code units
+    pub content_utf16: Utf16Range,
+    /// Whether `segments` and `chunks` were cut short by
+    /// `DocumentHandle::get_snapshot_with`
+    pub is_truncated: bool,
+    /// Unresolved review comments on this block; only filled in by
+    /// `DocumentHandle::get_annotated_snapshot`
+    pub annotation_count: u32,
+    /// Whether to dim the block in focus mode
+    pub focus: FocusHintDto,
+}
+
+/// How a block shows in focus mode (see `DocumentHandle::set_active_block`).
+#[derive(Debug, Clone, Copy, PartialEq, uniffi::Enum)]
+pub enum FocusHintDto {
+    /// No block is active
+    Normal,
+    Active,
+    /// Contains the active block, e.g. its list
+    Ancestor,
+    Dimmed,
+}
+
+impl From<FocusHint> for FocusHintDto {
+    fn from(hint: FocusHint) -> Self {
+        match hint {
+            FocusHint::Normal => Self::Normal,
+            FocusHint::Active => Self::Active,
+            FocusHint::Ancestor => Self::Ancestor,
+            FocusHint::Dimmed => Self::Dimmed,
+        }
+    }
+}
+
+/// The active block's lines and sentences, for highlighting the sentence
+/// at the caret or keeping its line centred.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct ActiveBlockDto {
+    pub block_id: String,
+    /// Each line of the block's text, without indentation or line break
+    pub lines: Vec<ByteRange>,
+    pub lines_utf16: Vec<Utf16Range>,
+    /// Each sentence; may span lines
+    pub sentences: Vec<ByteRange>,
+    pub sentences_utf16: Vec<Utf16Range>,
+}
+
+impl ActiveBlockDto {
+    fn from_engine(active: &editing::ActiveBlock, utf16: &Utf16Index) -> Self {
+        Self {
+            block_id: active.id.0.to_string(),
+            lines: active.lines.iter().map(ByteRange::from_engine).collect(),
+            lines_utf16: active.lines.iter().map(|r| utf16.range(r)).collect(),
+            sentences: active
+                .sentences
+                .iter()
+                .map(ByteRange::from_engine)
+                .collect(),
+            sentences_utf16: active.sentences.iter().map(|r| utf16.range(r)).collect(),
+        }
+    }
+}
+
+/// The kind of a [`Block`].
+#[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+pub enum BlockKindDto {
+    Paragraph,
+    Heading {
+        /// 1-6
+        level: u8,
+    },
+    /// List container; its items are the block's children
+    List {
+        ordered: bool,
+    },
+    ListItem {
+        /// Marker as written, with its trailing space (e.g. `"- "`, `"1. "`)
+        marker: String,
+        /// Display label for ordered items when renumbered (e.g. `b.`);
+        /// `None` means show `marker`
+        ordinal: Option<String>,
+        /// The item's `[ ]`/`[x]` checkbox, if any
+        checkbox: Option<CheckboxDto>,
+        /// From the checkbox or a `TODO`/`DOING`/`DONE`/`LATER` keyword;
+        /// `None` for plain items
+        task_state: Option<TaskStateDto>,
+    },
+    CodeFence {
+        language: Option<String>,
+    },
+    ThematicBreak,
+    BlockQuote,
+    FrontMatter,
+    /// Table container; its rows are the block's children
+    Table {
+        /// Number of rows, header included
+        rows: u64,
+        /// Alignment of each column, from the delimiter row
+        alignments: Vec<TableAlignmentDto>,
+    },
+    TableRow {
+        is_header: bool,
+    },
+    TableCell,
+    /// A note embed, `![[Note]]` or `![[Note#Heading]]`. Once resolved
+    /// (see [`DocumentHandle::get_snapshot_with_embeds`]) its children are
+    /// the embedded note's blocks: read-only, with ranges into that note's
+    /// text rather than this document's.
+    Embed {
+        target: String,
+        heading: Option<String>,
+        /// Path of the embedded note; `None` if unresolved or not found
+        source_path: Option<String>,
+        /// Block id of the embedded heading in that note
+        source_anchor: Option<String>,
+    },
+}
+
+impl BlockKindDto {
+    fn from_engine(kind: &BlockKind) -> Self {
+        match kind {
+            // Unwrapped by `convert_block_into`
+            BlockKind::Root => unreachable!(),
+            BlockKind::Paragraph { .. } => Self::Paragraph,
+            BlockKind::Heading { level } => Self::Heading { level: *level },
+            BlockKind::List { ordered } => Self::List { ordered: *ordered },
+            BlockKind::ListItem {
+                marker,
+                checkbox,
+                task_state,
+                ordinal,
+            } => Self::ListItem {
+                marker: marker.clone(),
+                ordinal: ordinal.clone(),
+                checkbox: checkbox.as_ref().map(|checkbox| CheckboxDto {
+                    checked: checkbox.checked,
+                    range: ByteRange::from_engine(&checkbox.byte_range),
+                }),
+                task_state: task_state.map(TaskStateDto::from),
+            },
+            BlockKind::FencedCode { language, .. } => Self::CodeFence {
+                language: language.clone(),
+            },
+            BlockKind::ThematicBreak => Self::ThematicBreak,
+            BlockKind::BlockQuote => Self::BlockQuote,
+            BlockKind::FrontMatter { .. } => Self::FrontMatter,
+            BlockKind::Table { rows, alignments } => Self::Table {
+                rows: *rows as u64,
+                alignments: alignments
+                    .iter()
+                    .copied()
+                    .map(TableAlignmentDto::from)
+                    .collect(),
+            },
+            BlockKind::TableRow { is_header } => Self::TableRow {
+                is_header: *is_header,
+            },
+            BlockKind::TableCell => Self::TableCell,
+            BlockKind::Embed {
+                target,
+                heading,
+                source,
+            } => Self::Embed {
+                target: target.clone(),
+                heading: heading.clone(),
+                source_path: source.as_ref().map(|source| source.path.to_string()),
+                source_anchor: source
+                    .as_ref()
+                    .and_then(|source| source.anchor)
+                    .map(|id| id.0.to_string()),
+            },
+        }
+    }
+}
+
+/// A list item's checkbox.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct CheckboxDto {
+    pub checked: bool,
+    /// Where the `[ ]`/`[x]` is in the source, for toggling it
+    pub range: ByteRange,
+}
+
+/// Task state of a list item.
+#[derive(Debug, Clone, Copy, PartialEq, uniffi::Enum)]
+pub enum TaskStateDto {
+    /// `TODO` or an unchecked `[ ]`
+    Todo,
+    Doing,
+    /// `DONE` or a checked `[x]`
+    Done,
+    Later,
+}
+
+impl From<TaskState> for TaskStateDto {
+    fn from(state: TaskState) -> Self {
+        match state {
+            TaskState::Todo => Self::Todo,
+            TaskState::Doing => Self::Doing,
+            TaskState::Done => Self::Done,
+            TaskState::Later => Self::Later,
+        }
+    }
+}
+
+impl From<TaskStateDto> for TaskState {
+    fn from(state: TaskStateDto) -> Self {
+        match state {
+            TaskStateDto::Todo => Self::Todo,
+            TaskStateDto::Doing => Self::Doing,
+            TaskStateDto::Done => Self::Done,
+            TaskStateDto::Later => Self::Later,
+        }
+    }
+}
+
+/// How a table column's cells are aligned.
+#[derive(Debug, Clone, Copy, PartialEq, uniffi::Enum)]
+pub enum TableAlignmentDto {
+    /// Not specified; render like `Left`
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+impl From<TableAlignment> for TableAlignmentDto {
+    fn from(alignment: TableAlignment) -> Self {
+        match alignment {
+            TableAlignment::None => Self::None,
+            TableAlignment::Left => Self::Left,
+            TableAlignment::Center => Self::Center,
+            TableAlignment::Right => Self::Right,
+        }
+    }
+}
+
+/// Part of a long paragraph.
+#[derive(uniffi::Record)]
+pub struct ParagraphChunk {
+    /// Start byte offset in source
+    pub start: u64,
+    /// End byte offset in source
+    pub end: u64,
+    /// The paragraph's inline segments within this chunk
+    pub segments: Vec<TextSegment>,
+}
+
+/// A segment of inline content within a block.
+/// Supports recursive structure for nested formatting (ADR-0013).
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct TextSegment {
+    pub kind: SegmentKindDto,
+    /// Text to display: the text itself, a link's text, a wiki-link's alias
+    /// or target, an image's alt text or a tag's name (without `#`). Empty
+    /// for containers and line breaks.
+    pub content: String,
+    /// Child segments for container nodes (emphasis, strong)
+    pub children: Vec<TextSegment>,
+    /// Start byte offset in source, markup included (e.g. the `[[` of a
+    /// wiki-link)
+    pub source_start: u64,
+    /// End byte offset in source, markup included
+    pub source_end: u64,
+    /// `source_start` relative to the block's `content_start`
+    pub local_start: u64,
+    /// `source_end` relative to the block's `content_start`
+    pub local_end: u64,
+    /// `source_start..source_end` in UTF-16 code units
+    pub source_utf16: Utf16Range,
+}
+
+/// The kind of a [`TextSegment`].
+#[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+pub enum SegmentKindDto {
+    Text,
+    Emphasis,
+    Strong,
+    Code,
+    Strikethrough,
+    /// `[[target]]` or `[[target|alias]]`
+    WikiLink {
+        target: String,
+        alias: Option<String>,
+    },
+    /// `[text](url)`
+    Link {
+        url: String,
+    },
+    /// `![alt](url)`
+    Image {
+        url: String,
+    },
+    Tag,
+    HardBreak,
+    SoftBreak,
+}
+
+impl TextSegment {
+    /// Convert a segment of a block whose content starts at byte
+    /// `content_start`.
+    fn from_segment(segment: &InlineSegment, content_start: usize, utf16: &Utf16Index) -> Self {
+        let node = |kind, content: &String, children: Vec<TextSegment>| Self {
+            kind,
+            content: content.clone(),
+            children,
+            source_start: segment.range.start as u64,
+            source_end: segment.range.end as u64,
+            local_start: (segment.range.start - content_start) as u64,
+            local_end: (segment.range.end - content_start) as u64,
+            source_utf16: utf16.range(&segment.range),
+        };
+        let leaf = |kind, content: &String| node(kind, content, vec![]);
+        let container = |kind, children: &[InlineSegment]| {
+            let children = children
+                .iter()
+                .map(|child| Self::from_segment(child, content_start, utf16))
+                .collect();
+            node(kind, &String::new(), children)
+        };
+        match &segment.kind {
+            InlineNode::Text(text) => leaf(SegmentKindDto::Text, text),
+            InlineNode::WikiLink { target, alias } => leaf(
+                SegmentKindDto::WikiLink {
+                    target: target.clone(),
+                    alias: alias.clone(),
+                },
+                alias.as_ref().unwrap_or(target),
+            ),
+            InlineNode::Link { text, url } => leaf(SegmentKindDto::Link { url: url.clone() }, text),
+            InlineNode::Emphasis(children) => container(SegmentKindDto::Emphasis, children),
+            InlineNode::Strong(children) => container(SegmentKindDto::Strong, children),
+            InlineNode::Code(text) => leaf(SegmentKindDto::Code, text),
+            InlineNode::Image { alt, url } => leaf(SegmentKindDto::Image { url: url.clone() }, alt),
+            InlineNode::Strikethrough(text) => leaf(SegmentKindDto::Strikethrough, text),
+            InlineNode::Tag { name } => leaf(SegmentKindDto::Tag, name),
+            InlineNode::HardBreak => container(SegmentKindDto::HardBreak, &[]),
+            InlineNode::SoftBreak => container(SegmentKindDto::SoftBreak, &[]),
+        }
+    }
+}
+
+// ============ Dashboard ============
+
+/// A note passed in for the dashboard, read by the app.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct DashboardNote {
+    /// Path relative to the notes root
+    pub path: String,
+    pub content: String,
+    /// Last modified time in milliseconds since the Unix epoch, if known
+    pub modified_ms: Option<i64>,
+}
+
+/// An open task for the dashboard.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct DashboardTask {
+    pub path: String,
+    /// 1-based line number
+    pub line: u32,
+    pub text: String,
+    /// `YYYY-MM-DD` from the task's `due::` property
+    pub deadline: Option<String>,
+}
+
+/// Rollup for one area (top-level folder or `area::` property).
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct DashboardArea {
+    pub name: String,
+    pub note_count: u32,
+    pub open_tasks: Vec<DashboardTask>,
+    /// Paths of the most recently modified notes, newest first
+    pub recent_edits: Vec<String>,
+    /// Overdue and upcoming deadlines, soonest first
+    pub upcoming: Vec<DashboardTask>,
+}
+
+impl From<&dashboard::Task> for DashboardTask {
+    fn from(task: &dashboard::Task) -> Self {
+        Self {
+            path: task.path.to_string(),
+            line: task.line as u32,
+            text: task.text.clone(),
+            deadline: task.deadline.map(|date| date.to_string()),
+        }
+    }
+}
+
+/// Group notes by area with task, edit and deadline rollups.
+///
+/// `today` is the local date as `YYYY-MM-DD`.
+#[uniffi::export]
+pub fn build_dashboard(
+    notes: Vec<DashboardNote>,
+    today: String,
+) -> Result<Vec<DashboardArea>, FfiError> {
+    boundary("build_dashboard", || dashboard_areas(notes, today))
+}
+
+fn dashboard_areas(
+    notes: Vec<DashboardNote>,
+    today: String,
+) -> Result<Vec<DashboardArea>, FfiError> {
+    let today = today.parse().map_err(|e| FfiError::ParseError {
+        reason: format!("invalid date {today:?}: {e}"),
+    })?;
+    let notes = notes.into_iter().map(|note| dashboard::DashboardNote {
+        path: note.path.into(),
+        source: note.content,
+        modified: note
+            .modified_ms
+            .and_then(|ms| jiff::Timestamp::from_millisecond(ms).ok()),
+    });
+
+    Ok(dashboard::Dashboard::build(notes, today)
+        .areas
+        .iter()
+        .map(|area| DashboardArea {
+            name: area.name.clone(),
+            note_count: area.notes as u32,
+            open_tasks: area.open_tasks.iter().map(Into::into).collect(),
+            recent_edits: area
+                .recent_edits
+                .iter()
+                .map(|edit| edit.path.to_string())
+                .collect(),
+            upcoming: area.upcoming.iter().map(Into::into).collect(),
+        })
+        .collect())
+}
+
+// ============ Date Entry ============
+
+/// A suggested date for date autocomplete.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct DateCompletion {
+    /// The phrase, e.g. `next friday`
+    pub label: String,
+    /// `YYYY-MM-DD`
+    pub date: String,
+}
+
+/// Dates to suggest for `typed`, the text entered so far (`tom`,
+/// `next fri`, `in 3 days`). Insert the chosen one with
+/// [`CmdDto::InsertDate`].
+///
+/// `today` is the local date as `YYYY-MM-DD`.
+#[uniffi::export]
+pub fn complete_dates(typed: String, today: String) -> Result<Vec<DateCompletion>, FfiError> {
+    boundary("complete_dates", || {
+        let today = today.parse().map_err(|e| FfiError::ParseError {
+            reason: format!("invalid date {today:?}: {e}"),
+        })?;
+        Ok(dates::complete_dates(&typed, today)
+            .into_iter()
+            .map(|completion| DateCompletion {
+                label: completion.label,
+                date: completion.date.to_string(),
+            })
+            .collect())
+    })
+}
+
+// ============ Find and Replace ============
+
+/// How [`plan_replace_all`] matches.
+#[derive(Debug, Clone, Default, PartialEq, uniffi::Record)]
+pub struct ReplaceOptionsDto {
+    /// Pattern is a regular expression (`$1` in the replacement inserts a
+    /// group) rather than literal text
+    pub regex: bool,
+    pub ignore_case: bool,
+    /// Only notes in this folder
+    pub folder: Option<String>,
+}
+
+/// One match and what replaces it.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct ReplaceEditDto {
+    /// Byte range of the match
+    pub start: u64,
+    pub end: u64,
+    /// 1-based
+    pub line: u64,
+    /// The line the match is on, for the preview
+    pub context: String,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+/// The replacements in one note.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct ReplacePatchDto {
+    pub path: String,
+    pub edits: Vec<ReplaceEditDto>,
+}
+
+/// Preview replacing `pattern` with `replacement` in every note under
+/// `notes_root`: the matches in each note, ordered by path. Nothing is
+/// written.
+#[uniffi::export]
+pub fn plan_replace_all(
+    notes_root: String,
+    pattern: String,
+    replacement: String,
+    options: ReplaceOptionsDto,
+) -> Result<Vec<ReplacePatchDto>, FfiError> {
+    boundary("plan_replace_all", || {
+        let options = replace::ReplaceOptions {
+            mode: if options.regex {
+                replace::MatchMode::Regex
+            } else {
+                replace::MatchMode::Literal
+            },
+            ignore_case: options.ignore_case,
+            folder: options.folder.map(|f| io::relative_path_from_str(&f)),
+            dry_run: true,
+        };
+        let patches =
+            replace::plan_replace_all(Path::new(&notes_root), &pattern, &replacement, &options)?;
+        Ok(patches
+            .into_iter()
+            .map(|patch| ReplacePatchDto {
+                path: patch.path.to_string(),
+                edits: patch
+                    .edits
+                    .into_iter()
+                    .map(|edit| ReplaceEditDto {
+                        start: edit.range.start as u64,
+                        end: edit.range.end as u64,
+                        line: edit.line as u64,
+                        context: edit.context,
+                        old_text: edit.old_text,
+                        new_text: edit.new_text,
+                    })
+                    .collect(),
+            })
+            .collect())
+    })
+}
+
+/// Write the (possibly filtered) patches from [`plan_replace_all`],
+/// returning how many notes changed. Undo with [`undo_replace_all`].
+#[uniffi::export]
+pub fn apply_replace_all(
+    notes_root: String,
+    patches: Vec<ReplacePatchDto>,
+) -> Result<u64, FfiError> {
+    boundary("apply_replace_all", || {
+        let patches: Vec<replace::ReplacePatch> = patches
+            .into_iter()
+            .map(|patch| replace::ReplacePatch {
+                path: io::relative_path_from_str(&patch.path),
+                edits: patch
+                    .edits
+                    .into_iter()
+                    .map(|edit| replace::ReplaceEdit {
+                        range: edit.start as usize..edit.end as usize,
+                        line: edit.line as usize,
+                        context: edit.context,
+                        old_text: edit.old_text,
+                        new_text: edit.new_text,
+                    })
+                    .collect(),
+            })
+            .collect();
+        Ok(replace::apply_replace_all(Path::new(&notes_root), &patches)? as u64)
+    })
+}
+
+/// Put back the notes changed by the last [`apply_replace_all`], returning
+/// how many, or None if there is nothing to undo. Fails, changing nothing,
+/// if any of them have been edited since.
+#[uniffi::export]
+pub fn undo_replace_all(notes_root: String) -> Result<Option<u64>, FfiError> {
+    boundary("undo_replace_all", || {
+        Ok(replace::undo_replace_all(Path::new(&notes_root))?.map(|n| n as u64))
+    })
+}
+
+// ============ Tracing ============
+
+/// The running trace, finished by [`stop_trace`].
+#[cfg(feature = "chrome-trace")]
+static TRACE: Mutex<Option<markdown_neuraxis_engine::trace::ChromeTrace>> = Mutex::new(None);
+
+/// Record engine spans (parsing, edits, snapshots, file IO) to a Chrome
+/// trace file at `path`, for diagnosing slowness. The file is complete
+/// after [`stop_trace`]; open it in Perfetto or `chrome://tracing`.
+///
+/// Only one trace can be recorded per process run.
+#[cfg(feature = "chrome-trace")]
+#[uniffi::export]
+pub fn start_trace(path: String) -> Result<(), FfiError> {
+    boundary("start_trace", || {
+        let trace =
+            markdown_neuraxis_engine::trace::start_chrome_trace(std::path::Path::new(&path))
+                .map_err(|e| FfiError::IoError {
+                    reason: e.to_string(),
+                })?;
+        *TRACE.lock().unwrap_or_else(|e| e.into_inner()) = Some(trace);
+        Ok(())
+    })
+}
+
+/// Finish the trace started by [`start_trace`], if any.
+#[cfg(feature = "chrome-trace")]
+#[uniffi::export]
+pub fn stop_trace() -> Result<(), FfiError> {
+    boundary("stop_trace", || {
+        TRACE.lock().unwrap_or_else(|e| e.into_inner()).take();
+        Ok(())
+    })
+}
+
+// ============ Standalone Functions ============
+
+/// Resolve a wiki-link target to a file path.
+///
+/// Searches the given file paths for a match (case-insensitive, with or without .md extension).
+/// Targets containing a folder must match the whole path. Paths and targets may use
+/// `/` or `\\` separators.
+/// Returns the matching file path, or None if not found.
+#[uniffi::export]
+pub fn resolve_wikilink(
+    target: String,
+    file_paths: Vec<String>,
+) -> Result<Option<String>, FfiError> {
+    boundary("resolve_wikilink", || {
+        Ok(file_paths
+            .into_iter()
+            .find(|path| links::target_matches_note(&target, &io::relative_path_from_str(path))))
+    })
+}
+
+/// Another name for the note at `path`, from [`note_aliases`].
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct NoteAlias {
+    pub alias: String,
+    pub path: String,
+}
+
+/// Other names a note goes by, from front matter `aliases`/`alias` or a
+/// Logseq `alias::` property, for link resolution and autocompletion.
+#[uniffi::export]
+pub fn note_aliases(content: String) -> Result<Vec<String>, FfiError> {
+    boundary("note_aliases", || Ok(titles::note_aliases(&content)))
+}
+
+/// Like [`resolve_wikilink`], falling back to a note with `target` as one
+/// of its `aliases` (case-insensitive) when no path matches.
+#[uniffi::export]
+pub fn resolve_wikilink_with_aliases(
+    target: String,
+    file_paths: Vec<String>,
+    aliases: Vec<NoteAlias>,
+) -> Result<Option<String>, FfiError> {
+    boundary("resolve_wikilink_with_aliases", || {
+        let by_path = file_paths
+            .into_iter()
+            .find(|path| links::target_matches_note(&target, &io::relative_path_from_str(path)));
+        Ok(by_path.or_else(|| {
+            aliases
+                .into_iter()
+                .find(|a| a.alias.eq_ignore_ascii_case(target.trim()))
+                .map(|a| a.path)
+        }))
+    })
 }
 
-/// A segment of inline content within a block.
-/// Supports recursive structure for nested formatting (ADR-0013).
-#[derive(uniffi::Record)]
-pub struct TextSegment {
-    /// Segment type: "text", "wiki_link", "url", "emphasis", "strong", "code", "link", "image"
-    pub kind: String,
-    /// The text content or link target (for leaf nodes like text, code, etc.)
-    pub content: String,
-    /// Child segments for container nodes (emphasis, strong)
-    pub children: Vec<TextSegment>,
+/// Hover-card contents for a note, from [`note_preview`].
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct NotePreviewDto {
+    pub path: String,
+    pub title: String,
+    /// Plain text of the first paragraph
+    pub excerpt: Option<String>,
+    /// First image's destination as written (a URL, relative path or
+    /// attachment name)
+    pub image_src: Option<String>,
+    /// Absolute path of that image if it's a local file that exists
+    pub image_path: Option<String>,
 }
 
-impl TextSegment {
-    fn from_segment(segment: &InlineSegment) -> Self {
-        Self::from_inline_node(&segment.kind)
-    }
+/// Preview of the note at `rel_path` for a wiki-link hover card: its title,
+/// first paragraph and first image, looking no further than the first
+/// `max_blocks` blocks. Cheap enough to call per hover, but it reads the
+/// file, so call it off the UI thread.
+#[uniffi::export]
+pub fn note_preview(
+    notes_root: String,
+    rel_path: String,
+    max_blocks: u32,
+) -> Result<NotePreviewDto, FfiError> {
+    boundary("note_preview", || {
+        let notes_root = Path::new(&notes_root);
+        let path = io::relative_path_from_str(&rel_path);
+        let preview = links::preview::load_preview(notes_root, &path, max_blocks as usize)?;
+        let image_path = preview
+            .image
+            .as_ref()
+            .and_then(|image| image.resolve(&path, notes_root));
+        Ok(NotePreviewDto {
+            path: preview.path.to_string(),
+            title: preview.title,
+            excerpt: preview.excerpt,
+            image_src: preview.image.map(|image| image.src),
+            image_path: image_path.map(|p| p.to_string_lossy().to_string()),
+        })
+    })
+}
 
-    fn from_inline_node(node: &InlineNode) -> Self {
-        match node {
-            InlineNode::Text(text) => Self {
-                kind: "text".to_string(),
-                content: text.clone(),
-                children: vec![],
-            },
-            InlineNode::WikiLink { target, alias } => Self {
-                kind: "wiki_link".to_string(),
-                // Use alias if present, otherwise target (for display)
-                content: alias.as_ref().unwrap_or(target).clone(),
-                children: vec![],
-            },
-            InlineNode::Link { text, url } => Self {
-                kind: "link".to_string(),
-                content: format!("{}|{}", text, url),
-                children: vec![],
-            },
-            InlineNode::Emphasis(children) => Self {
-                kind: "emphasis".to_string(),
-                content: String::new(),
-                children: children.iter().map(Self::from_inline_node).collect(),
-            },
-            InlineNode::Strong(children) => Self {
-                kind: "strong".to_string(),
-                content: String::new(),
-                children: children.iter().map(Self::from_inline_node).collect(),
-            },
-            InlineNode::Code(text) => Self {
-                kind: "code".to_string(),
-                content: text.clone(),
-                children: vec![],
+// ============ Kanban ============
+
+impl From<kanban::KanbanError> for FfiError {
+    fn from(e: kanban::KanbanError) -> Self {
+        match e {
+            kanban::KanbanError::Io(e) => e.into(),
+            kanban::KanbanError::CardNotFound(_) => Self::InvalidCommand {
+                reason: e.to_string(),
             },
-            InlineNode::Image { alt, url } => Self {
-                kind: "image".to_string(),
-                content: format!("{}|{}", alt, url),
-                children: vec![],
+            kanban::KanbanError::Document(_) => Self::ParseError {
+                reason: e.to_string(),
             },
-            InlineNode::Strikethrough(text) => Self {
-                kind: "strikethrough".to_string(),
-                content: text.clone(),
-                children: vec![],
+        }
+    }
+}
+
+/// A kanban column: a task state, or a value of a property (`None` for
+/// tasks without it). Also where to move a card to.
+#[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+pub enum KanbanColumnKeyDto {
+    State { state: TaskStateDto },
+    Property { name: String, value: Option<String> },
+}
+
+impl KanbanColumnKeyDto {
+    fn from_engine(key: &kanban::ColumnKey) -> Self {
+        match key {
+            kanban::ColumnKey::State(state) => Self::State {
+                state: (*state).into(),
             },
-            InlineNode::HardBreak => Self {
-                kind: "hard_break".to_string(),
-                content: String::new(),
-                children: vec![],
+            kanban::ColumnKey::Property { name, value } => Self::Property {
+                name: name.clone(),
+                value: value.clone(),
             },
-            InlineNode::SoftBreak => Self {
-                kind: "soft_break".to_string(),
-                content: String::new(),
-                children: vec![],
+        }
+    }
+
+    fn to_engine(&self) -> kanban::ColumnKey {
+        match self {
+            Self::State { state } => kanban::ColumnKey::State((*state).into()),
+            Self::Property { name, value } => kanban::ColumnKey::Property {
+                name: name.clone(),
+                value: value.clone(),
             },
         }
     }
 }
 
-// ============ Standalone Functions ============
+/// A task on a kanban board.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct KanbanCardDto {
+    pub path: String,
+    /// The task's block id in that note
+    pub block_id: String,
+    /// 1-based line number
+    pub line: u32,
+    /// First line of the task's text, without the state keyword
+    pub text: String,
+    pub state: TaskStateDto,
+}
 
-/// Resolve a wiki-link target to a file path.
-///
-/// Searches the given file paths for a match (case-insensitive, with or without .md extension).
-/// Returns the matching file path, or None if not found.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct KanbanColumnDto {
+    pub key: KanbanColumnKeyDto,
+    pub title: String,
+    pub cards: Vec<KanbanCardDto>,
+}
+
+/// Every task in the vault as kanban columns: by state, or by the value of
+/// the property `group_by_property` if given, with `property_values`
+/// always shown as columns in that order.
+#[uniffi::export]
+pub fn kanban_board(
+    notes_root: String,
+    group_by_property: Option<String>,
+    property_values: Vec<String>,
+) -> Result<Vec<KanbanColumnDto>, FfiError> {
+    boundary("kanban_board", || {
+        let group_by = match group_by_property {
+            Some(name) => kanban::GroupBy::Property {
+                name,
+                values: property_values,
+            },
+            None => kanban::GroupBy::State,
+        };
+        let board = kanban::Board::load(Path::new(&notes_root), group_by)?;
+        Ok(board
+            .columns
+            .iter()
+            .map(|column| KanbanColumnDto {
+                key: KanbanColumnKeyDto::from_engine(&column.key),
+                title: column.title.clone(),
+                cards: column
+                    .cards
+                    .iter()
+                    .map(|card| KanbanCardDto {
+                        path: card.path.to_string(),
+                        block_id: card.id.0.to_string(),
+                        line: card.line as u32,
+                        text: card.text.clone(),
+                        state: card.state.into(),
+                    })
+                    .collect(),
+            })
+            .collect())
+    })
+}
+
+/// Move a card to another column by rewriting the task in its note on
+/// disk. For a note open in a [`DocumentHandle`], use
+/// [`DocumentHandle::move_kanban_card`] instead.
 #[uniffi::export]
-pub fn resolve_wikilink(target: String, file_paths: Vec<String>) -> Option<String> {
-    let search_name = target
-        .strip_suffix(".md")
-        .or_else(|| target.strip_suffix(".MD"))
-        .unwrap_or(&target)
-        .to_lowercase();
-
-    file_paths.into_iter().find(|path| {
-        let filename = path.rsplit('/').next().unwrap_or(path);
-        let name_without_ext = filename
-            .strip_suffix(".md")
-            .or_else(|| filename.strip_suffix(".MD"))
-            .unwrap_or(filename);
-        name_without_ext.to_lowercase() == search_name
+pub fn kanban_move_card(
+    notes_root: String,
+    rel_path: String,
+    block_id: String,
+    to: KanbanColumnKeyDto,
+) -> Result<(), FfiError> {
+    boundary("kanban_move_card", || {
+        let path = io::relative_path_from_str(&rel_path);
+        let id = parse_block_id(&block_id)?;
+        kanban::move_card(Path::new(&notes_root), &path, id, &to.to_engine())?;
+        Ok(())
     })
 }
 
+fn parse_block_id(block_id: &str) -> Result<AnchorId, FfiError> {
+    block_id
+        .parse()
+        .map(AnchorId)
+        .map_err(|_| FfiError::ParseError {
+            reason: format!("invalid block id '{block_id}'"),
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -417,13 +2187,17 @@ mod tests {
         result
     }
 
-    /// Find a block by kind in the tree (depth-first)
-    fn find_block_by_kind<'a>(blocks: &'a [Block], kind: &str) -> Option<&'a Block> {
+    fn is_list_item(block: &Block) -> bool {
+        matches!(block.kind, BlockKindDto::ListItem { .. })
+    }
+
+    /// Find the first list item in the tree (depth-first)
+    fn find_list_item(blocks: &[Block]) -> Option<&Block> {
         for block in blocks {
-            if block.kind == kind {
+            if is_list_item(block) {
                 return Some(block);
             }
-            if let Some(found) = find_block_by_kind(&block.children, kind) {
+            if let Some(found) = find_list_item(&block.children) {
                 return Some(found);
             }
         }
@@ -436,12 +2210,13 @@ mod tests {
     }
 
     fn segment_to_text(segment: &TextSegment) -> String {
-        match segment.kind.as_str() {
-            "text" | "code" | "strikethrough" | "wiki_link" => segment.content.clone(),
-            "emphasis" | "strong" => segments_to_text(&segment.children),
-            "link" | "image" => segment.content.split('|').next().unwrap_or("").to_string(),
-            "hard_break" => "\n".to_string(),
-            _ => String::new(),
+        match segment.kind {
+            SegmentKindDto::Emphasis | SegmentKindDto::Strong => {
+                segments_to_text(&segment.children)
+            }
+            SegmentKindDto::HardBreak => "\n".to_string(),
+            SegmentKindDto::Tag | SegmentKindDto::SoftBreak => String::new(),
+            _ => segment.content.clone(),
         }
     }
 
@@ -450,7 +2225,7 @@ mod tests {
         let content = "# Hello World\n\n- Item 1\n- Item 2";
         let doc = DocumentHandle::from_string(content.to_string()).unwrap();
 
-        let text = doc.get_text();
+        let text = doc.get_text().unwrap();
         assert_eq!(text, content);
     }
 
@@ -459,27 +2234,50 @@ mod tests {
         let content = "# Heading\n\nParagraph text\n\n- List item";
         let doc = DocumentHandle::from_string(content.to_string()).unwrap();
 
-        let snapshot = doc.get_snapshot();
+        let snapshot = doc.get_snapshot().unwrap();
         // version is u64, no need to check >= 0
         assert!(!snapshot.blocks.is_empty());
 
         // Check first block is heading
         let heading = &snapshot.blocks[0];
-        assert_eq!(heading.kind, "heading");
-        assert_eq!(heading.heading_level, 1);
+        assert_eq!(heading.kind, BlockKindDto::Heading { level: 1 });
         // Content is now extracted from segments
         assert_eq!(segments_to_text(&heading.segments), "Heading");
     }
 
+    #[test]
+    fn test_table_snapshot() {
+        let content = "| a | b |\n|:--|:-:|\n| 1 | 2 |\n";
+        let doc = DocumentHandle::from_string(content.to_string()).unwrap();
+        let snapshot = doc.get_snapshot().unwrap();
+
+        let table = &snapshot.blocks[0];
+        assert_eq!(
+            table.kind,
+            BlockKindDto::Table {
+                rows: 2,
+                alignments: vec![TableAlignmentDto::Left, TableAlignmentDto::Center],
+            }
+        );
+        assert_eq!(
+            table.children[0].kind,
+            BlockKindDto::TableRow { is_header: true }
+        );
+        assert_eq!(
+            segments_to_text(&table.children[1].children[1].segments),
+            "2"
+        );
+    }
+
     #[test]
     fn test_source_byte_ranges() {
         let content = "# Heading\n\nParagraph text\n";
         let doc = DocumentHandle::from_string(content.to_string()).unwrap();
-        let snapshot = doc.get_snapshot();
+        let snapshot = doc.get_snapshot().unwrap();
 
         // Heading: "# Heading\n" = bytes 0..10
         let heading = &snapshot.blocks[0];
-        assert_eq!(heading.kind, "heading");
+        assert_eq!(heading.kind, BlockKindDto::Heading { level: 1 });
         assert_eq!(heading.source_start, 0);
         assert_eq!(heading.source_end, 10);
         assert_eq!(
@@ -489,7 +2287,7 @@ mod tests {
 
         // Paragraph: "Paragraph text\n" = bytes 11..26
         let para = &snapshot.blocks[1];
-        assert_eq!(para.kind, "paragraph");
+        assert_eq!(para.kind, BlockKindDto::Paragraph);
         assert_eq!(para.source_start, 11);
         assert_eq!(para.source_end, 26);
         assert_eq!(
@@ -502,17 +2300,17 @@ mod tests {
     fn test_source_byte_ranges_nested() {
         let content = "- parent\n  - child\n";
         let doc = DocumentHandle::from_string(content.to_string()).unwrap();
-        let snapshot = doc.get_snapshot();
+        let snapshot = doc.get_snapshot().unwrap();
 
         // Top-level list contains entire content
         let list = &snapshot.blocks[0];
-        assert_eq!(list.kind, "list");
+        assert_eq!(list.kind, BlockKindDto::List { ordered: false });
         assert_eq!(list.source_start, 0);
         assert_eq!(list.source_end, 19);
 
         // Parent list item
         let parent = &list.children[0];
-        assert_eq!(parent.kind, "list_item");
+        assert!(is_list_item(parent));
         assert_eq!(
             &content[parent.source_start as usize..parent.source_end as usize],
             "- parent\n  - child\n"
@@ -520,11 +2318,11 @@ mod tests {
 
         // Nested list within parent
         let nested_list = &parent.children[0];
-        assert_eq!(nested_list.kind, "list");
+        assert_eq!(nested_list.kind, BlockKindDto::List { ordered: false });
 
         // Child list item - range contained within parent
         let child = &nested_list.children[0];
-        assert_eq!(child.kind, "list_item");
+        assert!(is_list_item(child));
         assert!(child.source_start >= parent.source_start);
         assert!(child.source_end <= parent.source_end);
     }
@@ -534,11 +2332,11 @@ mod tests {
         // List item with nested child - content_range should exclude the nested list
         let content = "- parent\n  - child\n";
         let doc = DocumentHandle::from_string(content.to_string()).unwrap();
-        let snapshot = doc.get_snapshot();
+        let snapshot = doc.get_snapshot().unwrap();
 
         let list = &snapshot.blocks[0];
         let parent = &list.children[0];
-        assert_eq!(parent.kind, "list_item");
+        assert!(is_list_item(parent));
 
         // source_start/source_end includes entire list item with nested content
         assert_eq!(parent.source_start, 0);
@@ -576,22 +2374,23 @@ mod tests {
     fn test_block_kinds() {
         let content = "# H1\n## H2\n\n- Dash\n* Star\n+ Plus\n1. Numbered\n\n---\n\n> Quote\n\n```rust\ncode\n```";
         let doc = DocumentHandle::from_string(content.to_string()).unwrap();
-        let snapshot = doc.get_snapshot();
+        let snapshot = doc.get_snapshot().unwrap();
 
         // Collect all blocks from tree
         let all_blocks = collect_all_blocks(&snapshot.blocks);
 
         // Find heading blocks
-        let headings: Vec<_> = all_blocks.iter().filter(|b| b.kind == "heading").collect();
-        assert_eq!(headings.len(), 2);
-        assert_eq!(headings[0].heading_level, 1);
-        assert_eq!(headings[1].heading_level, 2);
-
-        // Find list items
-        let list_items: Vec<_> = all_blocks
+        let headings: Vec<_> = all_blocks
             .iter()
-            .filter(|b| b.kind == "list_item")
+            .filter_map(|b| match b.kind {
+                BlockKindDto::Heading { level } => Some(level),
+                _ => None,
+            })
             .collect();
+        assert_eq!(headings, vec![1, 2]);
+
+        // Find list items
+        let list_items: Vec<_> = all_blocks.iter().filter(|b| is_list_item(b)).collect();
         assert!(!list_items.is_empty());
     }
 
@@ -605,46 +2404,273 @@ mod tests {
     fn test_wiki_links_in_segments() {
         let content = "- Check [[My Page]] for info";
         let doc = DocumentHandle::from_string(content.to_string()).unwrap();
-        let snapshot = doc.get_snapshot();
+        let snapshot = doc.get_snapshot().unwrap();
 
         // Find the list item in the tree
-        let list_item = find_block_by_kind(&snapshot.blocks, "list_item");
+        let list_item = find_list_item(&snapshot.blocks);
         assert!(list_item.is_some());
 
         let segments = &list_item.unwrap().segments;
-        let wiki_link = segments.iter().find(|s| s.kind == "wiki_link");
+        let wiki_link = segments
+            .iter()
+            .find(|s| matches!(s.kind, SegmentKindDto::WikiLink { .. }));
         assert!(wiki_link.is_some());
         assert_eq!(wiki_link.unwrap().content, "My Page");
     }
 
+    #[test]
+    fn test_segment_ranges() {
+        let content = "Intro\n\n- Check **bold [[My Page]]** é\n";
+        let doc = DocumentHandle::from_string(content.to_string()).unwrap();
+        let snapshot = doc.get_snapshot().unwrap();
+
+        let item = find_list_item(&snapshot.blocks).unwrap();
+        let strong = &item.segments[1];
+        assert_eq!(strong.kind, SegmentKindDto::Strong);
+        let link = &strong.children[1];
+        let range = |segment: &TextSegment| {
+            &content[segment.source_start as usize..segment.source_end as usize]
+        };
+        assert_eq!(range(strong), "**bold [[My Page]]**");
+        assert_eq!(range(link), "[[My Page]]");
+        assert_eq!(
+            (link.local_start, link.local_end),
+            (
+                link.source_start - item.content_start,
+                link.source_end - item.content_start
+            )
+        );
+        assert_eq!(link.local_start, "- Check **bold ".len() as u64);
+
+        // UTF-16 offsets count the `é` as one code unit
+        let last = item.segments.last().unwrap();
+        assert_eq!(range(last), " é");
+        assert_eq!(
+            last.source_utf16,
+            Utf16Range {
+                start: last.source_start,
+                end: last.source_end - 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_typed_kinds() {
+        let content = "- [x] Done [[Page|the page]] see [docs](https://example.com)\n\n```rust\nfn main() {}\n```\n";
+        let doc = DocumentHandle::from_string(content.to_string()).unwrap();
+        let snapshot = doc.get_snapshot().unwrap();
+
+        let item = find_list_item(&snapshot.blocks).unwrap();
+        assert_eq!(
+            item.kind,
+            BlockKindDto::ListItem {
+                marker: "- ".to_string(),
+                ordinal: None,
+                checkbox: Some(CheckboxDto {
+                    checked: true,
+                    range: ByteRange { start: 2, end: 5 },
+                }),
+                task_state: Some(TaskStateDto::Done),
+            }
+        );
+        let kinds: Vec<&SegmentKindDto> = item
+            .segments
+            .iter()
+            .filter(|s| s.kind != SegmentKindDto::Text)
+            .map(|s| &s.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                &SegmentKindDto::WikiLink {
+                    target: "Page".to_string(),
+                    alias: Some("the page".to_string()),
+                },
+                &SegmentKindDto::Link {
+                    url: "https://example.com".to_string(),
+                },
+            ]
+        );
+        assert_eq!(segments_to_text(&item.segments), "Done the page see docs");
+        assert_eq!(
+            snapshot.blocks[1].kind,
+            BlockKindDto::CodeFence {
+                language: Some("rust".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_utf16_ranges() {
+        // "é" is 2 bytes and 1 code unit; "😀" is 4 bytes and 2 code units
+        let content = "# Café 😀\n\n- naïve\n";
+        let doc = DocumentHandle::from_string(content.to_string()).unwrap();
+        let snapshot = doc.get_snapshot().unwrap();
+
+        let heading = &snapshot.blocks[0];
+        assert_eq!(heading.source_end, 13);
+        assert_eq!(heading.source_utf16, Utf16Range { start: 0, end: 10 });
+        let item = &snapshot.blocks[1].children[0];
+        assert_eq!((item.content_start, item.content_end), (14, 23));
+        assert_eq!(item.content_utf16, Utf16Range { start: 11, end: 19 });
+
+        let kotlin: Vec<u16> = content.encode_utf16().collect();
+        assert_eq!(
+            String::from_utf16(&kotlin[11..19]).unwrap(),
+            &content[14..23]
+        );
+
+        assert_eq!(doc.byte_offset_from_utf16(9).unwrap(), Some(12));
+        assert_eq!(doc.byte_offset_from_utf16(8).unwrap(), None);
+        assert_eq!(doc.byte_offset_from_utf16(18).unwrap(), Some(22));
+        assert_eq!(doc.byte_offset_from_utf16(99).unwrap(), None);
+        assert_eq!(doc.utf16_offset_from_byte(7).unwrap(), Some(6));
+        assert_eq!(doc.utf16_offset_from_byte(6).unwrap(), None);
+    }
+
     #[test]
     fn test_resolve_wikilink_exact_match() {
         let paths = vec![
             "notes/My Page.md".to_string(),
             "journal/2024_01_01.md".to_string(),
         ];
-        let result = resolve_wikilink("My Page".to_string(), paths);
+        let result = resolve_wikilink("My Page".to_string(), paths).unwrap();
         assert_eq!(result, Some("notes/My Page.md".to_string()));
     }
 
+    #[test]
+    fn test_resolve_wikilink_with_aliases() {
+        let content = "---\naliases: [Big Plan]\n---\n# Plan\n".to_string();
+        let aliases: Vec<NoteAlias> = note_aliases(content)
+            .unwrap()
+            .into_iter()
+            .map(|alias| NoteAlias {
+                alias,
+                path: "projects/plan.md".to_string(),
+            })
+            .collect();
+        let paths = vec!["projects/plan.md".to_string(), "big plan.md".to_string()];
+
+        let resolve = |target: &str, paths: Vec<String>| {
+            resolve_wikilink_with_aliases(target.to_string(), paths, aliases.clone()).unwrap()
+        };
+        assert_eq!(
+            resolve("big plan", paths.clone()),
+            Some("big plan.md".to_string())
+        );
+        assert_eq!(
+            resolve("Big Plan", paths[..1].to_vec()),
+            Some("projects/plan.md".to_string())
+        );
+        assert_eq!(resolve("Other", paths), None);
+    }
+
+    #[test]
+    fn test_note_preview() {
+        let notes_dir = tempfile::TempDir::new().unwrap();
+        let root = notes_dir.path().to_string_lossy().to_string();
+        std::fs::create_dir(notes_dir.path().join("assets")).unwrap();
+        std::fs::write(notes_dir.path().join("assets/map.png"), "png").unwrap();
+        std::fs::write(
+            notes_dir.path().join("trip.md"),
+            "# Road Trip\n\nDrive **north** to [[Lakes]].\n\n![[map.png]]\n",
+        )
+        .unwrap();
+
+        let preview = note_preview(root.clone(), "trip.md".into(), 5).unwrap();
+        assert_eq!(preview.title, "Road Trip");
+        assert_eq!(preview.excerpt.as_deref(), Some("Drive north to Lakes."));
+        assert_eq!(preview.image_src.as_deref(), Some("map.png"));
+        assert_eq!(
+            preview.image_path,
+            Some(
+                notes_dir
+                    .path()
+                    .join("assets/map.png")
+                    .to_string_lossy()
+                    .to_string()
+            )
+        );
+
+        assert!(note_preview(root, "missing.md".into(), 5).is_err());
+    }
+
+    #[test]
+    fn test_kanban_board_and_moves() {
+        let notes_dir = tempfile::TempDir::new().unwrap();
+        let root = notes_dir.path().to_string_lossy().to_string();
+        std::fs::write(
+            notes_dir.path().join("tasks.md"),
+            "- TODO Write\n- [x] Ship\n",
+        )
+        .unwrap();
+
+        let columns = kanban_board(root.clone(), None, vec![]).unwrap();
+        let titles: Vec<_> = columns.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, ["TODO", "DOING", "DONE"]);
+        let card = columns[0].cards[0].clone();
+        assert_eq!((card.text.as_str(), card.line), ("Write", 1));
+
+        let doing = KanbanColumnKeyDto::State {
+            state: TaskStateDto::Doing,
+        };
+        kanban_move_card(root.clone(), card.path, card.block_id, doing.clone()).unwrap();
+        let text = std::fs::read_to_string(notes_dir.path().join("tasks.md")).unwrap();
+        assert_eq!(text, "- DOING Write\n- [x] Ship\n");
+
+        let columns = kanban_board(root, Some("owner".into()), vec!["sam".into()]).unwrap();
+        assert_eq!(columns[0].title, "No owner");
+        assert_eq!(
+            columns[1].key,
+            KanbanColumnKeyDto::Property {
+                name: "owner".into(),
+                value: Some("sam".into())
+            }
+        );
+
+        let doc = DocumentHandle::from_string(text).unwrap();
+        let id = doc.get_snapshot().unwrap().blocks[0].children[1].id.clone();
+        assert!(
+            doc.move_kanban_card(id.clone(), columns[1].key.clone())
+                .unwrap()
+        );
+        assert!(!doc.move_kanban_card(id, columns[1].key.clone()).unwrap());
+        assert_eq!(
+            doc.get_text().unwrap(),
+            "- DOING Write\n- [x] Ship owner:: sam\n"
+        );
+        assert!(doc.move_kanban_card("1".into(), doing).is_err());
+    }
+
     #[test]
     fn test_resolve_wikilink_case_insensitive() {
         let paths = vec!["Notes/my page.md".to_string()];
-        let result = resolve_wikilink("My Page".to_string(), paths);
+        let result = resolve_wikilink("My Page".to_string(), paths).unwrap();
         assert_eq!(result, Some("Notes/my page.md".to_string()));
     }
 
     #[test]
     fn test_resolve_wikilink_with_extension() {
         let paths = vec!["docs/README.md".to_string()];
-        let result = resolve_wikilink("README.md".to_string(), paths);
+        let result = resolve_wikilink("README.md".to_string(), paths).unwrap();
         assert_eq!(result, Some("docs/README.md".to_string()));
     }
 
+    #[test]
+    fn test_resolve_wikilink_windows_separators() {
+        let paths = vec![r"notes\sub\My Page.md".to_string()];
+        let result = resolve_wikilink("sub/my page".to_string(), paths.clone()).unwrap();
+        assert_eq!(result, None);
+        let result = resolve_wikilink(r"notes\sub\My Page".to_string(), paths.clone()).unwrap();
+        assert_eq!(result, Some(paths[0].clone()));
+        let result = resolve_wikilink("My Page".to_string(), paths.clone()).unwrap();
+        assert_eq!(result, Some(paths[0].clone()));
+    }
+
     #[test]
     fn test_resolve_wikilink_not_found() {
         let paths = vec!["notes/Other.md".to_string()];
-        let result = resolve_wikilink("Missing".to_string(), paths);
+        let result = resolve_wikilink("Missing".to_string(), paths).unwrap();
         assert_eq!(result, None);
     }
 
@@ -657,7 +2683,7 @@ mod tests {
         assert!(!content.ends_with('\n'));
 
         let doc = DocumentHandle::from_string(content.to_string()).unwrap();
-        let snapshot = doc.get_snapshot();
+        let snapshot = doc.get_snapshot().unwrap();
 
         assert!(!snapshot.blocks.is_empty());
     }
@@ -667,18 +2693,17 @@ mod tests {
         // Verify nested lists produce a proper tree with list containers preserved
         let content = "- parent\n  - child 1\n  - child 2\n    - grandchild";
         let doc = DocumentHandle::from_string(content.to_string()).unwrap();
-        let snapshot = doc.get_snapshot();
+        let snapshot = doc.get_snapshot().unwrap();
 
         // Top level should be a list container
         assert_eq!(snapshot.blocks.len(), 1);
         let list = &snapshot.blocks[0];
-        assert_eq!(list.kind, "list");
-        assert_eq!(list.list_ordered, Some(false));
+        assert_eq!(list.kind, BlockKindDto::List { ordered: false });
 
         // List should contain the parent list_item
         assert_eq!(list.children.len(), 1);
         let parent = &list.children[0];
-        assert_eq!(parent.kind, "list_item");
+        assert!(is_list_item(parent));
         assert!(segments_to_text(&parent.segments).contains("parent"));
 
         // Parent should have a nested list container
@@ -687,14 +2712,11 @@ mod tests {
             "Parent should have nested content"
         );
         let nested_list = &parent.children[0];
-        assert_eq!(nested_list.kind, "list");
+        assert_eq!(nested_list.kind, BlockKindDto::List { ordered: false });
 
         // Count total list items in tree (parent, child 1, child 2, grandchild)
         let all_blocks = collect_all_blocks(&snapshot.blocks);
-        let all_list_items: Vec<_> = all_blocks
-            .iter()
-            .filter(|b| b.kind == "list_item")
-            .collect();
+        let all_list_items: Vec<_> = all_blocks.iter().filter(|b| is_list_item(b)).collect();
         assert_eq!(
             all_list_items.len(),
             4,
@@ -705,7 +2727,7 @@ mod tests {
         let nested_items: Vec<_> = nested_list
             .children
             .iter()
-            .filter(|b| b.kind == "list_item")
+            .filter(|b| is_list_item(b))
             .collect();
         assert_eq!(
             nested_items.len(),
@@ -718,7 +2740,7 @@ mod tests {
             .iter()
             .find(|b| segments_to_text(&b.segments).contains("child 2"));
         assert!(child2.is_some(), "Should find child 2");
-        let grandchild = find_block_by_kind(&child2.unwrap().children, "list_item");
+        let grandchild = find_list_item(&child2.unwrap().children);
         assert!(grandchild.is_some(), "Should find grandchild through tree");
         assert!(segments_to_text(&grandchild.unwrap().segments).contains("grandchild"));
     }
@@ -728,15 +2750,15 @@ mod tests {
         // Verify blockquotes with content produce correct structure
         let content = "> This is a quote";
         let doc = DocumentHandle::from_string(content.to_string()).unwrap();
-        let snapshot = doc.get_snapshot();
+        let snapshot = doc.get_snapshot().unwrap();
 
         assert_eq!(snapshot.blocks.len(), 1);
         let quote = &snapshot.blocks[0];
-        assert_eq!(quote.kind, "block_quote");
+        assert_eq!(quote.kind, BlockKindDto::BlockQuote);
         // BlockQuote content is in Paragraph children, not direct segments
         assert!(quote.segments.is_empty());
         assert_eq!(quote.children.len(), 1);
-        assert_eq!(quote.children[0].kind, "paragraph");
+        assert_eq!(quote.children[0].kind, BlockKindDto::Paragraph);
         assert_eq!(
             segments_to_text(&quote.children[0].segments),
             "This is a quote"
@@ -748,38 +2770,42 @@ mod tests {
         // Verify list containers are preserved with ordered flag
         let content = "- item 1\n- item 2";
         let doc = DocumentHandle::from_string(content.to_string()).unwrap();
-        let snapshot = doc.get_snapshot();
+        let snapshot = doc.get_snapshot().unwrap();
 
         // Top level should be a list, not a list_item
         assert_eq!(snapshot.blocks.len(), 1);
-        assert_eq!(snapshot.blocks[0].kind, "list");
-        assert_eq!(snapshot.blocks[0].list_ordered, Some(false));
+        assert_eq!(
+            snapshot.blocks[0].kind,
+            BlockKindDto::List { ordered: false }
+        );
 
         // List should contain the list items as children
         assert_eq!(snapshot.blocks[0].children.len(), 2);
         let item1 = &snapshot.blocks[0].children[0];
         let item2 = &snapshot.blocks[0].children[1];
-        assert_eq!(item1.kind, "list_item");
-        assert_eq!(item2.kind, "list_item");
+        assert!(is_list_item(item1));
+        assert!(is_list_item(item2));
 
         // Segments should NOT contain the marker (no duplication)
         assert_eq!(segments_to_text(&item1.segments), "item 1");
         assert_eq!(segments_to_text(&item2.segments), "item 2");
 
         // Marker should be separate
-        assert_eq!(item1.list_marker, Some("- ".to_string()));
+        assert!(matches!(&item1.kind, BlockKindDto::ListItem { marker, .. } if marker == "- "));
     }
 
     #[test]
     fn test_ordered_list_container() {
-        // Verify ordered lists have list_ordered = true
+        // Verify ordered lists are marked as ordered
         let content = "1. first\n2. second";
         let doc = DocumentHandle::from_string(content.to_string()).unwrap();
-        let snapshot = doc.get_snapshot();
+        let snapshot = doc.get_snapshot().unwrap();
 
         assert_eq!(snapshot.blocks.len(), 1);
-        assert_eq!(snapshot.blocks[0].kind, "list");
-        assert_eq!(snapshot.blocks[0].list_ordered, Some(true));
+        assert_eq!(
+            snapshot.blocks[0].kind,
+            BlockKindDto::List { ordered: true }
+        );
     }
 
     #[test]
@@ -787,19 +2813,578 @@ mod tests {
         // Verify document with both ordered and unordered lists
         let content = "# Header\n\n- bullet 1\n- bullet 2\n\n1. numbered 1\n2. numbered 2";
         let doc = DocumentHandle::from_string(content.to_string()).unwrap();
-        let snapshot = doc.get_snapshot();
+        let snapshot = doc.get_snapshot().unwrap();
 
         // Should have: heading, unordered list, ordered list
         assert_eq!(snapshot.blocks.len(), 3);
 
-        assert_eq!(snapshot.blocks[0].kind, "heading");
+        assert_eq!(snapshot.blocks[0].kind, BlockKindDto::Heading { level: 1 });
 
-        assert_eq!(snapshot.blocks[1].kind, "list");
-        assert_eq!(snapshot.blocks[1].list_ordered, Some(false));
+        assert_eq!(
+            snapshot.blocks[1].kind,
+            BlockKindDto::List { ordered: false }
+        );
         assert_eq!(snapshot.blocks[1].children.len(), 2);
 
-        assert_eq!(snapshot.blocks[2].kind, "list");
-        assert_eq!(snapshot.blocks[2].list_ordered, Some(true));
+        assert_eq!(
+            snapshot.blocks[2].kind,
+            BlockKindDto::List { ordered: true }
+        );
         assert_eq!(snapshot.blocks[2].children.len(), 2);
     }
+
+    // ============ Read state tests ============
+
+    #[test]
+    fn test_read_state_roundtrip_and_resolve() {
+        let content = "# Title\n\nFirst\n\nSecond\n";
+        let doc = DocumentHandle::from_string(content.to_string()).unwrap();
+        let snapshot = doc.get_snapshot().unwrap();
+        let second = snapshot.blocks.last().unwrap();
+        let position = ReadPosition {
+            block_id: second.id.clone(),
+            block_offset: second.source_start,
+            scroll_fraction: 0.8,
+        };
+
+        let state = ReadStateHandle::from_string(String::new()).unwrap();
+        state
+            .set("notes/long.md".to_string(), position.clone())
+            .unwrap();
+        let reloaded = ReadStateHandle::from_string(state.to_text().unwrap()).unwrap();
+        let saved = reloaded.get("notes/long.md".to_string()).unwrap().unwrap();
+
+        assert_eq!(saved, position);
+        assert_eq!(
+            doc.resolve_read_position(saved).unwrap(),
+            Some(second.id.clone())
+        );
+    }
+
+    #[test]
+    fn test_active_block_focus() {
+        let doc =
+            DocumentHandle::from_string("Café first. Then\nmore.\n\nOther\n".to_string()).unwrap();
+        let snapshot = doc.get_snapshot().unwrap();
+        assert!(snapshot.active.is_none());
+        assert_eq!(snapshot.blocks[0].focus, FocusHintDto::Normal);
+
+        let id = snapshot.blocks[0].id.clone();
+        doc.set_active_block(Some(id.clone())).unwrap();
+        assert_eq!(doc.active_block().unwrap(), Some(id.clone()));
+        let snapshot = doc.get_snapshot().unwrap();
+        let focus: Vec<_> = snapshot.blocks.iter().map(|b| b.focus).collect();
+        assert_eq!(focus, [FocusHintDto::Active, FocusHintDto::Dimmed]);
+
+        let active = snapshot.active.unwrap();
+        assert_eq!(active.block_id, id);
+        assert_eq!(active.sentences[1], ByteRange { start: 13, end: 23 });
+        // "é" is two bytes but one UTF-16 unit
+        assert_eq!(active.sentences_utf16[1], Utf16Range { start: 12, end: 22 });
+        assert_eq!(active.lines.len(), 2);
+
+        assert!(doc.set_active_block(Some("nope".to_string())).is_err());
+        doc.set_active_block(None).unwrap();
+        assert!(doc.get_snapshot().unwrap().active.is_none());
+    }
+
+    #[test]
+    fn test_snapshot_with_embeds() {
+        let notes_dir = tempfile::TempDir::new().unwrap();
+        let root = notes_dir.path().to_string_lossy().to_string();
+        std::fs::write(
+            notes_dir.path().join("other.md"),
+            "# Ötherß\n\n## Part\n\nText\n",
+        )
+        .unwrap();
+        let doc = DocumentHandle::from_string("Host\n\n![[Other#Part]]\n".to_string()).unwrap();
+
+        let plain = doc.get_snapshot().unwrap();
+        assert!(matches!(
+            &plain.blocks[1].kind,
+            BlockKindDto::Embed {
+                source_path: None,
+                ..
+            }
+        ));
+        assert!(plain.blocks[1].children.is_empty());
+
+        let snapshot = doc
+            .get_snapshot_with_embeds(root, Some("host.md".to_string()), None)
+            .unwrap();
+        let embed = &snapshot.blocks[1];
+        let BlockKindDto::Embed {
+            target,
+            heading,
+            source_path,
+            source_anchor,
+        } = &embed.kind
+        else {
+            panic!("expected embed, got {:?}", embed.kind);
+        };
+        assert_eq!(target, "Other");
+        assert_eq!(heading.as_deref(), Some("Part"));
+        assert_eq!(source_path.as_deref(), Some("other.md"));
+        assert_eq!(source_anchor.as_ref(), Some(&embed.children[0].id));
+        assert_eq!(embed.children.len(), 2);
+        // Ranges index the embedded note, where the accented title makes
+        // byte and UTF-16 offsets differ
+        assert_eq!(embed.children[1].content_start, 21);
+        assert_eq!(embed.children[1].content_utf16.start, 19);
+    }
+
+    #[test]
+    fn test_annotations_roundtrip_and_counts() {
+        let doc = Arc::new(DocumentHandle::from_string("First\n\nSecond\n".to_string()).unwrap());
+        let second = doc.get_snapshot().unwrap().blocks[1].id.clone();
+        let annotations = Arc::new(AnnotationsHandle::from_string(String::new()).unwrap());
+        let path = "notes/review.md".to_string();
+
+        let id = annotations
+            .add(
+                path.clone(),
+                doc.clone(),
+                second.clone(),
+                "Why?".to_string(),
+            )
+            .unwrap();
+        assert!(
+            annotations
+                .add(
+                    path.clone(),
+                    doc.clone(),
+                    "1".to_string(),
+                    "Lost".to_string()
+                )
+                .is_err()
+        );
+
+        let reloaded =
+            Arc::new(AnnotationsHandle::from_string(annotations.to_text().unwrap()).unwrap());
+        let listed = reloaded.list(path.clone(), doc.clone()).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].block_id.as_deref(), Some(second.as_str()));
+        assert_eq!(listed[0].quote, "Second");
+
+        let snapshot = doc
+            .get_annotated_snapshot(path.clone(), reloaded.clone(), None)
+            .unwrap();
+        let counts: Vec<u32> = snapshot.blocks.iter().map(|b| b.annotation_count).collect();
+        assert_eq!(counts, [0, 1]);
+
+        assert!(reloaded.set_resolved(path.clone(), id, true).unwrap());
+        let snapshot = doc.get_annotated_snapshot(path, reloaded, None).unwrap();
+        assert_eq!(snapshot.blocks[1].annotation_count, 0);
+    }
+
+    #[test]
+    fn test_read_position_rejects_invalid_block_id() {
+        let state = ReadStateHandle::from_string(String::new()).unwrap();
+        let position = ReadPosition {
+            block_id: "not-a-number".to_string(),
+            block_offset: 0,
+            scroll_fraction: 0.0,
+        };
+        assert!(state.set("a.md".to_string(), position).is_err());
+    }
+
+    // ============ Health tests ============
+
+    #[test]
+    fn test_health_check_healthy() {
+        let doc = DocumentHandle::from_string("# Title\n".to_string()).unwrap();
+        assert_eq!(doc.health_check().unwrap(), HealthStatus::Healthy);
+    }
+
+    #[test]
+    fn test_poisoned_lock_reloads_saved_text() {
+        let doc = std::sync::Arc::new(DocumentHandle::from_string("saved".to_string()).unwrap());
+
+        let poisoner = doc.clone();
+        let result = std::thread::spawn(move || {
+            let mut inner = poisoner.inner.lock().unwrap();
+            inner.apply(markdown_neuraxis_engine::editing::Cmd::InsertText {
+                at: 0,
+                text: "half-done ".to_string(),
+            });
+            panic!("engine failure mid-edit");
+        })
+        .join();
+        assert!(result.is_err());
+
+        assert_eq!(doc.get_text().unwrap(), "saved");
+        assert!(matches!(
+            doc.health_check().unwrap(),
+            HealthStatus::Recovered { reason } if reason.contains("poisoned")
+        ));
+        // Reported once, then healthy again
+        assert_eq!(doc.health_check().unwrap(), HealthStatus::Healthy);
+    }
+
+    #[test]
+    fn test_panic_during_edit_fails_without_replaying() {
+        let doc = DocumentHandle::from_string("saved".to_string()).unwrap();
+        doc.inner
+            .lock()
+            .unwrap()
+            .apply(markdown_neuraxis_engine::editing::Cmd::InsertText {
+                at: 5,
+                text: " unsaved".to_string(),
+            });
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+
+        let result = doc.with_document_mut(|d| {
+            calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            d.apply(markdown_neuraxis_engine::editing::Cmd::InsertText {
+                at: 13,
+                text: "!".to_string(),
+            });
+            panic!("invariant failed");
+        });
+
+        assert!(
+            matches!(result, Err(FfiError::Internal { reason, .. }) if reason == "invariant failed")
+        );
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(doc.get_text().unwrap(), "saved");
+        assert_eq!(
+            doc.health_check().unwrap(),
+            HealthStatus::Recovered {
+                reason: "panic: invariant failed".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_panic_becomes_internal_error() {
+        let result: Result<(), FfiError> = boundary("test.operation", || panic!("engine bug"));
+
+        let Err(FfiError::Internal { reason, backtrace }) = result else {
+            panic!("expected an internal error, got {result:?}");
+        };
+        assert_eq!(reason, "engine bug");
+        assert!(!backtrace.is_empty());
+        // Other tests may have reported a panic since, but not cleared it
+        assert!(last_error_report().is_some());
+    }
+
+    #[test]
+    fn test_mark_saved_updates_recovery_text() {
+        let doc = DocumentHandle::from_string("old".to_string()).unwrap();
+        doc.inner
+            .lock()
+            .unwrap()
+            .apply(markdown_neuraxis_engine::editing::Cmd::InsertText {
+                at: 3,
+                text: " and new".to_string(),
+            });
+        doc.mark_saved().unwrap();
+
+        // Corrupt the document so the health check has to rebuild it
+        doc.inner.lock().unwrap().set_selection(0..999);
+        assert!(matches!(
+            doc.health_check().unwrap(),
+            HealthStatus::Recovered { reason } if reason.starts_with("selection")
+        ));
+        assert_eq!(doc.get_text().unwrap(), "old and new");
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let doc = DocumentHandle::from_string("note".to_string()).unwrap();
+        assert!(!doc.can_undo().unwrap());
+        assert!(!doc.undo().unwrap());
+
+        doc.inner
+            .lock()
+            .unwrap()
+            .apply(markdown_neuraxis_engine::editing::Cmd::InsertText {
+                at: 4,
+                text: "s".to_string(),
+            });
+        assert!(doc.can_undo().unwrap());
+        assert!(doc.undo().unwrap());
+        assert_eq!(doc.get_text().unwrap(), "note");
+        assert!(doc.can_redo().unwrap());
+        assert!(doc.redo().unwrap());
+        assert_eq!(doc.get_text().unwrap(), "notes");
+    }
+
+    #[test]
+    fn test_read_only_refuses_edits_and_saves() {
+        let notes_dir = tempfile::TempDir::new().unwrap();
+        let root = notes_dir.path().to_string_lossy().to_string();
+        let path = notes_dir.path().join("note.md");
+        std::fs::write(&path, "- one").unwrap();
+
+        let doc = DocumentHandle::from_file(root, "note.md".to_string()).unwrap();
+        assert!(!doc.is_read_only().unwrap());
+        doc.set_read_only(true).unwrap();
+        let insert = CmdDto::InsertText {
+            at: 5,
+            text: " two".to_string(),
+        };
+        assert!(matches!(
+            doc.apply_command(insert.clone()),
+            Err(FfiError::ReadOnly { .. })
+        ));
+        assert!(matches!(doc.save(), Err(FfiError::ReadOnly { .. })));
+        assert_eq!(doc.get_text().unwrap(), "- one");
+
+        doc.set_read_only(false).unwrap();
+        doc.apply_command(insert).unwrap();
+        doc.save().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "- one two");
+    }
+
+    #[test]
+    fn test_replace_all_preview_apply_undo() {
+        let notes_dir = tempfile::TempDir::new().unwrap();
+        let root = notes_dir.path().to_string_lossy().to_string();
+        let path = notes_dir.path().join("note.md");
+        std::fs::write(&path, "Meet Bob, bob\n").unwrap();
+
+        let options = ReplaceOptionsDto {
+            ignore_case: true,
+            ..ReplaceOptionsDto::default()
+        };
+        let mut patches =
+            plan_replace_all(root.clone(), "bob".into(), "Rob".into(), options).unwrap();
+        assert_eq!(patches[0].edits.len(), 2);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "Meet Bob, bob\n");
+
+        // Only the first match
+        patches[0].edits.truncate(1);
+        assert_eq!(apply_replace_all(root.clone(), patches).unwrap(), 1);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "Meet Rob, bob\n");
+
+        assert_eq!(undo_replace_all(root.clone()).unwrap(), Some(1));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "Meet Bob, bob\n");
+        assert_eq!(undo_replace_all(root).unwrap(), None);
+    }
+
+    #[test]
+    fn test_from_file_and_save() {
+        let notes_dir = tempfile::TempDir::new().unwrap();
+        let root = notes_dir.path().to_string_lossy().to_string();
+        let path = notes_dir.path().join("note.md");
+        std::fs::write(&path, "- one").unwrap();
+
+        let doc = DocumentHandle::from_file(root.clone(), "note.md".to_string()).unwrap();
+        doc.apply_command(CmdDto::InsertText {
+            at: 5,
+            text: " two".to_string(),
+        })
+        .unwrap();
+        doc.save().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "- one two");
+
+        // Someone else edits the file
+        std::fs::write(&path, "- theirs").unwrap();
+        assert!(matches!(doc.save(), Err(FfiError::Conflict { .. })));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "- theirs");
+        doc.save_with(true).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "- one two");
+
+        assert!(matches!(
+            DocumentHandle::from_file(root, "missing.md".to_string()),
+            Err(FfiError::IoError { .. })
+        ));
+        assert!(
+            DocumentHandle::from_string(String::new())
+                .unwrap()
+                .save()
+                .is_err()
+        );
+    }
+
+    // ============ Editing tests ============
+
+    #[test]
+    fn test_apply_command_edits_document() {
+        let doc = DocumentHandle::from_string("- one".to_string()).unwrap();
+
+        let patch = doc.apply_command(CmdDto::SplitListItem { at: 5 }).unwrap();
+        assert_eq!(doc.get_text().unwrap(), "- one\n- ");
+        assert_eq!(patch.selection, ByteRange { start: 8, end: 8 });
+        assert_eq!(patch.version, 1);
+
+        doc.apply_command(CmdDto::InsertText {
+            at: 8,
+            text: "two".to_string(),
+        })
+        .unwrap();
+        doc.apply_command(CmdDto::IndentLines { start: 6, end: 11 })
+            .unwrap();
+        assert_eq!(doc.get_text().unwrap(), "- one\n  - two");
+
+        doc.apply_command(CmdDto::ToggleMarker {
+            line_start: 0,
+            marker: MarkerDto::Numbered {
+                label: "1.".to_string(),
+            },
+        })
+        .unwrap();
+        assert!(doc.get_text().unwrap().starts_with("1. one\n"));
+
+        assert!(doc.undo().unwrap());
+        assert!(doc.get_text().unwrap().starts_with("- one\n"));
+    }
+
+    #[test]
+    fn test_apply_command_rejects_bad_offsets() {
+        let doc = DocumentHandle::from_string("héllo".to_string()).unwrap();
+        for cmd in [
+            CmdDto::InsertText {
+                at: 2,
+                text: "x".to_string(),
+            },
+            CmdDto::DeleteRange { start: 0, end: 99 },
+            CmdDto::OutdentLines { start: 3, end: 1 },
+        ] {
+            assert!(matches!(
+                doc.apply_command(cmd),
+                Err(FfiError::InvalidCommand { .. })
+            ));
+        }
+        assert_eq!(doc.get_text().unwrap(), "héllo");
+    }
+
+    // ============ Long paragraph tests ============
+
+    #[test]
+    fn test_long_paragraph_chunks() {
+        let content = "A sentence with a [[Link]] in it. ".repeat(200);
+        let doc = DocumentHandle::from_string(content.clone()).unwrap();
+        let snapshot = doc.get_snapshot().unwrap();
+
+        assert_eq!(snapshot.blocks.len(), 1);
+        let paragraph = &snapshot.blocks[0];
+        assert!(paragraph.chunks.len() > 1);
+        let rejoined: String = paragraph
+            .chunks
+            .iter()
+            .map(|c| segments_to_text(&c.segments))
+            .collect();
+        assert_eq!(rejoined, segments_to_text(&paragraph.segments));
+        assert_eq!(paragraph.chunks.last().unwrap().end, paragraph.content_end);
+    }
+
+    #[test]
+    fn test_snapshot_truncates_long_blocks() {
+        let content = format!(
+            "Short\n\n{}\n",
+            "A sentence with a [[Link]] in it. ".repeat(200)
+        );
+        let doc = DocumentHandle::from_string(content).unwrap();
+        let snapshot = doc.get_snapshot_with(Some(50)).unwrap();
+
+        assert!(!snapshot.blocks[0].is_truncated);
+        let long = &snapshot.blocks[1];
+        assert!(long.is_truncated);
+        let text = segments_to_text(&long.segments);
+        assert!(text.len() <= 50);
+        // The second link did not fit, so it is dropped whole
+        assert_eq!(text, "A sentence with a Link in it. A sentence with a ");
+        let chunk_text: String = long
+            .chunks
+            .iter()
+            .map(|c| segments_to_text(&c.segments))
+            .collect();
+        assert_eq!(chunk_text, text);
+
+        let full = doc.get_block_content(long.id.clone()).unwrap().unwrap();
+        assert!(!full.is_truncated);
+        assert!(segments_to_text(&full.segments).len() > 1000);
+        assert!(
+            doc.get_block_content("missing".to_string())
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_truncate_segments_keeps_char_boundaries() {
+        let mut segments = vec![TextSegment {
+            kind: SegmentKindDto::Text,
+            content: "ééééé".to_string(),
+            children: vec![],
+            source_start: 2,
+            source_end: 12,
+            local_start: 0,
+            local_end: 10,
+            source_utf16: Utf16Range { start: 2, end: 7 },
+        }];
+        assert!(truncate_segments(&mut segments, 5));
+        assert_eq!(segments[0].content, "éé");
+        assert_eq!((segments[0].source_end, segments[0].local_end), (6, 4));
+        assert_eq!(segments[0].source_utf16, Utf16Range { start: 2, end: 4 });
+        assert!(!truncate_segments(&mut segments, 5));
+    }
+
+    // ============ Dashboard tests ============
+
+    #[test]
+    fn test_build_dashboard() {
+        let note = |path: &str, content: &str, modified_ms| DashboardNote {
+            path: path.to_string(),
+            content: content.to_string(),
+            modified_ms,
+        };
+        let areas = build_dashboard(
+            vec![
+                note(
+                    "projects/a.md",
+                    "- [ ] Ship due:: 2024-03-02
+",
+                    Some(1_000),
+                ),
+                note(
+                    "inbox.md",
+                    "area:: Home
+- [ ] Tidy
+",
+                    None,
+                ),
+            ],
+            "2024-03-01".to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(areas.len(), 2);
+        assert_eq!(areas[0].name, "Home");
+        assert_eq!(areas[0].open_tasks[0].text, "Tidy");
+        assert_eq!(areas[1].recent_edits, ["projects/a.md"]);
+        assert_eq!(areas[1].upcoming[0].deadline.as_deref(), Some("2024-03-02"));
+        assert!(build_dashboard(vec![], "soon".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_complete_and_insert_date() {
+        let completions = complete_dates("tom".to_string(), "2024-03-31".to_string()).unwrap();
+        assert_eq!(
+            completions,
+            [DateCompletion {
+                label: "tomorrow".to_string(),
+                date: "2024-04-01".to_string(),
+            }]
+        );
+
+        let doc = DocumentHandle::from_string("Due ".to_string()).unwrap();
+        doc.apply_command(CmdDto::InsertDate {
+            at: 4,
+            date: completions[0].date.clone(),
+            format: Some("link".to_string()),
+        })
+        .unwrap();
+        assert_eq!(doc.get_text().unwrap(), "Due [[2024-04-01]]");
+        assert!(matches!(
+            doc.apply_command(CmdDto::InsertDate {
+                at: 0,
+                date: "soon".to_string(),
+                format: None,
+            }),
+            Err(FfiError::InvalidCommand { .. })
+        ));
+    }
 }
@@ -0,0 +1,34 @@
+//! Lexer benchmarks.
+//!
+//! `ascii` takes the byte-wise fast path for the whole note. `non_ascii`
+//! lexes the same note with a leading non-ASCII character, which sends
+//! everything through Logos, so the difference is the fast path's gain.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use markdown_neuraxis_syntax::lexer::lex;
+use std::hint::black_box;
+
+fn large_note() -> String {
+    let mut note = String::from("# Large note\n\n");
+    for i in 0..2_000 {
+        note.push_str(&format!(
+            "- Item {i} with [[Wiki Link]] and *emphasis*\n  \
+             - Nested detail with `code` and [link](https://example.com/{i})\n\n\
+             Paragraph {i}: plain prose that goes on for a while. Another sentence here.\n\n"
+        ));
+    }
+    note
+}
+
+fn bench_lex(c: &mut Criterion) {
+    let ascii = large_note();
+    let non_ascii = format!("é{ascii}");
+
+    let mut group = c.benchmark_group("lex");
+    group.bench_function("ascii", |b| b.iter(|| lex(black_box(&ascii))));
+    group.bench_function("non_ascii", |b| b.iter(|| lex(black_box(&non_ascii))));
+    group.finish();
+}
+
+criterion_group!(benches, bench_lex);
+criterion_main!(benches);
@@ -0,0 +1,132 @@
+//! # Serializable AST
+//!
+//! External tools (scripts, other languages, test fixtures) often just want
+//! the parsed tree as JSON. Rowan's types are not serializable and tie the
+//! consumer to this crate's dependencies, so this module converts the CST
+//! into plain structs that mirror it one-to-one: every node and token, with
+//! its [`SyntaxKind`] (serialized by name, e.g. `"HEADING"`) and byte range.
+//!
+//! The conversion is still lossless: concatenating the token texts gives
+//! back the source.
+//!
+//! ```
+//! use markdown_neuraxis_syntax::{SyntaxKind, parse_to_ast};
+//!
+//! let ast = parse_to_ast("# Title\n");
+//! assert_eq!(ast.kind, SyntaxKind::ROOT);
+//! assert_eq!(ast.text(), "# Title\n");
+//! ```
+//!
+//! Only available with the `serde` feature.
+
+use rowan::NodeOrToken;
+use serde::{Deserialize, Serialize};
+
+use crate::syntax_kind::{SyntaxKind, SyntaxNode};
+
+/// A node with its children, in source order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AstNode {
+    pub kind: SyntaxKind,
+    /// Byte offset where the node starts
+    pub start: usize,
+    /// Byte offset where the node ends (exclusive)
+    pub end: usize,
+    pub children: Vec<AstElement>,
+}
+
+/// A leaf token and its source text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AstToken {
+    pub kind: SyntaxKind,
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+/// A child of an [`AstNode`], tagged as `"type": "node"` or `"type": "token"`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AstElement {
+    Node(AstNode),
+    Token(AstToken),
+}
+
+impl AstNode {
+    /// Convert a Rowan node and everything under it.
+    pub fn from_syntax(node: &SyntaxNode) -> Self {
+        let range = node.text_range();
+        Self {
+            kind: node.kind(),
+            start: range.start().into(),
+            end: range.end().into(),
+            children: node
+                .children_with_tokens()
+                .map(|child| match child {
+                    NodeOrToken::Node(node) => AstElement::Node(Self::from_syntax(&node)),
+                    NodeOrToken::Token(token) => {
+                        let range = token.text_range();
+                        AstElement::Token(AstToken {
+                            kind: token.kind(),
+                            start: range.start().into(),
+                            end: range.end().into(),
+                            text: token.text().to_string(),
+                        })
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// The source text covered by this node.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        self.push_text(&mut text);
+        text
+    }
+
+    fn push_text(&self, out: &mut String) {
+        for child in &self.children {
+            match child {
+                AstElement::Node(node) => node.push_text(out),
+                AstElement::Token(token) => out.push_str(&token.text),
+            }
+        }
+    }
+}
+
+/// Parse Markdown straight into a serializable [`AstNode`] tree.
+pub fn parse_to_ast(input: &str) -> AstNode {
+    AstNode::from_syntax(&crate::parse(input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ast_mirrors_cst() {
+        let source = "# Title\n\n- [ ] [[Link]] and *em*\n";
+        let ast = parse_to_ast(source);
+
+        assert_eq!(ast.text(), source);
+        assert_eq!((ast.start, ast.end), (0, source.len()));
+        let AstElement::Node(heading) = &ast.children[0] else {
+            panic!("expected a node");
+        };
+        assert_eq!(heading.kind, SyntaxKind::HEADING);
+        assert_eq!(heading.text(), "# Title\n");
+    }
+
+    #[test]
+    fn test_ast_json_roundtrip() {
+        let ast = parse_to_ast("Hi\n");
+        let json = serde_json::to_value(&ast).unwrap();
+
+        assert_eq!(json["kind"], "ROOT");
+        assert_eq!(json["children"][0]["type"], "node");
+        assert_eq!(json["children"][0]["kind"], "PARAGRAPH");
+        let back: AstNode = serde_json::from_value(json).unwrap();
+        assert_eq!(back, ast);
+    }
+}
@@ -0,0 +1,71 @@
+//! # Code Spans
+//!
+//! The grammar parses `` `code` `` as a `CODE_SPAN` node with the text
+//! between its backtick runs in a `CODE_SPAN_CONTENT` child. CommonMark
+//! then drops one space from each side when there is one on both sides and
+//! the text isn't all spaces, so a span can start or end with a backtick:
+//! ``` `` `ls` `` ``` shows `` `ls` ``. Use [`code_span_text`] for the text
+//! as shown.
+//!
+//! ```
+//! use markdown_neuraxis_syntax::{SyntaxKind, code_span::code_span_text, parse};
+//!
+//! let tree = parse("Run `` `ls` `` here.\n");
+//! let span = tree
+//!     .descendants()
+//!     .find(|node| node.kind() == SyntaxKind::CODE_SPAN)
+//!     .unwrap();
+//! assert_eq!(code_span_text(&span).as_deref(), Some("`ls`"));
+//! ```
+
+use crate::syntax_kind::{SyntaxKind, SyntaxNode};
+
+/// The text a `CODE_SPAN` node shows, or `None` for any other node.
+pub fn code_span_text(node: &SyntaxNode) -> Option<String> {
+    if node.kind() != SyntaxKind::CODE_SPAN {
+        return None;
+    }
+    let content = node
+        .children()
+        .find(|child| child.kind() == SyntaxKind::CODE_SPAN_CONTENT)?
+        .text()
+        .to_string();
+    let stripped = content
+        .strip_prefix(' ')
+        .and_then(|rest| rest.strip_suffix(' '));
+    Some(match stripped {
+        Some(stripped) if !content.trim_matches(' ').is_empty() => stripped.to_string(),
+        _ => content,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn texts(source: &str) -> Vec<String> {
+        parse(source)
+            .descendants()
+            .filter_map(|node| code_span_text(&node))
+            .collect()
+    }
+
+    #[test]
+    fn test_one_space_stripped_from_both_sides() {
+        assert_eq!(
+            texts("`` `a` `` and `  b  ` and ` c`\n"),
+            ["`a`", " b ", " c"]
+        );
+    }
+
+    #[test]
+    fn test_all_spaces_kept() {
+        assert_eq!(texts("`   `\n"), ["   "]);
+    }
+
+    #[test]
+    fn test_longer_run_inside() {
+        assert_eq!(texts("`` a ``` b ``\n"), ["a ``` b"]);
+    }
+}
@@ -0,0 +1,347 @@
+//! CommonMark spec conformance, tracked per section.
+//!
+//! Runs every example in `tests/commonmark/spec.json` and snapshots how many
+//! pass in each section, listing the failures. A parser change that fixes
+//! or breaks spec examples changes the snapshot, so coverage moves only
+//! when a reviewer accepts it.
+//!
+//! There's no HTML renderer to compare output with, so an example passes
+//! when the parsed tree has the same *shape* as the spec's HTML: the same
+//! blocks (paragraphs, headings, lists and items, blockquotes, code blocks,
+//! thematic breaks, tables, HTML blocks) with the same nesting, and the
+//! same inline elements (emphasis, strong, code spans, links, images, hard
+//! breaks) within them. Text isn't compared, so entities, escapes and
+//! whitespace handling aren't covered. The HTML reader is only good enough
+//! for the spec's output; raw HTML that looks like a known tag is misread,
+//! which just counts as a failure.
+//!
+//! To see why examples fail, list them in `COMMONMARK_EXAMPLES`:
+//!
+//! ```text
+//! COMMONMARK_EXAMPLES=43,229 cargo test -p markdown-neuraxis-syntax commonmark -- --nocapture
+//! ```
+
+use std::fmt::{self, Write as _};
+
+use rowan::NodeOrToken;
+
+use crate::{SyntaxKind, SyntaxNode, parse};
+
+/// A block or inline element and what's nested in it.
+#[derive(Debug, PartialEq)]
+struct Shape {
+    name: &'static str,
+    children: Vec<Shape>,
+}
+
+impl Shape {
+    fn leaf(name: &'static str) -> Self {
+        Self {
+            name,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// `ul[li[p[em]] li[p]]`
+struct Shapes<'a>(&'a [Shape]);
+
+impl fmt::Display for Shapes<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, shape) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_char(' ')?;
+            }
+            f.write_str(shape.name)?;
+            if !shape.children.is_empty() {
+                write!(f, "[{}]", Shapes(&shape.children))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The shape of the parsed tree.
+fn tree_shape(node: &SyntaxNode) -> Vec<Shape> {
+    let mut shapes = Vec::new();
+    for child in node.children_with_tokens() {
+        let node = match child {
+            NodeOrToken::Token(token) => {
+                if token.kind() == SyntaxKind::HARD_BREAK {
+                    shapes.push(Shape::leaf("br"));
+                }
+                continue;
+            }
+            NodeOrToken::Node(node) => node,
+        };
+        let name = match node.kind() {
+            SyntaxKind::PARAGRAPH => "p",
+            SyntaxKind::HEADING | SyntaxKind::SETEXT_HEADING => "h",
+            SyntaxKind::UNORDERED_LIST => "ul",
+            SyntaxKind::ORDERED_LIST => "ol",
+            SyntaxKind::LIST_ITEM => "li",
+            SyntaxKind::BLOCK_QUOTE => "quote",
+            SyntaxKind::FENCED_CODE | SyntaxKind::INDENTED_CODE => {
+                shapes.push(Shape::leaf("pre"));
+                continue;
+            }
+            SyntaxKind::THEMATIC_BREAK => "hr",
+            SyntaxKind::TABLE => {
+                shapes.push(Shape::leaf("table"));
+                continue;
+            }
+            SyntaxKind::HTML_BLOCK => "html",
+            SyntaxKind::FRONTMATTER => "frontmatter",
+            SyntaxKind::EMPHASIS => "em",
+            SyntaxKind::STRONG => "strong",
+            SyntaxKind::CODE_SPAN => "code",
+            SyntaxKind::LINK | SyntaxKind::AUTOLINK => "a",
+            SyntaxKind::IMAGE => {
+                // The spec renders alt text as plain text
+                shapes.push(Shape::leaf("img"));
+                continue;
+            }
+            SyntaxKind::STRIKETHROUGH => "del",
+            SyntaxKind::WIKILINK => "wikilink",
+            // Anything else (INLINE, TAG, PROPERTY, ...) is transparent
+            _ => {
+                shapes.extend(tree_shape(&node));
+                continue;
+            }
+        };
+        shapes.push(Shape {
+            name,
+            children: tree_shape(&node),
+        });
+    }
+    shapes
+}
+
+/// The shape of the spec's expected HTML.
+fn html_shape(html: &str) -> Vec<Shape> {
+    // Open elements, innermost last, and whether each is a paragraph the
+    // HTML leaves implicit (text directly in a tight list's `<li>`)
+    let mut stack = vec![(Shape::leaf("root"), false)];
+    let mut rest = html;
+
+    fn close(stack: &mut Vec<(Shape, bool)>) {
+        let (shape, _) = stack.pop().unwrap();
+        stack.last_mut().unwrap().0.children.push(shape);
+    }
+    fn add_leaf(stack: &mut [(Shape, bool)], name: &'static str) {
+        let children = &mut stack.last_mut().unwrap().0.children;
+        // Consecutive raw HTML is one block
+        if name != "html" || children.last().is_none_or(|last| last.name != "html") {
+            children.push(Shape::leaf(name));
+        }
+    }
+    // Open an implicit paragraph for inline content directly in a list
+    // item; inline content directly in a block container is raw HTML
+    fn inline_content(stack: &mut Vec<(Shape, bool)>) -> bool {
+        match stack.last().unwrap().0.name {
+            "li" => {
+                stack.push((Shape::leaf("p"), true));
+                true
+            }
+            "root" | "quote" | "ul" | "ol" => {
+                add_leaf(stack, "html");
+                false
+            }
+            _ => true,
+        }
+    }
+
+    while !rest.is_empty() {
+        let text_len = rest.find('<').unwrap_or(rest.len());
+        if !rest[..text_len].trim().is_empty() {
+            inline_content(&mut stack);
+        }
+        rest = &rest[text_len..];
+        if rest.is_empty() {
+            break;
+        }
+
+        let tag_len = rest.find('>').map_or(rest.len(), |end| end + 1);
+        let tag = &rest[..tag_len];
+        rest = &rest[tag_len..];
+        let closing = tag.starts_with("</");
+        let name: String = tag
+            .trim_start_matches(['<', '/'])
+            .chars()
+            .take_while(char::is_ascii_alphanumeric)
+            .collect();
+        let name = match name.as_str() {
+            "p" => "p",
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => "h",
+            "ul" => "ul",
+            "ol" => "ol",
+            "li" => "li",
+            "blockquote" => "quote",
+            "pre" => "pre",
+            "hr" => "hr",
+            "table" => "table",
+            "em" => "em",
+            "strong" => "strong",
+            "code" => "code",
+            "a" => "a",
+            "img" => "img",
+            "br" => "br",
+            _ => {
+                // Raw HTML: a block at block level, ignored inline
+                if !closing {
+                    let top = stack.last().unwrap().0.name;
+                    if matches!(top, "root" | "quote" | "ul" | "ol" | "li") {
+                        add_leaf(&mut stack, "html");
+                    }
+                }
+                continue;
+            }
+        };
+        let block = matches!(
+            name,
+            "p" | "h" | "ul" | "ol" | "li" | "quote" | "pre" | "hr" | "table"
+        );
+
+        if closing {
+            if stack.last().unwrap().1 && name != "p" {
+                close(&mut stack);
+            }
+            if stack.len() > 1 && stack.last().unwrap().0.name == name {
+                close(&mut stack);
+            }
+            continue;
+        }
+
+        if block {
+            if stack.last().unwrap().1 {
+                close(&mut stack);
+            }
+        } else if !inline_content(&mut stack) {
+            continue;
+        }
+        match name {
+            // Code and tables have no shape inside
+            "pre" | "table" => {
+                let end = format!("</{}>", if name == "pre" { "pre" } else { "table" });
+                rest = rest.find(&end).map_or("", |at| &rest[at + end.len()..]);
+                add_leaf(&mut stack, name);
+            }
+            "hr" | "img" | "br" => add_leaf(&mut stack, name),
+            _ => stack.push((Shape::leaf(name), false)),
+        }
+    }
+
+    while stack.len() > 1 {
+        close(&mut stack);
+    }
+    stack.pop().unwrap().0.children
+}
+
+/// `1-3, 5`
+fn ranges(numbers: &[u32]) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < numbers.len() {
+        let start = numbers[i];
+        while i + 1 < numbers.len() && numbers[i + 1] == numbers[i] + 1 {
+            i += 1;
+        }
+        if !out.is_empty() {
+            out.push_str(", ");
+        }
+        if numbers[i] == start {
+            write!(out, "{start}").unwrap();
+        } else {
+            write!(out, "{start}-{}", numbers[i]).unwrap();
+        }
+        i += 1;
+    }
+    out
+}
+
+#[test]
+fn test_html_shape_reads_implicit_paragraphs_and_code() {
+    let html = "<ul>\n<li>foo <em>bar</em></li>\n<li>\n<p>baz</p>\n<pre><code>&lt;em&gt;\n</code></pre>\n</li>\n</ul>\n<div>\n<hr />\n";
+    assert_eq!(
+        Shapes(&html_shape(html)).to_string(),
+        "ul[li[p[em]] li[p pre]] html hr"
+    );
+}
+
+#[test]
+fn test_tree_shape_matches_html_shape() {
+    let tree = parse("- foo *bar*\n\n# **x**\n![a](b)  \nc\n");
+    assert_eq!(
+        Shapes(&tree_shape(&tree)).to_string(),
+        "ul[li[p[em]]] h[strong] p[img br]"
+    );
+}
+
+#[test]
+fn test_commonmark_spec_coverage() {
+    let workspace_root = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap();
+    let spec = std::fs::read_to_string(workspace_root.join("tests/commonmark/spec.json")).unwrap();
+    let examples: Vec<serde_json::Value> = serde_json::from_str(&spec).unwrap();
+
+    // COMMONMARK_EXAMPLES=5,20 prints those examples' shapes
+    let show: Vec<u32> = std::env::var("COMMONMARK_EXAMPLES")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|n| n.trim().parse().ok())
+        .collect();
+
+    // Sections in spec order, with their passing and failing examples
+    let mut sections: Vec<(&str, Vec<u32>, Vec<u32>)> = Vec::new();
+    for example in &examples {
+        let number = example["example"].as_u64().unwrap() as u32;
+        let section = example["section"].as_str().unwrap();
+        let markdown = example["markdown"].as_str().unwrap();
+        let html = example["html"].as_str().unwrap();
+
+        if sections.last().is_none_or(|(name, ..)| *name != section) {
+            sections.push((section, Vec::new(), Vec::new()));
+        }
+        let (_, passed, failed) = sections.last_mut().unwrap();
+        let tree = parse(markdown);
+        assert_eq!(
+            tree.text().to_string(),
+            markdown,
+            "example {number}: roundtrip failed"
+        );
+        if show.contains(&number) {
+            eprintln!(
+                "example {number}: {markdown:?}\n  parsed: {}\n  spec:   {}",
+                Shapes(&tree_shape(&tree)),
+                Shapes(&html_shape(html))
+            );
+        }
+        if tree_shape(&tree) == html_shape(html) {
+            passed.push(number);
+        } else {
+            failed.push(number);
+        }
+    }
+
+    let mut report = String::new();
+    let mut total = 0;
+    for (name, passed, failed) in &sections {
+        total += passed.len();
+        let count = passed.len() + failed.len();
+        write!(report, "{name}: {}/{count}", passed.len()).unwrap();
+        if !failed.is_empty() {
+            write!(report, " (failing: {})", ranges(failed)).unwrap();
+        }
+        report.push('\n');
+    }
+    writeln!(report, "\nTotal: {total}/{}", examples.len()).unwrap();
+
+    let mut settings = insta::Settings::clone_current();
+    settings.set_prepend_module_to_snapshot(false);
+    settings
+        .set_snapshot_path(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/snapshots"));
+    settings.bind(|| insta::assert_snapshot!("commonmark_coverage", report));
+}
@@ -0,0 +1,212 @@
+//! # Code Fence Info Strings
+//!
+//! The grammar wraps the text after an opening code fence in a `CODE_INFO`
+//! node, with the language (if any) in a `CODE_LANGUAGE` child. This module
+//! reads the rest of that line into typed attributes so renderers don't
+//! each parse it their own way:
+//!
+//! ~~~text
+//! ```rust title="src/main.rs" {1,4-6} linenums
+//!    ^^^^ language
+//!         ^^^^^^^^^^^^^^^^^^^ attribute with a value
+//!                             ^^^^^^^ highlighted lines
+//!                                     ^^^^^^^^ attribute without a value
+//! ~~~
+//!
+//! ```
+//! use markdown_neuraxis_syntax::{FenceInfo, parse};
+//!
+//! let tree = parse("```rust title=main.rs {2}\nfn main() {}\n```\n");
+//! let info = FenceInfo::from_fenced_code(&tree.first_child().unwrap());
+//! assert_eq!(info.language.as_deref(), Some("rust"));
+//! assert_eq!(info.attribute("title"), Some("main.rs"));
+//! assert_eq!(info.highlight_lines, vec![2..=2]);
+//! ```
+
+use std::ops::RangeInclusive;
+
+use crate::syntax_kind::{SyntaxKind, SyntaxNode};
+
+/// A `key=value` or bare `key` attribute from a code fence info string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FenceAttribute {
+    pub key: String,
+    /// Value with any surrounding quotes removed; `None` for a bare key
+    pub value: Option<String>,
+}
+
+/// The parsed info string of a fenced code block.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FenceInfo {
+    /// Language word, e.g. `rust`
+    pub language: Option<String>,
+    /// Attributes in source order
+    pub attributes: Vec<FenceAttribute>,
+    /// 1-based line ranges from `{1,4-6}` groups, in source order
+    pub highlight_lines: Vec<RangeInclusive<u32>>,
+}
+
+impl FenceInfo {
+    /// Read the info string of a `FENCED_CODE` node. Nodes without one
+    /// give the default (no language or attributes).
+    pub fn from_fenced_code(node: &SyntaxNode) -> Self {
+        let Some(info) = node
+            .children()
+            .find(|child| child.kind() == SyntaxKind::CODE_INFO)
+        else {
+            return Self::default();
+        };
+        let language = info
+            .children()
+            .find(|child| child.kind() == SyntaxKind::CODE_LANGUAGE);
+        let attributes_start = language
+            .as_ref()
+            .map_or(info.text_range().start(), |language| {
+                language.text_range().end()
+            })
+            - info.text_range().start();
+
+        let text = info.text().to_string();
+        let mut parsed = Self::parse_attributes(&text[usize::from(attributes_start)..]);
+        parsed.language = language.map(|language| language.text().to_string());
+        parsed
+    }
+
+    /// Value of the first attribute named `key`, if it has one.
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.key == key)
+            .and_then(|attribute| attribute.value.as_deref())
+    }
+
+    /// Whether `line` (1-based) is in a highlighted range.
+    pub fn is_highlighted(&self, line: u32) -> bool {
+        self.highlight_lines
+            .iter()
+            .any(|range| range.contains(&line))
+    }
+
+    /// Parse the attributes following the language.
+    fn parse_attributes(text: &str) -> Self {
+        let mut info = Self::default();
+        let mut rest = text.trim_start();
+        while !rest.is_empty() {
+            if let Some(group) = rest.strip_prefix('{') {
+                let end = group.find('}').unwrap_or(group.len());
+                info.highlight_lines
+                    .extend(parse_line_ranges(&group[..end]));
+                rest = group.get(end + 1..).unwrap_or("");
+            } else {
+                let key_end = rest
+                    .find(|c: char| c.is_whitespace() || c == '=')
+                    .unwrap_or(rest.len());
+                let key = rest[..key_end].to_string();
+                rest = &rest[key_end..];
+                let value = match rest.strip_prefix('=') {
+                    Some(after) => {
+                        let (value, after) = split_value(after);
+                        rest = after;
+                        Some(value.to_string())
+                    }
+                    None => None,
+                };
+                if !key.is_empty() {
+                    info.attributes.push(FenceAttribute { key, value });
+                }
+            }
+            rest = rest.trim_start();
+        }
+        info
+    }
+}
+
+/// Split an attribute value (quoted with `"` or `'`, or up to whitespace)
+/// from the text after it.
+fn split_value(text: &str) -> (&str, &str) {
+    if let Some(quote) = text.chars().next().filter(|c| matches!(c, '"' | '\'')) {
+        let inner = &text[1..];
+        return match inner.find(quote) {
+            Some(end) => (&inner[..end], &inner[end + 1..]),
+            None => (inner, ""),
+        };
+    }
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    (&text[..end], &text[end..])
+}
+
+/// Parse `1,4-6` (commas or spaces between entries) into line ranges,
+/// skipping anything that isn't a number or range.
+fn parse_line_ranges(text: &str) -> impl Iterator<Item = RangeInclusive<u32>> + '_ {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter_map(|entry| match entry.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (start.trim().parse().ok()?, end.trim().parse().ok()?);
+                (start <= end).then_some(start..=end)
+            }
+            None => entry.trim().parse().ok().map(|line| line..=line),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn fence_info(source: &str) -> FenceInfo {
+        let tree = parse(source);
+        let node = tree
+            .descendants()
+            .find(|node| node.kind() == SyntaxKind::FENCED_CODE)
+            .unwrap();
+        FenceInfo::from_fenced_code(&node)
+    }
+
+    fn attribute(key: &str, value: Option<&str>) -> FenceAttribute {
+        FenceAttribute {
+            key: key.to_string(),
+            value: value.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_language_and_attributes() {
+        let info =
+            fence_info("```rust title=\"src/main.rs\" {1,4-6} linenums mode='a b'\nx\n```\n");
+        assert_eq!(info.language.as_deref(), Some("rust"));
+        assert_eq!(
+            info.attributes,
+            vec![
+                attribute("title", Some("src/main.rs")),
+                attribute("linenums", None),
+                attribute("mode", Some("a b")),
+            ]
+        );
+        assert_eq!(info.highlight_lines, vec![1..=1, 4..=6]);
+        assert!(info.is_highlighted(5));
+        assert!(!info.is_highlighted(3));
+    }
+
+    #[test]
+    fn test_attributes_without_language() {
+        let info = fence_info("~~~ {2} title=x\ncode\n~~~\n");
+        assert_eq!(info.language, None);
+        assert_eq!(info.attribute("title"), Some("x"));
+        assert_eq!(info.highlight_lines, vec![2..=2]);
+
+        let info = fence_info("```title=x\ncode\n```\n");
+        assert_eq!(info.language, None);
+        assert_eq!(info.attribute("title"), Some("x"));
+    }
+
+    #[test]
+    fn test_plain_and_malformed_info_strings() {
+        assert_eq!(fence_info("```\ncode\n```\n"), FenceInfo::default());
+        assert_eq!(fence_info("```c++\n```\n").language.as_deref(), Some("c++"));
+
+        let info = fence_info("``` python {3-1,x,7 title=\"open\n```\n");
+        assert_eq!(info.language.as_deref(), Some("python"));
+        assert_eq!(info.highlight_lines, vec![7..=7]);
+        assert!(info.attributes.is_empty());
+    }
+}
@@ -0,0 +1,139 @@
+//! # Heading Levels and Text
+//!
+//! ATX headings (`## Title`) take their level from the number of `#`s.
+//! Setext headings take it from their `SETEXT_UNDERLINE`: `===` makes a
+//! level 1 heading and `---` a level 2 one.
+//!
+//! An ATX heading's text is in a `HEADING_TEXT` node, without the `#`s or
+//! a closing sequence (`## Title ##`). A setext heading's text is the lines
+//! before its underline.
+//!
+//! ```
+//! use markdown_neuraxis_syntax::heading::{heading_level, heading_text};
+//! use markdown_neuraxis_syntax::parse;
+//!
+//! let tree = parse("Title\n=====\n\n### Part ###\n");
+//! let levels: Vec<_> = tree.children().filter_map(|node| heading_level(&node)).collect();
+//! assert_eq!(levels, vec![1, 3]);
+//! let texts: Vec<_> = tree.children().filter_map(|node| heading_text(&node)).collect();
+//! assert_eq!(texts, vec!["Title", "Part"]);
+//! ```
+
+use crate::{SyntaxKind, SyntaxNode};
+
+/// Level of a `HEADING` or `SETEXT_HEADING` node, or `None` for any other
+/// node.
+pub fn heading_level(node: &SyntaxNode) -> Option<u8> {
+    match node.kind() {
+        SyntaxKind::HEADING => Some(
+            node.children_with_tokens()
+                .take_while(|child| child.kind() == SyntaxKind::HASH)
+                .count() as u8,
+        ),
+        SyntaxKind::SETEXT_HEADING => {
+            let underline = node
+                .children()
+                .find(|child| child.kind() == SyntaxKind::SETEXT_UNDERLINE)?;
+            let equals = underline
+                .children_with_tokens()
+                .any(|child| child.kind() == SyntaxKind::EQUALS);
+            Some(if equals { 1 } else { 2 })
+        }
+        _ => None,
+    }
+}
+
+/// Text of a `HEADING` or `SETEXT_HEADING` node without its markers or
+/// the spaces around it, or `None` for any other node.
+pub fn heading_text(node: &SyntaxNode) -> Option<String> {
+    match node.kind() {
+        SyntaxKind::HEADING => Some(
+            node.children()
+                .find(|child| child.kind() == SyntaxKind::HEADING_TEXT)
+                .map(|text| text.text().to_string())
+                .unwrap_or_default(),
+        ),
+        SyntaxKind::SETEXT_HEADING => {
+            let text: String = node
+                .children_with_tokens()
+                .take_while(|child| child.kind() != SyntaxKind::SETEXT_UNDERLINE)
+                .map(|child| child.to_string())
+                .collect();
+            Some(text.trim().to_string())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn levels(source: &str) -> Vec<(SyntaxKind, u8)> {
+        parse(source)
+            .children()
+            .filter_map(|node| Some((node.kind(), heading_level(&node)?)))
+            .collect()
+    }
+
+    #[test]
+    fn test_setext_levels() {
+        assert_eq!(
+            levels("Title\n=====\n\nSubtitle\n---\n"),
+            vec![
+                (SyntaxKind::SETEXT_HEADING, 1),
+                (SyntaxKind::SETEXT_HEADING, 2)
+            ]
+        );
+        // One marker is enough, indented up to 3 spaces
+        assert_eq!(
+            levels("Title\n   =\n"),
+            vec![(SyntaxKind::SETEXT_HEADING, 1)]
+        );
+        assert_eq!(levels("Two lines\nof title\n--  \n").len(), 1);
+    }
+
+    #[test]
+    fn test_not_setext() {
+        // Spaced dashes are a thematic break after the paragraph
+        let tree = parse("Text\n- - -\n");
+        let kinds: Vec<_> = tree.children().map(|node| node.kind()).collect();
+        assert_eq!(
+            kinds,
+            vec![SyntaxKind::PARAGRAPH, SyntaxKind::THEMATIC_BREAK]
+        );
+        assert!(levels("Text\n    ===\n").is_empty());
+        assert!(levels("Text\n==x\n").is_empty());
+    }
+
+    fn texts(source: &str) -> Vec<String> {
+        parse(source)
+            .children()
+            .filter_map(|node| heading_text(&node))
+            .collect()
+    }
+
+    #[test]
+    fn test_closing_sequence_not_text() {
+        assert_eq!(
+            texts("## Title ##\n# C#\n#  Spaced\t#  \n### ###\n#\n"),
+            ["Title", "C#", "Spaced", "", ""]
+        );
+        // Escaped or attached `#`s are text
+        assert_eq!(texts("# Title \\#\n# Title#\n"), ["Title \\#", "Title#"]);
+    }
+
+    #[test]
+    fn test_setext_text() {
+        assert_eq!(
+            texts("  Two lines\nof title  \n---\n"),
+            ["Two lines\nof title"]
+        );
+    }
+
+    #[test]
+    fn test_atx_level() {
+        assert_eq!(levels("#### Four\n"), vec![(SyntaxKind::HEADING, 4)]);
+    }
+}
@@ -44,10 +44,32 @@
 //! - `-`, `*`, `+` → `DASH`, `STAR`, `PLUS` (lists, emphasis, thematic breaks)
 //! - `[`, `]`, `(`, `)` → bracket tokens (links)
 //! - `` ` ``, `~` → `BACKTICK`, `TILDE` (code, fenced blocks)
+//! - `\*`, `\[`, ... → `ESCAPE` (a backslash and the ASCII punctuation it
+//!   escapes, which then never acts as syntax); a lone `\` is `TEXT`. A
+//!   backslash before `` ` `` is left to the parser, as it escapes nothing
+//!   inside a code span.
 //!
 //! Everything else becomes `TEXT` tokens, grouped into runs of consecutive
 //! characters for efficiency (e.g., "Hello" is one TEXT token, not five).
 //!
+//! ## URLs
+//!
+//! The one exception is URLs: `https://example.com/a_b` is a single `URL`
+//! token rather than a run of text and punctuation, so the parser can tell a
+//! bare URL from its surroundings and its `_` or `*` never start emphasis.
+//! A URL starts where a `TEXT` token does, with a scheme and `://` (or
+//! `mailto:`), and runs until whitespace or one of `` <>[]`| ``, leaving
+//! trailing punctuation such as a sentence's final `.` or a closing `)` out.
+//!
+//! URLs are found in a pass over the tokens rather than by a Logos rule, as
+//! a rule overlapping `TEXT` would make Logos misread text like `a.png`.
+//!
+//! ## ASCII Fast Path
+//!
+//! Pure ASCII input is scanned byte by byte, mirroring the Logos rules, and
+//! Logos takes over from the first non-ASCII byte. The token output is the
+//! same either way; `benches/lexer.rs` measures the difference.
+//!
 //! ## Public API
 //!
 //! - [`lex`] - Tokenize input, returning `Vec<Token>`
@@ -152,8 +174,14 @@ pub enum TokenKind {
     #[token("=")]
     Equals,
 
-    /// Plain text - anything not matched by other rules
-    #[regex(r"[^\s\[\]()>`*+#|~.<_!:=-]+")]
+    /// Backslash escape of ASCII punctuation other than `` ` ``, e.g. `\*`
+    #[regex(r"\\[!-/:-@\[-_{-~]")]
+    Escape,
+
+    /// Plain text - anything not matched by other rules, or a backslash
+    /// that doesn't escape anything
+    #[regex(r"[^\s\[\]()>`*+#|~.<_!:=\\-]+")]
+    #[token("\\")]
     Text,
 }
 
@@ -181,6 +209,7 @@ impl TokenKind {
             TokenKind::Exclaim => SyntaxKind::EXCLAIM,
             TokenKind::Colon => SyntaxKind::COLON,
             TokenKind::Equals => SyntaxKind::EQUALS,
+            TokenKind::Escape => SyntaxKind::ESCAPE,
             TokenKind::Text => SyntaxKind::TEXT,
         }
     }
@@ -198,37 +227,297 @@ pub struct Token<'a> {
 /// Guarantees that all bytes from the input appear in the output tokens.
 pub fn lex(input: &str) -> Vec<Token<'_>> {
     let mut tokens = Vec::new();
-    let mut lexer = TokenKind::lexer(input);
-
-    while let Some(result) = lexer.next() {
-        let text = lexer.slice();
-        let kind = match result {
-            Ok(token_kind) => token_kind.to_syntax_kind(),
-            Err(()) => {
-                // Logos error means unrecognized character - treat as TEXT
-                SyntaxKind::TEXT
-            }
-        };
-        tokens.push(Token { kind, text });
-    }
-
+    tokenize(input, |kind, span| {
+        tokens.push(Token {
+            kind,
+            text: &input[span],
+        })
+    });
     tokens
 }
 
 /// Lex and return tokens along with their byte spans.
 pub fn lex_with_spans(input: &str) -> Vec<(Token<'_>, std::ops::Range<usize>)> {
     let mut tokens = Vec::new();
-    let mut lexer = TokenKind::lexer(input);
+    tokenize(input, |kind, span| {
+        let text = &input[span.clone()];
+        tokens.push((Token { kind, text }, span));
+    });
+    tokens
+}
+
+/// Produce every token's kind and span, in order, with URLs merged into
+/// single `URL` tokens.
+fn tokenize(input: &str, mut emit: impl FnMut(SyntaxKind, std::ops::Range<usize>)) {
+    let mut url_end = 0;
+    scan(input, |kind, span| {
+        if span.end <= url_end {
+            // Inside the URL already emitted
+        } else if span.start < url_end {
+            // A token the URL ends inside: the rest is trailing punctuation
+            emit(SyntaxKind::TEXT, url_end..span.end);
+        } else if kind == SyntaxKind::TEXT
+            && let Some(len) = url_len(&input[span.start..])
+        {
+            url_end = span.start + len;
+            emit(SyntaxKind::URL, span.start..url_end);
+            if span.end > url_end {
+                emit(SyntaxKind::TEXT, url_end..span.end);
+            }
+        } else {
+            emit(kind, span);
+        }
+    });
+}
+
+/// Length of the URL at the start of `text`, if there is one.
+fn url_len(text: &str) -> Option<usize> {
+    let scheme = text
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '+' | '.' | '-')))
+        .unwrap_or(text.len());
+    let body_start = if !text.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    } else if text[scheme..].starts_with("://") {
+        scheme + "://".len()
+    } else if text.starts_with("mailto:") {
+        "mailto:".len()
+    } else {
+        return None;
+    };
+
+    let mut len = None;
+    for (i, c) in text[body_start..].char_indices() {
+        if c.is_whitespace() || "<>[]`|".contains(c) {
+            break;
+        }
+        if !".,;:!?'\"*_~()".contains(c) {
+            len = Some(body_start + i + c.len_utf8());
+        }
+    }
+    len
+}
+
+/// Produce every token's kind and span, in order, without merging URLs.
+///
+/// Most notes are pure ASCII, where scanning bytes directly is much cheaper
+/// than Logos' UTF-8 aware matching. The fast path handles leading ASCII and
+/// hands the rest of the input to Logos from the start of the token where it
+/// meets the first non-ASCII byte. Tokens are context-free, so the output is
+/// identical to lexing everything with Logos.
+fn scan(input: &str, mut emit: impl FnMut(SyntaxKind, std::ops::Range<usize>)) {
+    let bytes = input.as_bytes();
+    let class_at = |pos: usize| bytes.get(pos).map(|&b| BYTE_CLASSES[b as usize]);
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let start = pos;
+        pos += 1;
+        let kind = match BYTE_CLASSES[bytes[start] as usize] {
+            ByteClass::Punctuation(kind) => kind,
+            ByteClass::Backslash
+                if bytes
+                    .get(pos)
+                    .is_some_and(|&b| b.is_ascii_punctuation() && b != b'`') =>
+            {
+                pos += 1;
+                SyntaxKind::ESCAPE
+            }
+            ByteClass::Backslash => SyntaxKind::TEXT,
+            ByteClass::Text => {
+                while class_at(pos) == Some(ByteClass::Text) {
+                    pos += 1;
+                }
+                if class_at(pos) == Some(ByteClass::NonAscii) {
+                    // The text run continues past ASCII; let Logos decide where it ends
+                    pos = start;
+                    break;
+                }
+                SyntaxKind::TEXT
+            }
+            ByteClass::Blank => {
+                while class_at(pos) == Some(ByteClass::Blank) {
+                    pos += 1;
+                }
+                SyntaxKind::WHITESPACE
+            }
+            ByteClass::Newline => SyntaxKind::NEWLINE,
+            ByteClass::CarriageReturn if bytes.get(pos) == Some(&b'\n') => {
+                pos += 1;
+                SyntaxKind::NEWLINE
+            }
+            // Whitespace no rule matches: Logos reports an error, lexed as TEXT
+            ByteClass::CarriageReturn | ByteClass::Unmatched => SyntaxKind::TEXT,
+            ByteClass::NonAscii => {
+                pos = start;
+                break;
+            }
+        };
+        emit(kind, start..pos);
+    }
 
+    let mut lexer = TokenKind::lexer(&input[pos..]);
     while let Some(result) = lexer.next() {
         let span = lexer.span();
-        let text = lexer.slice();
         let kind = match result {
             Ok(token_kind) => token_kind.to_syntax_kind(),
+            // Logos error means unrecognized character - treat as TEXT
             Err(()) => SyntaxKind::TEXT,
         };
-        tokens.push((Token { kind, text }, span));
+        emit(kind, pos + span.start..pos + span.end);
     }
+}
 
-    tokens
+/// How the ASCII fast path treats a byte, mirroring the Logos rules above.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ByteClass {
+    /// Part of a `Text` run
+    Text,
+    /// Space or tab
+    Blank,
+    Newline,
+    CarriageReturn,
+    /// Vertical tab and form feed, which no rule matches
+    Unmatched,
+    /// A single-byte token
+    Punctuation(SyntaxKind),
+    /// `\`, which starts an escape before ASCII punctuation
+    Backslash,
+    NonAscii,
+}
+
+const BYTE_CLASSES: [ByteClass; 256] = {
+    let mut table = [ByteClass::NonAscii; 256];
+    let mut byte = 0;
+    while byte < 128 {
+        table[byte] = ByteClass::Text;
+        byte += 1;
+    }
+    table[b' ' as usize] = ByteClass::Blank;
+    table[b'\t' as usize] = ByteClass::Blank;
+    table[b'\n' as usize] = ByteClass::Newline;
+    table[b'\r' as usize] = ByteClass::CarriageReturn;
+    table[0x0B] = ByteClass::Unmatched;
+    table[0x0C] = ByteClass::Unmatched;
+    table[b'>' as usize] = ByteClass::Punctuation(SyntaxKind::GT);
+    table[b'-' as usize] = ByteClass::Punctuation(SyntaxKind::DASH);
+    table[b'*' as usize] = ByteClass::Punctuation(SyntaxKind::STAR);
+    table[b'+' as usize] = ByteClass::Punctuation(SyntaxKind::PLUS);
+    table[b'`' as usize] = ByteClass::Punctuation(SyntaxKind::BACKTICK);
+    table[b'~' as usize] = ByteClass::Punctuation(SyntaxKind::TILDE);
+    table[b'[' as usize] = ByteClass::Punctuation(SyntaxKind::LBRACKET);
+    table[b']' as usize] = ByteClass::Punctuation(SyntaxKind::RBRACKET);
+    table[b'|' as usize] = ByteClass::Punctuation(SyntaxKind::PIPE);
+    table[b'(' as usize] = ByteClass::Punctuation(SyntaxKind::LPAREN);
+    table[b')' as usize] = ByteClass::Punctuation(SyntaxKind::RPAREN);
+    table[b'#' as usize] = ByteClass::Punctuation(SyntaxKind::HASH);
+    table[b'<' as usize] = ByteClass::Punctuation(SyntaxKind::LT);
+    table[b'.' as usize] = ByteClass::Punctuation(SyntaxKind::DOT);
+    table[b'_' as usize] = ByteClass::Punctuation(SyntaxKind::UNDERSCORE);
+    table[b'!' as usize] = ByteClass::Punctuation(SyntaxKind::EXCLAIM);
+    table[b':' as usize] = ByteClass::Punctuation(SyntaxKind::COLON);
+    table[b'=' as usize] = ByteClass::Punctuation(SyntaxKind::EQUALS);
+    table[b'\\' as usize] = ByteClass::Backslash;
+    table
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lex with Logos alone, as before the ASCII fast path.
+    fn lex_logos(input: &str) -> Vec<(SyntaxKind, std::ops::Range<usize>)> {
+        let mut lexer = TokenKind::lexer(input);
+        let mut tokens = Vec::new();
+        while let Some(result) = lexer.next() {
+            let kind = result.map_or(SyntaxKind::TEXT, |k| k.to_syntax_kind());
+            tokens.push((kind, lexer.span()));
+        }
+        tokens
+    }
+
+    fn assert_same_as_logos(input: &str) {
+        let mut fast = Vec::new();
+        scan(input, |kind, span| fast.push((kind, span)));
+        assert_eq!(fast, lex_logos(input), "input: {input:?}");
+    }
+
+    fn urls(input: &str) -> Vec<&str> {
+        lex(input)
+            .into_iter()
+            .filter(|token| token.kind == SyntaxKind::URL)
+            .map(|token| token.text)
+            .collect()
+    }
+
+    #[test]
+    fn test_ascii_fast_path_matches_logos() {
+        for input in [
+            "",
+            "# Heading\n\nSome *emphasis* and `code`.\n",
+            "- [ ] task\n  - nested item\n1. first\n",
+            "> quote | table | row\n---\n[[wiki]] ![img](a.png) <http://x>\n",
+            "a_b!c:d=e~f+g#h\t\t  tabs\r\nwindows\r\n",
+            "lone\rcarriage\x0bvertical\x0cfeed\r",
+            "\x00\x01control\x1f\x7f",
+            "\\*not em\\* \\[x\\] a\\b c\\\\ \\`tick\\` trailing\\\nend\\",
+        ] {
+            assert_same_as_logos(input);
+        }
+    }
+
+    #[test]
+    fn test_falls_back_to_logos_on_non_ascii() {
+        for input in [
+            "é",
+            "café au lait\n",
+            "ascii first, then naïve text\n",
+            "word\u{a0}nbsp and\u{2003}em space\n",
+            "- list\n- ünïcode *item*\n",
+            "emoji 🎉[link](x)\n",
+            "\\é\\ü and \\*ä\\*\n",
+        ] {
+            assert_same_as_logos(input);
+        }
+    }
+
+    #[test]
+    fn test_urls_are_single_tokens() {
+        assert_eq!(
+            urls("see https://example.com/a_b?q=1. or (http://x.io/p) <https://y.io>"),
+            vec![
+                "https://example.com/a_b?q=1",
+                "http://x.io/p",
+                "https://y.io"
+            ]
+        );
+        assert_eq!(
+            urls("mailto:me@example.com, svn+ssh://host/repo* https://ü.example/päth"),
+            vec![
+                "mailto:me@example.com",
+                "svn+ssh://host/repo",
+                "https://ü.example/päth"
+            ]
+        );
+        assert_eq!(
+            urls("[[https://a.io|alias]] `https://b.io` **https://c.io**"),
+            vec!["https://a.io", "https://b.io", "https://c.io"]
+        );
+        assert!(urls("https:// 1http://x example.com mailto: a:b").is_empty());
+    }
+
+    #[test]
+    fn test_url_trailing_punctuation_stays_text() {
+        let tokens = lex("https://x.io/it's' end");
+        assert_eq!(tokens[0].text, "https://x.io/it's");
+        assert_eq!(
+            tokens[1],
+            Token {
+                kind: SyntaxKind::TEXT,
+                text: "'"
+            }
+        );
+        let input = "(https://x.io/a).";
+        let text: String = lex(input).iter().map(|t| t.text).collect();
+        assert_eq!(text, input);
+    }
 }
@@ -59,10 +59,20 @@
 //! markdown-neuraxis-syntax/
 //! ├── lib.rs           # This file - public API and integration tests
 //! ├── syntax_kind.rs   # SyntaxKind enum (tokens + nodes) and Rowan integration
+//! ├── syntax_error.rs  # SyntaxError for malformed markdown the parser recovered from
 //! ├── lexer.rs         # Logos-based tokenizer
+//! ├── ast.rs           # Serializable AST mirror of the CST (`serde` feature)
+//! ├── code_span.rs     # Code span text (CommonMark space stripping)
+//! ├── fence_info.rs    # Typed code fence info strings (language, attributes)
+//! ├── heading.rs       # Heading levels (ATX and setext)
+//! ├── tag.rs           # Tag names (`#tag`)
+//! ├── visit.rs         # Tree walks: nodes of a kind, ancestors, nodes at an offset
+//! ├── wikilink.rs      # Wiki-link parts (target, section, alias)
+//! ├── reparse.rs       # Incremental reparse reusing unchanged blocks
+//! ├── commonmark.rs    # CommonMark spec coverage report (tests only)
 //! └── parser/
 //!     ├── mod.rs       # Parser struct, Marker system, public parse() function
-//!     ├── event.rs     # Event enum (Start, Token, Finish, Placeholder)
+//!     ├── event.rs     # Event enum (Start, Token, Finish, Error, Placeholder)
 //!     ├── sink.rs      # Converts events to Rowan GreenNode
 //!     └── grammar/
 //!         ├── mod.rs   # Root document parsing
@@ -92,7 +102,8 @@
 //! lines of Rust code. Key benefits:
 //!
 //! - **Error tolerance**: Malformed input produces a valid (if imperfect) tree
-//! - **Incremental potential**: Rowan supports incremental reparsing
+//! - **Incremental reparsing**: [`reparse`] reparses only the blocks an edit
+//!   touches, sharing every other green node with the old tree
 //! - **Memory efficient**: Green nodes are interned and shared
 //! - **Type safe**: The marker system prevents tree corruption at compile time
 //!
@@ -104,12 +115,31 @@
 //!   The reference implementation we're following
 //! - [Rowan crate docs](https://docs.rs/rowan) - The underlying tree library
 
+#[cfg(feature = "serde")]
+pub mod ast;
+pub mod code_span;
+#[cfg(test)]
+mod commonmark;
+pub mod fence_info;
+pub mod heading;
 pub mod lexer;
 pub mod parser;
+pub mod reparse;
+pub mod syntax_error;
 pub mod syntax_kind;
+pub mod table_alignment;
+pub mod tag;
+pub mod visit;
+pub mod wikilink;
 
-pub use parser::parse;
+#[cfg(feature = "serde")]
+pub use ast::parse_to_ast;
+pub use fence_info::{FenceAttribute, FenceInfo};
+pub use parser::{parse, parse_with_errors};
+pub use reparse::{TextEdit, reparse};
+pub use syntax_error::SyntaxError;
 pub use syntax_kind::{MarkdownLang, SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken};
+pub use table_alignment::TableAlignment;
 
 #[cfg(test)]
 mod tests {
@@ -15,7 +15,7 @@
 //!
 //! ## Event Types
 //!
-//! The four event types form a simple protocol:
+//! The event types form a simple protocol:
 //!
 //! ```text
 //! Start(HEADING)     ← Begin a HEADING node
@@ -26,6 +26,9 @@
 //! Finish             ← End the HEADING node
 //! ```
 //!
+//! An `Error` right after a `Finish` reports the node just finished as
+//! malformed (see [`SyntaxError`](crate::SyntaxError)).
+//!
 //! The Sink processes these in order, maintaining a stack of open nodes.
 //! Start pushes, Finish pops.
 //!
@@ -68,12 +71,22 @@ pub enum Event {
     /// consumes. Usually 1, but can be more when grouping.
     Token { kind: SyntaxKind, n_raw_tokens: u8 },
 
+    /// Add the first `len` bytes of the current raw token as a token.
+    ///
+    /// The next `Token` event starts with the rest of that raw token, so
+    /// one lexer token can be shared between two nodes (e.g. indentation
+    /// that is partly code).
+    TokenPrefix { kind: SyntaxKind, len: u32 },
+
     /// Finish the current node.
     ///
     /// Must be paired with a preceding `Start`. The Sink pops the node
     /// stack when it sees this.
     Finish,
 
+    /// Report the node finished by the preceding `Finish` as malformed.
+    Error { message: String },
+
     /// A placeholder that will be replaced.
     ///
     /// When `parser.start()` is called, a `Placeholder` is pushed. Later,
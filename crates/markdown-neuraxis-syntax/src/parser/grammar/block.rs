@@ -27,7 +27,9 @@
 //! - Blockquotes: `> quote`
 //! - Lists: `-`, `*`, `+`, `1.`
 //! - Task checkboxes: `- [ ]`, `- [x]`
-//! - Fenced code: `` ``` `` and `~~~`
+//! - Task state keywords: `- TODO`, `- DOING`, `- DONE`, `- LATER`
+//! - Fenced code: `` ``` `` and `~~~`, with an info string (`CODE_INFO`)
+//!   holding the language and attributes
 //! - Indented code: 4+ spaces at line start
 //! - Thematic breaks: `---`, `***`
 //! - HTML blocks: `<div>...</div>`
@@ -91,14 +93,14 @@ pub fn block(p: &mut Parser<'_, '_>) {
         SyntaxKind::HASH => heading(p),
         SyntaxKind::GT => blockquote(p),
         SyntaxKind::PIPE => {
-            if is_table_start(p) {
-                table(p);
+            if is_table_start(p, false) {
+                table(p, 0);
             } else {
                 paragraph(p);
             }
         }
         SyntaxKind::DASH | SyntaxKind::STAR | SyntaxKind::PLUS => {
-            // Could be frontmatter (--- at doc start with closing ---), thematic break, list item, or paragraph
+            // Could be frontmatter (--- or +++ at doc start with a closing fence), thematic break, list item, or paragraph
             if p.at_document_start() && is_frontmatter_start(p) {
                 frontmatter(p);
             } else if is_thematic_break(p) {
@@ -147,27 +149,28 @@ pub fn block(p: &mut Parser<'_, '_>) {
     }
 }
 
-/// Check if current position starts valid frontmatter (--- with closing ---)
+/// Check if current position starts valid frontmatter: `---` (YAML) or
+/// `+++` (TOML) with a matching closing fence
 fn is_frontmatter_start(p: &Parser<'_, '_>) -> bool {
-    // Must be exactly 3 dashes followed by newline
-    if p.current() != SyntaxKind::DASH {
+    let fence = p.current();
+    if !matches!(fence, SyntaxKind::DASH | SyntaxKind::PLUS) {
         return false;
     }
 
     let mut count = 0;
     let mut i = 0;
 
-    while p.nth(i) == SyntaxKind::DASH {
+    while p.nth(i) == fence {
         count += 1;
         i += 1;
     }
 
-    // Must be exactly 3 dashes, then newline (not EOF - need content)
+    // Must be exactly 3 fence characters, then newline (not EOF - need content)
     if count != 3 || p.nth(i) != SyntaxKind::NEWLINE {
         return false;
     }
 
-    // Look ahead to find closing ---
+    // Look ahead to find the closing fence
     i += 1; // skip newline
     loop {
         // Skip to start of next line
@@ -179,15 +182,16 @@ fn is_frontmatter_start(p: &Parser<'_, '_>) -> bool {
         }
         i += 1; // skip newline
 
-        // Check if this line is ---
-        if p.nth(i) == SyntaxKind::DASH {
-            let mut dash_count = 0;
+        // Check if this line is the closing fence
+        if p.nth(i) == fence {
+            let mut fence_count = 0;
             let mut j = i;
-            while p.nth(j) == SyntaxKind::DASH {
-                dash_count += 1;
+            while p.nth(j) == fence {
+                fence_count += 1;
                 j += 1;
             }
-            if dash_count == 3 && (p.nth(j) == SyntaxKind::NEWLINE || p.nth(j) == SyntaxKind::EOF) {
+            if fence_count == 3 && (p.nth(j) == SyntaxKind::NEWLINE || p.nth(j) == SyntaxKind::EOF)
+            {
                 return true; // Found closing fence
             }
         }
@@ -198,16 +202,12 @@ fn is_frontmatter_start(p: &Parser<'_, '_>) -> bool {
     }
 }
 
-/// Check if current position is a frontmatter fence (exactly ---)
-fn is_frontmatter_fence(p: &Parser<'_, '_>) -> bool {
-    if p.current() != SyntaxKind::DASH {
-        return false;
-    }
-
+/// Check if current position is a frontmatter fence (exactly three `fence`)
+fn is_frontmatter_fence(p: &Parser<'_, '_>, fence: SyntaxKind) -> bool {
     let mut count = 0;
     let mut i = 0;
 
-    while p.nth(i) == SyntaxKind::DASH {
+    while p.nth(i) == fence {
         count += 1;
         i += 1;
     }
@@ -215,28 +215,29 @@ fn is_frontmatter_fence(p: &Parser<'_, '_>) -> bool {
     count == 3 && (p.nth(i) == SyntaxKind::NEWLINE || p.nth(i) == SyntaxKind::EOF)
 }
 
-/// Parse YAML frontmatter block
+/// Parse a YAML (`---`) or TOML (`+++`) frontmatter block
 fn frontmatter(p: &mut Parser<'_, '_>) {
     let m = p.start();
+    let fence = p.current();
 
-    // Consume opening ---
-    p.bump(); // -
-    p.bump(); // -
-    p.bump(); // -
+    // Consume opening fence
+    p.bump();
+    p.bump();
+    p.bump();
     p.eat(SyntaxKind::NEWLINE);
 
-    // Consume content until closing --- or EOF
+    // Consume content until closing fence or EOF
     loop {
         if p.at_end() {
             break;
         }
 
         // Check for closing fence at line start
-        if p.at(SyntaxKind::DASH) && is_frontmatter_fence(p) {
-            // Consume closing ---
-            p.bump(); // -
-            p.bump(); // -
-            p.bump(); // -
+        if is_frontmatter_fence(p, fence) {
+            // Consume closing fence
+            p.bump();
+            p.bump();
+            p.bump();
             p.eat(SyntaxKind::NEWLINE);
             break;
         }
@@ -257,21 +258,16 @@ fn is_html_block_start(p: &Parser<'_, '_>) -> bool {
         return false;
     }
 
+    // Autolinks (<https://...>, <me@example.com>) start paragraphs, not HTML
+    if inline::autolink_len(p).is_some() {
+        return false;
+    }
+
     // Look at what follows <
     let next = p.nth(1);
 
-    // If followed by TEXT, check if it's a URL scheme (autolink) or HTML tag
+    // If followed by TEXT, it's a tag name
     if next == SyntaxKind::TEXT {
-        let text = p.nth_text(1);
-        // Common URL schemes - these are autolinks, not HTML
-        if text.starts_with("http")
-            || text.starts_with("https")
-            || text.starts_with("ftp")
-            || text.starts_with("mailto")
-        {
-            return false;
-        }
-        // It's a tag name
         return true;
     }
 
@@ -306,27 +302,29 @@ fn html_block(p: &mut Parser<'_, '_>) {
     m.complete(p, SyntaxKind::HTML_BLOCK);
 }
 
-/// Check if current position is a setext heading underline (=== or ---)
+/// Check if current position is a setext heading underline: a run of `=`
+/// or `-` indented at most 3 spaces, with only whitespace after it.
 fn is_setext_underline(p: &Parser<'_, '_>) -> bool {
-    let marker = p.current();
-    if !matches!(marker, SyntaxKind::EQUALS | SyntaxKind::DASH) {
-        return false;
-    }
-
-    // Need at least 3 markers for a setext underline
-    let mut count = 0;
     let mut i = 0;
-
-    while p.nth(i) != SyntaxKind::EOF && p.nth(i) != SyntaxKind::NEWLINE {
-        match p.nth(i) {
-            k if k == marker => count += 1,
-            SyntaxKind::WHITESPACE => {} // trailing whitespace OK
-            _ => return false,           // any other character invalidates
+    if p.at(SyntaxKind::WHITESPACE) {
+        if whitespace_width(p.current_text()) > 3 {
+            return false;
         }
         i += 1;
     }
 
-    count >= 3
+    let marker = p.nth(i);
+    if !matches!(marker, SyntaxKind::EQUALS | SyntaxKind::DASH) {
+        return false;
+    }
+    while p.nth(i) == marker {
+        i += 1;
+    }
+    // Trailing whitespace is fine; spaces between markers are not
+    if p.nth(i) == SyntaxKind::WHITESPACE {
+        i += 1;
+    }
+    matches!(p.nth(i), SyntaxKind::NEWLINE | SyntaxKind::EOF)
 }
 
 /// Check if current position starts a bullet list item (marker + whitespace)
@@ -339,14 +337,19 @@ fn is_bullet_list_start(p: &Parser<'_, '_>) -> bool {
 
 /// Check if current position is a thematic break (---, ***, etc.)
 fn is_thematic_break(p: &Parser<'_, '_>) -> bool {
-    let marker = p.current();
+    is_thematic_break_at(p, 0)
+}
+
+/// Check if position at offset is a thematic break.
+fn is_thematic_break_at(p: &Parser<'_, '_>, offset: usize) -> bool {
+    let marker = p.nth(offset);
     if !matches!(marker, SyntaxKind::DASH | SyntaxKind::STAR) {
         return false;
     }
 
     // Need at least 3 markers
     let mut count = 0;
-    let mut i = 0;
+    let mut i = offset;
 
     while p.nth(i) != SyntaxKind::EOF && p.nth(i) != SyntaxKind::NEWLINE {
         match p.nth(i) {
@@ -384,7 +387,7 @@ fn is_code_fence_at(p: &Parser<'_, '_>, offset: usize) -> bool {
     count >= 3
 }
 
-/// Parse an ATX heading.
+/// Parse an ATX heading, with its text in a `HEADING_TEXT` node.
 fn heading(p: &mut Parser<'_, '_>) {
     let m = p.start();
 
@@ -396,8 +399,18 @@ fn heading(p: &mut Parser<'_, '_>) {
     // Consume optional space after hashes
     p.eat(SyntaxKind::WHITESPACE);
 
-    // Parse inline content until end of line
-    inline::inline_until_newline(p);
+    // Parse inline content up to any closing sequence
+    let len = heading_text_len(p);
+    if len > 0 {
+        let text = p.start();
+        inline::inline_in_next(p, len);
+        text.complete(p, SyntaxKind::HEADING_TEXT);
+    }
+
+    // Closing `#`s and trailing spaces
+    while !p.at_end() && !p.at(SyntaxKind::NEWLINE) {
+        p.bump();
+    }
 
     // Consume the newline if present
     p.eat(SyntaxKind::NEWLINE);
@@ -405,6 +418,34 @@ fn heading(p: &mut Parser<'_, '_>) {
     m.complete(p, SyntaxKind::HEADING);
 }
 
+/// Number of tokens of heading text ahead, leaving out trailing spaces and
+/// a closing sequence: `#`s at the end of the line after a space, or making
+/// up the whole line (`## ##` is an empty heading).
+fn heading_text_len(p: &Parser<'_, '_>) -> usize {
+    let mut len = 0;
+    while !matches!(p.nth(len), SyntaxKind::NEWLINE | SyntaxKind::EOF) {
+        len += 1;
+    }
+    let trim_space = |len: usize| {
+        if len > 0 && p.nth(len - 1) == SyntaxKind::WHITESPACE {
+            len - 1
+        } else {
+            len
+        }
+    };
+
+    let len = trim_space(len);
+    let mut hashes = len;
+    while hashes > 0 && p.nth(hashes - 1) == SyntaxKind::HASH {
+        hashes -= 1;
+    }
+    if hashes < len && (hashes == 0 || p.nth(hashes - 1) == SyntaxKind::WHITESPACE) {
+        trim_space(hashes)
+    } else {
+        len
+    }
+}
+
 /// Parse a blockquote line.
 ///
 /// Each line starting with `>` creates a BLOCK_QUOTE node.
@@ -423,7 +464,7 @@ fn blockquote(p: &mut Parser<'_, '_>) {
         blockquote(p);
     } else {
         // Parse content until end of line
-        inline::inline_until_newline(p);
+        inline::inline_until_newline_with_breaks(p);
     }
 
     // Consume newline
@@ -588,6 +629,12 @@ fn blocks_in_list_item(p: &mut Parser<'_, '_>, content_indent: usize, sibling_in
         p.eat(SyntaxKind::WHITESPACE);
     }
 
+    // Then an optional task state keyword: TODO, DOING, DONE, LATER
+    if is_task_marker(p) {
+        task_marker(p);
+        p.eat(SyntaxKind::WHITESPACE);
+    }
+
     // First block: we're right after "- ", parse immediately (no indent check)
     dispatch_block_in_list_item(p, content_indent, sibling_indent);
 
@@ -665,46 +712,51 @@ fn dispatch_block_in_list_item(
     match p.current() {
         SyntaxKind::HASH => heading(p),
         SyntaxKind::GT => blockquote(p),
+        SyntaxKind::PIPE if is_table_start(p, true) => table(p, content_indent),
         SyntaxKind::DASH | SyntaxKind::STAR | SyntaxKind::PLUS => {
-            if p.nth(1) == SyntaxKind::WHITESPACE {
+            if is_thematic_break(p) {
+                thematic_break(p);
+            } else if p.nth(1) == SyntaxKind::WHITESPACE {
                 // Nested list
                 nested_list(p, content_indent);
             } else {
                 // Not a list marker (e.g., emphasis), treat as paragraph
-                paragraph_in_list_item(p, content_indent, sibling_indent);
+                paragraph_in_list_item(p, sibling_indent);
             }
         }
         SyntaxKind::TEXT => {
             if is_numbered_list_item(p) {
                 nested_list(p, content_indent);
             } else {
-                paragraph_in_list_item(p, content_indent, sibling_indent);
+                paragraph_in_list_item(p, sibling_indent);
             }
         }
         SyntaxKind::BACKTICK | SyntaxKind::TILDE => {
             if is_code_fence(p) {
                 fenced_code(p);
             } else {
-                paragraph_in_list_item(p, content_indent, sibling_indent);
+                paragraph_in_list_item(p, sibling_indent);
             }
         }
-        _ => paragraph_in_list_item(p, content_indent, sibling_indent),
+        _ => paragraph_in_list_item(p, sibling_indent),
     }
 }
 
 /// Parse a paragraph within a list item, with indent-aware continuation.
 ///
-/// Parses inline content, then loops for continuation lines that are:
-/// - Not outdented below content_indent
-/// - Not block markers (which end the paragraph)
+/// Parses inline content, then loops for continuation lines that are not
+/// block markers (which end the paragraph). Lines indented less than the
+/// item's content column, even at column 0, are lazy continuations: they
+/// still belong to the paragraph, as in CommonMark.
 ///
-/// `content_indent` is the expected content column for this list item.
-/// `_sibling_indent` is unused but kept for API consistency.
-fn paragraph_in_list_item(p: &mut Parser<'_, '_>, content_indent: usize, _sibling_indent: usize) {
+/// `sibling_indent` is the indent where sibling list items would appear. In
+/// a nested list that is the parent item's content column, so a line there
+/// belongs to the parent instead.
+fn paragraph_in_list_item(p: &mut Parser<'_, '_>, sibling_indent: usize) {
     let para = p.start();
 
     // Parse first line
-    inline::inline_until_newline(p);
+    inline::inline_until_newline_with_breaks(p);
 
     if !p.eat(SyntaxKind::NEWLINE) {
         para.complete(p, SyntaxKind::PARAGRAPH);
@@ -722,31 +774,31 @@ fn paragraph_in_list_item(p: &mut Parser<'_, '_>, content_indent: usize, _siblin
             break;
         }
 
-        // Must have some indentation
-        if !p.at(SyntaxKind::WHITESPACE) {
-            break;
-        }
-
-        let ws_width = whitespace_width(p.current_text());
+        // Offset of the line's content, after any indentation
+        let indented = p.at(SyntaxKind::WHITESPACE);
+        let content = usize::from(indented);
 
-        // Below content indent = outdented, end paragraph
-        if ws_width < content_indent {
+        // Blank line (whitespace-only)
+        if matches!(p.nth(content), SyntaxKind::NEWLINE | SyntaxKind::EOF) {
             break;
         }
 
-        // Blank line (whitespace-only)
-        if p.nth(1) == SyntaxKind::NEWLINE || p.nth(1) == SyntaxKind::EOF {
+        // Back at the parent item's content column
+        if sibling_indent > 0 && indented && whitespace_width(p.current_text()) == sibling_indent {
             break;
         }
 
-        // Block markers interrupt the paragraph
-        if interrupts_paragraph(p, 1) {
+        // Block markers interrupt the paragraph, including a thematic break
+        // that an outdented (lazy) line can't continue into a setext heading
+        if interrupts_paragraph(p, content) || is_thematic_break_at(p, content) {
             break;
         }
 
         // Continuation line - consume indentation and content
-        p.bump();
-        inline::inline_until_newline(p);
+        if indented {
+            p.bump();
+        }
+        inline::inline_until_newline_with_breaks(p);
         if !p.eat(SyntaxKind::NEWLINE) {
             break;
         }
@@ -809,6 +861,27 @@ fn checkbox(p: &mut Parser<'_, '_>) {
     m.complete(p, SyntaxKind::CHECKBOX);
 }
 
+/// Task state keywords recognised at the start of a list item.
+pub(super) const TASK_KEYWORDS: [&str; 4] = ["TODO", "DOING", "DONE", "LATER"];
+
+/// Check if current position is a task state keyword ending at a space or
+/// the end of the line (`TODOS` and `TODO:` are ordinary text)
+fn is_task_marker(p: &Parser<'_, '_>) -> bool {
+    p.current() == SyntaxKind::TEXT
+        && TASK_KEYWORDS.contains(&p.current_text())
+        && matches!(
+            p.nth(1),
+            SyntaxKind::WHITESPACE | SyntaxKind::NEWLINE | SyntaxKind::EOF
+        )
+}
+
+/// Parse a task state keyword
+fn task_marker(p: &mut Parser<'_, '_>) {
+    let m = p.start();
+    p.bump();
+    m.complete(p, SyntaxKind::TASK_MARKER);
+}
+
 /// Check if current position is a numbered list item (e.g., "1. ")
 fn is_numbered_list_item(p: &Parser<'_, '_>) -> bool {
     // Must start with TEXT containing only digits
@@ -880,37 +953,89 @@ fn is_indented_code_block(p: &Parser<'_, '_>) -> bool {
     }
 }
 
-/// Parse an indented code block (4+ spaces at line start)
+/// Parse an indented code block (4+ spaces at line start).
+///
+/// Blank lines between indented lines belong to the block, but trailing
+/// blank lines don't. Every token is kept, indentation included, so the
+/// block's text is exactly the source.
 fn indented_code(p: &mut Parser<'_, '_>) {
     let m = p.start();
 
     // Parse consecutive indented lines
     loop {
-        // Consume the whitespace and line content
-        while !p.at_end() && !p.at(SyntaxKind::NEWLINE) {
-            p.bump();
-        }
+        code_line(p);
 
         // Consume newline
         if !p.eat(SyntaxKind::NEWLINE) {
             break;
         }
 
-        // Check if next line continues the code block (4+ spaces)
-        if p.at(SyntaxKind::WHITESPACE) {
-            let ws_width = whitespace_width(p.current_text());
-            if ws_width >= 4 {
-                continue; // Continue code block
+        // Look past blank lines for another indented line
+        let mut lookahead = 0;
+        let mut blank_lines = 0;
+        loop {
+            match (p.nth(lookahead), p.nth(lookahead + 1)) {
+                (SyntaxKind::NEWLINE, _) => lookahead += 1,
+                (SyntaxKind::WHITESPACE, SyntaxKind::NEWLINE) => lookahead += 2,
+                _ => break,
             }
+            blank_lines += 1;
         }
 
-        // Not a continuation - end code block
-        break;
+        // Continue if the next non-blank line is indented 4+ spaces
+        let continues = p.nth(lookahead) == SyntaxKind::WHITESPACE
+            && whitespace_width(p.nth_text(lookahead)) >= 4
+            && p.nth(lookahead + 1) != SyntaxKind::EOF;
+        if !continues {
+            break;
+        }
+        // Blank lines are code too, and keep any spaces past the indentation
+        for _ in 0..blank_lines {
+            code_line(p);
+            p.bump();
+        }
     }
 
     m.complete(p, SyntaxKind::INDENTED_CODE);
 }
 
+/// One line of indented code, without its line break: up to four columns
+/// of indentation in a `CODE_INDENT` node, then the rest in a `CODE_TEXT`
+/// node. Wider indentation is split, so the extra spaces are code.
+fn code_line(p: &mut Parser<'_, '_>) {
+    let mut split = false;
+    if p.at(SyntaxKind::WHITESPACE) {
+        let indent = p.start();
+        let len = code_indent_len(p.current_text());
+        if len < p.current_text().len() {
+            p.bump_prefix(len, SyntaxKind::WHITESPACE);
+            split = true;
+        } else {
+            p.bump();
+        }
+        indent.complete(p, SyntaxKind::CODE_INDENT);
+    }
+    if split || !(p.at_end() || p.at(SyntaxKind::NEWLINE)) {
+        let text = p.start();
+        while !p.at_end() && !p.at(SyntaxKind::NEWLINE) {
+            p.bump();
+        }
+        text.complete(p, SyntaxKind::CODE_TEXT);
+    }
+}
+
+/// Bytes of `whitespace` in its first four columns, a tab counting as four.
+fn code_indent_len(whitespace: &str) -> usize {
+    let mut columns = 0;
+    for (i, c) in whitespace.char_indices() {
+        if columns >= 4 {
+            return i;
+        }
+        columns += if c == '\t' { 4 } else { 1 };
+    }
+    whitespace.len()
+}
+
 /// Check if current whitespace precedes an indented list item
 fn is_indented_list_item(p: &Parser<'_, '_>) -> bool {
     if p.current() != SyntaxKind::WHITESPACE {
@@ -956,15 +1081,19 @@ fn fenced_code(p: &mut Parser<'_, '_>) {
         fence_len += 1;
     }
 
-    // Parse info string (language)
-    while !p.at_end() && !p.at(SyntaxKind::NEWLINE) {
+    // Parse info string (language and attributes)
+    while p.at(SyntaxKind::WHITESPACE) {
         p.bump();
     }
+    if !p.at_end() && !p.at(SyntaxKind::NEWLINE) {
+        code_info(p);
+    }
 
     // Consume newline after opening fence
     p.eat(SyntaxKind::NEWLINE);
 
     // Parse content until closing fence
+    let mut closed = false;
     loop {
         if p.at_end() {
             break;
@@ -995,6 +1124,7 @@ fn fenced_code(p: &mut Parser<'_, '_>) {
                     p.bump();
                 }
                 p.eat(SyntaxKind::NEWLINE);
+                closed = true;
                 break;
             }
         }
@@ -1006,7 +1136,47 @@ fn fenced_code(p: &mut Parser<'_, '_>) {
         p.eat(SyntaxKind::NEWLINE);
     }
 
-    m.complete(p, SyntaxKind::FENCED_CODE);
+    let code = m.complete(p, SyntaxKind::FENCED_CODE);
+    if !closed {
+        // The code runs to the end of the document, as CommonMark says
+        code.error(p, "unclosed code fence");
+    }
+}
+
+/// Parse a code fence info string up to the end of the line. The first
+/// word is the language unless it is already an attribute (`{1,3}` or
+/// `key=value`).
+fn code_info(p: &mut Parser<'_, '_>) {
+    let m = p.start();
+
+    if !at_fence_attribute(p) {
+        let language = p.start();
+        while !p.at_end() && !p.at(SyntaxKind::NEWLINE) && !p.at(SyntaxKind::WHITESPACE) {
+            p.bump();
+        }
+        language.complete(p, SyntaxKind::CODE_LANGUAGE);
+    }
+
+    while !p.at_end() && !p.at(SyntaxKind::NEWLINE) {
+        p.bump();
+    }
+
+    m.complete(p, SyntaxKind::CODE_INFO);
+}
+
+/// Whether the next word of an info string is an attribute.
+fn at_fence_attribute(p: &Parser<'_, '_>) -> bool {
+    if p.current_text().starts_with('{') {
+        return true;
+    }
+    let mut n = 0;
+    loop {
+        match p.nth(n) {
+            SyntaxKind::EQUALS => return true,
+            SyntaxKind::WHITESPACE | SyntaxKind::NEWLINE | SyntaxKind::EOF => return false,
+            _ => n += 1,
+        }
+    }
 }
 
 /// Parse a paragraph (default block), possibly converting to setext heading.
@@ -1015,7 +1185,7 @@ fn paragraph(p: &mut Parser<'_, '_>) {
 
     // Consume until blank line or block-level construct
     loop {
-        inline::inline_until_newline(p);
+        inline::inline_until_newline_with_breaks(p);
 
         if !p.eat(SyntaxKind::NEWLINE) {
             break;
@@ -1028,10 +1198,11 @@ fn paragraph(p: &mut Parser<'_, '_>) {
 
         // Check for setext heading underline (=== or ---)
         if is_setext_underline(p) {
-            // Consume the underline
+            let underline = p.start();
             while !p.at_end() && !p.at(SyntaxKind::NEWLINE) {
                 p.bump();
             }
+            underline.complete(p, SyntaxKind::SETEXT_UNDERLINE);
             p.eat(SyntaxKind::NEWLINE);
             m.complete(p, SyntaxKind::SETEXT_HEADING);
             return;
@@ -1067,7 +1238,10 @@ fn paragraph(p: &mut Parser<'_, '_>) {
 }
 
 /// Check if current position starts a GFM table (header row + delimiter row).
-fn is_table_start(p: &Parser<'_, '_>) -> bool {
+///
+/// In a list item (`indented`), the delimiter row may be indented to the
+/// item's content.
+fn is_table_start(p: &Parser<'_, '_>, indented: bool) -> bool {
     if p.current() != SyntaxKind::PIPE {
         return false;
     }
@@ -1083,6 +1257,9 @@ fn is_table_start(p: &Parser<'_, '_>) -> bool {
         return false;
     }
     i += 1;
+    if indented && p.nth(i) == SyntaxKind::WHITESPACE {
+        i += 1;
+    }
 
     // Second line must be a delimiter row: starts with | followed by dashes
     if p.nth(i) != SyntaxKind::PIPE {
@@ -1099,8 +1276,9 @@ fn is_table_start(p: &Parser<'_, '_>) -> bool {
     matches!(p.nth(i), SyntaxKind::DASH | SyntaxKind::COLON)
 }
 
-/// Parse a GFM-style table.
-fn table(p: &mut Parser<'_, '_>) {
+/// Parse a GFM-style table. In a list item, `indent` is the item's content
+/// indent, which rows after the first must have.
+fn table(p: &mut Parser<'_, '_>, indent: usize) {
     let m = p.start();
 
     // Parse header section
@@ -1109,11 +1287,17 @@ fn table(p: &mut Parser<'_, '_>) {
     head.complete(p, SyntaxKind::TABLE_HEAD);
 
     // Parse delimiter row
+    if indent > 0 {
+        p.eat(SyntaxKind::WHITESPACE);
+    }
     table_delimiter_row(p);
 
     // Parse body section (remaining rows)
     let body = p.start();
-    while !p.at_end() && p.at(SyntaxKind::PIPE) {
+    while !p.at_end() && at_table_row(p, indent) {
+        if indent > 0 {
+            p.bump();
+        }
         table_row(p);
     }
     body.complete(p, SyntaxKind::TABLE_BODY);
@@ -1121,6 +1305,16 @@ fn table(p: &mut Parser<'_, '_>) {
     m.complete(p, SyntaxKind::TABLE);
 }
 
+/// Check if the current line is a table row indented by `indent`.
+fn at_table_row(p: &Parser<'_, '_>, indent: usize) -> bool {
+    if indent == 0 {
+        return p.at(SyntaxKind::PIPE);
+    }
+    p.at(SyntaxKind::WHITESPACE)
+        && whitespace_width(p.current_text()) >= indent
+        && p.nth(1) == SyntaxKind::PIPE
+}
+
 /// Parse a table row (sequence of cells).
 fn table_row(p: &mut Parser<'_, '_>) {
     let m = p.start();
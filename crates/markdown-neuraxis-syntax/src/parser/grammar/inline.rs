@@ -13,23 +13,50 @@
 //! | `[` | Link or wikilink |
 //! | `` ` `` | Code span |
 //! | `*` | Emphasis or strong |
+//! | `#` | Tag |
+//! | `<` | Autolink |
 //! | (other) | Plain text |
 //!
 //! ## Wikilinks vs Standard Links
 //!
 //! We support both:
-//! - **Wikilinks**: `[[page]]` or `[[page|display text]]` (MDNX extension)
-//! - **Standard links**: `[text](url)` (CommonMark)
+//! - **Wikilinks**: `[[page]]`, `[[page#section]]` or `[[page|display text]]`
+//!   (MDNX extension), the parts as nodes (see [`crate::wikilink`])
+//! - **Standard links**: `[text](url "title")` (CommonMark), the parts in
+//!   the parens as `LINK_DESTINATION` and `LINK_TITLE` nodes
 //!
 //! Disambiguation: if we see `[[` (two brackets), it's a wikilink. Otherwise,
 //! we try to parse a standard link and fall back to plain text.
 //!
+//! ## Emphasis
+//!
+//! `*` and `_` can't be matched as they're met: whether `***` opens strong,
+//! emphasis or both depends on what follows. So delimiter runs are parsed
+//! as plain tokens, each reserving a slot before and after it, and once the
+//! line (or link text) is done [`Delimiters::resolve`] runs CommonMark's
+//! "process emphasis" algorithm and fills the slots with the `EMPHASIS` and
+//! `STRONG` nodes' starts and finishes. Unmatched delimiters stay plain
+//! text. Emphasis doesn't span lines.
+//!
+//! ## Escapes and Hard Breaks
+//!
+//! The lexer makes `\*`, `\[` etc. `ESCAPE` tokens, so escaped punctuation
+//! never matches as a delimiter here. `` \` `` is the exception, handled by
+//! not opening a code span after a lone `\`.
+//!
+//! In text where line breaks count (paragraphs, list items, blockquotes),
+//! [`inline_until_newline_with_breaks`] turns two or more trailing spaces,
+//! or a trailing `\`, into a `HARD_BREAK` token when another line follows.
+//!
 //! ## Error Tolerance
 //!
 //! Inline parsing is lenient:
-//! - Unclosed `[[` still produces a WIKILINK node (containing the unclosed content)
+//! - Unclosed `[[` still produces a WIKILINK node (containing the unclosed
+//!   content), reported as a [`SyntaxError`](crate::SyntaxError)
 //! - `[text]` without `(url)` becomes an INLINE node (bracket as plain text)
-//! - Unmatched `*` is consumed as plain text
+//! - A link's `(...)` ends at the first `)`, even inside `<...>` or a title
+//! - Unmatched `*` and `_` delimiters, and backtick runs with no run of
+//!   the same length after them, are consumed as plain text
 //!
 //! This ensures we always produce a valid tree that preserves all bytes.
 //!
@@ -38,34 +65,76 @@
 //! - Emphasis: `*em*`, `_em_`, `**strong**`, `__strong__`
 //! - Strikethrough: `~~text~~`
 //! - Images: `![alt](url)`
-//! - Autolinks: `<https://url>`
+//! - Autolinks: `<https://url>` and `<me@example.com>`
+//! - Bare URLs: `https://url`, a single `URL` token (see [`crate::lexer`])
 //! - Goal references: `((uuid))` (MDNX extension)
 //! - Properties: `name:: value` (MDNX extension)
+//! - Tags: `#name` (see [`crate::tag`])
+
+use std::ops::Range;
 
 use crate::parser::Parser;
 use crate::syntax_kind::SyntaxKind;
+use crate::tag::{is_tag_char, tag_name};
+
+/// Parse inline content in the next `len` tokens of the line, dispatching
+/// to specific inline element handlers. An element starting within them,
+/// such as a code span, can run past them.
+pub fn inline_in_next(p: &mut Parser<'_, '_>, len: usize) {
+    let stop = p.remaining().saturating_sub(len);
+    let mut delimiters = Delimiters::default();
+    while p.remaining() > stop && !p.at_end() && !p.at(SyntaxKind::NEWLINE) {
+        inline_element(p, &mut delimiters);
+    }
+    delimiters.resolve(p);
+}
 
-/// Parse inline content until newline or EOF.
-///
-/// This is the main entry point called by block parsers. It consumes tokens
-/// until it hits a newline, dispatching to specific inline element handlers.
-pub fn inline_until_newline(p: &mut Parser<'_, '_>) {
+/// Parse inline content until newline or EOF, for text where line breaks
+/// count: a hard break at the end of the line becomes a `HARD_BREAK` token.
+pub fn inline_until_newline_with_breaks(p: &mut Parser<'_, '_>) {
+    let mut delimiters = Delimiters::default();
     while !p.at_end() && !p.at(SyntaxKind::NEWLINE) {
-        inline_element(p);
+        if at_hard_break(p) {
+            p.bump_n(1, SyntaxKind::HARD_BREAK);
+        } else {
+            inline_element(p, &mut delimiters);
+        }
     }
+    delimiters.resolve(p);
+}
+
+/// Check if the current token is two or more trailing spaces or a trailing
+/// `\`, followed by a line that isn't blank.
+fn at_hard_break(p: &Parser<'_, '_>) -> bool {
+    let breaks = match p.current() {
+        SyntaxKind::WHITESPACE => p.current_text().ends_with("  "),
+        SyntaxKind::TEXT => p.current_text() == "\\",
+        _ => false,
+    };
+    let next_line = if p.nth(2) == SyntaxKind::WHITESPACE {
+        3
+    } else {
+        2
+    };
+    breaks
+        && p.nth(1) == SyntaxKind::NEWLINE
+        && !matches!(p.nth(next_line), SyntaxKind::NEWLINE | SyntaxKind::EOF)
 }
 
 /// Parse inline content until pipe, newline, or EOF.
 ///
 /// Used for table cells where pipes delimit cell boundaries.
 pub fn inline_until_pipe_or_newline(p: &mut Parser<'_, '_>) {
+    let mut delimiters = Delimiters::default();
     while !p.at_end() && !p.at(SyntaxKind::NEWLINE) && !p.at(SyntaxKind::PIPE) {
-        inline_element(p);
+        inline_element(p, &mut delimiters);
     }
+    delimiters.resolve(p);
 }
 
-/// Parse a single inline element.
-fn inline_element(p: &mut Parser<'_, '_>) {
+/// Parse a single inline element. Emphasis delimiters are added to
+/// `delimiters` for the caller to resolve.
+fn inline_element(p: &mut Parser<'_, '_>, delimiters: &mut Delimiters) {
     match p.current() {
         SyntaxKind::LBRACKET => {
             // Could be wikilink [[...]] or standard link [...]()
@@ -75,13 +144,15 @@ fn inline_element(p: &mut Parser<'_, '_>) {
                 link_or_text(p);
             }
         }
+        // `\`` is an escaped backtick, not the start of a code span
+        SyntaxKind::BACKTICK if p.prev() == SyntaxKind::TEXT && p.prev_text() == "\\" => p.bump(),
         SyntaxKind::BACKTICK => code_span(p),
-        SyntaxKind::STAR => emphasis_or_strong(p, SyntaxKind::STAR),
-        SyntaxKind::UNDERSCORE => emphasis_or_strong(p, SyntaxKind::UNDERSCORE),
+        SyntaxKind::STAR | SyntaxKind::UNDERSCORE => delimiters.push_run(p),
         SyntaxKind::TILDE => strikethrough(p),
         SyntaxKind::EXCLAIM => {
-            // Could be image ![alt](url)
-            if p.nth(1) == SyntaxKind::LBRACKET {
+            // Could be image ![alt](url), or an embed ![[target]] which is
+            // plain `!` followed by a wikilink
+            if p.nth(1) == SyntaxKind::LBRACKET && p.nth(2) != SyntaxKind::LBRACKET {
                 image(p);
             } else {
                 p.bump();
@@ -95,7 +166,14 @@ fn inline_element(p: &mut Parser<'_, '_>) {
                 p.bump();
             }
         }
-        SyntaxKind::LT => autolink(p),
+        SyntaxKind::LT => match autolink_len(p) {
+            Some(len) => autolink(p, len),
+            None => p.bump(),
+        },
+        SyntaxKind::HASH => match tag_len(p) {
+            Some(len) => tag(p, len),
+            None => p.bump(),
+        },
         SyntaxKind::TEXT => {
             // Check for property pattern: TEXT COLON COLON
             if p.nth(1) == SyntaxKind::COLON && p.nth(2) == SyntaxKind::COLON {
@@ -111,7 +189,7 @@ fn inline_element(p: &mut Parser<'_, '_>) {
     }
 }
 
-/// Parse a wikilink: [[target]] or [[target|alias]]
+/// Parse a wikilink: [[target]], [[target#section]] or [[target|alias]]
 fn wikilink(p: &mut Parser<'_, '_>) {
     let m = p.start();
 
@@ -120,24 +198,53 @@ fn wikilink(p: &mut Parser<'_, '_>) {
     p.bump(); // [
     p.bump(); // [
 
-    // Consume content until ]] or newline
-    let mut found_close = false;
-    while !p.at_end() && !p.at(SyntaxKind::NEWLINE) {
-        if p.at(SyntaxKind::RBRACKET) && p.nth(1) == SyntaxKind::RBRACKET {
-            p.bump(); // ]
-            p.bump(); // ]
-            found_close = true;
-            break;
-        }
-        p.bump();
+    // The parts split at the first `#` and the first `|` after it
+    wikilink_part(
+        p,
+        SyntaxKind::WIKILINK_TARGET,
+        &[SyntaxKind::HASH, SyntaxKind::PIPE],
+    );
+    if p.eat(SyntaxKind::HASH) {
+        wikilink_part(p, SyntaxKind::WIKILINK_SECTION, &[SyntaxKind::PIPE]);
+    }
+    if p.eat(SyntaxKind::PIPE) {
+        wikilink_part(p, SyntaxKind::WIKILINK_ALIAS, &[]);
     }
 
+    let found_close = p.at(SyntaxKind::RBRACKET) && p.nth(1) == SyntaxKind::RBRACKET;
     if found_close {
-        m.complete(p, SyntaxKind::WIKILINK);
-    } else {
-        // Unclosed wikilink - treat as error but keep the node
-        m.complete(p, SyntaxKind::WIKILINK);
+        p.bump(); // ]
+        p.bump(); // ]
+    }
+
+    let wikilink = m.complete(p, SyntaxKind::WIKILINK);
+    if !found_close {
+        // Unclosed wikilink - keep the node but report it
+        wikilink.error(p, "unclosed wikilink, expected `]]`");
+    }
+}
+
+/// Parse one part of a wikilink as a `kind` node, up to one of `ends`,
+/// the closing `]]` or the end of the line. The spaces around the part are
+/// left outside the node, and an empty part gets no node.
+fn wikilink_part(p: &mut Parser<'_, '_>, kind: SyntaxKind, ends: &[SyntaxKind]) {
+    let at_end = |p: &Parser<'_, '_>, n: usize| {
+        let current = p.nth(n);
+        matches!(current, SyntaxKind::NEWLINE | SyntaxKind::EOF)
+            || ends.contains(&current)
+            || (current == SyntaxKind::RBRACKET && p.nth(n + 1) == SyntaxKind::RBRACKET)
+    };
+
+    p.eat(SyntaxKind::WHITESPACE);
+    if !at_end(p, 0) {
+        let m = p.start();
+        let trailing_space = |p: &Parser<'_, '_>| p.at(SyntaxKind::WHITESPACE) && at_end(p, 1);
+        while !at_end(p, 0) && !trailing_space(p) {
+            p.bump();
+        }
+        m.complete(p, kind);
     }
+    p.eat(SyntaxKind::WHITESPACE);
 }
 
 /// Parse a standard link [text](url) or plain text.
@@ -148,16 +255,17 @@ fn link_or_text(p: &mut Parser<'_, '_>) {
     debug_assert!(p.at(SyntaxKind::LBRACKET));
     p.bump();
 
-    // Consume text until ]
+    // Consume text until ], with emphasis matched within the text only
+    let mut delimiters = Delimiters::default();
     while !p.at_end() && !p.at(SyntaxKind::NEWLINE) && !p.at(SyntaxKind::RBRACKET) {
         // Handle nested inline elements in link text
         match p.current() {
             SyntaxKind::BACKTICK => code_span(p),
-            SyntaxKind::STAR => emphasis_or_strong(p, SyntaxKind::STAR),
-            SyntaxKind::UNDERSCORE => emphasis_or_strong(p, SyntaxKind::UNDERSCORE),
+            SyntaxKind::STAR | SyntaxKind::UNDERSCORE => delimiters.push_run(p),
             _ => p.bump(),
         }
     }
+    delimiters.resolve(p);
 
     // Check for ]
     if !p.eat(SyntaxKind::RBRACKET) {
@@ -169,11 +277,7 @@ fn link_or_text(p: &mut Parser<'_, '_>) {
     // Check for (url)
     if p.at(SyntaxKind::LPAREN) {
         p.bump(); // (
-
-        // Consume URL until )
-        while !p.at_end() && !p.at(SyntaxKind::NEWLINE) && !p.at(SyntaxKind::RPAREN) {
-            p.bump();
-        }
+        link_destination_and_title(p);
 
         if p.eat(SyntaxKind::RPAREN) {
             m.complete(p, SyntaxKind::LINK);
@@ -186,166 +290,246 @@ fn link_or_text(p: &mut Parser<'_, '_>) {
     }
 }
 
-/// Parse a code span `code`.
-fn code_span(p: &mut Parser<'_, '_>) {
-    let m = p.start();
+/// Parse what follows a link or image's `(`, up to its `)` or the end of
+/// the line: a `LINK_DESTINATION` (`<...>`, or everything up to the first
+/// space) and then a `LINK_TITLE` if there is more.
+fn link_destination_and_title(p: &mut Parser<'_, '_>) {
+    let at_close =
+        |p: &Parser<'_, '_>| p.at_end() || p.at(SyntaxKind::NEWLINE) || p.at(SyntaxKind::RPAREN);
+
+    p.eat(SyntaxKind::WHITESPACE);
+    let bracketed = p.eat(SyntaxKind::LT);
+    if !at_close(p) && !p.at(SyntaxKind::GT) {
+        let m = p.start();
+        while !at_close(p)
+            && !p.at(if bracketed {
+                SyntaxKind::GT
+            } else {
+                SyntaxKind::WHITESPACE
+            })
+        {
+            p.bump();
+        }
+        m.complete(p, SyntaxKind::LINK_DESTINATION);
+    }
+    if bracketed {
+        p.eat(SyntaxKind::GT);
+    }
+
+    p.eat(SyntaxKind::WHITESPACE);
+    if !at_close(p) {
+        let m = p.start();
+        // Spaces before the `)` are not part of the title
+        let trailing_space = |p: &Parser<'_, '_>| {
+            p.at(SyntaxKind::WHITESPACE)
+                && matches!(
+                    p.nth(1),
+                    SyntaxKind::RPAREN | SyntaxKind::NEWLINE | SyntaxKind::EOF
+                )
+        };
+        while !at_close(p) && !trailing_space(p) {
+            p.bump();
+        }
+        m.complete(p, SyntaxKind::LINK_TITLE);
+        p.eat(SyntaxKind::WHITESPACE);
+    }
+}
 
+/// Parse a code span `code`, with the text between the backtick runs in a
+/// `CODE_SPAN_CONTENT` node (see [`crate::code_span`]).
+fn code_span(p: &mut Parser<'_, '_>) {
     // Count opening backticks
     let mut open_count = 0;
-    while p.at(SyntaxKind::BACKTICK) {
-        p.bump();
+    while p.nth(open_count) == SyntaxKind::BACKTICK {
         open_count += 1;
     }
 
-    // Track whether we find content and closing backticks
-    let mut has_content = false;
-    let mut found_close = false;
-
-    // Parse content until matching backticks
-    while !p.at_end() && !p.at(SyntaxKind::NEWLINE) {
-        if p.at(SyntaxKind::BACKTICK) {
+    // Look ahead for the matching backticks on this line
+    let mut n = open_count;
+    let mut close = None;
+    while !matches!(p.nth(n), SyntaxKind::NEWLINE | SyntaxKind::EOF) {
+        if p.nth(n) == SyntaxKind::BACKTICK {
             // Count consecutive backticks
             let mut close_count = 0;
-
-            while p.nth(close_count) == SyntaxKind::BACKTICK {
+            while p.nth(n + close_count) == SyntaxKind::BACKTICK {
                 close_count += 1;
             }
-
             if close_count == open_count {
-                // Matching close - consume the backticks
-                for _ in 0..close_count {
-                    p.bump();
-                }
-                found_close = true;
+                close = Some(n);
                 break;
-            } else {
-                // Not matching - consume one backtick and continue
-                p.bump();
-                has_content = true;
             }
+            // Not matching - a longer or shorter run is content
+            n += close_count;
         } else {
-            p.bump();
-            has_content = true;
+            n += 1;
         }
     }
 
-    // Only produce CODE_SPAN if properly closed with content
-    if found_close && has_content {
-        m.complete(p, SyntaxKind::CODE_SPAN);
-    } else {
-        // Unclosed or empty - abandon marker, tokens become plain text
-        m.abandon(p);
+    let Some(close) = close else {
+        // Unclosed - the opening run is plain text
+        for _ in 0..open_count {
+            p.bump();
+        }
+        return;
+    };
+
+    let m = p.start();
+    for _ in 0..open_count {
+        p.bump();
+    }
+    let content = p.start();
+    for _ in open_count..close {
+        p.bump();
+    }
+    content.complete(p, SyntaxKind::CODE_SPAN_CONTENT);
+    for _ in 0..open_count {
+        p.bump();
     }
+    m.complete(p, SyntaxKind::CODE_SPAN);
 }
 
-/// Look ahead to see if there's a matching close for a nested construct.
-/// Used to decide whether `ahead_count` delimiters start a nested construct
-/// or should be used to close the current one.
-fn has_matching_close(p: &Parser<'_, '_>, delimiter: SyntaxKind, ahead_count: usize) -> bool {
-    // We want to nest with `ahead_count` delimiters. But we can only consume
-    // up to 2 (max for strong). So the nested construct would use min(ahead_count, 2).
-    let nested_count = ahead_count.min(2);
-
-    // Scan ahead (past the current delimiter run) looking for a matching close
-    let mut offset = ahead_count; // Start after the delimiter run
-    loop {
-        let token = p.nth(offset);
-        if token == SyntaxKind::NEWLINE || token == SyntaxKind::EOF {
-            return false; // No matching close found
-        }
-        if token == delimiter {
-            // Count consecutive delimiters at this position
-            let mut count = 0;
-            while p.nth(offset + count) == delimiter {
-                count += 1;
-            }
-            if count >= nested_count {
-                return true; // Found matching close
-            }
-            offset += count;
-        } else {
-            offset += 1;
-        }
-    }
+/// A run of `*` or `_` delimiters, e.g. `**`.
+struct DelimiterRun {
+    kind: SyntaxKind,
+    /// Slots before and after each delimiter token
+    slots: Vec<(usize, usize)>,
+    /// Delimiters not yet matched: openers use them from the end, closers
+    /// from the start
+    unmatched: Range<usize>,
+    can_open: bool,
+    can_close: bool,
 }
 
-/// Parse emphasis *text* or strong **text** (or underscore variants).
-fn emphasis_or_strong(p: &mut Parser<'_, '_>, delimiter: SyntaxKind) {
-    let m = p.start();
+/// The delimiter runs of a line or link text, matched into emphasis by
+/// [`Delimiters::resolve`].
+#[derive(Default)]
+struct Delimiters {
+    runs: Vec<DelimiterRun>,
+}
 
-    // Count opening delimiters
-    let mut open_count = 0;
-    while p.at(delimiter) && open_count < 2 {
-        p.bump();
-        open_count += 1;
-    }
+impl Delimiters {
+    /// Consume a run of `*` or `_`, reserving slots for the emphasis it may
+    /// open or close.
+    fn push_run(&mut self, p: &mut Parser<'_, '_>) {
+        let kind = p.current();
+        let before = p.prev_text().chars().next_back();
+        let mut slots = Vec::new();
+        while p.at(kind) {
+            let start = p.slot();
+            p.bump();
+            slots.push((start, p.slot()));
+        }
+        let after = p.current_text().chars().next();
+
+        // Flanking rules: a run can open if it isn't followed by whitespace,
+        // or by punctuation unless preceded by whitespace or punctuation
+        // (and the mirror image to close). Line edges count as whitespace.
+        let left_flanking = !is_space(after)
+            && (!is_punctuation(after) || is_space(before) || is_punctuation(before));
+        let right_flanking = !is_space(before)
+            && (!is_punctuation(before) || is_space(after) || is_punctuation(after));
+        // `_` can't open or close inside a word, e.g. snake_case_name
+        let (can_open, can_close) = if kind == SyntaxKind::UNDERSCORE {
+            (
+                left_flanking && (!right_flanking || is_punctuation(before)),
+                right_flanking && (!left_flanking || is_punctuation(after)),
+            )
+        } else {
+            (left_flanking, right_flanking)
+        };
 
-    if open_count == 0 {
-        m.abandon(p);
-        return;
+        self.runs.push(DelimiterRun {
+            kind,
+            unmatched: 0..slots.len(),
+            slots,
+            can_open,
+            can_close,
+        });
     }
 
-    // Track whether we find content and closing delimiters
-    let mut has_content = false;
-    let mut found_close = false;
-
-    // Parse content until matching delimiters
-    while !p.at_end() && !p.at(SyntaxKind::NEWLINE) {
-        if p.at(delimiter) {
-            // Count all consecutive delimiters ahead
-            let mut ahead_count = 0;
-            while p.nth(ahead_count) == delimiter {
-                ahead_count += 1;
+    /// Match closers with the nearest possible openers, from the left, and
+    /// start and finish the emphasis nodes in their slots.
+    fn resolve(mut self, p: &mut Parser<'_, '_>) {
+        // Lowest opener index worth searching from, per closer kind, length
+        // mod 3 and whether it can also open; earlier ones are known not to
+        // match.
+        let mut bottoms: Vec<(SyntaxKind, usize, bool, usize)> = Vec::new();
+        let mut closer = 0;
+        while closer < self.runs.len() {
+            let run = &self.runs[closer];
+            if !run.can_close || run.unmatched.is_empty() {
+                closer += 1;
+                continue;
             }
-
-            if ahead_count < open_count {
-                // Fewer delimiters - definitely nested (emphasis inside strong)
-                emphasis_or_strong(p, delimiter);
-                has_content = true;
-            } else if ahead_count == open_count {
-                // Exact match - close
-                for _ in 0..open_count {
-                    p.bump();
-                }
-                found_close = true;
-                break;
+            let key = (run.kind, run.slots.len() % 3, run.can_open);
+            let bottom = bottoms
+                .iter()
+                .find(|(kind, len, open, _)| (*kind, *len, *open) == key)
+                .map_or(0, |entry| entry.3);
+
+            let opener = (bottom..closer).rev().find(|&i| self.can_match(i, closer));
+            let Some(opener) = opener else {
+                bottoms.retain(|(kind, len, open, _)| (*kind, *len, *open) != key);
+                bottoms.push((key.0, key.1, key.2, closer));
+                closer += 1;
+                continue;
+            };
+
+            // Strong if both sides have two to spare, else emphasis
+            let count = if self.runs[opener].unmatched.len() >= 2
+                && self.runs[closer].unmatched.len() >= 2
+            {
+                2
+            } else {
+                1
+            };
+            let open = &mut self.runs[opener];
+            open.unmatched.end -= count;
+            let start = open.slots[open.unmatched.end].0;
+            let close = &mut self.runs[closer];
+            close.unmatched.start += count;
+            let finish = close.slots[close.unmatched.start - 1].1;
+            let kind = if count == 2 {
+                SyntaxKind::STRONG
             } else {
-                // More delimiters - look ahead for matching close
-                // If we find a matching close for nested, nest. Otherwise close.
-                if has_matching_close(p, delimiter, ahead_count) {
-                    emphasis_or_strong(p, delimiter);
-                    has_content = true;
-                } else {
-                    // No matching close for nested - close current with open_count
-                    for _ in 0..open_count {
-                        p.bump();
-                    }
-                    found_close = true;
-                    break;
-                }
+                SyntaxKind::EMPHASIS
+            };
+            p.start_at(start, kind);
+            p.finish_at(finish);
+
+            // Delimiters between the pair can no longer match anything
+            for run in &mut self.runs[opener + 1..closer] {
+                run.unmatched.end = run.unmatched.start;
             }
-        } else {
-            // Parse other inline elements (wikilinks, code, links, etc.)
-            inline_element(p);
-            has_content = true;
         }
     }
 
-    // Only produce EMPHASIS/STRONG if properly closed with content
-    // Otherwise the downstream content range calculation would be invalid
-    if found_close && has_content {
-        let kind = if open_count >= 2 {
-            SyntaxKind::STRONG
-        } else {
-            SyntaxKind::EMPHASIS
-        };
-        m.complete(p, kind);
-    } else {
-        // Unclosed or empty - abandon marker, tokens become plain text
-        m.abandon(p);
+    /// Whether run `opener` can open emphasis closed by run `closer`.
+    fn can_match(&self, opener: usize, closer: usize) -> bool {
+        let (open, close) = (&self.runs[opener], &self.runs[closer]);
+        if open.kind != close.kind || !open.can_open || open.unmatched.is_empty() {
+            return false;
+        }
+        // The "rule of 3": a run that could both open and close only pairs
+        // with one whose combined length isn't a multiple of 3, so that
+        // `*foo**bar*` is one emphasis rather than two
+        let (open_len, close_len) = (open.slots.len(), close.slots.len());
+        !((open.can_close || close.can_open)
+            && (open_len + close_len) % 3 == 0
+            && !(open_len % 3 == 0 && close_len % 3 == 0))
     }
 }
 
+/// Whitespace, or the edge of the line, for the flanking rules.
+fn is_space(c: Option<char>) -> bool {
+    c.is_none_or(char::is_whitespace)
+}
+
+/// Punctuation or a symbol, for the flanking rules.
+fn is_punctuation(c: Option<char>) -> bool {
+    c.is_some_and(|c| c.is_ascii_punctuation() || !(c.is_alphanumeric() || c.is_whitespace()))
+}
+
 /// Parse image ![alt](url).
 fn image(p: &mut Parser<'_, '_>) {
     let m = p.start();
@@ -372,11 +556,7 @@ fn image(p: &mut Parser<'_, '_>) {
     // Check for (url)
     if p.at(SyntaxKind::LPAREN) {
         p.bump(); // (
-
-        // Consume URL until )
-        while !p.at_end() && !p.at(SyntaxKind::NEWLINE) && !p.at(SyntaxKind::RPAREN) {
-            p.bump();
-        }
+        link_destination_and_title(p);
 
         if p.eat(SyntaxKind::RPAREN) {
             m.complete(p, SyntaxKind::IMAGE);
@@ -414,31 +594,84 @@ fn property(p: &mut Parser<'_, '_>) {
     m.complete(p, SyntaxKind::PROPERTY);
 }
 
-/// Parse autolink <url>.
-fn autolink(p: &mut Parser<'_, '_>) {
-    let m = p.start();
+/// Number of tokens between the `<` and `>` of an autolink, or `None` if
+/// the `<` doesn't start one.
+///
+/// The content is a single URL, or an email address: text with one `@`
+/// and no whitespace. Anything else, like inline HTML, is plain text.
+pub(super) fn autolink_len(p: &Parser<'_, '_>) -> Option<usize> {
+    if p.nth(1) == SyntaxKind::URL && p.nth(2) == SyntaxKind::GT {
+        return Some(1);
+    }
+    let mut email = String::new();
+    let mut len = 0;
+    while matches!(
+        p.nth(len + 1),
+        SyntaxKind::TEXT
+            | SyntaxKind::DOT
+            | SyntaxKind::DASH
+            | SyntaxKind::PLUS
+            | SyntaxKind::UNDERSCORE
+    ) {
+        email.push_str(p.nth_text(len + 1));
+        len += 1;
+    }
+    let (user, domain) = email.split_once('@')?;
+    let is_email = !user.is_empty() && !domain.is_empty() && !domain.contains('@');
+    (is_email && p.nth(len + 1) == SyntaxKind::GT).then_some(len)
+}
 
-    // Consume opening <
+/// Parse an autolink: `<`, the `len` tokens found by [`autolink_len`], `>`.
+fn autolink(p: &mut Parser<'_, '_>, len: usize) {
+    let m = p.start();
     debug_assert!(p.at(SyntaxKind::LT));
     p.bump();
+    for _ in 0..len {
+        p.bump();
+    }
+    debug_assert!(p.at(SyntaxKind::GT));
+    p.bump();
+    m.complete(p, SyntaxKind::AUTOLINK);
+}
 
-    // Consume content until >
-    let mut found_close = false;
-    while !p.at_end() && !p.at(SyntaxKind::NEWLINE) {
-        if p.at(SyntaxKind::GT) {
-            p.bump();
-            found_close = true;
+/// Number of tokens after the `#` making up a tag, or `None` if the `#`
+/// doesn't start one.
+///
+/// A tag is a run of TEXT tokens joined by `-` or `_`, ending after the
+/// first TEXT token with a character that can't be in a tag name.
+fn tag_len(p: &Parser<'_, '_>) -> Option<usize> {
+    if !matches!(
+        p.prev(),
+        SyntaxKind::EOF | SyntaxKind::WHITESPACE | SyntaxKind::NEWLINE
+    ) {
+        return None;
+    }
+    let mut text = String::new();
+    let mut len = 0;
+    while p.nth(len + 1) == SyntaxKind::TEXT {
+        let token = p.nth_text(len + 1);
+        text.push_str(token);
+        len += 1;
+        let joined = matches!(p.nth(len + 1), SyntaxKind::DASH | SyntaxKind::UNDERSCORE)
+            && p.nth(len + 2) == SyntaxKind::TEXT;
+        if !token.chars().all(is_tag_char) || !joined {
             break;
         }
-        p.bump();
+        text.push_str(p.nth_text(len + 1));
+        len += 1;
     }
+    tag_name(&text).map(|_| len)
+}
 
-    if found_close {
-        m.complete(p, SyntaxKind::AUTOLINK);
-    } else {
-        // Unclosed < - treat as inline text
-        m.complete(p, SyntaxKind::INLINE);
+/// Parse a tag: `#` and the `len` tokens found by [`tag_len`].
+fn tag(p: &mut Parser<'_, '_>, len: usize) {
+    let m = p.start();
+    debug_assert!(p.at(SyntaxKind::HASH));
+    p.bump();
+    for _ in 0..len {
+        p.bump();
     }
+    m.complete(p, SyntaxKind::TAG);
 }
 
 /// Parse goal reference ((uuid)).
@@ -487,7 +720,7 @@ fn strikethrough(p: &mut Parser<'_, '_>) {
     let mut found_close = false;
 
     // Parse content until matching ~~
-    while !p.at_end() && !p.at(SyntaxKind::NEWLINE) {
+    while !p.at_end() && !p.at(SyntaxKind::NEWLINE) && !at_hard_break(p) {
         if p.at(SyntaxKind::TILDE) && p.nth(1) == SyntaxKind::TILDE {
             // Found closing ~~
             p.bump(); // ~
@@ -508,5 +741,102 @@ fn strikethrough(p: &mut Parser<'_, '_>) {
     }
 }
 
-// All parsing behavior is verified by snapshot tests in tests/snapshots/.
+// Parsing behavior is mostly verified by snapshot tests in tests/snapshots/.
 // Edge cases are in tests/snapshots/malformed/ and tests/snapshots/combinations/.
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+    use crate::syntax_kind::{SyntaxKind, SyntaxNode};
+    use rowan::NodeOrToken;
+
+    /// Render emphasis as HTML tags and everything else as source text,
+    /// to compare with the CommonMark spec's examples.
+    fn render(node: &SyntaxNode) -> String {
+        let (tag, delimiters) = match node.kind() {
+            SyntaxKind::EMPHASIS => ("em", 1),
+            SyntaxKind::STRONG => ("strong", 2),
+            _ => ("", 0),
+        };
+        let children: Vec<_> = node.children_with_tokens().collect();
+        let inner: String = children[delimiters..children.len() - delimiters]
+            .iter()
+            .map(|child| match child {
+                NodeOrToken::Node(node) => render(node),
+                NodeOrToken::Token(token) => token.text().to_string(),
+            })
+            .collect();
+        if tag.is_empty() {
+            inner
+        } else {
+            format!("<{tag}>{inner}</{tag}>")
+        }
+    }
+
+    fn html(input: &str) -> String {
+        render(&parse(input)).trim_end().to_string()
+    }
+
+    #[test]
+    fn test_spec_emphasis_flanking() {
+        let cases = [
+            ("*foo bar*", "<em>foo bar</em>"),
+            ("a * foo bar*", "a * foo bar*"),
+            ("a*\"foo\"*", "a*\"foo\"*"),
+            ("foo*bar*", "foo<em>bar</em>"),
+            ("5*6*78", "5<em>6</em>78"),
+            ("_foo bar_", "<em>foo bar</em>"),
+            ("_ foo bar_", "_ foo bar_"),
+            ("foo_bar_", "foo_bar_"),
+            ("5_6_78", "5_6_78"),
+            ("foo-_(bar)_", "foo-<em>(bar)</em>"),
+            ("_foo*", "_foo*"),
+            ("*foo bar *", "*foo bar *"),
+            ("*(*foo)", "*(*foo)"),
+            ("*(*foo*)*", "<em>(<em>foo</em>)</em>"),
+            ("_foo_bar_baz_", "<em>foo_bar_baz</em>"),
+            ("snake_case_name", "snake_case_name"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(html(input), expected, "for {input:?}");
+        }
+    }
+
+    #[test]
+    fn test_spec_strong_and_nesting() {
+        let cases = [
+            ("**foo bar**", "<strong>foo bar</strong>"),
+            ("** foo bar**", "** foo bar**"),
+            ("foo**bar**", "foo<strong>bar</strong>"),
+            ("__foo bar__", "<strong>foo bar</strong>"),
+            ("foo__bar__", "foo__bar__"),
+            ("**a *b* c**", "<strong>a <em>b</em> c</strong>"),
+            ("*foo **bar** baz*", "<em>foo <strong>bar</strong> baz</em>"),
+            ("***both***", "<em><strong>both</strong></em>"),
+            ("*foo**bar**baz*", "<em>foo<strong>bar</strong>baz</em>"),
+            ("*foo**bar*", "<em>foo**bar</em>"),
+            ("***foo** bar*", "<em><strong>foo</strong> bar</em>"),
+            ("*foo **bar***", "<em>foo <strong>bar</strong></em>"),
+            ("foo***bar***baz", "foo<em><strong>bar</strong></em>baz"),
+            (
+                "foo******bar*********baz",
+                "foo<strong><strong><strong>bar</strong></strong></strong>***baz",
+            ),
+            ("**foo*", "*<em>foo</em>"),
+            ("*foo**", "<em>foo</em>*"),
+            (
+                "**foo bar *baz bim* bam**",
+                "<strong>foo bar <em>baz bim</em> bam</strong>",
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(html(input), expected, "for {input:?}");
+        }
+    }
+
+    #[test]
+    fn test_emphasis_in_link_text_matches_within_the_text() {
+        assert_eq!(html("*[a*](b)"), "*[a*](b)");
+        assert_eq!(html("[*a*](b)"), "[<em>a</em>](b)");
+    }
+}
@@ -17,17 +17,15 @@
 //! A typical grammar function looks like:
 //!
 //! ```ignore
-//! fn heading(p: &mut Parser) {
+//! fn blockquote(p: &mut Parser) {
 //!     let m = p.start();           // 1. Start a node
 //!
-//!     while p.at(SyntaxKind::HASH) {  // 2. Consume tokens
-//!         p.bump();
-//!     }
+//!     p.bump();                    // 2. Consume tokens
 //!     p.eat(SyntaxKind::WHITESPACE);
-//!     inline::inline_until_newline(p); // 3. Call other grammar rules
+//!     inline::inline_until_newline_with_breaks(p); // 3. Call other grammar rules
 //!     p.eat(SyntaxKind::NEWLINE);
 //!
-//!     m.complete(p, SyntaxKind::HEADING); // 4. Complete the node
+//!     m.complete(p, SyntaxKind::BLOCK_QUOTE); // 4. Complete the node
 //! }
 //! ```
 //!
@@ -40,7 +38,11 @@
 //! - Or just consume them into the current node
 //! - Avoid panicking or returning errors
 //!
-//! The goal is a valid tree that preserves all input bytes.
+//! The goal is a valid tree that preserves all input bytes. Where a node
+//! had to be guessed at, report it with [`CompletedMarker::error`] so
+//! [`parse_with_errors`](crate::parse_with_errors) can surface it.
+//!
+//! [`CompletedMarker::error`]: crate::parser::CompletedMarker::error
 
 mod block;
 mod inline;
@@ -48,6 +50,19 @@ mod inline;
 use crate::parser::Parser;
 use crate::syntax_kind::SyntaxKind;
 
+/// Whether a `TEXT` token with this text is just a word to the grammar,
+/// so changing it to another such word can't change the tree around it.
+/// Most rules only look at token kinds; this rules out the texts some
+/// rules read: list numbers, task keywords, `[x]` checkboxes, `\`, fence
+/// attributes and the punctuation the emphasis flanking rules check. Keep
+/// it in step with rules that look at `TEXT` content.
+pub(crate) fn is_plain_word(text: &str) -> bool {
+    !text.is_empty()
+        && text.chars().all(char::is_alphabetic)
+        && !matches!(text, "x" | "X")
+        && !block::TASK_KEYWORDS.contains(&text)
+}
+
 /// Parse the root document.
 ///
 /// This is the entry point for parsing. It creates a ROOT node containing
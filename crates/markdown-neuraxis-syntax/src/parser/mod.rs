@@ -77,7 +77,10 @@ pub mod sink;
 
 mod grammar;
 
+pub(crate) use grammar::is_plain_word;
+
 use crate::lexer::{Token, lex};
+use crate::syntax_error::SyntaxError;
 use crate::syntax_kind::{SyntaxKind, SyntaxNode};
 use event::Event;
 use sink::Sink;
@@ -107,10 +110,16 @@ impl<'t, 'input> Parser<'t, 'input> {
     }
 
     /// Parse the tokens and return a syntax tree.
-    pub fn parse(mut self) -> SyntaxNode {
+    pub fn parse(self) -> SyntaxNode {
+        self.parse_with_errors().0
+    }
+
+    /// Parse the tokens and return a syntax tree, with the problems the
+    /// grammar recovered from.
+    pub fn parse_with_errors(mut self) -> (SyntaxNode, Vec<SyntaxError>) {
         grammar::root(&mut self);
         let sink = Sink::new(self.tokens, self.events);
-        sink.finish()
+        sink.finish_with_errors()
     }
 
     /// Start a new node and return a marker.
@@ -136,6 +145,22 @@ impl<'t, 'input> Parser<'t, 'input> {
             .unwrap_or(SyntaxKind::EOF)
     }
 
+    /// Kind of the token before the current one, or EOF at the start.
+    pub fn prev(&self) -> SyntaxKind {
+        self.pos
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .map_or(SyntaxKind::EOF, |t| t.kind)
+    }
+
+    /// Text of the token before the current one, or `""` at the start.
+    pub fn prev_text(&self) -> &'input str {
+        self.pos
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .map_or("", |t| t.text)
+    }
+
     /// Check if at end of input.
     pub fn at_end(&self) -> bool {
         self.pos >= self.tokens.len()
@@ -176,6 +201,37 @@ impl<'t, 'input> Parser<'t, 'input> {
         }
     }
 
+    /// Consume the first `len` bytes of the current token as a token of
+    /// `kind`. The parser stays on the token, and the next
+    /// [`bump`](Self::bump) takes the rest of it.
+    pub fn bump_prefix(&mut self, len: usize, kind: SyntaxKind) {
+        debug_assert!(len < self.current_text().len());
+        self.events.push(Event::TokenPrefix {
+            kind,
+            len: len as u32,
+        });
+    }
+
+    /// Reserve a slot in the events for a node start or finish decided
+    /// later, e.g. once emphasis delimiters are matched. Unfilled slots
+    /// are ignored.
+    pub fn slot(&mut self) -> usize {
+        self.events.push(Event::Placeholder);
+        self.events.len() - 1
+    }
+
+    /// Start a node of `kind` at a [`slot`](Self::slot).
+    pub fn start_at(&mut self, slot: usize, kind: SyntaxKind) {
+        debug_assert!(matches!(self.events[slot], Event::Placeholder));
+        self.events[slot] = Event::start(kind);
+    }
+
+    /// Finish the innermost open node at a [`slot`](Self::slot).
+    pub fn finish_at(&mut self, slot: usize) {
+        debug_assert!(matches!(self.events[slot], Event::Placeholder));
+        self.events[slot] = Event::Finish;
+    }
+
     /// Get the text of the current token.
     pub fn current_text(&self) -> &'input str {
         self.tokens.get(self.pos).map(|t| t.text).unwrap_or("")
@@ -328,6 +384,15 @@ pub struct CompletedMarker {
 }
 
 impl CompletedMarker {
+    /// Report this node as malformed. Call it right after completing the
+    /// node: the error covers the last node finished.
+    pub fn error(self, p: &mut Parser<'_, '_>, message: impl Into<String>) {
+        debug_assert!(matches!(p.events.last(), Some(Event::Finish)));
+        p.events.push(Event::Error {
+            message: message.into(),
+        });
+    }
+
     /// Create a new parent node that will contain this node.
     ///
     /// Returns a new `Marker` that, when completed, will become the parent
@@ -355,6 +420,24 @@ pub fn parse(source: &str) -> SyntaxNode {
     parser.parse()
 }
 
+/// Parse markdown source into a syntax tree, with the malformed markdown
+/// the parser recovered from, e.g. an unclosed wikilink. The tree is the
+/// same as [`parse`]'s.
+///
+/// ```
+/// use markdown_neuraxis_syntax::parse_with_errors;
+///
+/// let (tree, errors) = parse_with_errors("See [[Notes\n");
+/// assert_eq!(tree.text().to_string(), "See [[Notes\n");
+/// assert_eq!(errors.len(), 1);
+/// assert_eq!(u32::from(errors[0].range.start()), 4);
+/// ```
+pub fn parse_with_errors(source: &str) -> (SyntaxNode, Vec<SyntaxError>) {
+    let tokens = lex(source);
+    let parser = Parser::new(&tokens);
+    parser.parse_with_errors()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -373,6 +456,31 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn parse_with_errors_reports_unclosed_constructs() {
+        let source = "# Ok\n\nSee [[Notes and more\n\n```rust\nfn main() {}\n";
+        let (tree, errors) = parse_with_errors(source);
+        assert_eq!(tree.text().to_string(), source);
+
+        let reported: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|error| (&source[error.range], error.message.as_str()))
+            .collect();
+        assert_eq!(
+            reported,
+            vec![
+                ("[[Notes and more", "unclosed wikilink, expected `]]`"),
+                ("```rust\nfn main() {}\n", "unclosed code fence"),
+            ]
+        );
+
+        assert!(
+            parse_with_errors("[[Notes]] and\n```\ncode\n```\n")
+                .1
+                .is_empty()
+        );
+    }
+
     #[test]
     fn marker_can_be_abandoned() {
         let tokens = lex("test");
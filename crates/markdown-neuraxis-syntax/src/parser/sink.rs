@@ -10,7 +10,8 @@
 //! 1. **Start** → Call `builder.start_node(kind)`
 //! 2. **Token** → Call `builder.token(kind, text)` (text comes from the token stream)
 //! 3. **Finish** → Call `builder.finish_node()`
-//! 4. **Placeholder** → Skip (these are abandoned markers)
+//! 4. **Error** → Record a [`SyntaxError`] for the node just finished
+//! 5. **Placeholder** → Skip (these are abandoned markers)
 //!
 //! ## Forward Parent Resolution
 //!
@@ -62,11 +63,15 @@
 //! The `n_raw_tokens` field in Token events allows grouping multiple lexer
 //! tokens into one tree token. The Sink concatenates the text from
 //! `n_raw_tokens` consecutive tokens.
+//!
+//! Going the other way, a `TokenPrefix` event takes only the start of a
+//! lexer token, and the next `Token` event gets the rest.
 
-use rowan::GreenNodeBuilder;
+use rowan::{GreenNodeBuilder, TextRange, TextSize};
 
 use crate::lexer::Token;
 use crate::parser::event::Event;
+use crate::syntax_error::SyntaxError;
 use crate::syntax_kind::{SyntaxKind, SyntaxNode};
 
 /// Converts parser events and tokens into a Rowan syntax tree.
@@ -83,7 +88,16 @@ pub struct Sink<'t, 'input> {
     builder: GreenNodeBuilder<'static>,
     tokens: &'t [Token<'input>],
     cursor: usize,
+    /// Bytes of the token at `cursor` already taken by a `TokenPrefix`
+    split: usize,
     events: Vec<Event>,
+    /// Text offset of the next token
+    offset: TextSize,
+    /// Start offsets of the open nodes, innermost last
+    open: Vec<TextSize>,
+    /// Range of the last finished node, for `Error` events
+    finished: TextRange,
+    errors: Vec<SyntaxError>,
 }
 
 impl<'t, 'input> Sink<'t, 'input> {
@@ -93,12 +107,23 @@ impl<'t, 'input> Sink<'t, 'input> {
             builder: GreenNodeBuilder::new(),
             tokens,
             cursor: 0,
+            split: 0,
             events,
+            offset: TextSize::default(),
+            open: Vec::new(),
+            finished: TextRange::default(),
+            errors: Vec::new(),
         }
     }
 
     /// Consume the sink and build the syntax tree.
-    pub fn finish(mut self) -> SyntaxNode {
+    pub fn finish(self) -> SyntaxNode {
+        self.finish_with_errors().0
+    }
+
+    /// Consume the sink and build the syntax tree, with the errors the
+    /// parser reported.
+    pub fn finish_with_errors(mut self) -> (SyntaxNode, Vec<SyntaxError>) {
         // Process forward_parent links to create proper tree structure
         let mut forward_parents = Vec::new();
 
@@ -128,19 +153,35 @@ impl<'t, 'input> Sink<'t, 'input> {
                     // Start nodes in reverse order (outermost first)
                     for kind in forward_parents.drain(..).rev() {
                         self.builder.start_node(kind.into());
+                        self.open.push(self.offset);
                     }
                 }
                 Event::Token { kind, n_raw_tokens } => {
                     self.token(kind, n_raw_tokens as usize);
                 }
+                Event::TokenPrefix { kind, len } => {
+                    let start = self.split;
+                    self.split += len as usize;
+                    let text = &self.tokens[self.cursor].text[start..self.split];
+                    self.offset += TextSize::of(text);
+                    self.builder.token(kind.into(), text);
+                }
                 Event::Finish => {
                     self.builder.finish_node();
+                    let start = self.open.pop().unwrap();
+                    self.finished = TextRange::new(start, self.offset);
+                }
+                Event::Error { message } => {
+                    self.errors.push(SyntaxError {
+                        range: self.finished,
+                        message,
+                    });
                 }
                 Event::Placeholder => {}
             }
         }
 
-        SyntaxNode::new_root(self.builder.finish())
+        (SyntaxNode::new_root(self.builder.finish()), self.errors)
     }
 
     fn token(&mut self, kind: SyntaxKind, n_raw_tokens: usize) {
@@ -151,7 +192,9 @@ impl<'t, 'input> Sink<'t, 'input> {
             .iter()
             .map(|t| t.text)
             .collect();
-        self.builder.token(kind.into(), &text);
+        let text = &text[std::mem::take(&mut self.split)..];
+        self.offset += TextSize::of(text);
+        self.builder.token(kind.into(), text);
     }
 }
 
@@ -204,4 +247,37 @@ mod tests {
 
         assert_eq!(tree.text().to_string(), input);
     }
+
+    #[test]
+    fn sink_reports_errors_on_the_finished_node() {
+        let tokens = lex("a [[b");
+
+        let events = vec![
+            Event::start(SyntaxKind::ROOT),
+            Event::token(SyntaxKind::TEXT),
+            Event::token(SyntaxKind::WHITESPACE),
+            Event::start(SyntaxKind::WIKILINK),
+            Event::Token {
+                kind: SyntaxKind::LBRACKET,
+                n_raw_tokens: 2,
+            },
+            Event::token(SyntaxKind::TEXT),
+            Event::Finish,
+            Event::Error {
+                message: "unclosed".to_string(),
+            },
+            Event::Finish,
+        ];
+
+        let (tree, errors) = Sink::new(&tokens, events).finish_with_errors();
+
+        assert_eq!(tree.text().to_string(), "a [[b");
+        assert_eq!(
+            errors,
+            vec![SyntaxError {
+                range: TextRange::new(2.into(), 5.into()),
+                message: "unclosed".to_string(),
+            }]
+        );
+    }
 }
@@ -0,0 +1,376 @@
+//! # Incremental Reparsing
+//!
+//! [`parse`] always lexes and parses the whole document, so every keystroke
+//! in a large note pays for the whole note. [`reparse`] instead reparses
+//! only the top-level blocks an edit touches and splices the result into
+//! the old tree, reusing the green nodes of every other block.
+//!
+//! ## How It Works
+//!
+//! ```text
+//! ROOT children:  [A] \n [B] \n [C] \n [D] \n [E]
+//! edit inside C:         └─── window ───┘
+//! ```
+//!
+//! Typing inside a word takes a faster path first: if the edit stays on one
+//! line and re-lexing that line changes only one word's `TEXT` token, that
+//! token is swapped in place (see `reparse_word`). Nothing else is lexed
+//! or parsed.
+//!
+//! Otherwise:
+//!
+//! 1. Find the top-level blocks the edit touches (`C`)
+//! 2. Widen the window by one block on each side (`B` and `D`)
+//! 3. Parse the edited window text on its own
+//! 4. Check the padding blocks reparsed to exactly the same green nodes
+//! 5. Build a new ROOT from the old children before the window, the
+//!    reparsed window and the old children after it
+//!
+//! Markdown blocks mostly don't affect each other, but some edits do reach
+//! past their block: opening a code fence swallows everything after it,
+//! `===` under a paragraph turns it into a heading, a new list item can
+//! merge two lists. All of those change a neighbouring block, so the
+//! padding check in step 4 catches them and [`reparse`] falls back to a
+//! full [`parse`]. The result is always the same tree a full parse gives.
+
+use std::ops::Range;
+
+use rowan::{GreenNode, GreenToken, NodeOrToken, TextRange, TextSize, TokenAtOffset};
+
+use crate::lexer::lex;
+use crate::parser::{is_plain_word, parse};
+use crate::syntax_kind::{SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken};
+
+/// A single text replacement, in byte offsets of the old text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// Range of the old text to replace
+    pub delete: Range<usize>,
+    /// Text inserted in its place
+    pub insert: String,
+}
+
+impl TextEdit {
+    pub fn insert(at: usize, text: impl Into<String>) -> Self {
+        Self {
+            delete: at..at,
+            insert: text.into(),
+        }
+    }
+
+    pub fn replace(delete: Range<usize>, text: impl Into<String>) -> Self {
+        Self {
+            delete,
+            insert: text.into(),
+        }
+    }
+
+    /// Apply the edit to `text`.
+    pub fn apply(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len() + self.insert.len());
+        out.push_str(&text[..self.delete.start]);
+        out.push_str(&self.insert);
+        out.push_str(&text[self.delete.end..]);
+        out
+    }
+}
+
+/// The tree for `old_tree`'s text with `edit` applied, reusing unchanged
+/// top-level blocks where that gives the same result as a full parse.
+///
+/// # Panics
+///
+/// If the edit range is out of bounds or not on a char boundary.
+pub fn reparse(old_tree: &SyntaxNode, edit: &TextEdit) -> SyntaxNode {
+    if let Some(tree) = reparse_word(old_tree, edit) {
+        return tree;
+    }
+    let new_text = edit.apply(&old_tree.text().to_string());
+    reparse_blocks(old_tree, edit, &new_text).unwrap_or_else(|| parse(&new_text))
+}
+
+/// Replace the one word a single-line edit changes, or `None` if re-lexing
+/// the line changes anything else.
+///
+/// The grammar reads token kinds, not text, except for a few texts (see
+/// [`is_plain_word`]); so when the line lexes to the same tokens apart from
+/// one `TEXT` token, and it's a plain word before and after, a full parse
+/// would build the same tree around the new token.
+fn reparse_word(root: &SyntaxNode, edit: &TextEdit) -> Option<SyntaxNode> {
+    if edit.insert.contains(['\n', '\r']) {
+        return None;
+    }
+    let line = line_tokens(root, TextSize::try_from(edit.delete.start).ok()?)?;
+    let line_start = usize::from(line.first()?.text_range().start());
+    let content_end = line
+        .iter()
+        .rfind(|token| token.kind() != SyntaxKind::NEWLINE)
+        .map_or(line_start, |token| token.text_range().end().into());
+    if edit.delete.end > content_end {
+        return None;
+    }
+    if line.iter().any(|token| {
+        token.parent_ancestors().any(|node| {
+            matches!(
+                node.kind(),
+                SyntaxKind::FENCED_CODE
+                    | SyntaxKind::INDENTED_CODE
+                    | SyntaxKind::HTML_BLOCK
+                    | SyntaxKind::FRONTMATTER
+            )
+        })
+    }) {
+        return None;
+    }
+
+    let old_line: String = line.iter().map(|token| token.text()).collect();
+    let local = TextEdit::replace(
+        edit.delete.start - line_start..edit.delete.end - line_start,
+        edit.insert.as_str(),
+    );
+    let new_line = local.apply(&old_line);
+    let (old_tokens, new_tokens) = (lex(&old_line), lex(&new_line));
+    if old_tokens.len() != new_tokens.len() {
+        return None;
+    }
+    let mut changed = old_tokens
+        .iter()
+        .zip(&new_tokens)
+        .enumerate()
+        .filter(|(_, (old, new))| old != new);
+    let (index, (old, new)) = changed.next()?;
+    if changed.next().is_some()
+        || (old.kind, new.kind) != (SyntaxKind::TEXT, SyntaxKind::TEXT)
+        || !is_plain_word(old.text)
+        || !is_plain_word(new.text)
+    {
+        return None;
+    }
+
+    // The tree token must be that lexer token, not merged with others
+    let old_start = line_start
+        + old_tokens[..index]
+            .iter()
+            .map(|t| t.text.len())
+            .sum::<usize>();
+    let old_range = TextRange::at(TextSize::try_from(old_start).ok()?, TextSize::of(old.text));
+    let token = root.covering_element(old_range).into_token()?;
+    if token.kind() != SyntaxKind::TEXT || token.text_range() != old_range {
+        return None;
+    }
+
+    let green = token.replace_with(GreenToken::new(SyntaxKind::TEXT.into(), new.text));
+    Some(SyntaxNode::new_root(green))
+}
+
+/// The tokens of the line containing `offset`, including its newline.
+fn line_tokens(root: &SyntaxNode, offset: TextSize) -> Option<Vec<SyntaxToken>> {
+    // At a line start, the token before `offset` ends the previous line
+    let token = match root.token_at_offset(offset) {
+        TokenAtOffset::None => return None,
+        TokenAtOffset::Single(token) => token,
+        TokenAtOffset::Between(before, after) => {
+            if before.kind() == SyntaxKind::NEWLINE {
+                after
+            } else {
+                before
+            }
+        }
+    };
+    if token.kind() == SyntaxKind::NEWLINE {
+        return None;
+    }
+
+    let mut first = token.clone();
+    while let Some(prev) = first.prev_token()
+        && prev.kind() != SyntaxKind::NEWLINE
+    {
+        first = prev;
+    }
+    let mut line = vec![first];
+    while line.last().unwrap().kind() != SyntaxKind::NEWLINE
+        && let Some(next) = line.last().unwrap().next_token()
+    {
+        line.push(next);
+    }
+    Some(line)
+}
+
+/// Splice a reparsed window of top-level blocks into the old tree, or
+/// `None` if the edit may affect blocks outside the window.
+fn reparse_blocks(root: &SyntaxNode, edit: &TextEdit, new_text: &str) -> Option<SyntaxNode> {
+    let children: Vec<SyntaxElement> = root.children_with_tokens().collect();
+    let blocks: Vec<usize> = children
+        .iter()
+        .enumerate()
+        .filter(|(_, child)| child.as_node().is_some())
+        .map(|(i, _)| i)
+        .collect();
+    let range = |i: usize| -> Range<usize> {
+        let range = children[i].text_range();
+        range.start().into()..range.end().into()
+    };
+
+    // Blocks touching the edit, including ones it only abuts
+    let first = blocks
+        .iter()
+        .position(|&i| range(i).end >= edit.delete.start)?;
+    let last = blocks
+        .iter()
+        .rposition(|&i| range(i).start <= edit.delete.end)?;
+    let (first, last) = (first.min(last), first.max(last));
+    let pad_before = first > 0;
+    let pad_after = last + 1 < blocks.len();
+    let start_child = blocks[if pad_before { first - 1 } else { first }];
+    let end_child = blocks[if pad_after { last + 1 } else { last }];
+
+    let old_window = range(start_child).start..range(end_child).end;
+    if edit.delete.start < old_window.start || edit.delete.end > old_window.end {
+        return None;
+    }
+    let new_end = old_window.end + edit.insert.len() - edit.delete.len();
+    let reparsed = parse(&new_text[old_window.start..new_end]);
+    let window: Vec<SyntaxElement> = reparsed.children_with_tokens().collect();
+
+    let same_block = |new: Option<&SyntaxElement>, old: usize| {
+        matches!(
+            (new, &children[old]),
+            (Some(NodeOrToken::Node(new)), NodeOrToken::Node(old)) if new.green() == old.green()
+        )
+    };
+    if pad_before && !same_block(window.first(), start_child) {
+        return None;
+    }
+    if pad_after && !same_block(window.last(), end_child) {
+        return None;
+    }
+
+    let green_children = children[..start_child]
+        .iter()
+        .chain(&window)
+        .chain(&children[end_child + 1..])
+        .map(|child| match child {
+            NodeOrToken::Node(node) => NodeOrToken::Node(node.green().into_owned()),
+            NodeOrToken::Token(token) => NodeOrToken::Token(token.green().to_owned()),
+        })
+        .collect::<Vec<_>>();
+    let green = GreenNode::new(SyntaxKind::ROOT.into(), green_children);
+    Some(SyntaxNode::new_root(green))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "# Title\n\nFirst paragraph with [[link]].\n\n- one\n- two\n  - nested\n\n> quote\n\n```rust\nfn main() {}\n```\n\nLast paragraph.\n";
+
+    /// Apply `edit` both ways and check the trees match exactly.
+    fn assert_matches_full_parse(source: &str, edit: TextEdit) -> SyntaxNode {
+        let incremental = reparse(&parse(source), &edit);
+        let full = parse(&edit.apply(source));
+        assert_eq!(
+            format!("{incremental:#?}"),
+            format!("{full:#?}"),
+            "edit {edit:?} on {source:?}"
+        );
+        incremental
+    }
+
+    #[test]
+    fn test_reparse_matches_full_parse_for_every_position() {
+        for insert in ["x", "\n", "\n\n", "- ", "```\n", "===\n", "# ", "**", "> "] {
+            for at in (0..=DOC.len()).filter(|&at| DOC.is_char_boundary(at)) {
+                assert_matches_full_parse(DOC, TextEdit::insert(at, insert));
+            }
+        }
+    }
+
+    #[test]
+    fn test_reparse_deletions_match_full_parse() {
+        for start in 0..DOC.len() {
+            for len in [1, 2, 5, 12] {
+                let end = (start + len).min(DOC.len());
+                assert_matches_full_parse(DOC, TextEdit::replace(start..end, ""));
+            }
+        }
+    }
+
+    #[test]
+    fn test_reparse_reuses_untouched_blocks() {
+        let old = parse(DOC);
+        let edit = TextEdit::insert(DOC.find("quote").unwrap(), "short ");
+        let new = reparse(&old, &edit);
+        assert_eq!(new.to_string(), edit.apply(DOC));
+
+        let green_ptr = |node: SyntaxNode| &*node.green() as *const _ as usize;
+        let old_blocks: Vec<_> = old.children().map(green_ptr).collect();
+        let new_blocks: Vec<_> = new.children().map(green_ptr).collect();
+        // Heading and first paragraph, and the last paragraph, are shared
+        assert_eq!(old_blocks[..2], new_blocks[..2]);
+        assert_eq!(old_blocks.last(), new_blocks.last());
+    }
+
+    /// Lines whose words the grammar reads, for the word fast path
+    const TRICKY: &str = "- TOD task\n- [y] box\n1. one\n\nSee #tag and mailt:me or <me@ex.com>\n\\\\word *em*phasis_x_ **a**b\n\n<dv>\n\n```\nfn code\n```\n";
+
+    #[test]
+    fn test_word_edits_match_full_parse() {
+        for source in [DOC, TRICKY] {
+            for at in (0..=source.len()).filter(|&at| source.is_char_boundary(at)) {
+                for insert in ["a", "O", "o", "x", "1", "é", " "] {
+                    assert_matches_full_parse(source, TextEdit::insert(at, insert));
+                }
+                if at < source.len() && source.is_char_boundary(at + 1) {
+                    assert_matches_full_parse(source, TextEdit::replace(at..at + 1, "x"));
+                    assert_matches_full_parse(source, TextEdit::replace(at..at + 1, "i"));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_typing_in_a_word_swaps_only_its_token() {
+        let old = parse(DOC);
+        let at = DOC.find("paragraph with").unwrap() + 4;
+        let edit = TextEdit::insert(at, "gg");
+        let new = reparse_word(&old, &edit).expect("word fast path");
+        assert_eq!(new.to_string(), edit.apply(DOC));
+        let green_ptr = |node: SyntaxNode| &*node.green() as *const _ as usize;
+        let shared = old
+            .children()
+            .map(green_ptr)
+            .zip(new.children().map(green_ptr))
+            .filter(|(old, new)| old == new)
+            .count();
+        assert_eq!(shared, old.children().count() - 1);
+    }
+
+    #[test]
+    fn test_word_fast_path_skips_edits_that_may_change_structure() {
+        let old = parse(TRICKY);
+        let at = |text: &str| TRICKY.find(text).unwrap() + text.len();
+        // TOD -> TODO becomes a task marker
+        assert!(reparse_word(&old, &TextEdit::insert(at("TOD"), "O")).is_none());
+        // [y] -> [x] becomes a checkbox
+        let y = at("[y");
+        assert!(reparse_word(&old, &TextEdit::replace(y - 1..y, "x")).is_none());
+        // mailt -> mailto: becomes a URL
+        assert!(reparse_word(&old, &TextEdit::insert(at("mailt"), "o")).is_none());
+        // Inside a code fence
+        assert!(reparse_word(&old, &TextEdit::insert(at("fn cod"), "e")).is_none());
+        // Edits that add a line
+        assert!(reparse_word(&old, &TextEdit::insert(at("See"), "\n")).is_none());
+        // But typing in an ordinary word takes it
+        assert!(reparse_word(&old, &TextEdit::insert(at("ta"), "a")).is_some());
+    }
+
+    #[test]
+    fn test_opening_fence_falls_back_to_full_parse() {
+        let source = "Para one.\n\nPara two.\n\nPara three.\n";
+        let new = assert_matches_full_parse(source, TextEdit::insert(11, "```\n"));
+        assert_eq!(
+            new.children().last().map(|n| n.kind()),
+            Some(SyntaxKind::FENCED_CODE)
+        );
+    }
+}
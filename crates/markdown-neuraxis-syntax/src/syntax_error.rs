@@ -0,0 +1,18 @@
+//! Problems the parser recovered from.
+//!
+//! Parsing never fails: malformed markdown still produces a tree that holds
+//! every byte. Where the grammar had to guess, e.g. a `[[` wikilink with no
+//! `]]`, it records a [`SyntaxError`] alongside the tree, which
+//! [`parse_with_errors`](crate::parse_with_errors) returns so editors can
+//! show a hint.
+
+use rowan::TextRange;
+
+/// Malformed markdown at `range`, described by `message`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    /// Source range of the malformed node
+    pub range: TextRange,
+    /// What's wrong, e.g. "unclosed wikilink, expected `]]`"
+    pub message: String,
+}
@@ -62,6 +62,7 @@
 /// We use SCREAMING_CASE following the rust-analyzer convention for SyntaxKind.
 #[repr(u16)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(non_camel_case_types)]
 pub enum SyntaxKind {
     // === Tokens (lexer output) ===
@@ -93,7 +94,7 @@ pub enum SyntaxKind {
     LPAREN,
     /// `)` for link URLs
     RPAREN,
-    /// `#` for headings
+    /// `#` for headings and tags
     HASH,
     /// `<` for HTML blocks
     LT,
@@ -109,6 +110,14 @@ pub enum SyntaxKind {
     EQUALS,
     /// Raw HTML content
     HTML_TEXT,
+    /// URL with a scheme (`https://...`, `mailto:...`)
+    URL,
+    /// Backslash escape of ASCII punctuation, e.g. `\*`
+    ESCAPE,
+    /// Hard line break at the end of a line: two or more trailing spaces or
+    /// a `\`. The parser gives this kind to a `WHITESPACE` or `\` `TEXT`
+    /// token, followed by the `NEWLINE`.
+    HARD_BREAK,
     /// End of file marker
     EOF,
 
@@ -131,6 +140,10 @@ pub enum SyntaxKind {
     THEMATIC_BREAK,
     /// Fenced code block
     FENCED_CODE,
+    /// Info string after an opening code fence (`rust title="x" {1,3}`)
+    CODE_INFO,
+    /// Language word at the start of a [`CODE_INFO`](Self::CODE_INFO)
+    CODE_LANGUAGE,
     /// Raw HTML block
     HTML_BLOCK,
     /// Inline content container
@@ -157,11 +170,14 @@ pub enum SyntaxKind {
     BLOCK_REF,
     /// Task checkbox `[ ]` or `[x]`
     CHECKBOX,
+    /// Task state keyword at the start of a list item (`TODO`, `DOING`,
+    /// `DONE`, `LATER`)
+    TASK_MARKER,
     /// Setext heading (underlined)
     SETEXT_HEADING,
     /// Indented code block
     INDENTED_CODE,
-    /// YAML frontmatter block
+    /// YAML (`---`) or TOML (`+++`) frontmatter block
     FRONTMATTER,
     /// Table container
     TABLE,
@@ -175,6 +191,32 @@ pub enum SyntaxKind {
     TABLE_CELL,
     /// Table delimiter row (|---|---|)
     TABLE_DELIMITER,
+    /// Tag `#name`
+    TAG,
+    /// Underline of a setext heading (`===` or `---`)
+    SETEXT_UNDERLINE,
+    /// Destination of a [`LINK`](Self::LINK) or [`IMAGE`](Self::IMAGE),
+    /// without the `<>` around a bracketed one
+    LINK_DESTINATION,
+    /// Title after a link or image destination, with its quotes or parens
+    LINK_TITLE,
+    /// Note a [`WIKILINK`](Self::WIKILINK) points at, before any `#` or `|`
+    WIKILINK_TARGET,
+    /// Heading or block after the `#` of a wikilink
+    WIKILINK_SECTION,
+    /// Display text after the `|` of a wikilink
+    WIKILINK_ALIAS,
+    /// Text of a [`CODE_SPAN`](Self::CODE_SPAN) between its backtick runs
+    CODE_SPAN_CONTENT,
+    /// Text of a [`HEADING`](Self::HEADING), without the `#`s, a closing
+    /// sequence or the spaces around it
+    HEADING_TEXT,
+    /// Indentation of a line of [`INDENTED_CODE`](Self::INDENTED_CODE), up
+    /// to four columns
+    CODE_INDENT,
+    /// A line of [`INDENTED_CODE`](Self::INDENTED_CODE) after its
+    /// indentation, without the line break
+    CODE_TEXT,
 
     /// Error recovery node
     ERROR,
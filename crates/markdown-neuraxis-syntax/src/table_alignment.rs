@@ -0,0 +1,91 @@
+//! # Table Column Alignment
+//!
+//! The delimiter row under a table's header sets how each column is
+//! aligned, following GFM:
+//!
+//! ~~~text
+//! | left | center | right | none |
+//! |:-----|:------:|------:|------|
+//! ~~~
+//!
+//! ```
+//! use markdown_neuraxis_syntax::{TableAlignment, parse};
+//!
+//! let tree = parse("| a | b |\n|:--|--:|\n");
+//! let table = tree.first_child().unwrap();
+//! assert_eq!(
+//!     TableAlignment::for_table(&table),
+//!     vec![TableAlignment::Left, TableAlignment::Right]
+//! );
+//! ```
+
+use crate::syntax_kind::{SyntaxKind, SyntaxNode};
+
+/// How a table column's cells are aligned.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TableAlignment {
+    /// No colons: left to the renderer
+    #[default]
+    None,
+    /// `:---`
+    Left,
+    /// `:---:`
+    Center,
+    /// `---:`
+    Right,
+}
+
+impl TableAlignment {
+    /// Column alignments of a `TABLE` node, one per delimiter row cell.
+    /// Empty if the table has no delimiter row.
+    pub fn for_table(table: &SyntaxNode) -> Vec<Self> {
+        table
+            .children()
+            .find(|child| child.kind() == SyntaxKind::TABLE_DELIMITER)
+            .map(|delimiter| Self::parse_delimiter_row(&delimiter.text().to_string()))
+            .unwrap_or_default()
+    }
+
+    /// Parse a delimiter row such as `|:--|:-:|--:|`.
+    fn parse_delimiter_row(row: &str) -> Vec<Self> {
+        let row = row.trim();
+        let row = row.strip_prefix('|').unwrap_or(row);
+        let row = row.strip_suffix('|').unwrap_or(row);
+        row.split('|')
+            .map(|cell| {
+                let cell = cell.trim();
+                match (cell.starts_with(':'), cell.len() > 1 && cell.ends_with(':')) {
+                    (true, true) => Self::Center,
+                    (true, false) => Self::Left,
+                    (false, true) => Self::Right,
+                    (false, false) => Self::None,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn alignments(source: &str) -> Vec<TableAlignment> {
+        let tree = parse(source);
+        let table = tree
+            .descendants()
+            .find(|node| node.kind() == SyntaxKind::TABLE)
+            .unwrap();
+        TableAlignment::for_table(&table)
+    }
+
+    #[test]
+    fn test_alignments_from_delimiter_row() {
+        use TableAlignment::*;
+        assert_eq!(
+            alignments("| l | c | r | n |\n|:--|:-:|--:|---|\n| 1 | 2 | 3 | 4 |\n"),
+            vec![Left, Center, Right, None]
+        );
+        assert_eq!(alignments("|a|b|\n| :-- | ---: |\n"), vec![Left, Right]);
+    }
+}
@@ -0,0 +1,41 @@
+//! # Tags
+//!
+//! A tag is `#` followed by letters, digits, `-`, `_` or `/` (for nested
+//! tags like `#project/home`), with at least one character that isn't a
+//! digit so `issue #42` isn't a tag. It must start the line's inline
+//! content or follow whitespace, so `page#anchor` isn't one either.
+//!
+//! A `#` at the start of a block line still begins a heading, even without
+//! a space after it, so tags need other text before them on such lines.
+//!
+//! The grammar parses tags as `TAG` nodes. The tokens of a `TAG` node can
+//! run past the tag itself (`#tag,` lexes `tag,` as one token), so use
+//! [`tag_name`] for the name.
+//!
+//! ```
+//! use markdown_neuraxis_syntax::{SyntaxKind, parse, tag::tag_name};
+//!
+//! let tree = parse("Filed under #work/admin, for now.\n");
+//! let tag = tree
+//!     .descendants()
+//!     .find(|node| node.kind() == SyntaxKind::TAG)
+//!     .unwrap();
+//! assert_eq!(tag_name(&tag.text().to_string()), Some("work/admin"));
+//! ```
+
+/// Whether `c` can be part of a tag name.
+pub fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '-' | '_' | '/')
+}
+
+/// The name of the tag at the start of `text` (with or without its `#`),
+/// or `None` if it doesn't start with a valid tag name.
+pub fn tag_name(text: &str) -> Option<&str> {
+    let text = text.strip_prefix('#').unwrap_or(text);
+    if !text.starts_with(char::is_alphanumeric) {
+        return None;
+    }
+    let end = text.find(|c| !is_tag_char(c)).unwrap_or(text.len());
+    let name = &text[..end];
+    name.contains(|c: char| !c.is_ascii_digit()).then_some(name)
+}
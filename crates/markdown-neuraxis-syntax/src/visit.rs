@@ -0,0 +1,187 @@
+//! # Walking the Tree
+//!
+//! Small helpers for the walks every consumer of the tree needs, after
+//! rust-analyzer's `algo` module: nodes or tokens of a kind in preorder,
+//! the closest ancestor of a kind, and the nodes at an offset or around a
+//! range. Every node keeps its source range (`text_range()`), so results can
+//! be mapped straight back to the text.
+//!
+//! ```
+//! use markdown_neuraxis_syntax::{SyntaxKind, parse, visit};
+//!
+//! let tree = parse("# Title\n\nSee [[Notes]] and [[Ideas]].\n");
+//! let links: Vec<String> = visit::nodes_of_kind(&tree, SyntaxKind::WIKILINK)
+//!     .map(|node| node.text().to_string())
+//!     .collect();
+//! assert_eq!(links, ["[[Notes]]", "[[Ideas]]"]);
+//!
+//! // The paragraph around the second link
+//! let paragraph = visit::node_at_offset(&tree, 26.into(), SyntaxKind::PARAGRAPH).unwrap();
+//! assert_eq!(paragraph.text().to_string(), "See [[Notes]] and [[Ideas]].\n");
+//! ```
+
+use rowan::{TextRange, TextSize};
+
+use crate::syntax_kind::{SyntaxKind, SyntaxNode, SyntaxToken};
+
+/// `node` and its descendants of `kind`, in preorder.
+pub fn nodes_of_kind(node: &SyntaxNode, kind: SyntaxKind) -> impl Iterator<Item = SyntaxNode> {
+    node.descendants().filter(move |node| node.kind() == kind)
+}
+
+/// `node` and its descendants of any of `kinds`, in preorder.
+pub fn nodes_of_kinds<'a>(
+    node: &SyntaxNode,
+    kinds: &'a [SyntaxKind],
+) -> impl Iterator<Item = SyntaxNode> + 'a {
+    node.descendants()
+        .filter(move |node| kinds.contains(&node.kind()))
+}
+
+/// The first direct child of `node` of `kind`, e.g. a link's
+/// `LINK_DESTINATION`.
+pub fn child_of_kind(node: &SyntaxNode, kind: SyntaxKind) -> Option<SyntaxNode> {
+    node.children().find(|child| child.kind() == kind)
+}
+
+/// The tokens of `kind` under `node`, in source order.
+pub fn tokens_of_kind(node: &SyntaxNode, kind: SyntaxKind) -> impl Iterator<Item = SyntaxToken> {
+    node.descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(move |token| token.kind() == kind)
+}
+
+/// `node` itself if it's of `kind`, else its closest ancestor that is.
+pub fn ancestor_of_kind(node: &SyntaxNode, kind: SyntaxKind) -> Option<SyntaxNode> {
+    node.ancestors().find(|node| node.kind() == kind)
+}
+
+/// Whether `node` is of `kind` or inside a node of `kind`.
+pub fn has_ancestor(node: &SyntaxNode, kind: SyntaxKind) -> bool {
+    ancestor_of_kind(node, kind).is_some()
+}
+
+/// The nodes containing `offset`, innermost first. Where `offset` falls
+/// between two tokens, the nodes around either are included, shorter first.
+pub fn ancestors_at_offset(
+    root: &SyntaxNode,
+    offset: TextSize,
+) -> impl Iterator<Item = SyntaxNode> {
+    let mut nodes: Vec<SyntaxNode> = Vec::new();
+    for token in root.token_at_offset(offset) {
+        for node in token.parent_ancestors() {
+            if !nodes.contains(&node) {
+                nodes.push(node);
+            }
+        }
+    }
+    nodes.sort_by_key(|node| node.text_range().len());
+    nodes.into_iter()
+}
+
+/// The innermost node of `kind` containing `offset`.
+pub fn node_at_offset(root: &SyntaxNode, offset: TextSize, kind: SyntaxKind) -> Option<SyntaxNode> {
+    ancestors_at_offset(root, offset).find(|node| node.kind() == kind)
+}
+
+/// The smallest node containing all of `range`.
+///
+/// # Panics
+///
+/// If `range` isn't within `root`.
+pub fn covering_node(root: &SyntaxNode, range: TextRange) -> SyntaxNode {
+    match root.covering_element(range) {
+        rowan::NodeOrToken::Node(node) => node,
+        rowan::NodeOrToken::Token(token) => token.parent().unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const SOURCE: &str = "- **bold [[Link]]** item\n- two\n\nText `code` here\n";
+
+    fn range(start: usize, end: usize) -> TextRange {
+        TextRange::new((start as u32).into(), (end as u32).into())
+    }
+
+    #[test]
+    fn test_kind_filters_walk_in_preorder() {
+        let tree = parse(SOURCE);
+        let kinds: Vec<SyntaxKind> =
+            nodes_of_kinds(&tree, &[SyntaxKind::LIST_ITEM, SyntaxKind::WIKILINK])
+                .map(|node| node.kind())
+                .collect();
+        assert_eq!(
+            kinds,
+            [
+                SyntaxKind::LIST_ITEM,
+                SyntaxKind::WIKILINK,
+                SyntaxKind::LIST_ITEM
+            ]
+        );
+        assert_eq!(nodes_of_kind(&tree, SyntaxKind::CODE_SPAN).count(), 1);
+        assert_eq!(tokens_of_kind(&tree, SyntaxKind::BACKTICK).count(), 2);
+    }
+
+    #[test]
+    fn test_ancestor_queries() {
+        let tree = parse(SOURCE);
+        let link = nodes_of_kind(&tree, SyntaxKind::WIKILINK).next().unwrap();
+        assert_eq!(
+            ancestor_of_kind(&link, SyntaxKind::STRONG).map(|node| node.text().to_string()),
+            Some("**bold [[Link]]**".to_string())
+        );
+        assert_eq!(
+            ancestor_of_kind(&link, SyntaxKind::WIKILINK),
+            Some(link.clone())
+        );
+        assert!(has_ancestor(&link, SyntaxKind::UNORDERED_LIST));
+        assert!(!has_ancestor(&link, SyntaxKind::BLOCK_QUOTE));
+    }
+
+    #[test]
+    fn test_offset_queries_find_innermost_nodes() {
+        let tree = parse(SOURCE);
+        let at = |text: &str| TextSize::try_from(SOURCE.find(text).unwrap()).unwrap();
+
+        let kinds: Vec<SyntaxKind> = ancestors_at_offset(&tree, at("Link"))
+            .map(|node| node.kind())
+            .collect();
+        assert_eq!(
+            kinds[..3],
+            [
+                SyntaxKind::WIKILINK_TARGET,
+                SyntaxKind::WIKILINK,
+                SyntaxKind::STRONG
+            ]
+        );
+        assert_eq!(kinds.last(), Some(&SyntaxKind::ROOT));
+
+        // Between `**` and `bold`: the strong node either way
+        let strong = node_at_offset(&tree, at("bold"), SyntaxKind::STRONG).unwrap();
+        assert_eq!(strong.text().to_string(), "**bold [[Link]]**");
+        assert!(node_at_offset(&tree, at("two"), SyntaxKind::STRONG).is_none());
+    }
+
+    #[test]
+    fn test_covering_node() {
+        let tree = parse(SOURCE);
+        let code = SOURCE.find("code").unwrap();
+        assert_eq!(
+            covering_node(&tree, range(code, code + 2)).kind(),
+            SyntaxKind::CODE_SPAN_CONTENT
+        );
+        let text = SOURCE.find("Text").unwrap();
+        assert_eq!(
+            covering_node(&tree, range(text, code)).kind(),
+            SyntaxKind::PARAGRAPH
+        );
+        assert_eq!(
+            covering_node(&tree, range(0, text + 1)).kind(),
+            SyntaxKind::ROOT
+        );
+    }
+}
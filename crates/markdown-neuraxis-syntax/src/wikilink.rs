@@ -0,0 +1,116 @@
+//! # Wiki-Links
+//!
+//! The grammar parses `[[target#section|alias]]` as a `WIKILINK` node with
+//! each part in its own child node, without the spaces around it:
+//!
+//! ~~~text
+//! [[Project Notes#Next Steps|what's next]]
+//!   ^^^^^^^^^^^^^ WIKILINK_TARGET
+//!                 ^^^^^^^^^^ WIKILINK_SECTION
+//!                            ^^^^^^^^^^^ WIKILINK_ALIAS
+//! ~~~
+//!
+//! The parts split at the first `#` and the first `|` after it, so a
+//! section can't contain `|` but an alias can contain anything. Empty parts
+//! have no node: `[[#Heading]]` links to a section of the same note.
+//!
+//! ```
+//! use markdown_neuraxis_syntax::{SyntaxKind, parse, wikilink::WikiLinkParts};
+//!
+//! let tree = parse("See [[Notes # Plans | the plans]].\n");
+//! let node = tree
+//!     .descendants()
+//!     .find(|node| node.kind() == SyntaxKind::WIKILINK)
+//!     .unwrap();
+//! let link = WikiLinkParts::of(&node).unwrap();
+//! assert_eq!(link.target_text(), "Notes");
+//! assert_eq!(link.section.unwrap().text(), "Plans");
+//! assert_eq!(link.alias.unwrap().text(), "the plans");
+//! ```
+
+use crate::syntax_kind::{SyntaxKind, SyntaxNode};
+
+/// The parts of a closed `[[...]]` link.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WikiLinkParts {
+    /// `WIKILINK_TARGET` node, `None` for same-note links
+    pub target: Option<SyntaxNode>,
+    /// `WIKILINK_SECTION` node after the `#`
+    pub section: Option<SyntaxNode>,
+    /// `WIKILINK_ALIAS` node after the `|`
+    pub alias: Option<SyntaxNode>,
+}
+
+impl WikiLinkParts {
+    /// The parts of a `WIKILINK` node, or `None` for any other node or a
+    /// `[[` that is never closed.
+    pub fn of(node: &SyntaxNode) -> Option<Self> {
+        if node.kind() != SyntaxKind::WIKILINK || !node.text().to_string().ends_with("]]") {
+            return None;
+        }
+        let child = |kind| node.children().find(|child| child.kind() == kind);
+        Some(Self {
+            target: child(SyntaxKind::WIKILINK_TARGET),
+            section: child(SyntaxKind::WIKILINK_SECTION),
+            alias: child(SyntaxKind::WIKILINK_ALIAS),
+        })
+    }
+
+    /// Text of the target, empty for same-note links.
+    pub fn target_text(&self) -> String {
+        self.target
+            .as_ref()
+            .map(|target| target.text().to_string())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn parts(source: &str) -> Option<(String, Option<String>, Option<String>)> {
+        let tree = parse(source);
+        let node = tree
+            .descendants()
+            .find(|node| node.kind() == SyntaxKind::WIKILINK)?;
+        let link = WikiLinkParts::of(&node)?;
+        let text = |part: Option<SyntaxNode>| part.map(|node| node.text().to_string());
+        Some((link.target_text(), text(link.section), text(link.alias)))
+    }
+
+    #[test]
+    fn test_parts_split_at_first_hash_and_pipe() {
+        assert_eq!(
+            parts("[[C# notes#Part #2|a | b]]"),
+            Some((
+                "C".to_string(),
+                Some("notes#Part #2".to_string()),
+                Some("a | b".to_string())
+            ))
+        );
+        assert_eq!(
+            parts("[[Page|Alias#1]]"),
+            Some(("Page".to_string(), None, Some("Alias#1".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_empty_parts_have_no_node() {
+        assert_eq!(
+            parts("[[#Heading]]"),
+            Some((String::new(), Some("Heading".to_string()), None))
+        );
+        assert_eq!(
+            parts("[[ Page # | ]]"),
+            Some(("Page".to_string(), None, None))
+        );
+    }
+
+    #[test]
+    fn test_unclosed_has_no_parts() {
+        assert_eq!(parts("[[Page\n"), None);
+        assert_eq!(parts("[[Page]\n"), None);
+    }
+}
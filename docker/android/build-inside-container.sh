@@ -44,26 +44,26 @@ echo ''
 
 # Build Rust FFI for arm64 (devices)
 echo 'Building Rust FFI for aarch64-linux-android (arm64-v8a)...'
-cargo ndk -t aarch64-linux-android build --release -p markdown-neuraxis-ffi
+cargo ndk -t aarch64-linux-android build --profile android-release -p markdown-neuraxis-ffi
 
 # Build Rust FFI for x86_64 (emulators)
 echo 'Building Rust FFI for x86_64-linux-android (x86_64)...'
-cargo ndk -t x86_64-linux-android build --release -p markdown-neuraxis-ffi
+cargo ndk -t x86_64-linux-android build --profile android-release -p markdown-neuraxis-ffi
 
 # Create jniLibs in /build (Gradle init script adds this as source)
 mkdir -p /build/jniLibs/arm64-v8a
 mkdir -p /build/jniLibs/x86_64
 
 # Copy .so files from CARGO_TARGET_DIR
-cp "$CARGO_TARGET_DIR/aarch64-linux-android/release/libmarkdown_neuraxis_ffi.so" \
+cp "$CARGO_TARGET_DIR/aarch64-linux-android/android-release/libmarkdown_neuraxis_ffi.so" \
    /build/jniLibs/arm64-v8a/
-cp "$CARGO_TARGET_DIR/x86_64-linux-android/release/libmarkdown_neuraxis_ffi.so" \
+cp "$CARGO_TARGET_DIR/x86_64-linux-android/android-release/libmarkdown_neuraxis_ffi.so" \
    /build/jniLibs/x86_64/
 
 # Generate Kotlin bindings to /build (Gradle init script adds this as source)
 echo 'Generating Kotlin bindings...'
 cargo run -p markdown-neuraxis-ffi --bin uniffi-bindgen generate \
-  --library "$CARGO_TARGET_DIR/aarch64-linux-android/release/libmarkdown_neuraxis_ffi.so" \
+  --library "$CARGO_TARGET_DIR/aarch64-linux-android/android-release/libmarkdown_neuraxis_ffi.so" \
   --language kotlin \
   --out-dir /build/uniffi/
 